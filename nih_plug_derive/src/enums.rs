@@ -17,12 +17,13 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
 
     // The `Enum` trait is super simple: variant names are mapped to their index in the declaration
     // order, and the names are either just the variant name or a `#[name = "..."]` attribute in
-    // case the name should contain a space.
-    let mut variant_names = Vec::new();
+    // case the name should contain a space. Variants marked with `#[hidden]` are moved to the end
+    // so the visible variants keep a contiguous range of indices. Explicit discriminants are
+    // ignored since the matches below only use the variants' identifiers.
+    let mut visible_variants = Vec::new();
+    let mut hidden_variants = Vec::new();
     // IDs are optional, but they must either be set for all variants or for none of them
-    let mut variant_ids = Vec::new();
-    let mut to_index_tokens = Vec::new();
-    let mut from_index_tokens = Vec::new();
+    let mut has_ids = false;
     for (variant_idx, variant) in variants.iter().enumerate() {
         if !variant.fields.is_empty() {
             return syn::Error::new(variant.span(), "Variants cannot have any fields")
@@ -32,6 +33,7 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
 
         let mut name_attr: Option<String> = None;
         let mut id_attr: Option<String> = None;
+        let mut hidden = false;
         for attr in &variant.attrs {
             if attr.path.is_ident("name") {
                 match attr.parse_meta() {
@@ -81,15 +83,33 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
                         .into()
                     }
                 };
+            } else if attr.path.is_ident("hidden") {
+                match attr.parse_meta() {
+                    Ok(syn::Meta::Path(_)) => {
+                        if !hidden {
+                            hidden = true;
+                        } else {
+                            return syn::Error::new(attr.span(), "Duplicate hidden attribute")
+                                .to_compile_error()
+                                .into();
+                        }
+                    }
+                    _ => {
+                        return syn::Error::new(
+                            attr.span(),
+                            "The hidden attribute does not take any arguments: #[hidden]",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                };
             }
         }
 
         // IDs must either be set for all variants or for none of them
-        match (id_attr, variant_idx == 0, variant_ids.is_empty()) {
-            (Some(id), true, true) | (Some(id), false, false) => {
-                variant_ids.push(id);
-            }
-            (None, _, true) => (),
+        match (&id_attr, variant_idx == 0, has_ids) {
+            (Some(_), true, false) => has_ids = true,
+            (Some(_), false, true) | (None, _, false) => (),
             _ => {
                 return syn::Error::new(
                     variant.span(),
@@ -100,12 +120,50 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
             }
         }
 
-        match name_attr {
-            Some(name) => variant_names.push(name),
-            None => variant_names.push(variant.ident.to_string()),
+        // Hidden variants are only kept around so old state can still be loaded, and that requires
+        // stable IDs since hiding a variant changes the other variants' indices
+        if hidden && !has_ids {
+            return syn::Error::new(
+                variant.span(),
+                "Hidden variants require all variants to have an ID attribute",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        let name = name_attr.unwrap_or_else(|| variant.ident.to_string());
+        if hidden {
+            hidden_variants.push((&variant.ident, name, id_attr));
+        } else {
+            visible_variants.push((&variant.ident, name, id_attr));
+        }
+    }
+
+    if visible_variants.is_empty() {
+        return syn::Error::new(
+            struct_name.span(),
+            "Enums need at least one variant that is not hidden",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let default_variant_ident = visible_variants[0].0;
+    let num_hidden_variants = hidden_variants.len();
+    let mut variant_names = Vec::new();
+    let mut variant_ids = Vec::new();
+    let mut to_index_tokens = Vec::new();
+    let mut from_index_tokens = Vec::new();
+    for (variant_idx, (variant_ident, name, id)) in visible_variants
+        .into_iter()
+        .chain(hidden_variants)
+        .enumerate()
+    {
+        variant_names.push(name);
+        if let Some(id) = id {
+            variant_ids.push(id);
         }
 
-        let variant_ident = &variant.ident;
         to_index_tokens.push(quote! { #struct_name::#variant_ident => #variant_idx, });
         from_index_tokens.push(quote! { #variant_idx => #struct_name::#variant_ident, });
     }
@@ -116,10 +174,8 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
         quote! { Some(&[#(#variant_ids),*]) }
     };
 
-    let from_index_default_tokens = variants.first().map(|v| {
-        let variant_ident = &v.ident;
-        quote! { _ => #struct_name::#variant_ident, }
-    });
+    // The first visible variant always ends up at index 0
+    let from_index_default_tokens = quote! { _ => #struct_name::#default_variant_ident, };
 
     quote! {
         impl Enum for #struct_name {
@@ -131,6 +187,10 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
                 #ids_tokens
             }
 
+            fn num_hidden_variants() -> usize {
+                #num_hidden_variants
+            }
+
             fn to_index(self) -> usize {
                 match self {
                     #(#to_index_tokens)*
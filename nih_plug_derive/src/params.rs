@@ -40,10 +40,27 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         };
 
         // All attributes are mutually exclusive. If we encounter multiple or duplicate attributes,
-        // then we'll error out.
+        // then we'll error out. The only exception is `#[view_state]`, which modifies a
+        // `#[persist]` field and is handled after the other attributes.
         let mut processed_attribute = false;
+        let mut view_state_attr: Option<&syn::Attribute> = None;
         for attr in &field.attrs {
-            if attr.path.is_ident("id") {
+            if attr.path.is_ident("view_state") {
+                match attr.parse_meta() {
+                    Ok(syn::Meta::Path(..)) if view_state_attr.is_none() => {
+                        view_state_attr = Some(attr);
+                    }
+                    _ => {
+                        return syn::Error::new(
+                            attr.span(),
+                            "The view_state attribute should be a plain #[view_state] attribute \
+                             used together with #[persist = \"foo_bar\"]",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                }
+            } else if attr.path.is_ident("id") {
                 match attr.parse_meta() {
                     Ok(syn::Meta::NameValue(syn::MetaNameValue {
                         lit: syn::Lit::Str(s),
@@ -117,6 +134,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         persistent_fields.push(PersistentField {
                             key: s,
                             field: field_name.clone(),
+                            view_state: false,
                         });
 
                         processed_attribute = true;
@@ -246,6 +264,23 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                 processed_attribute = true;
             }
         }
+
+        if let Some(attr) = view_state_attr {
+            match persistent_fields.last_mut() {
+                Some(persistent_field) if &persistent_field.field == field_name => {
+                    persistent_field.view_state = true;
+                }
+                _ => {
+                    return syn::Error::new(
+                        attr.span(),
+                        "The view_state attribute can only be used on fields with a \
+                         #[persist = \"foo_bar\"] attribute",
+                    )
+                    .to_compile_error()
+                    .into()
+                }
+            }
+        }
     }
 
     // The next step is build the gathered information into tokens that can be spliced into a
@@ -265,6 +300,56 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         }
     };
 
+    let view_state_field_keys_tokens = {
+        // The keys need to get the same prefixes and suffixes as the serialized fields below
+        let view_state_field_keys_self_tokens = persistent_fields
+            .iter()
+            .filter(|persistent_field| persistent_field.view_state)
+            .map(|PersistentField { key, .. }| quote! { keys.push(String::from(#key)); });
+        let view_state_field_keys_nested_tokens = params
+            .iter()
+            .filter_map(|p| match p {
+                Param::Single { .. } => None,
+                Param::Nested(nested) => Some(nested),
+            })
+            .map(|nested| match nested {
+                NestedParams::Inline { field, .. } => {
+                    quote! { keys.extend(self.#field.view_state_field_keys()); }
+                }
+                NestedParams::Prefixed {
+                    field, id_prefix, ..
+                } => quote! {
+                    keys.extend(
+                        self.#field
+                            .view_state_field_keys()
+                            .into_iter()
+                            .map(|key| format!("{}_{}", #id_prefix, key)),
+                    );
+                },
+                NestedParams::Array { field, .. } => quote! {
+                    for (field_idx, field) in self.#field.iter().enumerate() {
+                        let idx = field_idx + 1;
+                        keys.extend(
+                            field
+                                .view_state_field_keys()
+                                .into_iter()
+                                .map(|key| format!("{}_{}", key, idx)),
+                        );
+                    }
+                },
+            });
+
+        quote! {
+            #[allow(unused_mut)]
+            let mut keys = Vec::new();
+            #(#view_state_field_keys_self_tokens)*
+
+            #(#view_state_field_keys_nested_tokens)*
+
+            keys
+        }
+    };
+
     let (serialize_fields_tokens, deserialize_fields_tokens) = {
         // Like with `param_map()`, we'll try to do the serialization for this struct and then
         // recursively call the child parameter structs. We don't know anything about the actual
@@ -273,7 +358,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         let (serialize_fields_self_tokens, deserialize_fields_match_self_tokens): (Vec<_>, Vec<_>) =
             persistent_fields
                 .into_iter()
-                .map(|PersistentField { field, key }| {
+                .map(|PersistentField { field, key, .. }| {
                     (
                         quote! {
                             match ::nih_plug::params::persist::PersistentField::map(
@@ -425,6 +510,10 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
             fn deserialize_fields(&self, serialized: &::std::collections::BTreeMap<String, String>) {
                 #deserialize_fields_tokens
             }
+
+            fn view_state_field_keys(&self) -> Vec<String> {
+                #view_state_field_keys_tokens
+            }
         }
     }
     .into()
@@ -466,6 +555,8 @@ struct PersistentField {
     field: syn::Ident,
     /// The field's unique key.
     key: syn::LitStr,
+    /// Whether the field was marked with `#[view_state]`.
+    view_state: bool,
 }
 
 /// A field containing another object whose parameters and persistent fields should be added to this
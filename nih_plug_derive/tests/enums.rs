@@ -0,0 +1,83 @@
+use nih_plug::params::enums::EnumParamInner;
+use nih_plug::prelude::*;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+enum WithHidden {
+    #[id = "a"]
+    A = 10,
+    #[id = "legacy"]
+    #[name = "Legacy Mode"]
+    #[hidden]
+    Legacy = 5,
+    #[id = "b"]
+    B = 0,
+}
+
+/// The type-erased parameter the wrapper uses to save and restore the parameter's state.
+fn inner(param: &EnumParam<WithHidden>) -> &EnumParamInner {
+    match param.as_ptr() {
+        ParamPtr::EnumParam(p) => unsafe { &*p },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn hidden_variants_come_last() {
+    assert_eq!(WithHidden::variants(), &["A", "B", "Legacy Mode"]);
+    assert_eq!(WithHidden::ids(), Some(&["a", "b", "legacy"][..]));
+    assert_eq!(WithHidden::num_hidden_variants(), 1);
+
+    // Explicit discriminants don't affect the indices
+    assert_eq!(WithHidden::A.to_index(), 0);
+    assert_eq!(WithHidden::B.to_index(), 1);
+    assert_eq!(WithHidden::Legacy.to_index(), 2);
+    assert_eq!(WithHidden::from_index(2), WithHidden::Legacy);
+    assert_eq!(WithHidden::from_index(3), WithHidden::A);
+}
+
+#[test]
+fn hidden_variants_are_not_exposed() {
+    let param = EnumParam::new("param", WithHidden::A);
+    assert_eq!(param.step_count(), Some(1));
+    assert_eq!(inner(&param).len(), 2);
+    assert_eq!(param.normalized_value_to_string(1.0, true), "B");
+    assert_eq!(param.string_to_normalized_value("Legacy Mode"), None);
+}
+
+#[test]
+fn hidden_variant_id_round_trip() {
+    let param = EnumParam::new("param", WithHidden::A);
+    assert!(inner(&param).set_from_id("legacy"));
+    assert_eq!(param.value(), WithHidden::Legacy);
+    assert_eq!(param.to_string(), "Legacy Mode");
+    assert_eq!(inner(&param).unmodulated_plain_id(), Some("legacy"));
+
+    let restored = EnumParam::new("param", WithHidden::B);
+    assert!(inner(&restored).set_from_id(inner(&param).unmodulated_plain_id().unwrap()));
+    assert_eq!(restored.value(), WithHidden::Legacy);
+    assert!(restored.changed_since_last_call());
+
+    // Selecting a visible variant clears the hidden variant, even if the visible variant was
+    // already the underlying integer parameter's value
+    assert!(inner(&restored).set_from_id("b"));
+    assert!(restored.changed_since_last_call());
+    assert_eq!(restored.value(), WithHidden::B);
+    assert_eq!(inner(&restored).unmodulated_plain_id(), Some("b"));
+}
+
+#[test]
+fn hidden_variant_normalized_value() {
+    let param = EnumParam::new("param", WithHidden::B);
+    assert!(inner(&param).set_from_id("legacy"));
+    assert_eq!(param.value(), WithHidden::Legacy);
+
+    // Hidden variants are not part of the parameter's range, so the host still sees the last
+    // selected visible variant
+    assert_eq!(param.unmodulated_normalized_value(), 1.0);
+    assert_eq!(param.modulated_normalized_value(), 1.0);
+    assert_eq!(inner(&param).unmodulated_normalized_value(), 1.0);
+    assert_eq!(
+        param.normalized_value_to_string(param.unmodulated_normalized_value(), true),
+        "B"
+    );
+}
@@ -1,3 +1,4 @@
+use nih_plug::params::{param_pages, param_values};
 use nih_plug::prelude::*;
 
 #[derive(Params)]
@@ -316,3 +317,85 @@ mod param_groups {
         );
     }
 }
+
+mod pages {
+    use super::*;
+
+    /// The names and parameter names of every page.
+    fn page_layout(params: &impl Params) -> Vec<(String, Vec<String>)> {
+        param_pages(params)
+            .into_iter()
+            .map(|page| {
+                let param_names = page
+                    .params
+                    .iter()
+                    .map(|param_ptr| unsafe { param_ptr.name() }.to_owned())
+                    .collect();
+
+                (page.name, param_names)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grouped() {
+        let p = GroupedParams::default();
+
+        // Ungrouped parameters come first, even if there are groups in between them
+        let layout = page_layout(&p);
+        assert_eq!(layout.len(), 3);
+        assert_eq!(
+            layout[0],
+            (
+                String::new(),
+                vec![String::from("one"), String::from("three")]
+            )
+        );
+        assert_eq!(layout[1].0, "Some Group");
+        assert_eq!(layout[1].1, ["one", "two", "three"]);
+        assert_eq!(layout[2].0, "Another Group");
+    }
+
+    #[test]
+    fn grouped_groups() {
+        let p = GroupedGroupedParams::default();
+
+        // Nested groups are placed on their top level group's page
+        let layout = page_layout(&p);
+        assert_eq!(layout.len(), 1);
+        assert_eq!(layout[0].0, "Top-level group");
+        assert_eq!(layout[0].1.len(), 8);
+    }
+}
+
+mod values {
+    use super::*;
+
+    #[test]
+    fn flat() {
+        let p = FlatParams::default();
+
+        let values = param_values(&p);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].id, "one");
+        assert_eq!(values[0].normalized_value, 1.0);
+        assert_eq!(values[0].display_value, "On");
+        assert_eq!(values[2].name, "three");
+        assert_eq!(values[2].plain_value, 0.0);
+        assert_eq!(values[2].display_value, "0");
+        assert_eq!(
+            values[2].to_string(),
+            "three (three): 0 (plain: 0, normalized: 0)"
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let p = GroupedParams::default();
+
+        // The IDs should match the ones from the `param_map()`
+        let value_ids: Vec<String> = param_values(&p).into_iter().map(|value| value.id).collect();
+        let param_ids: Vec<String> = p.param_map().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(value_ids, param_ids);
+    }
+}
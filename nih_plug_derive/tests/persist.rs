@@ -1,7 +1,8 @@
-use std::collections::BTreeMap;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
 
 use nih_plug::prelude::*;
+use nih_plug::wrapper::state::ParamValue;
 
 #[derive(Params, Default)]
 struct WrapperParams {
@@ -15,6 +16,25 @@ struct ArrayWrapperParams {
     pub inners: [InnerParams; 3],
 }
 
+#[derive(Params, Default)]
+struct ViewStateParams {
+    #[persist = "sound"]
+    pub sound: Mutex<u32>,
+    #[persist = "editor"]
+    #[view_state]
+    pub editor: Mutex<u32>,
+    #[nested(id_prefix = "foo")]
+    pub inner: InnerParams,
+}
+
+#[derive(Params, Default)]
+struct CollectionParams {
+    #[persist = "matrix"]
+    pub matrix: Arc<Mutex<Vec<(u32, f32)>>>,
+    #[persist = "presets"]
+    pub presets: Arc<Mutex<HashMap<String, Vec<u32>>>>,
+}
+
 #[derive(Default)]
 struct InnerParams {
     /// The value `deserialize()` has been called with so we can check that the prefix has been
@@ -38,6 +58,10 @@ unsafe impl Params for InnerParams {
     fn deserialize_fields(&self, serialized: &BTreeMap<String, String>) {
         *self.deserialize_called_with.lock().unwrap() = Some(serialized.clone());
     }
+
+    fn view_state_field_keys(&self) -> Vec<String> {
+        vec![String::from("bar")]
+    }
 }
 
 mod persist {
@@ -135,4 +159,105 @@ mod persist {
             }
         }
     }
+
+    mod view_state {
+        use super::super::*;
+
+        #[test]
+        fn view_state_field_keys() {
+            let params = ViewStateParams::default();
+
+            // The nested struct's key should get the same prefix as its serialized field
+            let keys = params.view_state_field_keys();
+            assert_eq!(keys, ["editor", "foo_bar"]);
+            assert_eq!(params.serialize_fields().len(), 3);
+        }
+
+        #[test]
+        fn view_state_field_keys_array() {
+            let params = ArrayWrapperParams::default();
+
+            let keys = params.view_state_field_keys();
+            assert_eq!(keys, ["bar_1", "bar_2", "bar_3"]);
+        }
+
+        /// A state object as the wrappers would create it for [`ViewStateParams`], with a sound
+        /// parameter and an editor parameter.
+        fn state() -> PluginState {
+            let params = ViewStateParams::default();
+
+            PluginState {
+                version: String::from("1.0.0"),
+                params: BTreeMap::from([
+                    (String::from("gain"), ParamValue::F32(0.5)),
+                    (String::from("zoom"), ParamValue::I32(2)),
+                ]),
+                fields: params.serialize_fields(),
+                view_state_params: BTreeSet::from([String::from("zoom")]),
+                view_state_fields: params.view_state_field_keys().into_iter().collect(),
+            }
+        }
+
+        #[test]
+        fn without_view_state() {
+            let state = state().without_view_state();
+
+            assert_eq!(state.version, "1.0.0");
+            assert_eq!(state.params.keys().collect::<Vec<_>>(), ["gain"]);
+            assert_eq!(state.fields.keys().collect::<Vec<_>>(), ["sound"]);
+            assert!(state.view_state_params.is_empty());
+            assert!(state.view_state_fields.is_empty());
+        }
+
+        #[test]
+        fn view_state_only() {
+            let state = state().view_state_only();
+
+            assert_eq!(state.version, "1.0.0");
+            assert_eq!(state.params.keys().collect::<Vec<_>>(), ["zoom"]);
+            assert_eq!(
+                state.fields.keys().collect::<Vec<_>>(),
+                ["editor", "foo_bar"]
+            );
+            assert_eq!(state.view_state_params, state().view_state_params);
+            assert_eq!(state.view_state_fields, state().view_state_fields);
+        }
+    }
+
+    mod collections {
+        use super::super::*;
+
+        #[test]
+        fn roundtrip_vec() {
+            let params = CollectionParams::default();
+            *params.matrix.lock().unwrap() = (0..100).map(|i| (i, i as f32 / 4.0)).collect();
+            let serialized = params.serialize_fields();
+
+            let restored = CollectionParams::default();
+            restored.deserialize_fields(&serialized);
+            assert_eq!(
+                *restored.matrix.lock().unwrap(),
+                *params.matrix.lock().unwrap()
+            );
+            assert_eq!(restored.matrix.lock().unwrap().len(), 100);
+        }
+
+        #[test]
+        fn roundtrip_hash_map() {
+            let params = CollectionParams::default();
+            {
+                let mut presets = params.presets.lock().unwrap();
+                presets.insert(String::from("Init"), Vec::new());
+                presets.insert(String::from("Bank A"), vec![1, 2, 3]);
+            }
+            let serialized = params.serialize_fields();
+
+            let restored = CollectionParams::default();
+            restored.deserialize_fields(&serialized);
+            assert_eq!(
+                *restored.presets.lock().unwrap(),
+                *params.presets.lock().unwrap()
+            );
+        }
+    }
 }
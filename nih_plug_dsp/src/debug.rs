@@ -0,0 +1,83 @@
+//! Crate-private versions of NIH-plug's debug assertion macros. These behave exactly the same as
+//! the ones exported from `nih_plug::debug`, but they only depend on the `log` crate.
+
+/// A `debug_assert!()` analogue that prints the error with line number information instead of
+/// panicking. During tests this is upgraded to a regular panicking `debug_assert!()`.
+macro_rules! nih_debug_assert {
+    ($cond:expr $(,)?) => (
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if cfg!(test) {
+           debug_assert!($cond);
+        } else if cfg!(debug_assertions) && !$cond {
+            $crate::util::permit_alloc(|| ::log::warn!(concat!("Debug assertion failed: ", stringify!($cond))));
+        }
+    );
+    ($cond:expr, $format:expr $(, $($args:tt)*)?) => (
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if cfg!(test) {
+           debug_assert!($cond, $format, $($($args)*)?);
+        } else if cfg!(debug_assertions) && !$cond {
+            $crate::util::permit_alloc(|| ::log::warn!(concat!("Debug assertion failed: ", stringify!($cond), ", ", $format), $($($args)*)?));
+        }
+    );
+}
+
+/// A `debug_assert_eq!()` analogue that prints the error with line number information instead of
+/// panicking. See [`nih_debug_assert!()`] for more information.
+macro_rules! nih_debug_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if cfg!(test) {
+            debug_assert_eq!($left, $right);
+        } else if cfg!(debug_assertions) && $left != $right {
+            $crate::util::permit_alloc(|| {
+                ::log::warn!(concat!(
+                    "Debug assertion failed: ",
+                    stringify!($left),
+                    " != ",
+                    stringify!($right)
+                ))
+            });
+        }
+    };
+    ($left:expr, $right:expr, $format:expr $(, $($args:tt)*)?) => {
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if cfg!(test) {
+            debug_assert_eq!($left, $right, $format, $($($args)*)?);
+        } else if cfg!(debug_assertions) && $left != $right {
+            $crate::util::permit_alloc(|| {
+                ::log::warn!(
+                    concat!(
+                        "Debug assertion failed: ",
+                        stringify!($left),
+                        " != ",
+                        stringify!($right),
+                        ", ",
+                        $format
+                    ),
+                    $($($args)*)?
+                )
+            });
+        }
+    };
+}
+
+/// A `debug_assert_ne!()` analogue that prints the error with line number information instead of
+/// panicking. See [`nih_debug_assert!()`] for more information.
+macro_rules! nih_debug_assert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if cfg!(test) {
+            debug_assert_ne!($left, $right);
+        } else if cfg!(debug_assertions) && $left == $right {
+            $crate::util::permit_alloc(|| {
+                ::log::warn!(concat!(
+                    "Debug assertion failed: ",
+                    stringify!($left),
+                    " == ",
+                    stringify!($right)
+                ))
+            });
+        }
+    };
+}
@@ -0,0 +1,20 @@
+//! NIH-plug's plugin API agnostic DSP utilities. This contains the parameter smoothers, the window
+//! functions, the oscillators, and the various conversion functions from `nih_plug::util`, without
+//! depending on any of the plugin API bindings, the wrappers, or the GUI related dependencies.
+//! This makes it possible to reuse the same DSP code outside of a plugin, for instance in a
+//! command line tool or in an embedded application.
+//!
+//! Everything in this crate is also re-exported from NIH-plug itself, so plugins don't need to
+//! depend on this crate directly. The smoothers can be found under `nih_plug::params::smoothing`
+//! and everything else is available under `nih_plug::util`.
+//!
+//! This crate still depends on the standard library for the floating point math functions.
+
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+#![allow(clippy::type_complexity)]
+
+#[macro_use]
+mod debug;
+
+pub mod smoothing;
+pub mod util;
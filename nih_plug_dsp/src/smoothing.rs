@@ -1,6 +1,6 @@
 //! Utilities to handle smoothing parameter changes over time.
 
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 use std::sync::Arc;
 
 // Re-exported here because it's sued in `SmoothingStyle`.
@@ -63,6 +63,16 @@ pub struct Smoother<T: Smoothable> {
     current: AtomicF32,
     /// The value we're smoothing towards
     target: T::Atomic,
+
+    /// The `Exponential` smoothing style's coefficient only depends on the number of smoothing
+    /// steps, which in turn only depends on the sample rate and the style's duration. Computing it
+    /// requires a `powf()`, so the last coefficient is cached together with the number of steps it
+    /// was computed for. This is 0 if there's no cached coefficient. Since the number of steps
+    /// changes whenever the sample rate or the style's duration changes, mutating `style` does not
+    /// require explicitly invalidating this cache.
+    cached_exponential_num_steps: AtomicU32,
+    /// The cached exponential smoothing coefficient for `cached_exponential_num_steps` steps.
+    cached_exponential_step_size: AtomicF32,
 }
 
 /// An iterator that continuously produces smoothed values. Can be used as an alternative to the
@@ -73,6 +83,37 @@ pub struct SmootherIter<'a, T: Smoothable> {
     smoother: &'a Smoother<T>,
 }
 
+/// A crossfade between a plugin's dry input and its processed output for implementing a click-free
+/// hard bypass. This wraps a [`Smoother<f32>`] that ramps between `0.0` (not bypassed, only the wet
+/// signal is output) and `1.0` (bypassed, only the dry signal is output), using a 10 millisecond
+/// linear fade by default.
+///
+/// Like the smoothers, this uses interior mutability so it can be shared between the plugin and a
+/// `BoolParam`'s callback through an `Arc`:
+///
+/// ```ignore
+/// let bypass_ramp = Arc::new(BypassRamp::default());
+/// let bypass = BoolParam::new("Bypass", false)
+///     .with_callback(bypass_ramp.param_callback())
+///     .make_bypass();
+///
+/// // In `initialize()`
+/// self.bypass_ramp.set_sample_rate(buffer_config.sample_rate);
+/// // In `reset()`
+/// self.bypass_ramp.reset(self.params.bypass.value());
+/// // In `process()`, after copying the dry signal to `dry` and processing `wet`
+/// self.bypass_ramp.mix_block(&dry, &mut wet);
+/// ```
+#[derive(Debug)]
+pub struct BypassRamp {
+    /// Smooths between 0.0 for the wet signal and 1.0 for the dry signal.
+    smoother: Smoother<f32>,
+    /// The sample rate used to compute the ramp's length. This is stored here so
+    /// [`set_bypassed()`][Self::set_bypassed()] can be called from a parameter callback. This is
+    /// 0.0 until [`set_sample_rate()`][Self::set_sample_rate()] has been called.
+    sample_rate: AtomicF32,
+}
+
 impl SmoothingStyle {
     /// Compute the number of steps to reach the target value based on the sample rate and this
     /// smoothing style's duration.
@@ -118,6 +159,16 @@ impl SmoothingStyle {
         }
     }
 
+    /// Whether this is the `Exponential` smoothing style, possibly wrapped in an
+    /// `OversamplingAware` style.
+    fn is_exponential(&self) -> bool {
+        match self {
+            Self::OversamplingAware(_, style) => style.is_exponential(),
+            Self::Exponential(_) => true,
+            _ => false,
+        }
+    }
+
     /// Compute the next value from `current` leading up to `target` using the `step_size` computed
     /// using [`SmoothingStyle::step_size()`]. Depending on the smoothing style this function may
     /// never completely reach `target`, so you will need to snap to `target` yourself after
@@ -186,6 +237,9 @@ impl<T: Smoothable> Default for Smoother<T> {
             step_size: Default::default(),
             current: AtomicF32::new(0.0),
             target: Default::default(),
+
+            cached_exponential_num_steps: AtomicU32::new(0),
+            cached_exponential_step_size: AtomicF32::new(0.0),
         }
     }
 }
@@ -209,6 +263,13 @@ impl<T: Smoothable> Clone for Smoother<T> {
             step_size: AtomicF32::new(self.step_size.load(Ordering::Relaxed)),
             current: AtomicF32::new(self.current.load(Ordering::Relaxed)),
             target: T::atomic_new(T::atomic_load(&self.target)),
+
+            cached_exponential_num_steps: AtomicU32::new(
+                self.cached_exponential_num_steps.load(Ordering::Relaxed),
+            ),
+            cached_exponential_step_size: AtomicF32::new(
+                self.cached_exponential_step_size.load(Ordering::Relaxed),
+            ),
         }
     }
 }
@@ -254,6 +315,8 @@ impl<T: Smoothable> Smoother<T> {
         T::atomic_store(&self.target, value);
         self.current.store(value.to_f32(), Ordering::Relaxed);
         self.steps_left.store(0, Ordering::Relaxed);
+        self.cached_exponential_num_steps
+            .store(0, Ordering::Relaxed);
     }
 
     /// Set the target value.
@@ -267,7 +330,7 @@ impl<T: Smoothable> Smoother<T> {
         let target_f32 = target.to_f32();
         self.step_size.store(
             if steps_left > 0 {
-                self.style.step_size(current, target_f32, steps_left as u32)
+                self.compute_step_size(current, target_f32, steps_left as u32)
             } else {
                 0.0
             },
@@ -275,6 +338,26 @@ impl<T: Smoothable> Smoother<T> {
         );
     }
 
+    /// Compute the step size for the current smoothing style. For the `Exponential` style the
+    /// coefficient is cached, so it's only recomputed when the number of steps changes.
+    fn compute_step_size(&self, current: f32, target: f32, num_steps: u32) -> f32 {
+        if !self.style.is_exponential() {
+            return self.style.step_size(current, target, num_steps);
+        }
+
+        if self.cached_exponential_num_steps.load(Ordering::Relaxed) == num_steps {
+            return self.cached_exponential_step_size.load(Ordering::Relaxed);
+        }
+
+        let step_size = self.style.step_size(current, target, num_steps);
+        self.cached_exponential_step_size
+            .store(step_size, Ordering::Relaxed);
+        self.cached_exponential_num_steps
+            .store(num_steps, Ordering::Relaxed);
+
+        step_size
+    }
+
     /// Get the next value from this smoother. The value will be equal to the previous value once
     /// the smoothing period is over. This should be called exactly once per sample.
     // Yes, Clippy, like I said, this was intentional
@@ -485,6 +568,125 @@ impl<T: Smoothable> Smoother<T> {
     }
 }
 
+impl Smoother<f32> {
+    /// The same as [`set_target()`][Self::set_target()], but this does nothing if `target` is
+    /// bit-for-bit equal to the current target value. Calling [`set_target()`][Self::set_target()]
+    /// with an unchanged target would restart the smoothing period and recompute the step size,
+    /// so this is useful when the target value is set every sample, for instance from note
+    /// expression events or from another smoother.
+    #[inline]
+    pub fn set_target_relaxed(&self, sample_rate: f32, target: f32) {
+        if self.target.load(Ordering::Relaxed).to_bits() == target.to_bits() {
+            return;
+        }
+
+        self.set_target(sample_rate, target);
+    }
+}
+
+impl Default for BypassRamp {
+    fn default() -> Self {
+        Self::new(SmoothingStyle::Linear(10.0))
+    }
+}
+
+impl BypassRamp {
+    /// Create a bypass ramp that uses a custom smoothing style for the crossfade instead of the
+    /// default 10 millisecond linear fade.
+    pub fn new(style: SmoothingStyle) -> Self {
+        Self {
+            smoother: Smoother::new(style),
+            sample_rate: AtomicF32::new(0.0),
+        }
+    }
+
+    /// Set the sample rate used to compute the ramp's length. This should be called from the
+    /// plugin's `initialize()` function.
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// Start fading towards the dry signal if `bypassed` is true, or towards the wet signal
+    /// otherwise. If no sample rate has been set yet then the ramp immediately jumps to the new
+    /// state.
+    pub fn set_bypassed(&self, bypassed: bool) {
+        let target = if bypassed { 1.0 } else { 0.0 };
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        if sample_rate > 0.0 {
+            self.smoother.set_target(sample_rate, target);
+        } else {
+            self.smoother.reset(target);
+        }
+    }
+
+    /// Immediately jump to the bypassed or non-bypassed state without fading. This should be
+    /// called from the plugin's `reset()` function with the bypass parameter's current value.
+    pub fn reset(&self, bypassed: bool) {
+        self.smoother.reset(if bypassed { 1.0 } else { 0.0 });
+    }
+
+    /// Create a callback for a `BoolParam` that calls [`set_bypassed()`][Self::set_bypassed()]
+    /// whenever the parameter's value changes.
+    pub fn param_callback(self: &Arc<Self>) -> Arc<dyn Fn(bool) + Send + Sync> {
+        let this = self.clone();
+        Arc::new(move |bypassed| this.set_bypassed(bypassed))
+    }
+
+    /// Whether the ramp is currently fading between the dry and the wet signals.
+    #[inline]
+    pub fn is_smoothing(&self) -> bool {
+        self.smoother.is_smoothing()
+    }
+
+    /// Whether the plugin is fully bypassed and the ramp is no longer fading. When this returns
+    /// true the wet signal does not contribute to the output, so the plugin can skip its
+    /// processing altogether.
+    #[inline]
+    pub fn is_fully_bypassed(&self) -> bool {
+        !self.is_smoothing() && self.smoother.previous_value() >= 1.0
+    }
+
+    /// Get the dry signal's gain for the next sample and advance the ramp by one step. The wet
+    /// signal's gain is one minus this value. This can be used to apply the same gain to multiple
+    /// channels, since [`mix()`][Self::mix()] and [`mix_block()`][Self::mix_block()] advance the
+    /// ramp once for every sample they process.
+    #[inline]
+    pub fn next_dry_gain(&self) -> f32 {
+        self.smoother.next()
+    }
+
+    /// Crossfade a single sample between the dry and the wet signals. This advances the ramp by
+    /// one step.
+    #[inline]
+    pub fn mix(&self, dry: f32, wet: f32) -> f32 {
+        let dry_gain = self.next_dry_gain();
+        wet + ((dry - wet) * dry_gain)
+    }
+
+    /// Crossfade a block of samples between the dry and the wet signals, writing the result to
+    /// `wet`. This advances the ramp by one step for every sample, so for multichannel audio this
+    /// should either be used on a single channel, or [`next_dry_gain()`][Self::next_dry_gain()]
+    /// should be used instead.
+    pub fn mix_block(&self, dry: &[f32], wet: &mut [f32]) {
+        nih_debug_assert_eq!(dry.len(), wet.len());
+
+        if !self.is_smoothing() {
+            // The output is either the wet signal, which is already in place, or the dry signal
+            let dry_gain = self.smoother.previous_value();
+            if dry_gain >= 1.0 {
+                wet.copy_from_slice(&dry[..wet.len()]);
+                return;
+            } else if dry_gain <= 0.0 {
+                return;
+            }
+        }
+
+        for (dry_sample, wet_sample) in dry.iter().zip(wet.iter_mut()) {
+            *wet_sample = self.mix(*dry_sample, *wet_sample);
+        }
+    }
+}
+
 impl Smoothable for f32 {
     type Atomic = AtomicF32;
 
@@ -547,6 +749,37 @@ impl Smoothable for i32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn bypass_ramp_fade() {
+        let ramp = BypassRamp::default();
+        ramp.set_sample_rate(1000.0);
+        ramp.reset(false);
+        assert!(!ramp.is_fully_bypassed());
+
+        // At 1 kHz the 10 ms fade takes exactly 10 samples
+        ramp.set_bypassed(true);
+        let dry = [1.0; 12];
+        let mut wet = [0.0; 12];
+        ramp.mix_block(&dry, &mut wet);
+        for (idx, sample) in wet.iter().take(10).enumerate() {
+            approx::assert_relative_eq!(*sample, (idx + 1) as f32 / 10.0, epsilon = 1e-6);
+        }
+        assert_eq!(wet[10..], [1.0, 1.0]);
+        assert!(ramp.is_fully_bypassed());
+
+        let mut wet = [0.5; 4];
+        ramp.mix_block(&dry[..4], &mut wet);
+        assert_eq!(wet, [1.0; 4]);
+    }
+
+    #[test]
+    fn bypass_ramp_without_sample_rate() {
+        let ramp = BypassRamp::default();
+        ramp.set_bypassed(true);
+        assert!(ramp.is_fully_bypassed());
+        assert_eq!(ramp.mix(0.25, 0.75), 0.25);
+    }
+
     /// Applying `next()` `n` times should be the same as `next_step()` for `n` steps.
     #[test]
     fn linear_f32_next_equivalence() {
@@ -615,6 +848,68 @@ mod tests {
         assert_eq!(smoother.next(), 20.0);
     }
 
+    #[test]
+    fn linear_f32_set_target_relaxed() {
+        let smoother: Smoother<f32> = Smoother::new(SmoothingStyle::Linear(100.0));
+        smoother.reset(10.0);
+        smoother.set_target(100.0, 20.0);
+        smoother.next();
+        smoother.next();
+        let reference = smoother.clone();
+
+        // Setting the same target again should not restart the smoothing
+        smoother.set_target_relaxed(100.0, 20.0);
+        assert_eq!(smoother.steps_left(), reference.steps_left());
+        for _ in 0..10 {
+            assert_eq!(smoother.next(), reference.next());
+        }
+        assert_eq!(smoother.steps_left(), reference.steps_left());
+
+        // But a different target should behave exactly like `set_target()`
+        smoother.set_target_relaxed(100.0, 30.0);
+        reference.set_target(100.0, 30.0);
+        assert_eq!(smoother.steps_left(), reference.steps_left());
+        for _ in 0..10 {
+            assert_eq!(smoother.next(), reference.next());
+        }
+    }
+
+    #[test]
+    fn exponential_f32_cached_step_size() {
+        let mut smoother: Smoother<f32> = Smoother::new(SmoothingStyle::Exponential(100.0));
+        smoother.reset(0.0);
+        smoother.set_target(1000.0, 1.0);
+        let step_size = smoother.step_size.load(Ordering::Relaxed);
+        assert_eq!(
+            step_size,
+            SmoothingStyle::Exponential(100.0).step_size(0.0, 1.0, 100)
+        );
+
+        // Setting a new target with the same sample rate and style reuses the coefficient
+        smoother.set_target(1000.0, 0.5);
+        assert_eq!(smoother.step_size.load(Ordering::Relaxed), step_size);
+
+        // Both changing the sample rate and changing the style should result in a new coefficient
+        smoother.set_target(2000.0, 1.0);
+        assert_eq!(
+            smoother.step_size.load(Ordering::Relaxed),
+            SmoothingStyle::Exponential(100.0).step_size(0.0, 1.0, 200)
+        );
+
+        smoother.style = SmoothingStyle::Exponential(50.0);
+        smoother.set_target(1000.0, 0.0);
+        assert_eq!(
+            smoother.step_size.load(Ordering::Relaxed),
+            SmoothingStyle::Exponential(50.0).step_size(0.0, 1.0, 50)
+        );
+
+        // A different style with the same number of steps must not use the cached coefficient
+        smoother.style = SmoothingStyle::Linear(50.0);
+        smoother.reset(0.0);
+        smoother.set_target(1000.0, 1.0);
+        assert_eq!(smoother.step_size.load(Ordering::Relaxed), 1.0 / 50.0);
+    }
+
     #[test]
     fn linear_i32_smoothing() {
         let smoother: Smoother<i32> = Smoother::new(SmoothingStyle::Linear(100.0));
@@ -0,0 +1,285 @@
+//! Scratch buffers with SIMD-aligned channels, for use with vectorized third party DSP code.
+
+/// The alignment in bytes of every channel in an [`AlignedBuffers`] object. This is enough for
+/// aligned loads and stores with AVX-512, and thus also for SSE, AVX, and NEON.
+pub const SIMD_ALIGNMENT: usize = 64;
+
+/// The number of `f32` samples that fit in [`SIMD_ALIGNMENT`] bytes.
+const SAMPLES_PER_CHUNK: usize = SIMD_ALIGNMENT / std::mem::size_of::<f32>();
+
+/// A single aligned chunk of samples. The channels in [`AlignedBuffers`] are made out of these,
+/// which guarantees their alignment without needing a custom allocator.
+#[repr(C, align(64))]
+#[derive(Debug, Clone, Copy)]
+struct AlignedChunk([f32; SAMPLES_PER_CHUNK]);
+
+/// Preallocated scratch buffers where every channel starts at a [`SIMD_ALIGNMENT`]-byte aligned
+/// address. NIH-plug's `Buffer` points directly to the host's buffers, which may not be aligned at
+/// all. Vectorized routines that need aligned loads and stores can
+/// instead copy the audio into these buffers, process them in place, and then copy the results
+/// back. Both planar and interleaved copies are supported, since some libraries only accept
+/// interleaved audio.
+///
+/// These buffers should be allocated in `initialize()` with enough capacity for the maximum buffer
+/// size. None of the other functions allocate.
+///
+/// ```
+/// # use nih_plug_dsp::util::{AlignedBuffers, SIMD_ALIGNMENT};
+/// let mut buffers = AlignedBuffers::new(2, 512);
+///
+/// let left = vec![0.5; 512];
+/// let right = vec![-0.5; 512];
+/// buffers.copy_from(&[&left[..], &right[..]]);
+///
+/// for channel in buffers.channels_mut() {
+///     assert_eq!(channel.as_ptr() as usize % SIMD_ALIGNMENT, 0);
+///     // Call the vectorized routine here
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlignedBuffers {
+    /// The samples for all channels. Each channel takes up `chunks_per_channel` chunks.
+    chunks: Vec<AlignedChunk>,
+    /// The number of channels.
+    num_channels: usize,
+    /// The number of samples in each channel.
+    num_samples: usize,
+    /// The number of chunks between the start of two channels. This is at least 1 so
+    /// `chunks_exact()` can be used even if `num_samples` is 0.
+    chunks_per_channel: usize,
+}
+
+impl Default for AlignedBuffers {
+    /// Empty buffers without any channels. These can be allocated later using
+    /// [`resize()`][Self::resize()].
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            num_channels: 0,
+            num_samples: 0,
+            chunks_per_channel: 1,
+        }
+    }
+}
+
+impl AlignedBuffers {
+    /// Allocate `num_channels` zeroed channels containing `num_samples` samples each.
+    pub fn new(num_channels: usize, num_samples: usize) -> Self {
+        let mut buffers = Self::default();
+        buffers.resize(num_channels, num_samples);
+
+        buffers
+    }
+
+    /// Change the number of channels and the number of samples per channel. This clears the
+    /// buffers, and it only allocates if the buffers need to grow.
+    pub fn resize(&mut self, num_channels: usize, num_samples: usize) {
+        self.num_channels = num_channels;
+        self.num_samples = num_samples;
+        self.chunks_per_channel = num_samples.div_ceil(SAMPLES_PER_CHUNK).max(1);
+
+        self.chunks.clear();
+        self.chunks.resize(
+            num_channels * self.chunks_per_channel,
+            AlignedChunk([0.0; SAMPLES_PER_CHUNK]),
+        );
+    }
+
+    /// The number of channels.
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// The number of samples in each channel.
+    pub fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// Get a channel's samples. The slice's start is aligned to [`SIMD_ALIGNMENT`] bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_idx` is out of bounds.
+    pub fn channel(&self, channel_idx: usize) -> &[f32] {
+        assert!(channel_idx < self.num_channels);
+
+        let start = channel_idx * self.chunks_per_channel;
+        chunks_as_samples(
+            &self.chunks[start..start + self.chunks_per_channel],
+            self.num_samples,
+        )
+    }
+
+    /// Get a channel's samples. The slice's start is aligned to [`SIMD_ALIGNMENT`] bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_idx` is out of bounds.
+    pub fn channel_mut(&mut self, channel_idx: usize) -> &mut [f32] {
+        assert!(channel_idx < self.num_channels);
+
+        let start = channel_idx * self.chunks_per_channel;
+        chunks_as_samples_mut(
+            &mut self.chunks[start..start + self.chunks_per_channel],
+            self.num_samples,
+        )
+    }
+
+    /// Iterate over all channels. Every channel's start is aligned to [`SIMD_ALIGNMENT`] bytes.
+    pub fn channels_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        let num_samples = self.num_samples;
+        self.chunks
+            .chunks_exact_mut(self.chunks_per_channel)
+            .map(move |chunks| chunks_as_samples_mut(chunks, num_samples))
+    }
+
+    /// Copy planar audio into these buffers. `channels` contains one slice per channel, like the
+    /// slices returned from NIH-plug's `Buffer::as_slice()`. If the slices are shorter than the
+    /// buffers, then only the start of the buffers is overwritten.
+    pub fn copy_from<S: AsRef<[f32]>>(&mut self, channels: &[S]) {
+        nih_debug_assert_eq!(channels.len(), self.num_channels);
+
+        for (source, target) in channels.iter().zip(self.channels_mut()) {
+            let source = source.as_ref();
+            nih_debug_assert!(source.len() <= target.len());
+
+            let len = source.len().min(target.len());
+            target[..len].copy_from_slice(&source[..len]);
+        }
+    }
+
+    /// Copy the start of these buffers back to planar audio. This is the inverse of
+    /// [`copy_from()`][Self::copy_from()].
+    pub fn copy_to<S: AsMut<[f32]>>(&self, channels: &mut [S]) {
+        nih_debug_assert_eq!(channels.len(), self.num_channels);
+
+        for (channel_idx, target) in channels.iter_mut().enumerate() {
+            let target = target.as_mut();
+            let source = self.channel(channel_idx);
+            nih_debug_assert!(target.len() <= source.len());
+
+            let len = source.len().min(target.len());
+            target[..len].copy_from_slice(&source[..len]);
+        }
+    }
+
+    /// Deinterleave `num_samples` frames of interleaved audio into these buffers. `interleaved`
+    /// should contain `num_samples * num_channels` samples.
+    pub fn copy_from_interleaved(&mut self, interleaved: &[f32], num_samples: usize) {
+        nih_debug_assert!(num_samples <= self.num_samples);
+        nih_debug_assert!(interleaved.len() >= num_samples * self.num_channels);
+
+        let num_channels = self.num_channels;
+        for (channel_idx, channel) in self.channels_mut().enumerate() {
+            for (sample, frame) in channel
+                .iter_mut()
+                .zip(interleaved.chunks_exact(num_channels))
+                .take(num_samples)
+            {
+                *sample = frame[channel_idx];
+            }
+        }
+    }
+
+    /// Interleave the first `num_samples` samples of each channel into `interleaved`. This is the
+    /// inverse of [`copy_from_interleaved()`][Self::copy_from_interleaved()].
+    pub fn copy_to_interleaved(&self, interleaved: &mut [f32], num_samples: usize) {
+        nih_debug_assert!(num_samples <= self.num_samples);
+        nih_debug_assert!(interleaved.len() >= num_samples * self.num_channels);
+
+        for channel_idx in 0..self.num_channels {
+            for (sample, frame) in self
+                .channel(channel_idx)
+                .iter()
+                .zip(interleaved.chunks_exact_mut(self.num_channels))
+                .take(num_samples)
+            {
+                frame[channel_idx] = *sample;
+            }
+        }
+    }
+}
+
+/// Reinterpret a slice of chunks as the first `num_samples` samples contained within them.
+fn chunks_as_samples(chunks: &[AlignedChunk], num_samples: usize) -> &[f32] {
+    assert!(num_samples <= chunks.len() * SAMPLES_PER_CHUNK);
+
+    // SAFETY: `AlignedChunk` is a `repr(C)` wrapper around an `f32` array without any padding, so
+    //         the chunks form a contiguous array of `f32`s
+    unsafe { std::slice::from_raw_parts(chunks.as_ptr() as *const f32, num_samples) }
+}
+
+/// Reinterpret a slice of chunks as the first `num_samples` samples contained within them.
+fn chunks_as_samples_mut(chunks: &mut [AlignedChunk], num_samples: usize) -> &mut [f32] {
+    assert!(num_samples <= chunks.len() * SAMPLES_PER_CHUNK);
+
+    // SAFETY: See above
+    unsafe { std::slice::from_raw_parts_mut(chunks.as_mut_ptr() as *mut f32, num_samples) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment() {
+        // An odd number of samples makes sure the channels are padded to the alignment
+        let mut buffers = AlignedBuffers::new(3, 101);
+        assert_eq!(buffers.num_channels(), 3);
+        assert_eq!(buffers.num_samples(), 101);
+
+        for channel in buffers.channels_mut() {
+            assert_eq!(channel.len(), 101);
+            assert_eq!(channel.as_ptr() as usize % SIMD_ALIGNMENT, 0);
+        }
+    }
+
+    #[test]
+    fn test_default_is_empty() {
+        let mut buffers = AlignedBuffers::default();
+        assert_eq!(buffers.num_channels(), 0);
+        assert_eq!(buffers.channels_mut().count(), 0);
+
+        let no_channels: [&[f32]; 0] = [];
+        buffers.copy_from(&no_channels);
+        buffers.copy_from_interleaved(&[], 0);
+    }
+
+    #[test]
+    fn test_interleaved_roundtrip() {
+        let mut buffers = AlignedBuffers::new(2, 8);
+
+        let interleaved: Vec<f32> = (0..16).map(|sample| sample as f32).collect();
+        buffers.copy_from_interleaved(&interleaved, 8);
+        assert_eq!(
+            buffers.channel(0),
+            &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 14.0]
+        );
+        assert_eq!(
+            buffers.channel(1),
+            &[1.0, 3.0, 5.0, 7.0, 9.0, 11.0, 13.0, 15.0]
+        );
+
+        let mut output = vec![0.0; 16];
+        buffers.copy_to_interleaved(&mut output, 8);
+        assert_eq!(output, interleaved);
+    }
+
+    #[test]
+    fn test_planar_roundtrip() {
+        let mut buffers = AlignedBuffers::new(2, 16);
+
+        let mut left = vec![1.0; 4];
+        let mut right = vec![2.0; 4];
+        buffers.copy_from(&[&left[..], &right[..]]);
+        for channel in buffers.channels_mut() {
+            for sample in channel.iter_mut() {
+                *sample *= 2.0;
+            }
+        }
+
+        buffers.copy_to(&mut [&mut left[..], &mut right[..]]);
+        assert_eq!(left, [2.0; 4]);
+        assert_eq!(right, [4.0; 4]);
+    }
+}
@@ -0,0 +1,293 @@
+//! Envelope generators, useful for building synthesizers.
+
+/// Once the envelope drops below this value during its release stage, it snaps to zero and stops
+/// being active. This is about -60 dB.
+pub const ENVELOPE_SILENCE_THRESHOLD: f32 = 0.001;
+
+/// The attack stage transitions to the decay stage once the envelope gets this close to 1.0.
+const ATTACK_PEAK_THRESHOLD: f32 = 0.001;
+
+/// The stages an [`Adsr`] envelope goes through.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AdsrStage {
+    /// The envelope has not been triggered yet, or it has finished releasing. The output is 0.
+    #[default]
+    Idle,
+    /// The envelope is rising towards 1.0.
+    Attack,
+    /// The envelope is falling towards the sustain level. The envelope stays in this stage until
+    /// it gets released, so this also covers the sustain stage.
+    Decay,
+    /// The envelope is falling towards 0.0.
+    Release,
+}
+
+/// An ADSR envelope generator where every segment is a first order IIR filter moving towards the
+/// segment's target, so the segments have the usual exponential shapes. The output is in the range
+/// `[0, 1]`. Setting the sustain level to 1.0 turns this into an attack-release envelope where the
+/// attack stage continues until the envelope is released, regardless of the decay time. There are
+/// no allocations, so this can be stored directly in a synth's voices.
+///
+/// The segment times are the time constants of the filters, so the attack stage reaches about 63%
+/// of its target after the attack time.
+///
+/// ```
+/// # use nih_plug_dsp::util::envelope::Adsr;
+/// let mut envelope = Adsr::default();
+/// envelope.set_attack(44_100.0, 5.0);
+/// envelope.set_decay(44_100.0, 100.0);
+/// envelope.set_sustain(0.5);
+/// envelope.set_release(44_100.0, 200.0);
+///
+/// envelope.trigger();
+/// let mut output = [0.0; 64];
+/// envelope.next_block(&mut output, 64);
+///
+/// envelope.release();
+/// while envelope.is_active() {
+///     envelope.next();
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Adsr {
+    /// The current/previously returned value.
+    state: f32,
+    /// The stage the envelope is currently in.
+    stage: AdsrStage,
+
+    /// For each sample, the output becomes `(state * t) + (target * (1.0 - t))`. This is `t` during
+    /// the attack stage of the envelope generator.
+    attack_retain_t: f32,
+    /// `attack_retain_t`, but for the decay stage.
+    decay_retain_t: f32,
+    /// `attack_retain_t`, but for the release stage.
+    release_retain_t: f32,
+    /// The level the decay stage moves towards, in `[0, 1]`.
+    sustain_level: f32,
+}
+
+impl Default for Adsr {
+    /// An envelope with instant attack, decay, and release stages and full sustain.
+    fn default() -> Self {
+        Self {
+            state: 0.0,
+            stage: AdsrStage::Idle,
+
+            attack_retain_t: 0.0,
+            decay_retain_t: 0.0,
+            release_retain_t: 0.0,
+            sustain_level: 1.0,
+        }
+    }
+}
+
+impl Adsr {
+    /// Set the attack time in milliseconds. This can be changed at any time, including while the
+    /// envelope is running.
+    pub fn set_attack(&mut self, sample_rate: f32, time_ms: f32) {
+        self.attack_retain_t = retain_t(sample_rate, time_ms);
+    }
+
+    /// Set the decay time in milliseconds. This has no effect when the sustain level is 1.0.
+    pub fn set_decay(&mut self, sample_rate: f32, time_ms: f32) {
+        self.decay_retain_t = retain_t(sample_rate, time_ms);
+    }
+
+    /// Set the sustain level, in `[0, 1]`.
+    pub fn set_sustain(&mut self, level: f32) {
+        nih_debug_assert!((0.0..=1.0).contains(&level));
+        self.sustain_level = level.clamp(0.0, 1.0);
+    }
+
+    /// Set the release time in milliseconds.
+    pub fn set_release(&mut self, sample_rate: f32, time_ms: f32) {
+        self.release_retain_t = retain_t(sample_rate, time_ms);
+    }
+
+    /// Completely reset the envelope generator. The output drops to zero immediately.
+    pub fn reset(&mut self) {
+        self.state = 0.0;
+        self.stage = AdsrStage::Idle;
+    }
+
+    /// Start the attack stage of the envelope generator. This doesn't reset the current value, so
+    /// retriggering a voice that's still playing doesn't cause the amplitude to jump back to zero.
+    /// Call [`reset()`][Self::reset()] first to start from zero.
+    pub fn trigger(&mut self) {
+        self.stage = AdsrStage::Attack;
+    }
+
+    /// Start the release stage of the envelope generator. Does nothing if the envelope is not
+    /// active.
+    pub fn release(&mut self) {
+        if self.stage != AdsrStage::Idle {
+            self.stage = AdsrStage::Release;
+        }
+    }
+
+    /// The stage the envelope generator is currently in.
+    pub fn stage(&self) -> AdsrStage {
+        self.stage
+    }
+
+    /// Whether the envelope generator is producing output. This becomes `false` once the release
+    /// stage has dropped below [`ENVELOPE_SILENCE_THRESHOLD`], at which point a synth can
+    /// terminate the voice.
+    pub fn is_active(&self) -> bool {
+        self.stage != AdsrStage::Idle
+    }
+
+    /// Whether the envelope generator is in its release stage and has not finished releasing yet.
+    pub fn is_releasing(&self) -> bool {
+        self.stage == AdsrStage::Release
+    }
+
+    /// Return the current/previously returned value.
+    pub fn current(&self) -> f32 {
+        self.state
+    }
+
+    /// Compute the next value.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> f32 {
+        match self.stage {
+            AdsrStage::Idle => (),
+            AdsrStage::Attack => {
+                self.state = step(self.state, 1.0, self.attack_retain_t);
+
+                // With full sustain there's nothing to decay to, so the attack stage continues
+                // towards 1.0 until the envelope gets released
+                if self.sustain_level < 1.0 && self.state >= 1.0 - ATTACK_PEAK_THRESHOLD {
+                    self.stage = AdsrStage::Decay;
+                }
+            }
+            AdsrStage::Decay => {
+                self.state = step(self.state, self.sustain_level, self.decay_retain_t);
+            }
+            AdsrStage::Release => {
+                self.state = step(self.state, 0.0, self.release_retain_t);
+                if self.state < ENVELOPE_SILENCE_THRESHOLD {
+                    self.reset();
+                }
+            }
+        }
+
+        self.state
+    }
+
+    /// Compute the next `block_len` values and store them in `block_values`.
+    pub fn next_block(&mut self, block_values: &mut [f32], block_len: usize) {
+        nih_debug_assert!(block_values.len() >= block_len);
+        for value in block_values.iter_mut().take(block_len) {
+            *value = self.next();
+        }
+    }
+}
+
+/// Compute the filter coefficient for a segment with a time constant of `time_ms` milliseconds. A
+/// time of zero results in a coefficient of zero, which makes the segment instant.
+fn retain_t(sample_rate: f32, time_ms: f32) -> f32 {
+    nih_debug_assert!(sample_rate > 0.0);
+    nih_debug_assert!(time_ms >= 0.0);
+    (-1.0 / (time_ms / 1000.0 * sample_rate)).exp()
+}
+
+#[inline]
+fn step(state: f32, target: f32, t: f32) -> f32 {
+    (state * t) + (target * (1.0 - t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Buffr Glitch's one pole attack-release envelope, which the AR special case should match.
+    struct ReferenceArEnvelope {
+        state: f32,
+        attack_retain_t: f32,
+        release_retain_t: f32,
+        releasing: bool,
+    }
+
+    impl ReferenceArEnvelope {
+        fn next(&mut self) -> f32 {
+            let (target, t) = if self.releasing {
+                (0.0, self.release_retain_t)
+            } else {
+                (1.0, self.attack_retain_t)
+            };
+
+            self.state = (self.state * t) + (target * (1.0 - t));
+            self.state
+        }
+    }
+
+    #[test]
+    fn test_ar_matches_one_pole_envelope() {
+        const SAMPLE_RATE: f32 = 44_100.0;
+        const ATTACK_MS: f32 = 10.0;
+        const RELEASE_MS: f32 = 50.0;
+
+        let mut envelope = Adsr::default();
+        envelope.set_attack(SAMPLE_RATE, ATTACK_MS);
+        envelope.set_decay(SAMPLE_RATE, 0.0);
+        envelope.set_sustain(1.0);
+        envelope.set_release(SAMPLE_RATE, RELEASE_MS);
+        let mut reference = ReferenceArEnvelope {
+            state: 0.0,
+            attack_retain_t: (-1.0 / (ATTACK_MS / 1000.0 * SAMPLE_RATE)).exp(),
+            release_retain_t: (-1.0 / (RELEASE_MS / 1000.0 * SAMPLE_RATE)).exp(),
+            releasing: false,
+        };
+
+        envelope.trigger();
+        for _ in 0..4410 {
+            assert_eq!(envelope.next(), reference.next());
+        }
+        assert_eq!(envelope.stage(), AdsrStage::Attack);
+
+        envelope.release();
+        reference.releasing = true;
+        while envelope.is_active() {
+            let value = envelope.next();
+            let expected = reference.next();
+            if envelope.is_active() {
+                assert_eq!(value, expected);
+            } else {
+                assert_eq!(value, 0.0);
+                assert!(expected < ENVELOPE_SILENCE_THRESHOLD);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adsr_stages() {
+        const SAMPLE_RATE: f32 = 1000.0;
+
+        let mut envelope = Adsr::default();
+        envelope.set_attack(SAMPLE_RATE, 5.0);
+        envelope.set_decay(SAMPLE_RATE, 20.0);
+        envelope.set_sustain(0.25);
+        envelope.set_release(SAMPLE_RATE, 10.0);
+        assert!(!envelope.is_active());
+        assert_eq!(envelope.next(), 0.0);
+
+        envelope.trigger();
+        let mut output = [0.0; 300];
+        envelope.next_block(&mut output, 300);
+        assert!(output.windows(2).take(10).all(|w| w[1] > w[0]));
+        assert_eq!(envelope.stage(), AdsrStage::Decay);
+        approx::assert_relative_eq!(envelope.current(), 0.25, epsilon = 1e-3);
+
+        envelope.release();
+        let mut num_release_samples = 0;
+        while envelope.is_active() {
+            envelope.next();
+            num_release_samples += 1;
+            assert!(num_release_samples < 1000);
+        }
+        assert_eq!(envelope.stage(), AdsrStage::Idle);
+        assert_eq!(envelope.current(), 0.0);
+    }
+}
@@ -0,0 +1,474 @@
+//! Biquad filters, generic over `f32` and SIMD vector types.
+
+use num_complex::Complex32;
+use std::f32::consts;
+use std::ops::{Add, Mul, Sub};
+
+#[cfg(feature = "simd")]
+use std::simd::{LaneCount, Simd, SupportedLaneCount};
+
+/// A simple biquad filter with functions for generating coefficients for the common filter types
+/// from the Audio EQ Cookbook. Every channel needs its own filter, but multiple channels can be
+/// processed at once by using a SIMD vector type for `T`.
+///
+/// Based on <https://en.wikipedia.org/wiki/Digital_biquad_filter#Transposed_direct_forms>.
+///
+/// ```
+/// # use nih_plug_dsp::util::filter::{Biquad, BiquadCoefficients};
+/// let mut filter = Biquad::<f32>::default();
+/// filter.coefficients = BiquadCoefficients::lowpass(44_100.0, 1_000.0, 0.707);
+///
+/// let mut samples = [1.0; 64];
+/// for sample in samples.iter_mut() {
+///     *sample = filter.process(*sample);
+/// }
+/// ```
+///
+/// The type parameter T should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad<T> {
+    pub coefficients: BiquadCoefficients<T>,
+    s1: T,
+    s2: T,
+}
+
+/// State values with a smaller magnitude than this are flushed to zero by
+/// [`Biquad::process_denormal_safe()`]. This is well above the range where `f32`s become denormal,
+/// and at around -300 dB it's also well below anything audible.
+pub const DENORMAL_THRESHOLD: f32 = 1e-15;
+
+/// The coefficients `[b0, b1, b2, a1, a2]` for [`Biquad`]. These coefficients are all
+/// prenormalized, i.e. they have been divided by `a0`.
+///
+/// The type parameter T should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug)]
+pub struct BiquadCoefficients<T> {
+    b0: T,
+    b1: T,
+    b2: T,
+    a1: T,
+    a2: T,
+}
+
+/// Either an `f32` or some SIMD vector type of `f32`s that can be used with our biquads. This is
+/// implemented for `std::simd` vectors when NIH-plug's `simd` feature is enabled. Plugins can also
+/// implement this for their own vector types.
+pub trait SimdType:
+    Mul<Output = Self> + Sub<Output = Self> + Add<Output = Self> + Copy + Sized
+{
+    fn from_f32(value: f32) -> Self;
+
+    /// Replace values with a magnitude below [`DENORMAL_THRESHOLD`] with zero.
+    fn flush_denormals(self) -> Self;
+}
+
+impl<T: SimdType> Default for Biquad<T> {
+    /// Before setting constants the filter should just act as an identity function.
+    fn default() -> Self {
+        Self {
+            coefficients: BiquadCoefficients::identity(),
+            s1: T::from_f32(0.0),
+            s2: T::from_f32(0.0),
+        }
+    }
+}
+
+impl<T: SimdType> Biquad<T> {
+    /// Process a single sample.
+    pub fn process(&mut self, sample: T) -> T {
+        let result = self.coefficients.b0 * sample + self.s1;
+
+        self.s1 = self.coefficients.b1 * sample - self.coefficients.a1 * result + self.s2;
+        self.s2 = self.coefficients.b2 * sample - self.coefficients.a2 * result;
+
+        result
+    }
+
+    /// Process a single sample like [`process()`][Self::process()], but flush the filter's state
+    /// to zero once it decays below [`DENORMAL_THRESHOLD`]. Resonant filters and long filter
+    /// cascades can otherwise end up processing denormals for a long time after the input has gone
+    /// silent, which can cause CPU spikes if the host or platform does not enable flush-to-zero.
+    pub fn process_denormal_safe(&mut self, sample: T) -> T {
+        let result = self.process(sample);
+
+        self.s1 = self.s1.flush_denormals();
+        self.s2 = self.s2.flush_denormals();
+
+        result
+    }
+
+    /// Reset the state to zero, useful after making making large, non-interpolatable changes to the
+    /// filter coefficients.
+    pub fn reset(&mut self) {
+        self.s1 = T::from_f32(0.0);
+        self.s2 = T::from_f32(0.0);
+    }
+}
+
+impl<T: SimdType> BiquadCoefficients<T> {
+    /// Convert scalar coefficients into the correct vector type.
+    pub fn from_f32s(scalar: BiquadCoefficients<f32>) -> Self {
+        Self {
+            b0: T::from_f32(scalar.b0),
+            b1: T::from_f32(scalar.b1),
+            b2: T::from_f32(scalar.b2),
+            a1: T::from_f32(scalar.a1),
+            a2: T::from_f32(scalar.a2),
+        }
+    }
+
+    /// Filter coefficients that would cause the sound to be passed through as is.
+    pub fn identity() -> Self {
+        Self::from_f32s(BiquadCoefficients {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        })
+    }
+
+    /// Compute the coefficients for a low-pass filter. The magnitude response at `frequency` is
+    /// equal to `q`.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn lowpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha;
+        let b0 = ((1.0 - cos_omega0) / 2.0) / a0;
+        let b1 = (1.0 - cos_omega0) / a0;
+        let b2 = ((1.0 - cos_omega0) / 2.0) / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a high-pass filter. The magnitude response at `frequency` is
+    /// equal to `q`.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn highpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha;
+        let b0 = ((1.0 + cos_omega0) / 2.0) / a0;
+        let b1 = -(1.0 + cos_omega0) / a0;
+        let b2 = ((1.0 + cos_omega0) / 2.0) / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a band-pass filter with a constant 0 dB peak gain at
+    /// `frequency`.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn bandpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha;
+        let b0 = alpha / a0;
+        let b1 = 0.0;
+        let b2 = -alpha / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a notch filter. This completely removes `frequency` from the
+    /// signal.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn notch(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha;
+        let b0 = 1.0 / a0;
+        let b1 = (-2.0 * cos_omega0) / a0;
+        let b2 = 1.0 / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for an all-pass filter. This leaves the signal's magnitude response
+    /// untouched and only shifts its phase, with the phase shift passing through 180 degrees at
+    /// `frequency`.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn allpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 - alpha) / a0;
+        let b1 = (-2.0 * cos_omega0) / a0;
+        let b2 = (1.0 + alpha) / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a peaking EQ filter that boosts or cuts `frequency` by
+    /// `gain_db` decibels.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+
+        // We'll prenormalize everything with a0
+        let a0 = 1.0 + alpha / a;
+        let b0 = (1.0 + alpha * a) / a0;
+        let b1 = (-2.0 * cos_omega0) / a0;
+        let b2 = (1.0 - alpha * a) / a0;
+        let a1 = (-2.0 * cos_omega0) / a0;
+        let a2 = (1.0 - alpha / a) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a low shelf filter that boosts or cuts everything below
+    /// `frequency` by `gain_db` decibels. The gain at `frequency` is half of `gain_db`. A `q` of
+    /// `1 / sqrt(2)` results in the steepest slope without overshoot.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        // We'll prenormalize everything with a0
+        let a0 = (a + 1.0) + (a - 1.0) * cos_omega0 + sqrt_a_alpha;
+        let b0 = (a * ((a + 1.0) - (a - 1.0) * cos_omega0 + sqrt_a_alpha)) / a0;
+        let b1 = (2.0 * a * ((a - 1.0) - (a + 1.0) * cos_omega0)) / a0;
+        let b2 = (a * ((a + 1.0) - (a - 1.0) * cos_omega0 - sqrt_a_alpha)) / a0;
+        let a1 = (-2.0 * ((a - 1.0) + (a + 1.0) * cos_omega0)) / a0;
+        let a2 = ((a + 1.0) + (a - 1.0) * cos_omega0 - sqrt_a_alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+
+    /// Compute the coefficients for a high shelf filter that boosts or cuts everything above
+    /// `frequency` by `gain_db` decibels. The gain at `frequency` is half of `gain_db`. A `q` of
+    /// `1 / sqrt(2)` results in the steepest slope without overshoot.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_params(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        // We'll prenormalize everything with a0
+        let a0 = (a + 1.0) - (a - 1.0) * cos_omega0 + sqrt_a_alpha;
+        let b0 = (a * ((a + 1.0) + (a - 1.0) * cos_omega0 + sqrt_a_alpha)) / a0;
+        let b1 = (-2.0 * a * ((a - 1.0) + (a + 1.0) * cos_omega0)) / a0;
+        let b2 = (a * ((a + 1.0) + (a - 1.0) * cos_omega0 - sqrt_a_alpha)) / a0;
+        let a1 = (2.0 * ((a - 1.0) - (a + 1.0) * cos_omega0)) / a0;
+        let a2 = ((a + 1.0) - (a - 1.0) * cos_omega0 - sqrt_a_alpha) / a0;
+
+        Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
+    }
+}
+
+impl BiquadCoefficients<f32> {
+    /// Compute the filter's linear magnitude response at `frequency`. This can be used to draw
+    /// the filter's frequency response in a GUI.
+    pub fn magnitude_response(&self, sample_rate: f32, frequency: f32) -> f32 {
+        // This evaluates the transfer function at `z = e^(j * omega)`
+        let omega = consts::TAU * (frequency / sample_rate);
+        let z1 = Complex32::from_polar(1.0, -omega);
+        let z2 = z1 * z1;
+
+        let numerator = self.b0 + z1 * self.b1 + z2 * self.b2;
+        let denominator = Complex32::new(1.0, 0.0) + z1 * self.a1 + z2 * self.a2;
+
+        (numerator / denominator).norm()
+    }
+}
+
+/// Compute the `cos(omega0)` and `alpha` terms shared by all of the cookbook filters.
+fn cookbook_params(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+    nih_debug_assert!(sample_rate > 0.0);
+    nih_debug_assert!(frequency > 0.0);
+    nih_debug_assert!(frequency < sample_rate / 2.0);
+    nih_debug_assert!(q > 0.0);
+
+    let omega0 = consts::TAU * (frequency / sample_rate);
+    let cos_omega0 = omega0.cos();
+    let alpha = omega0.sin() / (2.0 * q);
+
+    (cos_omega0, alpha)
+}
+
+impl SimdType for f32 {
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn flush_denormals(self) -> Self {
+        if self.abs() < DENORMAL_THRESHOLD {
+            0.0
+        } else {
+            self
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<const LANES: usize> SimdType for Simd<f32, LANES>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        Simd::splat(value)
+    }
+
+    #[inline(always)]
+    fn flush_denormals(self) -> Self {
+        Simd::from_array(self.to_array().map(f32::flush_denormals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+    const NYQUIST: f32 = SAMPLE_RATE / 2.0;
+    const BUTTERWORTH_Q: f32 = consts::FRAC_1_SQRT_2;
+
+    fn assert_response(coefficients: BiquadCoefficients<f32>, frequency: f32, expected: f32) {
+        let response = coefficients.magnitude_response(SAMPLE_RATE, frequency);
+        assert!(
+            (response - expected).abs() < 1e-3,
+            "Expected a magnitude of {expected} at {frequency} Hz, got {response}"
+        );
+    }
+
+    #[test]
+    fn test_pass_filter_responses() {
+        let lowpass = BiquadCoefficients::lowpass(SAMPLE_RATE, 1_000.0, 2.0);
+        assert_response(lowpass, 1.0, 1.0);
+        assert_response(lowpass, 1_000.0, 2.0);
+        assert_response(lowpass, NYQUIST, 0.0);
+
+        let highpass = BiquadCoefficients::highpass(SAMPLE_RATE, 1_000.0, BUTTERWORTH_Q);
+        assert_response(highpass, 1.0, 0.0);
+        assert_response(highpass, 1_000.0, BUTTERWORTH_Q);
+        assert_response(highpass, NYQUIST, 1.0);
+
+        let bandpass = BiquadCoefficients::bandpass(SAMPLE_RATE, 1_000.0, 4.0);
+        assert_response(bandpass, 1.0, 0.0);
+        assert_response(bandpass, 1_000.0, 1.0);
+        assert_response(bandpass, NYQUIST, 0.0);
+
+        let notch = BiquadCoefficients::notch(SAMPLE_RATE, 1_000.0, 4.0);
+        assert_response(notch, 1.0, 1.0);
+        assert_response(notch, 1_000.0, 0.0);
+        assert_response(notch, NYQUIST, 1.0);
+
+        let allpass = BiquadCoefficients::allpass(SAMPLE_RATE, 1_000.0, 4.0);
+        for frequency in [1.0, 500.0, 1_000.0, 5_000.0, NYQUIST] {
+            assert_response(allpass, frequency, 1.0);
+        }
+    }
+
+    #[test]
+    fn test_eq_filter_responses() {
+        let gain = 10.0f32.powf(12.0 / 20.0);
+        let half_gain = 10.0f32.powf(6.0 / 20.0);
+
+        let peaking = BiquadCoefficients::peaking(SAMPLE_RATE, 1_000.0, 1.0, 12.0);
+        assert_response(peaking, 1.0, 1.0);
+        assert_response(peaking, 1_000.0, gain);
+        assert_response(peaking, NYQUIST, 1.0);
+
+        let low_shelf = BiquadCoefficients::low_shelf(SAMPLE_RATE, 1_000.0, BUTTERWORTH_Q, 12.0);
+        assert_response(low_shelf, 1.0, gain);
+        assert_response(low_shelf, 1_000.0, half_gain);
+        assert_response(low_shelf, NYQUIST, 1.0);
+
+        let high_shelf = BiquadCoefficients::high_shelf(SAMPLE_RATE, 1_000.0, BUTTERWORTH_Q, 12.0);
+        assert_response(high_shelf, 1.0, 1.0);
+        assert_response(high_shelf, 1_000.0, half_gain);
+        assert_response(high_shelf, NYQUIST, gain);
+    }
+
+    /// The filtered sine wave's amplitude should match the computed magnitude response once the
+    /// filter has settled.
+    #[test]
+    fn test_process_matches_response() {
+        for (coefficients, frequency) in [
+            (
+                BiquadCoefficients::lowpass(SAMPLE_RATE, 1_000.0, 2.0),
+                1_000.0,
+            ),
+            (
+                BiquadCoefficients::highpass(SAMPLE_RATE, 1_000.0, 0.5),
+                250.0,
+            ),
+            (
+                BiquadCoefficients::peaking(SAMPLE_RATE, 2_000.0, 1.0, -6.0),
+                2_000.0,
+            ),
+        ] {
+            let mut filter = Biquad::<f32> {
+                coefficients,
+                ..Default::default()
+            };
+
+            let mut peak = 0.0f32;
+            for sample_idx in 0..SAMPLE_RATE as usize {
+                let phase = consts::TAU * frequency * (sample_idx as f32 / SAMPLE_RATE);
+                let output = filter.process(phase.sin());
+
+                // The first half second is used to let the filter settle
+                if sample_idx >= SAMPLE_RATE as usize / 2 {
+                    peak = peak.max(output.abs());
+                }
+            }
+
+            let expected = coefficients.magnitude_response(SAMPLE_RATE, frequency);
+            assert!(
+                (peak - expected).abs() < 1e-2,
+                "Expected a peak of {expected} at {frequency} Hz, got {peak}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_denormal_safe_settles_to_zero() {
+        let mut filter = Biquad::<f32> {
+            coefficients: BiquadCoefficients::lowpass(44_100.0, 1_000.0, 20.0),
+            ..Default::default()
+        };
+
+        for _ in 0..64 {
+            filter.process_denormal_safe(1.0);
+        }
+
+        // A resonant filter rings for a long time, but it should eventually become exactly silent
+        // instead of producing denormals
+        let mut output = 1.0;
+        for _ in 0..(44_100 * 5) {
+            output = filter.process_denormal_safe(0.0);
+        }
+        assert_eq!(output, 0.0);
+        assert_eq!(filter.s1, 0.0);
+        assert_eq!(filter.s2, 0.0);
+    }
+}
@@ -0,0 +1,274 @@
+//! A lookahead brickwall limiter, useful as a last line of defense on a plugin's output.
+
+use std::collections::VecDeque;
+
+/// A lookahead brickwall limiter that guarantees that the output never exceeds the configured
+/// ceiling. The input is delayed by the lookahead amount so the gain reduction can be faded in
+/// before a peak arrives instead of abruptly clipping it. The gain reduction is computed from the
+/// loudest channel and applied to all channels, so the stereo image stays intact. The output is
+/// hard clipped to the ceiling as a final step, so rounding errors can never cause overs.
+///
+/// This is meant to protect against runaway feedback and other unexpectedly loud signals, and it's
+/// not meant to be a mastering limiter. Remember to report [`latency()`][Self::latency()] to the
+/// host.
+///
+/// ```
+/// # use nih_plug_dsp::util::BrickwallLimiter;
+/// let mut limiter = BrickwallLimiter::new(2, 64);
+/// limiter.set_release(44_100.0, 100.0);
+///
+/// let mut left = vec![4.0; 256];
+/// let mut right = vec![-4.0; 256];
+/// limiter.process(&mut [&mut left[..], &mut right[..]]);
+///
+/// assert!(left.iter().chain(right.iter()).all(|sample| sample.abs() <= 1.0));
+/// ```
+#[derive(Debug)]
+pub struct BrickwallLimiter {
+    /// The lookahead in samples. The limiter's latency is equal to this.
+    lookahead: usize,
+    /// The maximum absolute sample value after limiting.
+    ceiling: f32,
+    /// A gain applied to the input before limiting.
+    makeup_gain: f32,
+    /// The coefficient for the one-pole release filter. 0.0 means that the gain recovers
+    /// immediately.
+    release_coefficient: f32,
+
+    /// The delay lines for each channel. These contain `lookahead + 1` samples, and
+    /// `delay_line_pos` is the position the next sample is written to.
+    delay_lines: Vec<Vec<f32>>,
+    /// The sliding window minimum of the target gains over the last `lookahead + 1` samples. This
+    /// contains pairs of sample indices and gains, with the gains increasing from front to back.
+    target_gain_window: VecDeque<(usize, f32)>,
+    /// The released gains for the last `lookahead + 1` samples. These are averaged to obtain the
+    /// final gain, which fades the gain reduction in over the lookahead period.
+    gain_history: Vec<f32>,
+    /// The sum of `gain_history`. This is kept as a double to avoid accumulating errors.
+    gain_history_sum: f64,
+    /// The position in the delay lines and the gain history the next sample is written to.
+    delay_line_pos: usize,
+    /// The number of samples processed since the last reset, used to expire values from
+    /// `target_gain_window`.
+    sample_idx: usize,
+
+    /// The gain after the release filter.
+    release_envelope: f32,
+    /// The gain that was applied to the last sample.
+    current_gain: f32,
+}
+
+impl BrickwallLimiter {
+    /// Create a limiter for `num_channels` channels with enough capacity for `max_lookahead`
+    /// samples of lookahead. The lookahead is initially set to `max_lookahead`. The ceiling is set
+    /// to 0 dBFS, and the gain recovers immediately until
+    /// [`set_release()`][Self::set_release()] is called.
+    pub fn new(num_channels: usize, max_lookahead: usize) -> Self {
+        let window_len = max_lookahead + 1;
+
+        Self {
+            lookahead: max_lookahead,
+            ceiling: 1.0,
+            makeup_gain: 1.0,
+            release_coefficient: 0.0,
+
+            delay_lines: vec![vec![0.0; window_len]; num_channels],
+            target_gain_window: VecDeque::with_capacity(window_len),
+            gain_history: vec![1.0; window_len],
+            gain_history_sum: window_len as f64,
+            delay_line_pos: 0,
+            sample_idx: 0,
+
+            release_envelope: 1.0,
+            current_gain: 1.0,
+        }
+    }
+
+    /// Change the lookahead amount. This also resets the limiter. This does not allocate as long as
+    /// `lookahead` does not exceed the `max_lookahead` passed to [`new()`][Self::new()].
+    pub fn set_lookahead(&mut self, lookahead: usize) {
+        let window_len = lookahead + 1;
+        for delay_line in &mut self.delay_lines {
+            delay_line.resize(window_len, 0.0);
+        }
+        self.gain_history.resize(window_len, 1.0);
+        self.lookahead = lookahead;
+
+        self.reset();
+    }
+
+    /// The limiter's latency in samples. This is equal to the lookahead amount.
+    pub fn latency(&self) -> u32 {
+        self.lookahead as u32
+    }
+
+    /// Set the maximum absolute sample value after limiting, as a linear gain value.
+    pub fn set_ceiling(&mut self, ceiling: f32) {
+        nih_debug_assert!(ceiling > 0.0);
+
+        self.ceiling = ceiling;
+    }
+
+    /// Set a gain that's applied to the input before limiting, as a linear gain value. Since the
+    /// output is limited to the ceiling, this can be used to make up for the lost loudness without
+    /// risking overs.
+    pub fn set_makeup_gain(&mut self, gain: f32) {
+        nih_debug_assert!(gain >= 0.0);
+
+        self.makeup_gain = gain;
+    }
+
+    /// Set the release time in milliseconds. This is the time constant for recovering from gain
+    /// reduction, so the gain recovers about 63% of the way in this amount of time.
+    pub fn set_release(&mut self, sample_rate: f32, release_ms: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(release_ms >= 0.0);
+
+        let release_samples = release_ms / 1000.0 * sample_rate;
+        self.release_coefficient = if release_samples > 0.0 {
+            (-1.0 / release_samples).exp()
+        } else {
+            0.0
+        };
+    }
+
+    /// The gain that was applied to the last processed sample, as a linear gain value. This can be
+    /// used to display the amount of gain reduction.
+    pub fn current_gain(&self) -> f32 {
+        self.current_gain
+    }
+
+    /// Clear the delay lines and the gain reduction.
+    pub fn reset(&mut self) {
+        for delay_line in &mut self.delay_lines {
+            delay_line.fill(0.0);
+        }
+        self.target_gain_window.clear();
+        self.gain_history.fill(1.0);
+        self.gain_history_sum = self.gain_history.len() as f64;
+        self.delay_line_pos = 0;
+        self.sample_idx = 0;
+
+        self.release_envelope = 1.0;
+        self.current_gain = 1.0;
+    }
+
+    /// Limit a block of audio in place. `channels` should contain one equally sized slice per
+    /// channel, like the slices returned from NIH-plug's `Buffer::as_slice()`.
+    pub fn process(&mut self, channels: &mut [&mut [f32]]) {
+        nih_debug_assert_eq!(channels.len(), self.delay_lines.len());
+
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let window_len = self.lookahead + 1;
+        for sample_idx in 0..num_samples {
+            // The gain reduction is based on the loudest channel
+            let mut peak = 0.0f32;
+            for (channel, delay_line) in channels.iter_mut().zip(self.delay_lines.iter_mut()) {
+                let sample = channel[sample_idx] * self.makeup_gain;
+                delay_line[self.delay_line_pos] = sample;
+                peak = peak.max(sample.abs());
+            }
+
+            let target_gain = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            // The held gain is the lowest target gain within the lookahead window, so the gain
+            // reduction for a peak stays active until that peak has left the delay line. Expired
+            // values are removed first so this never exceeds the window's capacity.
+            while let Some((idx, _)) = self.target_gain_window.front() {
+                if self.sample_idx - idx < window_len {
+                    break;
+                }
+
+                self.target_gain_window.pop_front();
+            }
+            while let Some((_, gain)) = self.target_gain_window.back() {
+                if *gain < target_gain {
+                    break;
+                }
+
+                self.target_gain_window.pop_back();
+            }
+            self.target_gain_window
+                .push_back((self.sample_idx, target_gain));
+            let held_gain = self.target_gain_window[0].1;
+
+            // Gain reduction is applied immediately, and the gain recovers with the release time
+            self.release_envelope = if held_gain < self.release_envelope {
+                held_gain
+            } else {
+                held_gain + ((self.release_envelope - held_gain) * self.release_coefficient)
+            };
+
+            // Averaging the released gains over the lookahead window fades the gain reduction in
+            // before the peak leaves the delay line. Every gain in this window is at most the
+            // target gain for the sample that's about to leave the delay line.
+            self.gain_history_sum +=
+                self.release_envelope as f64 - self.gain_history[self.delay_line_pos] as f64;
+            self.gain_history[self.delay_line_pos] = self.release_envelope;
+            self.current_gain = (self.gain_history_sum / window_len as f64) as f32;
+
+            // The oldest sample in the delay line is the one right after the current position
+            self.delay_line_pos = (self.delay_line_pos + 1) % window_len;
+            for (channel, delay_line) in channels.iter_mut().zip(self.delay_lines.iter()) {
+                let delayed_sample = delay_line[self.delay_line_pos];
+                channel[sample_idx] =
+                    (delayed_sample * self.current_gain).clamp(-self.ceiling, self.ceiling);
+            }
+
+            self.sample_idx += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_exceeds_ceiling() {
+        let mut limiter = BrickwallLimiter::new(2, 32);
+        limiter.set_ceiling(0.5);
+        limiter.set_release(44_100.0, 50.0);
+
+        let mut left: Vec<f32> = (0..4096)
+            .map(|idx| (idx as f32 * 0.05).sin() * (idx % 300) as f32 * 0.02)
+            .collect();
+        let mut right: Vec<f32> = left.iter().map(|sample| -sample * 1.5).collect();
+        limiter.process(&mut [&mut left[..], &mut right[..]]);
+
+        for sample in left.iter().chain(right.iter()) {
+            assert!(sample.abs() <= 0.5, "{sample}");
+        }
+    }
+
+    #[test]
+    fn test_quiet_signal_is_only_delayed() {
+        let mut limiter = BrickwallLimiter::new(1, 16);
+        limiter.set_lookahead(8);
+        assert_eq!(limiter.latency(), 8);
+
+        let mut impulse = [0.0; 32];
+        impulse[0] = 0.75;
+        limiter.process(&mut [&mut impulse[..]]);
+
+        assert_eq!(impulse[8], 0.75);
+        assert_eq!(impulse.iter().filter(|sample| **sample != 0.0).count(), 1);
+        assert_eq!(limiter.current_gain(), 1.0);
+    }
+
+    #[test]
+    fn test_gain_reduction_is_faded_in() {
+        let mut limiter = BrickwallLimiter::new(1, 4);
+
+        let mut step = vec![0.5, 0.5, 0.5, 0.5, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0];
+        limiter.process(&mut [&mut step[..]]);
+
+        // The delayed 0.5 samples should already be attenuated before the step arrives
+        assert!(step[7] < 0.5);
+        approx::assert_relative_eq!(step[8], 1.0);
+    }
+}
@@ -0,0 +1,305 @@
+//! Deterministic white, pink, and blue noise generators, and helpers for applying the same spectral
+//! slopes to spectra.
+
+/// The colors of noise a [`NoiseGenerator`] can generate. NIH-plug implements its `Enum` trait for
+/// this type, so it can be used directly with an `EnumParam`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseColor {
+    /// Noise with equal power at every frequency.
+    #[default]
+    White,
+    /// Noise with equal power in every octave, so the power spectral density drops by 3 dB per
+    /// octave.
+    Pink,
+    /// Noise whose power spectral density rises by 3 dB per octave.
+    Blue,
+}
+
+/// The seed used by [`NoiseGenerator::new()`] when the seed passed to it is zero, since the
+/// xorshift generator would otherwise get stuck at zero.
+const FALLBACK_SEED: u32 = 0x9e3779b9;
+
+/// Scales Paul Kellet's pink noise filter's output so it roughly stays within `[-1, 1]`.
+const PINK_GAIN: f32 = 0.11;
+/// Scales the differentiated pink noise used for blue noise so it roughly stays within `[-1, 1]`.
+const BLUE_GAIN: f32 = 0.35;
+
+/// A cheap deterministic noise generator. The white noise is uniformly distributed in `[-1, 1]`
+/// and comes from a xorshift generator. Pink noise is created by filtering the white noise with
+/// Paul Kellet's refined pink noise filter, which follows the ideal -3 dB per octave slope within
+/// ±0.05 dB above roughly 10 Hz at common sample rates. Blue noise is the first difference of that
+/// pink noise, which adds 6 dB per octave. The colored noise is scaled so it roughly stays within
+/// `[-1, 1]`, but it is not clipped so individual samples may still slightly exceed that range.
+///
+/// The same seed always produces the same noise, so calling [`reset()`][Self::reset()] from a
+/// plugin's `reset()` function makes offline renders reproducible. There are no allocations, so
+/// this can be stored directly in a synth's voices.
+///
+/// ```
+/// # use nih_plug_dsp::util::noise::{NoiseColor, NoiseGenerator};
+/// let mut noise = NoiseGenerator::new(NoiseColor::Pink, 1337);
+///
+/// let mut output = [0.0; 64];
+/// noise.fill(&mut output);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NoiseGenerator {
+    color: NoiseColor,
+    /// The seed the generator was created with, used to restore the generator in
+    /// [`reset()`][Self::reset()].
+    seed: u32,
+    /// The xorshift generator's state. This is never zero.
+    rng_state: u32,
+
+    /// The state for Paul Kellet's pink noise filter.
+    pink_state: [f32; 7],
+    /// The previous pink noise sample, used to differentiate pink noise into blue noise.
+    previous_pink: f32,
+}
+
+impl NoiseGenerator {
+    /// Create a noise generator for noise of the specified color, seeded with `seed`.
+    pub fn new(color: NoiseColor, seed: u32) -> Self {
+        let seed = if seed == 0 { FALLBACK_SEED } else { seed };
+
+        Self {
+            color,
+            seed,
+            rng_state: seed,
+
+            pink_state: [0.0; 7],
+            previous_pink: 0.0,
+        }
+    }
+
+    /// The color of the generated noise.
+    pub fn color(&self) -> NoiseColor {
+        self.color
+    }
+
+    /// Change the color of the generated noise. This does not reset the generator, so this can be
+    /// changed while generating noise.
+    pub fn set_color(&mut self, color: NoiseColor) {
+        self.color = color;
+    }
+
+    /// Reset the generator to the state it was in when it was created, so it produces the exact
+    /// same noise again.
+    pub fn reset(&mut self) {
+        self.rng_state = self.seed;
+        self.pink_state = [0.0; 7];
+        self.previous_pink = 0.0;
+    }
+
+    /// Generate the next noise sample.
+    #[inline]
+    pub fn next_sample(&mut self) -> f32 {
+        match self.color {
+            NoiseColor::White => self.next_white(),
+            NoiseColor::Pink => self.next_pink() * PINK_GAIN,
+            NoiseColor::Blue => {
+                let pink = self.next_pink();
+                let blue = pink - self.previous_pink;
+                self.previous_pink = pink;
+
+                blue * BLUE_GAIN
+            }
+        }
+    }
+
+    /// Fill `output` with noise.
+    pub fn fill(&mut self, output: &mut [f32]) {
+        for sample in output {
+            *sample = self.next_sample();
+        }
+    }
+
+    /// Generate uniformly distributed white noise in `[-1, 1]`.
+    #[inline]
+    fn next_white(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+
+        (self.rng_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    /// Generate unscaled pink noise using Paul Kellet's refined pink noise filter.
+    #[inline]
+    fn next_pink(&mut self) -> f32 {
+        let white = self.next_white();
+        let b = &mut self.pink_state;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        pink
+    }
+}
+
+impl NoiseColor {
+    /// The slope of the noise's power spectral density in decibels per octave.
+    pub fn slope_db_per_octave(self) -> f32 {
+        match self {
+            NoiseColor::White => 0.0,
+            NoiseColor::Pink => -3.0103,
+            NoiseColor::Blue => 3.0103,
+        }
+    }
+
+    /// The gain to apply to a spectrum's bin at `frequency` so a flat spectrum takes on this
+    /// noise color's slope. See [`tilt_gain()`].
+    pub fn tilt_gain(self, frequency: f32, reference_frequency: f32) -> f32 {
+        tilt_gain(frequency, reference_frequency, self.slope_db_per_octave())
+    }
+}
+
+/// The linear gain for a spectral tilt of `db_per_octave` decibels of **power** per octave at
+/// `frequency`. The gain is 1.0 at `reference_frequency`. Frequencies that aren't positive, like
+/// the DC bin, are treated as the reference frequency to avoid infinite gains.
+#[inline]
+pub fn tilt_gain(frequency: f32, reference_frequency: f32, db_per_octave: f32) -> f32 {
+    if frequency <= 0.0 || reference_frequency <= 0.0 {
+        return 1.0;
+    }
+
+    // Power decibels per octave are converted to an amplitude gain, hence the 0.05 instead of 0.1
+    let octaves = (frequency / reference_frequency).log2();
+    10.0f32.powf(octaves * db_per_octave * 0.05)
+}
+
+/// Multiply each of `magnitudes` by the [`tilt_gain()`] for its frequency. `magnitudes` should
+/// contain the magnitudes or the complex bins of a real-to-complex FFT with a window size of
+/// `fft_size` samples. Bin `i` corresponds to a frequency of `i * sample_rate / fft_size` Hz.
+pub fn apply_tilt<T>(
+    magnitudes: &mut [T],
+    fft_size: usize,
+    sample_rate: f32,
+    reference_frequency: f32,
+    db_per_octave: f32,
+) where
+    T: std::ops::MulAssign<f32>,
+{
+    nih_debug_assert!(magnitudes.len() <= fft_size / 2 + 1);
+
+    let bin_frequency = sample_rate / fft_size as f32;
+    for (bin_idx, magnitude) in magnitudes.iter_mut().enumerate() {
+        *magnitude *= tilt_gain(
+            bin_idx as f32 * bin_frequency,
+            reference_frequency,
+            db_per_octave,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SEGMENT_SIZE: usize = 4096;
+    const NUM_SEGMENTS: usize = 64;
+    /// The bins used to estimate the spectral slope, spaced one octave apart. This covers roughly
+    /// 94 Hz to 6 kHz at 48 kHz.
+    const SLOPE_BINS: [usize; 7] = [8, 16, 32, 64, 128, 256, 512];
+
+    /// Estimate the noise's spectral slope in decibels per octave by averaging Hann windowed
+    /// periodograms for a couple bins spaced an octave apart, and then fitting a line through those
+    /// power levels.
+    fn measure_slope(color: NoiseColor) -> f32 {
+        let mut noise = NoiseGenerator::new(color, 1337);
+        let window: Vec<f32> = (0..SEGMENT_SIZE)
+            .map(|i| 0.5 - 0.5 * (TAU * i as f32 / SEGMENT_SIZE as f32).cos())
+            .collect();
+
+        let mut powers = [0.0f64; SLOPE_BINS.len()];
+        let mut segment = vec![0.0; SEGMENT_SIZE];
+        for _ in 0..NUM_SEGMENTS {
+            noise.fill(&mut segment);
+            for (power, center_bin) in powers.iter_mut().zip(SLOPE_BINS) {
+                // Averaging the neighbouring bins reduces the variance a bit further
+                for bin in center_bin - 1..=center_bin + 1 {
+                    let (mut re, mut im) = (0.0f64, 0.0f64);
+                    for (i, (sample, window)) in segment.iter().zip(&window).enumerate() {
+                        let phase = TAU as f64 * (bin * i) as f64 / SEGMENT_SIZE as f64;
+                        re += (sample * window) as f64 * phase.cos();
+                        im -= (sample * window) as f64 * phase.sin();
+                    }
+                    *power += re * re + im * im;
+                }
+            }
+        }
+
+        // A least squares fit of the power in decibels against the octave number
+        let points: Vec<(f64, f64)> = powers
+            .iter()
+            .enumerate()
+            .map(|(octave, power)| (octave as f64, 10.0 * power.log10()))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+        (covariance / variance) as f32
+    }
+
+    #[test]
+    fn test_pink_slope() {
+        let slope = measure_slope(NoiseColor::Pink);
+        assert!(
+            (slope - -3.01).abs() < 0.3,
+            "Pink noise slope: {slope} dB/octave"
+        );
+    }
+
+    #[test]
+    fn test_white_and_blue_slopes() {
+        let slope = measure_slope(NoiseColor::White);
+        assert!(slope.abs() < 0.3, "White noise slope: {slope} dB/octave");
+
+        let slope = measure_slope(NoiseColor::Blue);
+        assert!(
+            (slope - 3.01).abs() < 0.5,
+            "Blue noise slope: {slope} dB/octave"
+        );
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut noise = NoiseGenerator::new(NoiseColor::Pink, 42);
+        let mut first = [0.0; 256];
+        noise.fill(&mut first);
+
+        noise.reset();
+        let mut second = [0.0; 256];
+        noise.fill(&mut second);
+        assert_eq!(first, second);
+
+        let mut other_seed = [0.0; 256];
+        NoiseGenerator::new(NoiseColor::Pink, 43).fill(&mut other_seed);
+        assert_ne!(first, other_seed);
+    }
+
+    #[test]
+    fn test_tilt_gain() {
+        assert_eq!(tilt_gain(1000.0, 1000.0, -3.0), 1.0);
+        assert_eq!(tilt_gain(0.0, 1000.0, -3.0), 1.0);
+
+        // One octave up at pink noise's -3 dB of power per octave should halve the power, and -6 dB
+        // per octave should quarter it
+        let gain = NoiseColor::Pink.tilt_gain(2000.0, 1000.0);
+        assert!((gain * gain - 0.5).abs() < 1e-4);
+        let gain = tilt_gain(2000.0, 1000.0, -6.0206);
+        assert!((gain * gain - 0.25).abs() < 1e-4);
+    }
+}
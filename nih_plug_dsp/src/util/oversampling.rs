@@ -1,30 +1,16 @@
-// Soft Vacuum: Airwindows Hard Vacuum port with oversampling
-// Copyright (C) 2023-2024 Robbert van der Helm
-//
-// This program is free software: you can redistribute it and/or modify
-// it under the terms of the GNU General Public License as published by
-// the Free Software Foundation, either version 3 of the License, or
-// (at your option) any later version.
-//
-// This program is distributed in the hope that it will be useful,
-// but WITHOUT ANY WARRANTY; without even the implied warranty of
-// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
-// GNU General Public License for more details.
-//
-// You should have received a copy of the GNU General Public License
-// along with this program.  If not, see <https://www.gnu.org/licenses/>.
-
-use nih_plug::debug::*;
-
-/// The kernel used in `Lanczos3Oversampler`. Specified here as a constant since it is a constant.
-/// Precomputed since compile-time floating point arithmetic is still unstable.
+//! A multi-stage linear-phase oversampler, useful for reducing aliasing in nonlinear processing.
+
+/// The kernel used for the 2x oversampling stages in `Lanczos3Oversampler`. Specified here as a
+/// constant since it is a constant. Precomputed since compile-time floating point arithmetic is
+/// still unstable.
 ///
 /// Computed using:
 ///
 /// ```python
 /// LANCZOS_A = 3
+/// RATIO = 2
 ///
-/// x = np.arange(-LANCZOS_A * 2 + 1, LANCZOS_A * 2) / 2
+/// x = np.arange(-LANCZOS_A * RATIO + 1, LANCZOS_A * RATIO) / RATIO
 /// np.sinc(x) * np.sinc(x / LANCZOS_A)
 /// ```
 ///
@@ -60,8 +46,49 @@ const LANCZOS3_DOWNSAMPLING_KERNEL: [f32; 11] = [
     0.01215854,
 ];
 
-/// The latency introduced by the two filter kernels defined above, in samples.
-const LANZCOS3_KERNEL_LATENCY: usize = LANCZOS3_UPSAMPLING_KERNEL.len() / 2;
+/// The same as `LANCZOS3_UPSAMPLING_KERNEL`, but for the 3x oversampling stages. Computed using the
+/// same formula with `RATIO = 3`.
+const LANCZOS3_3X_UPSAMPLING_KERNEL: [f32; 17] = [
+    0.012660952,
+    0.031078931,
+    -0.0,
+    -0.09332674,
+    -0.14582303,
+    0.0,
+    0.3807169,
+    0.81030093,
+    1.0,
+    0.81030093,
+    0.3807169,
+    0.0,
+    -0.14582303,
+    -0.09332674,
+    -0.0,
+    0.031078931,
+    0.012660952,
+];
+
+/// `LANCZOS3_3X_UPSAMPLING_KERNEL` divided by three, used for downsampling so that upsampling
+/// followed by downsampling results in unity gain.
+const LANCZOS3_3X_DOWNSAMPLING_KERNEL: [f32; 17] = [
+    0.004220317,
+    0.010359644,
+    -0.0,
+    -0.031108914,
+    -0.048607678,
+    0.0,
+    0.12690563,
+    0.2701003,
+    0.33333333,
+    0.2701003,
+    0.12690563,
+    0.0,
+    -0.048607678,
+    -0.031108914,
+    -0.0,
+    0.010359644,
+    0.004220317,
+];
 
 /// A barebones multi-stage linear-phase oversampler that uses the lanzcos kernel with a=3 for a
 /// good approximation of a windowed sinc with only a 11 point kernel function (the kernel is
@@ -69,6 +96,10 @@ const LANZCOS3_KERNEL_LATENCY: usize = LANCZOS3_UPSAMPLING_KERNEL.len() / 2;
 /// done much more efficiently but I was in a hurry and this is simple to implement without having
 /// to look anything up.
 ///
+/// Every stage oversamples the signal by a factor of two, or by a factor of three when the
+/// oversampler is created using [`with_ratio()`][Self::with_ratio()]. The 3x stages use a 17 point
+/// version of the same kernel.
+///
 /// This only handles a single audio channel. Use multiple instances for multichannel audio.
 #[derive(Debug)]
 pub struct Lanczos3Oversampler {
@@ -80,6 +111,9 @@ pub struct Lanczos3Oversampler {
 
     /// The oversampler's latency. Precomputed for each possible number of active stages.
     latencies: Vec<u32>,
+    /// The number of stages that were used during the last processing call. Stages that become
+    /// active again after having been inactive are reset first so they don't output stale samples.
+    active_stages: usize,
 }
 
 /// A single oversampling stage. Contains the ring buffers and current position in that ringbuffer
@@ -87,13 +121,22 @@ pub struct Lanczos3Oversampler {
 /// stage.
 #[derive(Debug, Clone)]
 struct Lanzcos3Stage {
-    /// The amount of oversampling that happens at this stage. Will be 2 for the first stage, 4 for
-    /// the second stage, 8 for the third stage, and so forth. Used to calculate the stage's effect
-    /// on the oversampling's latency.
+    /// The amount of oversampling this stage applies to the previous stage's output. This is
+    /// either 2 or 3.
+    ratio: usize,
+    /// The amount of oversampling that happens at this stage, relative to the base sample rate.
+    /// With only 2x stages this will be 2 for the first stage, 4 for the second stage, 8 for the
+    /// third stage, and so forth. Used to calculate the stage's effect on the oversampling's
+    /// latency.
     oversampling_amount: usize,
 
-    /// These ring buffers contain `LANCZOS3_UPSAMPLING_KERNEL.len()` samples. The upsampling ring
-    /// buffer contains room to delay the signal further to make sure the _total_
+    /// The filter kernel used for upsampling. Depends on `ratio`.
+    upsampling_kernel: &'static [f32],
+    /// The filter kernel used for downsampling. This is the upsampling kernel divided by `ratio`.
+    downsampling_kernel: &'static [f32],
+
+    /// These ring buffers contain `upsampling_kernel.len()` samples. The upsampling ring buffer
+    /// contains room to delay the signal further to make sure the _total_
     /// (upsampling+downsampling) latency imposed on the signal is divisible by the stage's
     /// oversampling amount. That is needed to avoid fractional latency.
     upsampling_rb: Vec<f32>,
@@ -105,7 +148,7 @@ struct Lanzcos3Stage {
 
     /// No additional latency needs to be imposed for the downsampling, so to keep things simple
     /// this doesn't add any additional delay.
-    downsampling_rb: [f32; LANCZOS3_DOWNSAMPLING_KERNEL.len()],
+    downsampling_rb: Vec<f32>,
     downsampling_write_pos: usize,
 
     scratch_buffer: Vec<f32>,
@@ -117,9 +160,55 @@ impl Lanczos3Oversampler {
     /// oversampling = 1, 4x oversampling = 3, etc. The actual amount of oversampling stages used is
     /// passed to the `process()` function, and must be set to `max_factor` or lower.
     pub fn new(maximum_block_size: usize, max_factor: usize) -> Self {
-        let mut stages = Vec::with_capacity(max_factor);
-        for stage in 0..max_factor {
-            stages.push(Lanzcos3Stage::new(maximum_block_size, stage))
+        Self::from_stage_ratios(maximum_block_size, vec![2; max_factor])
+    }
+
+    /// Create a new oversampler that oversamples by `ratio`, which does not need to be a power of
+    /// two. The ratio is split up into 3x stages followed by 2x stages, so a ratio of 6 results in
+    /// a 3x stage followed by a 2x stage. Like with [`new()`][Self::new()], the factor passed to
+    /// [`process()`][Self::process()] is the number of stages to use. Passing
+    /// [`num_stages()`][Self::num_stages()] as the factor uses the full ratio, and
+    /// [`oversampling_times()`][Self::oversampling_times()] can be used to get the ratio for a
+    /// smaller number of stages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is zero, or if it cannot be written as `2^n * 3^m`. Ratios like 3, 4, 6,
+    /// 9, and 12 are supported, while 5 and 10 are not.
+    pub fn with_ratio(maximum_block_size: usize, ratio: usize) -> Self {
+        assert_ne!(ratio, 0, "The oversampling ratio cannot be zero");
+
+        let mut stage_ratios = Vec::new();
+        let mut remaining_ratio = ratio;
+        for stage_ratio in [3, 2] {
+            while remaining_ratio % stage_ratio == 0 {
+                stage_ratios.push(stage_ratio);
+                remaining_ratio /= stage_ratio;
+            }
+        }
+        assert_eq!(
+            remaining_ratio, 1,
+            "Unsupported oversampling ratio {ratio}, only ratios that consist of 2x and 3x stages \
+             are supported"
+        );
+
+        Self::from_stage_ratios(maximum_block_size, stage_ratios)
+    }
+
+    /// Create an oversampler with one stage for every ratio in `stage_ratios`. Every stage's ratio
+    /// must be either 2 or 3.
+    fn from_stage_ratios(maximum_block_size: usize, stage_ratios: Vec<usize>) -> Self {
+        let mut stages: Vec<Lanzcos3Stage> = Vec::with_capacity(stage_ratios.len());
+        for ratio in stage_ratios {
+            let previous_oversampling_amount = stages
+                .last()
+                .map(|stage| stage.oversampling_amount)
+                .unwrap_or(1);
+            stages.push(Lanzcos3Stage::new(
+                maximum_block_size,
+                ratio,
+                previous_oversampling_amount * ratio,
+            ))
         }
 
         // Since the number of active oversampling stages is passed to the process function, we also
@@ -133,7 +222,11 @@ impl Lanczos3Oversampler {
             })
             .collect();
 
-        Self { stages, latencies }
+        Self {
+            stages,
+            latencies,
+            active_stages: 0,
+        }
     }
 
     /// Reset the oversampling filters to their initial states.
@@ -143,8 +236,29 @@ impl Lanczos3Oversampler {
         }
     }
 
+    /// The number of oversampling stages. This is the maximum factor that can be passed to
+    /// [`process()`][Self::process()].
+    pub fn num_stages(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Get the amount of oversampling that happens for the given oversampling factor. For
+    /// oversamplers created using [`new()`][Self::new()] this is `2^factor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor > max_factor`.
+    pub fn oversampling_times(&self, factor: usize) -> usize {
+        if factor == 0 {
+            1
+        } else {
+            self.stages[factor - 1].oversampling_amount
+        }
+    }
+
     /// Get the latency in samples for the given oversampling factor. Fractional latency is
-    /// automatically avoided.
+    /// automatically avoided. This is always zero for factor 0 since 1x oversampling bypasses the
+    /// filters entirely.
     ///
     /// # Panics
     ///
@@ -159,52 +273,97 @@ impl Lanczos3Oversampler {
 
     /// Upsample `block` using the specified oversampling factor, process the upsampled version
     /// using `f`, and then downsample it again and write the results back to `block` with a
-    /// [`latency()`][Self::latency()] sample delay.
+    /// [`latency()`][Self::latency()] sample delay. With factor 0 `f` is called directly on `block`
+    /// without any filtering, so that case is both latency-free and bit-transparent. This is a
+    /// shorthand for calling [`upsample_only()`][Self::upsample_only()] followed by
+    /// [`downsample_only()`][Self::downsample_only()].
     ///
     /// # Panics
     ///
     /// Panics if `factor > max_factor`, or if `block`'s length is longer than the maximum block
     /// size.
     pub fn process(&mut self, block: &mut [f32], factor: usize, f: impl FnOnce(&mut [f32])) {
+        f(self.upsample_only(block, factor));
+        self.downsample_only(block, factor)
+    }
+
+    /// The first half of [`process()`][Self::process()]. Returns the upsampled version of the
+    /// signal that would normally be passed to `process`'s callback. The upsampled signal can be
+    /// modified in place, and it can then be downsampled again using
+    /// [`downsample_only()`][Self::downsample_only()]. This makes it possible to upsample the
+    /// signals from multiple oversamplers, process them together, and then downsample all of them
+    /// afterwards. Also useful for upsampling control signals, in which case the signal doesn't
+    /// need to be downsampled again. With factor 0 this returns `block` as is.
+    ///
+    /// The upsampled signal is delayed by the upsampling filters. Only the total latency of an
+    /// upsampling and downsampling round trip, [`latency()`][Self::latency()], is guaranteed to be
+    /// an integer amount of samples at the base sample rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor > max_factor`, or if `block`'s length is longer than the maximum block
+    /// size.
+    pub fn upsample_only<'a>(&'a mut self, block: &'a mut [f32], factor: usize) -> &'a mut [f32] {
         assert!(factor <= self.stages.len());
 
+        self.activate_stages(factor);
+
         // This is the 1x oversampling case, this should also modify the block to be consistent
         if factor == 0 {
-            f(block);
-            return;
+            return block;
         }
 
         assert!(
-            block.len() <= self.stages[0].scratch_buffer.len() / 2,
+            block.len() <= self.stages[0].scratch_buffer.len() / self.stages[0].ratio,
             "The block's size exceeds the maximum block size"
         );
 
-        let upsampled = self.upsample_from(block, factor);
-        f(upsampled);
-        self.downsample_to(block, factor)
+        self.upsample_from(block, factor)
     }
 
-    /// An upsample-only version of `process` that returns the upsampled version of the signal that
-    /// would normally be passed to `process`'s callback. Useful for upsampling control signals.
+    /// The second half of [`process()`][Self::process()]. Downsamples the upsampled signal
+    /// returned by the last [`upsample_only()`][Self::upsample_only()] call, including any
+    /// modifications made to it, and writes the results to `block`. `block` must have the same
+    /// length as the block passed to `upsample_only()`, and `factor` must also be the same. The
+    /// output is delayed by [`latency()`][Self::latency()] samples compared to the block passed to
+    /// `upsample_only()`. With factor 0 this does nothing since `upsample_only()` then returns the
+    /// original block.
     ///
     /// # Panics
     ///
     /// Panics if `factor > max_factor`, or if `block`'s length is longer than the maximum block
     /// size.
-    pub fn upsample_only<'a>(&'a mut self, block: &'a mut [f32], factor: usize) -> &'a mut [f32] {
+    pub fn downsample_only(&mut self, block: &mut [f32], factor: usize) {
         assert!(factor <= self.stages.len());
+        nih_debug_assert_eq!(
+            factor,
+            self.active_stages,
+            "The factor does not match the one used for upsampling"
+        );
 
-        // This is the 1x oversampling case, this should also modify the block to be consistent
         if factor == 0 {
-            return block;
+            return;
         }
 
         assert!(
-            block.len() <= self.stages[0].scratch_buffer.len() / 2,
+            block.len() <= self.stages[0].scratch_buffer.len() / self.stages[0].ratio,
             "The block's size exceeds the maximum block size"
         );
 
-        self.upsample_from(block, factor)
+        self.downsample_to(block, factor)
+    }
+
+    /// Reset the stages that are about to be used but that were not used during the previous call.
+    /// Otherwise switching to a higher oversampling factor would briefly output whatever was left
+    /// in those stages' ring buffers when they were last used.
+    fn activate_stages(&mut self, factor: usize) {
+        if factor > self.active_stages {
+            for stage in &mut self.stages[self.active_stages..factor] {
+                stage.reset();
+            }
+        }
+
+        self.active_stages = factor;
     }
 
     /// Upsample `block` through `factor` oversampling stages. Returns a reference to the
@@ -225,7 +384,7 @@ impl Lanczos3Oversampler {
         // the stage preceeding it
         self.stages[0].upsample_from(block);
 
-        let mut previous_upsampled_block_len = block.len() * 2;
+        let mut previous_upsampled_block_len = block.len() * self.stages[0].ratio;
         for to_stage_idx in 1..factor {
             // This requires splitting the vector so we can borrow the from-stage immutably and the
             // to-stage mutably at the same time
@@ -234,7 +393,7 @@ impl Lanczos3Oversampler {
             };
 
             to.upsample_from(&from.scratch_buffer[..previous_upsampled_block_len]);
-            previous_upsampled_block_len *= 2;
+            previous_upsampled_block_len *= to.ratio;
         }
 
         &mut self.stages[factor - 1].scratch_buffer[..previous_upsampled_block_len]
@@ -256,7 +415,6 @@ impl Lanczos3Oversampler {
         // This is the reverse of `upsample_from`. Starting from the last stage, the oversampling
         // stages are downsampled to the previous stage and then the first stage is downsampled to
         // `block`.
-        let mut next_downsampled_block_len = block.len() * 2usize.pow(factor as u32 - 1);
         for to_stage_idx in (1..factor).rev() {
             // This requires splitting the vector so we can borrow the from-stage immutably and the
             // to-stage mutably at the same time
@@ -264,32 +422,38 @@ impl Lanczos3Oversampler {
                 unreachable!()
             };
 
-            from.downsample_to(&mut to.scratch_buffer[..next_downsampled_block_len]);
-            next_downsampled_block_len /= 2;
+            let downsampled_block_len = block.len() * to.oversampling_amount;
+            from.downsample_to(&mut to.scratch_buffer[..downsampled_block_len]);
         }
 
         // And then the first stage downsamples to `block`
-        assert_eq!(next_downsampled_block_len, block.len());
         self.stages[0].downsample_to(block);
     }
 }
 
 impl Lanzcos3Stage {
-    /// Create a `stage_number`th oversampling stage, where `stage_number` is this stage's
-    /// zero-based index in a list of stages. Stage 0 handles the 2x oversampling, stage 1 handles
-    /// the 4x oversampling, stage 2 handles the 8x oversampling, etc.. This is used to make sure
-    /// the stage's effect on the total latency is always an integer amount.
+    /// Create an oversampling stage that oversamples its input by `ratio`, which must be either 2
+    /// or 3. `oversampling_amount` is the total amount of oversampling at this stage relative to
+    /// the base sample rate, so for a 2x stage following another 2x stage this is 4. This is used
+    /// to make sure the stage's effect on the total latency is always an integer amount.
     ///
     /// The maximum block size is used to allocate enough scratch space for oversampling that many
-    /// samples *at the base sample rate*. The scratch buffer's size automatically takes the stage
-    /// number into account.
-    pub fn new(maximum_block_size: usize, stage_number: usize) -> Self {
-        let oversampling_amount = 2usize.pow(stage_number as u32 + 1);
+    /// samples *at the base sample rate*. The scratch buffer's size automatically takes the
+    /// oversampling amount into account.
+    pub fn new(maximum_block_size: usize, ratio: usize, oversampling_amount: usize) -> Self {
+        let (upsampling_kernel, downsampling_kernel): (&[f32], &[f32]) = match ratio {
+            2 => (&LANCZOS3_UPSAMPLING_KERNEL, &LANCZOS3_DOWNSAMPLING_KERNEL),
+            3 => (
+                &LANCZOS3_3X_UPSAMPLING_KERNEL,
+                &LANCZOS3_3X_DOWNSAMPLING_KERNEL,
+            ),
+            _ => panic!("Unsupported oversampling stage ratio {ratio}"),
+        };
 
         // In theory we would only need to delay one of these, but we'll distribute the delay
         // cleanly
-        assert!(LANCZOS3_UPSAMPLING_KERNEL.len() == LANCZOS3_DOWNSAMPLING_KERNEL.len());
-        assert!(LANCZOS3_UPSAMPLING_KERNEL.len() % 2 == 1);
+        assert!(upsampling_kernel.len() == downsampling_kernel.len());
+        assert!(upsampling_kernel.len() % 2 == 1);
 
         // This is the latency of the upsampling and downsampling filter, at the base sample rate.
         // Because this stage's filtering happens at a higher sample rate (`oversampling_amount`
@@ -297,7 +461,8 @@ impl Lanzcos3Stage {
         // sample rate_ results in an integer amount of latency at the base sample rate. To do that,
         // the delay needs to be divisible by `oversampling_amount`. This extra delay is only
         // applied to the upsampling part to keep the downsampling simpler.
-        let uncompensated_stage_latency = LANZCOS3_KERNEL_LATENCY + LANZCOS3_KERNEL_LATENCY;
+        let kernel_latency = upsampling_kernel.len() / 2;
+        let uncompensated_stage_latency = kernel_latency + kernel_latency;
 
         // Say the oversampling amount is 4, then an uncompensated stage latency of 8 results in 0
         // additional samples of delay, 9 in 3, 10 in 2, 11 in 1, 12 in 0, etc. This is added to the
@@ -307,13 +472,17 @@ impl Lanzcos3Stage {
             as usize;
 
         Self {
+            ratio,
             oversampling_amount,
 
-            upsampling_rb: vec![0.0; LANCZOS3_UPSAMPLING_KERNEL.len() + additional_delay_required],
+            upsampling_kernel,
+            downsampling_kernel,
+
+            upsampling_rb: vec![0.0; upsampling_kernel.len() + additional_delay_required],
             upsampling_write_pos: 0,
             additional_upsampling_latency: additional_delay_required,
 
-            downsampling_rb: [0.0; LANCZOS3_DOWNSAMPLING_KERNEL.len()],
+            downsampling_rb: vec![0.0; downsampling_kernel.len()],
             downsampling_write_pos: 0,
 
             scratch_buffer: vec![0.0; maximum_block_size * oversampling_amount],
@@ -332,7 +501,8 @@ impl Lanzcos3Stage {
     /// The stage's effect on the oversampling's latency as a whole. This is already divided by the
     /// stage's oversampling amount.
     pub fn effective_latency(&self) -> u32 {
-        let uncompensated_stage_latency = LANZCOS3_KERNEL_LATENCY + LANZCOS3_KERNEL_LATENCY;
+        let kernel_latency = self.upsampling_kernel.len() / 2;
+        let uncompensated_stage_latency = kernel_latency + kernel_latency;
         let total_stage_latency = uncompensated_stage_latency + self.additional_upsampling_latency;
 
         let effective_latency = total_stage_latency as f32 / self.oversampling_amount as f32;
@@ -341,30 +511,34 @@ impl Lanzcos3Stage {
         effective_latency as u32
     }
 
-    /// Upsample `block` 2x and write the results to this stage's scratch buffer.
+    /// Upsample `block` by this stage's ratio and write the results to this stage's scratch
+    /// buffer.
     ///
     /// # Panics
     ///
-    /// Panics if `block`'s times two exceeds the scratch buffer's size.
+    /// Panics if `block`'s length times the ratio exceeds the scratch buffer's size.
     pub fn upsample_from(&mut self, block: &[f32]) {
-        let output_length = block.len() * 2;
+        let output_length = block.len() * self.ratio;
         assert!(output_length <= self.scratch_buffer.len());
 
-        // We'll first zero-stuff the input, and then run that through the lanczos halfband filter
+        // We'll first zero-stuff the input, and then run that through the lanczos filter
         for (input_sample_idx, input_sample) in block.iter().enumerate() {
-            let output_sample_idx = input_sample_idx * 2;
+            let output_sample_idx = input_sample_idx * self.ratio;
             self.scratch_buffer[output_sample_idx] = *input_sample;
-            self.scratch_buffer[output_sample_idx + 1] = 0.0;
+            self.scratch_buffer[output_sample_idx + 1..output_sample_idx + self.ratio].fill(0.0);
         }
 
         // The zero-stuffed input is now run through the lanczos filter, which is a windowed sinc
-        // filter where every even tap has a value of zero. That means that if the filter is
-        // centered on a non-zero sample, the output must be equal to that sample and we can thus
-        // skip the convolution step entirely. Another important consideration is that we are
-        // imposing an additional `self.additional_upsampling_latency` samples of delay on the input
-        // to make sure the effective latency of the oversampling is always an integer amount.
+        // filter where every `ratio`th tap (other than the center tap) has a value of zero. That
+        // means that if the filter is centered on a non-zero sample, the output must be equal to
+        // that sample and we can thus skip the convolution step entirely. Another important
+        // consideration is that we are imposing an additional `self.additional_upsampling_latency`
+        // samples of delay on the input to make sure the effective latency of the oversampling is
+        // always an integer amount.
+        let kernel_latency = self.upsampling_kernel.len() / 2;
+        let direct_read_phase = (kernel_latency + self.additional_upsampling_latency) % self.ratio;
         let mut direct_read_pos =
-            (self.upsampling_write_pos + LANZCOS3_KERNEL_LATENCY) % self.upsampling_rb.len();
+            (self.upsampling_write_pos + kernel_latency) % self.upsampling_rb.len();
         for output_sample_idx in 0..output_length {
             // For a more intuitive description, imagine that `self.additional_upsampling_latency`
             // is 2, and `self.upsampling_write_pos` is currently 0. For an 11-tap filter (like the
@@ -400,10 +574,12 @@ impl Lanzcos3Stage {
             //                    └- direct_read_position
             //
             // NOTE: 'Even samples' is considered from the perspective of a zero latency filter. In
-            //       this case the evenness of the filter's latency also needs to be considered. If
-            //       it's odd then the direct reading should also happen for odd indexed samples.
+            //       this case the filter's total delay also needs to be considered. For the 2x
+            //       stages the delay is odd, so the direct reading happens for odd indexed samples.
+            //       For the 3x stages the delay is not always divisible by three, so the sample
+            //       indices that can be read directly are shifted by the remainder.
             self.scratch_buffer[output_sample_idx] =
-                if output_sample_idx % 2 == (LANZCOS3_KERNEL_LATENCY % 2) {
+                if output_sample_idx % self.ratio == direct_read_phase {
                     nih_debug_assert_eq!(
                         self.upsampling_rb[(direct_read_pos + self.upsampling_rb.len() - 1)
                             % self.upsampling_rb.len()],
@@ -418,7 +594,7 @@ impl Lanzcos3Stage {
                 } else {
                     convolve_rb(
                         &self.upsampling_rb,
-                        &LANCZOS3_UPSAMPLING_KERNEL,
+                        self.upsampling_kernel,
                         self.upsampling_write_pos,
                     )
                 };
@@ -431,9 +607,9 @@ impl Lanzcos3Stage {
     ///
     /// # Panics
     ///
-    /// Panics if `block`'s divided by two exceeds the scratch buffer's size.
+    /// Panics if `block`'s length times the ratio exceeds the scratch buffer's size.
     pub fn downsample_to(&mut self, block: &mut [f32]) {
-        let input_length = block.len() * 2;
+        let input_length = block.len() * self.ratio;
         assert!(input_length <= self.scratch_buffer.len());
 
         // The additional delay to make the latency integer has already been taken into account in
@@ -445,22 +621,21 @@ impl Lanzcos3Stage {
             // The read/write head position needs to be incremented before filtering so that the
             // just-added sample becomes the last sample in the ring buffer
             self.downsampling_write_pos += 1;
-            if self.downsampling_write_pos == LANCZOS3_DOWNSAMPLING_KERNEL.len() {
+            if self.downsampling_write_pos == self.downsampling_rb.len() {
                 self.downsampling_write_pos = 0;
             }
 
             // Because downsampling by a factor of two is filtering followed by decimation (where
             // you take every even sample), we only need to compute the filtered output for the even
             // samples. This is similar to how we only need to filter half the samples in the
-            // upsampling step.
-            if input_sample_idx % 2 == 0 {
-                let output_sample_idx = input_sample_idx / 2;
+            // upsampling step. The same applies to every third sample for the 3x stages.
+            if input_sample_idx % self.ratio == 0 {
+                let output_sample_idx = input_sample_idx / self.ratio;
                 block[output_sample_idx] = convolve_rb(
                     &self.downsampling_rb,
-                    // NOTE: This is `LANCZOS3_UPSAMPLING_KERNEL`, but with a factor two gain
-                    //       decrease to compensate for the 2x gain increase that happened during
-                    //       the upsampling
-                    &LANCZOS3_DOWNSAMPLING_KERNEL,
+                    // NOTE: This is the upsampling kernel, but with a gain decrease to compensate
+                    //       for the gain increase that happened during the upsampling
+                    self.downsampling_kernel,
                     self.downsampling_write_pos,
                 )
             }
@@ -547,13 +722,10 @@ mod tests {
         }
 
         /// Makes sure that the reported latency is correct and is (more or less) an integer value
-        fn test_latency(oversampling_factor: usize) {
+        fn test_latency(mut oversampler: Lanczos3Oversampler, oversampling_factor: usize) {
             let mut delta_impulse = [0.0f32; 64];
             delta_impulse[0] = 1.0;
 
-            let mut oversampler =
-                Lanczos3Oversampler::new(delta_impulse.len(), oversampling_factor);
-
             let reported_latency = oversampler.latency(oversampling_factor) as usize;
             assert!(
                 delta_impulse.len() > reported_latency,
@@ -573,7 +745,7 @@ mod tests {
 
         /// Checks whether the output matches the input when compensating for the latency. Also
         /// applies a gain offset to make sure the process callback actually works.
-        fn test_sine_output(oversampling_factor: usize) {
+        fn test_sine_output(mut oversampler: Lanczos3Oversampler, oversampling_factor: usize) {
             // The gain applied to the oversampled version
             const GAIN: f32 = 2.0;
             // As a fraction of the sampling frequency
@@ -585,7 +757,6 @@ mod tests {
             }
 
             let mut output = input;
-            let mut oversampler = Lanczos3Oversampler::new(output.len(), oversampling_factor);
             oversampler.process(&mut output, oversampling_factor, |upsampled| {
                 for sample in upsampled {
                     *sample *= GAIN;
@@ -607,44 +778,183 @@ mod tests {
             }
         }
 
+        /// The same as `test_sine_output()`, but using separate upsampling and downsampling steps
+        /// on two oversamplers at the same time. The upsampled signals are mixed together before
+        /// being downsampled again.
+        fn test_split_roundtrip(oversampling_factor: usize) {
+            const FREQUENCY: f32 = 0.125;
+
+            let mut input = [0.0f32; 128];
+            for (i, sample) in input.iter_mut().enumerate() {
+                *sample = (i as f32 * (FREQUENCY * 2.0 * std::f32::consts::PI)).sin();
+            }
+
+            let mut output = input;
+            let mut other_output = input;
+            let mut oversampler = Lanczos3Oversampler::new(output.len(), oversampling_factor);
+            let mut other_oversampler = Lanczos3Oversampler::new(output.len(), oversampling_factor);
+            let upsampled = oversampler.upsample_only(&mut output, oversampling_factor);
+            let other_upsampled =
+                other_oversampler.upsample_only(&mut other_output, oversampling_factor);
+            for (sample, other_sample) in upsampled.iter_mut().zip(other_upsampled.iter_mut()) {
+                *sample += *other_sample;
+                *other_sample = 0.0;
+            }
+            oversampler.downsample_only(&mut output, oversampling_factor);
+            other_oversampler.downsample_only(&mut other_output, oversampling_factor);
+
+            let reported_latency = oversampler.latency(oversampling_factor) as usize;
+            for (input_sample_idx, input_sample) in input
+                .into_iter()
+                .enumerate()
+                .take(input.len() - reported_latency)
+            {
+                let output_sample_idx = input_sample_idx + reported_latency;
+
+                approx::assert_relative_eq!(
+                    input_sample * 2.0,
+                    output[output_sample_idx],
+                    epsilon = 0.1
+                );
+                approx::assert_relative_eq!(other_output[output_sample_idx], 0.0);
+            }
+        }
+
+        #[test]
+        fn bypass_1x() {
+            let mut input = [0.0f32; 64];
+            for (i, sample) in input.iter_mut().enumerate() {
+                *sample = (i as f32 * 0.37).sin();
+            }
+
+            let mut output = input;
+            let mut oversampler = Lanczos3Oversampler::new(output.len(), 2);
+            assert_eq!(oversampler.latency(0), 0);
+
+            // Without oversampling the signal should pass through completely untouched
+            oversampler.process(&mut output, 0, |_| ());
+            assert_eq!(output, input);
+        }
+
+        #[test]
+        fn no_stale_samples_after_bypass() {
+            let mut oversampler = Lanczos3Oversampler::new(64, 1);
+
+            let mut block = [1.0f32; 64];
+            oversampler.process(&mut block, 1, |_| ());
+            let mut block = [1.0f32; 64];
+            oversampler.process(&mut block, 0, |_| ());
+
+            // The filter's state from before switching to 1x should not leak into the output
+            let mut silence = [0.0f32; 64];
+            oversampler.process(&mut silence, 1, |_| ());
+            assert_eq!(silence, [0.0; 64]);
+        }
+
         #[test]
         fn latency_2x() {
-            test_latency(1);
+            test_latency(Lanczos3Oversampler::new(64, 1), 1);
         }
 
         #[test]
         fn latency_4x() {
-            test_latency(2);
+            test_latency(Lanczos3Oversampler::new(64, 2), 2);
         }
 
         #[test]
         fn latency_8x() {
-            test_latency(3);
+            test_latency(Lanczos3Oversampler::new(64, 3), 3);
         }
 
         #[test]
         fn latency_16x() {
-            test_latency(4);
+            test_latency(Lanczos3Oversampler::new(64, 4), 4);
         }
 
         #[test]
         fn sine_output_2x() {
-            test_sine_output(1);
+            test_sine_output(Lanczos3Oversampler::new(128, 1), 1);
         }
 
         #[test]
         fn sine_output_4x() {
-            test_sine_output(2);
+            test_sine_output(Lanczos3Oversampler::new(128, 2), 2);
         }
 
         #[test]
         fn sine_output_8x() {
-            test_sine_output(3);
+            test_sine_output(Lanczos3Oversampler::new(128, 3), 3);
         }
 
         #[test]
         fn sine_output_16x() {
-            test_sine_output(4);
+            test_sine_output(Lanczos3Oversampler::new(128, 4), 4);
+        }
+
+        #[test]
+        fn latency_3x() {
+            test_latency(Lanczos3Oversampler::with_ratio(64, 3), 1);
+        }
+
+        #[test]
+        fn latency_6x() {
+            test_latency(Lanczos3Oversampler::with_ratio(64, 6), 2);
+        }
+
+        #[test]
+        fn latency_12x() {
+            test_latency(Lanczos3Oversampler::with_ratio(64, 12), 3);
+        }
+
+        #[test]
+        fn sine_output_3x() {
+            test_sine_output(Lanczos3Oversampler::with_ratio(128, 3), 1);
+        }
+
+        #[test]
+        fn sine_output_6x() {
+            test_sine_output(Lanczos3Oversampler::with_ratio(128, 6), 2);
+        }
+
+        #[test]
+        fn sine_output_12x() {
+            test_sine_output(Lanczos3Oversampler::with_ratio(128, 12), 3);
+        }
+
+        #[test]
+        fn ratio_stages() {
+            let oversampler = Lanczos3Oversampler::with_ratio(64, 12);
+            assert_eq!(oversampler.num_stages(), 3);
+            assert_eq!(oversampler.oversampling_times(0), 1);
+            assert_eq!(oversampler.oversampling_times(1), 3);
+            assert_eq!(oversampler.oversampling_times(2), 6);
+            assert_eq!(oversampler.oversampling_times(3), 12);
+        }
+
+        #[test]
+        #[should_panic]
+        fn unsupported_ratio() {
+            Lanczos3Oversampler::with_ratio(64, 10);
+        }
+
+        #[test]
+        fn split_roundtrip_2x() {
+            test_split_roundtrip(1);
+        }
+
+        #[test]
+        fn split_roundtrip_4x() {
+            test_split_roundtrip(2);
+        }
+
+        #[test]
+        fn split_roundtrip_8x() {
+            test_split_roundtrip(3);
+        }
+
+        #[test]
+        fn split_roundtrip_16x() {
+            test_split_roundtrip(4);
         }
     }
 }
@@ -0,0 +1,172 @@
+//! Conversions between frequencies and perceptual frequency scales, useful for spacing frequency
+//! bands the way they're perceived instead of linearly.
+
+/// Convert a frequency in Hertz to the Bark scale using Traunmüller's formula. This skips the
+/// corrections for the lowest and highest critical bands so the conversion can be inverted exactly
+/// using [`bark_to_hz()`].
+///
+/// <https://en.wikipedia.org/wiki/Bark_scale>
+#[inline]
+pub fn hz_to_bark(hz: f32) -> f32 {
+    (26.81 * hz / (1960.0 + hz)) - 0.53
+}
+
+/// Convert a value on the Bark scale back to a frequency in Hertz. The inverse of
+/// [`hz_to_bark()`].
+#[inline]
+pub fn bark_to_hz(bark: f32) -> f32 {
+    1960.0 * (bark + 0.53) / (26.28 - bark)
+}
+
+/// Convert a frequency in Hertz to mels using the formula used by HTK and most other audio
+/// libraries.
+///
+/// <https://en.wikipedia.org/wiki/Mel_scale>
+#[inline]
+pub fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+/// Convert a value in mels back to a frequency in Hertz. The inverse of [`hz_to_mel()`].
+#[inline]
+pub fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Convert a frequency in Hertz to the ERB-rate scale, also known as Cams, using Glasberg and
+/// Moore's formula. This is the number of equivalent rectangular bandwidths below the frequency.
+///
+/// <https://en.wikipedia.org/wiki/Equivalent_rectangular_bandwidth>
+#[inline]
+pub fn hz_to_erb(hz: f32) -> f32 {
+    21.4 * (1.0 + 0.00437 * hz).log10()
+}
+
+/// Convert a value on the ERB-rate scale back to a frequency in Hertz. The inverse of
+/// [`hz_to_erb()`].
+#[inline]
+pub fn erb_to_hz(erb: f32) -> f32 {
+    (10.0f32.powf(erb / 21.4) - 1.0) / 0.00437
+}
+
+/// The equivalent rectangular bandwidth of the auditory filter centered at a frequency, in Hertz.
+#[inline]
+pub fn erb_bandwidth(hz: f32) -> f32 {
+    24.7 * (0.00437 * hz + 1.0)
+}
+
+/// A perceptual frequency scale. Used to space frequency bands evenly on that scale with
+/// [`band_edges()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerceptualScale {
+    /// The Bark scale, see [`hz_to_bark()`].
+    Bark,
+    /// The mel scale, see [`hz_to_mel()`].
+    Mel,
+    /// The ERB-rate scale, see [`hz_to_erb()`].
+    Erb,
+}
+
+impl PerceptualScale {
+    /// Convert a frequency in Hertz to a value on this scale.
+    #[inline]
+    pub fn from_hz(self, hz: f32) -> f32 {
+        match self {
+            PerceptualScale::Bark => hz_to_bark(hz),
+            PerceptualScale::Mel => hz_to_mel(hz),
+            PerceptualScale::Erb => hz_to_erb(hz),
+        }
+    }
+
+    /// Convert a value on this scale back to a frequency in Hertz.
+    #[inline]
+    pub fn to_hz(self, value: f32) -> f32 {
+        match self {
+            PerceptualScale::Bark => bark_to_hz(value),
+            PerceptualScale::Mel => mel_to_hz(value),
+            PerceptualScale::Erb => erb_to_hz(value),
+        }
+    }
+}
+
+/// Compute the edges for `num_bands` bands between `min_hz` and `max_hz` that are spaced evenly on
+/// a perceptual scale. This returns `num_bands + 1` frequencies in Hertz, where the first edge is
+/// `min_hz` and the last edge is `max_hz`.
+pub fn band_edges(scale: PerceptualScale, min_hz: f32, max_hz: f32, num_bands: usize) -> Vec<f32> {
+    let mut edges = vec![0.0; num_bands + 1];
+    band_edges_in_place(scale, min_hz, max_hz, &mut edges);
+
+    edges
+}
+
+/// The same as [`band_edges()`], but filling an existing slice instead. The number of bands is one
+/// less than the slice's length.
+pub fn band_edges_in_place(scale: PerceptualScale, min_hz: f32, max_hz: f32, edges: &mut [f32]) {
+    nih_debug_assert!(edges.len() >= 2);
+    nih_debug_assert!(min_hz < max_hz);
+
+    let min = scale.from_hz(min_hz);
+    let max = scale.from_hz(max_hz);
+    let num_bands = edges.len() - 1;
+    for (band_idx, edge) in edges.iter_mut().enumerate() {
+        let t = band_idx as f32 / num_bands as f32;
+        *edge = scale.to_hz(min + ((max - min) * t));
+    }
+
+    // Rounding errors shouldn't cause the outer edges to fall outside of the range
+    edges[0] = min_hz;
+    edges[num_bands] = max_hz;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALES: [PerceptualScale; 3] = [
+        PerceptualScale::Bark,
+        PerceptualScale::Mel,
+        PerceptualScale::Erb,
+    ];
+
+    #[test]
+    fn test_known_values() {
+        approx::assert_relative_eq!(hz_to_bark(1000.0), 8.527, epsilon = 1e-3);
+        approx::assert_relative_eq!(hz_to_mel(1000.0), 1000.0, epsilon = 0.1);
+        approx::assert_relative_eq!(hz_to_erb(1000.0), 15.62, epsilon = 1e-2);
+        approx::assert_relative_eq!(erb_bandwidth(1000.0), 132.639, max_relative = 1e-5);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for scale in SCALES {
+            for hz in [20.0, 100.0, 440.0, 1000.0, 5000.0, 20_000.0] {
+                approx::assert_relative_eq!(
+                    scale.to_hz(scale.from_hz(hz)),
+                    hz,
+                    max_relative = 1e-4
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_band_edges() {
+        for scale in SCALES {
+            let edges = band_edges(scale, 20.0, 20_000.0, 24);
+            assert_eq!(edges.len(), 25);
+            assert_eq!(edges[0], 20.0);
+            assert_eq!(edges[24], 20_000.0);
+
+            // The bands should get wider in Hertz, but stay the same width on the scale
+            let band_width = scale.from_hz(edges[1]) - scale.from_hz(edges[0]);
+            for window in edges.windows(3) {
+                assert!(window[2] - window[1] > window[1] - window[0]);
+                approx::assert_relative_eq!(
+                    scale.from_hz(window[2]) - scale.from_hz(window[1]),
+                    band_width,
+                    max_relative = 1e-3
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Helpers for working with the complex spectra produced by real-to-complex FFTs, useful in
+//! conjunction with NIH-plug's `StftHelper`.
+
+/// Re-exported so this module can be used without directly depending on `num-complex`. This is the
+/// same type used by RustFFT and realfft.
+pub use num_complex::Complex32;
+
+/// A thin wrapper around the `window_size / 2 + 1` complex frequency bins produced by a
+/// real-to-complex FFT. This takes care of the fiddly details of converting between the rectangular
+/// and polar forms, and of keeping the spectrum valid for the inverse real FFT.
+///
+/// ```
+/// # use nih_plug_dsp::util::spectral::{Complex32, SpectralBuffer};
+/// let mut complex_fft_buffer = vec![Complex32::new(1.0, 1.0); 513];
+/// let mut magnitudes = vec![0.0; 513];
+///
+/// let mut spectrum = SpectralBuffer::new(&mut complex_fft_buffer);
+/// spectrum.zero_dc_nyquist();
+/// spectrum.magnitudes_into(&mut magnitudes);
+/// ```
+pub struct SpectralBuffer<'a> {
+    bins: &'a mut [Complex32],
+}
+
+impl<'a> SpectralBuffer<'a> {
+    /// Wrap a slice of complex frequency bins. For a real-to-complex FFT of `window_size` samples
+    /// this should contain `window_size / 2 + 1` bins.
+    pub fn new(bins: &'a mut [Complex32]) -> Self {
+        Self { bins }
+    }
+
+    /// The number of frequency bins in the spectrum.
+    #[inline]
+    pub fn num_bins(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// The wrapped frequency bins.
+    #[inline]
+    pub fn bins(&self) -> &[Complex32] {
+        self.bins
+    }
+
+    /// The wrapped frequency bins, mutably.
+    #[inline]
+    pub fn bins_mut(&mut self) -> &mut [Complex32] {
+        self.bins
+    }
+
+    /// Write each bin's magnitude to `magnitudes`. The slice should contain exactly
+    /// [`num_bins()`][Self::num_bins()] elements.
+    pub fn magnitudes_into(&self, magnitudes: &mut [f32]) {
+        nih_debug_assert_eq!(magnitudes.len(), self.bins.len());
+
+        for (magnitude, bin) in magnitudes.iter_mut().zip(self.bins.iter()) {
+            *magnitude = bin.norm();
+        }
+    }
+
+    /// Write each bin's phase in radians to `phases`. These will be in the range `[-pi, pi]`. The
+    /// slice should contain exactly [`num_bins()`][Self::num_bins()] elements.
+    pub fn phases_into(&self, phases: &mut [f32]) {
+        nih_debug_assert_eq!(phases.len(), self.bins.len());
+
+        for (phase, bin) in phases.iter_mut().zip(self.bins.iter()) {
+            *phase = bin.arg();
+        }
+    }
+
+    /// Overwrite the spectrum using the magnitudes and phases from two slices. This is the inverse
+    /// of [`magnitudes_into()`][Self::magnitudes_into()] and
+    /// [`phases_into()`][Self::phases_into()]. The imaginary components of the first and last bin
+    /// are zeroed afterwards, see [`zero_dc_nyquist()`][Self::zero_dc_nyquist()].
+    pub fn set_polar(&mut self, magnitudes: &[f32], phases: &[f32]) {
+        nih_debug_assert_eq!(magnitudes.len(), self.bins.len());
+        nih_debug_assert_eq!(phases.len(), self.bins.len());
+
+        for ((bin, magnitude), phase) in self.bins.iter_mut().zip(magnitudes).zip(phases) {
+            *bin = Complex32::from_polar(*magnitude, *phase);
+        }
+
+        self.zero_dc_nyquist();
+    }
+
+    /// Multiply each bin with a real valued gain. This leaves the phases intact. The slice should
+    /// contain exactly [`num_bins()`][Self::num_bins()] elements.
+    pub fn apply_gains(&mut self, gains: &[f32]) {
+        nih_debug_assert_eq!(gains.len(), self.bins.len());
+
+        for (bin, gain) in self.bins.iter_mut().zip(gains) {
+            *bin *= *gain;
+        }
+    }
+
+    /// Multiply every bin with the same real valued gain. Useful for applying gain compensation
+    /// before the inverse FFT.
+    pub fn apply_gain(&mut self, gain: f32) {
+        for bin in self.bins.iter_mut() {
+            *bin *= gain;
+        }
+    }
+
+    /// Make sure the imaginary components of the DC bin and the Nyquist bin are zero. The spectrum
+    /// of a real valued signal always has a purely real first and last bin, and the inverse
+    /// real FFT will refuse to process (or produce garbage for) spectra that don't satisfy this
+    /// after modifying the bins. This assumes the wrapped spectrum was computed from an
+    /// even-length window, which is always the case when using power of two window sizes.
+    pub fn zero_dc_nyquist(&mut self) {
+        if let Some(first_bin) = self.bins.first_mut() {
+            first_bin.im = 0.0;
+        }
+        if let Some(last_bin) = self.bins.last_mut() {
+            last_bin.im = 0.0;
+        }
+    }
+}
+
+/// Smooth a spectrum's magnitudes over frequency using a constant-Q moving average. Every bin is
+/// replaced by the average of the bins within `octave_fraction` octaves around it, so with
+/// `octave_fraction = 1.0 / 3.0` this results in third-octave smoothing. Because the kernel's width
+/// is proportional to the bin's center frequency, low and high frequencies are smoothed by the
+/// same musical amount regardless of the sample rate or the FFT window size. The kernel spans only
+/// a single bin at the lowest frequencies, and it grows wider towards the higher frequencies.
+/// Setting `octave_fraction` to zero (or a negative value) leaves the magnitudes untouched.
+///
+/// `magnitudes` can be obtained using [`SpectralBuffer::magnitudes_into()`]. `scratch_buffer` is
+/// used for intermediate results so this doesn't need to allocate, and it must contain at least as
+/// many elements as `magnitudes`.
+pub fn smooth_magnitudes(magnitudes: &mut [f32], scratch_buffer: &mut [f32], octave_fraction: f32) {
+    nih_debug_assert!(scratch_buffer.len() >= magnitudes.len());
+    if octave_fraction <= 0.0 || magnitudes.is_empty() {
+        return;
+    }
+
+    // The scratch buffer contains a copy of the original magnitudes. Both ends of the kernel only
+    // ever move towards higher bins, so every bin's average can be computed in constant time by
+    // updating a running sum over `scratch_buffer[window_start_idx..window_end_idx]`. This sum is
+    // kept in double precision because spectra often have a huge dynamic range, and with single
+    // precision the quiet bins would get drowned out by rounding errors after the running sum has
+    // added and then subtracted much louder bins.
+    let scratch_buffer = &mut scratch_buffer[..magnitudes.len()];
+    scratch_buffer.copy_from_slice(magnitudes);
+    let mut window_sum = 0.0f64;
+    let mut window_start_idx = 0;
+    let mut window_end_idx = 0;
+
+    let lower_multiplier = 2.0f32.powf(-octave_fraction / 2.0);
+    let upper_multiplier = 2.0f32.powf(octave_fraction / 2.0);
+    let last_bin_idx = magnitudes.len() - 1;
+    for (bin_idx, magnitude) in magnitudes.iter_mut().enumerate() {
+        let start_idx = (bin_idx as f32 * lower_multiplier).round() as usize;
+        let end_idx = ((bin_idx as f32 * upper_multiplier).round() as usize).min(last_bin_idx);
+        if start_idx >= end_idx {
+            continue;
+        }
+
+        while window_end_idx <= end_idx {
+            window_sum += scratch_buffer[window_end_idx] as f64;
+            window_end_idx += 1;
+        }
+        while window_start_idx < start_idx {
+            window_sum -= scratch_buffer[window_start_idx] as f64;
+            window_start_idx += 1;
+        }
+
+        *magnitude = (window_sum / (end_idx - start_idx + 1) as f64) as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magnitudes_phases_roundtrip() {
+        let mut bins = vec![
+            Complex32::new(1.0, 0.0),
+            Complex32::new(0.0, 2.0),
+            Complex32::new(-3.0, 0.0),
+            Complex32::new(0.5, 0.0),
+        ];
+        let original_bins = bins.clone();
+        let mut magnitudes = vec![0.0; bins.len()];
+        let mut phases = vec![0.0; bins.len()];
+
+        let mut spectrum = SpectralBuffer::new(&mut bins);
+        spectrum.magnitudes_into(&mut magnitudes);
+        spectrum.phases_into(&mut phases);
+        assert_eq!(magnitudes, [1.0, 2.0, 3.0, 0.5]);
+
+        spectrum.set_polar(&magnitudes, &phases);
+        for (bin, original_bin) in bins.iter().zip(original_bins.iter()) {
+            approx::assert_relative_eq!(bin.re, original_bin.re, epsilon = 1e-6);
+            approx::assert_relative_eq!(bin.im, original_bin.im, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_apply_gains() {
+        let mut bins = vec![Complex32::new(1.0, 1.0); 3];
+        SpectralBuffer::new(&mut bins).apply_gains(&[0.0, 1.0, 2.0]);
+
+        assert_eq!(
+            bins,
+            [
+                Complex32::new(0.0, 0.0),
+                Complex32::new(1.0, 1.0),
+                Complex32::new(2.0, 2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_dc_nyquist() {
+        let mut bins = vec![Complex32::new(1.0, 1.0); 3];
+        SpectralBuffer::new(&mut bins).zero_dc_nyquist();
+
+        assert_eq!(
+            bins,
+            [
+                Complex32::new(1.0, 0.0),
+                Complex32::new(1.0, 1.0),
+                Complex32::new(1.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_dc_nyquist_empty() {
+        SpectralBuffer::new(&mut []).zero_dc_nyquist();
+    }
+
+    #[test]
+    fn test_smooth_magnitudes_zero_octave_fraction() {
+        let mut magnitudes: Vec<f32> = (0..64).map(|i| (i % 7) as f32).collect();
+        let original_magnitudes = magnitudes.clone();
+        let mut scratch_buffer = vec![0.0; magnitudes.len()];
+
+        smooth_magnitudes(&mut magnitudes, &mut scratch_buffer, 0.0);
+        assert_eq!(magnitudes, original_magnitudes);
+    }
+
+    #[test]
+    fn test_smooth_magnitudes_constant_q() {
+        // A flat spectrum should stay flat
+        let mut magnitudes = vec![2.0; 513];
+        let mut scratch_buffer = vec![0.0; magnitudes.len()];
+        smooth_magnitudes(&mut magnitudes, &mut scratch_buffer, 1.0);
+        for magnitude in &magnitudes {
+            approx::assert_relative_eq!(*magnitude, 2.0, epsilon = 1e-4);
+        }
+
+        // An impulse at a high bin should be spread out over more bins than an impulse at a low
+        // bin, since the kernel's width is proportional to the bin's frequency
+        let mut magnitudes = vec![0.0; 513];
+        magnitudes[16] = 1.0;
+        magnitudes[256] = 1.0;
+        smooth_magnitudes(&mut magnitudes, &mut scratch_buffer, 1.0);
+        let low_spread = magnitudes[..128].iter().filter(|m| **m > 0.0).count();
+        let high_spread = magnitudes[128..].iter().filter(|m| **m > 0.0).count();
+        assert!(low_spread > 1);
+        assert!(high_spread > low_spread * 8);
+        assert_eq!(magnitudes[0], 0.0);
+    }
+
+    #[test]
+    fn test_smooth_magnitudes_dynamic_range() {
+        // The quiet bins are 160 dB below the loud low bins. Once the kernel has moved past the
+        // loud bins, the quiet bins should not be affected by them anymore.
+        let mut magnitudes = vec![1e-4; 513];
+        magnitudes[..32].fill(1e4);
+        let mut scratch_buffer = vec![0.0; magnitudes.len()];
+        smooth_magnitudes(&mut magnitudes, &mut scratch_buffer, 1.0 / 3.0);
+        for magnitude in &magnitudes[64..] {
+            approx::assert_relative_eq!(*magnitude, 1e-4, max_relative = 1e-4);
+        }
+    }
+}
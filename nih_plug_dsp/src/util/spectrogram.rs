@@ -0,0 +1,232 @@
+//! A lock-free history of spectra for drawing scrolling spectrograms.
+
+use atomic_float::AtomicF32;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::spectral::Complex32;
+
+/// Keeps track of the most recent magnitude spectra produced by the audio thread so an editor can
+/// draw a scrolling spectrogram. The plugin computes a spectrum using its own FFT, for instance
+/// from within an `StftHelper` callback, and then adds it to the spectrogram using
+/// [`push_bins()`][Self::push_bins()] or [`push_magnitudes()`][Self::push_magnitudes()]. The
+/// editor reads the history through a [`SpectrogramOutput`] obtained from
+/// [`output()`][Self::output()].
+///
+/// All memory is allocated up front in [`new()`][Self::new()], and neither side ever blocks. Every
+/// magnitude is stored atomically, but a frame as a whole is not. The audio thread writes new
+/// spectra to a spare slot that is not part of the history at the start of a read, so a read is
+/// consistent as long as no spectra are pushed while it is in progress. For every spectrum that is
+/// pushed during a read, one more of the oldest frames may contain a mix of an old and a newer
+/// spectrum. The editor can compare the value returned by
+/// [`SpectrogramOutput::read_into()`] to [`SpectrogramOutput::frames_written()`] after reading to
+/// detect this.
+///
+/// ```
+/// # use nih_plug_dsp::util::Spectrogram;
+/// // A 1024 sample FFT produces 513 bins, and the editor can show the last 256 spectra
+/// let mut spectrogram = Spectrogram::new(1024, 256);
+/// let output = spectrogram.output();
+///
+/// // On the audio thread, after computing a spectrum
+/// let magnitudes = vec![0.5; 513];
+/// spectrogram.push_magnitudes(&magnitudes);
+///
+/// // In the editor
+/// let mut frames = vec![0.0; output.num_bins() * output.time_depth()];
+/// if output.read_into(&mut frames) > 0 {
+///     // Draw the frames from oldest to newest
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Spectrogram {
+    /// The storage shared with the [`SpectrogramOutput`]s.
+    shared: Arc<SharedSpectrogram>,
+    /// The slot the next frame will be written to.
+    write_slot: usize,
+}
+
+/// The reading half of a [`Spectrogram`]. This can be cloned and sent to the editor.
+#[derive(Debug, Clone)]
+pub struct SpectrogramOutput {
+    shared: Arc<SharedSpectrogram>,
+}
+
+#[derive(Debug)]
+struct SharedSpectrogram {
+    /// The number of frequency bins in each frame.
+    num_bins: usize,
+    /// The number of frames that can be read from the spectrogram.
+    time_depth: usize,
+    /// `time_depth + 1` frames containing `num_bins` magnitudes each. The extra slot is written to
+    /// while the other frames are being read.
+    frames: Vec<AtomicF32>,
+    /// The total number of frames that have been pushed. This is incremented after a frame has
+    /// been written, and it determines which slots contain the newest frames.
+    frames_written: AtomicUsize,
+}
+
+impl Spectrogram {
+    /// Create a spectrogram for an FFT with a window size of `fft_size` samples, storing the last
+    /// `time_depth` spectra. Each spectrum contains `fft_size / 2 + 1` bins, matching the output of
+    /// a real-to-complex FFT.
+    pub fn new(fft_size: usize, time_depth: usize) -> Self {
+        nih_debug_assert!(fft_size >= 2);
+        nih_debug_assert!(time_depth >= 1);
+
+        let num_bins = fft_size / 2 + 1;
+        let time_depth = time_depth.max(1);
+
+        Self {
+            shared: Arc::new(SharedSpectrogram {
+                num_bins,
+                time_depth,
+                frames: (0..(time_depth + 1) * num_bins)
+                    .map(|_| AtomicF32::new(0.0))
+                    .collect(),
+                frames_written: AtomicUsize::new(0),
+            }),
+            write_slot: 0,
+        }
+    }
+
+    /// Get a handle for reading the spectrogram from the editor.
+    pub fn output(&self) -> SpectrogramOutput {
+        SpectrogramOutput {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// The number of frequency bins in each spectrum.
+    pub fn num_bins(&self) -> usize {
+        self.shared.num_bins
+    }
+
+    /// The number of spectra that are kept around.
+    pub fn time_depth(&self) -> usize {
+        self.shared.time_depth
+    }
+
+    /// Add a spectrum from the complex bins produced by a real-to-complex FFT. This slice should
+    /// contain [`num_bins()`][Self::num_bins()] bins. The bins' magnitudes are stored as is, so
+    /// any normalization for the window and the FFT size should already have been applied.
+    pub fn push_bins(&mut self, bins: &[Complex32]) {
+        nih_debug_assert_eq!(bins.len(), self.shared.num_bins);
+
+        self.push_with(|bin_idx| bins.get(bin_idx).map(|bin| bin.norm()).unwrap_or(0.0));
+    }
+
+    /// Add a spectrum from a slice containing [`num_bins()`][Self::num_bins()] magnitudes.
+    pub fn push_magnitudes(&mut self, magnitudes: &[f32]) {
+        nih_debug_assert_eq!(magnitudes.len(), self.shared.num_bins);
+
+        self.push_with(|bin_idx| magnitudes.get(bin_idx).copied().unwrap_or(0.0));
+    }
+
+    /// Clear the history, for instance when the plugin gets reset.
+    pub fn clear(&mut self) {
+        for magnitude in &self.shared.frames {
+            magnitude.store(0.0, Ordering::Relaxed);
+        }
+        self.write_slot = 0;
+        self.shared.frames_written.store(0, Ordering::Release);
+    }
+
+    fn push_with(&mut self, mut magnitude: impl FnMut(usize) -> f32) {
+        let num_bins = self.shared.num_bins;
+        let frame = &self.shared.frames[self.write_slot * num_bins..][..num_bins];
+        for (bin_idx, target) in frame.iter().enumerate() {
+            target.store(magnitude(bin_idx), Ordering::Relaxed);
+        }
+
+        // The frame only becomes visible to the reader after this store
+        self.write_slot = (self.write_slot + 1) % (self.shared.time_depth + 1);
+        self.shared.frames_written.fetch_add(1, Ordering::Release);
+    }
+}
+
+impl SpectrogramOutput {
+    /// The number of frequency bins in each spectrum.
+    pub fn num_bins(&self) -> usize {
+        self.shared.num_bins
+    }
+
+    /// The number of spectra that are kept around.
+    pub fn time_depth(&self) -> usize {
+        self.shared.time_depth
+    }
+
+    /// The total number of spectra that have been pushed to the spectrogram. This can be compared
+    /// to a previous value to check whether there's new data to draw.
+    pub fn frames_written(&self) -> usize {
+        self.shared.frames_written.load(Ordering::Acquire)
+    }
+
+    /// Copy the spectrogram's history to `frames`, which should contain `time_depth() *
+    /// num_bins()` values. The frames are written from oldest to newest, with the bins for each
+    /// frame stored contiguously. Frames that haven't been written yet are zeroed. Returns the
+    /// total number of frames that have been pushed, just like
+    /// [`frames_written()`][Self::frames_written()].
+    pub fn read_into(&self, frames: &mut [f32]) -> usize {
+        let num_bins = self.shared.num_bins;
+        let time_depth = self.shared.time_depth;
+        nih_debug_assert_eq!(frames.len(), time_depth * num_bins);
+
+        let frames_written = self.frames_written();
+        let num_slots = time_depth + 1;
+        for (age, target) in frames.chunks_exact_mut(num_bins).rev().enumerate() {
+            // `age` 0 is the newest frame, which is stored at the end of `frames`
+            if age >= frames_written {
+                target.fill(0.0);
+                continue;
+            }
+
+            let slot = (frames_written - 1 - age) % num_slots;
+            let source = &self.shared.frames[slot * num_bins..][..num_bins];
+            for (target, source) in target.iter_mut().zip(source) {
+                *target = source.load(Ordering::Relaxed);
+            }
+        }
+
+        frames_written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_order() {
+        let mut spectrogram = Spectrogram::new(4, 3);
+        let output = spectrogram.output();
+        assert_eq!(output.num_bins(), 3);
+
+        let mut frames = vec![1.0; 9];
+        assert_eq!(output.read_into(&mut frames), 0);
+        assert_eq!(frames, [0.0; 9]);
+
+        spectrogram.push_magnitudes(&[1.0, 1.0, 1.0]);
+        spectrogram.push_magnitudes(&[2.0, 2.0, 2.0]);
+        assert_eq!(output.read_into(&mut frames), 2);
+        assert_eq!(frames, [0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let mut spectrogram = Spectrogram::new(2, 2);
+        let output = spectrogram.output();
+
+        for value in 1..=5 {
+            spectrogram.push_bins(&[Complex32::new(value as f32, 0.0); 2]);
+        }
+
+        let mut frames = vec![0.0; 4];
+        assert_eq!(output.read_into(&mut frames), 5);
+        assert_eq!(frames, [4.0, 4.0, 5.0, 5.0]);
+
+        spectrogram.clear();
+        assert_eq!(output.read_into(&mut frames), 0);
+        assert_eq!(frames, [0.0; 4]);
+    }
+}
@@ -0,0 +1,227 @@
+//! A CPU budget for polyphonic instruments that scales the number of voices with the processing
+//! load.
+
+use std::time::{Duration, Instant};
+
+/// The fraction of the real-time budget a plugin may use before voices are shed by default.
+pub const DEFAULT_CPU_BUDGET: f32 = 0.75;
+
+/// The load is only allowed to grow back to the maximum number of voices once it drops below this
+/// fraction of the CPU budget. This prevents the voice limit from oscillating.
+const RECOVERY_THRESHOLD: f32 = 0.6;
+
+/// How quickly the measured load follows the load of the most recent block. The load is smoothed
+/// so a single slow block, for instance because of a page fault, doesn't immediately shed voices.
+const LOAD_SMOOTHING: f32 = 0.25;
+
+/// Scales the number of voices a polyphonic instrument may use based on how long the plugin takes
+/// to process a block compared to the block's duration. The plugin calls
+/// [`start_block()`][Self::start_block()] at the start of its `process()` function and
+/// [`end_block()`][Self::end_block()] at the end. Once the smoothed load exceeds the CPU budget,
+/// the [`voice_limit()`][Self::voice_limit()] is scaled down proportionally, and it recovers one
+/// voice at a time once the load has dropped well below the budget. The limit never exceeds the
+/// configured maximum.
+///
+/// This does not manage any voices by itself. When the plugin has more active voices than the
+/// limit, it should fade out or terminate its quietest voices so the voices that are shed are the
+/// ones nobody will miss. The plugin should also call the process context's
+/// `set_current_voice_capacity()` function with the new limit whenever
+/// [`end_block()`][Self::end_block()] returns `true` so the host knows about the reduced capacity.
+///
+/// ```
+/// # use nih_plug_dsp::util::VoiceBudget;
+/// let mut budget = VoiceBudget::new(16);
+/// budget.set_max_voices(8);
+///
+/// budget.start_block();
+/// // Process the voices here
+/// let active_voices = 8;
+/// if budget.end_block(512, 44_100.0, active_voices) {
+///     // Report `budget.voice_limit()` to the host
+/// }
+///
+/// assert!(budget.voice_limit() <= 8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VoiceBudget {
+    /// The maximum number of voices the plugin supports.
+    voice_capacity: u32,
+    /// The user-configured maximum number of voices, in `1..=voice_capacity`.
+    max_voices: u32,
+    /// The fraction of a block's duration the plugin may spend processing the block.
+    cpu_budget: f32,
+
+    /// The current voice limit, in `1..=max_voices`.
+    voice_limit: u32,
+    /// The smoothed processing load, where 1.0 means that processing a block takes as long as the
+    /// block's duration.
+    load: f32,
+    /// The time [`start_block()`][Self::start_block()] was last called.
+    block_start: Option<Instant>,
+}
+
+impl VoiceBudget {
+    /// Create a voice budget for a plugin that supports at most `voice_capacity` voices. This
+    /// should match the plugin's `max_voice_capacity`.
+    pub fn new(voice_capacity: u32) -> Self {
+        nih_debug_assert!(voice_capacity >= 1);
+        let voice_capacity = voice_capacity.max(1);
+
+        Self {
+            voice_capacity,
+            max_voices: voice_capacity,
+            cpu_budget: DEFAULT_CPU_BUDGET,
+
+            voice_limit: voice_capacity,
+            load: 0.0,
+            block_start: None,
+        }
+    }
+
+    /// Set the maximum number of voices, for instance from a parameter. This is clamped to
+    /// `1..=voice_capacity`. Returns `true` if this changed the
+    /// [`voice_limit()`][Self::voice_limit()].
+    pub fn set_max_voices(&mut self, max_voices: u32) -> bool {
+        self.max_voices = max_voices.clamp(1, self.voice_capacity);
+
+        // Lowering the maximum takes effect immediately, while raising it only takes effect
+        // immediately if the plugin is not currently running low on CPU time. Otherwise the limit
+        // will slowly recover once the load drops.
+        let old_limit = self.voice_limit;
+        self.voice_limit = self.voice_limit.min(self.max_voices);
+        if self.load < self.cpu_budget * RECOVERY_THRESHOLD {
+            self.voice_limit = self.max_voices;
+        }
+
+        self.voice_limit != old_limit
+    }
+
+    /// Set the fraction of the real-time budget the plugin may use before voices are shed. Defaults
+    /// to [`DEFAULT_CPU_BUDGET`]. This is clamped to `(0, 1]`.
+    pub fn set_cpu_budget(&mut self, cpu_budget: f32) {
+        nih_debug_assert!(cpu_budget > 0.0 && cpu_budget <= 1.0);
+        self.cpu_budget = cpu_budget.clamp(f32::EPSILON, 1.0);
+    }
+
+    /// The number of voices the plugin may currently use.
+    pub fn voice_limit(&self) -> u32 {
+        self.voice_limit
+    }
+
+    /// The smoothed processing load, where 1.0 means that processing a block takes as long as the
+    /// block's duration.
+    pub fn load(&self) -> f32 {
+        self.load
+    }
+
+    /// Reset the measured load and the voice limit, for instance when the plugin gets reset.
+    pub fn reset(&mut self) {
+        self.voice_limit = self.max_voices;
+        self.load = 0.0;
+        self.block_start = None;
+    }
+
+    /// Start measuring the processing time for a block. Call this at the very start of the
+    /// `process()` function.
+    pub fn start_block(&mut self) {
+        self.block_start = Some(Instant::now());
+    }
+
+    /// Stop measuring the processing time for a block containing `num_samples` samples, and update
+    /// the voice limit. `active_voices` is the number of voices that were processed. Returns `true`
+    /// if the voice limit has changed.
+    pub fn end_block(&mut self, num_samples: usize, sample_rate: f32, active_voices: u32) -> bool {
+        nih_debug_assert!(
+            self.block_start.is_some(),
+            "end_block() was called without calling start_block()"
+        );
+
+        match self.block_start.take() {
+            Some(block_start) => self.add_measurement(
+                block_start.elapsed(),
+                num_samples,
+                sample_rate,
+                active_voices,
+            ),
+            None => false,
+        }
+    }
+
+    /// Update the voice limit using a block's processing time. This is what
+    /// [`end_block()`][Self::end_block()] uses, but it can also be called directly if the plugin
+    /// measures the processing time itself. Returns `true` if the voice limit has changed.
+    pub fn add_measurement(
+        &mut self,
+        processing_time: Duration,
+        num_samples: usize,
+        sample_rate: f32,
+        active_voices: u32,
+    ) -> bool {
+        if num_samples == 0 || sample_rate <= 0.0 {
+            return false;
+        }
+
+        let block_duration = num_samples as f32 / sample_rate;
+        let block_load = processing_time.as_secs_f32() / block_duration;
+        self.load += (block_load - self.load) * LOAD_SMOOTHING;
+
+        let old_limit = self.voice_limit;
+        if self.load > self.cpu_budget {
+            // The processing time is roughly proportional to the number of voices, so the number of
+            // voices that fit in the budget can be estimated from the current load. The limit
+            // always decreases by at least one voice so an overloaded plugin keeps shedding voices.
+            let voices = active_voices.min(self.voice_limit);
+            let scaled_voices = (voices as f32 * (self.cpu_budget / self.load)).floor() as u32;
+            self.voice_limit = scaled_voices.min(self.voice_limit.saturating_sub(1)).max(1);
+        } else if self.load < self.cpu_budget * RECOVERY_THRESHOLD {
+            self.voice_limit = (self.voice_limit + 1).min(self.max_voices);
+        }
+
+        self.voice_limit != old_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The processing time for a block of `num_samples` samples at 48 kHz with the given load.
+    fn processing_time(num_samples: usize, load: f32) -> Duration {
+        Duration::from_secs_f32(num_samples as f32 / 48_000.0 * load)
+    }
+
+    #[test]
+    fn test_shed_and_recover() {
+        let mut budget = VoiceBudget::new(16);
+        assert_eq!(budget.voice_limit(), 16);
+
+        // A sustained overload should scale down the number of voices
+        for _ in 0..20 {
+            budget.add_measurement(processing_time(512, 1.5), 512, 48_000.0, 16);
+        }
+        assert!(budget.voice_limit() < 16);
+        assert!(budget.voice_limit() >= 1);
+
+        // And the voices should come back once the load drops
+        for _ in 0..100 {
+            budget.add_measurement(processing_time(512, 0.1), 512, 48_000.0, 4);
+        }
+        assert_eq!(budget.voice_limit(), 16);
+    }
+
+    #[test]
+    fn test_max_voices() {
+        let mut budget = VoiceBudget::new(16);
+        assert!(budget.set_max_voices(4));
+        assert_eq!(budget.voice_limit(), 4);
+
+        for _ in 0..10 {
+            budget.add_measurement(processing_time(512, 0.1), 512, 48_000.0, 4);
+        }
+        assert_eq!(budget.voice_limit(), 4);
+
+        // This is clamped to the voice capacity
+        budget.set_max_voices(100);
+        assert_eq!(budget.voice_limit(), 16);
+    }
+}
@@ -0,0 +1,195 @@
+//! Windowing functions, useful in conjunction with NIH-plug's `StftHelper`.
+
+use std::f32;
+
+/// A Blackman window function with the 'standard' coefficients.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Blackman_window>
+pub fn blackman(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    blackman_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`blackman()`], but filling an existing slice instead. asfasdf
+pub fn blackman_in_place(window: &mut [f32]) {
+    let size = window.len();
+
+    let scale_1 = (2.0 * f32::consts::PI) / (size - 1) as f32;
+    let scale_2 = scale_1 * 2.0;
+    for (i, sample) in window.iter_mut().enumerate() {
+        let cos_1 = (scale_1 * i as f32).cos();
+        let cos_2 = (scale_2 * i as f32).cos();
+        *sample = 0.42 - (0.5 * cos_1) + (0.08 * cos_2);
+    }
+}
+
+/// A Hann window function.
+///
+/// <https://en.wikipedia.org/wiki/Hann_function>
+pub fn hann(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    hann_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`hann()`], but filling an existing slice instead.
+pub fn hann_in_place(window: &mut [f32]) {
+    let size = window.len();
+
+    // We want to scale `[0, size - 1]` to `[0, pi]`.
+    // XXX: The `sin^2()` version results in weird rounding errors that cause spectral leakage
+    let scale = (size as f32 - 1.0).recip() * f32::consts::TAU;
+    for (i, sample) in window.iter_mut().enumerate() {
+        let cos = (i as f32 * scale).cos();
+        *sample = 0.5 - (0.5 * cos)
+    }
+}
+
+/// A four-term Blackman-Harris window function. This has much lower sidelobes than the Hann and
+/// Blackman windows at the cost of a wider main lobe, which reduces spectral leakage.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Blackman%E2%80%93Harris_window>
+pub fn blackman_harris(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    blackman_harris_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`blackman_harris()`], but filling an existing slice instead.
+pub fn blackman_harris_in_place(window: &mut [f32]) {
+    let size = window.len();
+
+    let scale_1 = (size as f32 - 1.0).recip() * f32::consts::TAU;
+    let scale_2 = scale_1 * 2.0;
+    let scale_3 = scale_1 * 3.0;
+    for (i, sample) in window.iter_mut().enumerate() {
+        let cos_1 = (scale_1 * i as f32).cos();
+        let cos_2 = (scale_2 * i as f32).cos();
+        let cos_3 = (scale_3 * i as f32).cos();
+        *sample = 0.35875 - (0.48829 * cos_1) + (0.14128 * cos_2) - (0.01168 * cos_3);
+    }
+}
+
+/// A Tukey window function, also known as a tapered cosine window. `alpha` is the fraction of the
+/// window that is tapered, and it should be in the range `[0, 1]`. Half of that is used for the
+/// fade in at the start and the other half for the fade out at the end, with the window being flat
+/// in between. An `alpha` of 0 results in a rectangular window, and an `alpha` of 1 results in a
+/// [`hann()`] window.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Tukey_window>
+pub fn tukey(size: usize, alpha: f32) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    tukey_in_place(&mut window, alpha);
+
+    window
+}
+
+/// The same as [`tukey()`], but filling an existing slice instead.
+pub fn tukey_in_place(window: &mut [f32], alpha: f32) {
+    nih_debug_assert!((0.0..=1.0).contains(&alpha));
+    let alpha = alpha.clamp(0.0, 1.0);
+    let size = window.len();
+
+    // Like with the Hann window, `[0, size - 1]` is mapped to `[0, 1]`. The tapered regions are
+    // the two halves of a Hann window with a length of `alpha * (size - 1)`.
+    let scale = (size as f32 - 1.0).recip();
+    for (i, sample) in window.iter_mut().enumerate() {
+        let x = i as f32 * scale;
+        let tapered_x = if x < alpha / 2.0 {
+            x
+        } else if x > 1.0 - (alpha / 2.0) {
+            1.0 - x
+        } else {
+            *sample = 1.0;
+            continue;
+        };
+
+        let cos = (tapered_x * f32::consts::TAU / alpha).cos();
+        *sample = 0.5 - (0.5 * cos)
+    }
+}
+
+/// Compute the gain compensation needed when a signal is windowed with `window` both before the
+/// analysis and after the synthesis stage of an overlap-add process with `overlap_times` times
+/// overlap, like when using NIH-plug's `StftHelper`. This is the reciprocal of the
+/// average sum of the overlapping squared windows, so it works for any window function and overlap
+/// amount. For a Hann window with four times overlap or higher this is
+/// `((overlap_times / 4) * 1.5)^-1`.
+///
+/// This does not include the `1 / window_size` normalization needed after a round trip through an
+/// unnormalized FFT and IFFT, so that still needs to be applied separately.
+pub fn overlap_add_gain(window: &[f32], overlap_times: usize) -> f32 {
+    nih_debug_assert!(overlap_times > 0);
+    nih_debug_assert_eq!(window.len() % overlap_times, 0);
+
+    // Every output sample is the sum of `overlap_times` squared window samples spaced `hop_size`
+    // samples apart. Averaged over a single hop that's the sum of all squared window samples
+    // divided by the hop size.
+    let hop_size = window.len() / overlap_times;
+    let squared_sum: f32 = window.iter().map(|sample| sample * sample).sum();
+
+    hop_size as f32 / squared_sum
+}
+
+/// Multiply a buffer with a window function.
+#[inline]
+pub fn multiply_with_window(buffer: &mut [f32], window_function: &[f32]) {
+    // TODO: ALso use SIMD here if available
+    for (sample, window_sample) in buffer.iter_mut().zip(window_function) {
+        *sample *= window_sample;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlap_add_gain_hann() {
+        for overlap_times in [4, 8, 16] {
+            let window = hann(2048);
+            approx::assert_relative_eq!(
+                overlap_add_gain(&window, overlap_times),
+                ((overlap_times as f32 / 4.0) * 1.5).recip(),
+                max_relative = 1e-2
+            );
+        }
+    }
+
+    #[test]
+    fn test_tukey() {
+        let window = tukey(1025, 0.5);
+        approx::assert_relative_eq!(window[0], 0.0);
+        approx::assert_relative_eq!(window[1024], 0.0);
+        approx::assert_relative_eq!(window[512], 1.0);
+        // The middle half of the window should be flat
+        assert!(window[256..=768].iter().all(|&sample| sample == 1.0));
+
+        for (tukey_sample, hann_sample) in tukey(1024, 1.0).into_iter().zip(hann(1024)) {
+            approx::assert_abs_diff_eq!(tukey_sample, hann_sample, epsilon = 1e-6);
+        }
+
+        assert!(tukey(1024, 0.0).into_iter().all(|sample| sample == 1.0));
+    }
+
+    #[test]
+    fn test_blackman_harris() {
+        let window = blackman_harris(1025);
+        approx::assert_abs_diff_eq!(window[0], 0.00006, epsilon = 1e-6);
+        approx::assert_abs_diff_eq!(window[1024], 0.00006, epsilon = 1e-6);
+        approx::assert_relative_eq!(window[512], 1.0, max_relative = 1e-6);
+
+        assert!(blackman_harris(0).is_empty());
+    }
+
+    #[test]
+    fn test_overlap_add_gain_rectangular() {
+        let window = vec![1.0; 1024];
+        approx::assert_relative_eq!(overlap_add_gain(&window, 1), 1.0);
+        approx::assert_relative_eq!(overlap_add_gain(&window, 4), 0.25);
+    }
+}
@@ -1,14 +1,14 @@
 //! An [`Editor`] implementation for egui.
 
 use baseview::gl::GlConfig;
-use baseview::{Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
+use baseview::{PhySize, Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
 use crossbeam::atomic::AtomicCell;
 use egui_baseview::egui::Context;
 use egui_baseview::EguiWindow;
 use nih_plug::prelude::{Editor, GuiContext, ParamSetter, ParentWindowHandle};
 use parking_lot::RwLock;
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use crate::EguiState;
@@ -27,6 +27,10 @@ pub(crate) struct EguiEditor<T> {
     /// The scaling factor reported by the host, if any. On macOS this will never be set and we
     /// should use the system scaling factor instead.
     pub(crate) scaling_factor: AtomicCell<Option<f32>>,
+
+    /// Set by [`set_size()`][Editor::set_size()] when the editor is open. The next frame then
+    /// resizes the embedded window to match the size stored in the [`EguiState`].
+    pub(crate) resize_window: Arc<AtomicBool>,
 }
 
 /// This version of `baseview` uses a different version of `raw_window_handle than NIH-plug, so we
@@ -67,6 +71,8 @@ where
         let build = self.build.clone();
         let update = self.update.clone();
         let state = self.user_state.clone();
+        let egui_state = self.egui_state.clone();
+        let resize_window = self.resize_window.clone();
 
         let (unscaled_width, unscaled_height) = self.egui_state.size();
         let scaling_factor = self.scaling_factor.load();
@@ -100,9 +106,20 @@ where
             },
             state,
             move |egui_ctx, _queue, state| build(egui_ctx, &mut state.write()),
-            move |egui_ctx, _queue, state| {
+            move |egui_ctx, queue, state| {
                 let setter = ParamSetter::new(context.as_ref());
 
+                // By now the host has either accepted the size set through
+                // `GuiContext::request_resize_to()`, or the old size has been restored
+                if resize_window.swap(false, Ordering::AcqRel) {
+                    let (width, height) = egui_state.size();
+                    let pixels_per_point = egui_ctx.pixels_per_point();
+                    queue.resize(PhySize::new(
+                        (width as f32 * pixels_per_point).round() as u32,
+                        (height as f32 * pixels_per_point).round() as u32,
+                    ));
+                }
+
                 // For now, just always redraw. Most plugin GUIs have meters, and those almost always
                 // need a redraw. Later we can try to be a bit more sophisticated about this. Without
                 // this we would also have a blank GUI when it gets first opened because most DAWs open
@@ -134,6 +151,15 @@ where
         true
     }
 
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        self.egui_state.size.store((width, height));
+        if self.egui_state.is_open() {
+            self.resize_window.store(true, Ordering::Release);
+        }
+
+        true
+    }
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
         // As mentioned above, for now we'll always force a redraw to allow meter widgets to work
         // correctly. In the future we can use an `Arc<AtomicBool>` and only force a redraw when
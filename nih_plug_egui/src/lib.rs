@@ -57,6 +57,8 @@ where
         scaling_factor: AtomicCell::new(None),
         #[cfg(not(target_os = "macos"))]
         scaling_factor: AtomicCell::new(Some(1.0)),
+
+        resize_window: Arc::new(AtomicBool::new(false)),
     }))
 }
 
@@ -1,13 +1,22 @@
 //! A simple generic UI widget that renders all parameters in a [`Params`] object as a scrollable
-//! list of sliders and labels.
+//! list of sliders and labels, optionally split up into one page per parameter group.
 
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
 use egui_baseview::egui::{self, TextStyle, Ui, Vec2};
+use nih_plug::params::param_pages;
 use nih_plug::prelude::{Param, ParamFlags, ParamPtr, ParamSetter, Params};
 
 use super::ParamSlider;
 
+/// The tab label used for the page containing the parameters that are not part of any group.
+const UNGROUPED_PAGE_NAME: &str = "General";
+
+/// The base ID for storing a paged generic UI's current page. This is combined with the parent
+/// `Ui`'s ID and the address of the `Params` object so multiple paged generic UIs don't share their
+/// current page.
+static CURRENT_PAGE_MEMORY_ID: LazyLock<egui::Id> = LazyLock::new(|| egui::Id::new((file!(), 0)));
+
 /// A widget that can be used to create a generic UI with. This is used in conjuction with empty
 /// structs to emulate existential types.
 pub trait ParamWidget {
@@ -38,6 +47,67 @@ pub fn create(
     params: Arc<impl Params>,
     setter: &ParamSetter,
     widget: impl ParamWidget,
+) {
+    let param_ptrs = params
+        .param_map()
+        .into_iter()
+        .map(|(_, param_ptr, _)| param_ptr)
+        .filter(|param_ptr| !unsafe { param_ptr.flags() }.contains(ParamFlags::HIDE_IN_GENERIC_UI));
+
+    draw_param_list(ui, param_ptrs, setter, &widget);
+}
+
+/// Create a generic UI with a row of tabs that shows one page of parameters per top level
+/// parameter group, as in `#[nested(group = "...")]`. Parameters that are not part of any group are
+/// shown on the first page. The selected page's parameters are drawn as a scrollable list using the
+/// specified widget, just like in [`create()`]. Takes up all the remaining vertical space.
+pub fn create_paged(
+    ui: &mut Ui,
+    params: Arc<impl Params>,
+    setter: &ParamSetter,
+    widget: impl ParamWidget,
+) {
+    let pages = param_pages(params.as_ref());
+    if pages.is_empty() {
+        return;
+    }
+
+    let memory_id = CURRENT_PAGE_MEMORY_ID
+        .with(ui.id())
+        .with(Arc::as_ptr(&params) as *const () as usize);
+    let mut current_page = ui
+        .memory(|mem| mem.data.get_temp(memory_id))
+        .unwrap_or(0usize)
+        .min(pages.len() - 1);
+    ui.horizontal(|ui| {
+        for (page_idx, page) in pages.iter().enumerate() {
+            let label = if page.name.is_empty() {
+                UNGROUPED_PAGE_NAME
+            } else {
+                page.name.as_str()
+            };
+
+            ui.selectable_value(&mut current_page, page_idx, label);
+        }
+    });
+    ui.memory_mut(|mem| mem.data.insert_temp(memory_id, current_page));
+    ui.separator();
+
+    draw_param_list(
+        ui,
+        pages[current_page].params.iter().copied(),
+        setter,
+        &widget,
+    );
+}
+
+/// Draw a scrollable list of labels and widgets for `param_ptrs`. Takes up all the remaining
+/// vertical space.
+fn draw_param_list(
+    ui: &mut Ui,
+    param_ptrs: impl Iterator<Item = ParamPtr>,
+    setter: &ParamSetter,
+    widget: &impl ParamWidget,
 ) {
     let padding = Vec2::splat(ui.text_style_height(&TextStyle::Body) * 0.2);
     egui::containers::ScrollArea::vertical()
@@ -45,12 +115,7 @@ pub fn create(
         .auto_shrink([false, false])
         .show(ui, |ui| {
             let mut first_widget = true;
-            for (_, param_ptr, _) in params.param_map().into_iter() {
-                let flags = unsafe { param_ptr.flags() };
-                if flags.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
-                    continue;
-                }
-
+            for param_ptr in param_ptrs {
                 // This list looks weird without a little padding
                 if !first_widget {
                     ui.allocate_space(padding);
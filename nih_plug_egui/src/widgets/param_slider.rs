@@ -4,7 +4,7 @@ use egui_baseview::egui::{
     self, emath, vec2, Key, Response, Sense, Stroke, TextEdit, TextStyle, Ui, Vec2, Widget,
     WidgetText,
 };
-use nih_plug::prelude::{Param, ParamSetter};
+use nih_plug::prelude::{Param, ParamFlags, ParamSetter};
 use parking_lot::Mutex;
 
 use super::util;
@@ -220,10 +220,17 @@ impl<'a, P: Param> ParamSlider<'a, P> {
             ui.painter()
                 .rect_filled(response.rect, 0.0, ui.visuals().widgets.inactive.bg_fill);
 
-            let filled_proportion = self.normalized_value();
-            if filled_proportion > 0.0 {
+            // Bipolar parameters are filled from the center instead of from the left
+            let current_value = self.normalized_value();
+            let (fill_start, fill_end) = if self.param.flags().contains(ParamFlags::BIPOLAR) {
+                (current_value.min(0.5), current_value.max(0.5))
+            } else {
+                (0.0, current_value)
+            };
+            if fill_end - fill_start > 0.0 {
                 let mut filled_rect = response.rect;
-                filled_rect.set_width(response.rect.width() * filled_proportion);
+                filled_rect.set_left(response.rect.left() + (response.rect.width() * fill_start));
+                filled_rect.set_width(response.rect.width() * (fill_end - fill_start));
                 let filled_bg = if response.dragged() {
                     util::add_hsv(ui.visuals().selection.bg_fill, 0.0, -0.1, 0.1)
                 } else {
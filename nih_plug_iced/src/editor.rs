@@ -134,6 +134,9 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
         true
     }
 
+    // NOTE: `set_size()` is not implemented since `iced_baseview` doesn't have a way to resize the
+    //       embedded window, so `GuiContext::request_resize_to()` is not supported for iced editors
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
         // If there's already a paramter change notification in the channel then we don't need
         // to do anything else. This avoids queueing up redundant GUI redraws.
@@ -30,6 +30,10 @@ pub(crate) struct ViziaEditor {
     /// to compute a property in an event handler. Like when positioning an element based on the
     /// display value's width.
     pub(crate) emit_parameters_changed_event: Arc<AtomicBool>,
+    /// Whether to emit a [`GuiContextEvent::Resize`][widgets::GuiContextEvent::Resize] event during
+    /// the next idle callback. This is set in the `set_size()` implementation so the open window
+    /// picks up the new user scale factor.
+    pub(crate) emit_resize_event: Arc<AtomicBool>,
 }
 
 impl Editor for ViziaEditor {
@@ -76,6 +80,7 @@ impl Editor for ViziaEditor {
                     current_inner_window_size.width,
                     current_inner_window_size.height,
                 )),
+                last_size_preset: AtomicCell::new(vizia_state.size_preset()),
             }
             .build(cx);
 
@@ -94,7 +99,18 @@ impl Editor for ViziaEditor {
         })
         .on_idle({
             let emit_parameters_changed_event = self.emit_parameters_changed_event.clone();
+            let emit_resize_event = self.emit_resize_event.clone();
             move |cx| {
+                if emit_resize_event
+                    .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    cx.emit_custom(
+                        Event::new(widgets::GuiContextEvent::Resize)
+                            .propagate(Propagation::Subtree),
+                    );
+                }
+
                 if emit_parameters_changed_event
                     .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
                     .is_ok()
@@ -140,6 +156,26 @@ impl Editor for ViziaEditor {
         true
     }
 
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        // The window's unscaled size is computed from the `ViziaState`, so just like with the
+        // `ResizeHandle` widget the editor is resized by changing the user scale factor. If the
+        // aspect ratio doesn't match, then the editor is fit within the requested size.
+        let (unscaled_width, unscaled_height) = self.vizia_state.inner_logical_size();
+        if unscaled_width == 0 || unscaled_height == 0 {
+            return false;
+        }
+
+        let scale_factor = (width as f64 / unscaled_width as f64)
+            .min(height as f64 / unscaled_height as f64)
+            .max(0.5);
+        self.vizia_state.scale_factor.store(scale_factor);
+        if self.vizia_state.is_open() {
+            self.emit_resize_event.store(true, Ordering::Release);
+        }
+
+        true
+    }
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
         // This will cause a future idle callback to send a parameters changed event.
         // NOTE: We could add an event containing the parameter's ID and the normalized value, but
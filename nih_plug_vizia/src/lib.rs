@@ -63,6 +63,7 @@ where
         scaling_factor: AtomicCell::new(Some(1.0)),
 
         emit_parameters_changed_event: Arc::new(AtomicBool::new(false)),
+        emit_resize_event: Arc::new(AtomicBool::new(false)),
     }))
 }
 
@@ -88,9 +89,18 @@ pub enum ViziaTheming {
 #[derive(Serialize, Deserialize)]
 pub struct ViziaState {
     /// A function that returns the window's current size in logical pixels, before any sort of
-    /// scaling is applied. This size can be computed based on the plugin's current state.
+    /// scaling is applied. This size can be computed based on the plugin's current state. This is
+    /// not used when the editor has size presets.
     #[serde(skip, default = "empty_size_fn")]
     size_fn: Box<dyn Fn() -> (u32, u32) + Send + Sync>,
+    /// The editor's named layouts and their sizes when it was created using
+    /// [`new_with_size_presets()`][Self::new_with_size_presets()]. Empty otherwise.
+    #[serde(skip)]
+    size_presets: Vec<SizePreset>,
+    /// The index of the active preset in `size_presets`. This is persisted together with the scale
+    /// factor.
+    #[serde(with = "nih_plug::params::persist::serialize_atomic_cell", default)]
+    size_preset: AtomicCell<usize>,
     /// A scale factor that should be applied to `size` separate from from any system HiDPI scaling.
     /// This can be used to allow GUIs to be scaled uniformly.
     #[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]
@@ -100,6 +110,31 @@ pub struct ViziaState {
     open: AtomicBool,
 }
 
+/// One of the layouts for an editor created with
+/// [`ViziaState::new_with_size_presets()`]. For example, a compact layout and a full layout that
+/// shows an additional analyzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizePreset {
+    /// A human readable name for the layout. This can be shown in the GUI when offering a choice
+    /// between the different layouts.
+    pub name: &'static str,
+    /// The editor's width in logical pixels, before the user scale factor is applied.
+    pub width: u32,
+    /// The editor's height in logical pixels, before the user scale factor is applied.
+    pub height: u32,
+}
+
+impl SizePreset {
+    /// Create a new size preset.
+    pub const fn new(name: &'static str, width: u32, height: u32) -> Self {
+        Self {
+            name,
+            width,
+            height,
+        }
+    }
+}
+
 /// A default implementation for `size_fn` needed to be able to derive the `Deserialize` trait.
 fn empty_size_fn() -> Box<dyn Fn() -> (u32, u32) + Send + Sync> {
     Box::new(|| (0, 0))
@@ -111,6 +146,8 @@ impl Debug for ViziaState {
 
         f.debug_struct("ViziaState")
             .field("size_fn", &format!("<fn> ({}, {})", width, height))
+            .field("size_presets", &self.size_presets)
+            .field("size_preset", &self.size_preset)
             .field("scale_factor", &self.scale_factor)
             .field("open", &self.open)
             .finish()
@@ -120,6 +157,13 @@ impl Debug for ViziaState {
 impl<'a> PersistentField<'a, ViziaState> for Arc<ViziaState> {
     fn set(&self, new_value: ViziaState) {
         self.scale_factor.store(new_value.scale_factor.load());
+
+        // The presets themselves are not persisted, so the index may be out of bounds if the plugin
+        // removed some of its presets since the state was saved
+        let size_preset = new_value.size_preset.load();
+        if size_preset < self.size_presets.len() {
+            self.size_preset.store(size_preset);
+        }
     }
 
     fn map<F, R>(&self, f: F) -> R
@@ -138,6 +182,8 @@ impl ViziaState {
     pub fn new(size_fn: impl Fn() -> (u32, u32) + Send + Sync + 'static) -> Arc<ViziaState> {
         Arc::new(ViziaState {
             size_fn: Box::new(size_fn),
+            size_presets: Vec::new(),
+            size_preset: AtomicCell::new(0),
             scale_factor: AtomicCell::new(1.0),
             open: AtomicBool::new(false),
         })
@@ -152,11 +198,35 @@ impl ViziaState {
     ) -> Arc<ViziaState> {
         Arc::new(ViziaState {
             size_fn: Box::new(size_fn),
+            size_presets: Vec::new(),
+            size_preset: AtomicCell::new(0),
             scale_factor: AtomicCell::new(default_scale_factor),
             open: AtomicBool::new(false),
         })
     }
 
+    /// Initialize the GUI's state for an editor that offers multiple layouts with different sizes,
+    /// like a compact layout and a full layout. The first preset is used by default. The active
+    /// preset can be changed from the GUI using
+    /// [`GuiContextEvent::SetSizePreset`][widgets::GuiContextEvent::SetSizePreset], which also
+    /// negotiates the new size with the host. The active preset is persisted as part of this state
+    /// object, so the layout is restored together with the plugin's state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `presets` is empty.
+    pub fn new_with_size_presets(presets: Vec<SizePreset>) -> Arc<ViziaState> {
+        assert!(!presets.is_empty(), "At least one size preset is required");
+
+        Arc::new(ViziaState {
+            size_fn: empty_size_fn(),
+            size_presets: presets,
+            size_preset: AtomicCell::new(0),
+            scale_factor: AtomicCell::new(1.0),
+            open: AtomicBool::new(false),
+        })
+    }
+
     /// Returns a `(width, height)` pair for the current size of the GUI in logical pixels, after
     /// applying the user scale factor.
     pub fn scaled_logical_size(&self) -> (u32, u32) {
@@ -172,7 +242,22 @@ impl ViziaState {
     /// Returns a `(width, height)` pair for the current size of the GUI in logical pixels before
     /// applying the user scale factor.
     pub fn inner_logical_size(&self) -> (u32, u32) {
-        (self.size_fn)()
+        match self.size_presets.get(self.size_preset.load()) {
+            Some(preset) => (preset.width, preset.height),
+            None => (self.size_fn)(),
+        }
+    }
+
+    /// The editor's size presets if it was created using
+    /// [`new_with_size_presets()`][Self::new_with_size_presets()]. Empty otherwise.
+    pub fn size_presets(&self) -> &[SizePreset] {
+        &self.size_presets
+    }
+
+    /// The index of the active preset in [`size_presets()`][Self::size_presets()]. This is always 0
+    /// if the editor does not have any size presets.
+    pub fn size_preset(&self) -> usize {
+        self.size_preset.load()
     }
 
     /// Get the non-DPI related uniform scaling factor the GUI's size will be multiplied with. This
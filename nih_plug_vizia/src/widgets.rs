@@ -13,19 +13,23 @@ use vizia::prelude::*;
 
 use super::ViziaState;
 
+mod freeze_button;
 mod generic_ui;
 pub mod param_base;
 mod param_button;
 mod param_slider;
 mod peak_meter;
 mod resize_handle;
+mod toggle_button;
 pub mod util;
 
-pub use generic_ui::GenericUi;
+pub use freeze_button::FreezeButton;
+pub use generic_ui::{GenericUi, GroupedGenericUi, PagedGenericUi};
 pub use param_button::{ParamButton, ParamButtonExt};
 pub use param_slider::{ParamSlider, ParamSliderExt, ParamSliderStyle};
 pub use peak_meter::PeakMeter;
 pub use resize_handle::ResizeHandle;
+pub use toggle_button::ToggleButton;
 
 /// Register the default theme for the widgets exported by this module. This is automatically called
 /// for you when using [`create_vizia_editor()`][super::create_vizia_editor()].
@@ -106,6 +110,11 @@ pub enum GuiContextEvent {
     /// }
     /// ```
     Resize,
+    /// Switch to another one of the editor's size presets and resize the window to match. This
+    /// takes an index into [`ViziaState::size_presets()`], and it only works for editors created
+    /// using [`ViziaState::new_with_size_presets()`]. If the host rejects the new size, then the
+    /// previous preset is restored.
+    SetSizePreset(usize),
 }
 
 /// Handles parameter updates for VIZIA GUIs. Registered in
@@ -124,6 +133,9 @@ pub(crate) struct WindowModel {
     /// The last known unscaled logical window size. Used to prevent sending duplicate resize
     /// requests.
     pub last_inner_window_size: AtomicCell<(u32, u32)>,
+    /// The size preset that was active for `last_inner_window_size`. Used to restore the previous
+    /// preset if the host rejects a new size.
+    pub last_size_preset: AtomicCell<usize>,
 }
 
 impl Model for ParamModel {
@@ -149,9 +161,29 @@ impl Model for WindowModel {
         event.map(|gui_context_event, meta| match gui_context_event {
             GuiContextEvent::Resize => {
                 // This will trigger a `WindowEvent::GeometryChanged`, which in turn causes the
-                // handler below this to be fired
+                // handler below this to be fired. The user scale factor is only changed outside of
+                // vizia when the editor is resized using `GuiContext::request_resize_to()`.
                 let (width, height) = self.vizia_state.inner_logical_size();
                 cx.set_window_size(WindowSize { width, height });
+                cx.set_user_scale_factor(self.vizia_state.user_scale_factor());
+
+                meta.consume();
+            }
+            GuiContextEvent::SetSizePreset(size_preset) => {
+                let size_preset = *size_preset;
+                if size_preset < self.vizia_state.size_presets.len() {
+                    // This works the same way as `GuiContextEvent::Resize`, with the size being
+                    // determined by the active preset instead of by the size function
+                    self.vizia_state.size_preset.store(size_preset);
+                    let (width, height) = self.vizia_state.inner_logical_size();
+                    cx.set_window_size(WindowSize { width, height });
+                } else {
+                    nih_debug_assert_failure!(
+                        "Tried to switch to size preset {size_preset}, but the editor only has {} \
+                         size presets",
+                        self.vizia_state.size_presets.len()
+                    );
+                }
 
                 meta.consume();
             }
@@ -171,6 +203,9 @@ impl Model for WindowModel {
                 );
                 let old_logical_size @ (old_logical_width, old_logical_height) =
                     self.last_inner_window_size.load();
+                let old_size_preset = self
+                    .last_size_preset
+                    .swap(self.vizia_state.size_preset.load());
                 let scale_factor = cx.user_scale_factor();
                 let old_user_scale_factor = self.vizia_state.scale_factor.load();
 
@@ -186,6 +221,8 @@ impl Model for WindowModel {
                 self.vizia_state.scale_factor.store(scale_factor);
                 if !self.context.request_resize() {
                     self.last_inner_window_size.store(old_logical_size);
+                    self.last_size_preset.store(old_size_preset);
+                    self.vizia_state.size_preset.store(old_size_preset);
                     self.vizia_state.scale_factor.store(old_user_scale_factor);
 
                     // This will cause the window's size to be reverted on the next event loop
@@ -0,0 +1,29 @@
+//! A toggle button for freezing analyzers and other visualizations.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use vizia::prelude::*;
+
+use super::ToggleButton;
+
+/// A [`ToggleButton`] that freezes a spectrum analyzer or any other visualization that gets its
+/// data from the audio thread. The plugin should check the `Arc<AtomicBool>` on the audio thread
+/// and stop publishing new data while it is set, so the last published frame stays on screen. That
+/// way freezing the display doesn't require any locking. The `:checked` pseudoclass indicates
+/// whether or not the display is currently frozen.
+pub struct FreezeButton;
+
+impl FreezeButton {
+    /// Creates a new freeze button bound to an `Arc<AtomicBool>`. The label is usually just
+    /// "Freeze".
+    pub fn new<L, T>(cx: &mut Context, lens: L, label: impl Res<T> + Clone) -> Handle<ToggleButton>
+    where
+        L: Lens<Target = Arc<AtomicBool>>,
+        T: ToString,
+    {
+        ToggleButton::new(cx, lens, label)
+            // This makes it possible to assign a unique color to freeze buttons
+            .class("freeze")
+    }
+}
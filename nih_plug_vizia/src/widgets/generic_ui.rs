@@ -1,10 +1,14 @@
 //! Generic UIs for NIH-plug using VIZIA.
 
+use nih_plug::params::param_pages;
 use nih_plug::prelude::{ParamFlags, ParamPtr, Params};
 use vizia::prelude::*;
 
 use super::{ParamSlider, ParamSliderExt, ParamSliderStyle};
 
+/// The tab and section label used for the parameters that are not part of any group.
+const UNGROUPED_PAGE_NAME: &str = "General";
+
 /// Shows a generic UI for a [`Params`] object. For additional flexibility you can either use the
 /// [`new()`][`Self::new()`] method to have the generic UI decide which widget to use for your
 /// parameters, or you can use the [`new_custom()`][`Self::new_custom()`] method to determine this
@@ -31,13 +35,7 @@ impl GenericUi {
     {
         // Basic styling is done in the `theme.css` style sheet
         Self::new_custom(cx, params, move |cx, param_ptr| {
-            HStack::new(cx, |cx| {
-                // Align this on the right
-                Label::new(cx, unsafe { param_ptr.name() }).class("label");
-
-                Self::draw_widget(cx, params, param_ptr);
-            })
-            .class("row");
+            Self::draw_row(cx, params, param_ptr)
         })
     }
 
@@ -69,6 +67,22 @@ impl GenericUi {
         })
     }
 
+    /// Draw a row containing the parameter's name and the standard widget for it.
+    fn draw_row<L, PsRef, Ps>(cx: &mut Context, params: L, param_ptr: ParamPtr)
+    where
+        L: Lens<Target = PsRef>,
+        PsRef: AsRef<Ps> + 'static,
+        Ps: Params + 'static,
+    {
+        HStack::new(cx, |cx| {
+            // Align this on the right
+            Label::new(cx, unsafe { param_ptr.name() }).class("label");
+
+            Self::draw_widget(cx, params, param_ptr);
+        })
+        .class("row");
+    }
+
     /// The standard widget drawing function. This can be used together with `.new_custom()` to only
     /// draw the labels differently.
     pub fn draw_widget<L, PsRef, Ps>(cx: &mut Context, params: L, param_ptr: ParamPtr)
@@ -106,3 +120,209 @@ impl View for GenericUi {
         Some("generic-ui")
     }
 }
+
+/// Shows a generic UI for a [`Params`] object with one page per top level parameter group, as in
+/// `#[nested(group = "...")]`. A row of tabs at the top switches between the pages, and parameters
+/// that are not part of any group are shown on the first page. Each page is drawn the same way as
+/// [`GenericUi::new()`] would draw it, so it can also be wrapped in a [`ScrollView`] for plugins
+/// with longer parameter lists. The `:checked` pseudoclass on the tabs indicates which page is
+/// currently shown.
+#[derive(Lens)]
+pub struct PagedGenericUi {
+    /// The index of the page that's currently shown.
+    current_page: usize,
+}
+
+enum PagedGenericUiEvent {
+    /// Switch to the page with this index.
+    SetPage(usize),
+}
+
+/// A tab for selecting one of a [`PagedGenericUi`]'s pages.
+struct PageTab {
+    page_idx: usize,
+}
+
+/// Shows a generic UI for a [`Params`] object with one collapsible section per top level parameter
+/// group, as in `#[nested(group = "...")]`. This uses the same grouping as [`PagedGenericUi`], but
+/// all groups are shown below each other in declaration order instead of on separate pages.
+/// Parameters that are not part of any group are shown in the first section. Clicking on a
+/// section's header collapses or expands that section, and the `:checked` pseudoclass on the
+/// headers indicates which sections are expanded. Wrap this in a [`ScrollView`] for plugins with
+/// longer parameter lists.
+pub struct GroupedGenericUi;
+
+/// A single collapsible section in a [`GroupedGenericUi`].
+#[derive(Lens)]
+struct GroupSection {
+    /// Whether the section's parameters are currently hidden.
+    collapsed: bool,
+}
+
+enum GroupSectionEvent {
+    /// Collapse the section if it's expanded, or expand it if it's collapsed.
+    ToggleCollapsed,
+}
+
+/// The header for a [`GroupSection`]. Clicking on this toggles the section.
+struct GroupHeader;
+
+impl PagedGenericUi {
+    /// Creates a new [`PagedGenericUi`] for all provided parameters.
+    pub fn new<L, PsRef, Ps>(cx: &mut Context, params: L) -> Handle<'_, PagedGenericUi>
+    where
+        L: Lens<Target = PsRef> + Clone,
+        PsRef: AsRef<Ps> + 'static,
+        Ps: Params + 'static,
+    {
+        let pages = params.map(|params| param_pages(params.as_ref())).get(cx);
+
+        Self { current_page: 0 }.build(cx, move |cx| {
+            HStack::new(cx, |cx| {
+                for (page_idx, page) in pages.iter().enumerate() {
+                    let label = if page.name.is_empty() {
+                        UNGROUPED_PAGE_NAME
+                    } else {
+                        page.name.as_str()
+                    };
+
+                    PageTab { page_idx }
+                        .build(cx, |cx| {
+                            Label::new(cx, label).hoverable(false);
+                        })
+                        .checked(
+                            PagedGenericUi::current_page
+                                .map(move |current_page| *current_page == page_idx),
+                        )
+                        .class("page-tab");
+                }
+            })
+            .class("tabs");
+
+            Binding::new(cx, PagedGenericUi::current_page, move |cx, current_page| {
+                if let Some(page) = pages.get(current_page.get(cx)) {
+                    GenericUi.build(cx, |cx| {
+                        for &param_ptr in &page.params {
+                            GenericUi::draw_row(cx, params, param_ptr);
+                        }
+                    });
+                }
+            });
+        })
+    }
+}
+
+impl GroupedGenericUi {
+    /// Creates a new [`GroupedGenericUi`] for all provided parameters.
+    pub fn new<L, PsRef, Ps>(cx: &mut Context, params: L) -> Handle<'_, GroupedGenericUi>
+    where
+        L: Lens<Target = PsRef> + Clone,
+        PsRef: AsRef<Ps> + 'static,
+        Ps: Params + 'static,
+    {
+        let pages = params.map(|params| param_pages(params.as_ref())).get(cx);
+
+        Self.build(cx, move |cx| {
+            for page in pages {
+                let label = if page.name.is_empty() {
+                    String::from(UNGROUPED_PAGE_NAME)
+                } else {
+                    page.name.clone()
+                };
+
+                GroupSection { collapsed: false }
+                    .build(cx, move |cx| {
+                        GroupHeader
+                            .build(cx, |cx| {
+                                Label::new(cx, label.as_str()).hoverable(false);
+                            })
+                            .checked(GroupSection::collapsed.map(|collapsed| !*collapsed))
+                            .class("group-header");
+
+                        Binding::new(cx, GroupSection::collapsed, move |cx, collapsed| {
+                            if !collapsed.get(cx) {
+                                GenericUi.build(cx, |cx| {
+                                    for &param_ptr in &page.params {
+                                        GenericUi::draw_row(cx, params, param_ptr);
+                                    }
+                                });
+                            }
+                        });
+                    })
+                    .class("group");
+            }
+        })
+    }
+}
+
+impl View for PagedGenericUi {
+    fn element(&self) -> Option<&'static str> {
+        Some("paged-generic-ui")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(
+            |paged_generic_ui_event, meta| match *paged_generic_ui_event {
+                PagedGenericUiEvent::SetPage(page_idx) => {
+                    self.current_page = page_idx;
+
+                    meta.consume();
+                }
+            },
+        );
+    }
+}
+
+impl View for PageTab {
+    fn element(&self) -> Option<&'static str> {
+        // This reuses the param-button styling
+        Some("param-button")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.emit(PagedGenericUiEvent::SetPage(self.page_idx));
+
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
+
+impl View for GroupedGenericUi {
+    fn element(&self) -> Option<&'static str> {
+        Some("grouped-generic-ui")
+    }
+}
+
+impl View for GroupSection {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|group_section_event, meta| match group_section_event {
+            GroupSectionEvent::ToggleCollapsed => {
+                self.collapsed = !self.collapsed;
+
+                meta.consume();
+            }
+        });
+    }
+}
+
+impl View for GroupHeader {
+    fn element(&self) -> Option<&'static str> {
+        // This reuses the param-button styling, just like the paged generic UI's tabs
+        Some("param-button")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.emit(GroupSectionEvent::ToggleCollapsed);
+
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
@@ -1,6 +1,6 @@
 //! A slider that integrates with NIH-plug's [`Param`] types.
 
-use nih_plug::prelude::Param;
+use nih_plug::prelude::{Param, ParamFlags};
 use vizia::prelude::*;
 
 use super::param_base::ParamWidgetBase;
@@ -45,7 +45,8 @@ pub struct ParamSlider {
 pub enum ParamSliderStyle {
     /// Visualize the offset from the default value for continuous parameters with a default value
     /// at around half of its range, fill the bar from the left for discrete parameters and
-    /// continuous parameters without centered default values.
+    /// continuous parameters without centered default values. Parameters marked as bipolar using
+    /// [`ParamFlags::BIPOLAR`] are always filled from the mid point.
     Centered,
     /// Always fill the bar starting from the left.
     FromLeft,
@@ -318,6 +319,15 @@ impl ParamSlider {
         param: &P,
         current_value: f32,
     ) -> (f32, f32) {
+        // Bipolar parameters are always centered around the mid point, regardless of their default
+        // value
+        let style =
+            if style == ParamSliderStyle::Centered && param.flags().contains(ParamFlags::BIPOLAR) {
+                ParamSliderStyle::FromMidPoint
+            } else {
+                style
+            };
+
         let default_value = param.default_normalized_value();
         let step_count = param.step_count();
         let draw_fill_from_default = matches!(style, ParamSliderStyle::Centered)
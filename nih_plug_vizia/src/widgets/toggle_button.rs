@@ -0,0 +1,53 @@
+//! A toggle button for editor-only settings.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use vizia::prelude::*;
+
+/// A toggleable button for settings that are not parameters, like editor-only display options.
+/// This toggles an `Arc<AtomicBool>` that can be shared with the plugin, so the audio thread can
+/// also read the setting without any locking. This looks the same as a
+/// [`ParamButton`][super::ParamButton], and the `:checked` pseudoclass indicates whether or not
+/// the setting is currently enabled.
+pub struct ToggleButton {
+    value: Arc<AtomicBool>,
+}
+
+impl ToggleButton {
+    /// Creates a new [`ToggleButton`] bound to an `Arc<AtomicBool>`.
+    pub fn new<L, T>(cx: &mut Context, lens: L, label: impl Res<T> + Clone) -> Handle<Self>
+    where
+        L: Lens<Target = Arc<AtomicBool>>,
+        T: ToString,
+    {
+        Self {
+            value: lens.get(cx),
+        }
+        .build(cx, |cx| {
+            Label::new(cx, label).hoverable(false);
+        })
+        .checked(lens.map(|value| value.load(Ordering::Relaxed)))
+    }
+}
+
+impl View for ToggleButton {
+    fn element(&self) -> Option<&'static str> {
+        // This reuses the param-button styling
+        Some("param-button")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            // We don't need special double and triple click handling
+            WindowEvent::MouseDown(MouseButton::Left)
+            | WindowEvent::MouseDoubleClick(MouseButton::Left)
+            | WindowEvent::MouseTripleClick(MouseButton::Left) => {
+                self.value.fetch_xor(true, Ordering::Relaxed);
+
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
@@ -1,9 +1,12 @@
 use anyhow::Context;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -14,6 +17,13 @@ mod util;
 /// Re-export for the main function.
 pub use anyhow::Result;
 
+/// The environment variable NIH-plug's `nih_build_info!()` macro reads the git commit hash from.
+const GIT_HASH_ENV_VAR: &str = "NIH_PLUG_GIT_HASH";
+/// The environment variable NIH-plug's `nih_build_info!()` macro reads the build date from.
+const BUILD_DATE_ENV_VAR: &str = "NIH_PLUG_BUILD_DATE";
+/// The target triple used for `bundle-web` builds.
+const WEB_TARGET: &str = "wasm32-unknown-unknown";
+
 fn build_usage_string(command_name: &str) -> String {
     format!(
         "Usage:
@@ -23,6 +33,12 @@ fn build_usage_string(command_name: &str) -> String {
   {command_name} bundle-universal <package> [--release]  (macOS only)
   {command_name} bundle-universal -p <package1> -p <package2> ... [--release]  (macOS only)
 
+  {command_name} bundle-web <package> [--release]
+  {command_name} bundle-web -p <package1> -p <package2> ... [--release]
+
+  {command_name} validate <package>
+  {command_name} validate -p <package1> -p <package2> ...
+
   All other 'cargo build' options are supported, including '--target' and '--profile'."
     )
 }
@@ -34,6 +50,12 @@ type BundlerConfig = HashMap<String, PackageConfig>;
 #[derive(Debug, Clone, Deserialize)]
 struct PackageConfig {
     name: Option<String>,
+    /// The reverse-DNS identifier used for macOS bundles. Defaults to `com.nih-plug.<package>`.
+    identifier: Option<String>,
+    /// The version used for macOS bundles. Defaults to the package's version from `Cargo.toml`.
+    version: Option<String>,
+    /// The four character creator code used for macOS bundles. Defaults to `????`.
+    signature: Option<String>,
 }
 
 /// The target we're generating a plugin for. This can be either the native target or a cross
@@ -65,6 +87,24 @@ pub enum BundleType {
     Binary,
 }
 
+/// The metadata written to a macOS bundle's `Info.plist` and `PkgInfo` files. The values for a
+/// package can be set in `bundler.toml`. See [`BundleMetadata::for_package()`].
+#[derive(Debug, Clone)]
+pub struct BundleMetadata {
+    /// The bundle's `CFBundleIdentifier`, e.g. `com.example.my-plugin`.
+    pub identifier: String,
+    /// The bundle's version, used for both `CFBundleShortVersionString` and `CFBundleVersion`.
+    pub version: String,
+    /// The bundle's four character `CFBundleSignature`.
+    pub signature: String,
+}
+
+/// Collects the messages printed while bundling a package. Packages are bundled in parallel, so
+/// these messages are only written to STDERR once the package has been bundled to prevent the
+/// output for different packages from getting interleaved.
+#[derive(Debug, Default)]
+struct BundleLog(String);
+
 /// The main xtask entry point function. See the readme for instructions on how to use this.
 pub fn main() -> Result<()> {
     let args = std::env::args().skip(1);
@@ -97,13 +137,7 @@ pub fn main_with_args(command_name: &str, args: impl IntoIterator<Item = String>
 
             // As explained above, for efficiency's sake this is a two step process
             build(&packages, &other_args)?;
-
-            bundle(target_dir, &packages[0], &other_args, false)?;
-            for package in packages.into_iter().skip(1) {
-                bundle(target_dir, &package, &other_args, false)?;
-            }
-
-            Ok(())
+            bundle_all(target_dir, &packages, &other_args, false)
         }
         "bundle-universal" => {
             // The same as `--bundle`, but builds universal binaries for macOS Cargo will also error
@@ -136,13 +170,39 @@ pub fn main_with_args(command_name: &str, args: impl IntoIterator<Item = String>
 
             // This `true` indicates a universal build. This will cause the two sets of built
             // binaries to beq lipo'd together into universal binaries before bundling
-            bundle(target_dir, &packages[0], &other_args, true)?;
-            for package in packages.into_iter().skip(1) {
-                bundle(target_dir, &package, &other_args, true)?;
+            bundle_all(target_dir, &packages, &other_args, true)
+        }
+        "bundle-web" => {
+            // Web builds always use the same target, so any other target would be a mistake
+            let (packages, other_args) = split_bundle_args(args, &usage_string)?;
+            for arg in &other_args {
+                if arg == "--target" || arg.starts_with("--target=") {
+                    anyhow::bail!(
+                        "'{command_name} bundle-web' is incompatible with the '{arg}' option."
+                    )
+                }
+            }
+
+            let mut web_args = other_args.clone();
+            web_args.push(format!("--target={WEB_TARGET}"));
+            build(&packages, &web_args)?;
+
+            for package in &packages {
+                bundle_web(target_dir, package, &other_args)?;
             }
 
             Ok(())
         }
+        "validate" => {
+            // This validates the bundles created by a previous `bundle` command, so this doesn't
+            // build anything by itself
+            let (packages, other_args) = split_bundle_args(args, &usage_string)?;
+            if let Some(arg) = other_args.first() {
+                anyhow::bail!("Unexpected argument '{arg}'\n\n{usage_string}");
+            }
+
+            validate(target_dir, &packages)
+        }
         // This is only meant to be used by the CI, since using awk for this can be a bit spotty on
         // macOS
         "known-packages" => list_known_packages(),
@@ -186,10 +246,22 @@ pub fn chdir_workspace_root() -> Result<()> {
 /// Build one or more packages using the provided `cargo build` arguments. This should be called
 /// before calling [`bundle()`]. This requires the current working directory to have been set to
 /// the workspace's root using [`chdir_workspace_root()`].
+///
+/// The `NIH_PLUG_GIT_HASH` and `NIH_PLUG_BUILD_DATE` environment variables are set for the build
+/// so the plugins can access this information through NIH-plug's `nih_build_info!()` macro. These
+/// variables are left alone if they have already been set.
 pub fn build(packages: &[String], args: &[String]) -> Result<()> {
     let package_args = packages.iter().flat_map(|package| ["-p", package]);
 
-    let status = Command::new("cargo")
+    let mut command = Command::new("cargo");
+    if let Some(git_hash) = build_git_hash() {
+        command.env(GIT_HASH_ENV_VAR, git_hash);
+    }
+    if std::env::var_os(BUILD_DATE_ENV_VAR).is_none() {
+        command.env(BUILD_DATE_ENV_VAR, util::build_date());
+    }
+
+    let status = command
         .arg("build")
         .args(package_args)
         .args(args)
@@ -204,10 +276,11 @@ pub fn build(packages: &[String], args: &[String]) -> Result<()> {
 
 /// Bundle a package that was previously built by a call to [`build()`] using the provided `cargo
 /// build` arguments. These two functions are split up because building can be done in parallel by
-/// Cargo itself while bundling is sequential. Options from the `bundler.toml` file in the
-/// workspace's root are respected (see
-/// <https://github.com/robbert-vdh/nih-plug/blob/master/bundler.toml>). This requires the current
-/// working directory to have been set to the workspace's root using [`chdir_workspace_root()`].
+/// Cargo itself, while bundling happens per package. See [`bundle_all()`] for bundling multiple
+/// packages at once. Options from the `bundler.toml` file in the workspace's root are respected
+/// (see <https://github.com/robbert-vdh/nih-plug/blob/master/bundler.toml>). This requires the
+/// current working directory to have been set to the workspace's root using
+/// [`chdir_workspace_root()`].
 ///
 /// If the package also exposes a binary target in addition to a library (or just a binary, in case
 /// the binary target has a different name) then this will also be copied into the `bundled`
@@ -217,16 +290,96 @@ pub fn build(packages: &[String], args: &[String]) -> Result<()> {
 /// specified instead, then this will assume both `x86_64-apple-darwin` and `aarch64-apple-darwin`
 /// have been built and it will try to lipo those together instead.
 pub fn bundle(target_dir: &Path, package: &str, args: &[String], universal: bool) -> Result<()> {
-    let mut build_type_dir = "debug";
+    let bundler_config = load_bundler_config()?;
+
+    let mut log = BundleLog::default();
+    let result = bundle_package(
+        target_dir,
+        package,
+        args,
+        universal,
+        bundler_config.as_ref(),
+        &mut log,
+    );
+    log.flush();
+
+    result
+}
+
+/// The same as [`bundle()`], but for multiple packages at once. The packages are bundled in
+/// parallel since they are all written to their own bundles. The output for each package is
+/// printed in one go once that package has been bundled. If bundling fails for any of the packages,
+/// then the error for the first of those packages is returned after all other packages have been
+/// bundled.
+pub fn bundle_all(
+    target_dir: &Path,
+    packages: &[String],
+    args: &[String],
+    universal: bool,
+) -> Result<()> {
+    let bundler_config = load_bundler_config()?;
+    let num_threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(packages.len());
+
+    // The worker threads take the next package from the list until all of them have been bundled
+    let next_package_idx = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Result<()>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let package_idx = next_package_idx.fetch_add(1, Ordering::Relaxed);
+                        let Some(package) = packages.get(package_idx) else {
+                            break;
+                        };
+
+                        let mut log = BundleLog::default();
+                        let result = bundle_package(
+                            target_dir,
+                            package,
+                            args,
+                            universal,
+                            bundler_config.as_ref(),
+                            &mut log,
+                        )
+                        .with_context(|| format!("Could not bundle '{package}'"));
+                        log.flush();
+
+                        results.push((package_idx, result));
+                    }
+
+                    results
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Bundling thread panicked"))
+            .collect()
+    });
+
+    results.sort_by_key(|(package_idx, _)| *package_idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// The implementation for [`bundle()`]. Messages are written to `log` instead of directly to
+/// STDERR.
+fn bundle_package(
+    target_dir: &Path,
+    package: &str,
+    args: &[String],
+    universal: bool,
+    bundler_config: Option<&BundlerConfig>,
+    log: &mut BundleLog,
+) -> Result<()> {
+    let build_type_dir = build_type_dir(args)?;
     let mut cross_compile_target: Option<String> = None;
     for arg_idx in (0..args.len()).rev() {
         let arg = &args[arg_idx];
         match arg.as_str() {
-            "--profile" => {
-                // Since Rust 1.57 you can have custom profiles
-                build_type_dir = args.get(arg_idx + 1).context("Missing profile name")?;
-            }
-            "--release" => build_type_dir = "release",
             "--target" => {
                 // When cross compiling we should generate the correct bundle type
                 cross_compile_target = Some(
@@ -235,11 +388,6 @@ pub fn bundle(target_dir: &Path, package: &str, args: &[String], universal: bool
                         .to_owned(),
                 );
             }
-            arg if arg.starts_with("--profile=") => {
-                build_type_dir = arg
-                    .strip_prefix("--profile=")
-                    .context("Missing profile name")?;
-            }
             arg if arg.starts_with("--target=") => {
                 cross_compile_target = Some(
                     arg.strip_prefix("--target=")
@@ -282,13 +430,15 @@ pub fn bundle(target_dir: &Path, package: &str, args: &[String], universal: bool
             anyhow::bail!("Could not find built libraries for universal build.");
         }
 
-        eprintln!();
+        log.line("");
         if build_bin {
             bundle_binary(
                 target_dir,
                 package,
                 &[&x86_64_bin_path, &aarch64_bin_path],
                 CompilationTarget::MacOSUniversal,
+                bundler_config,
+                log,
             )?;
         }
         if build_lib {
@@ -297,6 +447,8 @@ pub fn bundle(target_dir: &Path, package: &str, args: &[String], universal: bool
                 package,
                 &[&x86_64_lib_path, &aarch64_lib_path],
                 CompilationTarget::MacOSUniversal,
+                bundler_config,
+                log,
             )?;
         }
     } else {
@@ -319,18 +471,140 @@ to your Cargo.toml file?"#,
             );
         }
 
-        eprintln!();
+        log.line("");
         if bin_path.exists() {
-            bundle_binary(target_dir, package, &[&bin_path], compilation_target)?;
+            bundle_binary(
+                target_dir,
+                package,
+                &[&bin_path],
+                compilation_target,
+                bundler_config,
+                log,
+            )?;
         }
         if lib_path.exists() {
-            bundle_plugin(target_dir, package, &[&lib_path], compilation_target)?;
+            bundle_plugin(
+                target_dir,
+                package,
+                &[&lib_path],
+                compilation_target,
+                bundler_config,
+                log,
+            )?;
         }
     }
 
     Ok(())
 }
 
+/// Bundle a package that was previously built for the `wasm32-unknown-unknown` target by a call to
+/// [`build()`] so it can be run in a web browser. This runs `wasm-bindgen` over the built
+/// WebAssembly module, and writes the resulting JavaScript glue code and module to
+/// `target/bundled/<name>.web` together with a minimal `index.html` file that loads them. Only
+/// packages with a binary target can be run this way, since the browser needs a standalone entry
+/// point to call. The `--release` and `--profile` options from `args` are respected. This requires
+/// the current working directory to have been set to the workspace's root using
+/// [`chdir_workspace_root()`].
+pub fn bundle_web(target_dir: &Path, package: &str, args: &[String]) -> Result<()> {
+    let build_type_dir = build_type_dir(args)?;
+    let target_base = target_base(target_dir, Some(WEB_TARGET))?.join(build_type_dir);
+
+    // The browser needs a standalone entry point to call. Plugin libraries are built as `cdylib`s,
+    // which also produce a WebAssembly module, but the VST3 and CLAP entry points are meaningless
+    // in a browser.
+    let package_targets = &package_metadata(package)?
+        .with_context(|| format!("Could not find the package '{package}'"))?
+        .targets;
+    let has_binary = package_targets
+        .iter()
+        .any(|target| target.name == package && target.kind.iter().any(|kind| kind == "bin"));
+    if !has_binary {
+        if package_targets
+            .iter()
+            .any(|target| target.kind.iter().any(|kind| kind == "cdylib"))
+        {
+            anyhow::bail!(
+                r#"'{package}' only exports a plugin library, which cannot be run in a browser.
+
+Hint: Add a binary target containing:
+
+fn main() {{
+    nih_export_standalone::<PluginName>();
+}}
+
+to your package to create a standalone application that can be bundled for the web."#
+            );
+        }
+
+        anyhow::bail!(
+            "'{package}' has neither a standalone binary target nor a plugin library target that \
+             can be bundled for the web."
+        );
+    }
+
+    // Unlike libraries, binary targets keep the dashes in their names
+    let bin_path = target_base.join(format!("{package}.wasm"));
+    if !bin_path.exists() {
+        anyhow::bail!(
+            "Could not find a built WebAssembly module for '{package}' at '{}'",
+            bin_path.display()
+        );
+    }
+
+    // Cargo will replace dashes with underscores
+    let module_name = package.replace('-', "_");
+    let bundle_name = bundle_name(package, load_bundler_config()?.as_ref())?;
+    let web_bundle_home = bundle_home(target_dir).join(format!("{bundle_name}.web"));
+    fs::create_dir_all(&web_bundle_home).context("Could not create web bundle directory")?;
+
+    let status = Command::new("wasm-bindgen")
+        .args([
+            "--target",
+            "web",
+            "--no-typescript",
+            "--out-name",
+            &module_name,
+        ])
+        .arg("--out-dir")
+        .arg(&web_bundle_home)
+        .arg(&bin_path)
+        .status()
+        .context(
+            "Could not run 'wasm-bindgen'. It can be installed using 'cargo install \
+             wasm-bindgen-cli'.",
+        )?;
+    if !status.success() {
+        anyhow::bail!("Could not run 'wasm-bindgen' on '{}'", bin_path.display());
+    }
+
+    fs::write(
+        web_bundle_home.join("index.html"),
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8" />
+    <title>{bundle_name}</title>
+  </head>
+  <body>
+    <script type="module">
+      import init from "./{module_name}.js";
+
+      init();
+    </script>
+  </body>
+</html>
+"#
+        ),
+    )
+    .context("Could not create index.html file")?;
+
+    eprintln!();
+    eprintln!("Created a web bundle at '{}'", web_bundle_home.display());
+
+    Ok(())
+}
+
 /// Bundle a standalone target. If `bin_path` contains more than one path, then the binaries will be
 /// combined into a single binary using a method that depends on the compilation target. For
 /// universal macOS builds this uses lipo.
@@ -339,12 +613,12 @@ fn bundle_binary(
     package: &str,
     bin_paths: &[&Path],
     compilation_target: CompilationTarget,
+    bundler_config: Option<&BundlerConfig>,
+    log: &mut BundleLog,
 ) -> Result<()> {
     let bundle_home_dir = bundle_home(target_dir);
-    let bundle_name = match load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
-        Some(PackageConfig { name: Some(name) }) => name,
-        _ => package.to_string(),
-    };
+    let bundle_name = bundle_name(package, bundler_config)?;
+    let bundle_metadata = BundleMetadata::from_config(package, bundler_config)?;
 
     // On MacOS the standalone target needs to be in a bundle
     let standalone_bundle_binary_name =
@@ -379,18 +653,18 @@ fn bundle_binary(
             .expect("Malformed standalone binary path"),
     );
     maybe_create_macos_bundle_metadata(
-        package,
+        &bundle_metadata,
         &bundle_name,
         &standalone_bundle_home,
         compilation_target,
         BundleType::Binary,
     )?;
-    maybe_codesign(&standalone_bundle_home, compilation_target);
+    codesign(&standalone_bundle_home, compilation_target, log);
 
-    eprintln!(
+    log.line(format_args!(
         "Created a standalone bundle at '{}'",
         standalone_bundle_home.display()
-    );
+    ));
 
     Ok(())
 }
@@ -403,12 +677,12 @@ fn bundle_plugin(
     package: &str,
     lib_paths: &[&Path],
     compilation_target: CompilationTarget,
+    bundler_config: Option<&BundlerConfig>,
+    log: &mut BundleLog,
 ) -> Result<()> {
     let bundle_home_dir = bundle_home(target_dir);
-    let bundle_name = match load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
-        Some(PackageConfig { name: Some(name) }) => name,
-        _ => package.to_string(),
-    };
+    let bundle_name = bundle_name(package, bundler_config)?;
+    let bundle_metadata = BundleMetadata::from_config(package, bundler_config)?;
 
     // We'll detect the plugin formats supported by the plugin binary and create bundled accordingly.
     // If `lib_path` contains paths to multiple plugins that need to be combined into a macOS
@@ -446,15 +720,18 @@ fn bundle_plugin(
                 .expect("Malformed CLAP library path"),
         );
         maybe_create_macos_bundle_metadata(
-            package,
+            &bundle_metadata,
             &bundle_name,
             &clap_bundle_home,
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(&clap_bundle_home, compilation_target);
+        codesign(&clap_bundle_home, compilation_target, log);
 
-        eprintln!("Created a CLAP bundle at '{}'", clap_bundle_home.display());
+        log.line(format_args!(
+            "Created a CLAP bundle at '{}'",
+            clap_bundle_home.display()
+        ));
     }
     if bundle_vst2 {
         let vst2_bundle_library_name = vst2_bundle_library_name(&bundle_name, compilation_target);
@@ -474,15 +751,18 @@ fn bundle_plugin(
                 .expect("Malformed VST2 library path"),
         );
         maybe_create_macos_bundle_metadata(
-            package,
+            &bundle_metadata,
             &bundle_name,
             &vst2_bundle_home,
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(&vst2_bundle_home, compilation_target);
+        codesign(&vst2_bundle_home, compilation_target, log);
 
-        eprintln!("Created a VST2 bundle at '{}'", vst2_bundle_home.display());
+        log.line(format_args!(
+            "Created a VST2 bundle at '{}'",
+            vst2_bundle_home.display()
+        ));
     }
     if bundle_vst3 {
         let vst3_lib_path =
@@ -501,23 +781,109 @@ fn bundle_plugin(
             .parent()
             .unwrap();
         maybe_create_macos_bundle_metadata(
-            package,
+            &bundle_metadata,
             &bundle_name,
             vst3_bundle_home,
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(vst3_bundle_home, compilation_target);
+        codesign(vst3_bundle_home, compilation_target, log);
 
-        eprintln!("Created a VST3 bundle at '{}'", vst3_bundle_home.display());
+        log.line(format_args!(
+            "Created a VST3 bundle at '{}'",
+            vst3_bundle_home.display()
+        ));
     }
     if !bundled_plugin {
-        eprintln!("Not creating any plugin bundles because the package does not export any plugins")
+        log.line("Not creating any plugin bundles because the package does not export any plugins")
     }
 
     Ok(())
 }
 
+/// Validate the plugins for one or more packages that were previously bundled using [`bundle()`].
+/// CLAP plugins are validated using `clap-validator`, and VST3 plugins are validated using
+/// `pluginval`. Only the bundles for the native target are validated. If one of these validators
+/// is not installed, then this prints a warning and skips that format, just like
+/// [`maybe_codesign()`] does. Returns an error if any of the validators reported a failure. This
+/// requires the current working directory to have been set to the workspace's root using
+/// [`chdir_workspace_root()`].
+pub fn validate(target_dir: &Path, packages: &[String]) -> Result<()> {
+    let bundle_home_dir = bundle_home(target_dir);
+    let bundler_config = load_bundler_config()?;
+
+    let mut failures = Vec::new();
+    for package in packages {
+        let bundle_name = bundle_name(package, bundler_config.as_ref())?;
+        // These are the bundle paths on all platforms. On Linux and Windows the CLAP plugin is a
+        // plain library file instead of a directory.
+        let clap_bundle_home = bundle_home_dir.join(format!("{bundle_name}.clap"));
+        let vst3_bundle_home = bundle_home_dir.join(format!("{bundle_name}.vst3"));
+        if !clap_bundle_home.exists() && !vst3_bundle_home.exists() {
+            anyhow::bail!(
+                "Could not find any bundled plugins for '{package}' in '{}'. Make sure to bundle \
+                 the package first.",
+                bundle_home_dir.display()
+            );
+        }
+
+        if clap_bundle_home.exists() {
+            eprintln!("Validating '{}'", clap_bundle_home.display());
+            let passed = maybe_run_validator(
+                Command::new("clap-validator")
+                    .arg("validate")
+                    .arg(&clap_bundle_home),
+            );
+            if passed == Some(false) {
+                failures.push(clap_bundle_home);
+            }
+        }
+        if vst3_bundle_home.exists() {
+            eprintln!("Validating '{}'", vst3_bundle_home.display());
+            let passed = maybe_run_validator(
+                Command::new("pluginval")
+                    .args(["--strictness-level", "5", "--validate"])
+                    .arg(&vst3_bundle_home),
+            );
+            if passed == Some(false) {
+                failures.push(vst3_bundle_home);
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        let failures: Vec<_> = failures
+            .iter()
+            .map(|path| format!("'{}'", path.display()))
+            .collect();
+        anyhow::bail!("Validation failed for {}", failures.join(", "))
+    }
+}
+
+/// Run a plugin validator and return whether the validation passed. If the validator could not be
+/// run, for instance because it is not installed, then this prints a warning and returns `None`.
+fn maybe_run_validator(command: &mut Command) -> Option<bool> {
+    match command.status() {
+        Ok(status) => {
+            if !status.success() {
+                eprintln!("Validation failed with {status}");
+            }
+
+            Some(status.success())
+        }
+        Err(err) => {
+            eprintln!(
+                "WARNING: Could not run '{}', skipping validation: {err}",
+                command.get_program().to_string_lossy()
+            );
+
+            None
+        }
+    }
+}
+
 /// This lists the packages configured in `bundler.toml`. This is only used as part of the CI when
 /// bundling plugins.
 pub fn list_known_packages() -> Result<()> {
@@ -548,6 +914,30 @@ fn load_bundler_config() -> Result<Option<BundlerConfig>> {
     Ok(Some(result))
 }
 
+/// The name of the bundles created for a package. This can be overridden in `bundler.toml`.
+fn bundle_name(package: &str, bundler_config: Option<&BundlerConfig>) -> Result<String> {
+    match bundler_config.and_then(|c| c.get(package)) {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => Ok(name.clone()),
+        _ => Ok(package.to_string()),
+    }
+}
+
+/// Cargo's metadata for a package in the current workspace, if the workspace contains that package.
+fn package_metadata(package: &str) -> Result<Option<cargo_metadata::Package>> {
+    let cargo_metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path("./Cargo.toml")
+        .no_deps()
+        .exec()
+        .context("Could not parse `cargo-metadata`")?;
+
+    Ok(cargo_metadata
+        .packages
+        .into_iter()
+        .find(|p| p.name == package))
+}
+
 /// Split the `xtask bundle` arguments into a list of packages and a list of other arguments. The
 /// package vector either contains just the first argument, or if the arguments iterator starts with
 /// one or more occurences of `-p <package>` then this will contain all those packages.
@@ -575,6 +965,31 @@ fn split_bundle_args(
     Ok((packages, other_args))
 }
 
+/// The name of the directory Cargo writes the build artifacts to for the `cargo build` arguments
+/// in `args`. This is `debug` by default, `release` for release builds, or the name of a custom
+/// profile.
+fn build_type_dir(args: &[String]) -> Result<&str> {
+    let mut build_type_dir = "debug";
+    for arg_idx in (0..args.len()).rev() {
+        let arg = &args[arg_idx];
+        match arg.as_str() {
+            "--profile" => {
+                // Since Rust 1.57 you can have custom profiles
+                build_type_dir = args.get(arg_idx + 1).context("Missing profile name")?;
+            }
+            "--release" => build_type_dir = "release",
+            arg if arg.starts_with("--profile=") => {
+                build_type_dir = arg
+                    .strip_prefix("--profile=")
+                    .context("Missing profile name")?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(build_type_dir)
+}
+
 /// The target we're compiling for. This is used to determine the paths and options for creating
 /// plugin bundles.
 fn compilation_target(cross_compile_target: Option<&str>) -> Result<CompilationTarget> {
@@ -727,12 +1142,69 @@ fn vst3_bundle_library_name(package: &str, target: CompilationTarget) -> String
     }
 }
 
+/// The git commit hash that's embedded in the plugins during [`build()`]. This uses the value from
+/// the `NIH_PLUG_GIT_HASH` environment variable if it has been set.
+fn build_git_hash() -> Option<String> {
+    match std::env::var(GIT_HASH_ENV_VAR) {
+        Ok(git_hash) => Some(git_hash),
+        Err(_) => util::git_short_hash(),
+    }
+}
+
+impl BundleMetadata {
+    /// Get the bundle metadata for a package. Fields that have not been set in the package's
+    /// section in `bundler.toml` use their default values. The version defaults to the package's
+    /// version from its `Cargo.toml` file, or to `1.0.0` if the package is not part of the current
+    /// workspace. This requires the current working directory to have been set to the workspace's
+    /// root using [`chdir_workspace_root()`].
+    pub fn for_package(package: &str) -> Result<Self> {
+        Self::from_config(package, load_bundler_config()?.as_ref())
+    }
+
+    /// The same as [`for_package()`][Self::for_package()], but using an already loaded
+    /// `bundler.toml` file.
+    fn from_config(package: &str, bundler_config: Option<&BundlerConfig>) -> Result<Self> {
+        let config = bundler_config.and_then(|c| c.get(package)).cloned();
+        let (identifier, version, signature) = match config {
+            Some(PackageConfig {
+                identifier,
+                version,
+                signature,
+                ..
+            }) => (identifier, version, signature),
+            None => (None, None, None),
+        };
+
+        let version = match version {
+            Some(version) => version,
+            None => match package_metadata(package)? {
+                Some(package_metadata) => package_metadata.version.to_string(),
+                None => String::from("1.0.0"),
+            },
+        };
+        let signature = signature.unwrap_or_else(|| String::from("????"));
+        if signature.len() != 4 || !signature.is_ascii() {
+            anyhow::bail!(
+                "The bundle signature for '{package}' must consist of exactly four ASCII \
+                 characters, got '{signature}'"
+            );
+        }
+
+        Ok(Self {
+            identifier: identifier.unwrap_or_else(|| format!("com.nih-plug.{package}")),
+            version,
+            signature,
+        })
+    }
+}
+
 /// If compiling for macOS, create all of the bundl-y stuff Steinberg and Apple require you to have.
 ///
 /// This still requires you to move the dylib file to `{bundle_home}/Contents/macOS/{package}`
-/// yourself first.
+/// yourself first. If the bundle was built from a git repository, then the commit hash is stored in
+/// a custom `NIHPlugGitHash` key since `CFBundleVersion` may only contain numbers and periods.
 pub fn maybe_create_macos_bundle_metadata(
-    package: &str,
+    metadata: &BundleMetadata,
     display_name: &str,
     bundle_home: &Path,
     target: CompilationTarget,
@@ -750,11 +1222,22 @@ pub fn maybe_create_macos_bundle_metadata(
         BundleType::Binary => "APPL",
     };
 
-    // TODO: May want to add bundler.toml fields for the identifier, version and signature at some
-    //       point.
+    // These values come from the user's configuration, so they need to be escaped before they can
+    // be included in the XML file
+    let display_name = xml_escape(display_name);
+    let identifier = xml_escape(&metadata.identifier);
+    let version = xml_escape(&metadata.version);
+    let signature = xml_escape(&metadata.signature);
+    let git_hash_entry = match build_git_hash() {
+        Some(git_hash) => format!(
+            "\n    <key>NIHPlugGitHash</key>\n    <string>{}</string>",
+            xml_escape(&git_hash)
+        ),
+        None => String::new(),
+    };
     fs::write(
         bundle_home.join("Contents").join("PkgInfo"),
-        format!("{package_type}????"),
+        format!("{package_type}{}", metadata.signature),
     )
     .context("Could not create PkgInfo file")?;
     fs::write(
@@ -769,7 +1252,7 @@ pub fn maybe_create_macos_bundle_metadata(
     <key>CFBundleIconFile</key>
     <string></string>
     <key>CFBundleIdentifier</key>
-    <string>com.nih-plug.{package}</string>
+    <string>{identifier}</string>
     <key>CFBundleName</key>
     <string>{display_name}</string>
     <key>CFBundleDisplayName</key>
@@ -777,15 +1260,15 @@ pub fn maybe_create_macos_bundle_metadata(
     <key>CFBundlePackageType</key>
     <string>{package_type}</string>
     <key>CFBundleSignature</key>
-    <string>????</string>
+    <string>{signature}</string>
     <key>CFBundleShortVersionString</key>
-    <string>1.0.0</string>
+    <string>{version}</string>
     <key>CFBundleVersion</key>
-    <string>1.0.0</string>
+    <string>{version}</string>
     <key>NSHumanReadableCopyright</key>
     <string></string>
     <key>NSHighResolutionCapable</key>
-    <true/>
+    <true/>{git_hash_entry}
   </dict>
 </plist>
 "#),
@@ -795,12 +1278,28 @@ pub fn maybe_create_macos_bundle_metadata(
     Ok(())
 }
 
+/// Escape the characters in `value` that have a special meaning in XML text.
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 /// If compiling for macOS, try to self-sign the bundle at the given path. This shouldn't be
 /// necessary, but AArch64 macOS is stricter about these things and sometimes self built plugins may
 /// not load otherwise. Presumably in combination with hardened runtimes.
 ///
 /// If the codesigning command could not be run then this merely prints a warning.
 pub fn maybe_codesign(bundle_home: &Path, target: CompilationTarget) {
+    let mut log = BundleLog::default();
+    codesign(bundle_home, target, &mut log);
+    log.flush();
+}
+
+/// The implementation for [`maybe_codesign()`]. The warning and `codesign`'s own output are written
+/// to `log`.
+fn codesign(bundle_home: &Path, target: CompilationTarget, log: &mut BundleLog) {
     if !matches!(
         target,
         CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal
@@ -808,17 +1307,36 @@ pub fn maybe_codesign(bundle_home: &Path, target: CompilationTarget) {
         return;
     }
 
-    let success = Command::new("codesign")
+    // The output is captured so it doesn't get mixed up with the output for other packages
+    match Command::new("codesign")
         .arg("-f")
         .arg("-s")
         .arg("-")
         .arg(bundle_home)
-        .status()
-        .is_ok();
-    if !success {
-        eprintln!(
+        .output()
+    {
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stderr.trim().is_empty() {
+                log.line(stderr.trim_end());
+            }
+        }
+        Err(_) => log.line(format_args!(
             "WARNING: Could not self-sign '{}', it may fail to run depending on the environment",
             bundle_home.display()
-        )
+        )),
+    }
+}
+
+impl BundleLog {
+    /// Add a line to the log.
+    fn line(&mut self, message: impl Display) {
+        self.0.push_str(&message.to_string());
+        self.0.push('\n');
+    }
+
+    /// Write the collected messages to STDERR in one go.
+    fn flush(self) {
+        let _ = std::io::stderr().lock().write_all(self.0.as_bytes());
     }
 }
@@ -64,3 +64,63 @@ pub fn lipo(inputs: &[&Path], target: &Path) -> Result<()> {
         Ok(())
     }
 }
+
+/// The short hash of the git commit the current working directory is at, or `None` if this is not
+/// a git repository or if git is not installed. A `-dirty` suffix is added when the working tree
+/// contains uncommitted changes.
+pub fn git_short_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args([
+            "describe",
+            "--always",
+            "--dirty",
+            "--abbrev=7",
+            "--exclude=*",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let hash = String::from_utf8(output.stdout).ok()?;
+    let hash = hash.trim();
+    if hash.is_empty() {
+        None
+    } else {
+        Some(hash.to_owned())
+    }
+}
+
+/// The current UTC date in `YYYY-MM-DD` format. If the `SOURCE_DATE_EPOCH` environment variable is
+/// set then that timestamp is used instead to keep builds reproducible.
+pub fn build_date() -> String {
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|timestamp| timestamp.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0)
+        });
+
+    // This converts the number of days since the Unix epoch to a proleptic Gregorian calendar date
+    // using Howard Hinnant's `civil_from_days()` algorithm, which avoids pulling in a date library
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_offset = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_offset + 2) / 5 + 1;
+    let month = if month_offset < 10 {
+        month_offset + 3
+    } else {
+        month_offset - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -25,19 +25,70 @@ use crate::MAX_OCTAVE_SHIFT;
 #[derive(Debug, Default)]
 pub struct RingBuffer {
     sample_rate: f32,
+    /// The maximum length of the recorded buffer in samples. Longer buffers are truncated to this
+    /// length.
+    max_period_samples: usize,
 
     /// When a key is pressed, `next_sample_pos` is set to 0 and the incoming audio is recorded into
     /// this buffer until `next_sample_pos` wraps back around to the start of the ring buffer. At
     /// that point the incoming audio is replaced by the previously recorded audio. These buffers
     /// are resized to match the length/frequency of the audio being played back.
     audio_buffers: Vec<Vec<f32>>,
-    /// The current playback position in `playback_buffers`.
+    /// The current recording position in `audio_buffers`. This is used while recording the buffer
+    /// and while crossfading the buffer's start. The buffer is always recorded forwards.
     next_sample_pos: usize,
     /// The length of the crossfade, in samples. After the first this additional samples are
     /// recorded and faded back into the buffer.
     crossfade_length: usize,
     /// See [`BufferStatus`].
     buffer_status: BufferStatus,
+    /// The direction the buffer should be played back in after it has been recorded.
+    playback_mode: PlaybackMode,
+    /// The current playback position in `audio_buffers`. This is only used after the buffer has
+    /// been recorded.
+    playback_pos: PlaybackPosition,
+    /// The rate the recorded buffer is played back at. At 1.0 the buffer is played back at its
+    /// original pitch. Other rates resample the buffer using linear interpolation.
+    playback_rate: f32,
+    /// The fractional part of the playback position, in `[0, 1)`. The sample at `playback_pos` is
+    /// interpolated with the next sample using this.
+    playback_phase: f32,
+    /// If set, then playback restarts from the start of the buffer every time the buffer's length
+    /// has been played back, regardless of the playback rate. This keeps the loops in time when the
+    /// buffer's length is synced to the host's tempo.
+    restart_loops: bool,
+    /// The number of samples output since playback last (re)started. Only used when
+    /// `restart_loops` is set.
+    samples_since_loop_start: usize,
+
+    /// When playback is retriggered while the buffer is still playing, the old `audio_buffers` are
+    /// swapped into here so they can be faded out while the new buffer starts recording. These
+    /// have the same capacity as `audio_buffers`.
+    previous_audio_buffers: Vec<Vec<f32>>,
+    /// The current playback position in `previous_audio_buffers`.
+    previous_playback_pos: PlaybackPosition,
+    /// The number of samples left in the fade out of `previous_audio_buffers`. This is 0 when the
+    /// previous buffer is not being played back.
+    previous_fade_remaining: usize,
+    /// The total length of the fade out of `previous_audio_buffers`, in samples.
+    previous_fade_length: usize,
+}
+
+/// The direction the recorded buffer is played back in.
+#[derive(Enum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// Loop the recorded buffer from start to end.
+    #[default]
+    #[id = "forward"]
+    Forward,
+    /// Loop the recorded buffer from end to start.
+    #[id = "reverse"]
+    Reverse,
+    /// Alternate between playing the buffer backwards and forwards. Since the recording is played
+    /// forwards, playback starts by going backwards.
+    #[id = "ping-pong"]
+    #[name = "Ping-Pong"]
+    PingPong,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -49,33 +100,99 @@ enum BufferStatus {
     /// second pass continues recording, and replaces the buffer's start with a cross faded version
     /// of that input and the existing contents.
     Crossfading,
-    /// The buffer has wrapped around once and `crossfade_length` is set to 0, or it has wrapped
-    /// around twice and crossfading is enabled. Samples only need to be read from the buffer, all
-    /// work is done.
+    /// The buffer has wrapped around once and `crossfade_length` is set to 0, or the crossfade has
+    /// been recorded. Samples only need to be read from the buffer, all work is done.
     Ready,
 }
 
+/// A read position for playing back a recorded buffer in a [`PlaybackMode`].
+#[derive(Debug, Default, Clone, Copy)]
+struct PlaybackPosition {
+    mode: PlaybackMode,
+    /// The index of the next sample to read.
+    pos: usize,
+    /// Whether `pos` is currently moving towards the start of the buffer.
+    backwards: bool,
+}
+
+impl PlaybackPosition {
+    /// The playback position right after a buffer of `len` samples has been recorded.
+    fn after_recording(mode: PlaybackMode, len: usize) -> Self {
+        match mode {
+            PlaybackMode::Forward => Self {
+                mode,
+                pos: 0,
+                backwards: false,
+            },
+            // The last sample has just been output while recording, so playback continues from the
+            // sample before that
+            PlaybackMode::Reverse | PlaybackMode::PingPong => Self {
+                mode,
+                pos: len.saturating_sub(2),
+                backwards: true,
+            },
+        }
+    }
+
+    /// Advance the position for a buffer containing `len` samples. Ping-pong playback bounces off
+    /// the buffer's edges without repeating the edge samples.
+    fn advance(&mut self, len: usize) {
+        match self.mode {
+            PlaybackMode::Forward => self.pos = (self.pos + 1) % len,
+            PlaybackMode::Reverse => self.pos = if self.pos == 0 { len - 1 } else { self.pos - 1 },
+            PlaybackMode::PingPong if self.backwards => {
+                if self.pos == 0 {
+                    self.pos = 1.min(len - 1);
+                    self.backwards = false;
+                } else {
+                    self.pos -= 1;
+                }
+            }
+            PlaybackMode::PingPong => {
+                if self.pos + 1 >= len {
+                    self.pos = len.saturating_sub(2);
+                    self.backwards = true;
+                } else {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
 impl RingBuffer {
     /// Initialize or resize the buffers to fit a certain number of channels and samples. The inner
     /// buffer capacity is determined by the number of samples it takes to represent the period of
-    /// MIDI note 0 at the specified sample rate, rounded up to a power of two. Make sure to call
-    /// [`reset()`][Self::reset()] after this.
-    pub fn resize(&mut self, num_channels: usize, sample_rate: f32) {
+    /// MIDI note 0 at the specified sample rate, rounded up to a power of two, or by
+    /// `min_period_seconds` if that is longer. Make sure to call [`reset()`][Self::reset()] after
+    /// this.
+    pub fn resize(&mut self, num_channels: usize, sample_rate: f32, min_period_seconds: f32) {
         nih_debug_assert!(num_channels >= 1);
         nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(min_period_seconds >= 0.0);
 
         // NOTE: We need to take the octave shift into account
         let lowest_note_frequency =
             util::midi_note_to_freq(0) / 2.0f32.powi(MAX_OCTAVE_SHIFT as i32);
         let loest_note_period_samples =
             (lowest_note_frequency.recip() * sample_rate).ceil() as usize;
-        let buffer_len = loest_note_period_samples.next_power_of_two();
+        let min_period_samples = (min_period_seconds * sample_rate).ceil() as usize;
+        let buffer_len = loest_note_period_samples
+            .next_power_of_two()
+            .max(min_period_samples);
 
         // Used later to compute period sizes in samples based on frequencies
         self.sample_rate = sample_rate;
+        self.max_period_samples = buffer_len;
 
         self.audio_buffers.resize_with(num_channels, Vec::new);
-        for buffer in self.audio_buffers.iter_mut() {
+        self.previous_audio_buffers
+            .resize_with(num_channels, Vec::new);
+        for buffer in self
+            .audio_buffers
+            .iter_mut()
+            .chain(self.previous_audio_buffers.iter_mut())
+        {
             buffer.resize(buffer_len, 0.0);
         }
     }
@@ -83,17 +200,65 @@ impl RingBuffer {
     /// Zero out the buffers.
     pub fn reset(&mut self) {
         // The current verion's buffers don't need to be reset since they're always initialized
-        // before being used
+        // before being used. Any fade out from a retrigger should be stopped though.
+        self.previous_fade_remaining = 0;
     }
 
     /// Prepare the playback buffers to play back audio at the specified frequency. This resets the
     /// buffer to record the next `note_period_samples`, which are then looped until the key is
     /// released. The crossfade length is also set at this point since right now we don't record
     /// more than necessary and can't change this afterwards.
-    pub fn prepare_playback(&mut self, frequency: f32, crossfade_ms: f32) {
+    ///
+    /// After recording, the buffer is played back according to `playback_mode`. The start of the
+    /// buffer is crossfaded with the audio following the recording for the forward and reverse
+    /// modes, since those modes jump between the buffer's end and start. Ping-pong playback
+    /// doesn't need this as it never jumps between the two.
+    ///
+    /// The recorded buffer is resampled to `playback_rate` during playback. If `restart_loops` is
+    /// set, then playback jumps back to the start every `note_period_samples` samples, even when
+    /// the playback rate is not 1.0. The buffer is truncated if the period is longer than the
+    /// buffer's capacity.
+    ///
+    /// If `retrigger` is set and the buffer was already playing back previously recorded audio,
+    /// then the old buffer will be crossfaded into the new recording over the same crossfade length
+    /// to avoid clicks. This allows a playing voice to be retriggered with a different grain size.
+    pub fn prepare_playback(
+        &mut self,
+        frequency: f32,
+        playback_rate: f32,
+        restart_loops: bool,
+        crossfade_ms: f32,
+        playback_mode: PlaybackMode,
+        retrigger: bool,
+    ) {
         nih_debug_assert!(frequency > 0.0);
+        nih_debug_assert!(playback_rate > 0.0);
         nih_debug_assert!(crossfade_ms >= 0.0);
-        let note_period_samples = (frequency.recip() * self.sample_rate).ceil() as usize;
+        let note_period_samples = ((frequency.recip() * self.sample_rate).ceil() as usize)
+            .clamp(1, self.max_period_samples);
+
+        // While recording the buffer outputs the input audio as is, so there's nothing to fade out
+        // in that case. The new recording outputs the exact same signal.
+        let crossfade_samples = (crossfade_ms / 1000.0 * self.sample_rate).ceil() as usize;
+        if retrigger
+            && crossfade_samples > 0
+            && !matches!(self.buffer_status, BufferStatus::Recording)
+        {
+            // Swapping the buffers doesn't allocate, and the old playback can continue from the
+            // previous buffer while the current buffer is being recorded to
+            std::mem::swap(&mut self.audio_buffers, &mut self.previous_audio_buffers);
+            self.previous_playback_pos = self.playback_pos;
+            // The fade out should not take up more than half of the new buffer's playback, and the
+            // previous buffer can't be faded out for longer than it was playing
+            let previous_fade_length = crossfade_samples
+                .min(note_period_samples / 2)
+                .min(self.previous_audio_buffers[0].len())
+                .max(1);
+            self.previous_fade_length = previous_fade_length;
+            self.previous_fade_remaining = previous_fade_length;
+        } else {
+            self.previous_fade_remaining = 0;
+        }
 
         // This buffer doesn't need to be cleared since the data is not read until the entire buffer
         // has been recorded to
@@ -105,24 +270,44 @@ impl RingBuffer {
         // The buffer is filled on the first `note_period_samples` calls to `next_sample`, plus a
         // little more for the crossfade if set
         self.next_sample_pos = 0;
-        self.crossfade_length =
-            ((crossfade_ms * self.sample_rate).ceil() as usize).min(note_period_samples);
+        self.crossfade_length = match playback_mode {
+            PlaybackMode::Forward | PlaybackMode::Reverse => {
+                crossfade_samples.min(note_period_samples)
+            }
+            PlaybackMode::PingPong => 0,
+        };
         self.buffer_status = BufferStatus::Recording;
+        self.playback_mode = playback_mode;
+        self.playback_rate = playback_rate;
+        self.restart_loops = restart_loops;
+    }
+
+    /// The length of the recorded buffer in samples. This is the period of the note frequency
+    /// passed to the last [`prepare_playback()`][Self::prepare_playback()] call.
+    pub fn period_samples(&self) -> usize {
+        self.audio_buffers
+            .first()
+            .map(|buffer| buffer.len())
+            .unwrap_or(0)
     }
 
     /// Read or write a sample from or to the ring buffer, and return the output. On the first loop
     /// this will store the input samples into the bufffer and return the input value as is.
-    /// Afterwards it will read the previously recorded data from the buffer. The read/write
-    /// position is advanced whenever the last channel is written to.
+    /// Afterwards it will read the previously recorded data from the buffer in the direction set
+    /// by the playback mode. The read/write positions are advanced whenever the last channel is
+    /// written to.
     pub fn next_sample(&mut self, channel_idx: usize, input_sample: f32) -> f32 {
-        match self.buffer_status {
+        let mut result = match self.buffer_status {
             BufferStatus::Recording => {
-                self.audio_buffers[channel_idx][self.next_sample_pos] = input_sample
+                self.audio_buffers[channel_idx][self.next_sample_pos] = input_sample;
+
+                input_sample
             }
-            BufferStatus::Crossfading if self.next_sample_pos < self.crossfade_length => {
+            BufferStatus::Crossfading => {
                 // This is an equal power fade between the part of the input after the first loop
                 // and the buffer's existing contents. The `.max(1)` is needed to avoid NaNs with
-                // crossfade lengths of 1 sample.
+                // crossfade lengths of 1 sample. Since the buffer's start is played back last in
+                // reverse playback, this also smooths out the reverse loop's wrap point.
                 let crossfade_t =
                     self.next_sample_pos as f32 / (self.crossfade_length - 1).max(1) as f32;
                 let new_t = (1.0 - crossfade_t).sqrt();
@@ -130,28 +315,96 @@ impl RingBuffer {
 
                 self.audio_buffers[channel_idx][self.next_sample_pos] = (input_sample * new_t)
                     + (self.audio_buffers[channel_idx][self.next_sample_pos] * existing_t);
+
+                self.read_playback(channel_idx)
             }
-            _ => (),
+            BufferStatus::Ready => self.read_playback(channel_idx),
+        };
+
+        // After a retrigger the previous buffer is faded out using another equal power fade
+        if self.previous_fade_remaining > 0 {
+            let previous_t = self.previous_fade_remaining as f32 / self.previous_fade_length as f32;
+            let previous_sample =
+                self.previous_audio_buffers[channel_idx][self.previous_playback_pos.pos];
+
+            result = (result * (1.0 - previous_t).sqrt()) + (previous_sample * previous_t.sqrt());
         }
-        let result = self.audio_buffers[channel_idx][self.next_sample_pos];
 
         // TODO: This can be done more efficiently, but you really won't notice the performance
         //       impact here
         if channel_idx == self.audio_buffers.len() - 1 {
-            self.next_sample_pos += 1;
+            let buffer_len = self.audio_buffers[0].len();
+            match self.buffer_status {
+                BufferStatus::Recording => {
+                    self.next_sample_pos += 1;
 
-            if self.next_sample_pos == self.audio_buffers[0].len() {
-                self.next_sample_pos = 0;
+                    if self.next_sample_pos == buffer_len {
+                        self.next_sample_pos = 0;
+                        self.playback_pos =
+                            PlaybackPosition::after_recording(self.playback_mode, buffer_len);
+                        self.playback_phase = 0.0;
+                        self.samples_since_loop_start = 0;
+                        self.buffer_status = if self.crossfade_length > 0 {
+                            BufferStatus::Crossfading
+                        } else {
+                            BufferStatus::Ready
+                        };
+                    }
+                }
+                BufferStatus::Crossfading => {
+                    self.next_sample_pos += 1;
+                    self.advance_playback(buffer_len);
 
-                self.buffer_status = match self.buffer_status {
-                    BufferStatus::Recording if self.crossfade_length > 0 => {
-                        BufferStatus::Crossfading
+                    if self.next_sample_pos == self.crossfade_length {
+                        self.buffer_status = BufferStatus::Ready;
                     }
-                    _ => BufferStatus::Ready,
-                };
+                }
+                BufferStatus::Ready => self.advance_playback(buffer_len),
+            }
+
+            if self.previous_fade_remaining > 0 {
+                self.previous_fade_remaining -= 1;
+                self.previous_playback_pos
+                    .advance(self.previous_audio_buffers[0].len());
             }
         }
 
         result
     }
+
+    /// Read the sample at the current playback position, interpolating between two samples when
+    /// the buffer is resampled.
+    fn read_playback(&self, channel_idx: usize) -> f32 {
+        let buffer = &self.audio_buffers[channel_idx];
+        let sample = buffer[self.playback_pos.pos];
+        if self.playback_phase == 0.0 {
+            return sample;
+        }
+
+        let mut next_pos = self.playback_pos;
+        next_pos.advance(buffer.len());
+
+        sample + ((buffer[next_pos.pos] - sample) * self.playback_phase)
+    }
+
+    /// Advance the playback position by one output sample according to the playback rate. With a
+    /// playback rate of 1.0 this is the same as advancing `playback_pos` directly.
+    fn advance_playback(&mut self, buffer_len: usize) {
+        if self.restart_loops {
+            self.samples_since_loop_start += 1;
+            if self.samples_since_loop_start >= buffer_len {
+                self.playback_pos =
+                    PlaybackPosition::after_recording(self.playback_mode, buffer_len);
+                self.playback_phase = 0.0;
+                self.samples_since_loop_start = 0;
+                return;
+            }
+        }
+
+        self.playback_phase += self.playback_rate;
+        while self.playback_phase >= 1.0 {
+            self.playback_pos.advance(buffer_len);
+            self.playback_phase -= 1.0;
+        }
+    }
 }
@@ -70,6 +70,13 @@ impl AREnvelope {
         }
     }
 
+    /// Restart the attack segment of the envelope generator without resetting its current value.
+    /// Used when retriggering a voice that's still playing so the amplitude doesn't jump back to
+    /// zero.
+    pub fn restart(&mut self) {
+        self.releasing = false;
+    }
+
     /// Start the release segment of the envelope generator.
     pub fn start_release(&mut self) {
         self.releasing = true;
@@ -26,6 +26,14 @@ pub const MAX_OCTAVE_SHIFT: u32 = 2;
 
 /// The number of channels supported by the plugin. We'll only do stereo for now.
 const NUM_CHANNELS: u32 = 2;
+/// The tempo used for [`GrainMode::Tempo`] when the host doesn't provide one.
+const DEFAULT_TEMPO: f64 = 120.0;
+/// The slowest tempo [`GrainMode::Tempo`] follows. Slower tempos are treated as this tempo, and the
+/// recorded buffers are large enough to fit the longest [`NoteDivision`] at this tempo.
+const MIN_TEMPO: f64 = 40.0;
+/// In [`GrainMode::Tempo`], this note plays the recorded buffer back at its original pitch.
+const TEMPO_MODE_ROOT_NOTE: u8 = 60;
+
 /// The maximum size of an audio block. We'll split up the audio in blocks and render smoothed
 /// values to buffers since these values may need to be reused for multiple voices.
 const MAX_BLOCK_SIZE: usize = 64;
@@ -37,6 +45,98 @@ struct BuffrGlitch {
     voices: [Voice; 8],
 }
 
+/// How the length of the recorded buffer is determined.
+#[derive(Enum, Debug, PartialEq, Eq)]
+enum GrainMode {
+    /// The buffer's length is the period of the played note's frequency, so the buffer repeats at
+    /// the note's pitch.
+    #[id = "pitch"]
+    Pitch,
+    /// The buffer's length is a note division synced to the host's tempo, and the played note
+    /// changes the playback pitch of the recorded buffer instead. This creates rhythmic stutters.
+    #[id = "tempo"]
+    #[name = "Tempo Sync"]
+    Tempo,
+}
+
+/// A note division for the buffer length in [`GrainMode::Tempo`].
+#[derive(Enum, Debug, PartialEq, Eq)]
+enum NoteDivision {
+    #[id = "1/2"]
+    #[name = "1/2"]
+    Half,
+    #[id = "1/4"]
+    #[name = "1/4"]
+    Quarter,
+    #[id = "1/4t"]
+    #[name = "1/4T"]
+    QuarterTriplet,
+    #[id = "1/8"]
+    #[name = "1/8"]
+    Eighth,
+    #[id = "1/8t"]
+    #[name = "1/8T"]
+    EighthTriplet,
+    #[id = "1/16"]
+    #[name = "1/16"]
+    Sixteenth,
+    #[id = "1/16t"]
+    #[name = "1/16T"]
+    SixteenthTriplet,
+    #[id = "1/32"]
+    #[name = "1/32"]
+    ThirtySecond,
+}
+
+impl NoteDivision {
+    /// The length of this note division in quarter notes.
+    fn beats(&self) -> f64 {
+        match self {
+            NoteDivision::Half => 2.0,
+            NoteDivision::Quarter => 1.0,
+            NoteDivision::QuarterTriplet => 2.0 / 3.0,
+            NoteDivision::Eighth => 0.5,
+            NoteDivision::EighthTriplet => 1.0 / 3.0,
+            NoteDivision::Sixteenth => 0.25,
+            NoteDivision::SixteenthTriplet => 1.0 / 6.0,
+            NoteDivision::ThirtySecond => 0.125,
+        }
+    }
+
+    /// The length of this note division in seconds at a certain tempo. Tempos below
+    /// [`MIN_TEMPO`] are clamped.
+    fn seconds(&self, tempo: f64) -> f64 {
+        self.beats() * 60.0 / tempo.max(MIN_TEMPO)
+    }
+
+    /// The length of the longest note division in seconds at [`MIN_TEMPO`]. The recorded buffers
+    /// need to be able to hold this many seconds of audio.
+    fn max_seconds() -> f64 {
+        (0..Self::variants().len())
+            .map(|idx| Self::from_index(idx).seconds(MIN_TEMPO))
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Which MIDI events Buffr Glitch should send to the host so other plugins can react to the
+/// playback.
+#[derive(Enum, Debug, PartialEq, Eq)]
+enum MidiOutputMode {
+    /// Don't send any MIDI.
+    #[id = "off"]
+    Off,
+    /// Send a note on event when a voice starts playing, and a note off event when the key is
+    /// released or when the voice is stolen.
+    #[id = "notes"]
+    Notes,
+    /// The same as [`Notes`][Self::Notes], but the note is retriggered every time the recorded
+    /// buffer loops around. This follows the note's frequency, so higher notes send a lot of
+    /// events.
+    #[id = "loops"]
+    #[name = "Loop Triggers"]
+    Loops,
+}
+
 /// A single voice, Buffr Glitch can be used in polypnoic mode. And even if only a single note is
 /// played at a time, this is needed for the amp envelope release to work correctly.
 ///
@@ -47,13 +147,28 @@ struct Voice {
 
     /// The MIDI note ID of the last note, if a note is pressed.
     midi_note_id: Option<u8>,
+    /// The last note's MIDI channel. Used together with `voice_id` to match note expressions to
+    /// this voice.
+    midi_channel: u8,
+    /// The last note's voice ID, if the host provided one.
+    voice_id: Option<i32>,
     /// The gain scaling from the velocity. If velocity sensitive mode is enabled, then this is the `[0, 1]` velocity
     /// devided by `100/127` such that MIDI velocity 100 corresponds to 1.0 gain.
     velocity_gain: f32,
-    /// The gain from the gain note expression.
-    gain_expression_gain: Smoother<f32>,
+    /// The gain and panning note expressions for this voice. Tuning expressions are not supported
+    /// since the playback pitch is fixed when the note is pressed.
+    note_expressions: util::NoteExpressions,
     /// The envelope genrator used during playback. Produces a `[0, 1]` result.
     amp_envelope: envelope::AREnvelope,
+
+    /// The last note's velocity. Used for the MIDI output.
+    velocity: f32,
+    /// The note and channel of the note on event that was last sent to the host for this voice, if
+    /// a note off event has not yet been sent for it.
+    output_note: Option<(u8, u8)>,
+    /// The number of samples until the next loop trigger should be sent when the MIDI output is
+    /// set to [`MidiOutputMode::Loops`], relative to the start of the current block.
+    samples_until_loop_trigger: usize,
 }
 
 #[derive(Params)]
@@ -69,6 +184,18 @@ struct BuffrGlitchParams {
     /// larger grain sizes.
     #[id = "octave_shift"]
     octave_shift: IntParam,
+    /// The direction the recorded buffer is played back in. Like the crossfade, this is set when
+    /// the note is pressed.
+    #[id = "playback_mode"]
+    playback_mode: EnumParam<buffer::PlaybackMode>,
+    /// Whether the recorded buffer's length follows the played note's pitch or the host's tempo.
+    /// Like the playback mode, this is set when the note is pressed.
+    #[id = "grain_mode"]
+    grain_mode: EnumParam<GrainMode>,
+    /// The buffer's length when the grain mode is set to [`GrainMode::Tempo`]. Tempos below
+    /// [`MIN_TEMPO`] are treated as [`MIN_TEMPO`].
+    #[id = "grain_division"]
+    grain_division: EnumParam<NoteDivision>,
 
     /// The attack time in milliseconds. Useful to avoid clicks. Or to introduce them if that's
     /// aesthetically pleasing.
@@ -84,6 +211,11 @@ struct BuffrGlitchParams {
     /// ieration.
     #[id = "crossfade_ms"]
     crossfade_ms: FloatParam,
+
+    /// Whether to send MIDI notes to the host when voices start playing or when their buffers loop
+    /// around. Useful for syncing other MIDI-reactive plugins to Buffr Glitch.
+    #[id = "midi_output"]
+    midi_output: EnumParam<MidiOutputMode>,
 }
 
 impl Default for BuffrGlitch {
@@ -103,10 +235,15 @@ impl Default for Voice {
             buffer: buffer::RingBuffer::default(),
 
             midi_note_id: None,
+            midi_channel: 0,
+            voice_id: None,
             velocity_gain: 1.0,
-            // This is initialized in `initialize()` since this relies on the sample rate
-            gain_expression_gain: Smoother::new(SmoothingStyle::Linear(5.0)),
+            note_expressions: util::NoteExpressions::new(5.0),
             amp_envelope: envelope::AREnvelope::default(),
+
+            velocity: 1.0,
+            output_note: None,
+            samples_until_loop_trigger: 0,
         }
     }
 }
@@ -136,6 +273,9 @@ impl Default for BuffrGlitchParams {
                     max: MAX_OCTAVE_SHIFT as i32,
                 },
             ),
+            playback_mode: EnumParam::new("Playback Mode", buffer::PlaybackMode::Forward),
+            grain_mode: EnumParam::new("Grain Mode", GrainMode::Pitch),
+            grain_division: EnumParam::new("Grain Division", NoteDivision::Sixteenth),
 
             attack_ms: FloatParam::new(
                 "Attack",
@@ -171,6 +311,8 @@ impl Default for BuffrGlitchParams {
             // This doesn't need smoothing because the value is set when the note is held down and cannot be changed afterwards
             .with_unit(" ms")
             .with_step_size(0.001),
+
+            midi_output: EnumParam::new("MIDI Output", MidiOutputMode::Off),
         }
     }
 }
@@ -191,6 +333,7 @@ impl Plugin for BuffrGlitch {
     }];
 
     const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::Basic;
 
     type SysExMessage = ();
     type BackgroundTask = ();
@@ -211,9 +354,11 @@ impl Plugin for BuffrGlitch {
             .get() as usize;
         self.sample_rate = buffer_config.sample_rate;
         for voice in &mut self.voices {
-            voice
-                .buffer
-                .resize(num_output_channels, buffer_config.sample_rate);
+            voice.buffer.resize(
+                num_output_channels,
+                buffer_config.sample_rate,
+                NoteDivision::max_seconds() as f32,
+            );
         }
 
         true
@@ -233,6 +378,8 @@ impl Plugin for BuffrGlitch {
     ) -> ProcessStatus {
         let num_samples = buffer.samples();
         let output = buffer.as_slice();
+        let midi_output = self.params.midi_output.value();
+        let tempo = context.transport().tempo.unwrap_or(DEFAULT_TEMPO);
 
         let mut next_event = context.next_event();
         let mut block_start: usize = 0;
@@ -245,26 +392,58 @@ impl Plugin for BuffrGlitch {
                     // If the event happens now, then we'll keep processing events
                     Some(event) if (event.timing() as usize) <= block_start => {
                         match event {
-                            NoteEvent::NoteOn { note, velocity, .. } => {
+                            NoteEvent::NoteOn {
+                                timing,
+                                voice_id,
+                                channel,
+                                note,
+                                velocity,
+                            } => {
                                 let new_voice_id = self.new_voice_id();
-                                self.voices[new_voice_id].note_on(&self.params, note, velocity);
+                                let voice = &mut self.voices[new_voice_id];
+
+                                // If the voice is stolen, then its output note also needs to end
+                                voice.send_output_note_off(context, timing);
+                                voice.note_on(
+                                    &self.params,
+                                    tempo,
+                                    voice_id,
+                                    channel,
+                                    note,
+                                    velocity,
+                                );
+                                match midi_output {
+                                    MidiOutputMode::Off => (),
+                                    MidiOutputMode::Notes => {
+                                        voice.send_output_note_on(context, timing)
+                                    }
+                                    // This is sent together with the other loop triggers so the
+                                    // events stay sorted
+                                    MidiOutputMode::Loops => voice.samples_until_loop_trigger = 0,
+                                }
                             }
-                            NoteEvent::NoteOff { note, .. } => {
+                            NoteEvent::NoteOff { timing, note, .. } => {
                                 for voice in &mut self.voices {
                                     if voice.midi_note_id == Some(note) {
                                         // Playback still continues until the release is done.
+                                        voice.send_output_note_off(context, timing);
                                         voice.note_off();
                                         break;
                                     }
                                 }
                             }
-                            NoteEvent::PolyVolume { note, gain, .. } => {
+                            NoteEvent::PolyVolume { .. } | NoteEvent::PolyPan { .. } => {
                                 for voice in &mut self.voices {
-                                    if voice.midi_note_id == Some(note) {
-                                        voice
-                                            .gain_expression_gain
-                                            .set_target(self.sample_rate, gain);
-                                        break;
+                                    if let Some(note) = voice.midi_note_id {
+                                        if voice.note_expressions.handle_event(
+                                            self.sample_rate,
+                                            &event,
+                                            voice.voice_id,
+                                            voice.midi_channel,
+                                            note,
+                                        ) {
+                                            break;
+                                        }
                                     }
                                 }
                             }
@@ -286,6 +465,10 @@ impl Plugin for BuffrGlitch {
             // The output buffer is filled with the active voices, so we need to read the inptu
             // first
             let block_len = block_end - block_start;
+            if midi_output == MidiOutputMode::Loops {
+                self.send_loop_triggers(context, block_start, block_len);
+            }
+
             let mut input = [[0.0; MAX_BLOCK_SIZE]; 2];
             input[0][..block_len].copy_from_slice(&output[0][block_start..block_end]);
             input[1][..block_len].copy_from_slice(&output[1][block_start..block_end]);
@@ -307,22 +490,30 @@ impl Plugin for BuffrGlitch {
                 voice
                     .amp_envelope
                     .next_block(&mut voice_amp_envelope, block_len);
-                let mut voice_gain_expression_gain = [0.0; MAX_BLOCK_SIZE];
-                voice
-                    .gain_expression_gain
-                    .next_block(&mut voice_gain_expression_gain, block_len);
+                let mut voice_gain_expression = [0.0; MAX_BLOCK_SIZE];
+                let mut voice_pan_expression = [0.0; MAX_BLOCK_SIZE];
+                let mut voice_tuning_expression = [0.0; MAX_BLOCK_SIZE];
+                voice.note_expressions.next_block(
+                    &mut voice_gain_expression,
+                    &mut voice_pan_expression,
+                    &mut voice_tuning_expression,
+                    block_len,
+                );
 
                 for (value_idx, sample_idx) in (block_start..block_end).enumerate() {
                     max_voice_amp_envelope[value_idx] =
                         max_voice_amp_envelope[value_idx].max(voice_amp_envelope[value_idx]);
                     let amp = voice.velocity_gain
-                        * voice_gain_expression_gain[value_idx]
+                        * voice_gain_expression[value_idx]
                         * voice_amp_envelope[value_idx];
+                    let (left_gain, right_gain) = util::pan_gains(voice_pan_expression[value_idx]);
 
                     // This will start recording on the first iteration, and then loop the recorded
                     // buffer afterwards
-                    output[0][sample_idx] += voice.buffer.next_sample(0, input[0][value_idx]) * amp;
-                    output[1][sample_idx] += voice.buffer.next_sample(1, input[1][value_idx]) * amp;
+                    output[0][sample_idx] +=
+                        voice.buffer.next_sample(0, input[0][value_idx]) * amp * left_gain;
+                    output[1][sample_idx] +=
+                        voice.buffer.next_sample(1, input[1][value_idx]) * amp * right_gain;
                 }
             }
 
@@ -388,6 +579,40 @@ impl BuffrGlitch {
 
         quietest_voice_id
     }
+
+    /// Send note events for the voices whose recorded buffers loop around in the next `block_len`
+    /// samples starting at `block_start`. This is done for all voices at once since the events
+    /// sent to the host need to be sorted by timing.
+    fn send_loop_triggers(
+        &mut self,
+        context: &mut impl ProcessContext<Self>,
+        block_start: usize,
+        block_len: usize,
+    ) {
+        loop {
+            let next_voice = self
+                .voices
+                .iter_mut()
+                .filter(|voice| voice.midi_note_id.is_some())
+                .min_by_key(|voice| voice.samples_until_loop_trigger);
+            match next_voice {
+                Some(voice) if voice.samples_until_loop_trigger < block_len => {
+                    let timing = (block_start + voice.samples_until_loop_trigger) as u32;
+                    voice.send_output_note_on(context, timing);
+                    voice.samples_until_loop_trigger += voice.buffer.period_samples().max(1);
+                }
+                _ => break,
+            }
+        }
+
+        for voice in self
+            .voices
+            .iter_mut()
+            .filter(|voice| voice.midi_note_id.is_some())
+        {
+            voice.samples_until_loop_trigger -= block_len;
+        }
+    }
 }
 
 impl Voice {
@@ -395,26 +620,69 @@ impl Voice {
         self.buffer.reset();
         self.midi_note_id = None;
         self.amp_envelope.reset();
+        self.output_note = None;
     }
 
-    /// Prepare playback on note on.
-    pub fn note_on(&mut self, params: &BuffrGlitchParams, midi_note_id: u8, velocity: f32) {
+    /// Prepare playback on note on. If the voice is still playing, for instance because it was
+    /// stolen, then the old playback is crossfaded into the new one to avoid clicks. The tempo is
+    /// used to compute the buffer's length in the tempo synced grain mode.
+    pub fn note_on(
+        &mut self,
+        params: &BuffrGlitchParams,
+        tempo: f64,
+        voice_id: Option<i32>,
+        midi_channel: u8,
+        midi_note_id: u8,
+        velocity: f32,
+    ) {
+        let retrigger = self.is_active();
+
         self.midi_note_id = Some(midi_note_id);
+        self.midi_channel = midi_channel;
+        self.voice_id = voice_id;
+        self.velocity = velocity;
         self.velocity_gain = if params.velocity_sensitive.value() {
             velocity / (100.0 / 127.0)
         } else {
             1.0
         };
-        self.gain_expression_gain.reset(1.0);
-        self.amp_envelope.reset();
+        self.note_expressions.reset();
+        if retrigger {
+            self.amp_envelope.restart();
+        } else {
+            self.amp_envelope.reset();
+        }
 
         // We'll copy audio to the playback buffer to match the pitch of the note
         // that was just played. The octave shift parameter makes it possible to get
-        // larger window sizes.
-        let note_frequency =
-            util::midi_note_to_freq(midi_note_id) * 2.0f32.powi(params.octave_shift.value());
-        self.buffer
-            .prepare_playback(note_frequency, params.crossfade_ms.value());
+        // larger window sizes. In the tempo synced mode the buffer's length is a note
+        // division instead, and the note and the octave shift change the playback pitch.
+        let octave_shift_multiplier = 2.0f32.powi(params.octave_shift.value());
+        let (buffer_frequency, playback_rate, restart_loops) = match params.grain_mode.value() {
+            GrainMode::Pitch => (
+                util::midi_note_to_freq(midi_note_id) * octave_shift_multiplier,
+                1.0,
+                false,
+            ),
+            GrainMode::Tempo => {
+                let division_seconds = params.grain_division.value().seconds(tempo);
+                let semitones = midi_note_id as f32 - TEMPO_MODE_ROOT_NOTE as f32;
+
+                (
+                    division_seconds.recip() as f32,
+                    2.0f32.powf(semitones / 12.0) * octave_shift_multiplier,
+                    true,
+                )
+            }
+        };
+        self.buffer.prepare_playback(
+            buffer_frequency,
+            playback_rate,
+            restart_loops,
+            params.crossfade_ms.value(),
+            params.playback_mode.value(),
+            retrigger,
+        );
     }
 
     /// Start releasing the note.
@@ -423,6 +691,42 @@ impl Voice {
         self.midi_note_id = None;
     }
 
+    /// Send a note on event to the host for the voice's current note. If a note on event was
+    /// previously sent for this voice, then that note is ended first.
+    fn send_output_note_on(&mut self, context: &mut impl ProcessContext<BuffrGlitch>, timing: u32) {
+        let Some(note) = self.midi_note_id else {
+            return;
+        };
+
+        self.send_output_note_off(context, timing);
+        context.send_event(NoteEvent::NoteOn {
+            timing,
+            voice_id: None,
+            channel: self.midi_channel,
+            note,
+            velocity: self.velocity,
+        });
+        self.output_note = Some((self.midi_channel, note));
+    }
+
+    /// Send a note off event to the host for the last note on event sent by
+    /// [`send_output_note_on()`][Self::send_output_note_on()], if that note hasn't ended yet.
+    fn send_output_note_off(
+        &mut self,
+        context: &mut impl ProcessContext<BuffrGlitch>,
+        timing: u32,
+    ) {
+        if let Some((channel, note)) = self.output_note.take() {
+            context.send_event(NoteEvent::NoteOff {
+                timing,
+                voice_id: None,
+                channel,
+                note,
+                velocity: 0.0,
+            });
+        }
+    }
+
     /// Whether the voice is (still) active.
     pub fn is_active(&self) -> bool {
         self.midi_note_id.is_some() || self.amp_envelope.is_releasing()
@@ -432,8 +736,6 @@ impl Voice {
 impl ClapPlugin for BuffrGlitch {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.buffr-glitch";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("MIDI-controller buffer repeat");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Synthesizer,
@@ -454,3 +756,43 @@ impl Vst3Plugin for BuffrGlitch {
 
 nih_export_clap!(BuffrGlitch);
 nih_export_vst3!(BuffrGlitch);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_division_seconds() {
+        assert_eq!(NoteDivision::Quarter.seconds(120.0), 0.5);
+        assert_eq!(NoteDivision::Half.seconds(120.0), 1.0);
+        assert_eq!(NoteDivision::ThirtySecond.seconds(60.0), 0.125);
+        assert!((NoteDivision::EighthTriplet.seconds(120.0) - (1.0 / 6.0)).abs() < 1e-12);
+
+        // Slower tempos are clamped, and the longest division is a half note at that tempo
+        assert_eq!(
+            NoteDivision::Half.seconds(1.0),
+            NoteDivision::Half.seconds(MIN_TEMPO)
+        );
+        assert_eq!(NoteDivision::max_seconds(), 3.0);
+    }
+
+    #[test]
+    fn test_longest_division_fits() {
+        for sample_rate in [44_100.0, 48_000.0, 192_000.0] {
+            let mut buffer = buffer::RingBuffer::default();
+            buffer.resize(2, sample_rate, NoteDivision::max_seconds() as f32);
+
+            let frequency = NoteDivision::Half.seconds(MIN_TEMPO).recip() as f32;
+            buffer.prepare_playback(
+                frequency,
+                1.0,
+                true,
+                0.0,
+                buffer::PlaybackMode::Forward,
+                false,
+            );
+            let expected_samples = (3.0 * sample_rate) as usize;
+            assert!(buffer.period_samples().abs_diff(expected_samples) <= 1);
+        }
+    }
+}
@@ -15,19 +15,31 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use nih_plug::prelude::*;
+use nih_plug::util::{filter, Lanczos3Oversampler};
 use nih_plug_vizia::ViziaState;
 use pcg::Pcg32iState;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 mod editor;
-mod filter;
 mod pcg;
 
 /// The number of channels we support. Hardcoded to allow for easier SIMD-ifying in the future.
 const NUM_CHANNELS: u32 = 2;
-/// The number of samples to iterate over at a time.
+/// The number of samples to iterate over at a time. Also used to create scratch buffers for the
+/// oversampling.
 const MAX_BLOCK_SIZE: usize = 64;
 
+/// The 2-logarithm of the maximum oversampling amount to use. 16x oversampling corresponds to
+/// factor 4.
+const MAX_OVERSAMPLING_FACTOR: usize = 4;
+const MAX_OVERSAMPLING_TIMES: usize = oversampling_factor_to_times(MAX_OVERSAMPLING_FACTOR);
+const MAX_OVERSAMPLED_BLOCK_SIZE: usize = MAX_BLOCK_SIZE * MAX_OVERSAMPLING_TIMES;
+
+/// This corresponds to 1x/no oversampling, which is how Crisp worked before oversampling was
+/// added.
+const DEFAULT_OVERSAMPLING_FACTOR: usize = 0;
+
 /// These seeds being fixed makes bouncing deterministic.
 const INITIAL_PRNG_SEED: Pcg32iState = Pcg32iState::new(69, 420);
 
@@ -36,14 +48,28 @@ const AMOUNT_GAIN_MULTIPLIER: f32 = 2.0;
 const MIN_FILTER_FREQUENCY: f32 = 5.0;
 const MAX_FILTER_FREQUENCY: f32 = 22_000.0;
 
+/// The center frequencies for the all-pass filters used to decorrelate the noise in
+/// [`StereoMode::MonoWide`]. Each channel uses different frequencies, so the channels end up with
+/// different phase responses while their magnitude responses stay identical.
+const DECORRELATION_FREQUENCIES: [[f32; 3]; NUM_CHANNELS as usize] =
+    [[280.0, 1_650.0, 6_100.0], [520.0, 3_100.0, 9_800.0]];
+/// The Q parameter for the decorrelation all-pass filters. A low Q spreads the phase shift out over
+/// a wide frequency range, which keeps transients from smearing too much.
+const DECORRELATION_Q: f32 = 0.5;
+
 /// This plugin essentially layers the sound with another copy of the signal ring modulated with
 /// white (or filtered) noise. That other copy of the sound may have a low-pass filter applied to it
 /// since this effect just turns into literal noise at high frequencies.
 pub struct Crisp {
     params: Arc<CrispParams>,
 
-    /// Needed for computing the filter coefficients.
+    /// Needed for computing the filter coefficients. This is the sample rate without any
+    /// oversampling applied.
     sample_rate: f32,
+    /// The oversampling amount the filter coefficients were last computed for. When the
+    /// oversampling amount changes, the filter coefficients need to be recomputed for the new
+    /// effective sample rate.
+    filter_oversampling_times: usize,
 
     /// A PRNG for generating noise, after that we'll implement PCG ourselves so we can easily
     /// SIMD-ify this in the future.
@@ -55,6 +81,48 @@ pub struct Crisp {
     /// Resonant filters for high- and then low- passing the noise signal, to make it even brighter.
     noise_hpf: [filter::Biquad<f32>; NUM_CHANNELS as usize],
     noise_lpf: [filter::Biquad<f32>; NUM_CHANNELS as usize],
+    /// Chains of all-pass filters that turn the shared noise into decorrelated noise for every
+    /// channel in [`StereoMode::MonoWide`]. See [`DECORRELATION_FREQUENCIES`].
+    noise_decorrelators: [[filter::Biquad<f32>; 3]; NUM_CHANNELS as usize],
+
+    /// Oversampling for each channel. The ring modulation and the rectification in the crispy modes
+    /// both add a lot of high frequency content that would otherwise alias.
+    oversamplers: [Lanczos3Oversampler; NUM_CHANNELS as usize],
+
+    /// Scratch buffers that the smoothed parameters and the noise can be rendered to. Allocated on
+    /// the heap because Windows uses tiny stack sizes which may eventually cause problems in some
+    /// hosts.
+    scratch_buffers: Box<ScratchBuffers>,
+}
+
+struct ScratchBuffers {
+    amount: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    rm_input_lpf_freq: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    rm_input_lpf_q: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    noise_hpf_freq: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    noise_hpf_q: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    noise_lpf_freq: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    noise_lpf_q: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+    output_gain: [f32; MAX_OVERSAMPLED_BLOCK_SIZE],
+
+    /// The filtered noise for each channel. In mono mode only the first channel's noise is used.
+    noise: [[f32; MAX_OVERSAMPLED_BLOCK_SIZE]; NUM_CHANNELS as usize],
+}
+
+impl Default for ScratchBuffers {
+    fn default() -> Self {
+        Self {
+            amount: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            rm_input_lpf_freq: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            rm_input_lpf_q: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            noise_hpf_freq: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            noise_hpf_q: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            noise_lpf_freq: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            noise_lpf_q: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            output_gain: [0.0; MAX_OVERSAMPLED_BLOCK_SIZE],
+            noise: [[0.0; MAX_OVERSAMPLED_BLOCK_SIZE]; NUM_CHANNELS as usize],
+        }
+    }
 }
 
 #[derive(Params)]
@@ -62,6 +130,7 @@ struct CrispParams {
     /// The editor state, saved together with the parameter state so the custom scaling can be
     /// restored.
     #[persist = "editor-state"]
+    #[view_state]
     editor_state: Arc<ViziaState>,
 
     /// On a range of `[0, 1]`, how much of the modulated sound to mix in.
@@ -100,10 +169,15 @@ struct CrispParams {
     /// If set, only output the RM'ed signal. Can be useful for further processing.
     #[id = "wtonly"]
     wet_only: BoolParam,
+
+    /// The current oversampling factor. This is the 2-logarithm of the oversampling amount. 0
+    /// corresponds to 1x/no oversampling, 1 to 2x oversampling, 2 to 4x, etc..
+    #[id = "ovrsmp"]
+    oversampling_factor: IntParam,
 }
 
 /// Controls the type of modulation to apply.
-#[derive(Enum, Debug, PartialEq)]
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
 enum Mode {
     /// RM the entire waveform.
     #[id = "soggy"]
@@ -126,6 +200,12 @@ enum StereoMode {
     /// Use a different noise source per channel.
     #[id = "stereo"]
     Stereo,
+    /// Use the same noise for both channels, but run it through a different chain of all-pass
+    /// filters for every channel. This widens the effect while keeping the channels' spectra
+    /// identical, so it still mostly collapses back to mono without cancelling out.
+    #[id = "mono-wide"]
+    #[name = "Mono (wide)"]
+    MonoWide,
 }
 
 impl Default for Crisp {
@@ -134,11 +214,19 @@ impl Default for Crisp {
             params: Arc::new(CrispParams::default()),
 
             sample_rate: 1.0,
+            filter_oversampling_times: 1,
 
             prng: INITIAL_PRNG_SEED,
             rm_input_lpf: [filter::Biquad::default(); NUM_CHANNELS as usize],
             noise_hpf: [filter::Biquad::default(); NUM_CHANNELS as usize],
             noise_lpf: [filter::Biquad::default(); NUM_CHANNELS as usize],
+            noise_decorrelators: [[filter::Biquad::default(); 3]; NUM_CHANNELS as usize],
+
+            oversamplers: std::array::from_fn(|_| {
+                Lanczos3Oversampler::new(MAX_BLOCK_SIZE, MAX_OVERSAMPLING_FACTOR)
+            }),
+
+            scratch_buffers: Box::default(),
         }
     }
 }
@@ -148,11 +236,20 @@ impl Default for CrispParams {
         let f32_hz_then_khz = formatters::v2s_f32_hz_then_khz(0);
         let from_f32_hz_then_khz = formatters::s2v_f32_hz_then_khz();
 
+        // This is set by the `oversampling_factor` parameter and is used by the smoothers of the
+        // other parameters so the oversampling amount always stays in sync
+        let oversampling_times = Arc::new(AtomicF32::new(oversampling_factor_to_times(
+            DEFAULT_OVERSAMPLING_FACTOR,
+        ) as f32));
+
         Self {
             editor_state: editor::default_state(),
 
             amount: FloatParam::new("Amount", 0.35, FloatRange::Linear { min: 0.0, max: 1.0 })
-                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_smoother(SmoothingStyle::OversamplingAware(
+                    oversampling_times.clone(),
+                    &SmoothingStyle::Linear(10.0),
+                ))
                 .with_unit("%")
                 .with_value_to_string(formatters::v2s_f32_percentage(0))
                 .with_string_to_value(formatters::s2v_f32_percentage()),
@@ -169,7 +266,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             // The unit is baked into the value so we can show the disabled string
             .with_value_to_string(Arc::new(|value| {
                 if value >= MAX_FILTER_FREQUENCY {
@@ -194,7 +294,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
             noise_hpf_freq: FloatParam::new(
                 "Noise HP Frequency",
@@ -205,7 +308,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             // The unit is baked into the value so we can show the disabled string
             .with_value_to_string({
                 let f32_hz_then_khz = f32_hz_then_khz.clone();
@@ -236,7 +342,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
             noise_lpf_freq: FloatParam::new(
                 "Noise LP Frequency",
@@ -247,7 +356,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             // The unit is baked into the value so we can show the disabled string
             .with_value_to_string(Arc::new(move |value| {
                 if value >= MAX_FILTER_FREQUENCY {
@@ -272,7 +384,10 @@ impl Default for CrispParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(100.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(100.0),
+            ))
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
             output_gain: FloatParam::new(
@@ -284,11 +399,42 @@ impl Default for CrispParams {
                     max: util::db_to_gain(0.0),
                 },
             )
-            .with_smoother(SmoothingStyle::Logarithmic(10.0))
+            .with_smoother(SmoothingStyle::OversamplingAware(
+                oversampling_times.clone(),
+                &SmoothingStyle::Logarithmic(10.0),
+            ))
             .with_unit(" dB")
             .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
             .with_string_to_value(formatters::s2v_f32_gain_to_db()),
             wet_only: BoolParam::new("Wet Only", false),
+
+            oversampling_factor: IntParam::new(
+                "Oversampling",
+                DEFAULT_OVERSAMPLING_FACTOR as i32,
+                IntRange::Linear {
+                    min: 0,
+                    max: MAX_OVERSAMPLING_FACTOR as i32,
+                },
+            )
+            .with_unit("x")
+            .with_callback(Arc::new(move |new_factor| {
+                oversampling_times.store(
+                    oversampling_factor_to_times(new_factor as usize) as f32,
+                    Ordering::Relaxed,
+                );
+            }))
+            .with_value_to_string(Arc::new(|value| {
+                // NIH-plug prevents `value` from being out of range and thus negative
+                let oversampling_times = oversampling_factor_to_times(value as usize);
+
+                oversampling_times.to_string()
+            }))
+            .with_string_to_value(Arc::new(|string| {
+                let oversampling_times: usize = string.parse().ok()?;
+
+                Some(oversampling_times_to_factor(oversampling_times) as i32)
+            }))
+            .affects_latency(),
         }
     }
 }
@@ -325,14 +471,18 @@ impl Plugin for Crisp {
         &mut self,
         _audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.sample_rate = buffer_config.sample_rate;
+        self.set_sample_rate(buffer_config.sample_rate);
 
         // The filter coefficients need to be reinitialized when loading a patch
-        self.update_rm_input_lpf();
-        self.update_noise_hpf();
-        self.update_noise_lpf();
+        self.filter_oversampling_times =
+            oversampling_factor_to_times(self.params.oversampling_factor.value() as usize);
+        self.update_all_filters();
+
+        context.set_latency_samples(
+            self.oversamplers[0].latency(self.params.oversampling_factor.value() as usize),
+        );
 
         true
     }
@@ -341,14 +491,9 @@ impl Plugin for Crisp {
         // By using the same seeds each time bouncing can be made deterministic
         self.prng = INITIAL_PRNG_SEED;
 
-        for filter in &mut self.rm_input_lpf {
-            filter.reset();
-        }
-        for filter in &mut self.noise_hpf {
-            filter.reset();
-        }
-        for filter in &mut self.noise_lpf {
-            filter.reset();
+        self.reset_filters();
+        for oversampler in &mut self.oversamplers {
+            oversampler.reset();
         }
     }
 
@@ -356,60 +501,153 @@ impl Plugin for Crisp {
         &mut self,
         buffer: &mut Buffer,
         _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        for (_, mut block) in buffer.iter_blocks(MAX_BLOCK_SIZE) {
-            let mut rm_outputs = [[0.0; NUM_CHANNELS as usize]; MAX_BLOCK_SIZE];
-
-            // Reduce per-sample branching a bit by iterating over smaller blocks and only then
-            // deciding what to do with the output. This version branches only once per sample (in
-            // `do_ring_mod()`) which can be trivially optimized to a masked min/max later.
-            // TODO: SIMD-ize this to process both channels at once
-            match self.params.stereo_mode.value() {
-                StereoMode::Mono => {
-                    for (channel_samples, rm_outputs) in block.iter_samples().zip(&mut rm_outputs) {
-                        let amount = self.params.amount.smoothed.next() * AMOUNT_GAIN_MULTIPLIER;
-
-                        // Controls the pre-RM LPF and the HPF applied to the noise signal
-                        self.maybe_update_filters();
-
-                        let noise = self.gen_noise(0);
-                        for (channel_idx, (sample, rm_output)) in
-                            channel_samples.into_iter().zip(rm_outputs).enumerate()
-                        {
-                            *rm_output = self.do_ring_mod(*sample, channel_idx, noise) * amount;
-                        }
+        let oversampling_factor = self.params.oversampling_factor.value() as usize;
+        let oversampling_times = oversampling_factor_to_times(oversampling_factor);
+
+        // If the oversampling factor parameter is changed then the host needs to know about the new
+        // latency, and the filters need to be recomputed for the new effective sample rate
+        context.set_latency_samples(self.oversamplers[0].latency(oversampling_factor));
+        if oversampling_times != self.filter_oversampling_times {
+            self.filter_oversampling_times = oversampling_times;
+            self.update_all_filters();
+        }
+
+        let mode = self.params.mode.value();
+        let stereo_mode = self.params.stereo_mode.value();
+        let wet_only = self.params.wet_only.value();
+        let oversampled_sample_rate = self.sample_rate * oversampling_times as f32;
+
+        for (_, block) in buffer.iter_blocks(MAX_BLOCK_SIZE) {
+            let upsampled_block_len = block.samples() * oversampling_times;
+
+            // These need to be checked before rendering the smoothed values, since the smoothers
+            // may reach their targets partway through the block
+            let rm_input_lpf_smoothing = self.params.rm_input_lpf_freq.smoothed.is_smoothing()
+                || self.params.rm_input_lpf_q.smoothed.is_smoothing();
+            let noise_hpf_smoothing = self.params.noise_hpf_freq.smoothed.is_smoothing()
+                || self.params.noise_hpf_q.smoothed.is_smoothing();
+            let noise_lpf_smoothing = self.params.noise_lpf_freq.smoothed.is_smoothing()
+                || self.params.noise_lpf_q.smoothed.is_smoothing();
+
+            let scratch_buffers = &mut *self.scratch_buffers;
+            for (param, values) in [
+                (&self.params.amount, &mut scratch_buffers.amount),
+                (
+                    &self.params.rm_input_lpf_freq,
+                    &mut scratch_buffers.rm_input_lpf_freq,
+                ),
+                (
+                    &self.params.rm_input_lpf_q,
+                    &mut scratch_buffers.rm_input_lpf_q,
+                ),
+                (
+                    &self.params.noise_hpf_freq,
+                    &mut scratch_buffers.noise_hpf_freq,
+                ),
+                (&self.params.noise_hpf_q, &mut scratch_buffers.noise_hpf_q),
+                (
+                    &self.params.noise_lpf_freq,
+                    &mut scratch_buffers.noise_lpf_freq,
+                ),
+                (&self.params.noise_lpf_q, &mut scratch_buffers.noise_lpf_q),
+                (&self.params.output_gain, &mut scratch_buffers.output_gain),
+            ] {
+                param.smoothed.next_block(values, upsampled_block_len);
+            }
+
+            // The noise is generated at the oversampled rate ahead of time. In mono mode all
+            // channels share the first channel's noise, and in the wide mono mode the first
+            // channel's noise is decorrelated separately for every channel.
+            let num_noise_channels = match stereo_mode {
+                StereoMode::Mono => 1,
+                StereoMode::Stereo | StereoMode::MonoWide => NUM_CHANNELS as usize,
+            };
+            for sample_idx in 0..upsampled_block_len {
+                if noise_hpf_smoothing {
+                    let coefficients = filter::BiquadCoefficients::highpass(
+                        oversampled_sample_rate,
+                        self.scratch_buffers.noise_hpf_freq[sample_idx],
+                        self.scratch_buffers.noise_hpf_q[sample_idx],
+                    );
+                    for filter in &mut self.noise_hpf {
+                        filter.coefficients = coefficients;
                     }
                 }
-                StereoMode::Stereo => {
-                    for (channel_samples, rm_outputs) in block.iter_samples().zip(&mut rm_outputs) {
-                        let amount = self.params.amount.smoothed.next() * AMOUNT_GAIN_MULTIPLIER;
-                        self.maybe_update_filters();
-                        for (channel_idx, (sample, rm_output)) in
-                            channel_samples.into_iter().zip(rm_outputs).enumerate()
-                        {
-                            let noise = self.gen_noise(channel_idx);
-                            *rm_output = self.do_ring_mod(*sample, channel_idx, noise) * amount;
-                        }
+                if noise_lpf_smoothing {
+                    let coefficients = filter::BiquadCoefficients::lowpass(
+                        oversampled_sample_rate,
+                        self.scratch_buffers.noise_lpf_freq[sample_idx],
+                        self.scratch_buffers.noise_lpf_q[sample_idx],
+                    );
+                    for filter in &mut self.noise_lpf {
+                        filter.coefficients = coefficients;
                     }
                 }
-            }
 
-            if self.params.wet_only.value() {
-                for (channel_samples, rm_outputs) in block.iter_samples().zip(&mut rm_outputs) {
-                    let output_gain = self.params.output_gain.smoothed.next();
-                    for (sample, rm_output) in channel_samples.into_iter().zip(rm_outputs) {
-                        *sample = *rm_output * output_gain;
+                if stereo_mode == StereoMode::MonoWide {
+                    let noise = self.gen_noise(0);
+                    for (channel_noise, decorrelators) in self
+                        .scratch_buffers
+                        .noise
+                        .iter_mut()
+                        .zip(self.noise_decorrelators.iter_mut())
+                    {
+                        channel_noise[sample_idx] = decorrelators
+                            .iter_mut()
+                            .fold(noise, |sample, filter| filter.process(sample));
                     }
-                }
-            } else {
-                for (channel_samples, rm_outputs) in block.iter_samples().zip(&mut rm_outputs) {
-                    let output_gain = self.params.output_gain.smoothed.next();
-                    for (sample, rm_output) in channel_samples.into_iter().zip(rm_outputs) {
-                        *sample = (*sample + *rm_output) * output_gain;
+                } else {
+                    for channel_idx in 0..num_noise_channels {
+                        self.scratch_buffers.noise[channel_idx][sample_idx] =
+                            self.gen_noise(channel_idx);
                     }
                 }
             }
+
+            let scratch_buffers = &*self.scratch_buffers;
+            for (channel_idx, (block_channel, (oversampler, rm_input_lpf))) in block
+                .into_iter()
+                .zip(
+                    self.oversamplers
+                        .iter_mut()
+                        .zip(self.rm_input_lpf.iter_mut()),
+                )
+                .enumerate()
+            {
+                let noise = &scratch_buffers.noise[channel_idx % num_noise_channels];
+
+                // The dry signal is also mixed in at the oversampled rate so it stays aligned with
+                // the oversampled RM signal
+                oversampler.process(block_channel, oversampling_factor, |upsampled| {
+                    assert!(upsampled.len() == upsampled_block_len);
+
+                    for (sample_idx, sample) in upsampled.iter_mut().enumerate() {
+                        if rm_input_lpf_smoothing {
+                            rm_input_lpf.coefficients = filter::BiquadCoefficients::lowpass(
+                                oversampled_sample_rate,
+                                scratch_buffers.rm_input_lpf_freq[sample_idx],
+                                scratch_buffers.rm_input_lpf_q[sample_idx],
+                            );
+                        }
+
+                        let rm_output = ring_mod(
+                            mode,
+                            rm_input_lpf.process_denormal_safe(*sample),
+                            noise[sample_idx],
+                        ) * scratch_buffers.amount[sample_idx]
+                            * AMOUNT_GAIN_MULTIPLIER;
+
+                        let output_gain = scratch_buffers.output_gain[sample_idx];
+                        if wet_only {
+                            *sample = rm_output * output_gain;
+                        } else {
+                            *sample = (*sample + rm_output) * output_gain;
+                        }
+                    }
+                });
+            }
         }
 
         ProcessStatus::Normal
@@ -417,82 +655,113 @@ impl Plugin for Crisp {
 }
 
 impl Crisp {
-    /// Generate a new noise sample with the high pass filter applied.
-    fn gen_noise(&mut self, channel: usize) -> f32 {
-        let noise = self.prng.next_f32() * 2.0 - 1.0;
-        let high_passed = self.noise_hpf[channel].process(noise);
-        self.noise_lpf[channel].process(high_passed)
-    }
-
-    /// Perform the RM step depending on the mode. This applies a low pass filter to the input
-    /// before RM'ing.
-    fn do_ring_mod(&mut self, sample: f32, channel_idx: usize, noise: f32) -> f32 {
-        let sample = self.rm_input_lpf[channel_idx].process(sample);
-
-        // TODO: Avoid branching in the main loop, this just makes it a bit easier to prototype
-        match self.params.mode.value() {
-            Mode::Soggy => sample * noise,
-            Mode::Crispy => sample.max(0.0) * noise,
-            Mode::CrispyNegated => sample.max(0.0) * noise,
+    /// Change the sample rate used to compute the filter coefficients. If the sample rate differs
+    /// from the previous one, then the filters' states are also cleared since those states only
+    /// make sense for the old coefficients. The coefficients themselves still need to be updated
+    /// using [`update_all_filters()`][Self::update_all_filters()].
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.reset_filters();
         }
     }
 
-    /// Update the filter coefficients if needed. Should be called once per sample.
-    fn maybe_update_filters(&mut self) {
-        if self.params.rm_input_lpf_freq.smoothed.is_smoothing()
-            || self.params.rm_input_lpf_q.smoothed.is_smoothing()
-        {
-            self.update_rm_input_lpf();
+    /// Clear the state of all filters without touching their coefficients.
+    fn reset_filters(&mut self) {
+        for filter in &mut self.rm_input_lpf {
+            filter.reset();
         }
-        if self.params.noise_hpf_freq.smoothed.is_smoothing()
-            || self.params.noise_hpf_q.smoothed.is_smoothing()
-        {
-            self.update_noise_hpf();
+        for filter in &mut self.noise_hpf {
+            filter.reset();
         }
-        if self.params.noise_lpf_freq.smoothed.is_smoothing()
-            || self.params.noise_lpf_q.smoothed.is_smoothing()
-        {
-            self.update_noise_lpf();
+        for filter in &mut self.noise_lpf {
+            filter.reset();
+        }
+        for filter in self.noise_decorrelators.iter_mut().flatten() {
+            filter.reset();
         }
     }
 
-    /// Update the filter coefficients if needed. Should be called explicitly from `initialize()`.
-    fn update_rm_input_lpf(&mut self) {
-        let frequency = self.params.rm_input_lpf_freq.smoothed.next();
-        let q = self.params.rm_input_lpf_q.smoothed.next();
-        let coefficients = filter::BiquadCoefficients::lowpass(self.sample_rate, frequency, q);
+    /// Generate a new noise sample with the high pass filter applied.
+    fn gen_noise(&mut self, channel: usize) -> f32 {
+        let noise = self.prng.next_f32() * 2.0 - 1.0;
+        let high_passed = self.noise_hpf[channel].process(noise);
+        self.noise_lpf[channel].process(high_passed)
+    }
+
+    /// Recompute all filter coefficients using the parameters' current smoothed values and the
+    /// current oversampling amount. Should be called explicitly from `initialize()` and when the
+    /// oversampling amount changes.
+    fn update_all_filters(&mut self) {
+        let sample_rate = self.sample_rate * self.filter_oversampling_times as f32;
+
+        let coefficients = filter::BiquadCoefficients::lowpass(
+            sample_rate,
+            self.params.rm_input_lpf_freq.smoothed.previous_value(),
+            self.params.rm_input_lpf_q.smoothed.previous_value(),
+        );
         for filter in &mut self.rm_input_lpf {
             filter.coefficients = coefficients;
         }
-    }
 
-    /// Update the filter coefficients if needed. Should be called explicitly from `initialize()`.
-    fn update_noise_hpf(&mut self) {
-        let frequency = self.params.noise_hpf_freq.smoothed.next();
-        let q = self.params.noise_hpf_q.smoothed.next();
-        let coefficients = filter::BiquadCoefficients::highpass(self.sample_rate, frequency, q);
+        let coefficients = filter::BiquadCoefficients::highpass(
+            sample_rate,
+            self.params.noise_hpf_freq.smoothed.previous_value(),
+            self.params.noise_hpf_q.smoothed.previous_value(),
+        );
         for filter in &mut self.noise_hpf {
             filter.coefficients = coefficients;
         }
-    }
 
-    /// Update the filter coefficients if needed. Should be called explicitly from `initialize()`.
-    fn update_noise_lpf(&mut self) {
-        let frequency = self.params.noise_lpf_freq.smoothed.next();
-        let q = self.params.noise_lpf_q.smoothed.next();
-        let coefficients = filter::BiquadCoefficients::lowpass(self.sample_rate, frequency, q);
+        let coefficients = filter::BiquadCoefficients::lowpass(
+            sample_rate,
+            self.params.noise_lpf_freq.smoothed.previous_value(),
+            self.params.noise_lpf_q.smoothed.previous_value(),
+        );
         for filter in &mut self.noise_lpf {
             filter.coefficients = coefficients;
         }
+
+        // These frequencies are fixed, but they still depend on the oversampled sample rate
+        for (decorrelators, frequencies) in self
+            .noise_decorrelators
+            .iter_mut()
+            .zip(DECORRELATION_FREQUENCIES)
+        {
+            for (filter, frequency) in decorrelators.iter_mut().zip(frequencies) {
+                filter.coefficients = filter::BiquadCoefficients::allpass(
+                    sample_rate,
+                    frequency.min(sample_rate * 0.45),
+                    DECORRELATION_Q,
+                );
+            }
+        }
+    }
+}
+
+/// Perform the RM step depending on the mode. The input should already have been low pass
+/// filtered.
+fn ring_mod(mode: Mode, sample: f32, noise: f32) -> f32 {
+    match mode {
+        Mode::Soggy => sample * noise,
+        Mode::Crispy => sample.max(0.0) * noise,
+        Mode::CrispyNegated => sample.max(0.0) * noise,
     }
 }
 
+// Used in the conversion for the oversampling amount parameter
+const fn oversampling_factor_to_times(factor: usize) -> usize {
+    2usize.pow(factor as u32)
+}
+
+const fn oversampling_times_to_factor(times: usize) -> usize {
+    times.ilog2() as usize
+}
+
 impl ClapPlugin for Crisp {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.crisp";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("Adds a bright crispy top end to low bass sounds");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
@@ -512,3 +781,111 @@ impl Vst3Plugin for Crisp {
 
 nih_export_clap!(Crisp);
 nih_export_vst3!(Crisp);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a Crisp instance with its filter coefficients computed for the given sample rate. The
+    /// wrapper normally resets the smoothers before calling `initialize()`, so that needs to be
+    /// done manually here.
+    fn initialized_crisp(sample_rate: f32) -> Crisp {
+        let mut crisp = Crisp::default();
+        for param in [
+            &crisp.params.rm_input_lpf_freq,
+            &crisp.params.rm_input_lpf_q,
+            &crisp.params.noise_hpf_freq,
+            &crisp.params.noise_hpf_q,
+            &crisp.params.noise_lpf_freq,
+            &crisp.params.noise_lpf_q,
+        ] {
+            param.smoothed.reset(param.value());
+        }
+
+        crisp.set_sample_rate(sample_rate);
+        crisp.update_all_filters();
+
+        crisp
+    }
+
+    /// Process a couple of samples through all of Crisp's filters to fill their states.
+    fn excite_filters(crisp: &mut Crisp) {
+        for _ in 0..16 {
+            for filter in crisp
+                .rm_input_lpf
+                .iter_mut()
+                .chain(crisp.noise_hpf.iter_mut())
+                .chain(crisp.noise_lpf.iter_mut())
+            {
+                filter.process(1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_rate_change_resets_filters() {
+        let mut crisp = initialized_crisp(44_100.0);
+        excite_filters(&mut crisp);
+
+        crisp.set_sample_rate(48_000.0);
+        crisp.update_all_filters();
+
+        let mut fresh_crisp = initialized_crisp(48_000.0);
+
+        for (filter, fresh_filter) in crisp
+            .rm_input_lpf
+            .iter_mut()
+            .chain(crisp.noise_hpf.iter_mut())
+            .chain(crisp.noise_lpf.iter_mut())
+            .zip(
+                fresh_crisp
+                    .rm_input_lpf
+                    .iter_mut()
+                    .chain(fresh_crisp.noise_hpf.iter_mut())
+                    .chain(fresh_crisp.noise_lpf.iter_mut()),
+            )
+        {
+            assert_eq!(filter.process(1.0), fresh_filter.process(1.0));
+            assert_eq!(filter.process(0.0), fresh_filter.process(0.0));
+        }
+    }
+
+    #[test]
+    fn test_same_sample_rate_keeps_filter_state() {
+        let mut crisp = initialized_crisp(44_100.0);
+        excite_filters(&mut crisp);
+
+        crisp.set_sample_rate(44_100.0);
+
+        // The high-pass filter's output for silence would be zero if its state had been cleared
+        assert_ne!(crisp.noise_hpf[0].process(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_noise_decorrelation_keeps_energy() {
+        let mut crisp = initialized_crisp(44_100.0);
+
+        // The decorrelators are all-pass filters, so an impulse's energy should be preserved while
+        // the channels' impulse responses differ from each other
+        let impulse_responses: Vec<Vec<f32>> = crisp
+            .noise_decorrelators
+            .iter_mut()
+            .map(|decorrelators| {
+                (0..44_100)
+                    .map(|sample_idx| {
+                        let impulse = if sample_idx == 0 { 1.0 } else { 0.0 };
+                        decorrelators
+                            .iter_mut()
+                            .fold(impulse, |sample, filter| filter.process(sample))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for impulse_response in &impulse_responses {
+            let energy: f32 = impulse_response.iter().map(|sample| sample * sample).sum();
+            assert!((energy - 1.0).abs() < 1e-3, "Energy: {energy}");
+        }
+        assert_ne!(impulse_responses[0], impulse_responses[1]);
+    }
+}
@@ -22,7 +22,8 @@ use std::sync::Arc;
 
 use self::filter::{FftFirFilter, FirCoefficients, FFT_INPUT_SIZE, FFT_SIZE};
 use crate::crossover::fir::filter::FILTER_SIZE;
-use crate::crossover::iir::biquad::{BiquadCoefficients, NEUTRAL_Q};
+use crate::crossover::iir::biquad::BiquadCoefficients;
+use crate::crossover::iir::{ButterworthSection, IirCrossoverType};
 use crate::{NUM_BANDS, NUM_CHANNELS};
 
 pub mod filter;
@@ -71,12 +72,37 @@ pub struct FirCrossover {
 }
 
 /// The type of FIR crossover to use.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum FirCrossoverType {
-    /// Emulates the filter slope of [`super::iir::IirCrossoverType`], but with linear-phase FIR
-    /// filters instead of minimum-phase IIR filters. The exact same filters are used to design the
-    /// FIR filters.
+    /// Emulates the filter slope of [`IirCrossoverType::LinkwitzRiley12`], but with linear-phase
+    /// FIR filters instead of minimum-phase IIR filters. The exact same filters are used to design
+    /// the FIR filters.
+    LinkwitzRiley12LinearPhase,
+    /// Emulates the filter slope of [`IirCrossoverType::LinkwitzRiley24`], but with linear-phase
+    /// FIR filters instead of minimum-phase IIR filters. The exact same filters are used to design
+    /// the FIR filters.
     LinkwitzRiley24LinearPhase,
+    /// Emulates the filter slope of [`IirCrossoverType::LinkwitzRiley36`], but with linear-phase
+    /// FIR filters instead of minimum-phase IIR filters. The exact same filters are used to design
+    /// the FIR filters.
+    LinkwitzRiley36LinearPhase,
+    /// Emulates the filter slope of [`IirCrossoverType::LinkwitzRiley48`], but with linear-phase
+    /// FIR filters instead of minimum-phase IIR filters. The exact same filters are used to design
+    /// the FIR filters.
+    LinkwitzRiley48LinearPhase,
+}
+
+impl FirCrossoverType {
+    /// The IIR crossover type whose magnitude response this crossover type emulates.
+    pub fn iir_equivalent(self) -> IirCrossoverType {
+        match self {
+            FirCrossoverType::LinkwitzRiley12LinearPhase => IirCrossoverType::LinkwitzRiley12,
+            FirCrossoverType::LinkwitzRiley24LinearPhase => IirCrossoverType::LinkwitzRiley24,
+            FirCrossoverType::LinkwitzRiley36LinearPhase => IirCrossoverType::LinkwitzRiley36,
+            FirCrossoverType::LinkwitzRiley48LinearPhase => IirCrossoverType::LinkwitzRiley48,
+        }
+    }
 }
 
 impl FirCrossover {
@@ -108,14 +134,26 @@ impl FirCrossover {
     /// Get the current latency in samples. This depends on the selected mode.
     pub fn latency(&self) -> u32 {
         // Actually, that's a lie, since we currently only do linear-phase filters with a constant
-        // size
+        // size. The steeper slopes use the same filter size.
         match self.mode {
-            FirCrossoverType::LinkwitzRiley24LinearPhase => {
+            FirCrossoverType::LinkwitzRiley12LinearPhase
+            | FirCrossoverType::LinkwitzRiley24LinearPhase
+            | FirCrossoverType::LinkwitzRiley36LinearPhase
+            | FirCrossoverType::LinkwitzRiley48LinearPhase => {
                 (FFT_INPUT_SIZE + (FILTER_SIZE / 2)) as u32
             }
         }
     }
 
+    /// Change the crossover type. The filter state is reset when this changes the type.
+    /// `.update()` must be called after changing this.
+    pub fn set_mode(&mut self, mode: FirCrossoverType) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
     /// Split the signal into bands using the crossovers previously configured through `.update()`.
     /// The split bands will be written to `band_outputs`. The main output should be cleared
     /// separately. For efficiency's sake this processes an entire channel at once to minimize the
@@ -215,11 +253,14 @@ impl FirCrossover {
         frequencies: [f32; NUM_BANDS - 1],
     ) {
         match self.mode {
-            FirCrossoverType::LinkwitzRiley24LinearPhase => {
+            FirCrossoverType::LinkwitzRiley12LinearPhase
+            | FirCrossoverType::LinkwitzRiley24LinearPhase
+            | FirCrossoverType::LinkwitzRiley36LinearPhase
+            | FirCrossoverType::LinkwitzRiley48LinearPhase => {
                 // The goal here is to design 2-5 filters with the same frequency response
-                // magnitudes as the split bands in the IIR LR24 crossover version with the same
-                // center frequencies would have. The algorithm works in two stages. First, the IIR
-                // low-pass filters for the 1-4 crossovers used in the equivalent IIR LR24 version
+                // magnitudes as the split bands in the equivalent IIR crossover version with the
+                // same center frequencies would have. The algorithm works in two stages. First, the
+                // IIR low-pass filters for the 1-4 crossovers used in the equivalent IIR version
                 // are computed and converted to equivalent linear-phase FIR filters using the
                 // algorithm described below in `FirCoefficients`. Then these are used to build the
                 // coefficients for the 2-5 bands:
@@ -236,11 +277,9 @@ impl FirCrossover {
 
                 // As explained above, we'll start with the low-pass band
                 nih_debug_assert!(num_bands >= 2);
-                let iir_coefs = BiquadCoefficients::lowpass(sample_rate, frequencies[0], NEUTRAL_Q);
+                let sections = self.mode.iir_equivalent().butterworth_sections();
                 let lp_fir_coefs =
-                    FirCoefficients::design_fourth_order_linear_phase_low_pass_from_biquad(
-                        iir_coefs,
-                    );
+                    design_linear_phase_low_pass(sample_rate, frequencies[0], sections);
                 self.band_filters[0].recompute_coefficients(
                     lp_fir_coefs.clone(),
                     &*self.r2c_plan,
@@ -260,12 +299,8 @@ impl FirCrossover {
                     // And the first band is already taken care of
                     .skip(1)
                 {
-                    let iir_coefs =
-                        BiquadCoefficients::lowpass(sample_rate, *split_frequency, NEUTRAL_Q);
                     let lp_fir_coefs =
-                        FirCoefficients::design_fourth_order_linear_phase_low_pass_from_biquad(
-                            iir_coefs,
-                        );
+                        design_linear_phase_low_pass(sample_rate, *split_frequency, sections);
 
                     // We want the band between the accumulated frequency response and the next
                     // crossover's low-pass filter
@@ -331,3 +366,95 @@ impl FirCrossover {
         self.io_buffers_next_indices.fill(0);
     }
 }
+
+/// Design a linear-phase low-pass FIR filter with the same magnitude response as a Linkwitz-Riley
+/// low-pass filter built from `sections`. Since the FIR design bidirectionally filters an impulse,
+/// each Butterworth section only needs to be applied once.
+fn design_linear_phase_low_pass(
+    sample_rate: f32,
+    frequency: f32,
+    sections: &[ButterworthSection],
+) -> FirCoefficients<FILTER_SIZE> {
+    let mut iir_coefs = [BiquadCoefficients::identity(); 2];
+    nih_debug_assert!(sections.len() <= iir_coefs.len());
+
+    for (coefs, section) in iir_coefs.iter_mut().zip(sections) {
+        *coefs = match section {
+            ButterworthSection::FirstOrder => {
+                BiquadCoefficients::lowpass_first_order(sample_rate, frequency)
+            }
+            ButterworthSection::SecondOrder(q) => {
+                BiquadCoefficients::lowpass(sample_rate, frequency, *q)
+            }
+        };
+    }
+
+    FirCoefficients::design_linear_phase_low_pass_from_biquads(&iir_coefs[..sections.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const CROSSOVER_FREQUENCY: f32 = 1_000.0;
+
+    /// The magnitude response of a linear-phase FIR filter, ignoring the filter's delay.
+    fn magnitude(coefficients: &FirCoefficients<FILTER_SIZE>, frequency: f32) -> f32 {
+        let omega = 2.0 * PI * frequency / SAMPLE_RATE;
+        let center_idx = FILTER_SIZE / 2;
+        let (re, im) = coefficients.0.iter().enumerate().fold(
+            (0.0f64, 0.0f64),
+            |(re, im), (sample_idx, sample)| {
+                let phase = (omega * (sample_idx as f32 - center_idx as f32)) as f64;
+                (
+                    re + (*sample as f64 * phase.cos()),
+                    im - (*sample as f64 * phase.sin()),
+                )
+            },
+        );
+
+        re.hypot(im) as f32
+    }
+
+    #[test]
+    fn test_linear_phase_responses() {
+        for (crossover_type, order) in [
+            (FirCrossoverType::LinkwitzRiley12LinearPhase, 2),
+            (FirCrossoverType::LinkwitzRiley24LinearPhase, 4),
+            (FirCrossoverType::LinkwitzRiley36LinearPhase, 6),
+            (FirCrossoverType::LinkwitzRiley48LinearPhase, 8),
+        ] {
+            let coefficients = design_linear_phase_low_pass(
+                SAMPLE_RATE,
+                CROSSOVER_FREQUENCY,
+                crossover_type.iir_equivalent().butterworth_sections(),
+            );
+
+            let dc_magnitude = magnitude(&coefficients, 0.0);
+            assert!(
+                (dc_magnitude - 1.0).abs() < 1e-4,
+                "{crossover_type:?}: {dc_magnitude}"
+            );
+
+            // The low-pass filter should have the same magnitude response as its IIR equivalent.
+            // The high-pass filter is computed through spectral inversion, so it will also match.
+            for frequency in [
+                CROSSOVER_FREQUENCY / 2.0,
+                CROSSOVER_FREQUENCY,
+                CROSSOVER_FREQUENCY * 2.0,
+            ] {
+                let warped_ratio = (PI * frequency / SAMPLE_RATE).tan()
+                    / (PI * CROSSOVER_FREQUENCY / SAMPLE_RATE).tan();
+                let expected = 1.0 / (1.0 + warped_ratio.powi(order));
+                let lp_magnitude = magnitude(&coefficients, frequency);
+                assert!(
+                    (lp_magnitude - expected).abs() < expected * 2e-2,
+                    "{crossover_type:?} at {frequency} Hz: {lp_magnitude} != {expected}"
+                );
+            }
+        }
+    }
+}
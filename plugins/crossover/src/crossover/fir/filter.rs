@@ -176,14 +176,15 @@ impl FftFirFilter {
 
 impl<const N: usize> FirCoefficients<N> {
     /// A somewhat crude but very functional and relatively fast way create linear phase FIR
-    /// **low-pass** filter that matches the frequency response of a fourth order biquad low-pass
-    /// filter. As in, this matches the frequency response magnitudes of applying those biquads to a
-    /// signal twice. This only works for low-pass filters, as the function normalizes the result to
-    /// hae unity gain at the DC bin. The algorithm works as follows:
+    /// **low-pass** filter that matches the frequency response of a series of biquad low-pass
+    /// filters. As in, this matches the frequency response magnitudes of applying those biquads to
+    /// a signal twice. So a single second order Butterworth filter results in a linear-phase LR24
+    /// filter. This only works for low-pass filters, as the function normalizes the result to hae
+    /// unity gain at the DC bin. The algorithm works as follows:
     ///
     /// - An impulse function (so all zeroes except for the first element) of length `FILTER_LEN / 2
-    ///   + 1` is filtered with the biquad.
-    /// - The biquad's state is reset, and the impulse response is filtered in the opposite
+    ///   + 1` is filtered with each of the biquads in series.
+    /// - The biquads' states are reset, and the impulse response is filtered in the opposite
     ///   direction.
     /// - At this point the bidirectionally filtered impulse response contains the **right** half of
     ///   a truncated linear phase FIR kernel.
@@ -206,8 +207,8 @@ impl<const N: usize> FirCoefficients<N> {
     ///   the coefficients. (one of the copies doesn't need to include the centermost coefficient)
     ///
     /// The corresponding high-pass filter can be computed through spectral inversion.
-    pub fn design_fourth_order_linear_phase_low_pass_from_biquad(
-        biquad_coefs: BiquadCoefficients<f32>,
+    pub fn design_linear_phase_low_pass_from_biquads(
+        biquad_coefs: &[BiquadCoefficients<f32>],
     ) -> Self {
         // Rust doesn't allow you to define this as a constant
         let center_idx = N / 2;
@@ -216,16 +217,21 @@ impl<const N: usize> FirCoefficients<N> {
         let mut impulse_response = [0.0; N];
         impulse_response[center_idx] = 1.0;
 
-        // ...and filter that in both directions
-        let mut biquad = Biquad::default();
-        biquad.coefficients = biquad_coefs;
-        for sample in impulse_response.iter_mut().skip(center_idx - 1) {
-            *sample = biquad.process(*sample);
+        // ...and filter that in both directions. The order of the filters doesn't matter here.
+        for coefs in biquad_coefs {
+            let mut biquad = Biquad::default();
+            biquad.coefficients = *coefs;
+            for sample in impulse_response.iter_mut().skip(center_idx - 1) {
+                *sample = biquad.process(*sample);
+            }
         }
 
-        biquad.reset();
-        for sample in impulse_response.iter_mut().skip(center_idx - 1).rev() {
-            *sample = biquad.process(*sample);
+        for coefs in biquad_coefs {
+            let mut biquad = Biquad::default();
+            biquad.coefficients = *coefs;
+            for sample in impulse_response.iter_mut().skip(center_idx - 1).rev() {
+                *sample = biquad.process(*sample);
+            }
         }
 
         // Now the right half of `impulse_response` contains a truncated right half of the
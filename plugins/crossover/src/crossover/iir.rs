@@ -16,13 +16,25 @@
 
 use nih_plug::buffer::ChannelSamples;
 use nih_plug::debug::*;
-use std::simd::f32x2;
 
 use self::biquad::{Biquad, BiquadCoefficients, NEUTRAL_Q};
+use crate::simd::f32x2;
+#[cfg(not(feature = "simd"))]
+use crate::simd::ChannelSamplesExt;
 use crate::NUM_BANDS;
 
 pub mod biquad;
 
+/// The maximum number of biquads used for either the low-pass or the high-pass section of a single
+/// crossover. This is needed for the LR48 crossovers.
+const MAX_CROSSOVER_STAGES: usize = 4;
+/// The maximum number of all-pass filters needed to compensate for a single crossover's phase
+/// shift. This is needed for the LR36 and LR48 crossovers.
+const MAX_ALL_PASS_STAGES: usize = 2;
+
+/// The Q values for the two second order sections of a fourth order Butterworth filter.
+const BUTTERWORTH_4_Q: [f32; 2] = [0.541_196_1, 1.306_563];
+
 #[derive(Debug)]
 pub struct IirCrossover {
     /// The kind of crossover to use. `.update_filters()` must be called after changing this.
@@ -36,11 +48,32 @@ pub struct IirCrossover {
 }
 
 /// The type of IIR crossover to use.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IirCrossoverType {
+    /// Crossover with gentle 12 dB/octave slopes. Uses a squared first order Butterworth filter
+    /// per crossover. The high-passed band is polarity inverted so the bands sum to a flat
+    /// magnitude response.
+    LinkwitzRiley12,
     /// Clean crossover with 24 dB/octave slopes and one period of delay in the power band. Stacks
     /// two Butterworth-style (i.e. $q = \frac{\sqrt{2}}{2}$) filters per crossover.
     LinkwitzRiley24,
+    /// Crossover with 36 dB/octave slopes. Uses a squared third order Butterworth filter per
+    /// crossover. Like with the LR12 crossover, the high-passed band is polarity inverted.
+    LinkwitzRiley36,
+    /// Steep crossover with 48 dB/octave slopes. Uses a squared fourth order Butterworth filter per
+    /// crossover.
+    LinkwitzRiley48,
+}
+
+/// A section of a Butterworth filter. A Linkwitz-Riley filter of order `2n` is an `n`th order
+/// Butterworth filter applied twice, and the sum of its low-pass and high-pass outputs has the same
+/// phase response as all-pass filters built from the same sections.
+#[derive(Debug, Clone, Copy)]
+pub enum ButterworthSection {
+    /// A first order section. Applying this twice is equivalent to a biquad with $q = 0.5$.
+    FirstOrder,
+    /// A second order section with a Q value.
+    SecondOrder(f32),
 }
 
 /// A single crossover using multiple biquads in series to get steeper slopes. This can do both the
@@ -49,10 +82,15 @@ pub enum IirCrossoverType {
 struct Crossover {
     /// Filters for the low-pass section of the crossover. Not all filters may be used dependign on
     /// the crossover type.
-    lp_filters: [Biquad<f32x2>; 2],
+    lp_filters: [Biquad<f32x2>; MAX_CROSSOVER_STAGES],
     /// Filters for the high-pass section of the crossover. Not all filters may be used dependign on
     /// the crossover type.
-    hp_filters: [Biquad<f32x2>; 2],
+    hp_filters: [Biquad<f32x2>; MAX_CROSSOVER_STAGES],
+    /// The number of filters from `lp_filters` and `hp_filters` that are in use.
+    num_stages: usize,
+    /// Whether the high-passed output should be polarity inverted. This is needed for the
+    /// crossovers built from odd order Butterworth filters.
+    invert_hp: bool,
 }
 
 /// The crossover is super simple and feeds the low-passed result to the next band output while
@@ -60,7 +98,7 @@ struct Crossover {
 /// have had more filters applied to them, the lower bands need to have their phase response
 /// adjusted to match the higher bands. So for the LR24 crossovers, low-passed band `n` will get a
 /// second order all-pass for the frequencies corresponding to crossovers `n + 1..NUM_CROSSOVERS`
-/// applied to it.
+/// applied to it. The other crossover types need one all-pass filter per Butterworth section.
 #[derive(Debug, Default)]
 struct AllPassCascade {
     /// The aforementioned all-pass filters. This is indexed by `[crossover_idx][0..num_bands -
@@ -68,8 +106,11 @@ struct AllPassCascade {
     /// the first crossover needs to have `[0][0]` and `[0][1]` applied to it. The last band doesn't
     /// need any compensation, hence the `NUM_BANDS - 2`. The outer array is equal to the number of
     /// crossovers. It will never contain any filters, but this makes the code a bit nicer by
-    /// needing an explicit check for this.
-    ap_filters: [[Biquad<f32x2>; NUM_BANDS - 2]; NUM_BANDS - 1],
+    /// needing an explicit check for this. The innermost array contains the all-pass filters for a
+    /// single crossover.
+    ap_filters: [[[Biquad<f32x2>; MAX_ALL_PASS_STAGES]; NUM_BANDS - 2]; NUM_BANDS - 1],
+    /// The number of filters from the innermost `ap_filters` arrays that are in use.
+    num_stages: usize,
 
     /// The number of activate bands. Only coefficients for used bands are computed in `ap_filters`.
     num_bands: usize,
@@ -102,59 +143,59 @@ impl IirCrossover {
         // be unsound
         assert!(main_io.len() == 2);
 
+        // The number of filters depends on the crossover type, but that has already been taken care
+        // of in `.update()`
         let mut samples: f32x2 = unsafe { main_io.to_simd_unchecked() };
-        match self.mode {
-            IirCrossoverType::LinkwitzRiley24 => {
-                for (crossover_idx, (crossover, band_channel_samples)) in self
-                    .crossovers
-                    .iter_mut()
-                    .zip(band_outputs.iter_mut())
-                    .take(num_bands - 1)
-                    .enumerate()
-                {
-                    let (lp_samples, hp_samples) = crossover.process_lr24(samples);
-
-                    // The low-pass result needs to have the same phase shift applied to it that
-                    // higher bands would get
-                    let lp_samples = self.all_passes.compensate_lr24(lp_samples, crossover_idx);
-
-                    unsafe { band_channel_samples.from_simd_unchecked(lp_samples) };
-                    samples = hp_samples;
-                }
+        for (crossover_idx, (crossover, band_channel_samples)) in self
+            .crossovers
+            .iter_mut()
+            .zip(band_outputs.iter_mut())
+            .take(num_bands - 1)
+            .enumerate()
+        {
+            let (lp_samples, hp_samples) = crossover.process(samples);
 
-                // And the final high-passed result should be written to the last band
-                unsafe { band_outputs[num_bands - 1].from_simd_unchecked(samples) };
-            }
+            // The low-pass result needs to have the same phase shift applied to it that higher
+            // bands would get
+            let lp_samples = self.all_passes.compensate(lp_samples, crossover_idx);
+
+            unsafe { band_channel_samples.from_simd_unchecked(lp_samples) };
+            samples = hp_samples;
+        }
+
+        // And the final high-passed result should be written to the last band
+        unsafe { band_outputs[num_bands - 1].from_simd_unchecked(samples) };
+    }
+
+    /// Change the crossover type. The filter state is reset when this changes the type.
+    /// `.update()` must be called after changing this.
+    pub fn set_mode(&mut self, mode: IirCrossoverType) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.reset();
         }
     }
 
     /// Update the crossover frequencies for all filters. `num_bands` is assumed to be in `[2,
-    /// NUM_BANDS]`.
+    /// NUM_BANDS]`, and the frequencies are assumed to be in ascending order.
     pub fn update(
         &mut self,
         sample_rate: f32,
         num_bands: usize,
         frequencies: [f32; NUM_BANDS - 1],
     ) {
-        // NOTE: Currently we don't actually need to make sure that the frequencies are monotonic
-
-        match self.mode {
-            IirCrossoverType::LinkwitzRiley24 => {
-                for (crossover, frequency) in self
-                    .crossovers
-                    .iter_mut()
-                    .zip(frequencies)
-                    .take(num_bands - 1)
-                {
-                    let lp_coefs = BiquadCoefficients::lowpass(sample_rate, frequency, NEUTRAL_Q);
-                    let hp_coefs = BiquadCoefficients::highpass(sample_rate, frequency, NEUTRAL_Q);
-                    crossover.update_coefficients(lp_coefs, hp_coefs);
-                }
-            }
+        let sections = self.mode.butterworth_sections();
+        for (crossover, frequency) in self
+            .crossovers
+            .iter_mut()
+            .zip(frequencies)
+            .take(num_bands - 1)
+        {
+            crossover.update_coefficients(sample_rate, frequency, sections);
         }
 
         self.all_passes
-            .update_coefficients(sample_rate, num_bands, &frequencies);
+            .update_coefficients(sample_rate, num_bands, &frequencies, sections);
     }
 
     /// Reset the internal filter state for all crossovers.
@@ -167,35 +208,86 @@ impl IirCrossover {
     }
 }
 
+impl IirCrossoverType {
+    /// The sections of the Butterworth filter that's applied twice to form the low-pass and
+    /// high-pass parts of this crossover type.
+    pub fn butterworth_sections(self) -> &'static [ButterworthSection] {
+        match self {
+            IirCrossoverType::LinkwitzRiley12 => &[ButterworthSection::FirstOrder],
+            IirCrossoverType::LinkwitzRiley24 => &[ButterworthSection::SecondOrder(NEUTRAL_Q)],
+            IirCrossoverType::LinkwitzRiley36 => &[
+                ButterworthSection::FirstOrder,
+                ButterworthSection::SecondOrder(1.0),
+            ],
+            IirCrossoverType::LinkwitzRiley48 => &[
+                ButterworthSection::SecondOrder(BUTTERWORTH_4_Q[0]),
+                ButterworthSection::SecondOrder(BUTTERWORTH_4_Q[1]),
+            ],
+        }
+    }
+}
+
 impl Crossover {
-    /// Process left and right audio samples through two low-pass and two high-pass filter stages.
-    /// The resulting tuple contains the low-passed and the high-passed samples. Used for the
-    /// Linkwitz-Riley 24 dB/octave crossover.
-    pub fn process_lr24(&mut self, samples: f32x2) -> (f32x2, f32x2) {
+    /// Process left and right audio samples through the crossover's low-pass and high-pass filter
+    /// stages. The resulting tuple contains the low-passed and the high-passed samples.
+    pub fn process(&mut self, samples: f32x2) -> (f32x2, f32x2) {
         let mut low_passed = samples;
-        for filter in &mut self.lp_filters[..2] {
+        for filter in &mut self.lp_filters[..self.num_stages] {
             low_passed = filter.process(low_passed)
         }
         let mut high_passed = samples;
-        for filter in &mut self.hp_filters[..2] {
+        for filter in &mut self.hp_filters[..self.num_stages] {
             high_passed = filter.process(high_passed)
         }
 
-        (low_passed, high_passed)
+        if self.invert_hp {
+            (low_passed, -high_passed)
+        } else {
+            (low_passed, high_passed)
+        }
     }
 
-    /// Update the coefficients for all filters in the crossover.
+    /// Update the coefficients for all filters in the crossover. Every Butterworth section is
+    /// applied twice, so two identical first order sections are combined into a single biquad.
     pub fn update_coefficients(
         &mut self,
-        lp_coefs: BiquadCoefficients<f32x2>,
-        hp_coefs: BiquadCoefficients<f32x2>,
+        sample_rate: f32,
+        frequency: f32,
+        sections: &[ButterworthSection],
     ) {
-        for filter in &mut self.lp_filters {
-            filter.coefficients = lp_coefs;
-        }
-        for filter in &mut self.hp_filters {
-            filter.coefficients = hp_coefs;
+        let mut num_stages = 0;
+        let mut order = 0;
+        for section in sections {
+            let (lp_coefs, hp_coefs, num_filters) = match section {
+                ButterworthSection::FirstOrder => {
+                    order += 1;
+                    (
+                        BiquadCoefficients::lowpass(sample_rate, frequency, 0.5),
+                        BiquadCoefficients::highpass(sample_rate, frequency, 0.5),
+                        1,
+                    )
+                }
+                ButterworthSection::SecondOrder(q) => {
+                    order += 2;
+                    (
+                        BiquadCoefficients::lowpass(sample_rate, frequency, *q),
+                        BiquadCoefficients::highpass(sample_rate, frequency, *q),
+                        2,
+                    )
+                }
+            };
+
+            for _ in 0..num_filters {
+                self.lp_filters[num_stages].coefficients = lp_coefs;
+                self.hp_filters[num_stages].coefficients = hp_coefs;
+                num_stages += 1;
+            }
         }
+
+        // For odd order Butterworth filters the low-pass and high-pass outputs would be 180 degrees
+        // out of phase at the crossover frequency
+        self.num_stages = num_stages;
+        self.invert_hp = order % 2 == 1;
     }
 
     /// Reset the internal filter state.
@@ -210,9 +302,9 @@ impl Crossover {
 }
 
 impl AllPassCascade {
-    /// Compensate lower bands for the additional phase shift introduced in higher bands when using
-    /// LR24 filters to split those bands.
-    pub fn compensate_lr24(&mut self, lp_samples: f32x2, band_idx: usize) -> f32x2 {
+    /// Compensate lower bands for the additional phase shift introduced in higher bands when
+    /// splitting those bands.
+    pub fn compensate(&mut self, lp_samples: f32x2, band_idx: usize) -> f32x2 {
         // The all-pass filters are set up based on the crossover that produced the low-passed
         // samples
         let crossover_idx = band_idx;
@@ -222,8 +314,10 @@ impl AllPassCascade {
         // `self.num_bands` were 4 then it would additionally also be filtered by
         // `self.ap_filters[0][1]`.
         let mut compensated = lp_samples;
-        for filter in &mut self.ap_filters[crossover_idx][..self.num_bands - band_idx - 2] {
-            compensated = filter.process(compensated)
+        for filters in &mut self.ap_filters[crossover_idx][..self.num_bands - band_idx - 2] {
+            for filter in &mut filters[..self.num_stages] {
+                compensated = filter.process(compensated)
+            }
         }
 
         compensated
@@ -231,22 +325,36 @@ impl AllPassCascade {
 
     /// Update the coefficients for all filters in the cascade. For every active band, this adds up
     /// to `num_bands - band_idx - 1` filters. The filter state of course cannot be shared between
-    /// bands, but the coefficients along the matrix's diagonals are identical.
+    /// bands, but the coefficients along the matrix's diagonals are identical. Every Butterworth
+    /// section in `sections` gets its own all-pass filter.
     pub fn update_coefficients(
         &mut self,
         sample_rate: f32,
         num_bands: usize,
         frequencies: &[f32; NUM_BANDS - 1],
+        sections: &[ButterworthSection],
     ) {
+        nih_debug_assert!(sections.len() <= MAX_ALL_PASS_STAGES);
+
         self.num_bands = num_bands;
+        self.num_stages = sections.len();
 
         // All output bands go through the first filter, so we don't compensate for that. `band_idx`
         // starts at 1
         for (crossover_idx, crossover_frequency) in
             frequencies.iter().enumerate().take(num_bands - 1).skip(1)
         {
-            let ap_coefs =
-                BiquadCoefficients::allpass(sample_rate, *crossover_frequency, NEUTRAL_Q);
+            let mut ap_coefs = [BiquadCoefficients::identity(); MAX_ALL_PASS_STAGES];
+            for (coefs, section) in ap_coefs.iter_mut().zip(sections) {
+                *coefs = match section {
+                    ButterworthSection::FirstOrder => {
+                        BiquadCoefficients::allpass_first_order(sample_rate, *crossover_frequency)
+                    }
+                    ButterworthSection::SecondOrder(q) => {
+                        BiquadCoefficients::allpass(sample_rate, *crossover_frequency, *q)
+                    }
+                };
+            }
 
             // This sets the coefficients in a diagonal pattern. If `crossover_idx == 2`, then this
             // will set the coefficients for these filters:
@@ -256,18 +364,125 @@ impl AllPassCascade {
             // ...
             // ```
             for target_crossover_idx in 0..crossover_idx {
-                self.ap_filters[target_crossover_idx][crossover_idx - target_crossover_idx - 1]
-                    .coefficients = ap_coefs;
+                let filters = &mut self.ap_filters[target_crossover_idx]
+                    [crossover_idx - target_crossover_idx - 1];
+                for (filter, coefs) in filters.iter_mut().zip(ap_coefs) {
+                    filter.coefficients = coefs;
+                }
             }
         }
     }
 
     /// Reset the internal filter state.
     pub fn reset(&mut self) {
-        for filters in &mut self.ap_filters {
+        for filters in self.ap_filters.iter_mut().flatten() {
             for filter in filters.iter_mut() {
                 filter.reset();
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const CROSSOVER_FREQUENCY: f32 = 1_000.0;
+    const IMPULSE_RESPONSE_LEN: usize = 8192;
+
+    /// Compute the low-pass and high-pass impulse responses for a single crossover.
+    fn impulse_responses(crossover_type: IirCrossoverType) -> (Vec<f32>, Vec<f32>) {
+        let mut crossover = Crossover::default();
+        crossover.update_coefficients(
+            SAMPLE_RATE,
+            CROSSOVER_FREQUENCY,
+            crossover_type.butterworth_sections(),
+        );
+
+        (0..IMPULSE_RESPONSE_LEN)
+            .map(|sample_idx| {
+                let input = if sample_idx == 0 { 1.0 } else { 0.0 };
+                let (lp, hp) = crossover.process(f32x2::splat(input));
+                (lp.to_array()[0], hp.to_array()[0])
+            })
+            .unzip()
+    }
+
+    fn magnitude(impulse_response: &[f32], frequency: f32) -> f32 {
+        let omega = 2.0 * PI * frequency / SAMPLE_RATE;
+        let (re, im) = impulse_response.iter().enumerate().fold(
+            (0.0f64, 0.0f64),
+            |(re, im), (sample_idx, sample)| {
+                let phase = (omega * sample_idx as f32) as f64;
+                (
+                    re + (*sample as f64 * phase.cos()),
+                    im - (*sample as f64 * phase.sin()),
+                )
+            },
+        );
+
+        re.hypot(im) as f32
+    }
+
+    /// The magnitude response of a Linkwitz-Riley low-pass filter of `order`, accounting for the
+    /// bilinear transform's frequency warping.
+    fn expected_low_pass_magnitude(order: i32, frequency: f32) -> f32 {
+        let warped_ratio =
+            (PI * frequency / SAMPLE_RATE).tan() / (PI * CROSSOVER_FREQUENCY / SAMPLE_RATE).tan();
+
+        1.0 / (1.0 + warped_ratio.powi(order))
+    }
+
+    #[test]
+    fn test_linkwitz_riley_responses() {
+        for (crossover_type, order) in [
+            (IirCrossoverType::LinkwitzRiley12, 2),
+            (IirCrossoverType::LinkwitzRiley24, 4),
+            (IirCrossoverType::LinkwitzRiley36, 6),
+            (IirCrossoverType::LinkwitzRiley48, 8),
+        ] {
+            let (lp, hp) = impulse_responses(crossover_type);
+
+            // Both bands are at -6 dB at the crossover frequency
+            let lp_magnitude = magnitude(&lp, CROSSOVER_FREQUENCY);
+            let hp_magnitude = magnitude(&hp, CROSSOVER_FREQUENCY);
+            assert!(
+                (lp_magnitude - 0.5).abs() < 1e-3,
+                "{crossover_type:?}: {lp_magnitude}"
+            );
+            assert!(
+                (hp_magnitude - 0.5).abs() < 1e-3,
+                "{crossover_type:?}: {hp_magnitude}"
+            );
+
+            // The slopes should match the filter's order
+            for frequency in [CROSSOVER_FREQUENCY / 2.0, CROSSOVER_FREQUENCY * 2.0] {
+                let expected_lp = expected_low_pass_magnitude(order, frequency);
+                let expected_hp = 1.0 - expected_lp;
+                let lp_magnitude = magnitude(&lp, frequency);
+                let hp_magnitude = magnitude(&hp, frequency);
+                assert!(
+                    (lp_magnitude - expected_lp).abs() < expected_lp * 1e-2,
+                    "{crossover_type:?} at {frequency} Hz: {lp_magnitude} != {expected_lp}"
+                );
+                assert!(
+                    (hp_magnitude - expected_hp).abs() < expected_hp * 1e-2,
+                    "{crossover_type:?} at {frequency} Hz: {hp_magnitude} != {expected_hp}"
+                );
+            }
+
+            // And the bands should sum to an all-pass filter
+            let sum: Vec<f32> = lp.iter().zip(&hp).map(|(lp, hp)| lp + hp).collect();
+            for frequency in [100.0, CROSSOVER_FREQUENCY, 5_000.0, 15_000.0] {
+                let sum_magnitude = magnitude(&sum, frequency);
+                assert!(
+                    (sum_magnitude - 1.0).abs() < 1e-3,
+                    "{crossover_type:?} at {frequency} Hz: {sum_magnitude}"
+                );
+            }
+        }
+    }
+}
@@ -18,7 +18,8 @@ use nih_plug::debug::*;
 
 use std::f32::consts;
 use std::ops::{Add, Mul, Sub};
-use std::simd::f32x2;
+
+use crate::simd::f32x2;
 
 pub const NEUTRAL_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
 
@@ -158,6 +159,32 @@ impl<T: SimdType> BiquadCoefficients<T> {
         Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
     }
 
+    /// Compute the coefficients for a first order low-pass filter. This is a biquad with the
+    /// second order coefficients set to zero.
+    ///
+    /// Based on the bilinear transform with frequency prewarping.
+    pub fn lowpass_first_order(sample_rate: f32, frequency: f32) -> Self {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(frequency > 0.0);
+        nih_debug_assert!(frequency < sample_rate / 2.0);
+
+        let k = (consts::PI * (frequency / sample_rate)).tan();
+
+        // We'll prenormalize everything with a0
+        let a0 = k + 1.0;
+        let b0 = k / a0;
+        let b1 = k / a0;
+        let a1 = (k - 1.0) / a0;
+
+        Self::from_f32s(BiquadCoefficients {
+            b0,
+            b1,
+            b2: 0.0,
+            a1,
+            a2: 0.0,
+        })
+    }
+
     /// Compute the coefficients for an all-pass filter.
     ///
     /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
@@ -181,6 +208,27 @@ impl<T: SimdType> BiquadCoefficients<T> {
 
         Self::from_f32s(BiquadCoefficients { b0, b1, b2, a1, a2 })
     }
+
+    /// Compute the coefficients for a first order all-pass filter. Used to compensate for the phase
+    /// shift of crossovers built from odd order Butterworth filters.
+    ///
+    /// Based on the bilinear transform with frequency prewarping.
+    pub fn allpass_first_order(sample_rate: f32, frequency: f32) -> Self {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(frequency > 0.0);
+        nih_debug_assert!(frequency < sample_rate / 2.0);
+
+        let k = (consts::PI * (frequency / sample_rate)).tan();
+        let coef = (k - 1.0) / (k + 1.0);
+
+        Self::from_f32s(BiquadCoefficients {
+            b0: coef,
+            b1: 1.0,
+            b2: 0.0,
+            a1: coef,
+            a2: 0.0,
+        })
+    }
 }
 
 impl SimdType for f32 {
@@ -16,9 +16,6 @@
 
 #![cfg_attr(feature = "simd", feature(portable_simd))]
 
-#[cfg(not(feature = "simd"))]
-compile_error!("Compiling without SIMD support is currently not supported");
-
 use crossover::fir::{FirCrossover, FirCrossoverType};
 use crossover::iir::{IirCrossover, IirCrossoverType};
 use nih_plug::prelude::*;
@@ -26,6 +23,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 mod crossover;
+mod simd;
 
 /// The number of channels this plugin supports. Hard capped at 2 for SIMD reasons.
 pub const NUM_CHANNELS: u32 = 2;
@@ -42,12 +40,15 @@ pub struct Crossover {
 
     buffer_config: BufferConfig,
 
-    /// Provides the LR24 crossover.
+    /// Provides the LR12, LR24, LR36, and LR48 crossovers.
     iir_crossover: IirCrossover,
-    /// Provides the linear-phase LR24 crossover.
+    /// Provides the linear-phase versions of the IIR crossovers.
     fir_crossover: FirCrossover,
     /// Set when the number of bands has changed and the filters must be updated.
     should_update_filters: Arc<AtomicBool>,
+    /// Set when the number of bands has changed and the host should be informed that the output
+    /// port names have changed.
+    should_update_port_names: Arc<AtomicBool>,
 }
 
 #[derive(Params)]
@@ -56,8 +57,8 @@ struct CrossoverParams {
     #[id = "bandcnt"]
     pub num_bands: IntParam,
 
-    // We'll only provide frequency controls, as gain, panning, solo, mute etc. is all already
-    // provided by Bitwig's UI
+    // We'll only provide frequency controls, as gain, panning, etc. is all already provided by
+    // Bitwig's UI
     #[id = "xov1fq"]
     pub crossover_1_freq: FloatParam,
     #[id = "xov2fq"]
@@ -71,10 +72,28 @@ struct CrossoverParams {
     // band control plus the four crossovers fits exactly in Bitwig's parameter list
     #[id = "xovtyp"]
     pub crossover_type: EnumParam<CrossoverType>,
+
+    /// Solo and mute controls for auditioning the bands in hosts that don't have a multi-out chain
+    /// UI like Bitwig's. These are all disabled by default, and they're placed after the other
+    /// parameters so they don't get in the way.
+    #[nested(array, group = "Band")]
+    pub bands: [BandParams; NUM_BANDS],
+}
+
+#[derive(Params)]
+struct BandParams {
+    /// When any of the active bands are soloed, all other bands are silenced. This takes precedence
+    /// over the mute parameters.
+    #[id = "solo"]
+    pub solo: BoolParam,
+    /// Silences the band, unless some band is soloed.
+    #[id = "mute"]
+    pub mute: BoolParam,
 }
 
 // The `non_exhaustive` is to prevent adding cases for latency compensation when adding more types
-// later
+// later. New variants are added at the end so existing automation keeps pointing to the same
+// crossover types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
 #[non_exhaustive]
 enum CrossoverType {
@@ -84,10 +103,61 @@ enum CrossoverType {
     #[id = "lr24-lp"]
     #[name = "LR24 (LP)"]
     LinkwitzRiley24LinearPhase,
+    #[id = "lr12"]
+    #[name = "LR12"]
+    LinkwitzRiley12,
+    #[id = "lr36"]
+    #[name = "LR36"]
+    LinkwitzRiley36,
+    #[id = "lr48"]
+    #[name = "LR48"]
+    LinkwitzRiley48,
+    #[id = "lr12-lp"]
+    #[name = "LR12 (LP)"]
+    LinkwitzRiley12LinearPhase,
+    #[id = "lr36-lp"]
+    #[name = "LR36 (LP)"]
+    LinkwitzRiley36LinearPhase,
+    #[id = "lr48-lp"]
+    #[name = "LR48 (LP)"]
+    LinkwitzRiley48LinearPhase,
+}
+
+/// The crossover implementation and its settings corresponding to a [`CrossoverType`].
+enum CrossoverMode {
+    Iir(IirCrossoverType),
+    Fir(FirCrossoverType),
+}
+
+impl CrossoverType {
+    /// Get the crossover implementation that should be used for this crossover type.
+    fn mode(self) -> CrossoverMode {
+        match self {
+            CrossoverType::LinkwitzRiley12 => CrossoverMode::Iir(IirCrossoverType::LinkwitzRiley12),
+            CrossoverType::LinkwitzRiley24 => CrossoverMode::Iir(IirCrossoverType::LinkwitzRiley24),
+            CrossoverType::LinkwitzRiley36 => CrossoverMode::Iir(IirCrossoverType::LinkwitzRiley36),
+            CrossoverType::LinkwitzRiley48 => CrossoverMode::Iir(IirCrossoverType::LinkwitzRiley48),
+            CrossoverType::LinkwitzRiley12LinearPhase => {
+                CrossoverMode::Fir(FirCrossoverType::LinkwitzRiley12LinearPhase)
+            }
+            CrossoverType::LinkwitzRiley24LinearPhase => {
+                CrossoverMode::Fir(FirCrossoverType::LinkwitzRiley24LinearPhase)
+            }
+            CrossoverType::LinkwitzRiley36LinearPhase => {
+                CrossoverMode::Fir(FirCrossoverType::LinkwitzRiley36LinearPhase)
+            }
+            CrossoverType::LinkwitzRiley48LinearPhase => {
+                CrossoverMode::Fir(FirCrossoverType::LinkwitzRiley48LinearPhase)
+            }
+        }
+    }
 }
 
 impl CrossoverParams {
-    fn new(should_update_filters: Arc<AtomicBool>) -> Self {
+    fn new(
+        should_update_filters: Arc<AtomicBool>,
+        should_update_port_names: Arc<AtomicBool>,
+    ) -> Self {
         let crossover_range = FloatRange::Skewed {
             min: MIN_CROSSOVER_FREQUENCY,
             max: MAX_CROSSOVER_FREQUENCY,
@@ -106,11 +176,10 @@ impl CrossoverParams {
                     max: NUM_BANDS as i32,
                 },
             )
-            .with_callback({
-                let should_update_filters = should_update_filters.clone();
-
-                Arc::new(move |_| should_update_filters.store(true, Ordering::Relaxed))
-            }),
+            .with_callback(Arc::new(move |_| {
+                should_update_filters.store(true, Ordering::Relaxed);
+                should_update_port_names.store(true, Ordering::Relaxed);
+            })),
 
             // TODO: More sensible default frequencies
             crossover_1_freq: FloatParam::new("Crossover 1", 200.0, crossover_range)
@@ -130,9 +199,13 @@ impl CrossoverParams {
                 .with_value_to_string(crossover_value_to_string)
                 .with_string_to_value(crossover_string_to_value),
 
-            crossover_type: EnumParam::new("Type", CrossoverType::LinkwitzRiley24).with_callback(
-                Arc::new(move |_| should_update_filters.store(true, Ordering::Relaxed)),
-            ),
+            crossover_type: EnumParam::new("Type", CrossoverType::LinkwitzRiley24)
+                .affects_latency(),
+
+            bands: [1, 2, 3, 4, 5].map(|band_number| BandParams {
+                solo: BoolParam::new(format!("Band {band_number} Solo"), false),
+                mute: BoolParam::new(format!("Band {band_number} Mute"), false),
+            }),
         }
     }
 }
@@ -140,9 +213,13 @@ impl CrossoverParams {
 impl Default for Crossover {
     fn default() -> Self {
         let should_update_filters = Arc::new(AtomicBool::new(false));
+        let should_update_port_names = Arc::new(AtomicBool::new(false));
 
         Crossover {
-            params: Arc::new(CrossoverParams::new(should_update_filters.clone())),
+            params: Arc::new(CrossoverParams::new(
+                should_update_filters.clone(),
+                should_update_port_names.clone(),
+            )),
 
             buffer_config: BufferConfig {
                 sample_rate: 1.0,
@@ -154,6 +231,7 @@ impl Default for Crossover {
             iir_crossover: IirCrossover::new(IirCrossoverType::LinkwitzRiley24),
             fir_crossover: FirCrossover::new(FirCrossoverType::LinkwitzRiley24LinearPhase),
             should_update_filters,
+            should_update_port_names,
         }
     }
 }
@@ -171,7 +249,8 @@ impl Plugin for Crossover {
         main_output_channels: NonZeroU32::new(NUM_CHANNELS),
 
         aux_input_ports: &[],
-        // Two to five of these ports will be used at a time
+        // Two to five of these ports will be used at a time. The unused ports are marked as such in
+        // `aux_output_port_names()`.
         aux_output_ports: &[new_nonzero_u32(NUM_CHANNELS); 5],
 
         names: PortNames {
@@ -192,6 +271,19 @@ impl Plugin for Crossover {
         self.params.clone()
     }
 
+    fn aux_output_port_names(&mut self) -> Option<AuxOutputPortNames> {
+        // The bands that are not currently in use still exist, but they're marked as being unused
+        // so it's clear which outputs carry audio
+        let params = self.params.clone();
+        Some(Box::new(move |_audio_io_layout, aux_output_idx| {
+            if aux_output_idx >= params.num_bands.value() as usize {
+                Some(format!("Band {} (unused)", aux_output_idx + 1))
+            } else {
+                None
+            }
+        }))
+    }
+
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
@@ -204,11 +296,9 @@ impl Plugin for Crossover {
         self.update_filters(1);
 
         // The FIR filters are linear-phase and introduce latency
-        match self.params.crossover_type.value() {
-            CrossoverType::LinkwitzRiley24 => (),
-            CrossoverType::LinkwitzRiley24LinearPhase => {
-                context.set_latency_samples(self.fir_crossover.latency())
-            }
+        match self.params.crossover_type.value().mode() {
+            CrossoverMode::Iir(_) => (),
+            CrossoverMode::Fir(_) => context.set_latency_samples(self.fir_crossover.latency()),
         }
 
         true
@@ -225,25 +315,64 @@ impl Plugin for Crossover {
         aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        // Right now both crossover types only do 24 dB/octave Linkwitz-Riley style crossovers
-        match self.params.crossover_type.value() {
-            CrossoverType::LinkwitzRiley24 => {
+        // The port names depend on the number of bands
+        if self
+            .should_update_port_names
+            .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            context.port_names_changed();
+        }
+
+        self.correct_crossover_order(context);
+
+        // All crossover types are Linkwitz-Riley style crossovers, either minimum-phase or
+        // linear-phase
+        match self.params.crossover_type.value().mode() {
+            CrossoverMode::Iir(_) => {
                 context.set_latency_samples(0);
 
                 self.process_iir(buffer, aux);
             }
-            CrossoverType::LinkwitzRiley24LinearPhase => {
+            CrossoverMode::Fir(_) => {
                 context.set_latency_samples(self.fir_crossover.latency());
 
                 self.process_fir(buffer, aux);
             }
         }
 
+        self.apply_solo_mute(aux);
+
         ProcessStatus::Normal
     }
 }
 
 impl Crossover {
+    /// If a crossover frequency has been set lower than the previous crossover, then move it back
+    /// up to the previous crossover's frequency. This means that dragging a crossover below the
+    /// previous one clamps it, and dragging a crossover above the next one pushes the next one
+    /// along. The corrections are sent to the host so its automation reflects them. The DSP code
+    /// also clamps the frequencies, so the order is correct even before the host has processed
+    /// these changes.
+    fn correct_crossover_order(&self, context: &mut impl ProcessContext<Self>) {
+        let crossover_params = [
+            &self.params.crossover_1_freq,
+            &self.params.crossover_2_freq,
+            &self.params.crossover_3_freq,
+            &self.params.crossover_4_freq,
+        ];
+
+        let mut previous_frequency = crossover_params[0].unmodulated_plain_value();
+        for param in &crossover_params[1..] {
+            let frequency = param.unmodulated_plain_value();
+            if frequency < previous_frequency {
+                context.set_parameter(*param, previous_frequency);
+            } else {
+                previous_frequency = frequency;
+            }
+        }
+    }
+
     /// Takes care of all of the boilerplate in zipping the outputs together to get a nice iterator
     /// friendly and SIMD-able interface for the processing function. Prevents having to branch per
     /// sample. The closure receives an input sample and it should write the output samples for each
@@ -341,6 +470,28 @@ impl Crossover {
         }
     }
 
+    /// Silence the bands that are muted, or that are not soloed while another band is soloed. This
+    /// is done after all bands have been computed so the crossovers themselves are not affected.
+    fn apply_solo_mute(&self, aux: &mut AuxiliaryBuffers) {
+        let num_bands = self.params.num_bands.value() as usize;
+        let active_bands = &self.params.bands[..num_bands];
+        let any_soloed = active_bands.iter().any(|band| band.solo.value());
+
+        for (band_params, band_buffer) in active_bands.iter().zip(aux.outputs.iter_mut()) {
+            let audible = if any_soloed {
+                band_params.solo.value()
+            } else {
+                !band_params.mute.value()
+            };
+
+            if !audible {
+                for channel_samples in band_buffer.as_slice() {
+                    channel_samples.fill(0.0);
+                }
+            }
+        }
+    }
+
     /// Returns whether the filters should be updated. There are different updating functions for
     /// the IIR and FIR crossovers.
     fn should_update_filters(&mut self) -> bool {
@@ -350,6 +501,7 @@ impl Crossover {
         self.should_update_filters
             .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
             .is_ok()
+            || self.params.crossover_type.changed_since_last_call()
             || self.params.crossover_1_freq.smoothed.is_smoothing()
             || self.params.crossover_2_freq.smoothed.is_smoothing()
             || self.params.crossover_3_freq.smoothed.is_smoothing()
@@ -359,34 +511,52 @@ impl Crossover {
     /// Update the filter coefficients for the crossovers. The step size can be used when the filter
     /// coefficietns aren't updated every sample.
     fn update_filters(&mut self, step_size: u32) {
-        let crossover_frequencies = [
+        let crossover_frequencies = ordered_crossover_frequencies([
             self.params.crossover_1_freq.smoothed.next_step(step_size),
             self.params.crossover_2_freq.smoothed.next_step(step_size),
             self.params.crossover_3_freq.smoothed.next_step(step_size),
             self.params.crossover_4_freq.smoothed.next_step(step_size),
-        ];
-
-        match self.params.crossover_type.value() {
-            CrossoverType::LinkwitzRiley24 => self.iir_crossover.update(
-                self.buffer_config.sample_rate,
-                self.params.num_bands.value() as usize,
-                crossover_frequencies,
-            ),
-            CrossoverType::LinkwitzRiley24LinearPhase => self.fir_crossover.update(
-                self.buffer_config.sample_rate,
-                self.params.num_bands.value() as usize,
-                crossover_frequencies,
-            ),
+        ]);
+
+        // The crossovers reset their filter state when the crossover type changes
+        match self.params.crossover_type.value().mode() {
+            CrossoverMode::Iir(iir_type) => {
+                self.iir_crossover.set_mode(iir_type);
+                self.iir_crossover.update(
+                    self.buffer_config.sample_rate,
+                    self.params.num_bands.value() as usize,
+                    crossover_frequencies,
+                );
+            }
+            CrossoverMode::Fir(fir_type) => {
+                self.fir_crossover.set_mode(fir_type);
+                self.fir_crossover.update(
+                    self.buffer_config.sample_rate,
+                    self.params.num_bands.value() as usize,
+                    crossover_frequencies,
+                );
+            }
         }
     }
 }
 
+/// Make sure the crossover frequencies are in ascending order. The parameters all share the same
+/// range and the host is free to set them to any value. Out of order parameters are corrected in
+/// `Crossover::correct_crossover_order()`, but that only takes effect at the end of the process
+/// call, so every crossover frequency is also clamped here so it's never lower than the previous
+/// one.
+fn ordered_crossover_frequencies(mut frequencies: [f32; NUM_BANDS - 1]) -> [f32; NUM_BANDS - 1] {
+    for i in 1..frequencies.len() {
+        frequencies[i] = frequencies[i].max(frequencies[i - 1]);
+    }
+
+    frequencies
+}
+
 impl ClapPlugin for Crossover {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.crossover";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("Cleanly split a signal into multiple bands");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
@@ -417,3 +587,27 @@ impl Vst3Plugin for Crossover {
 
 nih_export_clap!(Crossover);
 nih_export_vst3!(Crossover);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossover_type_indices() {
+        // These indices are used for automation and must not change
+        assert_eq!(CrossoverType::LinkwitzRiley24.to_index(), 0);
+        assert_eq!(CrossoverType::LinkwitzRiley24LinearPhase.to_index(), 1);
+    }
+
+    #[test]
+    fn test_ordered_crossover_frequencies() {
+        assert_eq!(
+            ordered_crossover_frequencies([200.0, 1000.0, 5000.0, 10000.0]),
+            [200.0, 1000.0, 5000.0, 10000.0]
+        );
+        assert_eq!(
+            ordered_crossover_frequencies([200.0, 100.0, 5000.0, 3000.0]),
+            [200.0, 200.0, 5000.0, 5000.0]
+        );
+    }
+}
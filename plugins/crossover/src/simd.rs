@@ -0,0 +1,120 @@
+// Crossover: clean crossovers as a multi-out plugin
+// Copyright (C) 2022-2024 Robbert van der Helm
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The two-lane vector type used to process both channels at once. When the `simd` feature is
+//! enabled this is [`std::simd::f32x2`], which requires a nightly compiler. Otherwise a scalar
+//! implementation with the same interface is used so the plugin can also be compiled on stable
+//! Rust, at the cost of some performance.
+
+#[cfg(feature = "simd")]
+pub use std::simd::f32x2;
+
+#[cfg(not(feature = "simd"))]
+pub use self::fallback::{f32x2, ChannelSamplesExt};
+
+#[cfg(not(feature = "simd"))]
+mod fallback {
+    use nih_plug::buffer::ChannelSamples;
+    use std::ops::{Add, Mul, Neg, Sub};
+
+    /// A scalar stand-in for `std::simd::f32x2`. This only implements the operations used in this
+    /// plugin.
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    pub struct f32x2([f32; 2]);
+
+    impl f32x2 {
+        #[inline]
+        pub fn splat(value: f32) -> Self {
+            Self([value; 2])
+        }
+
+        // Only used in the tests
+        #[cfg(test)]
+        #[inline]
+        pub fn to_array(self) -> [f32; 2] {
+            self.0
+        }
+    }
+
+    impl Add for f32x2 {
+        type Output = Self;
+
+        #[inline]
+        fn add(self, rhs: Self) -> Self {
+            Self([self.0[0] + rhs.0[0], self.0[1] + rhs.0[1]])
+        }
+    }
+
+    impl Sub for f32x2 {
+        type Output = Self;
+
+        #[inline]
+        fn sub(self, rhs: Self) -> Self {
+            Self([self.0[0] - rhs.0[0], self.0[1] - rhs.0[1]])
+        }
+    }
+
+    impl Mul for f32x2 {
+        type Output = Self;
+
+        #[inline]
+        fn mul(self, rhs: Self) -> Self {
+            Self([self.0[0] * rhs.0[0], self.0[1] * rhs.0[1]])
+        }
+    }
+
+    impl Neg for f32x2 {
+        type Output = Self;
+
+        #[inline]
+        fn neg(self) -> Self {
+            Self([-self.0[0], -self.0[1]])
+        }
+    }
+
+    /// Replacements for NIH-plug's SIMD conversion functions on [`ChannelSamples`], which are only
+    /// available when NIH-plug's `simd` feature is enabled.
+    pub trait ChannelSamplesExt {
+        /// Read the first two channels' samples.
+        ///
+        /// # Safety
+        ///
+        /// Undefined behavior if there are fewer than two channels.
+        unsafe fn to_simd_unchecked(&self) -> f32x2;
+
+        /// Write to the first two channels' samples.
+        ///
+        /// # Safety
+        ///
+        /// Undefined behavior if there are fewer than two channels.
+        #[allow(clippy::wrong_self_convention)]
+        unsafe fn from_simd_unchecked(&mut self, vector: f32x2);
+    }
+
+    impl ChannelSamplesExt for ChannelSamples<'_, '_> {
+        #[inline]
+        unsafe fn to_simd_unchecked(&self) -> f32x2 {
+            f32x2([self.get_unchecked(0), self.get_unchecked(1)])
+        }
+
+        #[inline]
+        unsafe fn from_simd_unchecked(&mut self, vector: f32x2) {
+            *self.get_unchecked_mut(0) = vector.0[0];
+            *self.get_unchecked_mut(1) = vector.0[1];
+        }
+    }
+}
@@ -20,6 +20,7 @@ use nih_plug::prelude::{Editor, Plugin};
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
 use self::button::SafeModeButton;
@@ -51,6 +52,9 @@ pub(crate) struct Data {
     /// The plugin's current sample rate.
     pub(crate) sample_rate: Arc<AtomicF32>,
     pub(crate) spectrum: Arc<Mutex<SpectrumOutput>>,
+    /// Whether the spectrum analyzer is frozen. The audio thread stops sending new spectrums while
+    /// this is set.
+    pub(crate) spectrum_frozen: Arc<AtomicBool>,
     /// Whether the safe mode button is enabled. The number of filter stages is capped at 40 while
     /// this is active.
     pub(crate) safe_mode_clamper: SafeModeClamper,
@@ -117,6 +121,7 @@ fn top_bar(cx: &mut Context) {
                 .with_label("Automation Precision")
                 .id("automation-precision");
 
+            FreezeButton::new(cx, Data::spectrum_frozen, "Freeze").left(Pixels(10.0));
             SafeModeButton::new(cx, Data::safe_mode_clamper, "Safe mode").left(Pixels(10.0));
 
             ParamButton::new(cx, Data::params, |params| &params.bypass)
@@ -16,22 +16,22 @@
 
 #![cfg_attr(feature = "simd", feature(portable_simd))]
 
-#[cfg(not(feature = "simd"))]
-compile_error!("Compiling without SIMD support is currently not supported");
-
 use atomic_float::AtomicF32;
 use editor::SafeModeClamper;
 use nih_plug::prelude::*;
-use std::simd::f32x2;
+use nih_plug::util::filter;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::params::{DiopserParams, SpreadStyle};
+use crate::simd::f32x2;
+#[cfg(not(feature = "simd"))]
+use crate::simd::ChannelSamplesExt;
 use crate::spectrum::{SpectrumInput, SpectrumOutput};
 
 mod editor;
-mod filter;
 mod params;
+mod simd;
 mod spectrum;
 
 /// The number of channels we support. Hardcoded to simplify the SIMD version.
@@ -70,6 +70,8 @@ pub struct Diopser {
     spectrum_input: SpectrumInput,
     /// This can be cloned and moved into the editor.
     spectrum_output: Arc<Mutex<SpectrumOutput>>,
+    /// Toggled from the editor to freeze the spectrum analyzer.
+    spectrum_frozen: Arc<AtomicBool>,
 }
 
 impl Default for Diopser {
@@ -79,7 +81,9 @@ impl Default for Diopser {
         let bypass_smoother = Arc::new(Smoother::new(SmoothingStyle::Linear(10.0)));
 
         // We only do stereo right now so this is simple
-        let (spectrum_input, spectrum_output) = SpectrumInput::new(NUM_CHANNELS as usize);
+        let spectrum_frozen = Arc::new(AtomicBool::new(false));
+        let (spectrum_input, spectrum_output) =
+            SpectrumInput::new(NUM_CHANNELS as usize, spectrum_frozen.clone());
 
         Self {
             params: Arc::new(DiopserParams::new(
@@ -98,6 +102,7 @@ impl Default for Diopser {
 
             spectrum_input,
             spectrum_output: Arc::new(Mutex::new(spectrum_output)),
+            spectrum_frozen,
         }
     }
 }
@@ -133,6 +138,7 @@ impl Plugin for Diopser {
 
                 sample_rate: self.sample_rate.clone(),
                 spectrum: self.spectrum_output.clone(),
+                spectrum_frozen: self.spectrum_frozen.clone(),
                 safe_mode_clamper: SafeModeClamper::new(self.params.clone()),
             },
             self.params.editor_state.clone(),
@@ -212,7 +218,7 @@ impl Plugin for Diopser {
                         .iter_mut()
                         .take(self.params.filter_stages.value() as usize)
                     {
-                        *wet_samples = filter.process(*wet_samples);
+                        *wet_samples = filter.process_denormal_safe(*wet_samples);
                     }
                 }
 
@@ -339,8 +345,6 @@ impl Diopser {
 impl ClapPlugin for Diopser {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.diopser";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("A totally original phase rotation plugin");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
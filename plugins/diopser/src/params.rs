@@ -60,10 +60,12 @@ pub struct DiopserParams {
     /// The editor state, saved together with the parameter state so the custom scaling can be
     /// restored.
     #[persist = "editor-state"]
+    #[view_state]
     pub editor_state: Arc<ViziaState>,
     /// If this option is enabled, then the filter stages parameter is limited to `[0, 40]`. This is
     /// editor-only state, and doesn't affect host automation.
     #[persist = "safe-mode"]
+    #[view_state]
     pub safe_mode: Arc<AtomicBool>,
 
     /// This plugin really doesn't need its own bypass parameter, but it's still useful to have a
@@ -19,6 +19,7 @@ use nih_plug::util::window::multiply_with_window;
 use realfft::num_complex::Complex32;
 use realfft::{RealFftPlanner, RealToComplex};
 use std::f32;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use triple_buffer::TripleBuffer;
 
@@ -51,6 +52,9 @@ pub struct SpectrumInput {
     /// A way to send data to the corresponding [`SpectrumOutput`]. `spectrum_result_buffer` gets
     /// copied into this buffer every time a new spectrum is available.
     triple_buffer_input: triple_buffer::Input<Spectrum>,
+    /// Set from the editor to freeze the displayed spectrum. The spectrum is still computed while
+    /// this is set, but it's not sent to the [`SpectrumOutput`] so the last frame stays visible.
+    frozen: Arc<AtomicBool>,
     /// A scratch buffer to compute the resulting power amplitude spectrum.
     spectrum_result_buffer: Spectrum,
 
@@ -64,8 +68,9 @@ pub struct SpectrumInput {
 }
 
 impl SpectrumInput {
-    /// Create a new spectrum input and output pair. The output should be moved to the editor.
-    pub fn new(num_channels: usize) -> (SpectrumInput, SpectrumOutput) {
+    /// Create a new spectrum input and output pair. The output should be moved to the editor. No
+    /// new spectrums are sent to the output while `frozen` is set.
+    pub fn new(num_channels: usize, frozen: Arc<AtomicBool>) -> (SpectrumInput, SpectrumOutput) {
         let (triple_buffer_input, triple_buffer_output) =
             TripleBuffer::new(&[0.0; SPECTRUM_WINDOW_SIZE / 2 + 1]).split();
 
@@ -77,6 +82,7 @@ impl SpectrumInput {
             smoothing_decay_weight: 0.0,
 
             triple_buffer_input,
+            frozen,
             spectrum_result_buffer: [0.0; SPECTRUM_WINDOW_SIZE / 2 + 1],
 
             plan: RealFftPlanner::new().plan_fft_forward(SPECTRUM_WINDOW_SIZE),
@@ -140,7 +146,11 @@ impl SpectrumInput {
                     }
                 }
 
-                self.triple_buffer_input.write(self.spectrum_result_buffer);
+                // The smoothing state is still updated while frozen so unfreezing doesn't cause a
+                // jump in the spectrum
+                if !self.frozen.load(Ordering::Relaxed) {
+                    self.triple_buffer_input.write(self.spectrum_result_buffer);
+                }
             },
         );
     }
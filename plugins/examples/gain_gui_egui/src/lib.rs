@@ -24,6 +24,7 @@ pub struct GainParams {
     /// The editor state, saved together with the parameter state so the custom scaling can be
     /// restored.
     #[persist = "editor-state"]
+    #[view_state]
     editor_state: Arc<EguiState>,
 
     #[id = "gain"]
@@ -77,18 +78,7 @@ impl Plugin for Gain {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(2),
-            main_output_channels: NonZeroU32::new(2),
-            ..AudioIOLayout::const_default()
-        },
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(1),
-            main_output_channels: NonZeroU32::new(1),
-            ..AudioIOLayout::const_default()
-        },
-    ];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &AudioIOLayout::main_io_layouts([2, 1]);
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -214,24 +204,18 @@ impl Plugin for Gain {
     }
 }
 
-impl ClapPlugin for Gain {
-    const CLAP_ID: &'static str = "com.moist-plugins-gmbh-egui.gain-gui";
-    const CLAP_DESCRIPTION: Option<&'static str> = Some("A smoothed gain parameter example plugin");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
-    const CLAP_FEATURES: &'static [ClapFeature] = &[
+nih_plugin_metadata!(Gain {
+    clap_id: "com.moist-plugins-gmbh-egui.gain-gui",
+    clap_description: "A smoothed gain parameter example plugin",
+    clap_features: [
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
         ClapFeature::Mono,
         ClapFeature::Utility,
-    ];
-}
-
-impl Vst3Plugin for Gain {
-    const VST3_CLASS_ID: [u8; 16] = *b"GainGuiYeahBoyyy";
-    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
-        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
-}
+    ],
+    vst3_class_id: *b"GainGuiYeahBoyyy",
+    vst3_subcategories: [Vst3SubCategory::Fx, Vst3SubCategory::Tools],
+});
 
 nih_export_clap!(Gain);
 nih_export_vst3!(Gain);
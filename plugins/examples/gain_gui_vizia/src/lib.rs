@@ -27,10 +27,16 @@ struct GainParams {
     /// The editor state, saved together with the parameter state so the custom scaling can be
     /// restored.
     #[persist = "editor-state"]
+    #[view_state]
     editor_state: Arc<ViziaState>,
 
     #[id = "gain"]
     pub gain: FloatParam,
+
+    /// The input's peak level for the current block. This is a read-only parameter that's always
+    /// updated, even when the GUI is closed, so the host can display it.
+    #[id = "input-level"]
+    pub input_level: FloatParam,
 }
 
 impl Default for Gain {
@@ -62,6 +68,17 @@ impl Default for GainParams {
             .with_unit(" dB")
             .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
             .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+            input_level: FloatParam::new(
+                "Input Level",
+                util::MINUS_INFINITY_DB,
+                FloatRange::Linear {
+                    min: util::MINUS_INFINITY_DB,
+                    max: 12.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .read_only(),
         }
     }
 }
@@ -74,18 +91,7 @@ impl Plugin for Gain {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(2),
-            main_output_channels: NonZeroU32::new(2),
-            ..AudioIOLayout::const_default()
-        },
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(1),
-            main_output_channels: NonZeroU32::new(1),
-            ..AudioIOLayout::const_default()
-        },
-    ];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &AudioIOLayout::main_io_layouts([2, 1]);
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -123,8 +129,17 @@ impl Plugin for Gain {
         &mut self,
         buffer: &mut Buffer,
         _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        // Cheap meters that should also be shown by the host can be computed unconditionally and
+        // sent to the host through a read-only parameter. This is done once per block.
+        let input_peak = buffer
+            .as_slice_immutable()
+            .iter()
+            .flat_map(|channel| channel.iter())
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        context.set_read_only_parameter(&self.params.input_level, util::gain_to_db(input_peak));
+
         for channel_samples in buffer.iter_samples() {
             let mut amplitude = 0.0;
             let num_samples = channel_samples.len();
@@ -156,24 +171,18 @@ impl Plugin for Gain {
     }
 }
 
-impl ClapPlugin for Gain {
-    const CLAP_ID: &'static str = "com.moist-plugins-gmbh.gain-gui-vizia";
-    const CLAP_DESCRIPTION: Option<&'static str> = Some("A smoothed gain parameter example plugin");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
-    const CLAP_FEATURES: &'static [ClapFeature] = &[
+nih_plugin_metadata!(Gain {
+    clap_id: "com.moist-plugins-gmbh.gain-gui-vizia",
+    clap_description: "A smoothed gain parameter example plugin",
+    clap_features: [
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
         ClapFeature::Mono,
         ClapFeature::Utility,
-    ];
-}
-
-impl Vst3Plugin for Gain {
-    const VST3_CLASS_ID: [u8; 16] = *b"GainGuiVIIIZIAAA";
-    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
-        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
-}
+    ],
+    vst3_class_id: *b"GainGuiVIIIZIAAA",
+    vst3_subcategories: [Vst3SubCategory::Fx, Vst3SubCategory::Tools],
+});
 
 nih_export_clap!(Gain);
 nih_export_vst3!(Gain);
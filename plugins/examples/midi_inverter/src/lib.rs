@@ -220,8 +220,6 @@ impl ClapPlugin for MidiInverter {
     const CLAP_ID: &'static str = "com.moist-plugins-gmbh.midi-inverter";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("Inverts all note and MIDI signals in ways you don't want to");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::NoteEffect, ClapFeature::Utility];
 }
 
@@ -1,13 +1,20 @@
 use nih_plug::prelude::*;
 use rand::Rng;
 use rand_pcg::Pcg32;
+use std::f32::consts::PI;
 use std::sync::Arc;
 
 /// The number of simultaneous voices for this synth.
 const NUM_VOICES: u32 = 16;
+/// The fade-out time in milliseconds for voices that are shed because the synth ran over its voice
+/// limit. This is short enough to free up the voice quickly, but long enough to avoid clicks.
+const VOICE_SHED_FADE_MS: f32 = 5.0;
 /// The maximum size of an audio block. We'll split up the audio in blocks and render smoothed
 /// values to buffers since these values may need to be reused for multiple voices.
 const MAX_BLOCK_SIZE: usize = 64;
+/// The pitch bend range in semitones. A full pitch bend in either direction changes the pitch of
+/// all voices on that channel by this many semitones.
+const PITCH_BEND_RANGE: f32 = 2.0;
 
 // Polyphonic modulation works by assigning integer IDs to parameters. Pattern matching on these in
 // `PolyModulation` and `MonoAutomation` events makes it possible to easily link these events to the
@@ -27,10 +34,41 @@ struct PolyModSynth {
     /// The next internal voice ID, used only to figure out the oldest voice for voice stealing.
     /// This is incremented by one each time a voice is created.
     next_internal_voice_id: u64,
+    /// Lowers the number of voices when processing starts taking too long. Voices over this limit
+    /// are faded out, starting with the quietest ones.
+    voice_budget: util::VoiceBudget,
+    /// Whether the host is rendering offline. The voice budget is disabled in that case since
+    /// offline renders don't need to keep up with real time.
+    offline: bool,
+
+    /// The current pitch bend for each MIDI channel, in semitones.
+    channel_pitch_bend: [f32; 16],
+    /// Hosts may send a `PolyTuning` event for a voice before the `NoteOn` event that starts that
+    /// voice on the same sample. Those tuning events are stored here and they are applied when the
+    /// matching voice starts. This is cleared at the end of every process call. The capacity is
+    /// allocated up front, and any additional tuning events are dropped once it's full.
+    pending_tunings: Vec<PendingTuning>,
+}
+
+/// A `PolyTuning` event for a voice that has not yet been started.
+#[derive(Debug, Clone, Copy)]
+struct PendingTuning {
+    voice_id: Option<i32>,
+    channel: u8,
+    note: u8,
+    /// The tuning offset in semitones.
+    tuning: f32,
 }
 
 #[derive(Params)]
 struct PolyModSynthParams {
+    /// The oscillator's waveform. This is the same for every voice.
+    #[id = "waveform"]
+    waveform: EnumParam<util::osc::Waveform>,
+    /// The maximum number of voices. The synth may use fewer voices than this when it's running out
+    /// of CPU time.
+    #[id = "max_voices"]
+    max_voices: IntParam,
     /// A voice's gain. This can be polyphonically modulated.
     #[id = "gain"]
     gain: FloatParam,
@@ -40,6 +78,27 @@ struct PolyModSynthParams {
     /// The amplitude envelope release time. This is the same for every voice.
     #[id = "amp_rel"]
     amp_release_ms: FloatParam,
+    /// The time it takes for a new voice to glide from the pitch of the previously played voice to
+    /// its own pitch. Set to 0 to disable glide. This is the same for every voice.
+    #[id = "glide"]
+    glide_ms: FloatParam,
+
+    /// The lowpass filter's cutoff frequency, before the filter envelope is applied.
+    #[id = "flt_cut"]
+    filter_cutoff: FloatParam,
+    /// The lowpass filter's resonance, from 0 for no resonance to 1 for almost self-oscillation.
+    #[id = "flt_res"]
+    filter_resonance: FloatParam,
+    /// How much the filter envelope modulates the cutoff frequency, in octaves. This can also be
+    /// negative.
+    #[id = "flt_env"]
+    filter_env_amount: FloatParam,
+    /// The filter envelope attack time. This is the same for every voice.
+    #[id = "flt_atk"]
+    filter_attack_ms: FloatParam,
+    /// The filter envelope release time. This is the same for every voice.
+    #[id = "flt_rel"]
+    filter_release_ms: FloatParam,
 }
 
 /// Data for a single synth voice. In a real synth where performance matter, you may want to use a
@@ -60,18 +119,30 @@ struct Voice {
     internal_voice_id: u64,
     /// The square root of the note's velocity. This is used as a gain multiplier.
     velocity_sqrt: f32,
+    /// The voice's tuning offset in semitones from `PolyTuning` events. The channel's pitch bend is
+    /// added on top of this. Both are applied on top of `frequency`, so they are not glided.
+    tuning: f32,
 
-    /// The voice's current phase. This is randomized at the start of the voice
-    phase: f32,
-    /// The phase increment. This is based on the voice's frequency, derived from the note index.
-    /// Since we don't support pitch expressions or pitch bend, this value stays constant for the
-    /// duration of the voice.
-    phase_delta: f32,
+    /// The voice's bandlimited oscillator. Its phase is randomized at the start of the voice.
+    oscillator: util::osc::Oscillator,
+    /// The voice's untuned frequency in Hz, derived from the note index. When glide is enabled,
+    /// this smoothly slides from the pitch of the previously played voice to the note's pitch. The
+    /// phase increment is computed from this value and the voice's tuning and pitch bend for every
+    /// sample.
+    frequency: Smoother<f32>,
     /// Whether the key has been released and the voice is in its release stage. The voice will be
     /// terminated when the amplitude envelope hits 0 while the note is releasing.
     releasing: bool,
+    /// Whether the voice is being faded out because the synth is over its voice limit. These voices
+    /// no longer count towards the limit, and they ignore note off events.
+    shedding: bool,
     /// Fades between 0 and 1 with timings based on the global attack and release settings.
     amp_envelope: Smoother<f32>,
+    /// The same as `amp_envelope`, but using the filter envelope's timings. This modulates the
+    /// filter's cutoff frequency.
+    filter_envelope: Smoother<f32>,
+    /// The voice's lowpass filter.
+    filter: Svf,
 
     /// If this voice has polyphonic gain modulation applied, then this contains the normalized
     /// offset and a smoother.
@@ -87,6 +158,11 @@ impl Default for PolyModSynth {
             // `[None; N]` requires the `Some(T)` to be `Copy`able
             voices: [0; NUM_VOICES as usize].map(|_| None),
             next_internal_voice_id: 0,
+            voice_budget: util::VoiceBudget::new(NUM_VOICES),
+            offline: false,
+
+            channel_pitch_bend: [0.0; 16],
+            pending_tunings: Vec::with_capacity(NUM_VOICES as usize),
         }
     }
 }
@@ -94,6 +170,15 @@ impl Default for PolyModSynth {
 impl Default for PolyModSynthParams {
     fn default() -> Self {
         Self {
+            waveform: EnumParam::new("Waveform", util::osc::Waveform::Saw),
+            max_voices: IntParam::new(
+                "Max Voices",
+                NUM_VOICES as i32,
+                IntRange::Linear {
+                    min: 1,
+                    max: NUM_VOICES as i32,
+                },
+            ),
             gain: FloatParam::new(
                 "Gain",
                 util::db_to_gain(-12.0),
@@ -136,6 +221,74 @@ impl Default for PolyModSynthParams {
             )
             .with_step_size(0.1)
             .with_unit(" ms"),
+            glide_ms: FloatParam::new(
+                "Glide",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
+
+            // The filter is fully open by default, so it doesn't do anything until you turn down
+            // the cutoff frequency or dial in some envelope modulation
+            filter_cutoff: FloatParam::new(
+                "Filter Cutoff",
+                20_000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(20.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            filter_resonance: FloatParam::new(
+                "Filter Resonance",
+                0.3,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            filter_env_amount: FloatParam::new(
+                "Filter Env Amount",
+                0.0,
+                FloatRange::Linear {
+                    min: -8.0,
+                    max: 8.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(20.0))
+            .with_step_size(0.01)
+            .with_unit(" oct"),
+            filter_attack_ms: FloatParam::new(
+                "Filter Attack",
+                10.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
+            filter_release_ms: FloatParam::new(
+                "Filter Release",
+                300.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
         }
     }
 }
@@ -154,9 +307,11 @@ impl Plugin for PolyModSynth {
         ..AudioIOLayout::const_default()
     }];
 
-    // We won't need any MIDI CCs here, we just want notes and polyphonic modulation
-    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
+    // Besides notes and polyphonic modulation, this synth also responds to MIDI pitch bend
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+    // Together with reseeding the PRNG in `reset()`, this makes repeated renders identical
+    const RESET_SMOOTHERS_ON_RESET: bool = true;
 
     type SysExMessage = ();
     type BackgroundTask = ();
@@ -165,6 +320,17 @@ impl Plugin for PolyModSynth {
         self.params.clone()
     }
 
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.offline = buffer_config.process_mode == ProcessMode::Offline;
+
+        true
+    }
+
     // If the synth as a variable number of voices, you will need to call
     // `context.set_current_voice_capacity()` in `initialize()` and in `process()` (when the
     // capacity changes) to inform the host about this.
@@ -174,6 +340,9 @@ impl Plugin for PolyModSynth {
 
         self.voices.fill(None);
         self.next_internal_voice_id = 0;
+        self.voice_budget.reset();
+        self.channel_pitch_bend = [0.0; 16];
+        self.pending_tunings.clear();
     }
 
     fn process(
@@ -187,10 +356,24 @@ impl Plugin for PolyModSynth {
         // num_remaining_samples, next_event_idx - block_start_idx)`. Because blocks also need to be
         // split on note events, it's easier to work with raw audio here and to do the splitting by
         // hand.
+        if self.offline {
+            // Only the maximum number of voices applies when rendering offline, so a slow render
+            // doesn't shed voices
+            self.voice_budget.reset();
+        } else {
+            self.voice_budget.start_block();
+        }
+
         let num_samples = buffer.samples();
         let sample_rate = context.transport().sample_rate;
         let output = buffer.as_slice();
 
+        // If the synth is over its voice limit because the limit was lowered, then the quietest
+        // voices are faded out
+        self.voice_budget
+            .set_max_voices(self.params.max_voices.value() as u32);
+        self.shed_voices(sample_rate);
+
         let mut next_event = context.next_event();
         let mut block_start: usize = 0;
         let mut block_end: usize = MAX_BLOCK_SIZE.min(num_samples);
@@ -206,8 +389,9 @@ impl Plugin for PolyModSynth {
                 match next_event {
                     // If the event happens now, then we'll keep processing events
                     Some(event) if (event.timing() as usize) <= block_start => {
-                        // This synth doesn't support any of the polyphonic expression events. A
-                        // real synth plugin however will want to support those.
+                        // Besides tuning, this synth doesn't support any of the polyphonic
+                        // expression events. A real synth plugin however will want to support
+                        // those.
                         match event {
                             NoteEvent::NoteOn {
                                 timing,
@@ -223,13 +407,45 @@ impl Plugin for PolyModSynth {
                                 ));
                                 amp_envelope.reset(0.0);
                                 amp_envelope.set_target(sample_rate, 1.0);
+                                let filter_envelope = Smoother::new(SmoothingStyle::Exponential(
+                                    self.params.filter_attack_ms.value(),
+                                ));
+                                filter_envelope.reset(0.0);
+                                filter_envelope.set_target(sample_rate, 1.0);
+
+                                // The host may have already sent a tuning event for this voice
+                                // before starting it
+                                let tuning = self
+                                    .take_pending_tuning(voice_id, channel, note)
+                                    .unwrap_or(0.0);
+
+                                // The glide starts at the current pitch of the most recently
+                                // started voice that's still playing. This needs to be done before
+                                // starting the new voice since that may steal this voice. The
+                                // logarithmic smoother makes the glide linear in pitch. Tuning and
+                                // pitch bend are applied separately so they don't affect the glide.
+                                let note_frequency = util::midi_note_to_freq(note);
+                                let glide_start_frequency = self
+                                    .voices
+                                    .iter()
+                                    .filter_map(|v| v.as_ref())
+                                    .max_by_key(|v| v.internal_voice_id)
+                                    .map(|v| v.frequency.previous_value())
+                                    .unwrap_or(note_frequency);
+                                let frequency = Smoother::new(SmoothingStyle::Logarithmic(
+                                    self.params.glide_ms.value(),
+                                ));
+                                frequency.reset(glide_start_frequency);
+                                frequency.set_target(sample_rate, note_frequency);
 
                                 let voice =
                                     self.start_voice(context, timing, voice_id, channel, note);
                                 voice.velocity_sqrt = velocity.sqrt();
-                                voice.phase = initial_phase;
-                                voice.phase_delta = util::midi_note_to_freq(note) / sample_rate;
+                                voice.tuning = tuning;
+                                voice.oscillator.reset(initial_phase);
+                                voice.frequency = frequency;
                                 voice.amp_envelope = amp_envelope;
+                                voice.filter_envelope = filter_envelope;
                             }
                             NoteEvent::NoteOff {
                                 timing: _,
@@ -248,6 +464,24 @@ impl Plugin for PolyModSynth {
                             } => {
                                 self.choke_voices(context, timing, voice_id, channel, note);
                             }
+                            NoteEvent::PolyTuning {
+                                timing: _,
+                                voice_id,
+                                channel,
+                                note,
+                                tuning,
+                            } => self.set_voice_tuning(voice_id, channel, note, tuning),
+                            NoteEvent::MidiPitchBend {
+                                timing: _,
+                                channel,
+                                value,
+                            } => {
+                                // The pitch bend is normalized to `[0, 1]`, with 0.5 meaning no
+                                // pitch bend. This is applied to the channel's voices while
+                                // rendering.
+                                self.channel_pitch_bend[channel as usize] =
+                                    (value - 0.5) * 2.0 * PITCH_BEND_RANGE;
+                            }
                             NoteEvent::PolyModulation {
                                 timing: _,
                                 voice_id,
@@ -368,10 +602,26 @@ impl Plugin for PolyModSynth {
             let mut gain = [0.0; MAX_BLOCK_SIZE];
             let mut voice_gain = [0.0; MAX_BLOCK_SIZE];
             let mut voice_amp_envelope = [0.0; MAX_BLOCK_SIZE];
+            let mut voice_frequency = [0.0; MAX_BLOCK_SIZE];
+            let mut voice_filter_envelope = [0.0; MAX_BLOCK_SIZE];
+            let mut filter_cutoff = [0.0; MAX_BLOCK_SIZE];
+            let mut filter_resonance = [0.0; MAX_BLOCK_SIZE];
+            let mut filter_env_amount = [0.0; MAX_BLOCK_SIZE];
             self.params.gain.smoothed.next_block(&mut gain, block_len);
+            self.params
+                .filter_cutoff
+                .smoothed
+                .next_block(&mut filter_cutoff, block_len);
+            self.params
+                .filter_resonance
+                .smoothed
+                .next_block(&mut filter_resonance, block_len);
+            self.params
+                .filter_env_amount
+                .smoothed
+                .next_block(&mut filter_env_amount, block_len);
+            let waveform = self.params.waveform.value();
 
-            // TODO: Some form of band limiting
-            // TODO: Filter
             for voice in self.voices.iter_mut().filter_map(|v| v.as_mut()) {
                 // Depending on whether the voice has polyphonic modulation applied to it,
                 // either the global parameter values are used, or the voice's smoother is used
@@ -390,15 +640,33 @@ impl Plugin for PolyModSynth {
                 voice
                     .amp_envelope
                     .next_block(&mut voice_amp_envelope, block_len);
+                voice.frequency.next_block(&mut voice_frequency, block_len);
+                let pitch_multiplier = pitch_multiplier(
+                    voice.tuning,
+                    self.channel_pitch_bend[voice.channel as usize],
+                );
+                voice
+                    .filter_envelope
+                    .next_block(&mut voice_filter_envelope, block_len);
+                voice.oscillator.waveform = waveform;
 
                 for (value_idx, sample_idx) in (block_start..block_end).enumerate() {
                     let amp = voice.velocity_sqrt * gain[value_idx] * voice_amp_envelope[value_idx];
-                    let sample = (voice.phase * 2.0 - 1.0) * amp;
+                    voice
+                        .oscillator
+                        .set_frequency(sample_rate, voice_frequency[value_idx] * pitch_multiplier);
+                    let sample = voice.oscillator.next_sample();
 
-                    voice.phase += voice.phase_delta;
-                    if voice.phase >= 1.0 {
-                        voice.phase -= 1.0;
-                    }
+                    // The filter envelope modulates the cutoff frequency in octaves
+                    let cutoff = filter_cutoff[value_idx]
+                        * 2.0f32
+                            .powf(filter_env_amount[value_idx] * voice_filter_envelope[value_idx]);
+                    let sample = voice.filter.process_lowpass(
+                        sample_rate,
+                        cutoff,
+                        filter_resonance[value_idx],
+                        sample,
+                    ) * amp;
 
                     output[0][sample_idx] += sample;
                     output[1][sample_idx] += sample;
@@ -429,6 +697,18 @@ impl Plugin for PolyModSynth {
             block_end = (block_start + MAX_BLOCK_SIZE).min(num_samples);
         }
 
+        // Tuning events for voices that were never started are not kept around for the next buffer
+        self.pending_tunings.clear();
+
+        // The voice limit is scaled with the time it took to process this buffer. The wrapper only
+        // informs the host about the new capacity when it has actually changed.
+        if !self.offline {
+            let num_voices = self.voices.iter().filter(|voice| voice.is_some()).count() as u32;
+            self.voice_budget
+                .end_block(num_samples, sample_rate, num_voices);
+        }
+        context.set_current_voice_capacity(self.voice_budget.voice_limit());
+
         ProcessStatus::Normal
     }
 }
@@ -442,8 +722,9 @@ impl PolyModSynth {
             .position(|voice| matches!(voice, Some(voice) if voice.voice_id == voice_id))
     }
 
-    /// Start a new voice with the given voice ID. If all voices are currently in use, the oldest
-    /// voice will be stolen. Returns a reference to the new voice.
+    /// Start a new voice with the given voice ID. If all voices are currently in use or if the
+    /// synth has hit its voice limit, the oldest voice will be stolen. Returns a reference to the
+    /// new voice.
     fn start_voice(
         &mut self,
         context: &mut impl ProcessContext<Self>,
@@ -458,33 +739,52 @@ impl PolyModSynth {
             channel,
             note,
             velocity_sqrt: 1.0,
+            tuning: 0.0,
 
-            phase: 0.0,
-            phase_delta: 0.0,
+            oscillator: util::osc::Oscillator::default(),
+            frequency: Smoother::none(),
             releasing: false,
+            shedding: false,
             amp_envelope: Smoother::none(),
+            filter_envelope: Smoother::none(),
+            filter: Svf::default(),
 
             voice_gain: None,
         };
         self.next_internal_voice_id = self.next_internal_voice_id.wrapping_add(1);
 
+        // Voices that are being shed don't count towards the voice limit
+        let num_playing_voices = self
+            .voices
+            .iter()
+            .filter(|voice| matches!(voice, Some(voice) if !voice.shedding))
+            .count();
+        let at_voice_limit = num_playing_voices >= self.voice_budget.voice_limit() as usize;
+        let free_voice_idx = if at_voice_limit {
+            None
+        } else {
+            self.voices.iter().position(|voice| voice.is_none())
+        };
+
         // Can't use `.iter_mut().find()` here because nonlexical lifetimes don't apply to return
         // values
-        match self.voices.iter().position(|voice| voice.is_none()) {
+        match free_voice_idx {
             Some(free_voice_idx) => {
                 self.voices[free_voice_idx] = Some(new_voice);
                 return self.voices[free_voice_idx].as_mut().unwrap();
             }
             None => {
-                // If there is no free voice, find and steal the oldest one
-                // SAFETY: We can skip a lot of checked unwraps here since we already know all voices are in
-                //         use
-                let oldest_voice = unsafe {
-                    self.voices
-                        .iter_mut()
-                        .min_by_key(|voice| voice.as_ref().unwrap_unchecked().internal_voice_id)
-                        .unwrap_unchecked()
-                };
+                // If there is no free voice, find and steal the oldest one. When the synth has hit
+                // its voice limit, this should be a voice that's still playing. There's always at
+                // least one candidate since the voice limit is at least one.
+                let oldest_voice = self
+                    .voices
+                    .iter_mut()
+                    .filter(
+                        |voice| matches!(voice, Some(voice) if !(at_voice_limit && voice.shedding)),
+                    )
+                    .min_by_key(|voice| voice.as_ref().unwrap().internal_voice_id)
+                    .unwrap();
 
                 // The stolen voice needs to be terminated so the host can reuse its modulation
                 // resources
@@ -504,6 +804,49 @@ impl PolyModSynth {
         }
     }
 
+    /// Set the tuning offset in semitones for one or more voices. If `voice_id` is provided, then
+    /// only the voice with that ID is affected. Otherwise all voices with a matching channel and
+    /// note are affected. If no voice matches, then the tuning is stored until the matching voice
+    /// is started in the same process call.
+    fn set_voice_tuning(&mut self, voice_id: Option<i32>, channel: u8, note: u8, tuning: f32) {
+        let mut found_voice = false;
+        for voice in self.voices.iter_mut().filter_map(|v| v.as_mut()) {
+            let is_this_voice = match voice_id {
+                Some(voice_id) => voice.voice_id == voice_id,
+                None => voice.channel == channel && voice.note == note,
+            };
+            if is_this_voice {
+                voice.tuning = tuning;
+                found_voice = true;
+            }
+        }
+
+        if !found_voice && self.pending_tunings.len() < self.pending_tunings.capacity() {
+            // A later tuning event for the same voice replaces the earlier one
+            self.take_pending_tuning(voice_id, channel, note);
+            self.pending_tunings.push(PendingTuning {
+                voice_id,
+                channel,
+                note,
+                tuning,
+            });
+        }
+    }
+
+    /// Remove and return the pending tuning offset for a voice that's about to be started, if the
+    /// host sent a tuning event for it before starting it. See `pending_tunings`.
+    fn take_pending_tuning(&mut self, voice_id: Option<i32>, channel: u8, note: u8) -> Option<f32> {
+        let idx =
+            self.pending_tunings
+                .iter()
+                .position(|pending| match (pending.voice_id, voice_id) {
+                    (Some(pending_voice_id), Some(voice_id)) => pending_voice_id == voice_id,
+                    _ => pending.channel == channel && pending.note == note,
+                })?;
+
+        Some(self.pending_tunings.swap_remove(idx).tuning)
+    }
+
     /// Start the release process for one or more voice by changing their amplitude envelope. If
     /// `voice_id` is not provided, then this will terminate all matching voices.
     fn start_release_for_voices(
@@ -520,7 +863,9 @@ impl PolyModSynth {
                     channel: candidate_channel,
                     note: candidate_note,
                     releasing,
+                    shedding: false,
                     amp_envelope,
+                    filter_envelope,
                     ..
                 }) if voice_id == Some(*candidate_voice_id)
                     || (channel == *candidate_channel && note == *candidate_note) =>
@@ -529,6 +874,9 @@ impl PolyModSynth {
                     amp_envelope.style =
                         SmoothingStyle::Exponential(self.params.amp_release_ms.value());
                     amp_envelope.set_target(sample_rate, 0.0);
+                    filter_envelope.style =
+                        SmoothingStyle::Exponential(self.params.filter_release_ms.value());
+                    filter_envelope.set_target(sample_rate, 0.0);
 
                     // If this targetted a single voice ID, we're done here. Otherwise there may be
                     // multiple overlapping voices as we enabled support for that in the
@@ -542,6 +890,33 @@ impl PolyModSynth {
         }
     }
 
+    /// Fade out the quietest voices until the number of voices that are not already being faded out
+    /// fits within the voice budget's current voice limit.
+    fn shed_voices(&mut self, sample_rate: f32) {
+        let num_playing_voices = self
+            .voices
+            .iter()
+            .filter(|voice| matches!(voice, Some(voice) if !voice.shedding))
+            .count();
+        let voice_limit = self.voice_budget.voice_limit() as usize;
+
+        for _ in voice_limit..num_playing_voices {
+            let quietest_voice = self
+                .voices
+                .iter_mut()
+                .filter_map(|voice| voice.as_mut())
+                .filter(|voice| !voice.shedding)
+                .min_by(|a, b| a.loudness().total_cmp(&b.loudness()));
+            if let Some(voice) = quietest_voice {
+                // The voice will be terminated as usual once the fade out has finished
+                voice.shedding = true;
+                voice.releasing = true;
+                voice.amp_envelope.style = SmoothingStyle::Exponential(VOICE_SHED_FADE_MS);
+                voice.amp_envelope.set_target(sample_rate, 0.0);
+            }
+        }
+    }
+
     /// Immediately terminate one or more voice, removing it from the pool and informing the host
     /// that the voice has ended. If `voice_id` is not provided, then this will terminate all
     /// matching voices.
@@ -582,6 +957,58 @@ impl PolyModSynth {
     }
 }
 
+impl Voice {
+    /// A rough estimate of how loud this voice currently is. Used to decide which voices to shed
+    /// first.
+    fn loudness(&self) -> f32 {
+        self.velocity_sqrt * self.amp_envelope.previous_value()
+    }
+}
+
+/// A simple lowpass state variable filter using the trapezoidal integration approach from Andrew
+/// Simper's "Linear Trap Integrated SVF" paper. This stays stable and responds well when the cutoff
+/// frequency is modulated at audio rate, which is why the coefficients can simply be recomputed for
+/// every sample.
+#[derive(Debug, Default, Clone)]
+struct Svf {
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    /// Filter a single sample. `resonance` is in `[0, 1]`, where 0 results in a Q of 0.5 and 1 is
+    /// close to self-oscillation.
+    fn process_lowpass(
+        &mut self,
+        sample_rate: f32,
+        cutoff: f32,
+        resonance: f32,
+        input: f32,
+    ) -> f32 {
+        // The cutoff may be pushed past the Nyquist frequency by the filter envelope
+        let cutoff = cutoff.clamp(10.0, sample_rate * 0.49);
+        let g = (PI * cutoff / sample_rate).tan();
+        let k = 2.0 - (1.98 * resonance);
+
+        let a1 = (1.0 + g * (g + k)).recip();
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        let v3 = input - self.ic2eq;
+        let v1 = (a1 * self.ic1eq) + (a2 * v3);
+        let v2 = self.ic2eq + (a2 * self.ic1eq) + (a3 * v3);
+        self.ic1eq = (2.0 * v1) - self.ic1eq;
+        self.ic2eq = (2.0 * v2) - self.ic2eq;
+
+        v2
+    }
+}
+
+/// The frequency multiplier for a tuning offset and a pitch bend, both in semitones.
+fn pitch_multiplier(tuning: f32, pitch_bend: f32) -> f32 {
+    2.0f32.powf((tuning + pitch_bend) / 12.0)
+}
+
 /// Compute a voice ID in case the host doesn't provide them. Polyphonic modulation will not work in
 /// this case, but playing notes will.
 const fn compute_fallback_voice_id(note: u8, channel: u8) -> i32 {
@@ -592,8 +1019,6 @@ impl ClapPlugin for PolyModSynth {
     const CLAP_ID: &'static str = "com.moist-plugins-gmbh.poly-mod-synth";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("A simple polyphonic synthesizer with support for polyphonic modulation");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::Instrument,
         ClapFeature::Synthesizer,
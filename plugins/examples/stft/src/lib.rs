@@ -166,8 +166,6 @@ impl Plugin for Stft {
 impl ClapPlugin for Stft {
     const CLAP_ID: &'static str = "com.moist-plugins-gmbh.stft";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("An example plugin using the STFT helper");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
@@ -99,8 +99,6 @@ impl ClapPlugin for SysEx {
     const CLAP_ID: &'static str = "com.moist-plugins-gmbh.sysex";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("An example plugin to demonstrate sending and receiving SysEx");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::NoteEffect, ClapFeature::Utility];
 }
 
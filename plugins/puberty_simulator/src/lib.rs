@@ -20,6 +20,10 @@ use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 use std::f32;
 use std::sync::Arc;
 
+use crate::phase_vocoder::PhaseVocoder;
+
+mod phase_vocoder;
+
 const MIN_WINDOW_ORDER: usize = 6;
 #[allow(dead_code)]
 const MIN_WINDOW_SIZE: usize = 1 << MIN_WINDOW_ORDER; // 64
@@ -51,8 +55,15 @@ struct PubertySimulator {
     /// The algorithms for the FFT and IFFT operations, for each supported order so we can switch
     /// between them without replanning or allocations. Initialized during `initialize()`.
     plan_for_order: Option<[Plan; MAX_WINDOW_ORDER - MIN_WINDOW_ORDER + 1]>,
-    /// The output of our real->complex FFT.
-    complex_fft_buffer: Vec<Complex32>,
+    /// The output of our real->complex FFT, for each channel. All channels are processed at the
+    /// same time so the stereo link option can take every channel into account.
+    complex_fft_buffers: Vec<Vec<Complex32>>,
+    /// The sum of all channels' spectra, used as a shared phase reference when the stereo link
+    /// option is enabled.
+    reference_fft_buffer: Vec<Complex32>,
+    /// Used for the non-broken pitch shifting mode. This keeps track of the phases between STFT
+    /// frames.
+    phase_vocoder: PhaseVocoder,
 }
 
 /// A plan for a specific window size, all of which will be precomputed during initilaization.
@@ -77,9 +88,14 @@ struct PubertySimulatorParams {
     #[id = "ovrlap"]
     overlap_times_order: IntParam,
 
-    /// The type of broken pitch shifting to apply.
+    /// The type of (broken) pitch shifting to apply.
     #[id = "mode"]
     mode: EnumParam<PitchShiftingMode>,
+    /// When enabled, the polar interpolation mode uses a shared phase reference for all channels
+    /// so the phase relationships between the channels are preserved. Without this the stereo
+    /// image tends to smear and wander.
+    #[id = "link"]
+    stereo_link: BoolParam,
 }
 
 #[derive(Enum, Debug, PartialEq)]
@@ -94,6 +110,11 @@ enum PitchShiftingMode {
     #[id = "interpolated-polar"]
     #[name = "Also very broken"]
     InterpolatePolar,
+    /// An actual phase vocoder that keeps track of the bins' phases between frames. This is the
+    /// only mode that results in clean pitch shifting, which makes it the most boring one.
+    #[id = "phase-vocoder"]
+    #[name = "Not broken"]
+    PhaseVocoder,
 }
 
 impl Default for PubertySimulator {
@@ -105,7 +126,11 @@ impl Default for PubertySimulator {
             window_function: Vec::with_capacity(MAX_WINDOW_SIZE),
 
             plan_for_order: None,
-            complex_fft_buffer: Vec::with_capacity(MAX_WINDOW_SIZE / 2 + 1),
+            complex_fft_buffers: (0..2)
+                .map(|_| Vec::with_capacity(MAX_WINDOW_SIZE / 2 + 1))
+                .collect(),
+            reference_fft_buffer: Vec::with_capacity(MAX_WINDOW_SIZE / 2 + 1),
+            phase_vocoder: PhaseVocoder::new(2, MAX_WINDOW_SIZE / 2 + 1),
         }
     }
 }
@@ -130,7 +155,8 @@ impl Default for PubertySimulatorParams {
             // overlap-add, but sounds kind of slick
             .with_smoother(SmoothingStyle::Linear(100.0))
             .with_unit(" Octaves")
-            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .bipolar(),
 
             window_size_order: IntParam::new(
                 "Window Size",
@@ -141,7 +167,8 @@ impl Default for PubertySimulatorParams {
                 },
             )
             .with_value_to_string(power_of_two_val2str.clone())
-            .with_string_to_value(power_of_two_str2val.clone()),
+            .with_string_to_value(power_of_two_str2val.clone())
+            .affects_latency(),
             overlap_times_order: IntParam::new(
                 "Window Overlap",
                 DEFAULT_OVERLAP_ORDER as i32,
@@ -153,6 +180,7 @@ impl Default for PubertySimulatorParams {
             .with_value_to_string(power_of_two_val2str)
             .with_string_to_value(power_of_two_str2val),
             mode: EnumParam::new("Mode", PitchShiftingMode::InterpolateRectangular),
+            stereo_link: BoolParam::new("Stereo Link", false),
         }
     }
 }
@@ -165,12 +193,7 @@ impl Plugin for PubertySimulator {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    // We'll only do stereo for simplicity's sake
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
-        ..AudioIOLayout::const_default()
-    }];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &AudioIOLayout::main_io_layouts([2, 1]);
 
     type SysExMessage = ();
     type BackgroundTask = ();
@@ -181,10 +204,27 @@ impl Plugin for PubertySimulator {
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         _buffer_config: &BufferConfig,
         context: &mut impl InitContext<Self>,
     ) -> bool {
+        // This plugin can accept a variable number of audio channels, so we need to resize
+        // channel-dependent data structures accordingly
+        let num_output_channels = audio_io_layout
+            .main_output_channels
+            .expect("Plugin does not have a main output")
+            .get() as usize;
+        if self.stft.num_channels() != num_output_channels {
+            self.stft = util::StftHelper::new(num_output_channels, MAX_WINDOW_SIZE, 0);
+            self.complex_fft_buffers = (0..num_output_channels)
+                .map(|_| Vec::with_capacity(MAX_WINDOW_SIZE / 2 + 1))
+                .collect();
+            self.phase_vocoder = PhaseVocoder::new(num_output_channels, MAX_WINDOW_SIZE / 2 + 1);
+
+            // This forces the buffers to be resized for the current window size below
+            self.window_function.clear();
+        }
+
         // Planning with RustFFT is very fast, but it will still allocate we we'll plan all of the
         // FFTs we might need in advance
         if self.plan_for_order.is_none() {
@@ -195,15 +235,17 @@ impl Plugin for PubertySimulator {
                     c2r_plan: planner.plan_fft_inverse(1 << order),
                 })
                 .collect();
-            self.plan_for_order = Some(
-                plan_for_order
-                    .try_into()
-                    .unwrap_or_else(|_| panic!("Mismatched plan orders")),
-            );
+            match plan_for_order.try_into() {
+                Ok(plan_for_order) => self.plan_for_order = Some(plan_for_order),
+                Err(_) => {
+                    // This can only happen if the plan array's length doesn't match the range
+                    // between `MIN_WINDOW_ORDER` and `MAX_WINDOW_ORDER`
+                    nih_error!("Mismatched FFT plan orders, failing initialization");
+                    return false;
+                }
+            }
         }
 
-        // Normally we'd also initialize the STFT helper for the correct channel count here, but we
-        // only do stereo so that's not necessary
         let window_size = self.window_size();
         if self.window_function.len() != window_size {
             self.resize_for_window(window_size);
@@ -217,6 +259,7 @@ impl Plugin for PubertySimulator {
     fn reset(&mut self) {
         // This zeroes out the buffers
         self.stft.set_block_size(self.window_size());
+        self.phase_vocoder.reset();
     }
 
     fn process(
@@ -230,11 +273,6 @@ impl Plugin for PubertySimulator {
         let window_size = self.window_size();
         let overlap_times = self.overlap_times();
         let sample_rate = context.transport().sample_rate;
-        // The overlap gain compensation is based on a squared Hann window, which will sum perfectly
-        // at four times overlap or higher. We'll apply a regular Hann window before the analysis
-        // and after the synthesis.
-        let gain_compensation: f32 =
-            ((overlap_times as f32 / 4.0) * 1.5).recip() / window_size as f32;
 
         // If the window size has changed since the last process call, reset the buffers and chance
         // our latency. All of these buffers already have enough capacity
@@ -244,157 +282,260 @@ impl Plugin for PubertySimulator {
             context.set_latency_samples(self.stft.latency_samples());
         }
 
+        // We'll apply a regular Hann window before the analysis and after the synthesis. The
+        // overlap gain compensation accounts for this squared window, and the extra division
+        // compensates for the gain introduced by the IDFT operation.
+        let gain_compensation: f32 =
+            util::window::overlap_add_gain(&self.window_function, overlap_times)
+                / window_size as f32;
+
         // These plans have already been made during initialization we can switch between versions
         // without reallocating
         let fft_plan = &mut self.plan_for_order.as_mut().unwrap()
             [self.params.window_size_order.value() as usize - MIN_WINDOW_ORDER];
 
-        let mut smoothed_pitch_value = 0.0;
+        let mode = self.params.mode.value();
+        let stereo_link = self.params.stereo_link.value();
         self.stft
-            .process_overlap_add(buffer, overlap_times, |channel_idx, real_fft_buffer| {
-                // This loop runs whenever there's a block ready, so we can't easily do any post- or
-                // pre-processing without muddying up the interface. But if this is channel 0, then
-                // we're dealing with a new block. We'll use this for our parameter smoothing.
-                if channel_idx == 0 {
-                    smoothed_pitch_value = self
-                        .params
-                        .pitch_octaves
-                        .smoothed
-                        .next_step((window_size / overlap_times) as u32);
+            .process_overlap_add_multichannel(buffer, overlap_times, |real_fft_buffers| {
+                // This callback runs whenever there's a block ready for all channels, so we'll use
+                // this for our parameter smoothing
+                let smoothed_pitch_value = self
+                    .params
+                    .pitch_octaves
+                    .smoothed
+                    .next_step((window_size / overlap_times) as u32);
+                let bin_mapping = BinMapping {
+                    window_size,
+                    sample_rate,
+                    // Negated because pitching down should cause us to take values from higher
+                    // frequency bins
+                    frequency_multiplier: 2.0f32.powf(-smoothed_pitch_value),
+                    // Random extra gain, not sure
+                    gain: 3.0 * gain_compensation,
+                };
+
+                for (real_fft_buffer, complex_fft_buffer) in real_fft_buffers
+                    .iter_mut()
+                    .zip(self.complex_fft_buffers.iter_mut())
+                {
+                    // We'll window the input with a Hann function to avoid spectral leakage
+                    util::window::multiply_with_window(real_fft_buffer, &self.window_function);
+
+                    // RustFFT doesn't actually need a scratch buffer here, so we'll pass an empty
+                    // buffer instead
+                    fft_plan
+                        .r2c_plan
+                        .process_with_scratch(real_fft_buffer, complex_fft_buffer, &mut [])
+                        .unwrap();
                 }
-                // Negated because pitching down should cause us to take values from higher frequency bins
-                let frequency_multiplier = 2.0f32.powf(-smoothed_pitch_value);
-
-                // We'll window the input with a Hann function to avoid spectral leakage
-                util::window::multiply_with_window(real_fft_buffer, &self.window_function);
-
-                // RustFFT doesn't actually need a scratch buffer here, so we'll pass an empty
-                // buffer instead
-                fft_plan
-                    .r2c_plan
-                    .process_with_scratch(real_fft_buffer, &mut self.complex_fft_buffer, &mut [])
-                    .unwrap();
-
-                // TODO: Move this to helper functions. These functions capture a lot of variables
-                //       here so that might require some work. And branch preductors are probably
-                //       good enough to be able to put the match inside of the `process_bin`
-                //       function, but it seems preferable to have it outside of the loop.
-                let num_bins = self.complex_fft_buffer.len();
-                match self.params.mode.value() {
+
+                match mode {
                     PitchShiftingMode::InterpolateRectangular => {
-                        // This simply interpolates the sine and cosine waves composing the complex
-                        // sinusoids from the frequency bins to neighbouring frequency bins scaled
-                        // by the octave pitch multiplies. The iteration order dependson the pitch
-                        // shifting direction since we're doing it in place.
-                        let mut process_bin = |bin_idx| {
-                            let frequency = bin_idx as f32 / window_size as f32 * sample_rate;
-                            let target_frequency = frequency * frequency_multiplier;
-
-                            // Simple linear interpolation
-                            let target_bin = target_frequency / sample_rate * window_size as f32;
-                            let target_bin_floor = target_bin.floor() as usize;
-                            let target_bin_ceil = target_bin.ceil() as usize;
-                            let target_floor_t = target_bin % 1.0;
-                            let target_ceil_t = 1.0 - target_floor_t;
-                            let target_floor = self
-                                .complex_fft_buffer
-                                .get(target_bin_floor)
-                                .copied()
-                                .unwrap_or_default();
-                            let target_ceil = self
-                                .complex_fft_buffer
-                                .get(target_bin_ceil)
-                                .copied()
-                                .unwrap_or_default();
-
-                            self.complex_fft_buffer[bin_idx] = (target_floor * target_floor_t
-                                + target_ceil * target_ceil_t)
-                                * 3.0 // Random extra gain, not sure
-                                * gain_compensation;
-                        };
-
-                        if frequency_multiplier >= 1.0 {
-                            for bin_idx in 0..num_bins {
-                                process_bin(bin_idx);
-                            }
-                        } else {
-                            for bin_idx in (0..num_bins).rev() {
-                                process_bin(bin_idx);
-                            }
+                        for complex_fft_buffer in self.complex_fft_buffers.iter_mut() {
+                            interpolate_rectangular(complex_fft_buffer, &bin_mapping);
                         }
                     }
+                    PitchShiftingMode::InterpolatePolar
+                        if stereo_link && self.complex_fft_buffers.len() > 1 =>
+                    {
+                        interpolate_polar_linked(
+                            &mut self.complex_fft_buffers,
+                            &mut self.reference_fft_buffer,
+                            &bin_mapping,
+                        );
+                    }
                     PitchShiftingMode::InterpolatePolar => {
-                        // Same as the above, but interpolating in the polar form instead. While
-                        // this does sound more correct it doesn't sound nearly as hilarious, and it
-                        // just sounds bad at this point. But maybe there's some use for this.
-                        let mut process_bin = |bin_idx| {
-                            let frequency = bin_idx as f32 / window_size as f32 * sample_rate;
-                            let target_frequency = frequency * frequency_multiplier;
-
-                            // Simple linear interpolation
-                            let target_bin = target_frequency / sample_rate * window_size as f32;
-                            let target_bin_floor = target_bin.floor() as usize;
-                            let target_bin_ceil = target_bin.ceil() as usize;
-                            let target_floor_t = target_bin % 1.0;
-                            let target_ceil_t = 1.0 - target_floor_t;
-                            let target_floor = self
-                                .complex_fft_buffer
-                                .get(target_bin_floor)
-                                .copied()
-                                .unwrap_or_default();
-                            let target_ceil = self
-                                .complex_fft_buffer
-                                .get(target_bin_ceil)
-                                .copied()
-                                .unwrap_or_default();
-
-                            let target_floor_magnitude = target_floor.norm();
-                            let target_floor_phase = target_floor.arg();
-                            let target_ceil_magnitude = target_ceil.norm();
-                            let target_ceil_phase = target_ceil.arg();
-
-                            self.complex_fft_buffer[bin_idx] = Complex32::from_polar(
-                                (target_floor_magnitude * target_floor_t)
-                                    + (target_ceil_magnitude * target_ceil_t),
-                                (target_floor_phase * target_floor_t)
-                                    + (target_ceil_phase * target_ceil_t),
-                            ) * 3.0 // Random extra gain, not sure
-                                * gain_compensation;
-                        };
-
-                        if frequency_multiplier >= 1.0 {
-                            for bin_idx in 0..num_bins {
-                                process_bin(bin_idx);
-                            }
-                        } else {
-                            for bin_idx in (0..num_bins).rev() {
-                                process_bin(bin_idx);
-                            }
+                        for complex_fft_buffer in self.complex_fft_buffers.iter_mut() {
+                            interpolate_polar(complex_fft_buffer, &bin_mapping);
+                        }
+                    }
+                    PitchShiftingMode::PhaseVocoder => {
+                        for (channel_idx, complex_fft_buffer) in
+                            self.complex_fft_buffers.iter_mut().enumerate()
+                        {
+                            // Unlike the broken modes, this doesn't need any random extra gain
+                            self.phase_vocoder.process(
+                                channel_idx,
+                                complex_fft_buffer,
+                                bin_mapping.frequency_multiplier.recip(),
+                                overlap_times,
+                                gain_compensation,
+                            );
                         }
                     }
                 }
 
-                // Make sure the imaginary components on the first and last bin are zero
-                self.complex_fft_buffer[0].im = 0.0;
-                self.complex_fft_buffer[num_bins - 1].im = 0.0;
-
-                // Inverse FFT back into the scratch buffer. This will be added to a ring buffer
-                // which gets written back to the host at a one block delay.
-                fft_plan
-                    .c2r_plan
-                    .process_with_scratch(&mut self.complex_fft_buffer, real_fft_buffer, &mut [])
-                    .unwrap();
-
-                // Apply the window function once more to reduce time domain aliasing. The gain
-                // compensation compensates for the squared Hann window that would be applied if we
-                // didn't do any processing at all.
-                util::window::multiply_with_window(real_fft_buffer, &self.window_function);
+                for (real_fft_buffer, complex_fft_buffer) in real_fft_buffers
+                    .iter_mut()
+                    .zip(self.complex_fft_buffers.iter_mut())
+                {
+                    // Make sure the imaginary components on the first and last bin are zero
+                    util::SpectralBuffer::new(complex_fft_buffer).zero_dc_nyquist();
+
+                    // Inverse FFT back into the scratch buffer. This will be added to a ring buffer
+                    // which gets written back to the host at a one block delay.
+                    fft_plan
+                        .c2r_plan
+                        .process_with_scratch(complex_fft_buffer, real_fft_buffer, &mut [])
+                        .unwrap();
+
+                    // Apply the window function once more to reduce time domain aliasing. The gain
+                    // compensation compensates for the squared Hann window that would be applied if
+                    // we didn't do any processing at all.
+                    util::window::multiply_with_window(real_fft_buffer, &self.window_function);
+                }
             });
 
         ProcessStatus::Normal
     }
 }
 
+/// The parameters needed to map an output bin to the bins it should take its values from.
+struct BinMapping {
+    window_size: usize,
+    sample_rate: f32,
+    /// The factor the bins' frequencies get multiplied by. Greater than one when pitching down.
+    frequency_multiplier: f32,
+    /// The gain applied to every output bin.
+    gain: f32,
+}
+
+impl BinMapping {
+    /// Get the bin indices `bin_idx` should take its values from, along with the weights for those
+    /// bins. This is `(floor_bin_idx, ceil_bin_idx, floor_t, ceil_t)`. The indices may point past
+    /// the end of the spectrum.
+    fn source_bins(&self, bin_idx: usize) -> (usize, usize, f32, f32) {
+        let frequency = bin_idx as f32 / self.window_size as f32 * self.sample_rate;
+        let target_frequency = frequency * self.frequency_multiplier;
+
+        // Simple linear interpolation
+        let target_bin = target_frequency / self.sample_rate * self.window_size as f32;
+        let target_bin_floor = target_bin.floor() as usize;
+        let target_bin_ceil = target_bin.ceil() as usize;
+        let target_floor_t = target_bin % 1.0;
+        let target_ceil_t = 1.0 - target_floor_t;
+
+        (
+            target_bin_floor,
+            target_bin_ceil,
+            target_floor_t,
+            target_ceil_t,
+        )
+    }
+
+    /// Call `process_bin` for every bin in the spectrum in an order that allows the spectrum to be
+    /// modified in place. The iteration order depends on the pitch shifting direction.
+    fn for_each_bin(&self, num_bins: usize, mut process_bin: impl FnMut(usize)) {
+        if self.frequency_multiplier >= 1.0 {
+            for bin_idx in 0..num_bins {
+                process_bin(bin_idx);
+            }
+        } else {
+            for bin_idx in (0..num_bins).rev() {
+                process_bin(bin_idx);
+            }
+        }
+    }
+}
+
+/// Directly interpolate the sine and cosine waves composing the complex sinusoids from the
+/// frequency bins to neighbouring frequency bins scaled by the octave pitch multiplier.
+fn interpolate_rectangular(complex_fft_buffer: &mut [Complex32], bin_mapping: &BinMapping) {
+    bin_mapping.for_each_bin(complex_fft_buffer.len(), |bin_idx| {
+        let (target_bin_floor, target_bin_ceil, target_floor_t, target_ceil_t) =
+            bin_mapping.source_bins(bin_idx);
+        let target_floor = complex_fft_buffer
+            .get(target_bin_floor)
+            .copied()
+            .unwrap_or_default();
+        let target_ceil = complex_fft_buffer
+            .get(target_bin_ceil)
+            .copied()
+            .unwrap_or_default();
+
+        complex_fft_buffer[bin_idx] =
+            (target_floor * target_floor_t + target_ceil * target_ceil_t) * bin_mapping.gain;
+    });
+}
+
+/// Same as [`interpolate_rectangular()`], but interpolating in the polar form instead. While this
+/// does sound more correct it doesn't sound nearly as hilarious, and it just sounds bad at this
+/// point. But maybe there's some use for this.
+fn interpolate_polar(complex_fft_buffer: &mut [Complex32], bin_mapping: &BinMapping) {
+    bin_mapping.for_each_bin(complex_fft_buffer.len(), |bin_idx| {
+        let (target_bin_floor, target_bin_ceil, target_floor_t, target_ceil_t) =
+            bin_mapping.source_bins(bin_idx);
+        let target_floor = complex_fft_buffer
+            .get(target_bin_floor)
+            .copied()
+            .unwrap_or_default();
+        let target_ceil = complex_fft_buffer
+            .get(target_bin_ceil)
+            .copied()
+            .unwrap_or_default();
+
+        let target_floor_magnitude = target_floor.norm();
+        let target_floor_phase = target_floor.arg();
+        let target_ceil_magnitude = target_ceil.norm();
+        let target_ceil_phase = target_ceil.arg();
+
+        complex_fft_buffer[bin_idx] = Complex32::from_polar(
+            (target_floor_magnitude * target_floor_t) + (target_ceil_magnitude * target_ceil_t),
+            (target_floor_phase * target_floor_t) + (target_ceil_phase * target_ceil_t),
+        ) * bin_mapping.gain;
+    });
+}
+
+/// The same as [`interpolate_polar()`], but the interpolated phase is computed once from a
+/// reference spectrum containing the sum of all channels. Every channel then adds its own phase
+/// offset relative to that reference, so the phase relationships between the channels are
+/// preserved. `reference_fft_buffer` is used as scratch space for the reference spectrum.
+fn interpolate_polar_linked(
+    complex_fft_buffers: &mut [Vec<Complex32>],
+    reference_fft_buffer: &mut [Complex32],
+    bin_mapping: &BinMapping,
+) {
+    let num_bins = complex_fft_buffers[0].len();
+    nih_debug_assert_eq!(reference_fft_buffer.len(), num_bins);
+
+    reference_fft_buffer.fill(Complex32::default());
+    for complex_fft_buffer in complex_fft_buffers.iter() {
+        for (reference_bin, bin) in reference_fft_buffer.iter_mut().zip(complex_fft_buffer) {
+            *reference_bin += *bin;
+        }
+    }
+
+    // The reference spectrum is never written to, so it always contains the original spectrum
+    let reference_fft_buffer = &*reference_fft_buffer;
+    bin_mapping.for_each_bin(num_bins, |bin_idx| {
+        let (target_bin_floor, target_bin_ceil, target_floor_t, target_ceil_t) =
+            bin_mapping.source_bins(bin_idx);
+        // The phase offset between the channel and the reference is taken from whichever bin
+        // contributes most to the interpolated value
+        let target_bin_nearest = if target_floor_t >= target_ceil_t {
+            target_bin_floor
+        } else {
+            target_bin_ceil
+        };
+
+        let reference_bin = |idx: usize| reference_fft_buffer.get(idx).copied().unwrap_or_default();
+        let reference_phase = (reference_bin(target_bin_floor).arg() * target_floor_t)
+            + (reference_bin(target_bin_ceil).arg() * target_ceil_t);
+        let reference_nearest_phase = reference_bin(target_bin_nearest).arg();
+
+        for complex_fft_buffer in complex_fft_buffers.iter_mut() {
+            let channel_bin = |idx: usize| complex_fft_buffer.get(idx).copied().unwrap_or_default();
+            let magnitude = (channel_bin(target_bin_floor).norm() * target_floor_t)
+                + (channel_bin(target_bin_ceil).norm() * target_ceil_t);
+            let phase_offset = channel_bin(target_bin_nearest).arg() - reference_nearest_phase;
+
+            complex_fft_buffer[bin_idx] =
+                Complex32::from_polar(magnitude, reference_phase + phase_offset) * bin_mapping.gain;
+        }
+    });
+}
+
 impl PubertySimulator {
     fn window_size(&self) -> usize {
         1 << self.params.window_size_order.value() as usize
@@ -409,8 +550,12 @@ impl PubertySimulator {
         // The FFT algorithms for this window size have already been planned
         self.stft.set_block_size(window_size);
         self.window_function.resize(window_size, 0.0);
-        self.complex_fft_buffer
+        for complex_fft_buffer in &mut self.complex_fft_buffers {
+            complex_fft_buffer.resize(window_size / 2 + 1, Complex32::default());
+        }
+        self.reference_fft_buffer
             .resize(window_size / 2 + 1, Complex32::default());
+        self.phase_vocoder.resize(window_size / 2 + 1);
         util::window::hann_in_place(&mut self.window_function);
     }
 }
@@ -418,11 +563,10 @@ impl PubertySimulator {
 impl ClapPlugin for PubertySimulator {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.puberty-simulator";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("Simulates a pitched down cracking voice");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
+        ClapFeature::Mono,
         ClapFeature::Glitch,
         ClapFeature::PitchShifter,
     ];
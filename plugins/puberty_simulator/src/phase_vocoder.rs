@@ -0,0 +1,154 @@
+// Puberty Simulator: the next generation in voice change simulation technology
+// Copyright (C) 2022-2024 Robbert van der Helm
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use nih_plug::prelude::*;
+use realfft::num_complex::Complex32;
+use std::f32::consts::TAU;
+
+/// A basic phase vocoder pitch shifter. This moves the bins' magnitudes to other bins like the
+/// broken modes do, but it also estimates every bin's true frequency from the phase difference
+/// between two consecutive frames. Those frequencies are then used to accumulate the output phases
+/// so the output stays coherent between frames. Unlike the other pitch shifting modes, this thus
+/// needs to keep track of state for every channel between STFT frames.
+pub struct PhaseVocoder {
+    /// The analysis phases for every bin from the previous frame, for every channel.
+    previous_phases: Vec<Vec<f32>>,
+    /// The accumulated output phases for every bin, for every channel.
+    synthesis_phases: Vec<Vec<f32>>,
+
+    // These are scratch buffers that are shared between all channels
+    magnitudes: Vec<f32>,
+    phases: Vec<f32>,
+    synthesis_magnitudes: Vec<f32>,
+    /// The true frequency of the bin that ended up in this output bin, in fractional bins.
+    synthesis_frequencies: Vec<f32>,
+}
+
+impl PhaseVocoder {
+    /// Create a phase vocoder for `num_channels` channels with enough capacity for `max_num_bins`
+    /// bins. [`resize()`][Self::resize()] needs to be called before processing audio.
+    pub fn new(num_channels: usize, max_num_bins: usize) -> Self {
+        let channel_buffers = || {
+            (0..num_channels)
+                .map(|_| Vec::with_capacity(max_num_bins))
+                .collect()
+        };
+
+        Self {
+            previous_phases: channel_buffers(),
+            synthesis_phases: channel_buffers(),
+
+            magnitudes: Vec::with_capacity(max_num_bins),
+            phases: Vec::with_capacity(max_num_bins),
+            synthesis_magnitudes: Vec::with_capacity(max_num_bins),
+            synthesis_frequencies: Vec::with_capacity(max_num_bins),
+        }
+    }
+
+    /// Change the number of bins. This also resets the phase vocoder's state. This does not
+    /// allocate as long as `num_bins` does not exceed the capacity passed to
+    /// [`new()`][Self::new()].
+    pub fn resize(&mut self, num_bins: usize) {
+        for buffer in self
+            .previous_phases
+            .iter_mut()
+            .chain(self.synthesis_phases.iter_mut())
+            .chain([
+                &mut self.magnitudes,
+                &mut self.phases,
+                &mut self.synthesis_magnitudes,
+                &mut self.synthesis_frequencies,
+            ])
+        {
+            buffer.resize(num_bins, 0.0);
+        }
+
+        self.reset();
+    }
+
+    /// Clear the phases stored for every channel.
+    pub fn reset(&mut self) {
+        for phases in self
+            .previous_phases
+            .iter_mut()
+            .chain(self.synthesis_phases.iter_mut())
+        {
+            phases.fill(0.0);
+        }
+    }
+
+    /// Pitch shift a channel's spectrum in place. The bins' frequencies are multiplied by
+    /// `pitch_multiplier`, and the output is multiplied by `gain`. `overlap_times` is needed to
+    /// compute how much each bin's phase is expected to advance between two frames.
+    pub fn process(
+        &mut self,
+        channel_idx: usize,
+        complex_fft_buffer: &mut [Complex32],
+        pitch_multiplier: f32,
+        overlap_times: usize,
+        gain: f32,
+    ) {
+        let num_bins = complex_fft_buffer.len();
+        nih_debug_assert_eq!(num_bins, self.magnitudes.len());
+
+        let previous_phases = &mut self.previous_phases[channel_idx];
+        let synthesis_phases = &mut self.synthesis_phases[channel_idx];
+
+        let mut spectrum = util::SpectralBuffer::new(complex_fft_buffer);
+        spectrum.magnitudes_into(&mut self.magnitudes);
+        spectrum.phases_into(&mut self.phases);
+
+        // Between two frames the phase of bin `k` is expected to advance by `k` times this amount.
+        // Any deviation from that means that the bin's true frequency is slightly off from the
+        // bin's center frequency.
+        let expected_phase_step = TAU / overlap_times as f32;
+
+        self.synthesis_magnitudes.fill(0.0);
+        self.synthesis_frequencies.fill(0.0);
+        for (bin_idx, ((magnitude, phase), previous_phase)) in self
+            .magnitudes
+            .iter()
+            .zip(self.phases.iter())
+            .zip(previous_phases.iter_mut())
+            .enumerate()
+        {
+            let phase_delta = phase - *previous_phase - (bin_idx as f32 * expected_phase_step);
+            *previous_phase = *phase;
+
+            // This wraps the deviation to `[-pi, pi]`
+            let phase_deviation = phase_delta - ((phase_delta / TAU).round() * TAU);
+            let true_bin = bin_idx as f32 + (phase_deviation / expected_phase_step);
+
+            let target_bin_idx = (bin_idx as f32 * pitch_multiplier).round() as usize;
+            if target_bin_idx < num_bins {
+                self.synthesis_magnitudes[target_bin_idx] += magnitude;
+                self.synthesis_frequencies[target_bin_idx] = true_bin * pitch_multiplier;
+            }
+        }
+
+        for (synthesis_phase, synthesis_frequency) in synthesis_phases
+            .iter_mut()
+            .zip(self.synthesis_frequencies.iter())
+        {
+            // The modulo keeps the accumulated phases from losing precision over time
+            *synthesis_phase =
+                (*synthesis_phase + (synthesis_frequency * expected_phase_step)) % TAU;
+        }
+
+        spectrum.set_polar(&self.synthesis_magnitudes, synthesis_phases);
+        spectrum.apply_gain(gain);
+    }
+}
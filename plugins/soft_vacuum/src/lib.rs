@@ -19,9 +19,9 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use nih_plug::prelude::*;
+use nih_plug::util::Lanczos3Oversampler;
 
 mod hard_vacuum;
-mod oversampling;
 
 /// The maximum number of samples to process at a time. Used to create scratch buffers for the
 /// oversampling.
@@ -43,11 +43,11 @@ struct SoftVacuum {
     /// needs to maintain its own state.
     hard_vacuum_processors: Vec<hard_vacuum::HardVacuum>,
     /// Oversampling for each channel.
-    oversamplers: Vec<oversampling::Lanczos3Oversampler>,
+    oversamplers: Vec<Lanczos3Oversampler>,
     /// Oversampling for each channel's slew control signal. This is upsampled separately to make
     /// the oversampled algorithm sound similar to the regular, non oversampled version as the slews
     /// will necessarily be lower in the oversampled version.
-    slew_oversamplers: Vec<oversampling::Lanczos3Oversampler>,
+    slew_oversamplers: Vec<Lanczos3Oversampler>,
 
     /// Scratch buffers that the smoothed parameters can be rendered to. Allocated on the heap
     /// because Windows uses tiny stack sizes which may eventually cause problems in some hosts.
@@ -113,6 +113,12 @@ impl Default for SoftVacuumParams {
         let oversampling_times = Arc::new(AtomicF32::new(oversampling_factor_to_times(
             DEFAULT_OVERSAMPLING_FACTOR,
         ) as f32));
+        // The oversampling factor parameter displays the oversampling amount for each factor
+        let oversampling_amounts: Vec<String> = (0..=MAX_OVERSAMPLING_FACTOR)
+            .map(|factor| oversampling_factor_to_times(factor).to_string())
+            .collect();
+        let oversampling_amounts: Vec<&str> =
+            oversampling_amounts.iter().map(String::as_str).collect();
 
         Self {
             // Goes up to 200%, with the second half being nonlinear
@@ -189,17 +195,8 @@ impl Default for SoftVacuumParams {
                     Ordering::Relaxed,
                 );
             }))
-            .with_value_to_string(Arc::new(|value| {
-                // NIH-plug prevents `value` from being out of range and thus negative
-                let oversampling_times = oversampling_factor_to_times(value as usize);
-
-                oversampling_times.to_string()
-            }))
-            .with_string_to_value(Arc::new(|string| {
-                let oversampling_times: usize = string.parse().ok()?;
-
-                Some(oversampling_times_to_factor(oversampling_times) as i32)
-            })),
+            .with_display_values(&oversampling_amounts)
+            .affects_latency(),
         }
     }
 }
@@ -226,18 +223,7 @@ impl Plugin for SoftVacuum {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(2),
-            main_output_channels: NonZeroU32::new(2),
-            ..AudioIOLayout::const_default()
-        },
-        AudioIOLayout {
-            main_input_channels: NonZeroU32::new(1),
-            main_output_channels: NonZeroU32::new(1),
-            ..AudioIOLayout::const_default()
-        },
-    ];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &AudioIOLayout::main_io_layouts([2, 1]);
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
@@ -262,10 +248,10 @@ impl Plugin for SoftVacuum {
         self.hard_vacuum_processors
             .resize_with(num_channels, hard_vacuum::HardVacuum::default);
         self.oversamplers.resize_with(num_channels, || {
-            oversampling::Lanczos3Oversampler::new(MAX_BLOCK_SIZE, MAX_OVERSAMPLING_FACTOR)
+            Lanczos3Oversampler::new(MAX_BLOCK_SIZE, MAX_OVERSAMPLING_FACTOR)
         });
         self.slew_oversamplers.resize_with(num_channels, || {
-            oversampling::Lanczos3Oversampler::new(MAX_BLOCK_SIZE, MAX_OVERSAMPLING_FACTOR)
+            Lanczos3Oversampler::new(MAX_BLOCK_SIZE, MAX_OVERSAMPLING_FACTOR)
         });
 
         if let Some(oversampler) = self.oversamplers.first() {
@@ -394,16 +380,10 @@ const fn oversampling_factor_to_times(factor: usize) -> usize {
     2usize.pow(factor as u32)
 }
 
-const fn oversampling_times_to_factor(times: usize) -> usize {
-    times.ilog2() as usize
-}
-
 impl ClapPlugin for SoftVacuum {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.soft-vacuum";
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("Airwindows Hard Vacuum port with oversampling");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
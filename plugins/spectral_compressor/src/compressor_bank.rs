@@ -43,6 +43,11 @@ const HIGH_FREQ_RATIO_ROLLOFF_FREQUENCY_LN: f32 = 10.001068; // 22_050.0f32.ln()
 /// configured timingsafter the compressor bank has been reset.
 const ENVELOPE_FOLLOWER_TIMING_FADE_MS: f32 = 150.0;
 
+/// The averaging time for the main input's spectrum in [`ThresholdMode::SelfMatch`]. This needs to
+/// be much longer than the compressor's attack and release times, since otherwise the thresholds
+/// would simply follow the envelopes and nothing would get compressed.
+const SELF_MATCH_AVERAGING_MS: f32 = 3000.0;
+
 /// A bank of compressors so each FFT bin can be compressed individually. The vectors in this struct
 /// will have a capacity of `MAX_WINDOW_SIZE / 2 + 1` and a size that matches the current complex
 /// FFT buffer size. This is stored as a struct of arrays to make SIMD-ing easier in the future.
@@ -99,7 +104,8 @@ pub struct CompressorBank {
     envelope_followers_timing_scale: f32,
     /// When sidechaining is enabled, this contains the per-channel frqeuency spectrum magnitudes
     /// for the current block. The compressor thresholds and knee values are multiplied by these
-    /// values to get the effective thresholds.
+    /// values to get the effective thresholds. In the self matching mode this instead contains a
+    /// long term average of the main input's magnitude spectrum.
     sidechain_spectrum_magnitudes: Vec<Vec<f32>>,
     /// The window size this compressor bank was configured for. This is used to compute the
     /// coefficients for the envelope followers in the process function.
@@ -171,6 +177,13 @@ pub enum ThresholdMode {
     #[id = "sidechain_compress"]
     #[name = "Sidechain Compression"]
     SidechainCompress,
+    /// The same as [`SidechainMatch`][Self::SidechainMatch], but using a long term average of the
+    /// main input's own spectrum instead of a sidechain input. This gradually matches the input to
+    /// its own average spectrum, which acts like an automatic match EQ without needing to route a
+    /// sidechain signal to the plugin.
+    #[id = "self_match"]
+    #[name = "Self Matching"]
+    SelfMatch,
 }
 
 /// Contains the compressor parameters for both the upwards and downwards compressor banks.
@@ -305,9 +318,9 @@ impl ThresholdParams {
             // the curve should be a flat offset to the sidechain input at the default settings.
             slope: match self.mode.value() {
                 ThresholdMode::Internal => self.curve_slope.value() - 3.0,
-                ThresholdMode::SidechainMatch | ThresholdMode::SidechainCompress => {
-                    self.curve_slope.value()
-                }
+                ThresholdMode::SidechainMatch
+                | ThresholdMode::SidechainCompress
+                | ThresholdMode::SelfMatch => self.curve_slope.value(),
             },
             curve: self.curve_curve.value(),
         }
@@ -572,7 +585,9 @@ impl CompressorBank {
         // some huge spikes.
         self.envelope_followers_timing_scale = 0.0;
 
-        // Sidechain data doesn't need to be reset as it will be overwritten immediately before use
+        // Sidechain data doesn't need to be reset as it will be overwritten immediately before use.
+        // The averaged spectra used in the self matching mode use the same timing scale as the
+        // envelope followers, so they will also be overwritten on the next processing cycle.
     }
 
     /// Apply the magnitude compression to a buffer of FFT bins. The compressors are first updated
@@ -597,7 +612,9 @@ impl CompressorBank {
         // size, so all operations on it are limited to the actual number of used bins.
         let num_bins = buffer.len();
         let num_channels = self.sidechain_spectrum_magnitudes.len();
-        let should_update_analyzer_data = params.editor_state.is_open();
+        // While the analyzer is frozen the last published data stays on screen
+        let should_update_analyzer_data =
+            params.editor_state.is_open() && !params.analyzer_frozen.load(Ordering::Relaxed);
         if should_update_analyzer_data && channel_idx == 0 {
             // NOTE: This may briefly show a huge amount of accumulated data when the editor has
             //       just been opened. If this doesn't look too obvious or too jarring this is
@@ -623,6 +640,13 @@ impl CompressorBank {
                 self.update_envelopes_sidechain(channel_idx, params, overlap_times);
                 self.compress(buffer, channel_idx, params, first_non_dc_bin)
             }
+            ThresholdMode::SelfMatch => {
+                // This is the sidechain matching mode with the main input's averaged spectrum used
+                // in place of the sidechain spectrum
+                self.update_self_match_spectra(buffer, channel_idx, overlap_times);
+                self.update_envelopes(buffer, channel_idx, params, overlap_times);
+                self.compress_sidechain_match(buffer, channel_idx, params, first_non_dc_bin)
+            }
         };
 
         // When processing the last channel we can finalize the spectrum analyzer data and send it
@@ -678,7 +702,7 @@ impl CompressorBank {
     pub fn process_sidechain(&mut self, sc_buffer: &[Complex32], channel_idx: usize) {
         nih_debug_assert_eq!(sc_buffer.len(), self.ln_freqs.len());
 
-        self.update_sidechain_spectra(sc_buffer, channel_idx);
+        self.update_sidechain_spectra(sc_buffer, channel_idx, 0.0);
     }
 
     /// Update the envelope followers based on the bin magnitudes.
@@ -814,15 +838,45 @@ impl CompressorBank {
         }
     }
 
-    /// Update the spectral data using the sidechain input
-    fn update_sidechain_spectra(&mut self, sc_buffer: &[Complex32], channel_idx: usize) {
+    /// Update the spectral data using the main input's spectrum for the self matching mode. This
+    /// averages the magnitudes over [`SELF_MATCH_AVERAGING_MS`].
+    fn update_self_match_spectra(
+        &mut self,
+        buffer: &[Complex32],
+        channel_idx: usize,
+        overlap_times: usize,
+    ) {
+        let effective_sample_rate =
+            self.sample_rate / (self.window_size as f32 / overlap_times as f32);
+
+        // This uses the same timing scale as the envelope followers so the average immediately
+        // snaps to the input after a reset
+        let averaging_ms = SELF_MATCH_AVERAGING_MS * self.envelope_followers_timing_scale;
+        let old_t = if averaging_ms == 0.0 {
+            0.0
+        } else {
+            (-1.0 / (averaging_ms / 1000.0 * effective_sample_rate)).exp()
+        };
+
+        self.update_sidechain_spectra(buffer, channel_idx, old_t);
+    }
+
+    /// Update the spectral data using the sidechain input. `old_t` is the coefficient the previous
+    /// magnitudes are multiplied by, which is 0 when the magnitudes should not be averaged.
+    fn update_sidechain_spectra(
+        &mut self,
+        sc_buffer: &[Complex32],
+        channel_idx: usize,
+        old_t: f32,
+    ) {
         nih_debug_assert!(channel_idx < self.sidechain_spectrum_magnitudes.len());
 
+        let new_t = 1.0 - old_t;
         for (bin, magnitude) in sc_buffer
             .iter()
             .zip(self.sidechain_spectrum_magnitudes[channel_idx].iter_mut())
         {
-            *magnitude = bin.norm();
+            *magnitude = (old_t * *magnitude) + (new_t * bin.norm());
         }
     }
 
@@ -15,12 +15,11 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use atomic_float::AtomicF32;
-use crossbeam::atomic::AtomicCell;
 use nih_plug::prelude::*;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
-use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
-use serde::{Deserialize, Serialize};
+use nih_plug_vizia::{assets, create_vizia_editor, SizePreset, ViziaState, ViziaTheming};
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
 use self::analyzer::Analyzer;
@@ -43,25 +42,23 @@ const COLUMN_WIDTH: Units = Pixels(330.0);
 
 const DARKER_GRAY: Color = Color::rgb(0x69, 0x69, 0x69);
 
-/// The editor's mode. Essentially just a boolean to indicate whether the analyzer is shown or
-/// not.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum EditorMode {
-    // These serialization names are hardcoded so the variants can be renamed them later without
-    // breaking preset compatibility
-    #[serde(rename = "collapsed")]
-    Collapsed,
-    #[default]
-    #[serde(rename = "analyzer-visible")]
-    AnalyzerVisible,
-}
+/// The index of the editor's default size preset, which shows the analyzer next to the controls.
+const ANALYZER_VISIBLE_SIZE_PRESET: usize = 0;
+/// The index of the size preset that only shows the controls.
+pub(crate) const COLLAPSED_SIZE_PRESET: usize = 1;
 
 #[derive(Clone, Lens)]
 pub struct Data {
     pub(crate) params: Arc<SpectralCompressorParams>,
 
-    /// Determines which parts of the GUI are visible, and in turn decides the GUI's size.
-    pub(crate) editor_mode: Arc<AtomicCell<EditorMode>>,
+    /// The editor's active size preset determines whether the analyzer is visible.
+    pub(crate) editor_state: Arc<ViziaState>,
+    /// Whether the analyzer is frozen. The audio thread stops sending new analyzer data while this
+    /// is set.
+    pub(crate) analyzer_frozen: Arc<AtomicBool>,
+    /// Whether the analyzer resamples the spectrum onto its logarithmic frequency axis instead of
+    /// drawing the individual FFT bins.
+    pub(crate) analyzer_log_binning: Arc<AtomicBool>,
 
     pub(crate) analyzer_data: Arc<Mutex<triple_buffer::Output<AnalyzerData>>>,
     /// Used by the analyzer to determine which FFT bins belong to which frequencies.
@@ -71,15 +68,16 @@ pub struct Data {
 impl Model for Data {}
 
 // Makes sense to also define this here, makes it a bit easier to keep track of
-pub(crate) fn default_state(editor_mode: Arc<AtomicCell<EditorMode>>) -> Arc<ViziaState> {
-    ViziaState::new(move || match editor_mode.load() {
-        EditorMode::Collapsed => (COLLAPSED_GUI_WIDTH, GUI_HEIGHT),
-        EditorMode::AnalyzerVisible => (EXPANDED_GUI_WIDTH, GUI_HEIGHT),
-    })
+pub(crate) fn default_state() -> Arc<ViziaState> {
+    // The order of these presets needs to match the `*_SIZE_PRESET` constants
+    ViziaState::new_with_size_presets(vec![
+        SizePreset::new("Analyzer visible", EXPANDED_GUI_WIDTH, GUI_HEIGHT),
+        SizePreset::new("Collapsed", COLLAPSED_GUI_WIDTH, GUI_HEIGHT),
+    ])
 }
 
 pub(crate) fn create(editor_state: Arc<ViziaState>, editor_data: Data) -> Option<Box<dyn Editor>> {
-    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
+    create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, context| {
         assets::register_noto_sans_light(cx);
         assets::register_noto_sans_thin(cx);
 
@@ -92,11 +90,11 @@ pub(crate) fn create(editor_state: Arc<ViziaState>, editor_data: Data) -> Option
         HStack::new(cx, |cx| {
             main_column(cx);
 
-            let analyzer_visible = Data::editor_mode
-                .map(|editor_mode| editor_mode.load() == EditorMode::AnalyzerVisible);
-            Binding::new(cx, analyzer_visible, |cx, analyzer_visible| {
+            let analyzer_visible = Data::editor_state
+                .map(|editor_state| editor_state.size_preset() == ANALYZER_VISIBLE_SIZE_PRESET);
+            Binding::new(cx, analyzer_visible, move |cx, analyzer_visible| {
                 if analyzer_visible.get(cx) {
-                    analyzer_column(cx);
+                    analyzer_column(cx, context.clone());
                 }
             });
         });
@@ -108,7 +106,7 @@ pub(crate) fn create(editor_state: Arc<ViziaState>, editor_data: Data) -> Option
 fn main_column(cx: &mut Context) {
     VStack::new(cx, |cx| {
         HStack::new(cx, |cx| {
-            EditorModeButton::new(cx, Data::editor_mode, "Show analyzer")
+            EditorModeButton::new(cx, Data::editor_state, "Show analyzer")
                 // Makes this align a bit nicer with the plugin name
                 .top(Pixels(2.0))
                 .left(Pixels(2.0));
@@ -222,13 +220,32 @@ fn main_column(cx: &mut Context) {
     .child_right(Stretch(1.0));
 }
 
-fn analyzer_column(cx: &mut Context) {
-    Analyzer::new(cx, Data::analyzer_data, Data::sample_rate)
-        // These arbitrary 12 pixels are to align with the analyzer toggle botton
-        .space(Pixels(12.0))
-        .bottom(Pixels(12.0))
-        .left(Pixels(2.0))
-        .top(Pixels(12.0));
+fn analyzer_column(cx: &mut Context, context: Arc<dyn GuiContext>) {
+    ZStack::new(cx, |cx| {
+        Analyzer::new(
+            cx,
+            Data::analyzer_data,
+            Data::sample_rate,
+            Data::analyzer_log_binning,
+            context,
+        );
+
+        HStack::new(cx, |cx| {
+            ToggleButton::new(cx, Data::analyzer_log_binning, "Log binning");
+            FreezeButton::new(cx, Data::analyzer_frozen, "Freeze");
+        })
+        .size(Auto)
+        .col_between(Pixels(10.0))
+        .top(Pixels(10.0))
+        .right(Pixels(10.0))
+        .left(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    })
+    // These arbitrary 12 pixels are to align with the analyzer toggle botton
+    .space(Pixels(12.0))
+    .bottom(Pixels(12.0))
+    .left(Pixels(2.0))
+    .top(Pixels(12.0));
 }
 
 fn make_column(cx: &mut Context, title: &str, contents: impl FnOnce(&mut Context)) {
@@ -16,9 +16,10 @@
 
 use atomic_float::AtomicF32;
 use nih_plug::nih_debug_assert;
+use nih_plug::prelude::GuiContext;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::vizia::vg;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::analyzer::AnalyzerData;
@@ -33,6 +34,10 @@ const LN_FREQ_RANGE_START_HZ: f32 = 3.4011974; // 30.0f32.ln();
 const LN_FREQ_RANGE_END_HZ: f32 = 9.998797; // 22_000.0f32.ln();
 const LN_FREQ_RANGE: f32 = LN_FREQ_RANGE_END_HZ - LN_FREQ_RANGE_START_HZ;
 
+/// The number of points the spectrum is resampled to when logarithmic binning is enabled. These
+/// points are spaced evenly on the analyzer's logarithmic frequency axis.
+const LOG_BINNING_NUM_POINTS: usize = 512;
+
 /// The color used for drawing the overlay. Currently not configurable using the style sheet (that
 /// would be possible by moving this to a dedicated view and overlaying that).
 ///
@@ -54,22 +59,33 @@ const UPWARDS_THRESHOLD_CURVE_COLOR: vg::Color = vg::Color::rgbaf(0.55, 0.70, 0.
 pub struct Analyzer {
     analyzer_data: Arc<Mutex<triple_buffer::Output<AnalyzerData>>>,
     sample_rate: Arc<AtomicF32>,
+    /// When set, the spectrum is resampled onto the logarithmic frequency axis instead of drawing
+    /// the individual FFT bins. See [`draw_spectrum_log_binned()`].
+    log_binning: Arc<AtomicBool>,
+    /// Used to check whether the plugin has been initialized yet. Until then the sample rate is
+    /// not known, so the analyzer can't map the bins to frequencies.
+    gui_context: Arc<dyn GuiContext>,
 }
 
 impl Analyzer {
     /// Creates a new [`Analyzer`].
-    pub fn new<LAnalyzerData, LRate>(
+    pub fn new<LAnalyzerData, LRate, LLogBinning>(
         cx: &mut Context,
         analyzer_data: LAnalyzerData,
         sample_rate: LRate,
+        log_binning: LLogBinning,
+        gui_context: Arc<dyn GuiContext>,
     ) -> Handle<Self>
     where
         LAnalyzerData: Lens<Target = Arc<Mutex<triple_buffer::Output<AnalyzerData>>>>,
         LRate: Lens<Target = Arc<AtomicF32>>,
+        LLogBinning: Lens<Target = Arc<AtomicBool>>,
     {
         Self {
             analyzer_data: analyzer_data.get(cx),
             sample_rate: sample_rate.get(cx),
+            log_binning: log_binning.get(cx),
+            gui_context,
         }
         .build(
             cx,
@@ -77,6 +93,23 @@ impl Analyzer {
             |_cx| (),
         )
     }
+
+    /// Draw the spectrum, the threshold curve, and the gain reduction.
+    fn draw_analyzer(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        // The analyzer data is pulled directly from the spectral `CompressorBank`
+        let mut analyzer_data = self.analyzer_data.lock().unwrap();
+        let analyzer_data = analyzer_data.read();
+        let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
+
+        if self.log_binning.load(Ordering::Relaxed) {
+            draw_spectrum_log_binned(cx, canvas, analyzer_data, nyquist);
+        } else {
+            draw_spectrum(cx, canvas, analyzer_data, nyquist);
+        }
+        draw_threshold_curve(cx, canvas, analyzer_data);
+        draw_gain_reduction(cx, canvas, analyzer_data, nyquist);
+        // TODO: Display the frequency range below the graph
+    }
 }
 
 impl View for Analyzer {
@@ -90,15 +123,12 @@ impl View for Analyzer {
             return;
         }
 
-        // The analyzer data is pulled directly from the spectral `CompressorBank`
-        let mut analyzer_data = self.analyzer_data.lock().unwrap();
-        let analyzer_data = analyzer_data.read();
-        let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
-
-        draw_spectrum(cx, canvas, analyzer_data, nyquist);
-        draw_threshold_curve(cx, canvas, analyzer_data);
-        draw_gain_reduction(cx, canvas, analyzer_data, nyquist);
-        // TODO: Display the frequency range below the graph
+        // Some hosts open the editor before initializing the plugin. The spectrum and gain
+        // reduction would be drawn at the wrong frequencies until then, so only the border is
+        // drawn.
+        if self.gui_context.is_plugin_initialized() {
+            self.draw_analyzer(cx, canvas);
+        }
 
         // Draw the border last
         let border_width = cx.border_width();
@@ -144,25 +174,12 @@ fn draw_spectrum(
     let text_color: vg::Color = cx.font_color().into();
     // This is used to draw the individual bars
     let bars_paint = vg::Paint::color(text_color).with_line_width(line_width);
-    // And this color is used to draw the mesh part of the spectrum. We'll create a gradient paint
-    // that fades from this to `text_color` when we know the mesh's x-coordinates.
-    let mut lighter_text_color = text_color;
-    lighter_text_color.r = (lighter_text_color.r + 0.25) / 1.25;
-    lighter_text_color.g = (lighter_text_color.g + 0.25) / 1.25;
-    lighter_text_color.b = (lighter_text_color.b + 0.25) / 1.25;
 
     // The frequency belonging to a bin in Hz
     let bin_frequency = |bin_idx: f32| (bin_idx / analyzer_data.num_bins as f32) * nyquist_hz;
     // A `[0, 1]` value indicating at which relative x-coordinate a bin should be drawn at
     let bin_t =
         |bin_idx: f32| (bin_frequency(bin_idx).ln() - LN_FREQ_RANGE_START_HZ) / LN_FREQ_RANGE;
-    // Converts a linear magnitude value in to a `[0, 1]` value where 0 is -80 dB or lower, and 1 is
-    // +20 dB or higher.
-    let magnitude_height = |magnitude: f32| {
-        nih_debug_assert!(magnitude >= 0.0);
-        let magnitude_db = nih_plug::util::gain_to_db(magnitude);
-        db_to_unclamped_t(magnitude_db).clamp(0.0, 1.0)
-    };
 
     // The first part of this drawing routing is simple. Individual bins are drawn as bars until the
     // distance between the bars approaches `mesh_start_delta_threshold`. After that the rest is
@@ -240,10 +257,111 @@ fn draw_spectrum(
     mesh_path.line_to(previous_physical_x_coord, mesh_start_y_coordinate);
     mesh_path.close();
 
-    let mesh_paint = vg::Paint::linear_gradient_stops(
+    let mesh_paint = mesh_paint(
+        text_color,
         mesh_start_x_coordiante,
-        0.0,
         previous_physical_x_coord,
+    );
+    canvas.fill_path(&mesh_path, &mesh_paint);
+}
+
+/// An alternative to [`draw_spectrum()`] that resamples the spectrum to
+/// [`LOG_BINNING_NUM_POINTS`] points spaced evenly on the logarithmic frequency axis, and then
+/// draws those points as a single solid mesh. Points that cover multiple bins use the loudest of
+/// those bins, and points that fall between two bins interpolate between them. This makes the low
+/// frequencies, where the FFT bins are spaced far apart on the logarithmic axis, easier to read.
+fn draw_spectrum_log_binned(
+    cx: &mut DrawContext,
+    canvas: &mut Canvas,
+    analyzer_data: &AnalyzerData,
+    nyquist_hz: f32,
+) {
+    let bounds = cx.bounds();
+    let num_bins = analyzer_data.num_bins;
+    if num_bins < 2 {
+        return;
+    }
+
+    let line_width = cx.scale_factor() * 1.5;
+    let text_color: vg::Color = cx.font_color().into();
+
+    // The inverse of `bin_frequency` in `draw_spectrum()`. This is a fractional bin index.
+    let t_bin = |t: f32| {
+        let frequency = (LN_FREQ_RANGE_START_HZ + (LN_FREQ_RANGE * t)).exp();
+        (frequency / nyquist_hz) * num_bins as f32
+    };
+    let envelope_followers = &analyzer_data.envelope_followers[..num_bins];
+    let point_magnitude = |point_idx: usize| {
+        // Every point covers the frequency range halfway to its neighbours
+        let point_t = |idx: f32| idx / (LOG_BINNING_NUM_POINTS - 1) as f32;
+        let start_bin = t_bin(point_t(point_idx as f32 - 0.5)).max(0.0);
+        let end_bin = t_bin(point_t(point_idx as f32 + 0.5)).min((num_bins - 1) as f32);
+
+        let first_bin_idx = start_bin.ceil() as usize;
+        let last_bin_idx = end_bin.floor() as usize;
+        if first_bin_idx <= last_bin_idx {
+            envelope_followers[first_bin_idx..=last_bin_idx]
+                .iter()
+                .copied()
+                .fold(0.0f32, f32::max)
+        } else {
+            let bin = t_bin(point_t(point_idx as f32)).clamp(0.0, (num_bins - 1) as f32);
+            let lower_bin_idx = (bin.floor() as usize).min(num_bins - 2);
+            let bin_t = bin - lower_bin_idx as f32;
+
+            (envelope_followers[lower_bin_idx] * (1.0 - bin_t))
+                + (envelope_followers[lower_bin_idx + 1] * bin_t)
+        }
+    };
+
+    let mesh_start_x_coordinate = bounds.x;
+    let mesh_end_x_coordinate = bounds.x + bounds.w;
+    let mesh_start_y_coordinate = bounds.y + bounds.h;
+
+    let mut mesh_path = vg::Path::new();
+    mesh_path.move_to(mesh_start_x_coordinate, mesh_start_y_coordinate);
+    for point_idx in 0..LOG_BINNING_NUM_POINTS {
+        let t = point_idx as f32 / (LOG_BINNING_NUM_POINTS - 1) as f32;
+        let physical_x_coord = bounds.x + (bounds.w * t);
+        let height = magnitude_height(point_magnitude(point_idx));
+        if height > 0.0 {
+            mesh_path.line_to(
+                physical_x_coord,
+                // This includes the line width, since this path is not stroked
+                bounds.y + (bounds.h * (1.0 - height) - (line_width / 2.0)).max(0.0),
+            );
+        } else {
+            mesh_path.line_to(physical_x_coord, mesh_start_y_coordinate);
+        }
+    }
+    mesh_path.line_to(mesh_end_x_coordinate, mesh_start_y_coordinate);
+    mesh_path.close();
+
+    let mesh_paint = mesh_paint(text_color, mesh_start_x_coordinate, mesh_end_x_coordinate);
+    canvas.fill_path(&mesh_path, &mesh_paint);
+}
+
+/// Converts a linear magnitude value in to a `[0, 1]` value where 0 is -80 dB or lower, and 1 is
+/// +20 dB or higher.
+#[inline]
+fn magnitude_height(magnitude: f32) -> f32 {
+    nih_debug_assert!(magnitude >= 0.0);
+    let magnitude_db = nih_plug::util::gain_to_db(magnitude);
+    db_to_unclamped_t(magnitude_db).clamp(0.0, 1.0)
+}
+
+/// The paint used to fill the mesh part of the spectrum. This fades from a slightly lighter version
+/// of `text_color` at the left of the mesh to `text_color` on the right.
+fn mesh_paint(text_color: vg::Color, start_x_coordinate: f32, end_x_coordinate: f32) -> vg::Paint {
+    let mut lighter_text_color = text_color;
+    lighter_text_color.r = (lighter_text_color.r + 0.25) / 1.25;
+    lighter_text_color.g = (lighter_text_color.g + 0.25) / 1.25;
+    lighter_text_color.b = (lighter_text_color.b + 0.25) / 1.25;
+
+    vg::Paint::linear_gradient_stops(
+        start_x_coordinate,
+        0.0,
+        end_x_coordinate,
         0.0,
         [
             (0.0, lighter_text_color),
@@ -252,8 +370,7 @@ fn draw_spectrum(
         ],
     )
     // NOTE:  This is very important, otherwise this looks all kinds of gnarly
-    .with_anti_alias(false);
-    canvas.fill_path(&mesh_path, &mesh_paint);
+    .with_anti_alias(false)
 }
 
 /// Overlays the threshold curve over the spectrum analyzer. If either the upwards or downwards
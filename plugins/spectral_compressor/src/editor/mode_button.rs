@@ -16,32 +16,37 @@
 
 use std::sync::Arc;
 
-use crossbeam::atomic::AtomicCell;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::GuiContextEvent;
+use nih_plug_vizia::ViziaState;
 
-use super::EditorMode;
+use super::{ANALYZER_VISIBLE_SIZE_PRESET, COLLAPSED_SIZE_PRESET};
 
-/// A custom toggleable button that allows changing between the collapsed and expanded editor modes.
+/// A custom toggleable button that allows changing between the collapsed and expanded editor
+/// layouts. These layouts are the editor's size presets.
 pub struct EditorModeButton {
-    mode: Arc<AtomicCell<EditorMode>>,
+    editor_state: Arc<ViziaState>,
 }
 
 impl EditorModeButton {
-    /// Creates a new button bound to the editor mode setting.
+    /// Creates a new button bound to the editor's state.
     pub fn new<L, T>(cx: &mut Context, lens: L, label: impl Res<T> + Clone) -> Handle<Self>
     where
-        L: Lens<Target = Arc<AtomicCell<EditorMode>>>,
+        L: Lens<Target = Arc<ViziaState>>,
         T: ToString,
     {
-        Self { mode: lens.get(cx) }
-            .build(cx, |cx| {
-                Label::new(cx, label).hoverable(false);
-            })
-            .checked(lens.map(|v| v.load() == EditorMode::AnalyzerVisible))
-            // We'll pretend this is a param-button, so this class is used for assigning a unique
-            // color
-            .class("editor-mode")
+        Self {
+            editor_state: lens.get(cx),
+        }
+        .build(cx, |cx| {
+            Label::new(cx, label).hoverable(false);
+        })
+        .checked(
+            lens.map(|editor_state| editor_state.size_preset() == ANALYZER_VISIBLE_SIZE_PRESET),
+        )
+        // We'll pretend this is a param-button, so this class is used for assigning a unique
+        // color
+        .class("editor-mode")
     }
 }
 
@@ -56,16 +61,16 @@ impl View for EditorModeButton {
             WindowEvent::MouseDown(MouseButton::Left)
             | WindowEvent::MouseDoubleClick(MouseButton::Left)
             | WindowEvent::MouseTripleClick(MouseButton::Left) => {
-                let current_mode = self.mode.load();
-                let new_mode = match current_mode {
-                    EditorMode::Collapsed => EditorMode::AnalyzerVisible,
-                    EditorMode::AnalyzerVisible => EditorMode::Collapsed,
-                };
-                self.mode.store(new_mode);
+                let new_size_preset =
+                    if self.editor_state.size_preset() == ANALYZER_VISIBLE_SIZE_PRESET {
+                        COLLAPSED_SIZE_PRESET
+                    } else {
+                        ANALYZER_VISIBLE_SIZE_PRESET
+                    };
 
-                // This uses the function stored in our `ViziaState` to declaratively resize the GUI
-                // to the correct size
-                cx.emit(GuiContextEvent::Resize);
+                // This switches the layout and negotiates the new size with the host. If the host
+                // rejects the new size, then the old layout is restored.
+                cx.emit(GuiContextEvent::SetSizePreset(new_size_preset));
 
                 meta.consume();
             }
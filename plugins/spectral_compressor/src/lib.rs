@@ -16,13 +16,11 @@
 
 use analyzer::AnalyzerData;
 use atomic_float::AtomicF32;
-use crossbeam::atomic::AtomicCell;
-use editor::EditorMode;
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 use realfft::num_complex::Complex32;
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use triple_buffer::TripleBuffer;
 
@@ -71,6 +69,10 @@ pub struct SpectralCompressor {
     /// Spectral per-bin upwards and downwards compressors with soft-knee settings. This is where
     /// the magic happens.
     compressor_bank: compressor_bank::CompressorBank,
+    /// The number of samples the host has guaranteed the sidechain input to be silent for, up to
+    /// the start of the current buffer. Once this covers an entire window, the sidechain FFT can
+    /// be skipped while the sidechain input stays silent.
+    sidechain_silent_samples: usize,
 
     /// The algorithms for the FFT and IFFT operations, for each supported order so we can switch
     /// between them without replanning or allocations. Initialized during `initialize()`.
@@ -93,14 +95,19 @@ struct Plan {
 
 #[derive(Params)]
 pub struct SpectralCompressorParams {
-    /// The editor state, saved together with the parameter state so the custom scaling can be
-    /// restored.
+    /// The editor state, saved together with the parameter state so the custom scaling and the
+    /// editor's layout can be restored.
     #[persist = "editor-state"]
+    #[view_state]
     pub editor_state: Arc<ViziaState>,
-    /// The mode the editor is currently in. Essentially just a fancy boolean to indicate whether
-    /// it's expanded or not.
-    #[persist = "editor-mode"]
-    pub editor_mode: Arc<AtomicCell<EditorMode>>,
+    /// Whether the analyzer resamples the spectrum onto its logarithmic frequency axis instead of
+    /// drawing the individual FFT bins. This only affects the editor.
+    #[persist = "analyzer-log-binning"]
+    #[view_state]
+    pub analyzer_log_binning: Arc<AtomicBool>,
+    /// Whether the analyzer is frozen. No new analyzer data is sent to the editor while this is
+    /// set. This is not persisted.
+    pub analyzer_frozen: Arc<AtomicBool>,
 
     // NOTE: These `Arc`s are only here temporarily to work around Vizia's Lens requirements so we
     // can use the generic UIs
@@ -179,6 +186,7 @@ impl Default for SpectralCompressor {
             window_function: Vec::with_capacity(MAX_WINDOW_SIZE),
             dry_wet_mixer: dry_wet_mixer::DryWetMixer::new(0, 0, 0),
             compressor_bank,
+            sidechain_silent_samples: 0,
 
             // This is initialized later since we don't want to do non-trivial computations before
             // the plugin is initialized
@@ -223,7 +231,8 @@ impl Default for GlobalParams {
                 },
             )
             .with_value_to_string(formatters::v2s_i32_power_of_two())
-            .with_string_to_value(formatters::s2v_i32_power_of_two()),
+            .with_string_to_value(formatters::s2v_i32_power_of_two())
+            .affects_latency(),
             overlap_times_order: IntParam::new(
                 "Window Overlap",
                 DEFAULT_OVERLAP_ORDER as i32,
@@ -265,11 +274,10 @@ impl SpectralCompressorParams {
     /// Create a new [`SpectralCompressorParams`] object. Changing any of the compressor threshold
     /// or ratio parameters causes the passed compressor bank's parameters to be updated.
     pub fn new(compressor_bank: &compressor_bank::CompressorBank) -> Self {
-        let editor_mode: Arc<AtomicCell<EditorMode>> = Arc::default();
-
         SpectralCompressorParams {
-            editor_state: editor::default_state(editor_mode.clone()),
-            editor_mode,
+            editor_state: editor::default_state(),
+            analyzer_log_binning: Arc::new(AtomicBool::new(false)),
+            analyzer_frozen: Arc::new(AtomicBool::new(false)),
 
             // TODO: Do still enable per-block smoothing for these settings, because why not. This
             //       will require updating the compressor bank.
@@ -323,7 +331,9 @@ impl Plugin for SpectralCompressor {
             editor::Data {
                 params: self.params.clone(),
 
-                editor_mode: self.params.editor_mode.clone(),
+                editor_state: self.params.editor_state.clone(),
+                analyzer_frozen: self.params.analyzer_frozen.clone(),
+                analyzer_log_binning: self.params.analyzer_log_binning.clone(),
 
                 analyzer_data: self.analyzer_output_data.clone(),
                 sample_rate: self.sample_rate.clone(),
@@ -331,6 +341,26 @@ impl Plugin for SpectralCompressor {
         )
     }
 
+    fn filter_state(state: &mut PluginState) {
+        // Older versions stored whether the analyzer was hidden in a separate `editor-mode` field.
+        // This is now the editor state's size preset.
+        if state.fields.remove("editor-mode").as_deref() != Some("\"collapsed\"") {
+            return;
+        }
+
+        if let Some(editor_state) = state.fields.get_mut("editor-state") {
+            if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(editor_state) {
+                if let Some(object) = json.as_object_mut() {
+                    object.insert(
+                        String::from("size_preset"),
+                        editor::COLLAPSED_SIZE_PRESET.into(),
+                    );
+                    *editor_state = json.to_string();
+                }
+            }
+        }
+    }
+
     fn initialize(
         &mut self,
         audio_io_layout: &AudioIOLayout,
@@ -371,11 +401,13 @@ impl Plugin for SpectralCompressor {
                     c2r_plan: planner.plan_fft_inverse(1 << order),
                 })
                 .collect();
-            self.plan_for_order = Some(
-                plan_for_order
-                    .try_into()
-                    .unwrap_or_else(|_| panic!("Mismatched plan orders")),
-            );
+            match plan_for_order.try_into() {
+                Ok(plan_for_order) => self.plan_for_order = Some(plan_for_order),
+                Err(_) => {
+                    nih_error!("Mismatched FFT plan orders, failing initialization");
+                    return false;
+                }
+            }
         }
 
         let window_size = self.window_size();
@@ -388,6 +420,7 @@ impl Plugin for SpectralCompressor {
     fn reset(&mut self) {
         self.dry_wet_mixer.reset();
         self.compressor_bank.reset();
+        self.sidechain_silent_samples = 0;
     }
 
     fn process(
@@ -413,15 +446,20 @@ impl Plugin for SpectralCompressor {
         // The Hann window function spreads the DC signal out slightly, so we'll clear all 0-20 Hz
         // bins for this. With small window sizes you probably don't want this as it would result in
         // a significant low-pass filter. When it's disabled, the DC bin will also be compressed.
+        // The sample rate is only a placeholder until the plugin has been initialized, so this is
+        // clamped to make sure a misbehaving host can't cause out of bounds bin indices.
+        nih_debug_assert!(self.buffer_config.sample_rate > 1.0);
         let first_non_dc_bin_idx =
-            (20.0 / ((self.buffer_config.sample_rate / 2.0) / num_bins as f32)).floor() as usize
-                + 1;
+            ((20.0 / ((self.buffer_config.sample_rate / 2.0) / num_bins as f32)).floor() as usize
+                + 1)
+            .min(num_bins);
 
-        // The overlap gain compensation is based on a squared Hann window, which will sum perfectly
-        // at four times overlap or higher. We'll apply a regular Hann window before the analysis
-        // and after the synthesis.
+        // We'll apply a regular Hann window before the analysis and after the synthesis. The
+        // overlap gain compensation accounts for this squared window, and the extra division
+        // compensates for the gain introduced by the IDFT operation.
         let gain_compensation: f32 =
-            ((overlap_times as f32 / 4.0) * 1.5).recip() / window_size as f32;
+            util::window::overlap_add_gain(&self.window_function, overlap_times)
+                / window_size as f32;
 
         // We'll apply the square root of the total gain compensation at the DFT and the IDFT
         // stages. That way the compressor threshold values make much more sense. This version of
@@ -436,7 +474,9 @@ impl Plugin for SpectralCompressor {
         self.dry_wet_mixer.write_dry(buffer);
 
         match self.params.threshold.mode.value() {
-            compressor_bank::ThresholdMode::Internal => self.stft.process_overlap_add(
+            // The self matching mode analyzes the main input, so it doesn't need the sidechain STFT
+            compressor_bank::ThresholdMode::Internal
+            | compressor_bank::ThresholdMode::SelfMatch => self.stft.process_overlap_add(
                 buffer,
                 overlap_times,
                 |channel_idx, real_fft_buffer| {
@@ -457,12 +497,29 @@ impl Plugin for SpectralCompressor {
             ),
             compressor_bank::ThresholdMode::SidechainMatch
             | compressor_bank::ThresholdMode::SidechainCompress => {
+                // If the sidechain input was already silent for an entire window before this
+                // buffer, then every sidechain window analyzed in this buffer only contains silence
+                // and its spectrum will be all zeroes
+                let sidechain_silent = aux.inputs[0].is_silent();
+                let skip_sidechain_fft =
+                    sidechain_silent && self.sidechain_silent_samples >= window_size;
+                self.sidechain_silent_samples = if sidechain_silent {
+                    self.sidechain_silent_samples
+                        .saturating_add(buffer.samples())
+                } else {
+                    0
+                };
+
                 self.stft.process_overlap_add_sidechain(
                     buffer,
                     [&aux.inputs[0]],
                     overlap_times,
                     |channel_idx, sidechain_buffer_idx, real_fft_buffer| {
-                        if sidechain_buffer_idx.is_some() {
+                        if sidechain_buffer_idx.is_some() && skip_sidechain_fft {
+                            self.complex_fft_buffer.fill(Complex32::default());
+                            self.compressor_bank
+                                .process_sidechain(&self.complex_fft_buffer, channel_idx);
+                        } else if sidechain_buffer_idx.is_some() {
                             process_stft_sidechain(
                                 channel_idx,
                                 real_fft_buffer,
@@ -523,6 +580,7 @@ impl SpectralCompressor {
         // `self.plan_for_order`, and all of these data structures already have enough capacity, so
         // we just need to change some sizes.
         self.stft.set_block_size(window_size);
+        self.sidechain_silent_samples = 0;
         self.window_function.resize(window_size, 0.0);
         util::window::hann_in_place(&mut self.window_function);
         self.complex_fft_buffer
@@ -622,8 +680,6 @@ fn process_stft_sidechain(
 impl ClapPlugin for SpectralCompressor {
     const CLAP_ID: &'static str = "nl.robbertvanderhelm.spectral-compressor";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("Turn things into pink noise on demand");
-    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
@@ -118,6 +118,37 @@ impl AudioIOLayout {
         }
     }
 
+    /// A layout with `num_channels` main input and output channels, and no auxiliary ports. This is
+    /// by far the most common layout for effect plugins. Can be used in a `const` context.
+    pub const fn main_io(num_channels: u32) -> Self {
+        Self {
+            main_input_channels: NonZeroU32::new(num_channels),
+            main_output_channels: NonZeroU32::new(num_channels),
+            ..Self::const_default()
+        }
+    }
+
+    /// Create a [`main_io()`][Self::main_io()] layout for each of the channel counts, in order.
+    /// Since the first layout is used as the default layout, the most common channel count should
+    /// come first. This can be used directly to define `Plugin::AUDIO_IO_LAYOUTS`:
+    ///
+    /// ```
+    /// # use nih_plug::prelude::*;
+    /// // A stereo layout that also supports mono
+    /// const AUDIO_IO_LAYOUTS: &[AudioIOLayout] = &AudioIOLayout::main_io_layouts([2, 1]);
+    /// # assert_eq!(AUDIO_IO_LAYOUTS[1].main_output_channels, NonZeroU32::new(1));
+    /// ```
+    pub const fn main_io_layouts<const N: usize>(channel_counts: [u32; N]) -> [Self; N] {
+        let mut layouts = [Self::const_default(); N];
+        let mut idx = 0;
+        while idx < N {
+            layouts[idx] = Self::main_io(channel_counts[idx]);
+            idx += 1;
+        }
+
+        layouts
+    }
+
     /// A descriptive name for the layout. This is taken from `PortNames::layout` if set. Otherwise
     /// it is generated based on the layout.
     pub fn name(&self) -> String {
@@ -2,11 +2,18 @@
 
 use std::marker::PhantomData;
 
+#[cfg(feature = "simd")]
+use std::simd::{LaneCount, SupportedLaneCount};
+
 mod blocks;
 mod samples;
+#[cfg(feature = "simd")]
+mod simd_blocks;
 
 pub use blocks::{Block, BlockChannelsIter, BlocksIter};
 pub use samples::{ChannelSamples, ChannelSamplesIter, SamplesIter};
+#[cfg(feature = "simd")]
+pub use simd_blocks::{SimdBlock, SimdBlocksIter};
 
 /// The audio buffers used during processing. This contains the output audio output buffers with the
 /// inputs already copied to the outputs. You can either use the iterator adapters to conveniently
@@ -29,6 +36,9 @@ pub struct Buffer<'a> {
     /// buffers, and it also cannot be stored in a field next to it because that would mean
     /// containing mutable references to data stored in a mutex.
     output_slices: Vec<&'a mut [f32]>,
+    /// Whether the host has guaranteed that this buffer only contains silence. See
+    /// [`is_silent()`][Self::is_silent()].
+    silent: bool,
 }
 
 impl<'a> Buffer<'a> {
@@ -50,6 +60,18 @@ impl<'a> Buffer<'a> {
         self.num_samples == 0
     }
 
+    /// Returns true if this buffer is guaranteed to only contain silence. This is only ever set for
+    /// auxiliary input buffers, when the host marks the sidechain input as silent through CLAP's
+    /// constant mask or VST3's silence flags, or when the host did not provide any data for the
+    /// input at all. Plugins can use this to skip analyzing a silent sidechain input.
+    ///
+    /// This is conservative. A buffer that only contains zeroes will still return false if the host
+    /// didn't say so.
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
     /// Obtain the raw audio buffers.
     #[inline]
     pub fn as_slice(&mut self) -> &mut [&'a mut [f32]] {
@@ -108,6 +130,63 @@ impl<'a> Buffer<'a> {
         }
     }
 
+    /// Iterate over the buffer in blocks of at most `BLOCK_SIZE` samples, like
+    /// [`iter_blocks()`][Self::iter_blocks()], but with every sample's channels packed into a
+    /// single `Simd<f32, LANES>` vector. The lanes map to the channels, not to the samples, so
+    /// with a stereo buffer and `LANES = 2` the yielded blocks dereference to a slice with one
+    /// `f32x2` per frame containing that frame's left and right samples. This makes it possible to
+    /// process all channels at once without having to pack and unpack the vectors by hand.
+    ///
+    /// Since the channels are stored separately, the samples are copied into the yielded blocks,
+    /// and they are written back to the buffer when a block is dropped. The blocks are stored on
+    /// the stack, so this does not allocate.
+    ///
+    /// Returns `None` if the buffer's number of channels doesn't match `LANES`. In that case
+    /// [`iter_blocks()`][Self::iter_blocks()] or [`iter_samples()`][Self::iter_samples()] can be
+    /// used to fall back to scalar processing:
+    ///
+    /// ```ignore
+    /// match buffer.iter_simd_blocks::<2, 64>() {
+    ///     Some(blocks) => {
+    ///         for (_, mut block) in blocks {
+    ///             for frame in block.iter_mut() {
+    ///                 *frame *= f32x2::splat(0.5);
+    ///             }
+    ///         }
+    ///     }
+    ///     None => {
+    ///         for channel_samples in buffer.iter_samples() {
+    ///             for sample in channel_samples {
+    ///                 *sample *= 0.5;
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `BLOCK_SIZE` is 0.
+    #[cfg(feature = "simd")]
+    #[inline]
+    pub fn iter_simd_blocks<'slice, const LANES: usize, const BLOCK_SIZE: usize>(
+        &'slice mut self,
+    ) -> Option<SimdBlocksIter<'slice, 'a, LANES, BLOCK_SIZE>>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+    {
+        assert!(BLOCK_SIZE > 0);
+        if self.channels() != LANES {
+            return None;
+        }
+
+        Some(SimdBlocksIter {
+            buffers: self.output_slices.as_mut_slice(),
+            current_block_start: 0,
+            _marker: PhantomData,
+        })
+    }
+
     /// Set the slices in the raw output slice vector. This vector needs to be resized to match the
     /// number of output channels during the plugin's initialization. Then during audio processing,
     /// these slices should be updated to point to the plugin's audio buffers. The `num_samples`
@@ -131,6 +210,13 @@ impl<'a> Buffer<'a> {
             nih_debug_assert_eq!(slice.len(), num_samples);
         }
     }
+
+    /// Mark this buffer as only containing silence, or clear that mark. See
+    /// [`is_silent()`][Self::is_silent()]. Wrappers should only set this when the host guarantees
+    /// that the buffer is silent.
+    pub(crate) fn set_silent(&mut self, silent: bool) {
+        self.silent = silent;
+    }
 }
 
 #[cfg(any(miri, test))]
@@ -198,4 +284,43 @@ mod miri {
             assert_eq!(real_buffers[0][i], 0.0);
         }
     }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn simd_blocks() {
+        use std::simd::f32x2;
+
+        let mut real_buffers = vec![
+            (0..100).map(|i| i as f32).collect::<Vec<_>>(),
+            (0..100).map(|i| -(i as f32)).collect::<Vec<_>>(),
+        ];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(100, |output_slices| {
+                let (first_channel, other_channels) = real_buffers.split_at_mut(1);
+                *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+            })
+        };
+
+        assert!(buffer.iter_simd_blocks::<4, 32>().is_none());
+
+        // The lanes should contain the channels, so there's one vector per stereo frame
+        let blocks = buffer.iter_simd_blocks::<2, 32>().unwrap();
+        assert_eq!(blocks.len(), 4);
+        for (block_start, mut block) in blocks {
+            assert_eq!(block.len(), (100 - block_start).min(32));
+            for (sample_idx, frame) in block.iter_mut().enumerate() {
+                let sample = (block_start + sample_idx) as f32;
+                assert_eq!(*frame, f32x2::from_array([sample, -sample]));
+
+                *frame *= f32x2::from_array([2.0, 3.0]);
+            }
+        }
+
+        // The blocks should have been written back to the buffer when they were dropped
+        for i in 0..100 {
+            assert_eq!(real_buffers[0][i], i as f32 * 2.0);
+            assert_eq!(real_buffers[1][i], i as f32 * -3.0);
+        }
+    }
 }
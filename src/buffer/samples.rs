@@ -137,6 +137,31 @@ impl<'slice, 'sample> ChannelSamples<'slice, 'sample> {
         }
     }
 
+    /// Get a sample's value by index. Useful when you only need to read the channel data.
+    #[inline]
+    pub fn get(&self, channel_index: usize) -> Option<f32> {
+        // SAFETY: The sample bound has already been checked
+        unsafe {
+            Some(
+                *(*self.buffers)
+                    .get(channel_index)?
+                    .get_unchecked(self.current_sample),
+            )
+        }
+    }
+
+    /// The same as [`get()`][Self::get()], but without any bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `channel_index` must be in the range `0..Self::len()`.
+    #[inline]
+    pub unsafe fn get_unchecked(&self, channel_index: usize) -> f32 {
+        *(*self.buffers)
+            .get_unchecked(channel_index)
+            .get_unchecked(self.current_sample)
+    }
+
     /// Access a sample by index. Useful when you would otherwise iterate over this 'Channels'
     /// iterator multiple times.
     #[inline]
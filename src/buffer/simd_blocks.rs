@@ -0,0 +1,141 @@
+//! Per-block iterators that pack each sample's channels into a SIMD vector.
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::simd::{LaneCount, Simd, SupportedLaneCount};
+
+/// An iterator over all samples in the buffer, slicing over the sample-dimension in blocks of at
+/// most `BLOCK_SIZE` samples. Every sample's channels are packed into a single SIMD vector. See
+/// [`Buffer::iter_simd_blocks()`][super::Buffer::iter_simd_blocks()]. Yields both the block and
+/// the offset from the start of the buffer.
+pub struct SimdBlocksIter<'slice, 'sample: 'slice, const LANES: usize, const BLOCK_SIZE: usize>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    /// The raw output buffers. These contain exactly `LANES` channels.
+    pub(super) buffers: *mut [&'sample mut [f32]],
+    pub(super) current_block_start: usize,
+    pub(super) _marker: PhantomData<&'slice mut [&'sample mut [f32]]>,
+}
+
+/// A block yielded by [`SimdBlocksIter`]. This dereferences to a slice containing one
+/// `Simd<f32, LANES>` vector per sample in the block, where lane `n` contains channel `n`'s sample.
+/// So for a stereo buffer, every `f32x2` contains the left and right channel's samples for a
+/// single frame.
+///
+/// Since the buffer's channels are stored separately, the samples are copied to this block when
+/// it's created, and they are written back to the buffer when the block is dropped.
+pub struct SimdBlock<'slice, 'sample: 'slice, const LANES: usize, const BLOCK_SIZE: usize>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    /// The raw output buffers. These contain exactly `LANES` channels.
+    buffers: *mut [&'sample mut [f32]],
+    current_block_start: usize,
+    /// The index of the last sample in the block plus one.
+    current_block_end: usize,
+    /// The block's samples, with one vector per sample. Only the first `current_block_end -
+    /// current_block_start` vectors are used.
+    frames: [Simd<f32, LANES>; BLOCK_SIZE],
+    _marker: PhantomData<&'slice mut [&'sample mut [f32]]>,
+}
+
+impl<'slice, 'sample, const LANES: usize, const BLOCK_SIZE: usize> Iterator
+    for SimdBlocksIter<'slice, 'sample, LANES, BLOCK_SIZE>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    type Item = (usize, SimdBlock<'slice, 'sample, LANES, BLOCK_SIZE>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer_len = unsafe { (*self.buffers).first().map(|b| b.len()).unwrap_or(0) };
+        if self.current_block_start < buffer_len {
+            let current_block_start = self.current_block_start;
+            let current_block_end = (self.current_block_start + BLOCK_SIZE).min(buffer_len);
+
+            // The channels become the vectors' lanes
+            let mut frames = [Simd::splat(0.0); BLOCK_SIZE];
+            // SAFETY: The blocks yielded by this iterator never overlap
+            for (channel_idx, channel) in unsafe { (*self.buffers).iter() }.enumerate() {
+                for (frame, sample) in frames
+                    .iter_mut()
+                    .zip(&channel[current_block_start..current_block_end])
+                {
+                    frame[channel_idx] = *sample;
+                }
+            }
+
+            let block = SimdBlock {
+                buffers: self.buffers,
+                current_block_start,
+                current_block_end,
+                frames,
+                _marker: self._marker,
+            };
+
+            self.current_block_start += BLOCK_SIZE;
+
+            Some((current_block_start, block))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffer_len = unsafe { (*self.buffers).first().map(|b| b.len()).unwrap_or(0) };
+        let remaining = buffer_len
+            .saturating_sub(self.current_block_start)
+            .div_ceil(BLOCK_SIZE);
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<const LANES: usize, const BLOCK_SIZE: usize> ExactSizeIterator
+    for SimdBlocksIter<'_, '_, LANES, BLOCK_SIZE>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+}
+
+impl<const LANES: usize, const BLOCK_SIZE: usize> Deref for SimdBlock<'_, '_, LANES, BLOCK_SIZE>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    type Target = [Simd<f32, LANES>];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.frames[..self.current_block_end - self.current_block_start]
+    }
+}
+
+impl<const LANES: usize, const BLOCK_SIZE: usize> DerefMut for SimdBlock<'_, '_, LANES, BLOCK_SIZE>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.frames[..self.current_block_end - self.current_block_start]
+    }
+}
+
+impl<const LANES: usize, const BLOCK_SIZE: usize> Drop for SimdBlock<'_, '_, LANES, BLOCK_SIZE>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    fn drop(&mut self) {
+        // SAFETY: It is not possible to have multiple mutable references to the same sample at the
+        //         same time since the blocks yielded by `SimdBlocksIter` never overlap
+        for (channel_idx, channel) in unsafe { (*self.buffers).iter_mut() }.enumerate() {
+            for (sample, frame) in channel[self.current_block_start..self.current_block_end]
+                .iter_mut()
+                .zip(&self.frames)
+            {
+                *sample = frame[channel_idx];
+            }
+        }
+    }
+}
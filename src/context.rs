@@ -1,5 +1,6 @@
 //! Different contexts the plugin can use to make callbacks to the host in different...contexts.
 
+use std::ffi::c_void;
 use std::fmt::Display;
 
 pub mod gui;
@@ -27,3 +28,30 @@ impl Display for PluginApi {
         }
     }
 }
+
+/// The raw host object the plugin was instantiated with, as returned by the contexts' `raw_host()`
+/// functions. This is an escape hatch for using host features NIH-plug doesn't wrap, like querying
+/// a CLAP extension or a host-specific API that is not part of the plugin standard. Prefer
+/// NIH-plug's own abstractions where they exist, since NIH-plug is not aware of anything done
+/// through these pointers.
+///
+/// # Safety
+///
+/// These pointers are only valid while the plugin instance is alive, and the plugin must respect
+/// the plugin API's threading rules when calling into them. Calling functions through these
+/// pointers that NIH-plug also uses, like the CLAP host's `request_restart()` or the VST3
+/// component handler's `restartComponent()`, may cause NIH-plug's internal state to no longer
+/// match the host's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawHost {
+    /// A `*const clap_host` pointing to the host's `clap_host` struct. Extensions can be queried
+    /// using its `get_extension()` function.
+    Clap(*const c_void),
+    /// The `FUnknown*` host context the plugin was initialized with, or a null pointer if the host
+    /// did not pass one. Use `queryInterface()` to obtain the interface you need. The pointer is
+    /// not reference counted by NIH-plug beyond the plugin's lifetime, so `addRef()` it if you hold
+    /// on to an obtained interface.
+    Vst3(*mut c_void),
+    /// The standalone wrapper does not have a host.
+    Standalone,
+}
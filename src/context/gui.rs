@@ -1,9 +1,12 @@
 //! A context passed to a plugin's editor.
 
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
-use super::PluginApi;
-use crate::prelude::{Param, ParamPtr, Plugin, PluginState};
+use super::{PluginApi, RawHost};
+use crate::prelude::{Param, ParamPtr, Plugin, PluginState, Transport};
 
 /// Callbacks the plugin can make when the user interacts with its GUI such as updating parameter
 /// values. This is passed to the plugin during [`Editor::spawn()`][crate::prelude::Editor::spawn()]. All of
@@ -18,6 +21,33 @@ pub trait GuiContext: Send + Sync + 'static {
     /// about screen.
     fn plugin_api(&self) -> PluginApi;
 
+    /// Get the raw host object this plugin instance was created with. See [`RawHost`] for more
+    /// information.
+    ///
+    /// # Safety
+    ///
+    /// This is an escape hatch for advanced host interop. The returned pointers may only be used
+    /// according to the plugin API's rules, and only for as long as the plugin instance exists.
+    /// See [`RawHost`]'s safety section for the caveats.
+    unsafe fn raw_host(&self) -> RawHost;
+
+    /// Whether [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] has succeeded at
+    /// least once. Some hosts open the editor before the plugin has been initialized, so any data
+    /// the editor reads that only gets set up during initialization, like the current sample rate,
+    /// may not contain meaningful values yet while this returns `false`. Visualizations that
+    /// depend on that data should check this before drawing. This never changes back to `false`
+    /// once it has been set.
+    fn is_plugin_initialized(&self) -> bool;
+
+    /// Get a snapshot of the transport information at the start of the most recently processed
+    /// block, for instance to display the host's tempo, the current bar and beat position, or
+    /// whether the transport is playing in the editor. Since the editor runs on a different thread,
+    /// this may be a couple milliseconds out of date. Returns `None` if the plugin has not
+    /// processed any audio yet, or if the context does not keep track of the transport.
+    fn last_transport(&self) -> Option<Transport> {
+        None
+    }
+
     /// Ask the host to resize the editor window to the size specified by
     /// [`Editor::size()`][crate::prelude::Editor::size()]. This will return false if the host
     /// somehow didn't like this and rejected the resize, in which case the window should revert to
@@ -26,6 +56,14 @@ pub trait GuiContext: Send + Sync + 'static {
     /// TODO: Host->Plugin resizing has not been implemented yet
     fn request_resize(&self) -> bool;
 
+    /// Resize the editor to a new size in logical pixels and ask the host to resize the editor's
+    /// window to match. This calls [`Editor::set_size()`][crate::prelude::Editor::set_size()] on
+    /// the editor, so the new size is also reflected in
+    /// [`Editor::size()`][crate::prelude::Editor::size()] and in any persisted
+    /// editor state. Returns `false` if the editor doesn't support this or if the host rejected
+    /// the new size, in which case the editor's old size will have been restored.
+    fn request_resize_to(&self, width: u32, height: u32) -> bool;
+
     /// Inform the host a parameter will be automated. Create a [`ParamSetter`] and use
     /// [`ParamSetter::begin_set_parameter()`] instead for a safe, user friendly API.
     ///
@@ -63,6 +101,64 @@ pub trait GuiContext: Send + Sync + 'static {
     /// host. If the plugin is currently processing audio, then the parameter values will be
     /// restored at the end of the current processing cycle.
     fn set_state(&self, state: PluginState);
+
+    /// Get a state object containing the default values for all of the plugin's parameters.
+    /// Persistent fields don't have default values, so they are not included. Loading this state
+    /// with [`set_state()`][Self::set_state()] resets all parameters while leaving the persistent
+    /// fields untouched.
+    fn get_default_state(&self) -> PluginState;
+
+    /// Get only the editor and configuration part of the plugin's current state. These are the
+    /// parameters with the [`ParamFlags::VIEW_STATE`][crate::prelude::ParamFlags::VIEW_STATE]
+    /// flag and the persistent fields annotated with `#[view_state]`. The state can be restored
+    /// later using [`set_view_state()`][Self::set_view_state()] without affecting the plugin's
+    /// sound.
+    fn get_view_state(&self) -> PluginState {
+        self.get_state().view_state_only()
+    }
+
+    /// Restore only the editor and configuration part of a state object. The state may be a full
+    /// state object, like the ones returned by [`get_state()`][Self::get_state()], in which case
+    /// everything that affects the plugin's sound is ignored.
+    fn set_view_state(&self, state: PluginState) {
+        self.set_state(state.view_state_only());
+    }
+
+    /// Restore only the part of a state object that affects the plugin's sound. The editor and
+    /// configuration state stays untouched, so the editor's layout is kept when loading a preset.
+    fn set_sound_state(&self, state: PluginState) {
+        self.set_state(state.without_view_state());
+    }
+
+    /// Reset all parameters that affect the plugin's sound to their default values while keeping
+    /// the editor and configuration state. Persistent fields are left untouched, see
+    /// [`get_default_state()`][Self::get_default_state()].
+    fn reset_sound_state(&self) {
+        self.set_sound_state(self.get_default_state());
+    }
+
+    /// Write the plugin's current state to a JSON file. This uses the same format the plugin's
+    /// state is persisted in by the host (without compression), so it can be used to implement
+    /// "save preset" functionality in the plugin's GUI without having to come up with a custom
+    /// preset format.
+    fn export_state(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.get_state())?;
+
+        fs::write(path, json)
+    }
+
+    /// Load a state previously written by [`export_state()`][Self::export_state()] and restore
+    /// it using [`set_state()`][Self::set_state()]. The state is passed through
+    /// [`Plugin::filter_state()`] before it is loaded, so migrations also apply to preset files
+    /// saved by older versions of the plugin.
+    fn import_state(&self, path: &Path) -> io::Result<()> {
+        let json = fs::read(path)?;
+        let state: PluginState = serde_json::from_slice(&json)?;
+
+        self.set_state(state);
+
+        Ok(())
+    }
 }
 
 /// An way to run background tasks from the plugin's GUI, equivalent to the
@@ -129,6 +225,12 @@ impl<P: Plugin> AsyncExecutor<P> {
 }
 
 impl<'a> ParamSetter<'a> {
+    /// The maximum number of parameters
+    /// [`set_parameters_normalized()`][Self::set_parameters_normalized()] can change at once. Every
+    /// parameter change results in three events being sent to the host, and the wrappers can only
+    /// queue a limited number of events before the host picks them up.
+    pub const MAX_BATCH_SIZE: usize = 512;
+
     pub fn new(context: &'a dyn GuiContext) -> Self {
         Self {
             raw_context: context,
@@ -174,6 +276,45 @@ impl<'a> ParamSetter<'a> {
         };
     }
 
+    /// Set multiple parameters to already normalized values at once, for instance when loading a
+    /// preset from the plugin's own preset browser. This begins a gesture for every parameter, then
+    /// sets all of them, and finally ends all of the gestures again, so the host sees the changes
+    /// as a single overlapping edit instead of a sequence of unrelated ones. Unlike the other
+    /// functions on this object, this should not be wrapped in calls to
+    /// [`begin_set_parameter()`][Self::begin_set_parameter()] and
+    /// [`end_set_parameter()`][Self::end_set_parameter()].
+    ///
+    /// Use [`Param::as_ptr()`] to get the `ParamPtr`s for the parameters. Every parameter should
+    /// only be included once. Like with
+    /// [`set_parameter_normalized()`][Self::set_parameter_normalized()], the normalized values are
+    /// not snapped.
+    ///
+    /// At most [`MAX_BATCH_SIZE`][Self::MAX_BATCH_SIZE] parameters can be changed at once. Any
+    /// additional parameters trigger a debug assertion failure and are ignored. Use
+    /// [`GuiContext::set_state()`] to change more parameters at the same time.
+    pub fn set_parameters_normalized(&self, values: &[(ParamPtr, f32)]) {
+        nih_debug_assert!(
+            values.len() <= Self::MAX_BATCH_SIZE,
+            "Only the first {} of {} parameters will be changed",
+            Self::MAX_BATCH_SIZE,
+            values.len()
+        );
+        let values = &values[..values.len().min(Self::MAX_BATCH_SIZE)];
+
+        for (ptr, _) in values {
+            unsafe { self.raw_context.raw_begin_set_parameter(*ptr) };
+        }
+        for (ptr, normalized) in values {
+            unsafe {
+                self.raw_context
+                    .raw_set_parameter_normalized(*ptr, *normalized)
+            };
+        }
+        for (ptr, _) in values {
+            unsafe { self.raw_context.raw_end_set_parameter(*ptr) };
+        }
+    }
+
     /// Inform the host that you are done automating a parameter. This needs to be called after one
     /// or more [`set_parameter()`][Self::set_parameter()] calls for a parameter so the host knows
     /// the automation gesture has finished.
@@ -181,3 +322,118 @@ impl<'a> ParamSetter<'a> {
         unsafe { self.raw_context.raw_end_set_parameter(param.as_ptr()) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::prelude::{FloatParam, FloatRange};
+
+    /// The events a [`RecordingContext`] received, in order.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Event {
+        Begin(ParamPtr),
+        Set(ParamPtr, f32),
+        End(ParamPtr),
+    }
+
+    /// A `GuiContext` that only records the parameter events it receives.
+    #[derive(Default)]
+    struct RecordingContext {
+        events: Mutex<Vec<Event>>,
+    }
+
+    impl GuiContext for RecordingContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Standalone
+        }
+
+        unsafe fn raw_host(&self) -> RawHost {
+            RawHost::Standalone
+        }
+
+        fn is_plugin_initialized(&self) -> bool {
+            true
+        }
+
+        fn request_resize(&self) -> bool {
+            false
+        }
+
+        fn request_resize_to(&self, _width: u32, _height: u32) -> bool {
+            false
+        }
+
+        unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+            self.events.lock().push(Event::Begin(param));
+        }
+
+        unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+            self.events.lock().push(Event::Set(param, normalized));
+        }
+
+        unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+            self.events.lock().push(Event::End(param));
+        }
+
+        fn get_state(&self) -> PluginState {
+            empty_state()
+        }
+
+        fn set_state(&self, _state: PluginState) {}
+
+        fn get_default_state(&self) -> PluginState {
+            empty_state()
+        }
+    }
+
+    /// A state without any parameters or persistent fields.
+    fn empty_state() -> PluginState {
+        PluginState {
+            version: String::new(),
+            params: Default::default(),
+            fields: Default::default(),
+            view_state_params: Default::default(),
+            view_state_fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_set_parameters_normalized() {
+        let params: Vec<FloatParam> = (0..3)
+            .map(|_| FloatParam::new("Param", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 }))
+            .collect();
+        let values: Vec<(ParamPtr, f32)> = params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| (param.as_ptr(), idx as f32 / 2.0))
+            .collect();
+
+        let context = RecordingContext::default();
+        ParamSetter::new(&context).set_parameters_normalized(&values);
+
+        // All gestures are started before the first value is set, and they're only ended after the
+        // last value has been set
+        let events = context.events.into_inner();
+        let mut expected_events: Vec<Event> =
+            values.iter().map(|(p, _)| Event::Begin(*p)).collect();
+        expected_events.extend(values.iter().map(|(p, v)| Event::Set(*p, *v)));
+        expected_events.extend(values.iter().map(|(p, _)| Event::End(*p)));
+        assert_eq!(events, expected_events);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn test_set_parameters_normalized_bounded() {
+        let param = FloatParam::new("Param", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let values = vec![(param.as_ptr(), 1.0); ParamSetter::MAX_BATCH_SIZE + 1];
+
+        let context = RecordingContext::default();
+        ParamSetter::new(&context).set_parameters_normalized(&values);
+        assert_eq!(
+            context.events.into_inner().len(),
+            ParamSetter::MAX_BATCH_SIZE * 3
+        );
+    }
+}
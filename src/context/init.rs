@@ -1,6 +1,6 @@
 //! A context passed during plugin initialization.
 
-use super::PluginApi;
+use super::{PluginApi, RawHost};
 use crate::prelude::Plugin;
 
 /// Callbacks the plugin can make while it is being initialized. This is passed to the plugin during
@@ -14,6 +14,16 @@ pub trait InitContext<P: Plugin> {
     /// Get the current plugin API.
     fn plugin_api(&self) -> PluginApi;
 
+    /// Get the raw host object this plugin instance was created with. See [`RawHost`] for more
+    /// information.
+    ///
+    /// # Safety
+    ///
+    /// This is an escape hatch for advanced host interop. The returned pointers may only be used
+    /// according to the plugin API's rules, and only for as long as the plugin instance exists.
+    /// See [`RawHost`]'s safety section for the caveats.
+    unsafe fn raw_host(&self) -> RawHost;
+
     /// Run a task directly on this thread. This ensures that the task has finished executing before
     /// the plugin finishes initializing.
     ///
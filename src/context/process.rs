@@ -1,7 +1,7 @@
 //! A context passed during the process function.
 
-use super::PluginApi;
-use crate::prelude::{Plugin, PluginNoteEvent};
+use super::{PluginApi, RawHost};
+use crate::prelude::{Param, ParamFlags, ParamPtr, Plugin, PluginNoteEvent};
 
 /// Contains both context data and callbacks the plugin can use during processing. Most notably this
 /// is how a plugin sends and receives note events, gets transport information, and accesses
@@ -16,6 +16,16 @@ pub trait ProcessContext<P: Plugin> {
     /// Get the current plugin API.
     fn plugin_api(&self) -> PluginApi;
 
+    /// Get the raw host object this plugin instance was created with. See [`RawHost`] for more
+    /// information.
+    ///
+    /// # Safety
+    ///
+    /// This is an escape hatch for advanced host interop. The returned pointers may only be used
+    /// according to the plugin API's rules, and only for as long as the plugin instance exists.
+    /// See [`RawHost`]'s safety section for the caveats.
+    unsafe fn raw_host(&self) -> RawHost;
+
     /// Execute a task on a background thread using `[Plugin::task_executor]`. This allows you to
     /// defer expensive tasks for later without blocking either the process function or the GUI
     /// thread. As long as creating the `task` is realtime-safe, this operation is too.
@@ -75,13 +85,21 @@ pub trait ProcessContext<P: Plugin> {
     /// ```
     fn next_event(&mut self) -> Option<PluginNoteEvent<P>>;
 
+    /// The number of events that can still be returned by [`next_event()`][Self::next_event()]
+    /// during this process call. This does not consume any events, so it can be used to size a
+    /// scratch buffer or to take a fast path when there are no events.
+    fn remaining_events(&self) -> usize;
+
     /// Send an event to the host. Only available when
     /// [`Plugin::MIDI_OUTPUT`][crate::prelude::Plugin::MIDI_INPUT] is set. Will not do anything
     /// otherwise.
     fn send_event(&mut self, event: PluginNoteEvent<P>);
 
     /// Update the current latency of the plugin. If the plugin is currently processing audio, then
-    /// this may cause audio playback to be restarted.
+    /// this may cause audio playback to be restarted. The host is only notified when the latency
+    /// differs from the value it last queried, so calling this with an unchanged value is cheap.
+    /// See [`Plugin::DEFER_LATENCY_CHANGES`][crate::prelude::Plugin::DEFER_LATENCY_CHANGES] for a
+    /// way to avoid restarts while the host is processing audio.
     fn set_latency_samples(&self, samples: u32);
 
     /// Set the current voice **capacity** for this plugin (so not the number of currently active
@@ -92,21 +110,98 @@ pub trait ProcessContext<P: Plugin> {
     /// monophonic modulation when dropping the capacity down to 1.
     fn set_current_voice_capacity(&self, capacity: u32);
 
-    // TODO: Add this, this works similar to [GuiContext::set_parameter] but it adds the parameter
-    //       change to a queue (or directly to the VST3 plugin's parameter output queues) instead of
-    //       using main thread host automation (and all the locks involved there).
-    // fn set_parameter<P: Param>(&self, param: &P, value: P::Plain);
+    /// Inform the host that the names returned by
+    /// [`Plugin::aux_output_port_names()`][crate::prelude::Plugin::aux_output_port_names()] have
+    /// changed. The host will query the port names again at a later point. This is only supported
+    /// by CLAP and VST3 hosts, the standalone wrapper only queries the port names once at startup.
+    fn port_names_changed(&self);
+
+    /// Whether the plugin is currently receiving sample-accurate automation. This is only `true`
+    /// when
+    /// [`Plugin::SAMPLE_ACCURATE_AUTOMATION`][crate::prelude::Plugin::SAMPLE_ACCURATE_AUTOMATION]
+    /// is enabled and the host has sent at least one parameter change in the middle of a buffer
+    /// since processing was last started, in which case the buffer gets split up into smaller
+    /// blocks. Some hosts only send parameter changes at the start of the buffer. Plugins can use
+    /// this to decide between updating expensive coefficients for every sample or only once per
+    /// block.
+    fn sample_accurate_automation_active(&self) -> bool;
+
+    /// Set the value of a parameter that has been marked as read-only using the parameter's
+    /// `.read_only()` builder method, and send the new value to the host. This can be used to
+    /// show meters like a compressor's gain reduction in the host's UI, even when the plugin's own
+    /// editor is closed. The parameter's value is changed at the end of the current process call.
+    ///
+    /// Since this is sent to the host for every call, you should only update these values once per
+    /// block. Keep these meters cheap, and only do expensive GUI-only visualizations like spectrum
+    /// analyzers while the editor is open.
+    fn set_read_only_parameter<Pa: Param>(&mut self, param: &Pa, value: Pa::Plain)
+    where
+        Self: Sized,
+    {
+        nih_debug_assert!(
+            param.flags().contains(ParamFlags::READ_ONLY),
+            "set_read_only_parameter() called on a parameter that's not read-only"
+        );
+
+        let normalized = param.preview_normalized(value);
+        unsafe { self.raw_set_read_only_parameter_normalized(param.as_ptr(), normalized) };
+    }
+
+    /// The type-erased version of
+    /// [`set_read_only_parameter()`][Self::set_read_only_parameter()]. Use that function instead.
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_set_read_only_parameter_normalized(&mut self, param: ParamPtr, normalized: f32);
+
+    /// Change the value of a regular parameter from the audio thread, and send the new value to
+    /// the host wrapped in an automation gesture so the host records it as if the user had changed
+    /// the parameter. The parameter's value is changed at the end of the current process call.
+    ///
+    /// This is meant for correcting parameter values that depend on other parameters, like keeping
+    /// a set of crossover frequencies in ascending order. Only call this when the value actually
+    /// needs to change, as otherwise the plugin will end up fighting the host's automation. Use
+    /// [`set_read_only_parameter()`][Self::set_read_only_parameter()] for read-only parameters.
+    fn set_parameter<Pa: Param>(&mut self, param: &Pa, value: Pa::Plain)
+    where
+        Self: Sized,
+    {
+        nih_debug_assert!(
+            !param.flags().contains(ParamFlags::READ_ONLY),
+            "set_parameter() called on a read-only parameter, use set_read_only_parameter() instead"
+        );
+
+        let normalized = param.preview_normalized(value);
+        unsafe { self.raw_set_parameter_normalized(param.as_ptr(), normalized) };
+    }
+
+    /// The type-erased version of [`set_parameter()`][Self::set_parameter()]. Use that function
+    /// instead.
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_set_parameter_normalized(&mut self, param: ParamPtr, normalized: f32);
 }
 
 /// Information about the plugin's transport. Depending on the plugin API and the host not all
 /// fields may be available.
-#[derive(Debug)]
+///
+/// This information describes the transport at the start of the current block. Use
+/// [`at_sample_offset()`][Self::at_sample_offset()] to get the transport at a later point within
+/// the block.
+#[derive(Debug, Clone, Copy)]
 pub struct Transport {
     /// Whether the transport is currently running.
     pub playing: bool,
-    /// Whether recording is enabled in the project.
+    /// Whether recording is enabled in the project. This can be combined with `playing` to only
+    /// capture audio while the host is actually recording.
     pub recording: bool,
-    /// Whether the pre-roll is currently active, if the plugin API reports this information.
+    /// Whether the pre-roll is currently active, if the plugin API reports this information. Only
+    /// CLAP reports this. VST3 does not have an equivalent flag.
     pub preroll_active: Option<bool>,
 
     /// The sample rate in Hertz. Also passed in
@@ -115,6 +210,10 @@ pub struct Transport {
     pub sample_rate: f32,
     /// The project's tempo in beats per minute.
     pub tempo: Option<f64>,
+    /// The change in tempo in beats per minute for every sample in this block, if the host is
+    /// ramping the tempo. Only CLAP reports this. This is used by
+    /// [`at_sample_offset()`][Self::at_sample_offset()].
+    pub tempo_increment: Option<f64>,
     /// The time signature's numerator.
     pub time_sig_numerator: Option<i32>,
     /// The time signature's denominator.
@@ -162,6 +261,7 @@ impl Transport {
 
             sample_rate,
             tempo: None,
+            tempo_increment: None,
             time_sig_numerator: None,
             time_sig_denominator: None,
 
@@ -177,6 +277,138 @@ impl Transport {
         }
     }
 
+    /// Get the transport information at `sample_offset` samples into the current block. The host
+    /// only provides this information at the start of a block, so with large blocks the positions
+    /// would otherwise be stale by the end of the block. If the transport is playing, then this
+    /// advances the song position by `sample_offset` samples while following any tempo ramp from
+    /// [`tempo_increment`][Self::tempo_increment], and it wraps the position around the active
+    /// loop range. The position is left unchanged when the transport is stopped.
+    ///
+    /// This is computed from the information at the start of the block, so it won't reflect
+    /// changes the host didn't announce in advance, like jumps to another position in the song.
+    pub fn at_sample_offset(&self, sample_offset: u32) -> Transport {
+        let mut transport = *self;
+        if !self.playing || sample_offset == 0 {
+            return transport;
+        }
+
+        let loop_range_samples = self.loop_range_samples();
+        transport.pos_samples = self.pos_samples().map(|pos_samples| {
+            wrap_loop(
+                pos_samples,
+                pos_samples + sample_offset as i64,
+                loop_range_samples,
+            )
+        });
+
+        let loop_range_seconds = self.loop_range_seconds();
+        let elapsed_seconds = sample_offset as f64 / self.sample_rate as f64;
+        transport.pos_seconds = self.pos_seconds().map(|pos_seconds| {
+            wrap_loop(
+                pos_seconds,
+                pos_seconds + elapsed_seconds,
+                loop_range_seconds,
+            )
+        });
+
+        // Without a tempo the position in beats cannot be advanced
+        let loop_range_beats = self.loop_range_beats();
+        transport.pos_beats = match (self.pos_beats(), self.tempo) {
+            (Some(pos_beats), Some(tempo)) => {
+                let elapsed_beats = beats_elapsed(
+                    tempo,
+                    self.tempo_increment.unwrap_or(0.0),
+                    self.sample_rate,
+                    sample_offset,
+                );
+
+                Some(wrap_loop(
+                    pos_beats,
+                    pos_beats + elapsed_beats,
+                    loop_range_beats,
+                ))
+            }
+            _ => None,
+        };
+        transport.tempo = self
+            .tempo
+            .map(|tempo| tempo + (self.tempo_increment.unwrap_or(0.0) * sample_offset as f64));
+
+        // The bar information is advanced by whole bars so it stays consistent with the host's bar
+        // numbering. If the position wrapped around to the start of the loop then it's recomputed
+        // from the time signature instead.
+        transport.bar_start_pos_beats = None;
+        transport.bar_number = None;
+        if let (
+            Some(pos_beats),
+            Some(bar_start_pos_beats),
+            Some(bar_number),
+            Some(time_sig_numerator),
+            Some(time_sig_denominator),
+        ) = (
+            transport.pos_beats,
+            self.bar_start_pos_beats(),
+            self.bar_number(),
+            self.time_sig_numerator,
+            self.time_sig_denominator,
+        ) {
+            let quarter_note_bar_length =
+                time_sig_numerator as f64 / time_sig_denominator as f64 * 4.0;
+            if pos_beats >= bar_start_pos_beats {
+                let elapsed_bars =
+                    ((pos_beats - bar_start_pos_beats) / quarter_note_bar_length).floor();
+                transport.bar_start_pos_beats =
+                    Some(bar_start_pos_beats + (elapsed_bars * quarter_note_bar_length));
+                transport.bar_number = Some(bar_number + elapsed_bars as i32);
+            }
+        }
+
+        transport
+    }
+
+    /// Convert a duration in quarter notes to a duration in samples at the current tempo. Returns
+    /// `None` if the host did not provide a tempo. Any tempo ramp from
+    /// [`tempo_increment`][Self::tempo_increment] is not taken into account.
+    ///
+    /// For instance, to sync a delay's time to a dotted eighth note:
+    ///
+    /// ```ignore
+    /// // A dotted eighth note lasts for three quarters of a quarter note
+    /// let delay_samples = context
+    ///     .transport()
+    ///     .beats_to_samples(0.75)
+    ///     .unwrap_or(DEFAULT_DELAY_SAMPLES);
+    /// ```
+    pub fn beats_to_samples(&self, beats: f64) -> Option<f64> {
+        self.tempo
+            .map(|tempo| beats * 60.0 * self.sample_rate as f64 / tempo)
+    }
+
+    /// Convert a duration in samples to a duration in quarter notes at the current tempo. This is
+    /// the inverse of [`beats_to_samples()`][Self::beats_to_samples()]. Returns `None` if the host
+    /// did not provide a tempo.
+    pub fn samples_to_beats(&self, samples: f64) -> Option<f64> {
+        self.tempo
+            .map(|tempo| samples * tempo / 60.0 / self.sample_rate as f64)
+    }
+
+    /// The number of samples from the start of the block until the next grid point for a grid
+    /// with `division` quarter notes between grid points. For instance, `0.25` gives the offset
+    /// to the next sixteenth note and `4.0` gives the offset to the next whole note. This returns
+    /// 0 if the current position is exactly on the grid. The grid is aligned to the start of the
+    /// song, not to the start of the current bar. Returns `None` if the tempo or the position in
+    /// the song is not known, or if `division` is not positive.
+    pub fn samples_until_next_beat_division(&self, division: f64) -> Option<f64> {
+        if division <= 0.0 {
+            return None;
+        }
+
+        let pos_beats = self.pos_beats()?;
+        let next_grid_pos_beats = (pos_beats / division).ceil() * division;
+
+        self.beats_to_samples(next_grid_pos_beats - pos_beats)
+    }
+
     /// The position in the song in samples. Will be calculated from other information if needed.
     pub fn pos_samples(&self) -> Option<i64> {
         match (
@@ -337,3 +569,137 @@ impl Transport {
         }
     }
 }
+
+/// The number of quarter notes that pass during `num_samples` samples, starting at `tempo` beats
+/// per minute and changing the tempo by `tempo_increment` every sample.
+pub(crate) fn beats_elapsed(
+    tempo: f64,
+    tempo_increment: f64,
+    sample_rate: f32,
+    num_samples: u32,
+) -> f64 {
+    // This is the sum of the tempo at every sample, so it exactly matches a host that updates its
+    // tempo once per sample
+    let num_samples = num_samples as f64;
+    let summed_tempo =
+        (num_samples * tempo) + (tempo_increment * num_samples * (num_samples - 1.0) / 2.0);
+
+    summed_tempo / 60.0 / sample_rate as f64
+}
+
+/// Wrap `new_pos` back to the start of the loop if advancing the transport from `old_pos` to
+/// `new_pos` would cross the end of the loop. Positions that weren't inside of the loop to begin
+/// with are not wrapped.
+fn wrap_loop<T>(old_pos: T, mut new_pos: T, loop_range: Option<(T, T)>) -> T
+where
+    T: Copy + PartialOrd + std::ops::Sub<Output = T>,
+{
+    if let Some((loop_start, loop_end)) = loop_range {
+        let loop_length = loop_end - loop_start;
+        if old_pos < loop_end && loop_end > loop_start {
+            while new_pos >= loop_end {
+                new_pos = new_pos - loop_length;
+            }
+        }
+    }
+
+    new_pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing_transport() -> Transport {
+        let mut transport = Transport::new(48_000.0);
+        transport.playing = true;
+        transport.tempo = Some(120.0);
+        transport.time_sig_numerator = Some(4);
+        transport.time_sig_denominator = Some(4);
+        transport.pos_samples = Some(0);
+
+        transport
+    }
+
+    #[test]
+    fn test_at_sample_offset() {
+        let transport = playing_transport();
+
+        // At 120 BPM a bar of 4/4 takes two seconds
+        let later = transport.at_sample_offset(48_000);
+        assert_eq!(later.pos_samples(), Some(48_000));
+        assert_eq!(later.pos_seconds(), Some(1.0));
+        assert_eq!(later.pos_beats(), Some(2.0));
+        assert_eq!(later.bar_number(), Some(0));
+
+        let later = transport.at_sample_offset(96_000);
+        assert_eq!(later.bar_start_pos_beats(), Some(4.0));
+        assert_eq!(later.bar_number(), Some(1));
+    }
+
+    #[test]
+    fn test_at_sample_offset_stopped() {
+        let mut transport = playing_transport();
+        transport.playing = false;
+
+        assert_eq!(transport.at_sample_offset(48_000).pos_samples(), Some(0));
+    }
+
+    #[test]
+    fn test_at_sample_offset_tempo_ramp() {
+        let mut transport = playing_transport();
+        transport.tempo_increment = Some(0.001);
+
+        let later = transport.at_sample_offset(48_000);
+        assert_eq!(later.tempo, Some(168.0));
+        // The tempo goes from 120 to (almost) 168 BPM, so this is slightly less than 2.4 beats
+        let pos_beats = later.pos_beats().unwrap();
+        assert!(pos_beats > 2.39 && pos_beats < 2.4, "{pos_beats}");
+    }
+
+    #[test]
+    fn test_beats_to_samples() {
+        let mut transport = playing_transport();
+
+        // At 120 BPM a quarter note lasts half a second
+        assert_eq!(transport.beats_to_samples(0.75), Some(18_000.0));
+        assert_eq!(transport.samples_to_beats(18_000.0), Some(0.75));
+
+        transport.tempo = None;
+        assert_eq!(transport.beats_to_samples(0.75), None);
+        assert_eq!(transport.samples_to_beats(18_000.0), None);
+    }
+
+    #[test]
+    fn test_samples_until_next_beat_division() {
+        let mut transport = playing_transport();
+        assert_eq!(transport.samples_until_next_beat_division(0.25), Some(0.0));
+
+        // A sixteenth note lasts 6000 samples at 120 BPM
+        transport.pos_samples = Some(1_000);
+        approx::assert_relative_eq!(
+            transport.samples_until_next_beat_division(0.25).unwrap(),
+            5_000.0,
+            epsilon = 1e-6
+        );
+        approx::assert_relative_eq!(
+            transport.samples_until_next_beat_division(1.0).unwrap(),
+            23_000.0,
+            epsilon = 1e-6
+        );
+        assert_eq!(transport.samples_until_next_beat_division(0.0), None);
+
+        transport.tempo = None;
+        assert_eq!(transport.samples_until_next_beat_division(0.25), None);
+    }
+
+    #[test]
+    fn test_at_sample_offset_loop() {
+        let mut transport = playing_transport();
+        transport.pos_samples = Some(90_000);
+        transport.loop_range_samples = Some((48_000, 96_000));
+
+        let later = transport.at_sample_offset(12_000);
+        assert_eq!(later.pos_samples(), Some(54_000));
+    }
+}
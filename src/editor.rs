@@ -54,6 +54,17 @@ pub trait Editor: Send {
     /// there.
     fn set_scale_factor(&self, factor: f32) -> bool;
 
+    /// Change the editor's size as reported by [`size()`][Self::size()] to a new size in logical
+    /// pixels. This is called by [`GuiContext::request_resize_to()`] before asking the host to
+    /// resize the window, and it may be called again with the old size if the host rejects the new
+    /// size. Return `false` if the editor does not support this, for instance because its size is
+    /// computed from the plugin's state. Those editors should use
+    /// [`GuiContext::request_resize()`] instead.
+    #[allow(unused_variables)]
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        false
+    }
+
     /// Called whenever a specific parameter's value has changed while the editor is open. You don't
     /// need to do anything with this, but this can be used to force a redraw when the host sends a
     /// new value for a parameter or when a parameter change sent to the host gets processed.
@@ -2,6 +2,8 @@
 
 use std::sync::Weak;
 
+use crate::prelude::TaskPriority;
+
 mod background_thread;
 
 #[cfg(all(target_family = "unix", not(target_os = "macos")))]
@@ -57,12 +59,13 @@ where
     fn schedule_gui(&self, task: T) -> bool;
 
     /// Post a task to the background task queue so it can be run in a dedicated background thread
-    /// without blocking the plugin's GUI. This function needs to be callable at any time without
-    /// blocking.
+    /// without blocking the plugin's GUI. Tasks with [`TaskPriority::Low`] are posted to a separate
+    /// background thread so they don't block normal priority tasks. This function needs to be
+    /// callable at any time without blocking.
     ///
     /// If the task queue is full, then this will return false.
     #[must_use]
-    fn schedule_background(&self, task: T) -> bool;
+    fn schedule_background(&self, task: T, priority: TaskPriority) -> bool;
 
     /// Whether the calling thread is the event loop's main thread. This is usually the thread the
     /// event loop instance was initialized on.
@@ -3,13 +3,13 @@
 //!
 //! This is essentially a slimmed down version of the `LinuxEventLoop`.
 
-use anymap::Entry;
 use crossbeam::channel;
 use parking_lot::Mutex;
 use std::sync::{Arc, LazyLock, Weak};
 use std::thread::{self, JoinHandle};
 
 use super::MainThreadExecutor;
+use crate::prelude::TaskPriority;
 use crate::util::permit_alloc;
 
 /// See the module's documentation. This is a background thread that can be used to run tasks on.
@@ -24,6 +24,16 @@ pub(crate) struct BackgroundThread<T, E> {
     /// this thread will be woken up to execute the task on the executor. When the last worker
     /// thread handle gets dropped the thread is shut down.
     worker_thread: Arc<WorkerThread<T, E>>,
+    /// The same as `worker_thread`, but used for [`TaskPriority::Low`] tasks. Long running tasks
+    /// are run on this thread so they don't hold up the normal priority tasks.
+    low_priority_worker_thread: Arc<WorkerThread<T, E>>,
+}
+
+/// The (weak) handles to the worker threads as stored in `HANDLE_MAP`. There is one handle per
+/// [`TaskPriority`].
+struct WorkerThreadHandles<T, E> {
+    normal: Weak<WorkerThread<T, E>>,
+    low: Weak<WorkerThread<T, E>>,
 }
 
 /// A handle for the singleton worker thread. This lets multiple instances of the same plugin share
@@ -54,15 +64,21 @@ where
             executor,
             // The same worker thread can be shared by multiple instances. Lifecycle management
             // happens through reference counting.
-            worker_thread: get_or_create_worker_thread(),
+            worker_thread: get_or_create_worker_thread(TaskPriority::Normal),
+            low_priority_worker_thread: get_or_create_worker_thread(TaskPriority::Low),
         }
     }
 
-    pub fn schedule(&self, task: T) -> bool {
+    pub fn schedule(&self, task: T, priority: TaskPriority) -> bool {
+        let worker_thread = match priority {
+            TaskPriority::Normal => &self.worker_thread,
+            TaskPriority::Low => &self.low_priority_worker_thread,
+        };
+
         // NOTE: This may check the current thread ID, which involves an allocation whenever this
         //       first happens on a new thread because of the way thread local storage works
         permit_alloc(|| {
-            self.worker_thread
+            worker_thread
                 .tasks_sender
                 .try_send(Message::Task((task, self.executor.clone())))
                 .is_ok()
@@ -77,10 +93,15 @@ static HANDLE_MAP: LazyLock<Mutex<anymap::Map<dyn std::any::Any + Send>>> =
     LazyLock::new(|| Mutex::new(anymap::Map::new()));
 
 impl<T: Send + 'static, E: MainThreadExecutor<T> + 'static> WorkerThread<T, E> {
-    fn spawn() -> Self {
+    fn spawn(priority: TaskPriority) -> Self {
+        let thread_name = match priority {
+            TaskPriority::Normal => "bg-worker",
+            TaskPriority::Low => "bg-worker-low",
+        };
+
         let (tasks_sender, tasks_receiver) = channel::bounded(super::TASK_QUEUE_CAPACITY);
         let join_handle = thread::Builder::new()
-            .name(String::from("bg-worker"))
+            .name(String::from(thread_name))
             .spawn(move || worker_thread(tasks_receiver))
             .expect("Could not spawn background worker thread");
 
@@ -108,28 +129,31 @@ impl<T, E> Drop for WorkerThread<T, E> {
 
 /// Either acquire a handle for an existing worker thread or create one if it does not yet exists.
 /// This allows multiple plugin instances to share a worker thread. Reference counting happens
-/// automatically as part of this function and `WorkerThreadHandle`'s lifecycle.
-fn get_or_create_worker_thread<T, E>() -> Arc<WorkerThread<T, E>>
+/// automatically as part of this function and `WorkerThreadHandle`'s lifecycle. Every
+/// [`TaskPriority`] gets its own worker thread.
+fn get_or_create_worker_thread<T, E>(priority: TaskPriority) -> Arc<WorkerThread<T, E>>
 where
     T: Send + 'static,
     E: MainThreadExecutor<T> + 'static,
 {
     let mut handle_map = HANDLE_MAP.lock();
 
-    match handle_map.entry::<Weak<WorkerThread<T, E>>>() {
-        Entry::Occupied(mut entry) => {
-            let weak = entry.get_mut();
-            if let Some(arc) = weak.upgrade() {
-                arc
-            } else {
-                let arc = Arc::new(WorkerThread::spawn());
-                *weak = Arc::downgrade(&arc);
-                arc
-            }
-        }
-        Entry::Vacant(entry) => {
-            let arc = Arc::new(WorkerThread::spawn());
-            entry.insert(Arc::downgrade(&arc));
+    let handles = handle_map
+        .entry::<WorkerThreadHandles<T, E>>()
+        .or_insert_with(|| WorkerThreadHandles {
+            normal: Weak::new(),
+            low: Weak::new(),
+        });
+    let weak = match priority {
+        TaskPriority::Normal => &mut handles.normal,
+        TaskPriority::Low => &mut handles.low,
+    };
+
+    match weak.upgrade() {
+        Some(arc) => arc,
+        None => {
+            let arc = Arc::new(WorkerThread::spawn(priority));
+            *weak = Arc::downgrade(&arc);
             arc
         }
     }
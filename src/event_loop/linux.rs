@@ -6,6 +6,7 @@ use std::sync::Weak;
 use std::thread::{self, ThreadId};
 
 use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use crate::prelude::TaskPriority;
 use crate::util::permit_alloc;
 
 /// See [`EventLoop`][super::EventLoop].
@@ -48,14 +49,14 @@ where
 
             true
         } else {
-            self.background_thread.schedule(task)
+            self.background_thread.schedule(task, TaskPriority::Normal)
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
+    fn schedule_background(&self, task: T, priority: TaskPriority) -> bool {
         // This event loop implementation already uses a thread that's completely decoupled from the
         // operating system's or the host's main thread, so we don't need _another_ thread here
-        self.background_thread.schedule(task)
+        self.background_thread.schedule(task, priority)
     }
 
     fn is_main_thread(&self) -> bool {
@@ -12,6 +12,7 @@ use std::os::raw::c_void;
 use std::sync::Weak;
 
 use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use crate::prelude::TaskPriority;
 
 /// Wrapping the `CFRunLoopSourceRef` type is required to be able to annotate it as thread-safe.
 struct LoopSourceWrapper(CFRunLoopSourceRef);
@@ -108,8 +109,8 @@ where
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
-        self.background_thread.schedule(task)
+    fn schedule_background(&self, task: T, priority: TaskPriority) -> bool {
+        self.background_thread.schedule(task, priority)
     }
 
     fn is_main_thread(&self) -> bool {
@@ -19,6 +19,7 @@ use windows::Win32::UI::WindowsAndMessaging::{
 };
 
 use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use crate::prelude::TaskPriority;
 use crate::util::permit_alloc;
 
 /// The custom message ID for our notify event. If the hidden event loop window receives this, then
@@ -160,8 +161,8 @@ where
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
-        self.background_thread.schedule(task)
+    fn schedule_background(&self, task: T, priority: TaskPriority) -> bool {
+        self.background_thread.schedule(task, priority)
     }
 
     fn is_main_thread(&self) -> bool {
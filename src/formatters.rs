@@ -5,6 +5,7 @@
 //! functions. Most of these formatters come as a pair. Check each formatter's documentation for any
 //! additional usage information.
 
+use atomic_float::AtomicF32;
 use std::cmp::Ordering;
 use std::sync::Arc;
 
@@ -169,19 +170,7 @@ pub fn v2s_f32_hz_then_khz_with_note_name(
         }
 
         // This is the inverse of the formula in `f32_midi_note_to_freq`
-        let fractional_note = util::freq_to_midi_note(value);
-        let note = fractional_note.round();
-        let cents = ((fractional_note - note) * 100.0).round() as i32;
-
-        let note_name = util::NOTES[(note as i32).rem_euclid(12) as usize];
-        // NOTE: This is different compared from `(note as i32 / 12) - 1` because truncating always
-        //       rounds towards zero
-        let octave = (note / 12.0).floor() as i32 - 1;
-        let note_str = if cents == 0 || !include_cents {
-            format!("{note_name}{octave}")
-        } else {
-            format!("{note_name}{octave}, {cents:+} ct.")
-        };
+        let note_str = format_note_name(util::freq_to_midi_note(value), include_cents);
 
         if value < 1000.0 {
             format!("{value:.digits$} Hz, {note_str}")
@@ -200,10 +189,6 @@ pub fn v2s_f32_hz_then_khz_with_note_name(
 /// additionally also accepts note names in the same format as [`s2v_i32_note_formatter()`], and
 /// optionally also with cents in the form of `D#5, -23 ct.`.
 pub fn s2v_f32_hz_then_khz() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
-    // FIXME: This is a very crude way to reuse the note value formatter. There's no real runtime
-    //        penalty for doing it this way, but it does look less pretty.
-    let note_formatter = s2v_i32_note_formatter();
-
     Arc::new(move |string| {
         let string = string.trim();
 
@@ -224,7 +209,7 @@ pub fn s2v_f32_hz_then_khz() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
                 .trim_end_matches([' ', 'C', 'c', 'E', 'e', 'N', 'n', 'T', 't', 'S', 's', '.']);
 
             if let (Some(midi_note_number), Ok(cents)) = (
-                note_formatter(midi_note_number_str),
+                parse_note_name(midi_note_number_str),
                 cents_str.parse::<i32>(),
             ) {
                 let plain_note_freq = util::f32_midi_note_to_freq(midi_note_number as f32);
@@ -236,7 +221,7 @@ pub fn s2v_f32_hz_then_khz() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
         if let (_, Some(midi_note_number_str), _) | (Some(midi_note_number_str), None, None) =
             segments
         {
-            if let Some(midi_note_number) = note_formatter(midi_note_number_str) {
+            if let Some(midi_note_number) = parse_note_name(midi_note_number_str) {
                 return Some(util::f32_midi_note_to_freq(midi_note_number as f32));
             }
         }
@@ -255,6 +240,83 @@ pub fn s2v_f32_hz_then_khz() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
     })
 }
 
+/// Format a `f32` Hertz value as a note name like `A4` or `C#3`, where A4 is tuned to
+/// `reference_pitch` Hertz. This is usually 440 Hz. Values below 1 Hz are formatted as if they
+/// were 1 Hz, which is already more than three octaves below C-1. If `round_to_semitone` is false,
+/// then the deviation from the nearest note is included in cents, in the form of `D#5, -23 ct.`.
+/// Can be used with [`s2v_f32_note_name()`].
+pub fn v2s_f32_note_name(
+    round_to_semitone: bool,
+    reference_pitch: f32,
+) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
+    Arc::new(move |value| {
+        let fractional_note = 69.0 + (12.0 * (value.max(1.0) / reference_pitch).log2());
+        format_note_name(fractional_note, !round_to_semitone)
+    })
+}
+
+/// Parse a note name to a frequency in Hertz, where A4 is tuned to `reference_pitch` Hertz. This
+/// accepts the same note names as [`s2v_i32_note_formatter()`]. The note name may optionally be
+/// followed by cents in the form of `D#5, -23 ct.`. Used in conjunction with
+/// [`v2s_f32_note_name()`], and it should receive the same reference pitch.
+pub fn s2v_f32_note_name(reference_pitch: f32) -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
+    Arc::new(move |string| {
+        let (note_str, cents_str) = string.split_once(',').unwrap_or((string, "0"));
+        let cents_str = cents_str
+            .trim_start_matches([' ', '+'])
+            .trim_end_matches([' ', 'C', 'c', 'E', 'e', 'N', 'n', 'T', 't', 'S', 's', '.']);
+
+        let note = parse_note_name(note_str)?;
+        let cents: i32 = cents_str.parse().ok()?;
+
+        let fractional_note = note as f32 + (cents as f32 / 100.0);
+        Some(reference_pitch * 2.0f32.powf((fractional_note - 69.0) / 12.0))
+    })
+}
+
+/// Format a one-pole smoothing coefficient as the equivalent time constant in milliseconds. This is
+/// the time it takes for the filter to cover `1 - 1/e`, or about 63%, of the distance to its
+/// target. The coefficient is the amount of the previous output that's retained every sample, so
+/// a filter using it would compute `y = (coefficient * y) + ((1.0 - coefficient) * x)`.
+///
+/// Because the conversion depends on the sample rate, this takes an `Arc<AtomicF32>` that the
+/// plugin should update in `initialize()`. You should use `" ms"` as the parameter's unit. Used in
+/// conjunction with [`s2v_f32_ms_to_coefficient()`]. Keep in mind that coefficients for long
+/// times are very close to 1, so the displayed times lose some precision as they get longer.
+pub fn v2s_f32_coefficient_to_ms(
+    sample_rate: Arc<AtomicF32>,
+    digits: usize,
+) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
+    Arc::new(move |value| {
+        let sample_rate = sample_rate.load(std::sync::atomic::Ordering::Relaxed);
+        let time_ms = util::coefficient_to_ms(value, sample_rate);
+        if time_ms.is_infinite() {
+            String::from("inf")
+        } else {
+            format!("{time_ms:.digits$}")
+        }
+    })
+}
+
+/// Parse a time in milliseconds to the one-pole smoothing coefficient with that time constant.
+/// Handles the `ms` unit for you. Used in conjunction with [`v2s_f32_coefficient_to_ms()`], and it
+/// should receive the same sample rate.
+pub fn s2v_f32_ms_to_coefficient(
+    sample_rate: Arc<AtomicF32>,
+) -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
+    Arc::new(move |string| {
+        let string = string.trim_end_matches([' ', 'm', 'M', 's', 'S']);
+        let time_ms = if string.eq_ignore_ascii_case("inf") {
+            f32::INFINITY
+        } else {
+            string.parse().ok()?
+        };
+
+        let sample_rate = sample_rate.load(std::sync::atomic::Ordering::Relaxed);
+        Some(util::ms_to_coefficient(time_ms, sample_rate))
+    })
+}
+
 /// Format an order/power of two. Useful in conjunction with [`s2v_i32_power_of_two()`] to limit
 /// integer parameter ranges to be only powers of two.
 pub fn v2s_i32_power_of_two() -> Arc<dyn Fn(i32) -> String + Send + Sync> {
@@ -278,36 +340,58 @@ pub fn v2s_i32_note_formatter() -> Arc<dyn Fn(i32) -> String + Send + Sync> {
 }
 
 /// Parse a note name to a MIDI number using the inverse mapping from [`v2s_i32_note_formatter()`].
+/// This accepts sharps and flats in any case, so `A4`, `a#3`, and `Bb2` are all valid inputs.
 pub fn s2v_i32_note_formatter() -> Arc<dyn Fn(&str) -> Option<i32> + Send + Sync> {
-    Arc::new(|string| {
-        let string = string.trim();
-        if string.len() < 2 {
-            return None;
-        }
+    Arc::new(parse_note_name)
+}
 
-        // A valid trimmed string will either be be at least two characters (we already checked the
-        // length) or at least three characters if the second character is a hash, and there may be
-        // spaces in between the note name and the octave number
-        let (note_name, octave) = string
-            .split_once(|c: char| c.is_whitespace())
-            .unwrap_or_else(|| {
-                // Sharps need to be handled separately
-                if string.len() > 2 && &string[1..2] == "#" {
-                    (&string[..2], &string[2..])
-                } else {
-                    (&string[..1], &string[1..])
-                }
-            });
-
-        let note_id = util::NOTES
-            .iter()
-            .position(|&candidate| note_name.eq_ignore_ascii_case(candidate))?
-            as i32;
-        let octave: i32 = octave.trim().parse().ok()?;
-
-        // 0 = C-1, 12 = C0, 24 = C1
-        Some(note_id + (12 * (octave + 1)))
-    })
+/// Format a fractional MIDI note number as a note name like `A4` or `C#3`. If `include_cents` is
+/// set, then the deviation from the nearest note is included in cents, in the form of
+/// `D#5, -23 ct.`. Shared between the `f32` note name formatters.
+fn format_note_name(fractional_note: f32, include_cents: bool) -> String {
+    let note = fractional_note.round();
+    let cents = ((fractional_note - note) * 100.0).round() as i32;
+
+    // NOTE: This is different compared from `(note / 12) - 1` because truncating always rounds
+    //       towards zero
+    let note = note as i32;
+    let note_name = util::NOTES[note.rem_euclid(12) as usize];
+    let octave = note.div_euclid(12) - 1;
+    if cents == 0 || !include_cents {
+        format!("{note_name}{octave}")
+    } else {
+        format!("{note_name}{octave}, {cents:+} ct.")
+    }
+}
+
+/// Parse a note name with an optional sharp or flat, like `A4`, `a#3`, or `Bb2`, to a MIDI note
+/// number. See [`s2v_i32_note_formatter()`].
+fn parse_note_name(string: &str) -> Option<i32> {
+    let string = string.trim();
+    let mut chars = string.chars();
+    let note_id = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    // The octave number always needs to be present, so a `b` following the note name is always a
+    // flat. There may also be spaces between the note name and the octave number.
+    let remainder = chars.as_str();
+    let (accidental, octave) = match remainder.chars().next() {
+        Some('#') => (1, &remainder[1..]),
+        Some('b' | 'B') => (-1, &remainder[1..]),
+        _ => (0, remainder),
+    };
+    let octave: i32 = octave.trim().parse().ok()?;
+
+    // 0 = C-1, 12 = C0, 24 = C1
+    Some(note_id + accidental + (12 * (octave + 1)))
 }
 
 /// Display 'Bypassed' or 'Not Bypassed' depending on whether the parameter is true or false.
@@ -371,4 +455,60 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn i32_note_formatter_accidentals() {
+        let v2s = v2s_i32_note_formatter();
+        let s2v = s2v_i32_note_formatter();
+
+        assert_eq!(s2v("A4"), Some(69));
+        assert_eq!(s2v("a#3"), Some(58));
+        assert_eq!(s2v("Bb2"), Some(46));
+        assert_eq!(s2v("C# 4"), Some(61));
+        assert_eq!(s2v("H4"), None);
+        assert_eq!(s2v("Bb"), None);
+
+        for note in 0..128 {
+            assert_eq!(s2v(&v2s(note)), Some(note));
+        }
+    }
+
+    #[test]
+    fn f32_note_name_roundtrip() {
+        let v2s = v2s_f32_note_name(false, 440.0);
+        let s2v = s2v_f32_note_name(440.0);
+
+        assert_eq!(s2v("A4"), Some(440.0));
+        approx::assert_relative_eq!(s2v("a#3").unwrap(), 233.08188, epsilon = 1e-3);
+        approx::assert_relative_eq!(s2v("Bb2").unwrap(), 116.54094, epsilon = 1e-3);
+        assert_eq!(v2s(440.0), "A4");
+        assert_eq!(v2s(233.08188), "A#3");
+
+        for freq in [20.0, 116.54094, 261.62558, 445.0, 1000.0, 12345.0] {
+            let string = v2s(freq);
+            let roundtrip_freq = s2v(&string).unwrap();
+            assert_eq!(
+                v2s(roundtrip_freq),
+                string,
+                "Unexpected: {freq} -> {string} -> {roundtrip_freq}"
+            );
+        }
+    }
+
+    #[test]
+    fn f32_coefficient_to_ms_roundtrip() {
+        let sample_rate = Arc::new(AtomicF32::new(48_000.0));
+        let v2s = v2s_f32_coefficient_to_ms(sample_rate.clone(), 0);
+        let s2v = s2v_f32_ms_to_coefficient(sample_rate);
+
+        assert_eq!(v2s(0.0), "0");
+        assert_eq!(v2s(1.0), "inf");
+        assert_eq!(s2v("inf"), Some(1.0));
+
+        // Longer times get less precise as the coefficient approaches 1
+        for time_ms in ["1", "10", "100"] {
+            let coefficient = s2v(&format!("{time_ms} ms")).unwrap();
+            assert_eq!(v2s(coefficient), time_ms);
+        }
+    }
 }
@@ -9,6 +9,13 @@ pub mod sysex;
 
 pub use midi_consts::channel_event::control_change;
 
+/// The MIDI 1.0 High Resolution Velocity Prefix CC. This CC contains the lower seven bits for the
+/// next note on or note off event's velocity on the same channel.
+const HIGH_RESOLUTION_VELOCITY_PREFIX: u8 = 88;
+/// The number of MIDI CCs that can be used as the most significant byte of a two message 14-bit
+/// CC. The least significant byte is sent on the same CC number plus this value.
+const NUM_14BIT_CCS: u8 = 32;
+
 /// A plugin-specific note event type.
 ///
 /// The reason why this is defined like this instead of parameterizing `NoteEvent` with `P` is
@@ -30,6 +37,13 @@ pub enum MidiConfig {
     /// and assigns polyphonic modulation IDs to some of its parameters, then it will also receive
     /// polyphonic modulation events. This level is also needed to be able to send SysEx events.
     Basic,
+    /// The plugin receives full MIDI CCs, pitch bend, channel pressure, and SysEx, but no note on,
+    /// note off, choke, or polyphonic expression events. The note input port is presented to the
+    /// host as a plain MIDI input so hosts won't list the plugin as an instrument. For VST3 plugins
+    /// this adds the same 130*16 parameters as [`MidiCCs`][Self::MidiCCs]. When used for
+    /// [`Plugin::MIDI_OUTPUT`][crate::prelude::Plugin::MIDI_OUTPUT] this behaves the same as
+    /// [`MidiCCs`][Self::MidiCCs].
+    MidiCCOnly,
     /// The plugin receives full MIDI CCs as well as pitch bend information. For VST3 plugins this
     /// involves adding 130*16 parameters to bind to the the 128 MIDI CCs, pitch bend, and channel
     /// pressure.
@@ -63,8 +77,12 @@ pub enum NoteEvent<S> {
         channel: u8,
         /// The note's MIDI key number, in `0..128`.
         note: u8,
-        /// The note's velocity, in `[0, 1]`. Some plugin APIs may allow higher precision than the
-        /// 128 levels available in MIDI.
+        /// The note's velocity, already normalized to `[0, 1]`. Some plugin APIs may allow higher
+        /// precision than the 128 levels available in MIDI. CLAP and VST3 hosts can send these
+        /// higher precision velocities directly. For MIDI 1.0 input the CLAP and VST3 wrappers
+        /// combine the High Resolution Velocity Prefix CC (CC 88) with the note's 7-bit velocity,
+        /// see [`HighResolutionVelocity`]. When the velocity was received as a plain 7-bit MIDI
+        /// value it is always a multiple of `1 / 127`.
         velocity: f32,
     },
     /// A note off event, available on [`MidiConfig::Basic`] and up. Bitwig Studio does not provide
@@ -279,7 +297,7 @@ pub enum NoteEvent<S> {
         /// The note's brightness amount, in `[0, 1]`.
         brightness: f32,
     },
-    /// A MIDI channel pressure event, available on [`MidiConfig::MidiCCs`] and up.
+    /// A MIDI channel pressure event, available on [`MidiConfig::MidiCCOnly`] and up.
     MidiChannelPressure {
         timing: u32,
         /// The affected channel, in `0..16`.
@@ -287,7 +305,7 @@ pub enum NoteEvent<S> {
         /// The pressure, normalized to `[0, 1]` to match the poly pressure event.
         pressure: f32,
     },
-    /// A MIDI pitch bend, available on [`MidiConfig::MidiCCs`] and up.
+    /// A MIDI pitch bend, available on [`MidiConfig::MidiCCOnly`] and up.
     MidiPitchBend {
         timing: u32,
         /// The affected channel, in `0..16`.
@@ -295,13 +313,14 @@ pub enum NoteEvent<S> {
         /// The pressure, normalized to `[0, 1]`. `0.5` means no pitch bend.
         value: f32,
     },
-    /// A MIDI control change event, available on [`MidiConfig::MidiCCs`] and up.
+    /// A MIDI control change event, available on [`MidiConfig::MidiCCOnly`] and up.
     ///
     /// # Note
     ///
-    /// The wrapper does not perform any special handling for two message 14-bit CCs (where the CC
-    /// number is in `0..32`, and the next CC is that number plus 32) or for four message RPN
-    /// messages. For now you will need to handle these CCs yourself.
+    /// The CLAP and VST3 wrappers coalesce two message 14-bit CCs (where the CC number is in
+    /// `0..32`, and the LSB is sent on that number plus 32) using [`HighResolutionCCs`]. The
+    /// wrapper does not perform any special handling for four message RPN messages. For now you
+    /// will need to handle these CCs yourself.
     MidiCC {
         timing: u32,
         /// The affected channel, in `0..16`.
@@ -311,7 +330,7 @@ pub enum NoteEvent<S> {
         /// The CC's value, normalized to `[0, 1]`. Multiply by 127 to get the original raw value.
         value: f32,
     },
-    /// A MIDI program change event, available on [`MidiConfig::MidiCCs`] and up. VST3 plugins
+    /// A MIDI program change event, available on [`MidiConfig::MidiCCOnly`] and up. VST3 plugins
     /// cannot receive these events.
     MidiProgramChange {
         timing: u32,
@@ -422,8 +441,9 @@ impl<S: SysExMessage> NoteEvent<S> {
         let channel = status_byte & midi::MIDI_CHANNEL_MASK;
 
         if midi_data.len() >= 3 {
-            // TODO: Maybe add special handling for 14-bit CCs and RPN messages at some
-            //       point, right now the plugin has to figure it out for itself
+            // TODO: Maybe add special handling for RPN messages at some point, right now the
+            //       plugin has to figure it out for itself. 14-bit CCs are handled separately by
+            //       `HighResolutionCCs`.
             match event_type {
                 // You thought this was a note on? Think again! This is a cleverly disguised note off
                 // event straight from the 80s when Baud rate was still a limiting factor!
@@ -628,7 +648,8 @@ impl<S: SysExMessage> NoteEvent<S> {
 
     /// Subtract a sample offset from this event's timing, needed to compensate for the block
     /// splitting in the VST3 wrapper implementation because all events have to be read upfront.
-    #[cfg_attr(not(feature = "vst3"), allow(dead_code))]
+    /// The standalone wrapper also uses this when splitting buffers for automation playback.
+    #[cfg_attr(not(any(feature = "vst3", feature = "standalone")), allow(dead_code))]
     pub(crate) fn subtract_timing(&mut self, samples: u32) {
         match self {
             NoteEvent::NoteOn { timing, .. } => *timing -= samples,
@@ -651,6 +672,156 @@ impl<S: SysExMessage> NoteEvent<S> {
             NoteEvent::MidiSysEx { timing, .. } => *timing -= samples,
         }
     }
+
+    /// Add a sample offset to this event's timing, needed to make the output events from a split
+    /// block relative to the start of the buffer again in the standalone wrapper.
+    #[cfg_attr(not(feature = "standalone"), allow(dead_code))]
+    pub(crate) fn add_timing(&mut self, samples: u32) {
+        match self {
+            NoteEvent::NoteOn { timing, .. } => *timing += samples,
+            NoteEvent::NoteOff { timing, .. } => *timing += samples,
+            NoteEvent::Choke { timing, .. } => *timing += samples,
+            NoteEvent::VoiceTerminated { timing, .. } => *timing += samples,
+            NoteEvent::PolyModulation { timing, .. } => *timing += samples,
+            NoteEvent::MonoAutomation { timing, .. } => *timing += samples,
+            NoteEvent::PolyPressure { timing, .. } => *timing += samples,
+            NoteEvent::PolyVolume { timing, .. } => *timing += samples,
+            NoteEvent::PolyPan { timing, .. } => *timing += samples,
+            NoteEvent::PolyTuning { timing, .. } => *timing += samples,
+            NoteEvent::PolyVibrato { timing, .. } => *timing += samples,
+            NoteEvent::PolyExpression { timing, .. } => *timing += samples,
+            NoteEvent::PolyBrightness { timing, .. } => *timing += samples,
+            NoteEvent::MidiChannelPressure { timing, .. } => *timing += samples,
+            NoteEvent::MidiPitchBend { timing, .. } => *timing += samples,
+            NoteEvent::MidiCC { timing, .. } => *timing += samples,
+            NoteEvent::MidiProgramChange { timing, .. } => *timing += samples,
+            NoteEvent::MidiSysEx { timing, .. } => *timing += samples,
+        }
+    }
+}
+
+/// Combines the MIDI 1.0 High Resolution Velocity Prefix CC (CC 88) with the note on and note off
+/// event that follows it on the same channel, turning the two 7-bit values into a single 14-bit
+/// velocity. The CLAP and VST3 wrappers already use this for MIDI input, so plugins only need to
+/// use this themselves when parsing raw MIDI data with [`NoteEvent::from_midi()`].
+///
+/// The prefix CC events are not removed, so they will still be passed on to the plugin.
+#[derive(Debug, Default, Clone)]
+pub struct HighResolutionVelocity {
+    /// The lower seven bits from the last prefix CC for each channel, if it has not yet been
+    /// applied to a note event.
+    pending_lsbs: [Option<u8>; 16],
+}
+
+impl HighResolutionVelocity {
+    /// Create a new tracker without any pending velocity prefixes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget any pending velocity prefixes.
+    pub fn reset(&mut self) {
+        self.pending_lsbs = [None; 16];
+    }
+
+    /// Process an event. This stores the lower seven bits of the velocity if the event is a High
+    /// Resolution Velocity Prefix CC, and it applies those bits to the velocity if the event is a
+    /// note on or note off event. Events need to be processed in order. Velocities that already
+    /// have a higher resolution than 7-bit MIDI, like those sent by CLAP and VST3 hosts, are left
+    /// untouched.
+    pub fn process<S>(&mut self, event: &mut NoteEvent<S>) {
+        match event {
+            NoteEvent::MidiCC {
+                channel,
+                cc: HIGH_RESOLUTION_VELOCITY_PREFIX,
+                value,
+                ..
+            } => {
+                if let Some(pending_lsb) = self.pending_lsbs.get_mut(*channel as usize) {
+                    *pending_lsb = Some((*value * 127.0).round() as u8);
+                }
+            }
+            NoteEvent::NoteOn {
+                channel, velocity, ..
+            }
+            | NoteEvent::NoteOff {
+                channel, velocity, ..
+            } => {
+                let Some(lsb) = self
+                    .pending_lsbs
+                    .get_mut(*channel as usize)
+                    .and_then(Option::take)
+                else {
+                    return;
+                };
+
+                // If the host already sent a high resolution velocity then the prefix is ignored
+                let msb = (*velocity * 127.0).round();
+                if (*velocity * 127.0 - msb).abs() < 1e-3 {
+                    let velocity_14bit = ((msb as u16) << 7) | lsb as u16;
+                    *velocity = velocity_14bit as f32 / 16383.0;
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Coalesces two message 14-bit MIDI CCs. When a CC in `0..32` is followed by the CC with that
+/// number plus 32 on the same channel, the second CC event is replaced with an event for the first
+/// CC number that contains the combined 14-bit value. The CLAP and VST3 wrappers already use this
+/// for MIDI input, so plugins only need to use this themselves when parsing raw MIDI data with
+/// [`NoteEvent::from_midi()`].
+///
+/// Events are processed after they have been parsed, so running status in the original MIDI
+/// stream does not affect this. Like the MIDI specification prescribes, a new MSB resets the LSB,
+/// and any later LSB-only CC updates the value using the last MSB.
+#[derive(Debug, Default, Clone)]
+pub struct HighResolutionCCs {
+    /// The raw seven bit value of the last MSB CC in `0..32` for each channel, if one has been
+    /// received since the last reset.
+    msbs: [[Option<u8>; NUM_14BIT_CCS as usize]; 16],
+}
+
+impl HighResolutionCCs {
+    /// Create a new tracker without any stored MSBs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all stored MSBs.
+    pub fn reset(&mut self) {
+        self.msbs = [[None; NUM_14BIT_CCS as usize]; 16];
+    }
+
+    /// Process an event. This stores the value of MSB CCs, and it combines LSB CCs with the
+    /// previously stored MSB for the same channel and CC pair. LSB CCs without a preceding MSB are
+    /// left untouched. Events need to be processed in order.
+    pub fn process<S>(&mut self, event: &mut NoteEvent<S>) {
+        let NoteEvent::MidiCC {
+            channel, cc, value, ..
+        } = event
+        else {
+            return;
+        };
+        let Some(channel_msbs) = self.msbs.get_mut(*channel as usize) else {
+            return;
+        };
+
+        let raw_value = (*value * 127.0).round() as u8;
+        match *cc {
+            msb_cc @ 0..NUM_14BIT_CCS => channel_msbs[msb_cc as usize] = Some(raw_value),
+            lsb_cc @ NUM_14BIT_CCS..64 => {
+                let msb_cc = lsb_cc - NUM_14BIT_CCS;
+                if let Some(msb) = channel_msbs[msb_cc as usize] {
+                    let value_14bit = ((msb as u16) << 7) | raw_value as u16;
+                    *cc = msb_cc;
+                    *value = value_14bit as f32 / 16383.0;
+                }
+            }
+            _ => (),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -683,6 +854,101 @@ mod tests {
         assert_eq!(roundtrip_basic_event(event), event);
     }
 
+    #[test]
+    fn test_high_resolution_velocity() {
+        let mut high_resolution_velocity = HighResolutionVelocity::new();
+        let mut prefix = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 88, 0x40]).unwrap();
+        let mut note_on = NoteEvent::<()>::from_midi(TIMING, &[0x91, 2, 0x20]).unwrap();
+        high_resolution_velocity.process(&mut prefix);
+        high_resolution_velocity.process(&mut note_on);
+
+        match note_on {
+            NoteEvent::NoteOn { velocity, .. } => {
+                assert_eq!(velocity, ((0x20 << 7) | 0x40) as f32 / 16383.0)
+            }
+            event => panic!("Unexpected event: {event:?}"),
+        }
+
+        // The prefix only applies to a single note, and only on the same channel
+        let mut other_channel = NoteEvent::<()>::from_midi(TIMING, &[0x92, 2, 0x20]).unwrap();
+        let mut note_on = NoteEvent::<()>::from_midi(TIMING, &[0x91, 2, 0x20]).unwrap();
+        let mut next_note_on = note_on;
+        high_resolution_velocity.process(&mut prefix);
+        high_resolution_velocity.process(&mut other_channel);
+        high_resolution_velocity.process(&mut note_on);
+        high_resolution_velocity.process(&mut next_note_on);
+        assert_ne!(note_on, next_note_on);
+        match (other_channel, next_note_on) {
+            (
+                NoteEvent::NoteOn {
+                    velocity: other_velocity,
+                    ..
+                },
+                NoteEvent::NoteOn {
+                    velocity: next_velocity,
+                    ..
+                },
+            ) => {
+                assert_eq!(other_velocity, 0x20 as f32 / 127.0);
+                assert_eq!(next_velocity, 0x20 as f32 / 127.0);
+            }
+            events => panic!("Unexpected events: {events:?}"),
+        }
+    }
+
+    #[test]
+    fn test_high_resolution_ccs() {
+        let mut high_resolution_ccs = HighResolutionCCs::new();
+
+        // An LSB without a preceding MSB is passed through as is
+        let mut lsb = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 33, 0x10]).unwrap();
+        let unmodified_lsb = lsb;
+        high_resolution_ccs.process(&mut lsb);
+        assert_eq!(lsb, unmodified_lsb);
+
+        let mut msb = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 1, 0x20]).unwrap();
+        let unmodified_msb = msb;
+        let mut lsb = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 33, 0x40]).unwrap();
+        high_resolution_ccs.process(&mut msb);
+        high_resolution_ccs.process(&mut lsb);
+        assert_eq!(msb, unmodified_msb);
+        assert_eq!(
+            lsb,
+            NoteEvent::MidiCC {
+                timing: TIMING,
+                channel: 1,
+                cc: 1,
+                value: ((0x20 << 7) | 0x40) as f32 / 16383.0,
+            }
+        );
+
+        // Like with running status, a later LSB on its own reuses the last MSB. LSBs for other
+        // channels and CCs are not affected.
+        let mut next_lsb = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 33, 0x41]).unwrap();
+        let mut other_channel = NoteEvent::<()>::from_midi(TIMING, &[0xB2, 33, 0x41]).unwrap();
+        let mut other_cc = NoteEvent::<()>::from_midi(TIMING, &[0xB1, 34, 0x41]).unwrap();
+        let (unmodified_other_channel, unmodified_other_cc) = (other_channel, other_cc);
+        high_resolution_ccs.process(&mut next_lsb);
+        high_resolution_ccs.process(&mut other_channel);
+        high_resolution_ccs.process(&mut other_cc);
+        assert_eq!(
+            next_lsb,
+            NoteEvent::MidiCC {
+                timing: TIMING,
+                channel: 1,
+                cc: 1,
+                value: ((0x20 << 7) | 0x41) as f32 / 16383.0,
+            }
+        );
+        assert_eq!(other_channel, unmodified_other_channel);
+        assert_eq!(other_cc, unmodified_other_cc);
+
+        high_resolution_ccs.reset();
+        let mut lsb = unmodified_lsb;
+        high_resolution_ccs.process(&mut lsb);
+        assert_eq!(lsb, unmodified_lsb);
+    }
+
     #[test]
     fn test_note_off_midi_conversion() {
         let event = NoteEvent::<()>::NoteOff {
@@ -756,6 +1022,7 @@ mod tests {
 
     mod sysex {
         use super::*;
+        use crate::midi::sysex::Dump;
 
         #[derive(Clone, Debug, PartialEq)]
         enum MessageType {
@@ -809,6 +1076,44 @@ mod tests {
             }
         }
 
+        #[test]
+        fn test_large_message_roundtrip() {
+            let mut first_payload = [0; 510];
+            for (idx, byte) in first_payload.iter_mut().enumerate() {
+                *byte = (idx % 128) as u8;
+            }
+            let mut second_payload = first_payload;
+            second_payload.reverse();
+
+            // Two back-to-back messages should be converted separately and keep their own timings
+            let events = [
+                NoteEvent::MidiSysEx {
+                    timing: TIMING,
+                    message: Dump(first_payload),
+                },
+                NoteEvent::MidiSysEx {
+                    timing: TIMING + 1,
+                    message: Dump(second_payload),
+                },
+            ];
+            for event in &events {
+                let (padded_sysex_buffer, length) = match event.clone().as_midi() {
+                    Some(MidiResult::SysEx(padded_sysex_buffer, length)) => {
+                        (padded_sysex_buffer, length)
+                    }
+                    result => panic!("Unexpected result: {result:?}"),
+                };
+                let sysex_buffer =
+                    crate::midi::sysex::message_from_padded_buffer(&padded_sysex_buffer, length)
+                        .unwrap();
+                assert_eq!(sysex_buffer.len(), 512);
+
+                let parsed = NoteEvent::from_midi(event.timing(), sysex_buffer).unwrap();
+                assert_eq!(parsed, *event);
+            }
+            assert_ne!(events[0].timing(), events[1].timing());
+        }
+
         #[test]
         fn test_invalid_parse() {
             let midi_data = [0xf0, 0x0, 127, 0xf7];
@@ -37,6 +37,50 @@ pub trait SysExMessage: Debug + Clone + PartialEq + Send + Sync {
     fn to_buffer(self) -> (Self::Buffer, usize);
 }
 
+/// Get the message from a padded buffer returned by [`SysExMessage::to_buffer()`]. If the reported
+/// length exceeds the buffer's capacity, then the message would be truncated or read out of
+/// bounds, so it is rejected with a debug assertion failure and this returns `None` instead.
+pub(crate) fn message_from_padded_buffer(padded_buffer: &[u8], length: usize) -> Option<&[u8]> {
+    if length > padded_buffer.len() {
+        nih_debug_assert_failure!(
+            "SysEx message length ({} bytes) exceeds its buffer's capacity ({} bytes), the \
+             message will not be sent",
+            length,
+            padded_buffer.len()
+        );
+        return None;
+    }
+
+    Some(&padded_buffer[..length])
+}
+
+/// A message type for larger SysEx dumps, with a fixed size payload wrapped in the SysEx header and
+/// EOX bytes. Used to test the conversions between SysEx messages and the plugin APIs' events.
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Dump(pub [u8; 510]);
+
+#[cfg(test)]
+impl SysExMessage for Dump {
+    type Buffer = [u8; 512];
+
+    fn from_buffer(buffer: &[u8]) -> Option<Self> {
+        match buffer {
+            [0xf0, payload @ .., 0xf7] => Some(Dump(payload.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    fn to_buffer(self) -> (Self::Buffer, usize) {
+        let mut buffer = [0; 512];
+        buffer[0] = 0xf0;
+        buffer[1..511].copy_from_slice(&self.0);
+        buffer[511] = 0xf7;
+
+        (buffer, 512)
+    }
+}
+
 /// A default implementation plugins that don't need SysEx support can use.
 impl SysExMessage for () {
     type Buffer = [u8; 0];
@@ -22,7 +22,7 @@ mod integer;
 pub mod internals;
 pub mod persist;
 pub mod range;
-pub mod smoothing;
+pub use nih_plug_dsp::smoothing;
 
 pub use boolean::BoolParam;
 pub use enums::EnumParam;
@@ -50,9 +50,50 @@ bitflags::bitflags! {
         /// Don't show this parameter when generating a generic UI for the plugin using one of
         /// NIH-plug's generic UI widgets.
         const HIDE_IN_GENERIC_UI = 1 << 3;
+        /// The parameter's value is set by the plugin from the audio thread using
+        /// [`ProcessContext::set_read_only_parameter()`][set_read_only_parameter], for instance
+        /// to show a gain reduction meter in the host's UI. The host and the user cannot change
+        /// the parameter's value. This also implies `NON_AUTOMATABLE`.
+        ///
+        /// [set_read_only_parameter]: crate::prelude::ProcessContext::set_read_only_parameter()
+        const READ_ONLY = 1 << 4;
+        /// A display-only hint for editors that the parameter is bipolar, like a panning control
+        /// or a pitch shift in both directions. NIH-plug's slider widgets fill the bar from the
+        /// center of the range instead of from the left for these parameters. This is not sent
+        /// to the host.
+        const BIPOLAR = 1 << 5;
+        /// The parameter only affects the plugin's editor or configuration, and not the sound it
+        /// produces. The parameter is still saved and restored as part of the plugin's state, but
+        /// it's listed separately in the [`PluginState`][crate::wrapper::state::PluginState] so
+        /// A/B comparisons and similar features can leave it out. This is not sent to the host.
+        const VIEW_STATE = 1 << 6;
+        /// Changing the parameter's value may change the plugin's latency, like a window size or
+        /// an oversampling amount. The plugin should still report the new latency using
+        /// `set_latency_samples()` from its process function. For CLAP plugins the parameter is
+        /// marked as requiring a process call, so the plugin always gets to see value changes
+        /// even when the host changes the value while the plugin is not processing audio.
+        const AFFECTS_LATENCY = 1 << 7;
     }
 }
 
+/// The kind of quantity a parameter's value represents. Plugin formats don't have a standardized
+/// way to communicate this, but it's exposed through [`Param::value_type()`] so hosts and generic
+/// UIs can use it when they gain support for it. Set this using the `.with_value_type()` builder
+/// method on [`FloatParam`] and [`IntParam`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParamValueType {
+    /// A value without any particular meaning. This is the default.
+    #[default]
+    Generic,
+    /// A gain value, like a parameter using
+    /// [`v2s_f32_gain_to_db()`][crate::formatters::v2s_f32_gain_to_db()].
+    Gain,
+    /// A frequency in Hertz.
+    Frequency,
+    /// A duration, like an attack or release time.
+    Time,
+}
+
 // See https://rust-lang.github.io/api-guidelines/future-proofing.html for more information
 mod sealed {
     /// Dummy trait to prevent [`Param`] from being implemented outside of NIH-plug. This is not
@@ -83,6 +124,9 @@ pub trait Param: Display + Debug + sealed::Sealed {
     /// Get the unit label for this parameter, if any.
     fn unit(&self) -> &'static str;
 
+    /// Get the kind of quantity this parameter's value represents. See [`ParamValueType`].
+    fn value_type(&self) -> ParamValueType;
+
     /// Get this parameter's polyphonic modulation ID. If this is set for a parameter in a CLAP
     /// plugin, then polyphonic modulation will be enabled for that parameter. Polyphonic modulation
     /// is communicated to the plugin through
@@ -118,6 +162,14 @@ pub trait Param: Display + Debug + sealed::Sealed {
     /// Studio use modulation.
     fn unmodulated_normalized_value(&self) -> f32;
 
+    /// Returns `true` if this parameter's value has changed since the last time this function was
+    /// called, and then resets that state. This covers changes from automation, modulation, the
+    /// GUI, and state restores. This can be used to only recompute expensive values like filter
+    /// coefficients when a parameter has actually changed, without needing to set up callbacks or
+    /// to track the previous values yourself. Every parameter only tracks a single changed state,
+    /// so this should only be called from a single place, usually the process function.
+    fn changed_since_last_call(&self) -> bool;
+
     /// Get the unnormalized default value for this parameter.
     fn default_plain_value(&self) -> Self::Plain;
 
@@ -164,6 +216,19 @@ pub trait Param: Display + Debug + sealed::Sealed {
     /// string or some DAWs may show duplicate units.
     fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String;
 
+    /// The same as [`normalized_value_to_string()`][Self::normalized_value_to_string()], but
+    /// appending the string representation to `string` instead of returning a new string. The
+    /// wrappers use this with a reusable buffer so the host requesting a parameter's string
+    /// representation does not allocate.
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        include_unit: bool,
+        string: &mut String,
+    ) {
+        string.push_str(&self.normalized_value_to_string(normalized, include_unit));
+    }
+
     /// Get the string representation for a normalized value. Used as part of the wrappers.
     fn string_to_normalized_value(&self, string: &str) -> Option<f32>;
 
@@ -247,7 +312,19 @@ pub(crate) trait ParamMut: Param {
 /// The struct can also contain other fields that should be persisted along with the rest of the
 /// preset data. These fields should be [`PersistentField`][persist::PersistentField]s annotated
 /// with the `#[persist = "key"]` attribute containing types that can be serialized and deserialized
-/// with [Serde](https://serde.rs/).
+/// with [Serde](https://serde.rs/). This includes the common locks, the atomic integer types, and
+/// `AtomicCell<T>` for `Copy` values, as well as `Arc`s of those so the fields can be shared with
+/// an editor. A small `Copy` enum like an editor's current mode can thus simply be stored in an
+/// `Arc<AtomicCell<T>>`.
+///
+/// ## `#[view_state]`
+///
+/// Persistent fields that only contain editor or configuration state rather than anything that
+/// affects the plugin's sound, like an editor's size or the mode it's in, can additionally be
+/// annotated with `#[view_state]`. These fields are still saved and restored like any other
+/// persistent field, but they're listed separately in the plugin's
+/// [`PluginState`][crate::wrapper::state::PluginState] so preset comparisons can leave them out.
+/// The [`ParamFlags::VIEW_STATE`] flag does the same thing for parameters.
 ///
 /// ## `#[nested]`, `#[nested(group_name = "group name")]`
 ///
@@ -275,6 +352,22 @@ pub(crate) trait ParamMut: Param {
 /// parameter will belong to the group `Foo {array_index + 1}`, and it will have the renamed
 /// parameter ID `bar_{array_index + 1}`. The same thing applies to persistent field keys.
 ///
+/// # Parameters that depend on other parameters
+///
+/// A parameter's range is fixed, and the host is free to set every parameter to any value within
+/// that range. If a parameter's valid values depend on another parameter, like a maximum that
+/// should never be lower than a minimum or crossover frequencies that should stay ordered, then
+/// don't try to change the parameters themselves from a parameter callback. The host doesn't know
+/// about those changes and it will overwrite them again during automation playback. Instead, clamp
+/// the values where they are used in the DSP code.
+///
+/// If the parameters themselves should also reflect this relationship, then the corrected value
+/// needs to be sent to the host as an automation gesture. From the audio thread this can be done
+/// with [`ProcessContext::set_parameter()`][crate::prelude::ProcessContext::set_parameter()]. The
+/// Crossover plugin uses this to keep its crossover frequencies in ascending order. An editor can
+/// use a [`ParamSetter`][crate::prelude::ParamSetter] with a `begin_set_parameter()`,
+/// `set_parameter()`, `end_set_parameter()` gesture instead. Diopser's safe mode works like this.
+///
 /// # Safety
 ///
 /// This implementation is safe when using from the wrapper because the plugin's returned `Params`
@@ -311,6 +404,13 @@ pub unsafe trait Params: 'static + Send + Sync {
     /// [`persist::deserialize_field()`] under the hood.
     #[allow(unused_variables)]
     fn deserialize_fields(&self, serialized: &BTreeMap<String, String>) {}
+
+    /// The keys of all fields returned by [`serialize_fields()`][Self::serialize_fields()] that
+    /// only contain editor or configuration state. The derive macro includes all persistent fields
+    /// that have the `#[view_state]` attribute.
+    fn view_state_field_keys(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// This may be useful when building generic UIs using nested `Params` objects.
@@ -326,4 +426,105 @@ unsafe impl<P: Params> Params for Arc<P> {
     fn deserialize_fields(&self, serialized: &BTreeMap<String, String>) {
         self.as_ref().deserialize_fields(serialized)
     }
+
+    fn view_state_field_keys(&self) -> Vec<String> {
+        self.as_ref().view_state_field_keys()
+    }
+}
+
+/// A page of parameters for a generic UI, as returned by [`param_pages()`].
+#[derive(Debug, Clone)]
+pub struct ParamPage {
+    /// The name of the top level group the parameters belong to, or an empty string for the page
+    /// containing the parameters that are not part of any group.
+    pub name: String,
+    /// The parameters on this page, in the same order as in the `param_map()`. These pointers
+    /// follow the same rules as the ones returned by [`Params::param_map()`].
+    pub params: Vec<ParamPtr>,
+}
+
+/// Split a [`Params`] object's parameters into one page per top level group. This is meant for
+/// generic UIs that show a page or tab for every `#[nested(group = "...")]` parameter object.
+/// Groups nested within other groups end up on their top level group's page. Parameters that are
+/// not part of a group are placed on a page with an empty name, which always comes first. The
+/// other pages are ordered by their first parameter's position in the `param_map()`. Parameters
+/// with the [`ParamFlags::HIDE_IN_GENERIC_UI`] flag are left out, and so are pages that end up
+/// empty.
+pub fn param_pages(params: &(impl Params + ?Sized)) -> Vec<ParamPage> {
+    let mut pages = vec![ParamPage {
+        name: String::new(),
+        params: Vec::new(),
+    }];
+    for (_, param_ptr, group) in params.param_map() {
+        if unsafe { param_ptr.flags() }.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
+            continue;
+        }
+
+        let page_name = group.split('/').next().unwrap_or_default();
+        match pages.iter_mut().find(|page| page.name == page_name) {
+            Some(page) => page.params.push(param_ptr),
+            None => pages.push(ParamPage {
+                name: page_name.to_owned(),
+                params: vec![param_ptr],
+            }),
+        }
+    }
+
+    pages.retain(|page| !page.params.is_empty());
+    pages
+}
+
+/// A snapshot of a parameter's current value, as returned by [`param_values()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamValue {
+    /// The parameter's ID, including any prefixes added by nested parameter objects.
+    pub id: String,
+    /// The parameter's human readable name.
+    pub name: String,
+    /// The parameter's current normalized value in the `[0, 1]` range, without any monophonic
+    /// modulation applied to it.
+    pub normalized_value: f32,
+    /// The parameter's current plain value, without any monophonic modulation applied to it.
+    /// Integer, boolean, and enum parameters are converted to a floating point value.
+    pub plain_value: f32,
+    /// The parameter's current value formatted as a string, including its unit.
+    pub display_value: String,
+}
+
+/// Take a snapshot of all of a [`Params`] object's parameters' current values, in the same order as
+/// in the `param_map()`. This is meant for debugging. Logging these values makes it possible to
+/// reproduce the plugin's exact state from a bug report. The [`Display`] implementation on
+/// [`ParamValue`] prints a single line for each parameter:
+///
+/// ```ignore
+/// for value in param_values(self.params.as_ref()) {
+///     nih_log!("{value}");
+/// }
+/// ```
+pub fn param_values(params: &(impl Params + ?Sized)) -> Vec<ParamValue> {
+    params
+        .param_map()
+        .into_iter()
+        .map(|(id, param_ptr, _)| unsafe {
+            let normalized_value = param_ptr.unmodulated_normalized_value();
+
+            ParamValue {
+                id,
+                name: param_ptr.name().to_owned(),
+                normalized_value,
+                plain_value: param_ptr.unmodulated_plain_value(),
+                display_value: param_ptr.normalized_value_to_string(normalized_value, true),
+            }
+        })
+        .collect()
+}
+
+impl Display for ParamValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} (plain: {}, normalized: {})",
+            self.id, self.name, self.display_value, self.plain_value, self.normalized_value
+        )
+    }
 }
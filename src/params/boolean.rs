@@ -6,7 +6,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use super::internals::ParamPtr;
-use super::{Param, ParamFlags, ParamMut};
+use super::{Param, ParamFlags, ParamMut, ParamValueType};
 
 /// A simple boolean parameter.
 pub struct BoolParam {
@@ -24,6 +24,9 @@ pub struct BoolParam {
     /// `unmodulated_normalized_`. This needs to be stored separately since the normalized values are
     /// clamped, and this value persists after new automation events.
     modulation_offset: AtomicF32,
+    /// Set when the parameter's value changes, and cleared again by
+    /// [`changed_since_last_call()`][Param::changed_since_last_call()].
+    changed: AtomicBool,
     /// The field's default value.
     default: bool,
 
@@ -86,6 +89,10 @@ impl Param for BoolParam {
         ""
     }
 
+    fn value_type(&self) -> ParamValueType {
+        ParamValueType::Generic
+    }
+
     fn poly_modulation_id(&self) -> Option<u32> {
         self.poly_modulation_id
     }
@@ -110,6 +117,11 @@ impl Param for BoolParam {
         self.unmodulated_normalized_value.load(Ordering::Relaxed)
     }
 
+    #[inline]
+    fn changed_since_last_call(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
     #[inline]
     fn default_plain_value(&self) -> Self::Plain {
         self.default
@@ -136,6 +148,20 @@ impl Param for BoolParam {
         }
     }
 
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        _include_unit: bool,
+        string: &mut String,
+    ) {
+        let value = self.preview_plain(normalized);
+        match (value, &self.value_to_string) {
+            (v, Some(f)) => string.push_str(&f(v)),
+            (true, None) => string.push_str("On"),
+            (false, None) => string.push_str("Off"),
+        }
+    }
+
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         let string = string.trim();
         let value = match &self.string_to_value {
@@ -195,6 +221,7 @@ impl ParamMut for BoolParam {
                 .store(unmodulated_value, Ordering::Relaxed);
             self.unmodulated_normalized_value
                 .store(unmodulated_normalized_value, Ordering::Relaxed);
+            self.changed.store(true, Ordering::Relaxed);
             if let Some(f) = &self.value_changed {
                 f(value);
             }
@@ -236,6 +263,7 @@ impl BoolParam {
             unmodulated_value: AtomicBool::new(default),
             unmodulated_normalized_value: AtomicF32::new(if default { 1.0 } else { 0.0 }),
             modulation_offset: AtomicF32::new(0.0),
+            changed: AtomicBool::new(false),
             default,
 
             flags: ParamFlags::default(),
@@ -318,6 +346,18 @@ impl BoolParam {
         self
     }
 
+    /// Mark the parameter as read-only. The host and the user cannot change the parameter's value,
+    /// and the plugin should instead set it from the audio thread using
+    /// [`ProcessContext::set_read_only_parameter()`][set_read_only_parameter]. This is useful for
+    /// exposing meters to the host. This also implies `NON_AUTOMATABLE`.
+    ///
+    /// [set_read_only_parameter]: crate::prelude::ProcessContext::set_read_only_parameter()
+    pub fn read_only(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::READ_ONLY | ParamFlags::NON_AUTOMATABLE);
+        self
+    }
+
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
@@ -332,4 +372,19 @@ impl BoolParam {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Mark the parameter as view state. Use this for parameters that only affect the plugin's
+    /// editor or configuration, like an editor layout toggle, and not the sound it produces. See
+    /// [`ParamFlags::VIEW_STATE`] for more information.
+    pub fn view_state(mut self) -> Self {
+        self.flags.insert(ParamFlags::VIEW_STATE);
+        self
+    }
+
+    /// Mark the parameter as affecting the plugin's latency. See [`ParamFlags::AFFECTS_LATENCY`]
+    /// for more information.
+    pub fn affects_latency(mut self) -> Self {
+        self.flags.insert(ParamFlags::AFFECTS_LATENCY);
+        self
+    }
 }
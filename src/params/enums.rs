@@ -2,11 +2,12 @@
 
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 
 use super::internals::ParamPtr;
 use super::range::IntRange;
-use super::{IntParam, Param, ParamFlags, ParamMut};
+use super::{IntParam, Param, ParamFlags, ParamMut, ParamValueType};
 
 // Re-export the derive macro
 pub use nih_plug_derive::Enum;
@@ -45,6 +46,28 @@ pub use nih_plug_derive::Enum;
 ///
 /// You can safely move from not using IDs to using IDs without breaking patches, but you cannot go
 /// back to not using IDs after that.
+///
+/// Variants that should no longer be selectable, for instance because they have been replaced by a
+/// better alternative, can be marked with the `#[hidden]` attribute. Hidden variants are not shown
+/// to the host or in NIH-plug's generic UIs, but presets that use them can still be loaded and
+/// saved again. This requires all variants to have IDs. Explicit discriminants are allowed, but
+/// they don't affect the variants' indices. Since hidden variants are not part of the parameter's
+/// range, the parameter's normalized value stays at the last selected visible variant while a
+/// hidden variant is active, and that is also the value the host sees. Setting the parameter's
+/// normalized value, for instance through host automation, always selects a visible variant.
+///
+/// ```ignore
+/// #[derive(Enum)]
+/// enum Foo {
+///     #[id = "bar"]
+///     Bar = 1,
+///     #[id = "old-bar"]
+///     #[hidden]
+///     OldBar = 0,
+///     #[id = "baz"]
+///     Baz = 2,
+/// }
+/// ```
 pub trait Enum {
     /// The human readable names for the variants. These are displayed in the GUI or parameter list,
     /// and also used for parsing text back to a parameter value. The length of this slice
@@ -56,6 +79,14 @@ pub trait Enum {
     /// slice needs to be equal to [`variants()`][Self::variants()].
     fn ids() -> Option<&'static [&'static str]>;
 
+    /// The number of hidden variants. These are the last variants in
+    /// [`variants()`][Self::variants()], and they are excluded from the parameter's range. A
+    /// hidden variant can only be selected by loading it from the plugin's state or by setting it
+    /// from the plugin. Hidden variants require [`ids()`][Self::ids()] to be set.
+    fn num_hidden_variants() -> usize {
+        0
+    }
+
     /// Get the variant index (which may not be the same as the discriminator) corresponding to the
     /// active variant. The index needs to correspond to the name in
     /// [`variants()`][Self::variants()].
@@ -92,6 +123,20 @@ pub struct EnumParamInner {
     /// these identifiers are used when saving enum parameter values to the state. Otherwise the
     /// index is used.
     ids: Option<&'static [&'static str]>,
+    /// The number of variants that are not hidden, obtained from [Enum::num_hidden_variants()].
+    /// Only the first `num_visible_variants` variants are part of the integer parameter's range.
+    num_visible_variants: usize,
+
+    /// The index of the active hidden variant, or -1 if the active variant is not hidden. Hidden
+    /// variants fall outside of `inner`'s range, so they need to be stored separately. This takes
+    /// precedence over `inner`'s value.
+    hidden_value: AtomicI32,
+    /// Set when a hidden variant gets selected or deselected, and cleared again by
+    /// [`changed_since_last_call()`][Param::changed_since_last_call()].
+    hidden_value_changed: AtomicBool,
+    /// The callback from [`EnumParam::with_callback()`], if set. `inner` calls this for the visible
+    /// variants, but it needs to be called manually when switching to or from a hidden variant.
+    value_changed: Option<Arc<dyn Fn(i32) + Send + Sync>>,
 }
 
 impl<T: Enum + PartialEq> Display for EnumParam<T> {
@@ -105,7 +150,7 @@ impl Display for EnumParamInner {
         write!(
             f,
             "{}",
-            self.variants[self.inner.modulated_plain_value() as usize]
+            self.variants[self.modulated_plain_value() as usize]
         )
     }
 }
@@ -119,7 +164,7 @@ impl<T: Enum + PartialEq> Debug for EnumParam<T> {
 impl Debug for EnumParamInner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // This uses the above `Display` instance to show the value
-        if self.inner.modulated_plain_value() != self.inner.unmodulated_plain_value() {
+        if self.modulated_plain_value() != self.unmodulated_plain_value() {
             write!(f, "{}: {} (modulated)", &self.name(), &self)
         } else {
             write!(f, "{}: {}", &self.name(), &self)
@@ -141,6 +186,10 @@ impl<T: Enum + PartialEq> Param for EnumParam<T> {
         self.inner.unit()
     }
 
+    fn value_type(&self) -> ParamValueType {
+        self.inner.value_type()
+    }
+
     fn poly_modulation_id(&self) -> Option<u32> {
         self.inner.poly_modulation_id()
     }
@@ -165,6 +214,11 @@ impl<T: Enum + PartialEq> Param for EnumParam<T> {
         self.inner.unmodulated_normalized_value()
     }
 
+    #[inline]
+    fn changed_since_last_call(&self) -> bool {
+        self.inner.changed_since_last_call()
+    }
+
     #[inline]
     fn default_plain_value(&self) -> Self::Plain {
         T::from_index(self.inner.default_plain_value() as usize)
@@ -187,6 +241,16 @@ impl<T: Enum + PartialEq> Param for EnumParam<T> {
             .normalized_value_to_string(normalized, include_unit)
     }
 
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        include_unit: bool,
+        string: &mut String,
+    ) {
+        self.inner
+            .normalized_value_to_string_into(normalized, include_unit, string)
+    }
+
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         self.inner.string_to_normalized_value(string)
     }
@@ -224,15 +288,24 @@ impl Param for EnumParamInner {
         ""
     }
 
+    fn value_type(&self) -> ParamValueType {
+        ParamValueType::Generic
+    }
+
     fn poly_modulation_id(&self) -> Option<u32> {
         self.inner.poly_modulation_id()
     }
 
     #[inline]
     fn modulated_plain_value(&self) -> Self::Plain {
-        self.inner.modulated_plain_value()
+        match self.hidden_value.load(Ordering::Relaxed) {
+            -1 => self.inner.modulated_plain_value(),
+            hidden_value => hidden_value,
+        }
     }
 
+    /// Hidden variants are not part of the parameter's range, so while a hidden variant is active
+    /// this is the normalized value of the last selected visible variant.
     #[inline]
     fn modulated_normalized_value(&self) -> f32 {
         self.inner.modulated_normalized_value()
@@ -245,14 +318,25 @@ impl Param for EnumParamInner {
 
     #[inline]
     fn unmodulated_plain_value(&self) -> Self::Plain {
-        self.inner.unmodulated_plain_value()
+        match self.hidden_value.load(Ordering::Relaxed) {
+            -1 => self.inner.unmodulated_plain_value(),
+            hidden_value => hidden_value,
+        }
     }
 
+    /// See [`modulated_normalized_value()`][Self::modulated_normalized_value()].
     #[inline]
     fn unmodulated_normalized_value(&self) -> f32 {
         self.inner.unmodulated_normalized_value()
     }
 
+    #[inline]
+    fn changed_since_last_call(&self) -> bool {
+        // Both flags need to be cleared
+        let hidden_value_changed = self.hidden_value_changed.swap(false, Ordering::Relaxed);
+        self.inner.changed_since_last_call() || hidden_value_changed
+    }
+
     fn step_count(&self) -> Option<usize> {
         Some(self.len() - 1)
     }
@@ -270,9 +354,19 @@ impl Param for EnumParamInner {
         self.variants[index as usize].to_string()
     }
 
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        _include_unit: bool,
+        string: &mut String,
+    ) {
+        let index = self.preview_plain(normalized);
+        string.push_str(self.variants[index as usize]);
+    }
+
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         let string = string.trim();
-        self.variants
+        self.variants[..self.num_visible_variants]
             .iter()
             .position(|variant| variant == &string)
             .map(|idx| self.preview_normalized(idx as i32))
@@ -317,11 +411,39 @@ impl<T: Enum + PartialEq> ParamMut for EnumParam<T> {
 
 impl ParamMut for EnumParamInner {
     fn set_plain_value(&self, plain: Self::Plain) -> bool {
-        self.inner.set_plain_value(plain)
+        if plain >= self.num_visible_variants as i32 && (plain as usize) < self.variants.len() {
+            let old_hidden_value = self.hidden_value.swap(plain, Ordering::Relaxed);
+            if plain == old_hidden_value {
+                return false;
+            }
+
+            self.hidden_value_changed.store(true, Ordering::Relaxed);
+            if let Some(f) = &self.value_changed {
+                f(plain);
+            }
+
+            true
+        } else {
+            let was_hidden = self.hidden_value.swap(-1, Ordering::Relaxed) != -1;
+            let inner_changed = self.inner.set_plain_value(plain);
+
+            // If the integer parameter already had this value, then it won't have notified anyone
+            // about switching away from the hidden variant
+            if was_hidden && !inner_changed {
+                self.hidden_value_changed.store(true, Ordering::Relaxed);
+                if let Some(f) = &self.value_changed {
+                    f(self.inner.unmodulated_plain_value());
+                }
+            }
+
+            was_hidden || inner_changed
+        }
     }
 
     fn set_normalized_value(&self, normalized: f32) -> bool {
-        self.inner.set_normalized_value(normalized)
+        // This goes through `set_plain_value()` so selecting a visible variant while a hidden
+        // variant is active clears the hidden variant
+        self.set_plain_value(self.inner.preview_plain(normalized))
     }
 
     fn modulate_value(&self, modulation_offset: f32) -> bool {
@@ -339,19 +461,35 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
     pub fn new(name: impl Into<String>, default: T) -> Self {
         let variants = T::variants();
         let ids = T::ids();
+        let num_visible_variants = variants.len() - T::num_hidden_variants();
+        nih_debug_assert!(
+            T::num_hidden_variants() == 0 || ids.is_some(),
+            "Hidden enum variants require IDs"
+        );
+
+        let default = T::to_index(default) as i32;
+        nih_debug_assert!(
+            default < num_visible_variants as i32,
+            "The default value for an enum parameter cannot be a hidden variant"
+        );
 
         Self {
             inner: EnumParamInner {
                 inner: IntParam::new(
                     name,
-                    T::to_index(default) as i32,
+                    default.min(num_visible_variants as i32 - 1),
                     IntRange::Linear {
                         min: 0,
-                        max: variants.len() as i32 - 1,
+                        max: num_visible_variants as i32 - 1,
                     },
                 ),
                 variants,
                 ids,
+                num_visible_variants,
+
+                hidden_value: AtomicI32::new(-1),
+                hidden_value_changed: AtomicBool::new(false),
+                value_changed: None,
             },
             _marker: PhantomData,
         }
@@ -385,9 +523,10 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
     /// multiple times in rapid succession, and it can be run from both the GUI and the audio
     /// thread.
     pub fn with_callback(mut self, callback: Arc<dyn Fn(T) + Send + Sync>) -> Self {
-        self.inner.inner = self.inner.inner.with_callback(Arc::new(move |value| {
-            callback(T::from_index(value as usize))
-        }));
+        let callback: Arc<dyn Fn(i32) + Send + Sync> =
+            Arc::new(move |value| callback(T::from_index(value as usize)));
+        self.inner.inner = self.inner.inner.with_callback(callback.clone());
+        self.inner.value_changed = Some(callback);
         self
     }
 
@@ -399,6 +538,17 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
         self
     }
 
+    /// Mark the parameter as read-only. The host and the user cannot change the parameter's value,
+    /// and the plugin should instead set it from the audio thread using
+    /// [`ProcessContext::set_read_only_parameter()`][set_read_only_parameter]. This is useful for
+    /// exposing meters to the host. This also implies `NON_AUTOMATABLE`.
+    ///
+    /// [set_read_only_parameter]: crate::prelude::ProcessContext::set_read_only_parameter()
+    pub fn read_only(mut self) -> Self {
+        self.inner.inner = self.inner.inner.read_only();
+        self
+    }
+
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
@@ -413,13 +563,28 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
         self.inner.inner = self.inner.inner.hide_in_generic_ui();
         self
     }
+
+    /// Mark the parameter as view state. Use this for parameters that only affect the plugin's
+    /// editor or configuration, like an editor layout toggle, and not the sound it produces. See
+    /// [`ParamFlags::VIEW_STATE`] for more information.
+    pub fn view_state(mut self) -> Self {
+        self.inner.inner = self.inner.inner.view_state();
+        self
+    }
+
+    /// Mark the parameter as affecting the plugin's latency. See [`ParamFlags::AFFECTS_LATENCY`]
+    /// for more information.
+    pub fn affects_latency(mut self) -> Self {
+        self.inner.inner = self.inner.inner.affects_latency();
+        self
+    }
 }
 
 impl EnumParamInner {
-    /// Get the number of variants for this enum.
+    /// Get the number of variants for this enum. This excludes hidden variants.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        self.variants.len()
+        self.num_visible_variants
     }
 
     /// Get the stable ID for the parameter's current value according to
@@ -447,3 +612,66 @@ impl EnumParamInner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum WithHidden {
+        A,
+        B,
+        Legacy,
+    }
+
+    impl Enum for WithHidden {
+        fn variants() -> &'static [&'static str] {
+            &["A", "B", "Legacy"]
+        }
+
+        fn ids() -> Option<&'static [&'static str]> {
+            Some(&["a", "b", "legacy"])
+        }
+
+        fn num_hidden_variants() -> usize {
+            1
+        }
+
+        fn to_index(self) -> usize {
+            self as usize
+        }
+
+        fn from_index(index: usize) -> Self {
+            match index {
+                1 => Self::B,
+                2 => Self::Legacy,
+                _ => Self::A,
+            }
+        }
+    }
+
+    #[test]
+    fn set_normalized_value_while_hidden() {
+        let param = EnumParam::new("param", WithHidden::B);
+        assert!(param.inner.set_from_id("legacy"));
+        assert!(param.changed_since_last_call());
+        assert_eq!(param.value(), WithHidden::Legacy);
+
+        // The normalized value still belongs to the last visible variant
+        assert_eq!(param.unmodulated_normalized_value(), 1.0);
+        assert_eq!(param.modulated_normalized_value(), 1.0);
+        assert_eq!(param.inner.unmodulated_normalized_value(), 1.0);
+
+        // Setting that same normalized value selects the visible variant again
+        assert!(param.inner.set_normalized_value(1.0));
+        assert!(param.changed_since_last_call());
+        assert_eq!(param.value(), WithHidden::B);
+        assert_eq!(param.inner.unmodulated_plain_id(), Some("b"));
+        assert!(!param.inner.set_normalized_value(1.0));
+
+        assert!(param.inner.set_from_id("legacy"));
+        assert!(param.inner.set_normalized_value(0.0));
+        assert_eq!(param.value(), WithHidden::A);
+        assert_eq!(param.unmodulated_normalized_value(), 0.0);
+    }
+}
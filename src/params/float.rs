@@ -1,14 +1,15 @@
 //! Continuous (or discrete, with a step size) floating point parameters.
 
 use atomic_float::AtomicF32;
-use std::fmt::{Debug, Display};
-use std::sync::atomic::Ordering;
+use parking_lot::Mutex;
+use std::fmt::{Debug, Display, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use super::internals::ParamPtr;
+use super::internals::{ParamPtr, ValueToString};
 use super::range::FloatRange;
 use super::smoothing::{Smoother, SmoothingStyle};
-use super::{Param, ParamFlags, ParamMut};
+use super::{Param, ParamFlags, ParamMut, ParamValueType};
 
 /// A floating point parameter that's stored unnormalized. The range is used for the normalization
 /// process.
@@ -27,6 +28,9 @@ pub struct FloatParam {
     /// `unmodulated_normalized_`. This needs to be stored separately since the normalized values are
     /// clamped, and this value persists after new automation events.
     modulation_offset: AtomicF32,
+    /// Set when the parameter's value changes, and cleared again by
+    /// [`changed_since_last_call()`][Param::changed_since_last_call()].
+    changed: AtomicBool,
     /// The field's default plain, unnormalized value.
     default: f32,
     /// An optional smoother that will automatically interpolate between the new automation values
@@ -56,14 +60,23 @@ pub struct FloatParam {
     /// The parameter value's unit, added after [`value_to_string`][Self::value_to_string] if that
     /// is set. NIH-plug will not automatically add a space before the unit.
     unit: &'static str,
+    /// The kind of quantity this parameter's value represents. See [`ParamValueType`].
+    value_type: ParamValueType,
     /// If this parameter has been marked as polyphonically modulatable, then this will be a unique
     /// integer identifying the parameter. Because this value is determined by the plugin itself,
     /// the plugin can easily map
     /// [`NoteEvent::PolyModulation`][crate::prelude::NoteEvent::PolyModulation] events to the
     /// correct parameter by pattern matching on a constant.
     poly_modulation_id: Option<u32>,
-    /// Optional custom conversion function from a plain **unnormalized** value to a string.
-    value_to_string: Option<Arc<dyn Fn(f32) -> String + Send + Sync>>,
+    /// Optional custom conversion function from a plain **unnormalized** value to a string. The
+    /// function either returns a new string, or it appends the formatted value to the string
+    /// passed to it when set through
+    /// [`with_value_to_string_no_alloc()`][Self::with_value_to_string_no_alloc()].
+    value_to_string: Option<ValueToString<f32>>,
+    /// A reusable buffer for formatting the parameter with
+    /// [`value_to_string`][Self::value_to_string] through the parameter's `Display`
+    /// implementation, so the host repeatedly polling the parameter's value does not allocate.
+    value_to_string_scratch: Mutex<String>,
     /// Optional custom conversion function from a string to a plain **unnormalized** value. If the
     /// string cannot be parsed, then this should return a `None`. If this happens while the
     /// parameter is being updated then the update will be canceled.
@@ -76,7 +89,23 @@ pub struct FloatParam {
 impl Display for FloatParam {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match (&self.value_to_string, &self.step_size) {
-            (Some(func), _) => write!(f, "{}{}", func(self.value()), self.unit),
+            (Some(func), _) => {
+                // Functions set through `with_value_to_string()` already return a new string.
+                // Otherwise the scratch buffer is used, unless another thread is currently
+                // formatting this parameter.
+                let scratch = match func {
+                    ValueToString::NoAlloc(_) => self.value_to_string_scratch.try_lock(),
+                    ValueToString::Alloc(_) => None,
+                };
+                match scratch {
+                    Some(mut scratch) => {
+                        scratch.clear();
+                        func.write(self.value(), &mut scratch);
+                        write!(f, "{}{}", scratch, self.unit)
+                    }
+                    None => write!(f, "{}{}", func.format(self.value()), self.unit),
+                }
+            }
             (None, Some(step_size)) => {
                 let num_digits = decimals_from_step_size(*step_size);
                 write!(f, "{:.num_digits$}{}", self.value(), self.unit)
@@ -111,6 +140,10 @@ impl Param for FloatParam {
         self.unit
     }
 
+    fn value_type(&self) -> ParamValueType {
+        self.value_type
+    }
+
     fn poly_modulation_id(&self) -> Option<u32> {
         self.poly_modulation_id
     }
@@ -135,6 +168,11 @@ impl Param for FloatParam {
         self.unmodulated_normalized_value.load(Ordering::Relaxed)
     }
 
+    #[inline]
+    fn changed_since_last_call(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
     #[inline]
     fn default_plain_value(&self) -> Self::Plain {
         self.default
@@ -155,8 +193,14 @@ impl Param for FloatParam {
     fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
         let value = self.preview_plain(normalized);
         match (&self.value_to_string, &self.step_size, include_unit) {
-            (Some(f), _, true) => format!("{}{}", f(value), self.unit),
-            (Some(f), _, false) => f(value),
+            (Some(f), _, include_unit) => {
+                let mut string = f.format(value);
+                if include_unit {
+                    string.push_str(self.unit);
+                }
+
+                string
+            }
             (None, Some(step_size), true) => {
                 let num_digits = decimals_from_step_size(*step_size);
                 format!("{:.num_digits$}{}", value, self.unit)
@@ -170,6 +214,26 @@ impl Param for FloatParam {
         }
     }
 
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        include_unit: bool,
+        string: &mut String,
+    ) {
+        let value = self.preview_plain(normalized);
+        match (&self.value_to_string, &self.step_size) {
+            (Some(f), _) => f.write(value, string),
+            (None, Some(step_size)) => {
+                let num_digits = decimals_from_step_size(*step_size);
+                write!(string, "{value:.num_digits$}").unwrap();
+            }
+            (None, None) => write!(string, "{value}").unwrap(),
+        }
+        if include_unit {
+            string.push_str(self.unit);
+        }
+    }
+
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         let value = match &self.string_to_value {
             Some(f) => f(string.trim()),
@@ -229,6 +293,7 @@ impl ParamMut for FloatParam {
                 .store(unmodulated_value, Ordering::Relaxed);
             self.unmodulated_normalized_value
                 .store(unmodulated_normalized_value, Ordering::Relaxed);
+            self.changed.store(true, Ordering::Relaxed);
             if let Some(f) = &self.value_changed {
                 f(value);
             }
@@ -277,6 +342,7 @@ impl FloatParam {
             unmodulated_value: AtomicF32::new(default),
             unmodulated_normalized_value: AtomicF32::new(range.normalize(default)),
             modulation_offset: AtomicF32::new(0.0),
+            changed: AtomicBool::new(false),
             default,
             smoothed: Smoother::none(),
 
@@ -287,8 +353,10 @@ impl FloatParam {
             step_size: None,
             name: name.into(),
             unit: "",
+            value_type: ParamValueType::Generic,
             poly_modulation_id: None,
             value_to_string: None,
+            value_to_string_scratch: Mutex::new(String::new()),
             string_to_value: None,
         }
     }
@@ -363,6 +431,13 @@ impl FloatParam {
         self
     }
 
+    /// Mark what kind of quantity this parameter's value represents, like a gain or a frequency.
+    /// See [`ParamValueType`] for more information.
+    pub fn with_value_type(mut self, value_type: ParamValueType) -> Self {
+        self.value_type = value_type;
+        self
+    }
+
     /// Set the distance between steps of a [`FloatParam`]. Mostly useful for quantizing GUI input. If
     /// this is set and a [`value_to_string`][Self::with_value_to_string()] function is not set,
     /// then this is also used when formatting the parameter. This must be a positive, nonzero
@@ -378,7 +453,20 @@ impl FloatParam {
         mut self,
         callback: Arc<dyn Fn(f32) -> String + Send + Sync>,
     ) -> Self {
-        self.value_to_string = Some(callback);
+        self.value_to_string = Some(ValueToString::Alloc(callback));
+        self
+    }
+
+    /// The same as [`with_value_to_string()`][Self::with_value_to_string()], but the function
+    /// appends the formatted value to the string passed to it instead of returning a newly
+    /// allocated string. The parameter reuses the same buffer when formatting its value, so this
+    /// avoids allocations when the host frequently polls the parameter's value for display
+    /// purposes. This works well with [`std::fmt::Write`] and the `write!()` macro.
+    pub fn with_value_to_string_no_alloc(
+        mut self,
+        callback: Arc<dyn Fn(f32, &mut String) + Send + Sync>,
+    ) -> Self {
+        self.value_to_string = Some(ValueToString::NoAlloc(callback));
         self
     }
 
@@ -404,6 +492,18 @@ impl FloatParam {
         self
     }
 
+    /// Mark the parameter as read-only. The host and the user cannot change the parameter's value,
+    /// and the plugin should instead set it from the audio thread using
+    /// [`ProcessContext::set_read_only_parameter()`][set_read_only_parameter]. This is useful for
+    /// exposing meters to the host. This also implies `NON_AUTOMATABLE`.
+    ///
+    /// [set_read_only_parameter]: crate::prelude::ProcessContext::set_read_only_parameter()
+    pub fn read_only(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::READ_ONLY | ParamFlags::NON_AUTOMATABLE);
+        self
+    }
+
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
@@ -418,6 +518,30 @@ impl FloatParam {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Mark the parameter as bipolar. This is a display-only hint that causes NIH-plug's slider
+    /// widgets to fill the bar from the center of the parameter's range instead of from the left.
+    /// Useful for parameters like panning or pitch shifting controls where the center of the range
+    /// is the neutral value, even if the parameter's default value is not.
+    pub fn bipolar(mut self) -> Self {
+        self.flags.insert(ParamFlags::BIPOLAR);
+        self
+    }
+
+    /// Mark the parameter as view state. Use this for parameters that only affect the plugin's
+    /// editor or configuration, like an editor layout toggle, and not the sound it produces. See
+    /// [`ParamFlags::VIEW_STATE`] for more information.
+    pub fn view_state(mut self) -> Self {
+        self.flags.insert(ParamFlags::VIEW_STATE);
+        self
+    }
+
+    /// Mark the parameter as affecting the plugin's latency. See [`ParamFlags::AFFECTS_LATENCY`]
+    /// for more information.
+    pub fn affects_latency(mut self) -> Self {
+        self.flags.insert(ParamFlags::AFFECTS_LATENCY);
+        self
+    }
 }
 
 /// Calculate how many decimals to round to when displaying a floating point value with a specific
@@ -437,3 +561,74 @@ fn decimals_from_step_size(step_size: f32) -> usize {
 
     num_digits as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::formatters;
+
+    /// Check that formatting a parameter with `value_to_string` and `value_to_string_no_alloc`
+    /// results in the exact same strings.
+    fn assert_formatters_match(
+        value_to_string: Arc<dyn Fn(f32) -> String + Send + Sync>,
+        value_to_string_no_alloc: Arc<dyn Fn(f32, &mut String) + Send + Sync>,
+    ) {
+        let range = FloatRange::Linear {
+            min: 0.01,
+            max: 20.0,
+        };
+        let param = FloatParam::new("Param", 1.0, range)
+            .with_unit(" u")
+            .with_value_to_string(value_to_string);
+        let param_no_alloc = FloatParam::new("Param", 1.0, range)
+            .with_unit(" u")
+            .with_value_to_string_no_alloc(value_to_string_no_alloc);
+
+        let mut string = String::new();
+        for normalized in [0.0, 0.001, 0.04, 0.25, 0.5, 0.77, 1.0] {
+            for include_unit in [true, false] {
+                let expected = param.normalized_value_to_string(normalized, include_unit);
+                assert_eq!(
+                    expected,
+                    param_no_alloc.normalized_value_to_string(normalized, include_unit)
+                );
+
+                // This is what the wrappers use
+                for param in [&param, &param_no_alloc] {
+                    string.clear();
+                    param.normalized_value_to_string_into(normalized, include_unit, &mut string);
+                    assert_eq!(string, expected);
+                }
+            }
+        }
+
+        // The `Display` implementation reuses a scratch buffer, so this is checked twice
+        for _ in 0..2 {
+            assert_eq!(param.to_string(), param_no_alloc.to_string());
+        }
+    }
+
+    #[test]
+    fn value_to_string_no_alloc_percentage() {
+        assert_formatters_match(
+            formatters::v2s_f32_percentage(2),
+            Arc::new(|value, string| write!(string, "{:.2}", value * 100.0).unwrap()),
+        );
+    }
+
+    #[test]
+    fn value_to_string_no_alloc_compression_ratio() {
+        assert_formatters_match(
+            formatters::v2s_compression_ratio(1),
+            Arc::new(|value, string| {
+                if value >= 1.0 {
+                    write!(string, "{value:.1}:1").unwrap()
+                } else {
+                    write!(string, "1:{:.1}", value.recip()).unwrap()
+                }
+            }),
+        );
+    }
+}
@@ -1,14 +1,15 @@
 //! Stepped integer parameters.
 
 use atomic_float::AtomicF32;
-use std::fmt::{Debug, Display};
-use std::sync::atomic::{AtomicI32, Ordering};
+use parking_lot::Mutex;
+use std::fmt::{Debug, Display, Write};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 
-use super::internals::ParamPtr;
+use super::internals::{ParamPtr, ValueToString};
 use super::range::IntRange;
 use super::smoothing::{Smoother, SmoothingStyle};
-use super::{Param, ParamFlags, ParamMut};
+use super::{Param, ParamFlags, ParamMut, ParamValueType};
 
 /// A discrete integer parameter that's stored unnormalized. The range is used for the normalization
 /// process.
@@ -27,6 +28,9 @@ pub struct IntParam {
     /// `unmodulated_normalized_`. This needs to be stored separately since the normalized values are
     /// clamped, and this value persists after new automation events.
     modulation_offset: AtomicF32,
+    /// Set when the parameter's value changes, and cleared again by
+    /// [`changed_since_last_call()`][Param::changed_since_last_call()].
+    changed: AtomicBool,
     /// The field's default plain, unnormalized value.
     default: i32,
     /// An optional smoother that will automatically interpolate between the new automation values
@@ -52,14 +56,23 @@ pub struct IntParam {
     /// The parameter value's unit, added after `value_to_string` if that is set. NIH-plug will not
     /// automatically add a space before the unit.
     unit: &'static str,
+    /// The kind of quantity this parameter's value represents. See [`ParamValueType`].
+    value_type: ParamValueType,
     /// If this parameter has been marked as polyphonically modulatable, then this will be a unique
     /// integer identifying the parameter. Because this value is determined by the plugin itself,
     /// the plugin can easily map
     /// [`NoteEvent::PolyModulation`][crate::prelude::NoteEvent::PolyModulation] events to the
     /// correct parameter by pattern matching on a constant.
     poly_modulation_id: Option<u32>,
-    /// Optional custom conversion function from a plain **unnormalized** value to a string.
-    value_to_string: Option<Arc<dyn Fn(i32) -> String + Send + Sync>>,
+    /// Optional custom conversion function from a plain **unnormalized** value to a string. The
+    /// function either returns a new string, or it appends the formatted value to the string
+    /// passed to it when set through
+    /// [`with_value_to_string_no_alloc()`][Self::with_value_to_string_no_alloc()].
+    value_to_string: Option<ValueToString<i32>>,
+    /// A reusable buffer for formatting the parameter with
+    /// [`value_to_string`][Self::value_to_string] through the parameter's `Display`
+    /// implementation, so the host repeatedly polling the parameter's value does not allocate.
+    value_to_string_scratch: Mutex<String>,
     /// Optional custom conversion function from a string to a plain **unnormalized** value. If the
     /// string cannot be parsed, then this should return a `None`. If this happens while the
     /// parameter is being updated then the update will be canceled.
@@ -72,7 +85,23 @@ pub struct IntParam {
 impl Display for IntParam {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.value_to_string {
-            Some(func) => write!(f, "{}{}", func(self.value()), self.unit),
+            Some(func) => {
+                // Functions set through `with_value_to_string()` already return a new string.
+                // Otherwise the scratch buffer is used, unless another thread is currently
+                // formatting this parameter.
+                let scratch = match func {
+                    ValueToString::NoAlloc(_) => self.value_to_string_scratch.try_lock(),
+                    ValueToString::Alloc(_) => None,
+                };
+                match scratch {
+                    Some(mut scratch) => {
+                        scratch.clear();
+                        func.write(self.value(), &mut scratch);
+                        write!(f, "{}{}", scratch, self.unit)
+                    }
+                    None => write!(f, "{}{}", func.format(self.value()), self.unit),
+                }
+            }
             _ => write!(f, "{}{}", self.value(), self.unit),
         }
     }
@@ -103,6 +132,10 @@ impl Param for IntParam {
         self.unit
     }
 
+    fn value_type(&self) -> ParamValueType {
+        self.value_type
+    }
+
     fn poly_modulation_id(&self) -> Option<u32> {
         self.poly_modulation_id
     }
@@ -127,6 +160,11 @@ impl Param for IntParam {
         self.unmodulated_normalized_value.load(Ordering::Relaxed)
     }
 
+    #[inline]
+    fn changed_since_last_call(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
     #[inline]
     fn default_plain_value(&self) -> Self::Plain {
         self.default
@@ -147,13 +185,35 @@ impl Param for IntParam {
     fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
         let value = self.preview_plain(normalized);
         match (&self.value_to_string, include_unit) {
-            (Some(f), true) => format!("{}{}", f(value), self.unit),
-            (Some(f), false) => f(value),
+            (Some(f), include_unit) => {
+                let mut string = f.format(value);
+                if include_unit {
+                    string.push_str(self.unit);
+                }
+
+                string
+            }
             (None, true) => format!("{}{}", value, self.unit),
             (None, false) => format!("{value}"),
         }
     }
 
+    fn normalized_value_to_string_into(
+        &self,
+        normalized: f32,
+        include_unit: bool,
+        string: &mut String,
+    ) {
+        let value = self.preview_plain(normalized);
+        match &self.value_to_string {
+            Some(f) => f.write(value, string),
+            None => write!(string, "{value}").unwrap(),
+        }
+        if include_unit {
+            string.push_str(self.unit);
+        }
+    }
+
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         let value = match &self.string_to_value {
             Some(f) => f(string.trim()),
@@ -209,6 +269,7 @@ impl ParamMut for IntParam {
                 .store(unmodulated_value, Ordering::Relaxed);
             self.unmodulated_normalized_value
                 .store(unmodulated_normalized_value, Ordering::Relaxed);
+            self.changed.store(true, Ordering::Relaxed);
             if let Some(f) = &self.value_changed {
                 f(value);
             }
@@ -257,6 +318,7 @@ impl IntParam {
             unmodulated_value: AtomicI32::new(default),
             unmodulated_normalized_value: AtomicF32::new(range.normalize(default)),
             modulation_offset: AtomicF32::new(0.0),
+            changed: AtomicBool::new(false),
             default,
             smoothed: Smoother::none(),
 
@@ -266,8 +328,10 @@ impl IntParam {
             range,
             name: name.into(),
             unit: "",
+            value_type: ParamValueType::Generic,
             poly_modulation_id: None,
             value_to_string: None,
+            value_to_string_scratch: Mutex::new(String::new()),
             string_to_value: None,
         }
     }
@@ -339,13 +403,33 @@ impl IntParam {
         self
     }
 
+    /// Mark what kind of quantity this parameter's value represents, like a gain or a frequency.
+    /// See [`ParamValueType`] for more information.
+    pub fn with_value_type(mut self, value_type: ParamValueType) -> Self {
+        self.value_type = value_type;
+        self
+    }
+
     /// Use a custom conversion function to convert the plain, unnormalized value to a
     /// string.
     pub fn with_value_to_string(
         mut self,
         callback: Arc<dyn Fn(i32) -> String + Send + Sync>,
     ) -> Self {
-        self.value_to_string = Some(callback);
+        self.value_to_string = Some(ValueToString::Alloc(callback));
+        self
+    }
+
+    /// The same as [`with_value_to_string()`][Self::with_value_to_string()], but the function
+    /// appends the formatted value to the string passed to it instead of returning a newly
+    /// allocated string. The parameter reuses the same buffer when formatting its value, so this
+    /// avoids allocations when the host frequently polls the parameter's value for display
+    /// purposes. This works well with [`std::fmt::Write`] and the `write!()` macro.
+    pub fn with_value_to_string_no_alloc(
+        mut self,
+        callback: Arc<dyn Fn(i32, &mut String) + Send + Sync>,
+    ) -> Self {
+        self.value_to_string = Some(ValueToString::NoAlloc(callback));
         self
     }
 
@@ -365,6 +449,81 @@ impl IntParam {
         self
     }
 
+    /// Display every value in the parameter's range using a fixed label, and parse those labels
+    /// back to values. `labels[0]` is used for the range's minimum value, `labels[1]` for the value
+    /// after that, and so on. This is useful for parameters that store a compact index but display
+    /// some derived value, like an oversampling factor that is shown as the oversampling amount.
+    /// Since the host queries the parameter's strings, automation lanes and the host's generic UI
+    /// also use these labels. This replaces any existing
+    /// [`with_value_to_string()`][Self::with_value_to_string()] and
+    /// [`with_string_to_value()`][Self::with_string_to_value()] functions.
+    ///
+    /// When parsing, the labels are compared case insensitively, and the parameter's unit may
+    /// follow the label. The number of labels must match the number of values in the parameter's
+    /// range. If it doesn't, then any excess labels are ignored and values without a label are
+    /// displayed and parsed as plain numbers.
+    pub fn with_display_values(mut self, labels: &[&str]) -> Self {
+        let num_values = self.range.step_count() + 1;
+        nih_debug_assert_eq!(
+            labels.len(),
+            num_values,
+            "The number of display values needs to match the number of values in the parameter's \
+             range"
+        );
+
+        // Both the reversed and the regular ranges contain the same values
+        let min = self.range.unnormalize(0.0).min(self.range.unnormalize(1.0));
+        let labels: Arc<[String]> = labels
+            .iter()
+            .take(num_values)
+            .map(|label| label.to_string())
+            .collect();
+
+        let value_labels = labels.clone();
+        self.value_to_string = Some(ValueToString::NoAlloc(Arc::new(
+            move |value, string: &mut String| match value_labels.get((value - min) as usize) {
+                Some(label) => string.push_str(label),
+                None => write!(string, "{value}").unwrap(),
+            },
+        )));
+        self.string_to_value = Some(Arc::new(move |string| {
+            let string = string.trim();
+            if let Some(idx) = labels
+                .iter()
+                .position(|label| label.eq_ignore_ascii_case(string))
+            {
+                return Some(min + idx as i32);
+            }
+
+            // Otherwise the string may contain a unit after the label. The longest matching label
+            // is used, and the label cannot be followed by another digit so `16x` doesn't match
+            // `1`.
+            labels
+                .iter()
+                .enumerate()
+                .filter(|(_, label)| {
+                    string
+                        .get(..label.len())
+                        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(label))
+                        && !string[label.len()..]
+                            .trim_start()
+                            .starts_with(|c: char| c.is_ascii_digit())
+                })
+                .max_by_key(|(_, label)| label.len())
+                .map(|(idx, _)| min + idx as i32)
+                .or_else(|| {
+                    // Values without a label can only be entered as plain numbers
+                    let value: i32 = string
+                        .trim_end_matches(|c: char| !c.is_ascii_digit())
+                        .parse()
+                        .ok()?;
+                    let idx = value.checked_sub(min)? as usize;
+                    (labels.len()..num_values).contains(&idx).then_some(value)
+                })
+        }));
+        self
+    }
+
     /// Mark the parameter as non-automatable. This means that the parameter cannot be changed from
     /// an automation lane. The parameter can however still be manually changed by the user from
     /// either the plugin's own GUI or from the host's generic UI.
@@ -373,6 +532,18 @@ impl IntParam {
         self
     }
 
+    /// Mark the parameter as read-only. The host and the user cannot change the parameter's value,
+    /// and the plugin should instead set it from the audio thread using
+    /// [`ProcessContext::set_read_only_parameter()`][set_read_only_parameter]. This is useful for
+    /// exposing meters to the host. This also implies `NON_AUTOMATABLE`.
+    ///
+    /// [set_read_only_parameter]: crate::prelude::ProcessContext::set_read_only_parameter()
+    pub fn read_only(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::READ_ONLY | ParamFlags::NON_AUTOMATABLE);
+        self
+    }
+
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
@@ -387,4 +558,124 @@ impl IntParam {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Mark the parameter as bipolar. This is a display-only hint that causes NIH-plug's slider
+    /// widgets to fill the bar from the center of the parameter's range instead of from the left.
+    /// Useful for parameters like panning or pitch shifting controls where the center of the range
+    /// is the neutral value, even if the parameter's default value is not.
+    pub fn bipolar(mut self) -> Self {
+        self.flags.insert(ParamFlags::BIPOLAR);
+        self
+    }
+
+    /// Mark the parameter as view state. Use this for parameters that only affect the plugin's
+    /// editor or configuration, like an editor layout toggle, and not the sound it produces. See
+    /// [`ParamFlags::VIEW_STATE`] for more information.
+    pub fn view_state(mut self) -> Self {
+        self.flags.insert(ParamFlags::VIEW_STATE);
+        self
+    }
+
+    /// Mark the parameter as affecting the plugin's latency. See [`ParamFlags::AFFECTS_LATENCY`]
+    /// for more information.
+    pub fn affects_latency(mut self) -> Self {
+        self.flags.insert(ParamFlags::AFFECTS_LATENCY);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::formatters;
+
+    #[test]
+    fn value_to_string_no_alloc_note_formatter() {
+        let range = IntRange::Linear { min: 0, max: 127 };
+        let param = IntParam::new("Param", 69, range)
+            .with_value_to_string(formatters::v2s_i32_note_formatter());
+        let param_no_alloc = IntParam::new("Param", 69, range).with_value_to_string_no_alloc(
+            Arc::new(|value, string| {
+                let note_name = crate::util::NOTES[value.rem_euclid(12) as usize];
+                let octave = (value / 12) - 1;
+                write!(string, "{note_name}{octave}").unwrap()
+            }),
+        );
+
+        let mut string = String::new();
+        for value in [0, 11, 12, 60, 69, 127] {
+            let normalized = param.preview_normalized(value);
+            for include_unit in [true, false] {
+                let expected = param.normalized_value_to_string(normalized, include_unit);
+                assert_eq!(
+                    expected,
+                    param_no_alloc.normalized_value_to_string(normalized, include_unit)
+                );
+
+                // This is what the wrappers use
+                for param in [&param, &param_no_alloc] {
+                    string.clear();
+                    param.normalized_value_to_string_into(normalized, include_unit, &mut string);
+                    assert_eq!(string, expected);
+                }
+            }
+        }
+
+        // The `Display` implementation reuses a scratch buffer, so this is checked twice
+        for _ in 0..2 {
+            assert_eq!(param.to_string(), param_no_alloc.to_string());
+        }
+    }
+
+    #[test]
+    fn display_values() {
+        let param = IntParam::new("Oversampling", 1, IntRange::Linear { min: 0, max: 4 })
+            .with_unit("x")
+            .with_display_values(&["1", "2", "4", "8", "16"]);
+
+        assert_eq!(param.to_string(), "2x");
+        assert_eq!(
+            param.normalized_value_to_string(param.preview_normalized(4), false),
+            "16"
+        );
+
+        for (string, value) in [("1", 0), ("16", 4), ("16x", 4), ("8 x", 3), (" 4 ", 2)] {
+            assert_eq!(
+                param.string_to_normalized_value(string),
+                Some(param.preview_normalized(value)),
+                "{string}"
+            );
+        }
+        assert_eq!(param.string_to_normalized_value("3"), None);
+        assert_eq!(param.string_to_normalized_value("32x"), None);
+    }
+
+    /// Mismatched labels are a debug assertion failure, but they should still behave sensibly in
+    /// release builds.
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn display_values_count_mismatch() {
+        let too_few = IntParam::new("Oversampling", 4, IntRange::Linear { min: 0, max: 4 })
+            .with_unit("x")
+            .with_display_values(&["1", "2", "4"]);
+
+        assert_eq!(too_few.to_string(), "4x");
+        assert_eq!(too_few.normalized_value_to_string(0.0, true), "1x");
+        for (string, value) in [("1", 0), ("4x", 2), ("3", 3), ("4", 2), (" 4 x", 2)] {
+            assert_eq!(
+                too_few.string_to_normalized_value(string),
+                Some(too_few.preview_normalized(value)),
+                "{string}"
+            );
+        }
+        assert_eq!(too_few.string_to_normalized_value("0"), None);
+        assert_eq!(too_few.string_to_normalized_value("5"), None);
+
+        let too_many = IntParam::new("Oversampling", 1, IntRange::Linear { min: 0, max: 1 })
+            .with_display_values(&["1", "2", "4"]);
+        assert_eq!(too_many.normalized_value_to_string(1.0, true), "2");
+        assert_eq!(too_many.string_to_normalized_value("4"), None);
+    }
 }
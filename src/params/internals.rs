@@ -1,6 +1,8 @@
 //! Implementation details for the parameter management.
 
-use super::{Param, ParamFlags, ParamMut};
+use std::sync::Arc;
+
+use super::{Param, ParamFlags, ParamMut, ParamValueType};
 
 /// Internal pointers to parameters. This is an implementation detail used by the wrappers for type
 /// erasure.
@@ -63,6 +65,7 @@ macro_rules! param_ptr_forward(
 impl ParamPtr {
     param_ptr_forward!(pub unsafe fn name(&self) -> &str);
     param_ptr_forward!(pub unsafe fn unit(&self) -> &'static str);
+    param_ptr_forward!(pub unsafe fn value_type(&self) -> ParamValueType);
     param_ptr_forward!(pub unsafe fn poly_modulation_id(&self) -> Option<u32>);
     param_ptr_forward!(pub unsafe fn modulated_normalized_value(&self) -> f32);
     param_ptr_forward!(pub unsafe fn unmodulated_normalized_value(&self) -> f32);
@@ -71,6 +74,7 @@ impl ParamPtr {
     param_ptr_forward!(pub unsafe fn previous_normalized_step(&self, from: f32, finer: bool) -> f32);
     param_ptr_forward!(pub unsafe fn next_normalized_step(&self, from: f32, finer: bool) -> f32);
     param_ptr_forward!(pub unsafe fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String);
+    param_ptr_forward!(pub unsafe fn normalized_value_to_string_into(&self, normalized: f32, include_unit: bool, string: &mut String));
     param_ptr_forward!(pub unsafe fn string_to_normalized_value(&self, string: &str) -> Option<f32>);
     param_ptr_forward!(pub unsafe fn flags(&self) -> ParamFlags);
 
@@ -168,3 +172,37 @@ impl ParamPtr {
         }
     }
 }
+
+/// A custom conversion function from a plain value to a string for
+/// [`FloatParam`][super::FloatParam] and [`IntParam`][super::IntParam]. Both forms are stored as is, so a function set through
+/// `with_value_to_string()` is called exactly once and its string is used directly when the
+/// caller needs a new string anyways.
+pub(crate) enum ValueToString<T> {
+    /// Set through `with_value_to_string()`. Returns a newly allocated string.
+    Alloc(Arc<dyn Fn(T) -> String + Send + Sync>),
+    /// Set through `with_value_to_string_no_alloc()`. Appends the formatted value to an existing
+    /// string.
+    NoAlloc(Arc<dyn Fn(T, &mut String) + Send + Sync>),
+}
+
+impl<T> ValueToString<T> {
+    /// Append the formatted value to `string`.
+    pub fn write(&self, value: T, string: &mut String) {
+        match self {
+            ValueToString::Alloc(f) => string.push_str(&f(value)),
+            ValueToString::NoAlloc(f) => f(value, string),
+        }
+    }
+
+    /// Format the value to a new string.
+    pub fn format(&self, value: T) -> String {
+        match self {
+            ValueToString::Alloc(f) => f(value),
+            ValueToString::NoAlloc(f) => {
+                let mut string = String::new();
+                f(value, &mut string);
+                string
+            }
+        }
+    }
+}
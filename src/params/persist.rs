@@ -12,6 +12,11 @@ pub use serde_json::to_string as serialize_field;
 /// These types can be used with [`Params`][super::Params]' `#[persist = "..."]` attributes.
 ///
 /// This should be implemented for some type with interior mutability containing a `T`.
+///
+/// Growable collections like a `Vec<T>` or a `HashMap<String, T>` can be persisted by wrapping
+/// them in one of the locks this trait is implemented for, like an `Arc<Mutex<Vec<T>>>`. The lock
+/// is held while [`map()`][Self::map()] runs, so the collection cannot be modified while it's
+/// being serialized.
 //
 // TODO: Modifying these fields (or any parameter for that matter) should mark the plugin's state
 //       as dirty.
@@ -200,6 +205,8 @@ impl_persistent_atomic!(std::sync::atomic::AtomicUsize, usize);
 impl_persistent_atomic!(atomic_float::AtomicF32, f32);
 impl_persistent_atomic!(atomic_float::AtomicF64, f64);
 
+/// Useful for persisting small `Copy` values like an editor's current mode or the selected tab
+/// without needing a lock. The `Arc<AtomicCell<T>>` can then be shared directly with the editor.
 impl<'a, T> PersistentField<'a, T> for crossbeam::atomic::AtomicCell<T>
 where
     T: serde::Serialize + serde::Deserialize<'a> + Copy + Send,
@@ -217,8 +224,10 @@ where
 impl_persistent_arc!(crossbeam::atomic::AtomicCell<T>,
                      T: serde::Serialize + serde::Deserialize<'a> + Copy + Send);
 
-/// Can be used with the `#[serde(with = "nih_plug::params::internals::serialize_atomic_cell")]`
-/// attribute to serialize `AtomicCell<T>`s.
+/// Can be used with the `#[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]`
+/// attribute to serialize `AtomicCell<T>`s that are part of a larger serializable struct, like an
+/// editor's state object. This is not needed for `#[persist = "key"]` fields, as an
+/// `Arc<AtomicCell<T>>` can be persisted directly.
 pub mod serialize_atomic_cell {
     use crossbeam::atomic::AtomicCell;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -239,3 +248,29 @@ pub mod serialize_atomic_cell {
         T::deserialize(deserializer).map(AtomicCell::new)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossbeam::atomic::AtomicCell;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    enum EditorMode {
+        #[default]
+        Collapsed,
+        Expanded,
+    }
+
+    #[test]
+    fn test_atomic_cell_roundtrip() {
+        let mode: Arc<AtomicCell<EditorMode>> = Arc::default();
+        mode.store(EditorMode::Expanded);
+        let serialized = mode.map(serialize_field).unwrap();
+
+        let restored: Arc<AtomicCell<EditorMode>> = Arc::default();
+        restored.set(deserialize_field(&serialized).unwrap());
+        assert_eq!(restored.load(), EditorMode::Expanded);
+    }
+}
@@ -17,6 +17,11 @@ pub mod vst3;
 /// they can be deferred for later to avoid blocking realtime contexts.
 pub type TaskExecutor<P> = Box<dyn Fn(<P as Plugin>::BackgroundTask) + Send>;
 
+/// A function that returns the name for one of the plugin's auxiliary output ports given the active
+/// audio IO layout and the port's index, or `None` to use the name from the layout. See
+/// [`Plugin::aux_output_port_names()`].
+pub type AuxOutputPortNames = Box<dyn Fn(&AudioIOLayout, usize) -> Option<String> + Send + Sync>;
+
 /// The main plugin trait covering functionality common across most plugin formats. Most formats
 /// also have another trait with more specific data and functionality that needs to be implemented
 /// before the plugin can be exported to that format. The wrappers will use this to expose the
@@ -98,9 +103,32 @@ pub trait Plugin: Default + Send + 'static {
     /// plugins with no main output or with zero main output channels.
     const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout];
 
+    /// If this is set to true, then the VST3 wrapper will accept a host's bus arrangement if it
+    /// only differs from one of the [`AUDIO_IO_LAYOUTS`][Self::AUDIO_IO_LAYOUTS] by using mono
+    /// instead of stereo or the other way around for the main input or output. The plugin still
+    /// gets initialized with and processes audio using its own layout, and the wrapper up- or
+    /// downmixes the host's main channels to match. Mono is copied to both stereo channels, and
+    /// stereo is downmixed to mono by averaging both channels. This prevents a stereo-only plugin
+    /// from being silent or refusing to load on a mono track in hosts that don't adapt to the
+    /// plugin's layout. Layouts that match the host's bus arrangement exactly are always preferred.
+    ///
+    /// CLAP hosts always use one of the plugin's declared layouts, so this does not affect the CLAP
+    /// wrapper. Leave this disabled if the plugin should only ever be used with its exact layouts.
+    const REMAP_MAIN_IO_CHANNELS: bool = false;
+
     /// Whether the plugin accepts note events, and what which events it wants to receive. If this
     /// is set to [`MidiConfig::None`], then the plugin won't receive any note events.
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    /// Whether the plugin wants to receive note events when [`MIDI_INPUT`][Self::MIDI_INPUT] is set
+    /// to [`MidiConfig::Basic`] or higher. This includes note on, note off, and choke events, as
+    /// well as polyphonic pressure and the other polyphonic note expressions. Setting this to
+    /// `false` while setting `MIDI_INPUT` to [`MidiConfig::MidiCCs`] lets a plugin receive only
+    /// channel-wide events like MIDI CCs, pitch bend, and channel pressure, without the CLAP and
+    /// VST3 wrappers having to translate the note events the plugin would otherwise ignore.
+    /// Polyphonic modulation and SysEx events are not affected by this setting. Plugins that never
+    /// want to receive note events should use [`MidiConfig::MidiCCOnly`] instead, which also
+    /// presents the note input port to the host as a plain MIDI input.
+    const MIDI_INPUT_NOTES: bool = true;
     /// Whether the plugin can output note events. If this is set to [`MidiConfig::None`], then the
     /// plugin won't have a note output port. When this is set to another value, then in most hosts
     /// the plugin will consume all note and MIDI CC input. If you don't want that, then you will
@@ -117,6 +145,49 @@ pub trait Plugin: Default + Send + 'static {
     /// to do offline processing.
     const HARD_REALTIME_ONLY: bool = false;
 
+    /// If this is set to true, then parameter values restored while the plugin is processing audio,
+    /// for instance when importing a preset from the plugin's GUI using
+    /// [`GuiContext::set_state()`][crate::prelude::GuiContext::set_state()], will ramp from their
+    /// current values to the restored values using the parameters' smoothers. This avoids clicks
+    /// when switching presets at runtime. State restored by the host, like when loading a project,
+    /// is still applied immediately. The plugin is still reinitialized and reset after restoring
+    /// the state, so to fully avoid clicks the plugin should not clear its audible state there.
+    const SMOOTH_RUNTIME_STATE_RESTORES: bool = false;
+
+    /// If this is set to true, then all of the parameters' smoothers are snapped to their current
+    /// values right before the host calls [`reset()`][Self::reset()], instead of continuing to ramp
+    /// towards their targets. Together with resetting all other state, including random number
+    /// generators, in [`reset()`][Self::reset()], this makes offline renders fully deterministic
+    /// regardless of what happened before the render started. Smoothers are always reset when the
+    /// plugin gets initialized, so this only affects the host resetting an active plugin. Plugins
+    /// that only want to reset some of their smoothers can instead call
+    /// [`Smoother::reset()`][crate::prelude::Smoother::reset()] on those from their own
+    /// [`reset()`][Self::reset()] implementation.
+    const RESET_SMOOTHERS_ON_RESET: bool = false;
+
+    /// The length in milliseconds of a linear fade-in the wrapper applies to all of the plugin's
+    /// outputs after the host resets the plugin, for instance after seeking or when playback
+    /// starts. Plugins whose output does not immediately start from silence after clearing their
+    /// buffers in [`reset()`][Self::reset()], like plugins with dry signal paths or with
+    /// latency, would otherwise produce a click there. The fade starts at zero gain on the first
+    /// sample after the reset and reaches unity gain after this many milliseconds. The default
+    /// value of zero disables the fade. A couple of milliseconds is usually enough.
+    const RESET_FADE_IN_MS: f32 = 0.0;
+
+    /// If this is set to true, then latency changes made through the
+    /// [`ProcessContext`][crate::prelude::ProcessContext::set_latency_samples()] while the host is
+    /// processing audio are not reported to the host right away. The CLAP and VST3 wrappers instead
+    /// notify the host once it stops processing audio or deactivates the plugin. Some hosts
+    /// restart their entire audio graph whenever a plugin's latency changes, which causes dropouts
+    /// while sweeping a control that affects the latency, like an oversampling factor. The
+    /// downside is that the host's latency compensation will be off until playback stops, so only
+    /// enable this if that is acceptable for the plugin.
+    ///
+    /// Regardless of this setting, the wrappers only notify the host when the latency differs from
+    /// the value the host last queried, and multiple changes in quick succession are coalesced
+    /// into a single notification.
+    const DEFER_LATENCY_CHANGES: bool = false;
+
     /// The plugin's SysEx message type if it supports sending or receiving MIDI SysEx messages, or
     /// `()` if it does not. This type can be a struct or enum wrapping around one or more message
     /// types, and the [`SysExMessage`] trait is then used to convert between this type and basic
@@ -144,6 +215,19 @@ pub trait Plugin: Default + Send + 'static {
         Box::new(|_| ())
     }
 
+    /// How a background task should be scheduled when it's run using one of the
+    /// `execute_background()` functions. By default all tasks are run on the same worker thread in
+    /// the order they were scheduled. Tasks with [`TaskPriority::Low`] are instead run on a
+    /// separate worker thread, so long running jobs like decoding a large impulse response don't
+    /// hold up short, latency-sensitive tasks that were scheduled after them. Tasks run through
+    /// `execute_gui()` are not affected by this.
+    ///
+    /// This is called every time a background task is scheduled, so it should be cheap and
+    /// realtime-safe.
+    fn background_task_priority(task: &Self::BackgroundTask) -> TaskPriority {
+        TaskPriority::Normal
+    }
+
     /// The plugin's parameters. The host will update the parameter values before calling
     /// `process()`. These string parameter IDs parameters should never change as they are used to
     /// distinguish between parameters.
@@ -178,6 +262,23 @@ pub trait Plugin: Default + Send + 'static {
     /// This is an advanced feature that the vast majority of plugins won't need to implement.
     fn filter_state(state: &mut PluginState) {}
 
+    /// Override the names of the auxiliary output ports from the active audio IO layout. The
+    /// returned function is called with the active layout and an auxiliary output port's index.
+    /// Returning `None` from that function uses the name from the layout's
+    /// [`PortNames`][crate::prelude::PortNames]. This makes it possible to reflect the plugin's
+    /// current state in the port names, like marking outputs that are currently not in use. Call
+    /// [`ProcessContext::port_names_changed()`][crate::prelude::ProcessContext] whenever the names
+    /// returned by the function change so the host can query them again.
+    ///
+    /// Queried only once immediately after the plugin instance is created. The host may query the
+    /// port names while the plugin is processing audio, so the wrappers call the returned function
+    /// from the main thread without locking the plugin. It should only depend on data that's
+    /// shared with the plugin, like its parameters. This function takes `&mut self` to make it
+    /// easier to move data into the closure.
+    fn aux_output_port_names(&mut self) -> Option<AuxOutputPortNames> {
+        None
+    }
+
     //
     // The following functions follow the lifetime of the plugin.
     //
@@ -200,7 +301,14 @@ pub trait Plugin: Default + Send + 'static {
     /// - Depending on how the host restores plugin state, this function may be called multiple
     ///   times in rapid succession. It may thus be useful to check if the initialization work for
     ///   the current bufffer and audio IO configurations has already been performed first.
+    /// - The sample rate in `buffer_config` is always a positive, finite number, and
+    ///   [`process()`][Self::process()] is never called before this function has been called. The
+    ///   sample rate can only change when this function is called again, so any values that depend
+    ///   on the sample rate should be (re)computed here.
     /// - If the plugin fails to initialize for whatever reason, then this should return `false`.
+    ///   Fallible setup like FFT planning should report errors this way instead of panicking. The
+    ///   CLAP and VST3 wrappers will catch panics in this function and treat them as a failed
+    ///   initialization, but the plugin may be left in an inconsistent state when that happens.
     fn initialize(
         &mut self,
         audio_io_layout: &AudioIOLayout,
@@ -254,6 +362,20 @@ pub trait Plugin: Default + Send + 'static {
     fn deactivate(&mut self) {}
 }
 
+/// The scheduling priority for a [`Plugin::BackgroundTask`]. See
+/// [`Plugin::background_task_priority()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TaskPriority {
+    /// The task is short and latency-sensitive, and it should be run as soon as possible. This is
+    /// the default.
+    #[default]
+    Normal,
+    /// The task is heavy or long running, like loading an impulse response or a sample. These
+    /// tasks are run on a separate worker thread so they don't block [`TaskPriority::Normal`]
+    /// tasks.
+    Low,
+}
+
 /// Indicates the current situation after the plugin has processed audio.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessStatus {
@@ -269,3 +391,83 @@ pub enum ProcessStatus {
     /// infinite tail.
     KeepAlive,
 }
+
+/// Implement both [`ClapPlugin`][clap::ClapPlugin] and [`Vst3Plugin`][vst3::Vst3Plugin] for a
+/// plugin type from a single specification. The vendor, URL, and email address are already part of
+/// the [`Plugin`] trait, and the optional CLAP metadata uses the trait's defaults unless it is
+/// specified here. The features and subcategories are listed in full, so they can be customized
+/// exactly like when implementing the traits by hand.
+///
+/// The `clap_description`, `clap_manual_url`, and `clap_support_url` fields are optional. The VST3
+/// fields can be omitted to only implement `ClapPlugin`, for instance when the `vst3` feature is
+/// disabled. The fields need to be specified in this order. Plugins that need the other
+/// `ClapPlugin` items, like polyphonic modulation or remote control pages, should implement the
+/// traits by hand instead.
+///
+/// ```
+/// # use nih_plug::prelude::*;
+/// # use std::sync::Arc;
+/// # #[derive(Default)]
+/// # struct Gain;
+/// # #[derive(Params)]
+/// # struct GainParams {}
+/// # impl Plugin for Gain {
+/// #     const NAME: &'static str = "Gain";
+/// #     const VENDOR: &'static str = "Moist Plugins GmbH";
+/// #     const URL: &'static str = "https://youtu.be/dQw4w9WgXcQ";
+/// #     const EMAIL: &'static str = "info@example.com";
+/// #     const VERSION: &'static str = "0.0.1";
+/// #     const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[];
+/// #     type SysExMessage = ();
+/// #     type BackgroundTask = ();
+/// #     fn params(&self) -> Arc<dyn Params> { Arc::new(GainParams {}) }
+/// #     fn process(
+/// #         &mut self,
+/// #         _buffer: &mut Buffer,
+/// #         _aux: &mut AuxiliaryBuffers,
+/// #         _context: &mut impl ProcessContext<Self>,
+/// #     ) -> ProcessStatus { ProcessStatus::Normal }
+/// # }
+/// nih_plugin_metadata!(Gain {
+///     clap_id: "com.moist-plugins-gmbh.gain",
+///     clap_description: "A smoothed gain parameter example plugin",
+///     clap_features: [ClapFeature::AudioEffect, ClapFeature::Stereo, ClapFeature::Utility],
+///     vst3_class_id: *b"GainMoistestPlug",
+///     vst3_subcategories: [Vst3SubCategory::Fx, Vst3SubCategory::Tools],
+/// });
+///
+/// assert_eq!(Gain::CLAP_MANUAL_URL, Some(Gain::URL));
+/// ```
+#[macro_export]
+macro_rules! nih_plugin_metadata {
+    (
+        $plugin_ty:ty {
+            clap_id: $clap_id:expr,
+            $(clap_description: $clap_description:expr,)?
+            $(clap_manual_url: $clap_manual_url:expr,)?
+            $(clap_support_url: $clap_support_url:expr,)?
+            clap_features: [$($clap_feature:expr),* $(,)?]
+            $(,
+                vst3_class_id: $vst3_class_id:expr,
+                vst3_subcategories: [$($vst3_subcategory:expr),* $(,)?]
+            )?
+            $(,)?
+        }
+    ) => {
+        impl $crate::prelude::ClapPlugin for $plugin_ty {
+            const CLAP_ID: &'static str = $clap_id;
+            $(const CLAP_DESCRIPTION: Option<&'static str> = Some($clap_description);)?
+            $(const CLAP_MANUAL_URL: Option<&'static str> = Some($clap_manual_url);)?
+            $(const CLAP_SUPPORT_URL: Option<&'static str> = Some($clap_support_url);)?
+            const CLAP_FEATURES: &'static [$crate::prelude::ClapFeature] = &[$($clap_feature),*];
+        }
+
+        $(
+            impl $crate::prelude::Vst3Plugin for $plugin_ty {
+                const VST3_CLASS_ID: [u8; 16] = $vst3_class_id;
+                const VST3_SUBCATEGORIES: &'static [$crate::prelude::Vst3SubCategory] =
+                    &[$($vst3_subcategory),*];
+            }
+        )?
+    };
+}
@@ -8,11 +8,12 @@ pub trait ClapPlugin: Plugin {
     /// notation, e.g. `com.manufacturer.plugin-name`.
     const CLAP_ID: &'static str;
     /// An optional short description for the plugin.
-    const CLAP_DESCRIPTION: Option<&'static str>;
-    /// The URL to the plugin's manual, if available.
-    const CLAP_MANUAL_URL: Option<&'static str>;
+    const CLAP_DESCRIPTION: Option<&'static str> = None;
+    /// The URL to the plugin's manual, if available. Defaults to the plugin's
+    /// [`URL`][Plugin::URL].
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
     /// The URL to the plugin's support page, if available.
-    const CLAP_SUPPORT_URL: Option<&'static str>;
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
     /// Keywords describing the plugin. The host may use this to classify the plugin in its plugin
     /// browser.
     const CLAP_FEATURES: &'static [ClapFeature];
@@ -4,11 +4,15 @@ pub use std::num::NonZeroU32;
 // Re-export the macros, derive macros are already re-exported from their respective modules
 pub use crate::debug::*;
 
+pub use crate::nih_build_info;
 pub use crate::nih_export_clap;
 #[cfg(feature = "vst3")]
 pub use crate::nih_export_vst3;
+pub use crate::nih_plugin_metadata;
 #[cfg(feature = "standalone")]
-pub use crate::wrapper::standalone::{nih_export_standalone, nih_export_standalone_with_args};
+pub use crate::wrapper::standalone::{
+    nih_export_standalone, nih_export_standalone_with_args, nih_export_standalone_with_window_loop,
+};
 
 pub use crate::formatters;
 pub use crate::util;
@@ -23,7 +27,7 @@ pub use crate::context::process::{ProcessContext, Transport};
 pub use crate::context::remote_controls::{
     RemoteControlsContext, RemoteControlsPage, RemoteControlsSection,
 };
-pub use crate::context::PluginApi;
+pub use crate::context::{PluginApi, RawHost};
 // This also includes the derive macro
 pub use crate::editor::{Editor, ParentWindowHandle};
 pub use crate::midi::sysex::SysExMessage;
@@ -31,13 +35,14 @@ pub use crate::midi::{control_change, MidiConfig, NoteEvent, PluginNoteEvent};
 pub use crate::params::enums::{Enum, EnumParam};
 pub use crate::params::internals::ParamPtr;
 pub use crate::params::range::{FloatRange, IntRange};
-pub use crate::params::smoothing::{AtomicF32, Smoothable, Smoother, SmoothingStyle};
+pub use crate::params::smoothing::{AtomicF32, BypassRamp, Smoothable, Smoother, SmoothingStyle};
 pub use crate::params::Params;
-pub use crate::params::{BoolParam, FloatParam, IntParam, Param, ParamFlags};
+pub use crate::params::{BoolParam, FloatParam, IntParam, Param, ParamFlags, ParamValueType};
 pub use crate::plugin::clap::{ClapPlugin, PolyModulationConfig};
 #[cfg(feature = "vst3")]
 pub use crate::plugin::vst3::Vst3Plugin;
-pub use crate::plugin::{Plugin, ProcessStatus, TaskExecutor};
+pub use crate::plugin::{AuxOutputPortNames, Plugin, ProcessStatus, TaskExecutor, TaskPriority};
+pub use crate::util::envelope::{Adsr, AdsrStage};
 pub use crate::wrapper::clap::features::ClapFeature;
 pub use crate::wrapper::state::PluginState;
 #[cfg(feature = "vst3")]
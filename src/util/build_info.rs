@@ -0,0 +1,85 @@
+//! Build metadata that can be embedded into a plugin at compile time.
+
+use std::fmt::Display;
+
+/// The name of the environment variable [`nih_build_info!()`][crate::nih_build_info!()] reads the
+/// git commit hash from. `cargo xtask bundle` sets this automatically. When using a different build
+/// setup, this can also be set from a build script using
+/// `println!("cargo:rustc-env=NIH_PLUG_GIT_HASH=...")`.
+pub const GIT_HASH_ENV_VAR: &str = "NIH_PLUG_GIT_HASH";
+/// The name of the environment variable [`nih_build_info!()`][crate::nih_build_info!()] reads the
+/// build date from. See [`GIT_HASH_ENV_VAR`].
+pub const BUILD_DATE_ENV_VAR: &str = "NIH_PLUG_BUILD_DATE";
+
+/// Metadata describing the exact build of a plugin. This can be shown in an about screen or logged
+/// during initialization to make it easier to tell which build a user is running. Create this using
+/// the [`nih_build_info!()`][crate::nih_build_info!()] macro, which needs to be called from the
+/// plugin's own crate.
+///
+/// The `Display` implementation formats this as `1.0.0 (abcdef1, 2023-01-01)`, leaving out the
+/// parts that are not available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The crate's version, taken from `CARGO_PKG_VERSION`.
+    pub version: &'static str,
+    /// The short git commit hash the plugin was built from, if it was set through the
+    /// `NIH_PLUG_GIT_HASH` environment variable at compile time.
+    pub git_hash: Option<&'static str>,
+    /// The date the plugin was built on in `YYYY-MM-DD` format, if it was set through the
+    /// `NIH_PLUG_BUILD_DATE` environment variable at compile time.
+    pub build_date: Option<&'static str>,
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.version)?;
+        match (self.git_hash, self.build_date) {
+            (Some(git_hash), Some(build_date)) => write!(f, " ({git_hash}, {build_date})"),
+            (Some(metadata), None) | (None, Some(metadata)) => write!(f, " ({metadata})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Get the [`BuildInfo`] for the crate this macro is called from. The git hash and build date are
+/// read from the `NIH_PLUG_GIT_HASH` and `NIH_PLUG_BUILD_DATE` environment variables at compile
+/// time, and they will be `None` if those variables were not set.
+///
+/// ```
+/// # use nih_plug::prelude::*;
+/// const BUILD_INFO: util::BuildInfo = nih_build_info!();
+///
+/// assert_eq!(BUILD_INFO.version, env!("CARGO_PKG_VERSION"));
+/// nih_log!("Running build {BUILD_INFO}");
+/// ```
+#[macro_export]
+macro_rules! nih_build_info {
+    () => {
+        $crate::util::BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: option_env!("NIH_PLUG_GIT_HASH"),
+            build_date: option_env!("NIH_PLUG_BUILD_DATE"),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut build_info = BuildInfo {
+            version: "1.2.3",
+            git_hash: None,
+            build_date: None,
+        };
+        assert_eq!(build_info.to_string(), "1.2.3");
+
+        build_info.build_date = Some("2023-01-01");
+        assert_eq!(build_info.to_string(), "1.2.3 (2023-01-01)");
+
+        build_info.git_hash = Some("abcdef1");
+        assert_eq!(build_info.to_string(), "1.2.3 (abcdef1, 2023-01-01)");
+    }
+}
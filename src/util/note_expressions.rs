@@ -0,0 +1,189 @@
+//! Per-voice tracking for polyphonic note expressions.
+
+use crate::midi::NoteEvent;
+use crate::params::smoothing::{Smoother, SmoothingStyle};
+
+/// The volume, panning, and tuning note expressions for a single voice. Instead of matching
+/// [`NoteEvent::PolyVolume`], [`NoteEvent::PolyPan`], and [`NoteEvent::PolyTuning`] events to
+/// voices by hand, a synth can store one of these objects per voice, pass every incoming event to
+/// [`handle_event()`][Self::handle_event()], and then read the resolved values for the current
+/// block using [`next_block()`][Self::next_block()]. Changes are smoothed to avoid zipper noise.
+///
+/// ```
+/// # use nih_plug::prelude::*;
+/// let expressions = util::NoteExpressions::new(5.0);
+///
+/// let event: NoteEvent<()> = NoteEvent::PolyVolume {
+///     timing: 0,
+///     voice_id: None,
+///     channel: 0,
+///     note: 60,
+///     gain: 0.5,
+/// };
+/// assert!(expressions.handle_event(44_100.0, &event, None, 0, 60));
+/// ```
+#[derive(Debug)]
+pub struct NoteExpressions {
+    /// The voice's voltage gain ratio, where 1.0 is unity gain.
+    gain: Smoother<f32>,
+    /// The voice's panning in `[-1, 1]`, where -1 is panned hard left.
+    pan: Smoother<f32>,
+    /// The voice's tuning offset in semitones.
+    tuning: Smoother<f32>,
+}
+
+impl NoteExpressions {
+    /// Create a new note expression tracker. Changes to the expression values are smoothed
+    /// linearly over `smoothing_ms` milliseconds. Call [`reset()`][Self::reset()] whenever a voice
+    /// starts playing.
+    pub fn new(smoothing_ms: f32) -> Self {
+        let expressions = Self {
+            gain: Smoother::new(SmoothingStyle::Linear(smoothing_ms)),
+            pan: Smoother::new(SmoothingStyle::Linear(smoothing_ms)),
+            tuning: Smoother::new(SmoothingStyle::Linear(smoothing_ms)),
+        };
+        expressions.reset();
+
+        expressions
+    }
+
+    /// Reset the expressions to their neutral values. That's unity gain, center panning, and no
+    /// tuning offset.
+    pub fn reset(&self) {
+        self.gain.reset(1.0);
+        self.pan.reset(0.0);
+        self.tuning.reset(0.0);
+    }
+
+    /// Apply `event` if it's a volume, panning, or tuning expression event for the voice described
+    /// by `voice_id`, `channel`, and `note`. If both the event and the voice have a voice ID, then
+    /// those IDs are compared. Otherwise the event is matched based on the channel and note
+    /// number. Returns `true` if the event was applied to this voice.
+    pub fn handle_event<S>(
+        &self,
+        sample_rate: f32,
+        event: &NoteEvent<S>,
+        voice_id: Option<i32>,
+        channel: u8,
+        note: u8,
+    ) -> bool {
+        let (smoother, value, event_voice_id, event_channel, event_note) = match *event {
+            NoteEvent::PolyVolume {
+                voice_id,
+                channel,
+                note,
+                gain,
+                ..
+            } => (&self.gain, gain, voice_id, channel, note),
+            NoteEvent::PolyPan {
+                voice_id,
+                channel,
+                note,
+                pan,
+                ..
+            } => (&self.pan, pan, voice_id, channel, note),
+            NoteEvent::PolyTuning {
+                voice_id,
+                channel,
+                note,
+                tuning,
+                ..
+            } => (&self.tuning, tuning, voice_id, channel, note),
+            _ => return false,
+        };
+
+        let is_this_voice = match (event_voice_id, voice_id) {
+            (Some(event_voice_id), Some(voice_id)) => event_voice_id == voice_id,
+            _ => event_channel == channel && event_note == note,
+        };
+        if is_this_voice {
+            smoother.set_target(sample_rate, value);
+        }
+
+        is_this_voice
+    }
+
+    /// Fill the first `block_len` values of `gain`, `pan`, and `tuning` with the voice's smoothed
+    /// expression values for the next block. See [`pan_gains()`] for converting the panning values
+    /// to per-channel gains.
+    pub fn next_block(
+        &self,
+        gain: &mut [f32],
+        pan: &mut [f32],
+        tuning: &mut [f32],
+        block_len: usize,
+    ) {
+        self.gain.next_block(gain, block_len);
+        self.pan.next_block(pan, block_len);
+        self.tuning.next_block(tuning, block_len);
+    }
+}
+
+/// Convert a `[-1, 1]` panning value to left and right channel gains. This is a simple balance
+/// control, so center panning leaves both channels at unity gain and panning to one side
+/// attenuates the other channel.
+#[inline]
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly_pan(voice_id: Option<i32>, channel: u8, note: u8, pan: f32) -> NoteEvent<()> {
+        NoteEvent::PolyPan {
+            timing: 0,
+            voice_id,
+            channel,
+            note,
+            pan,
+        }
+    }
+
+    /// The panning value after processing a single sample.
+    fn next_pan(expressions: &NoteExpressions) -> f32 {
+        let (mut gain, mut pan, mut tuning) = ([0.0], [0.0], [0.0]);
+        expressions.next_block(&mut gain, &mut pan, &mut tuning, 1);
+
+        pan[0]
+    }
+
+    #[test]
+    fn test_match_voice_id() {
+        let expressions = NoteExpressions::new(0.0);
+
+        // Overlapping voices for the same note should be told apart by their voice IDs
+        let event = poly_pan(Some(2), 0, 60, 1.0);
+        assert!(!expressions.handle_event(44_100.0, &event, Some(1), 0, 60));
+        assert_eq!(next_pan(&expressions), 0.0);
+
+        let event = poly_pan(Some(1), 0, 60, 1.0);
+        assert!(expressions.handle_event(44_100.0, &event, Some(1), 0, 60));
+        assert_eq!(next_pan(&expressions), 1.0);
+    }
+
+    #[test]
+    fn test_match_note() {
+        let expressions = NoteExpressions::new(0.0);
+
+        let event = poly_pan(None, 1, 60, -1.0);
+        assert!(!expressions.handle_event(44_100.0, &event, None, 0, 60));
+
+        let event = poly_pan(None, 0, 60, -1.0);
+        assert!(expressions.handle_event(44_100.0, &event, Some(1), 0, 60));
+        assert_eq!(next_pan(&expressions), -1.0);
+
+        expressions.reset();
+        assert_eq!(next_pan(&expressions), 0.0);
+    }
+
+    #[test]
+    fn test_pan_gains() {
+        assert_eq!(pan_gains(0.0), (1.0, 1.0));
+        assert_eq!(pan_gains(-1.0), (1.0, 0.0));
+        assert_eq!(pan_gains(0.5), (0.5, 1.0));
+    }
+}
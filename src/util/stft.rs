@@ -42,11 +42,13 @@ pub struct StftHelper<const NUM_SIDECHAIN_INPUTS: usize = 0> {
     main_output_ring_buffers: Vec<Vec<f32>>,
     sidechain_ring_buffers: [Vec<Vec<f32>>; NUM_SIDECHAIN_INPUTS],
 
-    /// Results from the ring buffers are copied to this scratch buffer before being passed to the
-    /// plugin. Needed to handle overlap.
-    scratch_buffer: Vec<f32>,
+    /// Results from the ring buffers are copied to these scratch buffers before being passed to the
+    /// plugin. Needed to handle overlap. There's one scratch buffer per channel so
+    /// [`process_overlap_add_multichannel()`][Self::process_overlap_add_multichannel()] can pass
+    /// all channels to the callback at once.
+    scratch_buffers: Vec<Vec<f32>>,
     /// If padding is used, then this will contain the previous iteration's values from the padding
-    /// values in `scratch_buffer` (`scratch_buffer[(scratch_buffer.len() - padding -
+    /// values in `scratch_buffers` (`scratch_buffer[(scratch_buffer.len() - padding -
     /// 1)..scratch_buffer.len()]`). This is then added to the ring buffer in the next iteration.
     padding_buffers: Vec<Vec<f32>>,
 
@@ -202,9 +204,9 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
             sidechain_ring_buffers: [(); NUM_SIDECHAIN_INPUTS]
                 .map(|_| vec![vec![0.0; max_block_size]; num_channels]),
 
-            // When padding is used this scratch buffer will have a bunch of zeroes added to it
-            // after copying a block of audio to it
-            scratch_buffer: vec![0.0; max_block_size + max_padding],
+            // When padding is used these scratch buffers will have a bunch of zeroes added to them
+            // after copying a block of audio to them
+            scratch_buffers: vec![vec![0.0; max_block_size + max_padding]; num_channels],
             padding_buffers: vec![vec![0.0; max_padding]; num_channels],
 
             current_pos: 0,
@@ -316,6 +318,48 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         main_buffer: &mut M,
         sidechain_buffers: [&S; NUM_SIDECHAIN_INPUTS],
         overlap_times: usize,
+        process_cb: F,
+    ) where
+        M: StftInputMut,
+        S: StftInput,
+        F: FnMut(usize, Option<usize>, &mut [f32]),
+    {
+        self.process_overlap_add_sidechains(
+            main_buffer,
+            &sidechain_buffers,
+            overlap_times,
+            process_cb,
+        );
+    }
+
+    /// The same as [`process_overlap_add_sidechain()`][Self::process_overlap_add_sidechain()],
+    /// but the sidechain inputs are passed as a slice. This makes it easier to pass a varying
+    /// selection of sidechain buffers, for instance when they come from the plugin's auxiliary
+    /// inputs.
+    ///
+    /// The process function is called as `process_cb(channel_idx, sidechain_buffer_idx,
+    /// real_fft_buffer)`. For every block, it is first called for every channel of every sidechain
+    /// input in order, with `sidechain_buffer_idx` set to that input's index in
+    /// `sidechain_buffers`. It is then called for each of the main buffer's channels with
+    /// `sidechain_buffer_idx` set to `None`. This makes it possible to analyze multiple independent
+    /// sidechain spectra before processing the main input. The ring buffers for the sidechain
+    /// inputs are allocated up front based on `NUM_SIDECHAIN_INPUTS`, so this never allocates.
+    ///
+    /// `sidechain_buffers` may contain fewer than `NUM_SIDECHAIN_INPUTS` buffers, for instance when
+    /// some of the plugin's auxiliary inputs are disconnected. The buffers are always assigned to
+    /// the sidechain ring buffers starting from index 0, and the process function is not called
+    /// for the remaining sidechain inputs. Those inputs are treated as silence, so they don't
+    /// contain any stale audio if they're passed again in a later call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sidechain_buffers` contains more than `NUM_SIDECHAIN_INPUTS` buffers, or under
+    /// the same conditions as [`process_overlap_add()`][Self::process_overlap_add()].
+    pub fn process_overlap_add_sidechains<M, S, F>(
+        &mut self,
+        main_buffer: &mut M,
+        sidechain_buffers: &[&S],
+        overlap_times: usize,
         mut process_cb: F,
     ) where
         M: StftInputMut,
@@ -326,8 +370,15 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
             main_buffer.num_channels(),
             self.main_input_ring_buffers.len()
         );
+        assert!(sidechain_buffers.len() <= NUM_SIDECHAIN_INPUTS);
         assert!(overlap_times > 0);
 
+        for sidechain_ring_buffers in &mut self.sidechain_ring_buffers[sidechain_buffers.len()..] {
+            for ring_buffer in sidechain_ring_buffers.iter_mut() {
+                ring_buffer.fill(0.0);
+            }
+        }
+
         // We'll copy samples from `*_buffer` into `*_ring_buffers` while simultaneously copying
         // already processed samples from `main_ring_buffers` in into `main_buffer`
         let main_buffer_len = main_buffer.num_samples();
@@ -407,22 +458,26 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
                 // wrap around. Because premade FFT algorithms typically can't handle this, we'll
                 // start with copying the wrapped ranges from our ring buffers to the scratch
                 // buffer. Then we apply the windowing function and this it along to
-                for (sidechain_idx, sidechain_ring_buffers) in
-                    self.sidechain_ring_buffers.iter().enumerate()
+                for (sidechain_idx, sidechain_ring_buffers) in self
+                    .sidechain_ring_buffers
+                    .iter()
+                    .take(sidechain_buffers.len())
+                    .enumerate()
                 {
                     for (channel_idx, sidechain_ring_buffer) in
                         sidechain_ring_buffers.iter().enumerate()
                     {
+                        let scratch_buffer = &mut self.scratch_buffers[channel_idx];
                         copy_ring_to_scratch_buffer(
-                            &mut self.scratch_buffer,
+                            scratch_buffer,
                             self.current_pos,
                             sidechain_ring_buffer,
                         );
                         if self.padding > 0 {
-                            self.scratch_buffer[block_size..].fill(0.0);
+                            scratch_buffer[block_size..].fill(0.0);
                         }
 
-                        process_cb(channel_idx, Some(sidechain_idx), &mut self.scratch_buffer);
+                        process_cb(channel_idx, Some(sidechain_idx), scratch_buffer);
                     }
                 }
 
@@ -433,60 +488,170 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
                     .zip(self.padding_buffers.iter_mut())
                     .enumerate()
                 {
+                    let scratch_buffer = &mut self.scratch_buffers[channel_idx];
                     copy_ring_to_scratch_buffer(
-                        &mut self.scratch_buffer,
+                        scratch_buffer,
                         self.current_pos,
                         input_ring_buffer,
                     );
                     if self.padding > 0 {
-                        self.scratch_buffer[block_size..].fill(0.0);
+                        scratch_buffer[block_size..].fill(0.0);
                     }
 
-                    process_cb(channel_idx, None, &mut self.scratch_buffer);
+                    process_cb(channel_idx, None, scratch_buffer);
 
-                    // Add the padding from the last iteration (for this channel) to the scratch
-                    // buffer before it is copied to the output ring buffer. In case the padding is
-                    // longer than the block size, then this will cause everything else to be
-                    // shifted to the left so it can be added in the iteration after this.
-                    if self.padding > 0 {
-                        let padding_to_copy = cmp::min(self.padding, block_size);
-                        for (scratch_sample, padding_sample) in self.scratch_buffer
-                            [..padding_to_copy]
-                            .iter_mut()
-                            .zip(&mut padding_buffer[..padding_to_copy])
-                        {
-                            *scratch_sample += *padding_sample;
-                        }
+                    overlap_add_scratch_buffer(
+                        scratch_buffer,
+                        self.current_pos,
+                        self.padding,
+                        padding_buffer,
+                        output_ring_buffer,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The same as [`process_overlap_add()`][Self::process_overlap_add()], but instead of calling
+    /// the callback once for every channel, the callback receives all of the main buffer's
+    /// channels at once. This is useful when the processing for one channel depends on the other
+    /// channels, for instance when inter-channel phase relationships need to be preserved. The
+    /// argument to that function is `process_cb(real_fft_buffers)`, where `real_fft_buffers`
+    /// contains one buffer of `block_size` real valued samples (plus the padding) per channel.
+    /// These buffers should not be resized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `main_buffer` does not have the same number of channels as this [`StftHelper`].
+    pub fn process_overlap_add_multichannel<M, F>(
+        &mut self,
+        main_buffer: &mut M,
+        overlap_times: usize,
+        mut process_cb: F,
+    ) where
+        M: StftInputMut,
+        F: FnMut(&mut [Vec<f32>]),
+    {
+        assert_eq!(
+            main_buffer.num_channels(),
+            self.main_input_ring_buffers.len()
+        );
+        assert!(overlap_times > 0);
 
-                        // Any remaining padding tail should be moved towards the start of the
-                        // buffer
-                        padding_buffer.copy_within(padding_to_copy.., 0);
+        // See `process_overlap_add_sidechains` for an annotated version
+        let main_buffer_len = main_buffer.num_samples();
+        let num_channels = main_buffer.num_channels();
+        let block_size = self.main_input_ring_buffers[0].len();
+        let window_interval = (block_size / overlap_times) as i32;
+        let mut already_processed_samples = 0;
+        while already_processed_samples < main_buffer_len {
+            let remaining_samples = main_buffer_len - already_processed_samples;
+            let samples_until_next_window = ((window_interval - self.current_pos as i32 - 1)
+                .rem_euclid(window_interval)
+                + 1) as usize;
+            let samples_to_process = samples_until_next_window.min(remaining_samples);
 
-                        // And we obviously don't want this to feedback
-                        padding_buffer[self.padding - padding_to_copy..].fill(0.0);
-                    }
+            for sample_offset in 0..samples_to_process {
+                for channel_idx in 0..num_channels {
+                    let sample = unsafe {
+                        main_buffer.get_sample_unchecked_mut(
+                            channel_idx,
+                            already_processed_samples + sample_offset,
+                        )
+                    };
+                    let input_ring_buffer_sample = unsafe {
+                        self.main_input_ring_buffers
+                            .get_unchecked_mut(channel_idx)
+                            .get_unchecked_mut(self.current_pos + sample_offset)
+                    };
+                    let output_ring_buffer_sample = unsafe {
+                        self.main_output_ring_buffers
+                            .get_unchecked_mut(channel_idx)
+                            .get_unchecked_mut(self.current_pos + sample_offset)
+                    };
+                    *input_ring_buffer_sample = *sample;
+                    *sample = *output_ring_buffer_sample;
+                    *output_ring_buffer_sample = 0.0;
+                }
+            }
 
-                    // The actual overlap-add part of the equation
-                    add_scratch_to_ring_buffer(
-                        &self.scratch_buffer,
+            already_processed_samples += samples_to_process;
+            self.current_pos = (self.current_pos + samples_to_process) % block_size;
+
+            if samples_to_process == samples_until_next_window {
+                // All channels need to be copied to their scratch buffers before the callback can
+                // be called
+                for (input_ring_buffer, scratch_buffer) in self
+                    .main_input_ring_buffers
+                    .iter()
+                    .zip(self.scratch_buffers.iter_mut())
+                {
+                    copy_ring_to_scratch_buffer(
+                        scratch_buffer,
                         self.current_pos,
-                        output_ring_buffer,
+                        input_ring_buffer,
                     );
-
-                    // And the data from the padding area should be saved so it can be added to next
-                    // iteration's scratch buffer. Like mentioned above, the padding can be larger
-                    // than the block size so we also need to do overlap-add here.
                     if self.padding > 0 {
-                        for (padding_sample, scratch_sample) in padding_buffer
-                            .iter_mut()
-                            .zip(&mut self.scratch_buffer[block_size..])
-                        {
-                            *padding_sample += *scratch_sample;
-                        }
+                        scratch_buffer[block_size..].fill(0.0);
                     }
                 }
+
+                process_cb(&mut self.scratch_buffers);
+
+                for ((scratch_buffer, output_ring_buffer), padding_buffer) in self
+                    .scratch_buffers
+                    .iter_mut()
+                    .zip(self.main_output_ring_buffers.iter_mut())
+                    .zip(self.padding_buffers.iter_mut())
+                {
+                    overlap_add_scratch_buffer(
+                        scratch_buffer,
+                        self.current_pos,
+                        self.padding,
+                        padding_buffer,
+                        output_ring_buffer,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The number of samples [`flush()`][Self::flush()] needs to write to output everything that's
+    /// still buffered in this [`StftHelper`]. This is the latency plus the padding, so this is also
+    /// the tail length that should be reported to the host.
+    pub fn flush_length_samples(&self) -> usize {
+        self.main_input_ring_buffers[0].len() + self.padding
+    }
+
+    /// Write the samples that are still buffered in this [`StftHelper`] to `output_buffer` by
+    /// processing silence using [`process_overlap_add()`][Self::process_overlap_add()]. Without
+    /// this, the last block's worth of audio is lost when rendering audio offline, for instance
+    /// when the plugin is used in [`ProcessMode::Offline`][crate::prelude::ProcessMode::Offline]
+    /// and the host does not process the plugin's tail. `overlap_times` and `process_cb` should be
+    /// the same as the ones passed to `process_overlap_add()`.
+    ///
+    /// Any existing contents of `output_buffer` are overwritten. The buffer should contain
+    /// [`flush_length_samples()`][Self::flush_length_samples()] samples to output the entire tail.
+    /// This function can also be called multiple times with smaller buffers instead. Afterwards the
+    /// helper will have the same state as if it had only been fed silence, so it can be reused for
+    /// the next render.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output_buffer` does not have the same number of channels as this
+    /// [`StftHelper`].
+    pub fn flush<M, F>(&mut self, output_buffer: &mut M, overlap_times: usize, process_cb: F)
+    where
+        M: StftInputMut,
+        F: FnMut(usize, &mut [f32]),
+    {
+        for channel_idx in 0..output_buffer.num_channels() {
+            for sample_idx in 0..output_buffer.num_samples() {
+                unsafe { *output_buffer.get_sample_unchecked_mut(channel_idx, sample_idx) = 0.0 };
             }
         }
+
+        self.process_overlap_add(output_buffer, overlap_times, process_cb);
     }
 
     /// Similar to [`process_overlap_add()`][Self::process_overlap_add()], but without the inverse
@@ -505,7 +670,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         assert_eq!(buffer.num_channels(), self.main_input_ring_buffers.len());
         assert!(overlap_times > 0);
 
-        // See `process_overlap_add_sidechain` for an annotated version
+        // See `process_overlap_add_sidechains` for an annotated version
         let main_buffer_len = buffer.num_samples();
         let num_channels = buffer.num_channels();
         let block_size = self.main_input_ring_buffers[0].len();
@@ -539,19 +704,22 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
             self.current_pos = (self.current_pos + samples_to_process) % block_size;
 
             if samples_to_process == samples_until_next_window {
-                for (channel_idx, input_ring_buffer) in
-                    self.main_input_ring_buffers.iter().enumerate()
+                for (channel_idx, (input_ring_buffer, scratch_buffer)) in self
+                    .main_input_ring_buffers
+                    .iter()
+                    .zip(self.scratch_buffers.iter_mut())
+                    .enumerate()
                 {
                     copy_ring_to_scratch_buffer(
-                        &mut self.scratch_buffer,
+                        scratch_buffer,
                         self.current_pos,
                         input_ring_buffer,
                     );
                     if self.padding > 0 {
-                        self.scratch_buffer[block_size..].fill(0.0);
+                        scratch_buffer[block_size..].fill(0.0);
                     }
 
-                    analyze_cb(channel_idx, &mut self.scratch_buffer);
+                    analyze_cb(channel_idx, scratch_buffer);
                 }
             }
         }
@@ -572,8 +740,10 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
                 sidechain_ring_buffer.fill(0.0);
             }
         }
-        self.scratch_buffer.resize(block_size + self.padding, 0.0);
-        self.scratch_buffer.fill(0.0);
+        for scratch_buffer in &mut self.scratch_buffers {
+            scratch_buffer.resize(block_size + self.padding, 0.0);
+            scratch_buffer.fill(0.0);
+        }
 
         for padding_buffer in &mut self.padding_buffers {
             // In case this changed since the last call, like in `set_padding()`
@@ -600,6 +770,55 @@ fn copy_ring_to_scratch_buffer(
     scratch_buffer[num_copy_before_wrap..block_size].copy_from_slice(&ring_buffer[0..current_pos]);
 }
 
+/// Add the padding from the last iteration to the scratch buffer, overlap-add the scratch buffer to
+/// the output ring buffer, and then store the new padding so it can be added in the next
+/// iteration. This is done after the callback has processed a channel's scratch buffer.
+#[inline]
+fn overlap_add_scratch_buffer(
+    scratch_buffer: &mut [f32],
+    current_pos: usize,
+    padding: usize,
+    padding_buffer: &mut [f32],
+    output_ring_buffer: &mut [f32],
+) {
+    let block_size = output_ring_buffer.len();
+
+    // Add the padding from the last iteration (for this channel) to the scratch buffer before it is
+    // copied to the output ring buffer. In case the padding is longer than the block size, then
+    // this will cause everything else to be shifted to the left so it can be added in the
+    // iteration after this.
+    if padding > 0 {
+        let padding_to_copy = cmp::min(padding, block_size);
+        for (scratch_sample, padding_sample) in scratch_buffer[..padding_to_copy]
+            .iter_mut()
+            .zip(&mut padding_buffer[..padding_to_copy])
+        {
+            *scratch_sample += *padding_sample;
+        }
+
+        // Any remaining padding tail should be moved towards the start of the buffer
+        padding_buffer.copy_within(padding_to_copy.., 0);
+
+        // And we obviously don't want this to feedback
+        padding_buffer[padding - padding_to_copy..].fill(0.0);
+    }
+
+    // The actual overlap-add part of the equation
+    add_scratch_to_ring_buffer(scratch_buffer, current_pos, output_ring_buffer);
+
+    // And the data from the padding area should be saved so it can be added to next iteration's
+    // scratch buffer. Like mentioned above, the padding can be larger than the block size so we
+    // also need to do overlap-add here.
+    if padding > 0 {
+        for (padding_sample, scratch_sample) in padding_buffer
+            .iter_mut()
+            .zip(&mut scratch_buffer[block_size..])
+        {
+            *padding_sample += *scratch_sample;
+        }
+    }
+}
+
 /// Add data from the scratch buffer to the specified ring buffer. When writing samples from this
 /// ring buffer back to the host's outputs they must be cleared to prevent infinite feedback.
 #[inline]
@@ -620,3 +839,158 @@ fn add_scratch_to_ring_buffer(scratch_buffer: &[f32], current_pos: usize, ring_b
         *ring_sample += *scratch_sample;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flush_impulse() {
+        const BLOCK_SIZE: usize = 128;
+        const OVERLAP_TIMES: usize = 4;
+
+        let mut stft = StftHelper::<0>::new(2, BLOCK_SIZE, 0);
+        // With a rectangular window the overlapping windows add up to `OVERLAP_TIMES`
+        let mut process_cb = |_channel_idx: usize, real_fft_buffer: &mut [f32]| {
+            for sample in real_fft_buffer {
+                *sample /= OVERLAP_TIMES as f32;
+            }
+        };
+
+        // The impulse is shorter than the latency, so without flushing the output would be silent
+        let mut real_buffers = vec![vec![0.0; 64]; 2];
+        real_buffers[0][10] = 1.0;
+        real_buffers[1][20] = 0.5;
+        let input_energy: f32 = real_buffers.iter().flatten().map(|x| x * x).sum();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(64, |output_slices| {
+                let (first_channel, other_channels) = real_buffers.split_at_mut(1);
+                *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+            })
+        };
+        stft.process_overlap_add(&mut buffer, OVERLAP_TIMES, &mut process_cb);
+        let mut output_energy: f32 = real_buffers.iter().flatten().map(|x| x * x).sum();
+
+        let mut tail_buffers = vec![vec![1.0; stft.flush_length_samples()]; 2];
+        let mut tail_buffer = Buffer::default();
+        unsafe {
+            tail_buffer.set_slices(stft.flush_length_samples(), |output_slices| {
+                let (first_channel, other_channels) = tail_buffers.split_at_mut(1);
+                *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+            })
+        };
+        stft.flush(&mut tail_buffer, OVERLAP_TIMES, &mut process_cb);
+        output_energy += tail_buffers.iter().flatten().map(|x| x * x).sum::<f32>();
+
+        assert!((output_energy - input_energy).abs() < 1e-6);
+        assert_eq!(tail_buffers[0][BLOCK_SIZE - 64 + 10], 1.0);
+        assert_eq!(tail_buffers[1][BLOCK_SIZE - 64 + 20], 0.5);
+    }
+
+    #[test]
+    fn multichannel_matches_per_channel() {
+        const BLOCK_SIZE: usize = 64;
+        const OVERLAP_TIMES: usize = 4;
+        const NUM_SAMPLES: usize = 300;
+
+        let input: Vec<Vec<f32>> = (0..2)
+            .map(|channel_idx| {
+                (0..NUM_SAMPLES)
+                    .map(|sample_idx| ((sample_idx * (channel_idx + 3)) % 17) as f32 / 17.0)
+                    .collect()
+            })
+            .collect();
+
+        // The reference swaps the channels before processing them individually
+        let mut per_channel_stft = StftHelper::<0>::new(2, BLOCK_SIZE, 0);
+        let mut per_channel_output = input.clone();
+        per_channel_output.swap(0, 1);
+        {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(NUM_SAMPLES, |output_slices| {
+                    let (first_channel, other_channels) = per_channel_output.split_at_mut(1);
+                    *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+                })
+            };
+            per_channel_stft.process_overlap_add(
+                &mut buffer,
+                OVERLAP_TIMES,
+                |_, real_fft_buffer| {
+                    for sample in real_fft_buffer {
+                        *sample /= OVERLAP_TIMES as f32;
+                    }
+                },
+            );
+        }
+
+        // While the multichannel version swaps them in the callback, which requires access to both
+        // channels at once
+        let mut multichannel_stft = StftHelper::<0>::new(2, BLOCK_SIZE, 0);
+        let mut multichannel_output = input.clone();
+        {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(NUM_SAMPLES, |output_slices| {
+                    let (first_channel, other_channels) = multichannel_output.split_at_mut(1);
+                    *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+                })
+            };
+            multichannel_stft.process_overlap_add_multichannel(
+                &mut buffer,
+                OVERLAP_TIMES,
+                |real_fft_buffers| {
+                    assert_eq!(real_fft_buffers.len(), 2);
+                    real_fft_buffers.swap(0, 1);
+                    for sample in real_fft_buffers.iter_mut().flatten() {
+                        *sample /= OVERLAP_TIMES as f32;
+                    }
+                },
+            );
+        }
+
+        assert_eq!(multichannel_output, per_channel_output);
+        assert!((multichannel_output[0][BLOCK_SIZE + 10] - input[1][10]).abs() < 1e-6);
+        assert!((multichannel_output[1][BLOCK_SIZE + 10] - input[0][10]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sidechain_subset() {
+        const BLOCK_SIZE: usize = 16;
+        const NUM_SAMPLES: usize = 64;
+
+        let mut stft = StftHelper::<2>::new(1, BLOCK_SIZE, 0);
+        let mut main_channel = vec![0.0; NUM_SAMPLES];
+        let mut sidechain_channel = vec![1.0; NUM_SAMPLES];
+        let mut main_buffer = Buffer::default();
+        let mut sidechain_buffer = Buffer::default();
+        unsafe {
+            main_buffer.set_slices(NUM_SAMPLES, |output_slices| {
+                *output_slices = vec![&mut main_channel];
+            });
+            sidechain_buffer.set_slices(NUM_SAMPLES, |output_slices| {
+                *output_slices = vec![&mut sidechain_channel];
+            });
+        };
+
+        // Only the first sidechain input is passed, so the callback is never called for the second
+        let mut num_sidechain_calls = [0; 2];
+        let mut num_main_calls = 0;
+        stft.process_overlap_add_sidechains(
+            &mut main_buffer,
+            &[&sidechain_buffer],
+            1,
+            |_, sidechain_idx, real_fft_buffer| match sidechain_idx {
+                Some(sidechain_idx) => {
+                    assert!(real_fft_buffer.iter().all(|sample| *sample == 1.0));
+                    num_sidechain_calls[sidechain_idx] += 1;
+                }
+                None => num_main_calls += 1,
+            },
+        );
+
+        assert_eq!(num_main_calls, NUM_SAMPLES / BLOCK_SIZE);
+        assert_eq!(num_sidechain_calls, [NUM_SAMPLES / BLOCK_SIZE, 0]);
+    }
+}
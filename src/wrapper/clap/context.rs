@@ -6,13 +6,15 @@ use clap_sys::id::{clap_id, CLAP_INVALID_ID};
 use clap_sys::string_sizes::CLAP_NAME_SIZE;
 use std::cell::Cell;
 use std::collections::{HashMap, VecDeque};
+use std::ffi::c_void;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use super::wrapper::{OutputParamEvent, Task, Wrapper};
 use crate::event_loop::EventLoop;
 use crate::prelude::{
     ClapPlugin, GuiContext, InitContext, ParamPtr, PluginApi, PluginNoteEvent, ProcessContext,
-    RemoteControlsContext, RemoteControlsPage, RemoteControlsSection, Transport,
+    RawHost, RemoteControlsContext, RemoteControlsPage, RemoteControlsSection, Transport,
 };
 use crate::wrapper::util::strlcpy;
 
@@ -78,6 +80,10 @@ impl<P: ClapPlugin> InitContext<P> for WrapperInitContext<'_, P> {
         PluginApi::Clap
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Clap(self.wrapper.raw_host_callback() as *const c_void)
+    }
+
     fn execute(&self, task: P::BackgroundTask) {
         (self.wrapper.task_executor.lock())(task);
     }
@@ -97,8 +103,15 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         PluginApi::Clap
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Clap(self.wrapper.raw_host_callback() as *const c_void)
+    }
+
     fn execute_background(&self, task: P::BackgroundTask) {
-        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        let priority = P::background_task_priority(&task);
+        let task_posted = self
+            .wrapper
+            .schedule_background(Task::PluginTask(task), priority);
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
@@ -116,6 +129,11 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         self.input_events_guard.pop_front()
     }
 
+    #[inline]
+    fn remaining_events(&self) -> usize {
+        self.input_events_guard.len()
+    }
+
     fn send_event(&mut self, event: PluginNoteEvent<P>) {
         self.output_events_guard.push_back(event);
     }
@@ -127,6 +145,69 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
     fn set_current_voice_capacity(&self, capacity: u32) {
         self.wrapper.set_current_voice_capacity(capacity)
     }
+
+    fn port_names_changed(&self) {
+        self.wrapper.port_names_changed()
+    }
+
+    fn sample_accurate_automation_active(&self) -> bool {
+        self.wrapper
+            .sample_accurate_automation_active
+            .load(Ordering::Relaxed)
+    }
+
+    unsafe fn raw_set_read_only_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => {
+                // Like with the GUI parameter changes, the parameter's value will only be changed
+                // when the output event is written at the end of the processing cycle
+                let clap_plain_value = normalized as f64 * param.step_count().unwrap_or(1) as f64;
+                let success = self
+                    .wrapper
+                    .queue_parameter_event(OutputParamEvent::SetValue {
+                        param_hash: *hash,
+                        clap_plain_value,
+                    });
+
+                nih_debug_assert!(
+                    success,
+                    "Parameter output event queue was full, parameter change will not be sent to \
+                     the host"
+                );
+            }
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    unsafe fn raw_set_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => {
+                // This is sent as a complete gesture so the host records the change as if the user
+                // had changed the parameter. The value is changed when the output events are
+                // written at the end of the processing cycle.
+                let clap_plain_value = normalized as f64 * param.step_count().unwrap_or(1) as f64;
+                let success = self
+                    .wrapper
+                    .queue_parameter_event(OutputParamEvent::BeginGesture { param_hash: *hash })
+                    && self
+                        .wrapper
+                        .queue_parameter_event(OutputParamEvent::SetValue {
+                            param_hash: *hash,
+                            clap_plain_value,
+                        })
+                    && self
+                        .wrapper
+                        .queue_parameter_event(OutputParamEvent::EndGesture { param_hash: *hash });
+
+                nih_debug_assert!(
+                    success,
+                    "Parameter output event queue was full, parameter change will not be sent to \
+                     the host"
+                );
+            }
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
 }
 
 impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
@@ -134,10 +215,26 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
         PluginApi::Clap
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Clap(self.wrapper.raw_host_callback() as *const c_void)
+    }
+
+    fn is_plugin_initialized(&self) -> bool {
+        self.wrapper.plugin_initialized.load(Ordering::Acquire)
+    }
+
+    fn last_transport(&self) -> Option<Transport> {
+        self.wrapper.last_transport.load()
+    }
+
     fn request_resize(&self) -> bool {
         self.wrapper.request_resize()
     }
 
+    fn request_resize_to(&self, width: u32, height: u32) -> bool {
+        self.wrapper.request_resize_to(width, height)
+    }
+
     // All of these functions are supposed to be called from the main thread, so we'll put some
     // trust in the caller and assume that this is indeed the case
     unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
@@ -240,6 +337,10 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object_from_gui(state)
     }
+
+    fn get_default_state(&self) -> crate::wrapper::state::PluginState {
+        self.wrapper.get_default_state_object()
+    }
 }
 
 /// A remote control section. The plugin can fill this with information for one or more pages.
@@ -1,5 +1,9 @@
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::events::{
+    clap_event_header, clap_event_midi_sysex, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI_SYSEX,
+};
 use clap_sys::stream::{clap_istream, clap_ostream};
-use std::mem::MaybeUninit;
+use std::mem::{self, MaybeUninit};
 use std::ops::Deref;
 use std::os::raw::c_void;
 
@@ -85,6 +89,25 @@ impl<T> ClapPtr<T> {
     }
 }
 
+/// Check whether the host marked all of an audio buffer's channels as constant, and whether that
+/// constant value is zero. CLAP doesn't have a dedicated silence flag, so this is the only way to
+/// know that the host guarantees an input to be silent.
+///
+/// # Safety
+///
+/// If `buffer.data32` is not a null pointer, then it must point to `buffer.channel_count` valid
+/// channel pointers containing at least one sample each.
+pub unsafe fn is_constant_silence(buffer: &clap_audio_buffer) -> bool {
+    let num_channels = buffer.channel_count as usize;
+    if buffer.data32.is_null() || num_channels > u64::BITS as usize {
+        return false;
+    }
+
+    (0..num_channels).all(|channel_idx| {
+        buffer.constant_mask & (1 << channel_idx) != 0 && **buffer.data32.add(channel_idx) == 0.0
+    })
+}
+
 /// A buffer a stream can be read into. This is needed to allow reading into uninitialized vectors
 /// using slices without invoking UB.
 ///
@@ -165,3 +188,83 @@ pub fn write_stream(stream: &clap_ostream, slice: &[u8]) -> bool {
 
     true
 }
+
+/// Create an output SysEx event for a message in `sysex_buffer`. The event points to
+/// `sysex_buffer`, so it may only be passed to the host while that buffer is still alive.
+pub fn sysex_event(time: u32, sysex_buffer: &[u8]) -> clap_event_midi_sysex {
+    clap_event_midi_sysex {
+        header: clap_event_header {
+            size: mem::size_of::<clap_event_midi_sysex>() as u32,
+            time,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_: CLAP_EVENT_MIDI_SYSEX,
+            flags: 0,
+        },
+        port_index: 0,
+        // The host _should_ be making a copy of the data if it accepts the event. Should...
+        buffer: sysex_buffer.as_ptr(),
+        size: sysex_buffer.len() as u32,
+    }
+}
+
+/// Get the message from an input SysEx event.
+///
+/// # Safety
+///
+/// `event.buffer` needs to point to at least `event.size` bytes that stay alive for as long as the
+/// returned slice is used.
+pub unsafe fn sysex_event_buffer(event: &clap_event_midi_sysex) -> &[u8] {
+    assert!(!event.buffer.is_null());
+    std::slice::from_raw_parts(event.buffer, event.size as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::sysex::{self, Dump};
+    use crate::midi::{MidiResult, NoteEvent};
+
+    #[test]
+    fn sysex_event_roundtrip() {
+        let mut first_payload = [0; 510];
+        for (idx, byte) in first_payload.iter_mut().enumerate() {
+            *byte = (idx % 128) as u8;
+        }
+        let mut second_payload = first_payload;
+        second_payload.reverse();
+
+        // This follows the same steps as the wrapper's output and input event handling
+        let events = [
+            NoteEvent::MidiSysEx {
+                timing: 4,
+                message: Dump(first_payload),
+            },
+            NoteEvent::MidiSysEx {
+                timing: 5,
+                message: Dump(second_payload),
+            },
+        ];
+        let mut event_times = Vec::new();
+        for event in &events {
+            let (padded_sysex_buffer, length) = match event.clone().as_midi() {
+                Some(MidiResult::SysEx(padded_sysex_buffer, length)) => {
+                    (padded_sysex_buffer, length)
+                }
+                result => panic!("Unexpected result: {result:?}"),
+            };
+            let sysex_buffer =
+                sysex::message_from_padded_buffer(&padded_sysex_buffer, length).unwrap();
+            let clap_event = sysex_event(event.timing(), sysex_buffer);
+            assert_eq!(clap_event.size, 512);
+
+            let received_sysex_buffer = unsafe { sysex_event_buffer(&clap_event) };
+            assert_eq!(received_sysex_buffer, sysex_buffer);
+            let received_event =
+                NoteEvent::from_midi(clap_event.header.time, received_sysex_buffer).unwrap();
+            assert_eq!(received_event, *event);
+
+            event_times.push(clap_event.header.time);
+        }
+        assert_eq!(event_times, [4, 5]);
+    }
+}
@@ -16,8 +16,9 @@ use clap_sys::events::{
     CLAP_TRANSPORT_IS_RECORDING, CLAP_TRANSPORT_IS_WITHIN_PRE_ROLL,
 };
 use clap_sys::ext::audio_ports::{
-    clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS,
-    CLAP_PORT_MONO, CLAP_PORT_STEREO,
+    clap_audio_port_info, clap_host_audio_ports, clap_plugin_audio_ports,
+    CLAP_AUDIO_PORTS_RESCAN_NAMES, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_MONO,
+    CLAP_PORT_STEREO,
 };
 use clap_sys::ext::audio_ports_config::{
     clap_audio_ports_config, clap_plugin_audio_ports_config, CLAP_EXT_AUDIO_PORTS_CONFIG,
@@ -38,7 +39,7 @@ use clap_sys::ext::params::{
     clap_host_params, clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS,
     CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_BYPASS, CLAP_PARAM_IS_HIDDEN,
     CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID, CLAP_PARAM_IS_READONLY,
-    CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_VALUES,
+    CLAP_PARAM_IS_STEPPED, CLAP_PARAM_REQUIRES_PROCESS, CLAP_PARAM_RESCAN_VALUES,
 };
 use clap_sys::ext::render::{
     clap_plugin_render, clap_plugin_render_mode, CLAP_EXT_RENDER, CLAP_RENDER_OFFLINE,
@@ -80,20 +81,25 @@ use std::time::Duration;
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
 use super::descriptor::PluginDescriptor;
 use super::util::ClapPtr;
+use crate::context::process;
 use crate::event_loop::{BackgroundThread, EventLoop, MainThreadExecutor, TASK_QUEUE_CAPACITY};
-use crate::midi::MidiResult;
+use crate::midi::{sysex, HighResolutionCCs, HighResolutionVelocity, MidiResult};
 use crate::prelude::{
-    AsyncExecutor, AudioIOLayout, AuxiliaryBuffers, BufferConfig, ClapPlugin, Editor, MidiConfig,
-    NoteEvent, ParamFlags, ParamPtr, Params, ParentWindowHandle, Plugin, PluginNoteEvent,
-    ProcessMode, ProcessStatus, SysExMessage, TaskExecutor, Transport,
+    AsyncExecutor, AudioIOLayout, AuxOutputPortNames, AuxiliaryBuffers, BufferConfig, ClapPlugin,
+    Editor, MidiConfig, NoteEvent, ParamFlags, ParamPtr, Params, ParentWindowHandle, Plugin,
+    PluginNoteEvent, ProcessMode, ProcessStatus, SysExMessage, TaskExecutor, TaskPriority,
+    Transport,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::clap::context::RemoteControlPages;
-use crate::wrapper::clap::util::{read_stream, write_stream};
+use crate::wrapper::clap::util::{
+    is_constant_silence, read_stream, sysex_event, sysex_event_buffer, write_stream,
+};
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
 use crate::wrapper::util::{
-    clamp_input_event_timing, clamp_output_event_timing, hash_param_id, process_wrapper, strlcpy,
+    aux_output_port_name, catch_panic, clamp_input_event_timing, clamp_output_event_timing,
+    hash_param_id, process_wrapper, silence_outputs, strlcpy, LatencyTracker, ResetFadeIn,
 };
 
 /// How many output parameter changes we can store in our output parameter change queue. Storing
@@ -108,6 +114,9 @@ pub struct Wrapper<P: ClapPlugin> {
     plugin: Mutex<P>,
     /// The plugin's background task executor closure.
     pub task_executor: Mutex<TaskExecutor<P>>,
+    /// The plugin's auxiliary output port name overrides, if it has any. See
+    /// [`Plugin::aux_output_port_names()`]. These are called without locking the plugin.
+    aux_output_port_names: Option<AuxOutputPortNames>,
     /// The plugin's parameters. These are fetched once during initialization. That way the
     /// `ParamPtr`s are guaranteed to live at least as long as this object and we can interact with
     /// the `Params` object without having to acquire a lock on `plugin`.
@@ -126,6 +135,10 @@ pub struct Wrapper<P: ClapPlugin> {
     editor_scaling_factor: AtomicF32,
 
     is_processing: AtomicBool,
+    /// Whether the host has sent a parameter change in the middle of a buffer since processing was
+    /// last started. Only set when `P::SAMPLE_ACCURATE_AUTOMATION` is enabled. Exposed through
+    /// `ProcessContext::sample_accurate_automation_active()`.
+    pub sample_accurate_automation_active: AtomicBool,
     /// The current IO configuration, modified through the `clap_plugin_audio_ports_config`
     /// extension. Initialized to the plugin's first audio IO configuration.
     current_audio_io_layout: AtomicCell<AudioIOLayout>,
@@ -134,6 +147,9 @@ pub struct Wrapper<P: ClapPlugin> {
     current_buffer_config: AtomicCell<Option<BufferConfig>>,
     /// The current audio processing mode. Set through the render extension. Defaults to realtime.
     pub current_process_mode: AtomicCell<ProcessMode>,
+    /// Whether `Plugin::initialize()` has succeeded at least once. Exposed through
+    /// `GuiContext::is_plugin_initialized()`.
+    pub plugin_initialized: AtomicBool,
     /// The incoming events for the plugin, if `P::MIDI_INPUT` is set to `MidiConfig::Basic` or
     /// higher.
     ///
@@ -143,14 +159,26 @@ pub struct Wrapper<P: ClapPlugin> {
     /// Stores any events the plugin has output during the current processing cycle, analogous to
     /// `input_events`.
     output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Combines MIDI 1.0 High Resolution Velocity Prefix CCs from raw MIDI input events with the
+    /// velocity of the next note event on the same channel.
+    high_resolution_velocity: AtomicRefCell<HighResolutionVelocity>,
+    /// Coalesces two message 14-bit MIDI CCs from raw MIDI input events.
+    high_resolution_ccs: AtomicRefCell<HighResolutionCCs>,
     /// The last process status returned by the plugin. This is used for tail handling.
     last_process_status: AtomicCell<ProcessStatus>,
-    /// The current latency in samples, as set by the plugin through the [`ProcessContext`]. Uses
-    /// the latency extension.
-    pub current_latency: AtomicU32,
+    /// A copy of the transport information from the most recent process call, or `None` if the
+    /// plugin has not processed any audio yet. Used for `GuiContext::last_transport()`.
+    pub last_transport: AtomicCell<Option<Transport>>,
+    /// The current latency in samples, as set by the plugin through the [`ProcessContext`], and
+    /// the latency the host last queried through the latency extension. Decides when a
+    /// [`Task::LatencyChanged`] needs to be posted.
+    latency: LatencyTracker,
     /// A data structure that helps manage and create buffers for all of the plugin's inputs and
     /// outputs based on channel pointers provided by the host.
     buffer_manager: AtomicRefCell<BufferManager>,
+    /// The fade-in applied to the plugin's outputs after the host resets the plugin. See
+    /// [`Plugin::RESET_FADE_IN_MS`][crate::prelude::Plugin::RESET_FADE_IN_MS].
+    reset_fade_in: AtomicRefCell<ResetFadeIn>,
     /// The plugin is able to restore state through a method on the `GuiContext`. To avoid changing
     /// parameters mid-processing and running into garbled data if the host also tries to load state
     /// at the same time the restoring happens at the end of each processing call. If this zero
@@ -176,6 +204,7 @@ pub struct Wrapper<P: ClapPlugin> {
     _plugin_descriptor: Box<PluginDescriptor>,
 
     clap_plugin_audio_ports: clap_plugin_audio_ports,
+    host_audio_ports: AtomicRefCell<Option<ClapPtr<clap_host_audio_ports>>>,
 
     clap_plugin_gui: clap_plugin_gui,
     host_gui: AtomicRefCell<Option<ClapPtr<clap_host_gui>>>,
@@ -211,6 +240,10 @@ pub struct Wrapper<P: ClapPlugin> {
     /// having to add a setter function to the parameter (or even worse, have it be completely
     /// untyped).
     pub param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// A reusable buffer for formatting parameter values when the host requests a parameter's
+    /// string representation. This avoids allocations when the host frequently polls these strings.
+    /// The buffer starts out with room for 128 bytes, which is enough for most parameter values.
+    param_string_scratch: Mutex<String>,
     /// For all polyphonically modulatable parameters, mappings from the parameter hash's hash to
     /// the parameter's poly modulation ID. These IDs are then passed to the plugin, so it can
     /// quickly refer to parameter by matching on constant IDs.
@@ -279,6 +312,8 @@ pub enum Task<P: Plugin> {
     VoiceInfoChanged,
     /// Tell the host that it should rescan the current parameter values.
     RescanParamValues,
+    /// Tell the host that it should rescan the audio port names.
+    RescanAudioPortNames,
 }
 
 /// The types of CLAP parameter updates for events.
@@ -335,12 +370,12 @@ impl<P: ClapPlugin> EventLoop<Task<P>, Wrapper<P>> for Wrapper<P> {
         }
     }
 
-    fn schedule_background(&self, task: Task<P>) -> bool {
+    fn schedule_background(&self, task: Task<P>, priority: TaskPriority) -> bool {
         self.background_thread
             .borrow()
             .as_ref()
             .unwrap()
-            .schedule(task)
+            .schedule(task, priority)
     }
 
     fn is_main_thread(&self) -> bool {
@@ -361,11 +396,17 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
     fn execute(&self, task: Task<P>, is_gui_thread: bool) {
         // This function is always called from the main thread, from [Self::on_main_thread].
         match task {
-            Task::PluginTask(task) => (self.task_executor.lock())(task),
+            Task::PluginTask(task) => {
+                catch_panic("Plugin::task_executor()", || {
+                    (self.task_executor.lock())(task)
+                });
+            }
             Task::ParameterValuesChanged => {
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
-                        editor.lock().param_values_changed();
+                        catch_panic("Editor::param_values_changed()", || {
+                            editor.lock().param_values_changed()
+                        });
                     }
                 }
             }
@@ -373,9 +414,11 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
-                        editor
-                            .lock()
-                            .param_value_changed(param_id, normalized_value);
+                        catch_panic("Editor::param_value_changed()", || {
+                            editor
+                                .lock()
+                                .param_value_changed(param_id, normalized_value)
+                        });
                     }
                 }
             }
@@ -383,9 +426,11 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
-                        editor
-                            .lock()
-                            .param_modulation_changed(param_id, modulation_offset);
+                        catch_panic("Editor::param_modulation_changed()", || {
+                            editor
+                                .lock()
+                                .param_modulation_changed(param_id, modulation_offset)
+                        });
                     }
                 }
             }
@@ -393,6 +438,14 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 Some(host_latency) => {
                     nih_debug_assert!(is_gui_thread);
 
+                    // Any latency changes made after this point need to be sent to the host again.
+                    // The host will query the most recent latency regardless. If the latency has
+                    // since been changed back to the value the host already knows about, then
+                    // there's no need to notify the host at all.
+                    if !self.latency.take_pending() {
+                        return;
+                    }
+
                     // XXX: The CLAP docs mention that you should request a restart if this happens
                     //      while the plugin is activated (which is not entirely the same thing as
                     //      is processing, but we'll treat it as the same thing). In practice just
@@ -403,7 +456,10 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                         unsafe_clap_call! { host_latency=>changed(&*self.host_callback) };
                     }
                 }
-                None => nih_debug_assert_failure!("Host does not support the latency extension"),
+                None => {
+                    self.latency.cancel_pending();
+                    nih_debug_assert_failure!("Host does not support the latency extension")
+                }
             },
             Task::VoiceInfoChanged => match &*self.host_voice_info.borrow() {
                 Some(host_voice_info) => {
@@ -419,6 +475,23 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
                 None => nih_debug_assert_failure!("The host does not support parameters? What?"),
             },
+            Task::RescanAudioPortNames => match &*self.host_audio_ports.borrow() {
+                Some(host_audio_ports) => {
+                    nih_debug_assert!(is_gui_thread);
+
+                    // Unlike most other rescan flags, this one is allowed while the plugin is
+                    // activated
+                    unsafe_clap_call! {
+                        host_audio_ports=>rescan(
+                            &*self.host_callback,
+                            CLAP_AUDIO_PORTS_RESCAN_NAMES,
+                        )
+                    };
+                }
+                None => {
+                    nih_debug_assert_failure!("Host does not support the audio-ports extension")
+                }
+            },
         };
     }
 }
@@ -430,6 +503,7 @@ impl<P: ClapPlugin> Wrapper<P> {
     pub unsafe fn new(host_callback: *const clap_host) -> Arc<Self> {
         let mut plugin = P::default();
         let task_executor = Mutex::new(plugin.task_executor());
+        let aux_output_port_names = plugin.aux_output_port_names();
 
         // This is used to allow the plugin to restore preset data from its editor, see the comment
         // on `Self::updated_state_sender`
@@ -537,6 +611,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
             plugin: Mutex::new(plugin),
             task_executor,
+            aux_output_port_names,
             params,
             // Initialized later as it needs a reference to the wrapper for the async executor
             editor: AtomicRefCell::new(None),
@@ -544,21 +619,27 @@ impl<P: ClapPlugin> Wrapper<P> {
             editor_scaling_factor: AtomicF32::new(1.0),
 
             is_processing: AtomicBool::new(false),
+            sample_accurate_automation_active: AtomicBool::new(false),
             current_audio_io_layout: AtomicCell::new(
                 P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
             ),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
+            plugin_initialized: AtomicBool::new(false),
             input_events: AtomicRefCell::new(VecDeque::with_capacity(512)),
             output_events: AtomicRefCell::new(VecDeque::with_capacity(512)),
+            high_resolution_velocity: AtomicRefCell::new(HighResolutionVelocity::new()),
+            high_resolution_ccs: AtomicRefCell::new(HighResolutionCCs::new()),
             last_process_status: AtomicCell::new(ProcessStatus::Normal),
-            current_latency: AtomicU32::new(0),
+            last_transport: AtomicCell::new(None),
+            latency: LatencyTracker::default(),
             // This is initialized just before calling `Plugin::initialize()` so that during the
             // process call buffers can be initialized without any allocations
             buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
                 0,
                 AudioIOLayout::default(),
             )),
+            reset_fade_in: AtomicRefCell::new(ResetFadeIn::default()),
             updated_state_sender,
             updated_state_receiver,
 
@@ -595,6 +676,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 count: Some(Self::ext_audio_ports_count),
                 get: Some(Self::ext_audio_ports_get),
             },
+            host_audio_ports: AtomicRefCell::new(None),
 
             clap_plugin_gui: clap_plugin_gui {
                 is_api_supported: Some(Self::ext_gui_is_api_supported),
@@ -640,6 +722,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             param_group_by_hash,
             param_id_to_hash,
             param_ptr_to_hash,
+            param_string_scratch: Mutex::new(String::with_capacity(128)),
             poly_mod_ids_by_hash,
             output_parameter_events: ArrayQueue::new(OUTPUT_EVENT_QUEUE_CAPACITY),
 
@@ -705,7 +788,9 @@ impl<P: ClapPlugin> Wrapper<P> {
                     let wrapper = wrapper.clone();
 
                     move |task| {
-                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        let priority = P::background_task_priority(&task);
+                        let task_posted =
+                            wrapper.schedule_background(Task::PluginTask(task), priority);
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
@@ -747,6 +832,8 @@ impl<P: ClapPlugin> Wrapper<P> {
     }
 
     fn make_process_context(&self, transport: Transport) -> WrapperProcessContext<'_, P> {
+        self.last_transport.store(Some(transport));
+
         WrapperProcessContext {
             wrapper: self,
             input_events_guard: self.input_events.borrow_mut(),
@@ -755,6 +842,36 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// Snap all parameter smoothers to their current values if the plugin has enabled
+    /// [`Plugin::RESET_SMOOTHERS_ON_RESET`][crate::prelude::Plugin::RESET_SMOOTHERS_ON_RESET]. This
+    /// should be called right before the host-initiated `Plugin::reset()` calls.
+    pub fn reset_smoothers_if_enabled(&self) {
+        if !P::RESET_SMOOTHERS_ON_RESET {
+            return;
+        }
+
+        if let Some(buffer_config) = self.current_buffer_config.load() {
+            for param in self.param_by_hash.values() {
+                unsafe { param.update_smoother(buffer_config.sample_rate, true) };
+            }
+        }
+    }
+
+    /// Start the fade-in configured through
+    /// [`Plugin::RESET_FADE_IN_MS`][crate::prelude::Plugin::RESET_FADE_IN_MS], if any. This should
+    /// be called right after the host-initiated `Plugin::reset()` calls.
+    pub fn start_reset_fade_in_if_enabled(&self) {
+        if P::RESET_FADE_IN_MS <= 0.0 {
+            return;
+        }
+
+        if let Some(buffer_config) = self.current_buffer_config.load() {
+            self.reset_fade_in
+                .borrow_mut()
+                .start(P::RESET_FADE_IN_MS, buffer_config.sample_rate);
+        }
+    }
+
     /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
     /// gesture checks.
     #[allow(unused)]
@@ -765,6 +882,12 @@ impl<P: ClapPlugin> Wrapper<P> {
             .map(|s| s.as_str())
     }
 
+    /// The host's `clap_host` pointer this wrapper was created with. Used for
+    /// [`RawHost::Clap`][crate::prelude::RawHost::Clap].
+    pub fn raw_host_callback(&self) -> *const clap_host {
+        &*self.host_callback as *const clap_host
+    }
+
     /// Queue a parameter output event to be sent to the host at the end of the audio processing
     /// cycle, and request a parameter flush from the host if the plugin is not currently processing
     /// audio. The parameter's actual value will only be updated at that point so the value won't
@@ -810,6 +933,33 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// Change the editor's size and then request a resize from the host. If the host rejects the
+    /// new size, then the editor's old size is restored.
+    pub fn request_resize_to(&self, width: u32, height: u32) -> bool {
+        let old_size = match self.editor.borrow().as_ref() {
+            Some(editor) => {
+                let editor = editor.lock();
+                let old_size = editor.size();
+                if !editor.set_size(width, height) {
+                    return false;
+                }
+
+                old_size
+            }
+            None => return false,
+        };
+
+        let success = self.request_resize();
+        if !success {
+            if let Some(editor) = self.editor.borrow().as_ref() {
+                let (old_width, old_height) = old_size;
+                editor.lock().set_size(old_width, old_height);
+            }
+        }
+
+        success
+    }
+
     /// Convenience function for setting a value for a parameter as triggered by a VST3 parameter
     /// update. The same rate is for updating parameter smoothing.
     ///
@@ -1318,7 +1468,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 | NoteEvent::MidiPitchBend { .. }
                 | NoteEvent::MidiCC { .. }
                 | NoteEvent::MidiProgramChange { .. })
-                    if P::MIDI_OUTPUT >= MidiConfig::MidiCCs =>
+                    if P::MIDI_OUTPUT >= MidiConfig::MidiCCOnly =>
                 {
                     // NIH-plug already includes MIDI conversion functions, so we'll reuse those for
                     // the MIDI events
@@ -1349,24 +1499,14 @@ impl<P: ClapPlugin> Wrapper<P> {
                 {
                     // SysEx is supported on the basic MIDI config so this is separate
                     let (padded_sysex_buffer, length) = message.to_buffer();
-                    let padded_sysex_buffer = padded_sysex_buffer.borrow();
-                    nih_debug_assert!(padded_sysex_buffer.len() >= length);
-                    let sysex_buffer = &padded_sysex_buffer[..length];
-
-                    let event = clap_event_midi_sysex {
-                        header: clap_event_header {
-                            size: mem::size_of::<clap_event_midi_sysex>() as u32,
-                            time,
-                            space_id: CLAP_CORE_EVENT_SPACE_ID,
-                            type_: CLAP_EVENT_MIDI_SYSEX,
-                            flags: 0,
-                        },
-                        port_index: 0,
-                        // The host _should_ be making a copy of the data if it accepts the event. Should...
-                        buffer: sysex_buffer.as_ptr(),
-                        size: sysex_buffer.len() as u32,
+                    let Some(sysex_buffer) =
+                        sysex::message_from_padded_buffer(padded_sysex_buffer.borrow(), length)
+                    else {
+                        continue;
                     };
 
+                    let event = sysex_event(time, sysex_buffer);
+
                     clap_call! { out=>try_push(out, &event.header) }
                 }
                 _ => {
@@ -1482,7 +1622,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_ON) => {
-                if P::MIDI_INPUT >= MidiConfig::Basic {
+                if P::MIDI_INPUT >= MidiConfig::Basic
+                    && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+                    && P::MIDI_INPUT_NOTES
+                {
                     let event = &*(event as *const clap_event_note);
                     input_events.push_back(NoteEvent::NoteOn {
                         // When splitting up the buffer for sample accurate automation all events
@@ -1500,7 +1643,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_OFF) => {
-                if P::MIDI_INPUT >= MidiConfig::Basic {
+                if P::MIDI_INPUT >= MidiConfig::Basic
+                    && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+                    && P::MIDI_INPUT_NOTES
+                {
                     let event = &*(event as *const clap_event_note);
                     input_events.push_back(NoteEvent::NoteOff {
                         timing,
@@ -1516,7 +1662,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_CHOKE) => {
-                if P::MIDI_INPUT >= MidiConfig::Basic {
+                if P::MIDI_INPUT >= MidiConfig::Basic
+                    && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+                    && P::MIDI_INPUT_NOTES
+                {
                     let event = &*(event as *const clap_event_note);
                     input_events.push_back(NoteEvent::Choke {
                         timing,
@@ -1532,7 +1681,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_EXPRESSION) => {
-                if P::MIDI_INPUT >= MidiConfig::Basic {
+                if P::MIDI_INPUT >= MidiConfig::Basic
+                    && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+                    && P::MIDI_INPUT_NOTES
+                {
                     // TODO: Add support for the other expression types
                     let event = &*(event as *const clap_event_note_expression);
                     match event.expression_id {
@@ -1635,22 +1787,41 @@ impl<P: ClapPlugin> Wrapper<P> {
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI) => {
                 // In the Basic note port type, we'll still handle note on, note off, and polyphonic
                 // pressure events if the host sents us those. But we'll throw away any other MIDI
-                // messages to stay consistent with the VST3 wrapper.
+                // messages to stay consistent with the VST3 wrapper. These note events are also
+                // thrown away in the MidiCCOnly note port type or if the plugin opted out of
+                // receiving note events.
                 let event = &*(event as *const clap_event_midi);
 
-                match NoteEvent::from_midi(timing, &event.data) {
-                    Ok(
-                        note_event @ (NoteEvent::NoteOn { .. }
-                        | NoteEvent::NoteOff { .. }
-                        | NoteEvent::PolyPressure { .. }),
-                    ) if P::MIDI_INPUT >= MidiConfig::Basic => {
-                        input_events.push_back(note_event);
+                let mut note_event = match NoteEvent::from_midi(timing, &event.data) {
+                    Ok(note_event) => note_event,
+                    Err(n) => {
+                        nih_debug_assert_failure!("Unhandled MIDI message type {}", n);
+                        return;
+                    }
+                };
+
+                // This also needs to see the prefix CCs when the plugin doesn't receive MIDI CCs
+                self.high_resolution_velocity
+                    .borrow_mut()
+                    .process(&mut note_event);
+                self.high_resolution_ccs
+                    .borrow_mut()
+                    .process(&mut note_event);
+                match note_event {
+                    NoteEvent::NoteOn { .. }
+                    | NoteEvent::NoteOff { .. }
+                    | NoteEvent::PolyPressure { .. } => {
+                        if P::MIDI_INPUT >= MidiConfig::Basic
+                            && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+                            && P::MIDI_INPUT_NOTES
+                        {
+                            input_events.push_back(note_event);
+                        }
                     }
-                    Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                    _ if P::MIDI_INPUT >= MidiConfig::MidiCCOnly => {
                         input_events.push_back(note_event);
                     }
-                    Ok(_) => (),
-                    Err(n) => nih_debug_assert_failure!("Unhandled MIDI message type {}", n),
+                    _ => (),
                 };
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI_SYSEX)
@@ -1660,8 +1831,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
                 // `NoteEvent::from_midi` prints some tracing if parsing fails, which is not
                 // necessarily an error
-                assert!(!event.buffer.is_null());
-                let sysex_buffer = std::slice::from_raw_parts(event.buffer, event.size as usize);
+                let sysex_buffer = sysex_event_buffer(event);
                 if let Ok(note_event) = NoteEvent::from_midi(timing, sysex_buffer) {
                     input_events.push_back(note_event);
                 };
@@ -1688,6 +1858,17 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// Get a state object containing the default values for all of the plugin's parameters. May be
+    /// called by the plugin's GUI to reset the parameters.
+    pub fn get_default_state_object(&self) -> PluginState {
+        unsafe {
+            state::serialize_default_object::<P>(state::make_params_iter(
+                &self.param_by_hash,
+                &self.param_id_to_hash,
+            ))
+        }
+    }
+
     /// Update the plugin's internal state, called by the plugin itself from the GUI thread. To
     /// prevent corrupting data and changing parameters during processing the actual state is only
     /// updated at the end of the audio processing cycle.
@@ -1724,7 +1905,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             } else {
                 // Otherwise we'll set the state right here and now, since this function should be
                 // called from a GUI thread
-                self.set_state_inner(&mut state);
+                self.set_state_inner(&mut state, false);
                 break;
             }
         }
@@ -1738,13 +1919,33 @@ impl<P: ClapPlugin> Wrapper<P> {
         // Only make a callback if it's actually needed
         // XXX: For CLAP we could move this handling to the Plugin struct, but it may be worthwhile
         //      to keep doing it this way to stay consistent with VST3.
-        let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
-        if old_latency != samples {
-            let task_posted = self.schedule_gui(Task::LatencyChanged);
-            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        let defer = P::DEFER_LATENCY_CHANGES && self.is_processing.load(Ordering::SeqCst);
+        if self.latency.set(samples, defer) {
+            self.notify_latency_changed();
+        }
+    }
+
+    /// Notify the host about a latency change that was deferred while the plugin was processing
+    /// audio. Called when the host stops processing audio and when it deactivates the plugin.
+    fn flush_deferred_latency_change(&self) {
+        if self.latency.flush_deferred() {
+            self.notify_latency_changed();
         }
     }
 
+    fn notify_latency_changed(&self) {
+        let task_posted = self.schedule_gui(Task::LatencyChanged);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        if !task_posted {
+            self.latency.cancel_pending();
+        }
+    }
+
+    pub fn port_names_changed(&self) {
+        let task_posted = self.schedule_gui(Task::RescanAudioPortNames);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
     pub fn set_current_voice_capacity(&self, capacity: u32) {
         match P::CLAP_POLY_MODULATION_CONFIG {
             Some(config) => {
@@ -1773,13 +1974,15 @@ impl<P: ClapPlugin> Wrapper<P> {
     /// state is set from a couple places, so this function aims to deduplicate that. Includes
     /// `permit_alloc()`s around the deserialization and initialization for the use case where
     /// `set_state_object_from_gui()` was called while the plugin is process audio.
+    /// If `smooth_params` is set, then the parameters' smoothers will ramp to the restored values
+    /// instead of being reset.
     ///
     /// Implicitly emits `Task::ParameterValuesChanged`.
     ///
     /// # Notes
     ///
     /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
-    pub fn set_state_inner(&self, state: &mut PluginState) -> bool {
+    pub fn set_state_inner(&self, state: &mut PluginState, smooth_params: bool) -> bool {
         let audio_io_layout = self.current_audio_io_layout.load();
         let buffer_config = self.current_buffer_config.load();
 
@@ -1789,13 +1992,17 @@ impl<P: ClapPlugin> Wrapper<P> {
         //        supports runtime preset loading.  `state::deserialize_object()` normally never
         //        allocates, but if the plugin has persistent non-parameter data then its
         //        `deserialize_fields()` implementation may still allocate.
-        let mut success = permit_alloc(|| unsafe {
-            state::deserialize_object::<P>(
-                state,
-                self.params.clone(),
-                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
-                self.current_buffer_config.load().as_ref(),
-            )
+        let mut success = permit_alloc(|| {
+            catch_panic("Params::deserialize_fields()", || unsafe {
+                state::deserialize_object::<P>(
+                    state,
+                    self.params.clone(),
+                    state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    self.current_buffer_config.load().as_ref(),
+                    smooth_params,
+                )
+            })
+            .unwrap_or(false)
         });
         if !success {
             nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
@@ -1810,10 +2017,13 @@ impl<P: ClapPlugin> Wrapper<P> {
 
             // See above
             success = permit_alloc(|| {
-                plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+                catch_panic("Plugin::initialize()", || {
+                    plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+                })
+                .unwrap_or(false)
             });
             if success {
-                process_wrapper(|| plugin.reset());
+                process_wrapper(|| catch_panic("Plugin::reset()", || plugin.reset()));
             }
         }
 
@@ -1843,6 +2053,10 @@ impl<P: ClapPlugin> Wrapper<P> {
         // We weren't allowed to query these in the constructor, so we need to do it now instead.
         *wrapper.host_gui.borrow_mut() =
             query_host_extension::<clap_host_gui>(&wrapper.host_callback, CLAP_EXT_GUI);
+        *wrapper.host_audio_ports.borrow_mut() = query_host_extension::<clap_host_audio_ports>(
+            &wrapper.host_callback,
+            CLAP_EXT_AUDIO_PORTS,
+        );
         *wrapper.host_latency.borrow_mut() =
             query_host_extension::<clap_host_latency>(&wrapper.host_callback, CLAP_EXT_LATENCY);
         *wrapper.host_params.borrow_mut() =
@@ -1876,6 +2090,15 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(false, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
+        // Plugins can rely on the sample rate being valid, so we'll refuse to activate otherwise
+        if !(sample_rate.is_finite() && sample_rate > 0.0) {
+            nih_debug_assert_failure!(
+                "The host tried to activate the plugin with a sample rate of {}",
+                sample_rate
+            );
+            return false;
+        }
+
         let audio_io_layout = wrapper.current_audio_io_layout.load();
         let buffer_config = BufferConfig {
             sample_rate: sample_rate as f32,
@@ -1892,7 +2115,11 @@ impl<P: ClapPlugin> Wrapper<P> {
         // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
         let mut init_context = wrapper.make_init_context();
         let mut plugin = wrapper.plugin.lock();
-        if plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context) {
+        let initialized = catch_panic("Plugin::initialize()", || {
+            plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+        })
+        .unwrap_or(false);
+        if initialized {
             // NOTE: `Plugin::reset()` is called in `clap_plugin::start_processing()` instead of in
             //       this function
 
@@ -1903,6 +2130,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
             // Also store this for later, so we can reinitialize the plugin after restoring state
             wrapper.current_buffer_config.store(Some(buffer_config));
+            wrapper.plugin_initialized.store(true, Ordering::Release);
 
             true
         } else {
@@ -1914,7 +2142,10 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!((), plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        wrapper.plugin.lock().deactivate();
+        catch_panic("Plugin::deactivate()", || {
+            wrapper.plugin.lock().deactivate()
+        });
+        wrapper.flush_deferred_latency_change();
     }
 
     unsafe extern "C" fn start_processing(plugin: *const clap_plugin) -> bool {
@@ -1926,10 +2157,15 @@ impl<P: ClapPlugin> Wrapper<P> {
         // Always reset the processing status when the plugin gets activated or deactivated
         wrapper.last_process_status.store(ProcessStatus::Normal);
         wrapper.is_processing.store(true, Ordering::SeqCst);
+        wrapper
+            .sample_accurate_automation_active
+            .store(false, Ordering::SeqCst);
 
         // To be consistent with the VST3 wrapper, we'll also reset the buffers here in addition to
         // the dedicated `reset()` function.
-        process_wrapper(|| wrapper.plugin.lock().reset());
+        wrapper.reset_smoothers_if_enabled();
+        process_wrapper(|| catch_panic("Plugin::reset()", || wrapper.plugin.lock().reset()));
+        wrapper.start_reset_fade_in_if_enabled();
 
         true
     }
@@ -1939,13 +2175,16 @@ impl<P: ClapPlugin> Wrapper<P> {
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
         wrapper.is_processing.store(false, Ordering::SeqCst);
+        wrapper.flush_deferred_latency_change();
     }
 
     unsafe extern "C" fn reset(plugin: *const clap_plugin) {
         check_null_ptr!((), plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        process_wrapper(|| wrapper.plugin.lock().reset());
+        wrapper.reset_smoothers_if_enabled();
+        process_wrapper(|| catch_panic("Plugin::reset()", || wrapper.plugin.lock().reset()));
+        wrapper.start_reset_fade_in_if_enabled();
     }
 
     unsafe extern "C" fn process(
@@ -1994,22 +2233,36 @@ impl<P: ClapPlugin> Wrapper<P> {
                             // changes after the current sample if sample accurate automation is
                             // enabled
                             if P::SAMPLE_ACCURATE_AUTOMATION {
-                                match ((*next_event).space_id, (*next_event).type_) {
-                                    (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE)
-                                    | (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_TRANSPORT) => true,
-                                    (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_MOD) => {
-                                        let next_event =
-                                            &*(next_event as *const clap_event_param_mod);
-
-                                        // The buffer should not be split on polyphonic modulation
-                                        // as those events will be converted to note events
-                                        !(next_event.note_id != -1
-                                            && wrapper
-                                                .poly_mod_ids_by_hash
-                                                .contains_key(&next_event.param_id))
-                                    }
-                                    _ => false,
+                                let split_on_param_change =
+                                    match ((*next_event).space_id, (*next_event).type_) {
+                                        (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_TRANSPORT) => {
+                                            return true
+                                        }
+                                        (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE) => true,
+                                        (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_MOD) => {
+                                            let next_event =
+                                                &*(next_event as *const clap_event_param_mod);
+
+                                            // The buffer should not be split on polyphonic
+                                            // modulation as those events will be converted to
+                                            // note events
+                                            !(next_event.note_id != -1
+                                                && wrapper
+                                                    .poly_mod_ids_by_hash
+                                                    .contains_key(&next_event.param_id))
+                                        }
+                                        _ => false,
+                                    };
+
+                                // This predicate is only called for events after the current
+                                // sample, so this parameter change happens mid-buffer
+                                if split_on_param_change {
+                                    wrapper
+                                        .sample_accurate_automation_active
+                                        .store(true, Ordering::Relaxed);
                                 }
+
+                                split_on_param_change
                             } else {
                                 matches!(
                                     ((*next_event).space_id, (*next_event).type_,),
@@ -2039,8 +2292,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 // The buffer manager preallocated buffer slices for all the IO and storage for any
                 // axuiliary inputs.
                 // TODO: The audio buffers have a latency field, should we use those?
-                // TODO: Like with VST3, should we expose some way to access or set the silence/constant
-                //       flags?
+                // TODO: Should we also expose the constant flags for the main IO?
                 let mut buffer_manager = wrapper.buffer_manager.borrow_mut();
                 let buffers =
                     buffer_manager.create_buffers(block_start, block_len, |buffer_source| {
@@ -2091,6 +2343,11 @@ impl<P: ClapPlugin> Wrapper<P> {
 
                                         *aux_input_channel_pointers =
                                             Some(ChannelPointers { ptrs, num_channels });
+                                        // The constant mask applies to the entire buffer, so this
+                                        // also holds for every block when splitting the buffer
+                                        buffer_source.aux_input_silent[aux_input_no] =
+                                            process.frames_count > 0
+                                                && is_constant_silence(audio_input);
                                     }
                                     None => continue,
                                 }
@@ -2160,7 +2417,15 @@ impl<P: ClapPlugin> Wrapper<P> {
                     transport.preroll_active =
                         Some(context.flags & CLAP_TRANSPORT_IS_WITHIN_PRE_ROLL != 0);
                     if context.flags & CLAP_TRANSPORT_HAS_TEMPO != 0 {
-                        transport.tempo = Some(context.tempo);
+                        // The tempo may be ramping, in which case we need to compensate for the
+                        // block splitting here as well
+                        if P::SAMPLE_ACCURATE_AUTOMATION && block_start > 0 {
+                            transport.tempo =
+                                Some(context.tempo + (context.tempo_inc * block_start as f64));
+                        } else {
+                            transport.tempo = Some(context.tempo);
+                        }
+                        transport.tempo_increment = Some(context.tempo_inc);
                     }
                     if context.flags & CLAP_TRANSPORT_HAS_TIME_SIGNATURE != 0 {
                         transport.time_sig_numerator = Some(context.tsig_num as i32);
@@ -2178,8 +2443,12 @@ impl<P: ClapPlugin> Wrapper<P> {
                         {
                             transport.pos_beats = Some(
                                 beats
-                                    + (block_start as f64 / sample_rate as f64 / 60.0
-                                        * context.tempo),
+                                    + process::beats_elapsed(
+                                        context.tempo,
+                                        context.tempo_inc,
+                                        sample_rate,
+                                        block_start as u32,
+                                    ),
                             );
                         } else {
                             transport.pos_beats = Some(beats);
@@ -2188,11 +2457,8 @@ impl<P: ClapPlugin> Wrapper<P> {
                     if context.flags & CLAP_TRANSPORT_HAS_SECONDS_TIMELINE != 0 {
                         let seconds = context.song_pos_seconds as f64 / CLAP_SECTIME_FACTOR as f64;
 
-                        // Same here
-                        if P::SAMPLE_ACCURATE_AUTOMATION
-                            && block_start > 0
-                            && (context.flags & CLAP_TRANSPORT_HAS_TEMPO != 0)
-                        {
+                        // Same here, but this doesn't depend on the tempo
+                        if P::SAMPLE_ACCURATE_AUTOMATION && block_start > 0 {
                             transport.pos_seconds =
                                 Some(seconds + (block_start as f64 / sample_rate as f64));
                         } else {
@@ -2245,7 +2511,17 @@ impl<P: ClapPlugin> Wrapper<P> {
                         outputs: buffers.aux_outputs,
                     };
                     let mut context = wrapper.make_process_context(transport);
-                    let result = plugin.process(buffers.main_buffer, &mut aux, &mut context);
+                    let result = catch_panic("Plugin::process()", || {
+                        plugin.process(buffers.main_buffer, &mut aux, &mut context)
+                    })
+                    .unwrap_or_else(|| {
+                        silence_outputs(buffers.main_buffer, &mut aux);
+                        ProcessStatus::Error("The plugin panicked while processing audio")
+                    });
+                    wrapper
+                        .reset_fade_in
+                        .borrow_mut()
+                        .apply(buffers.main_buffer, &mut aux);
                     wrapper.last_process_status.store(result);
                     result
                 } else {
@@ -2287,7 +2563,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             //        doesn't do that
             let updated_state = permit_alloc(|| wrapper.updated_state_receiver.try_recv());
             if let Ok(mut state) = updated_state {
-                wrapper.set_state_inner(&mut state);
+                wrapper.set_state_inner(&mut state, P::SMOOTH_RUNTIME_STATE_RESTORES);
 
                 // We'll pass the state object back to the GUI thread so deallocation can happen
                 // there without potentially blocking the audio thread
@@ -2555,11 +2831,15 @@ impl<P: ClapPlugin> Wrapper<P> {
             }
             (false, false) => {
                 let aux_output_idx = if has_main_output { index - 1 } else { index } as usize;
+                // The plugin can override these names to reflect its current state
                 strlcpy(
                     &mut info.name,
-                    &current_audio_io_layout
-                        .aux_output_name(aux_output_idx)
-                        .expect("Out of bounds auxiliary output port"),
+                    &aux_output_port_name(
+                        wrapper.aux_output_port_names.as_ref(),
+                        &current_audio_io_layout,
+                        aux_output_idx,
+                    )
+                    .expect("Out of bounds auxiliary output port"),
                 );
             }
         };
@@ -2658,7 +2938,9 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         let mut editor_handle = wrapper.editor_handle.lock();
         if editor_handle.is_some() {
-            *editor_handle = None;
+            catch_panic("the editor handle's destructor", || {
+                drop(editor_handle.take())
+            });
         } else {
             nih_debug_assert_failure!("Tried destroying editor while the editor was not active");
         }
@@ -2674,14 +2956,17 @@ impl<P: ClapPlugin> Wrapper<P> {
             return false;
         }
 
-        if wrapper
-            .editor
-            .borrow()
-            .as_ref()
-            .unwrap()
-            .lock()
-            .set_scale_factor(scale as f32)
-        {
+        let scale_factor_set = catch_panic("Editor::set_scale_factor()", || {
+            wrapper
+                .editor
+                .borrow()
+                .as_ref()
+                .unwrap()
+                .lock()
+                .set_scale_factor(scale as f32)
+        })
+        .unwrap_or(false);
+        if scale_factor_set {
             wrapper
                 .editor_scaling_factor
                 .store(scale as f32, std::sync::atomic::Ordering::Relaxed);
@@ -2780,17 +3065,17 @@ impl<P: ClapPlugin> Wrapper<P> {
                 };
 
                 // This extension is only exposed when we have an editor
-                *editor_handle = Some(
+                *editor_handle = catch_panic("Editor::spawn()", || {
                     wrapper
                         .editor
                         .borrow()
                         .as_ref()
                         .unwrap()
                         .lock()
-                        .spawn(parent_handle, wrapper.clone().make_gui_context()),
-                );
+                        .spawn(parent_handle, wrapper.clone().make_gui_context())
+                });
 
-                true
+                editor_handle.is_some()
             } else {
                 nih_debug_assert_failure!(
                     "Host tried to attach editor while the editor is already attached"
@@ -2833,7 +3118,7 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(0, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        wrapper.current_latency.load(Ordering::SeqCst)
+        wrapper.latency.report()
     }
 
     unsafe extern "C" fn ext_note_ports_count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
@@ -2858,9 +3143,18 @@ impl<P: ClapPlugin> Wrapper<P> {
                 info.id = 0;
                 // NOTE: REAPER won't send us SysEx if we don't support the MIDI dialect
                 // TODO: Implement MPE (would just be a toggle for the plugin to expose it) and MIDI2
-                info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
-                info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
-                strlcpy(&mut info.name, "Note Input");
+                if P::MIDI_INPUT == MidiConfig::MidiCCOnly {
+                    // Plugins that only want MIDI CCs, pitch bend, and channel pressure don't
+                    // support the CLAP note dialect since those events can only be sent as MIDI.
+                    // This prevents hosts from treating the plugin as an instrument.
+                    info.supported_dialects = CLAP_NOTE_DIALECT_MIDI;
+                    info.preferred_dialect = CLAP_NOTE_DIALECT_MIDI;
+                    strlcpy(&mut info.name, "MIDI Input");
+                } else {
+                    info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
+                    info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
+                    strlcpy(&mut info.name, "Note Input");
+                }
 
                 true
             }
@@ -2869,8 +3163,8 @@ impl<P: ClapPlugin> Wrapper<P> {
 
                 let info = &mut *info;
                 info.id = 0;
-                // If `P::MIDI_OUTPUT < MidiConfig::MidiCCs` we'll throw away MIDI CCs, pitch bend
-                // messages, and other messages that are not basic note on, off and polyphonic
+                // If `P::MIDI_OUTPUT < MidiConfig::MidiCCOnly` we'll throw away MIDI CCs, pitch
+                // bend messages, and other messages that are not basic note on, off and polyphonic
                 // pressure messages. This way the behavior is the same as the VST3 wrapper.
                 info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
                 info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
@@ -2909,7 +3203,9 @@ impl<P: ClapPlugin> Wrapper<P> {
         let flags = param_ptr.flags();
         let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
         let hidden = flags.contains(ParamFlags::HIDDEN);
+        let read_only = flags.contains(ParamFlags::READ_ONLY);
         let is_bypass = flags.contains(ParamFlags::BYPASS);
+        let affects_latency = flags.contains(ParamFlags::AFFECTS_LATENCY);
 
         *param_info = std::mem::zeroed();
 
@@ -2928,9 +3224,17 @@ impl<P: ClapPlugin> Wrapper<P> {
         if hidden {
             param_info.flags |= CLAP_PARAM_IS_HIDDEN | CLAP_PARAM_IS_READONLY;
         }
+        if read_only {
+            param_info.flags |= CLAP_PARAM_IS_READONLY;
+        }
         if is_bypass {
             param_info.flags |= CLAP_PARAM_IS_BYPASS
         }
+        if affects_latency {
+            // This makes sure the plugin gets to see the new value in its process function so it
+            // can update its latency there, even if the host changes the value outside of it
+            param_info.flags |= CLAP_PARAM_REQUIRES_PROCESS
+        }
         if step_count.is_some() {
             param_info.flags |= CLAP_PARAM_IS_STEPPED
         }
@@ -2983,14 +3287,15 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         match wrapper.param_by_hash.get(&param_id) {
             Some(param_ptr) => {
-                strlcpy(
-                    dest,
-                    // CLAP does not have a separate unit, so we'll include the unit here
-                    &param_ptr.normalized_value_to_string(
-                        value as f32 / param_ptr.step_count().unwrap_or(1) as f32,
-                        true,
-                    ),
+                let mut string = wrapper.param_string_scratch.lock();
+                string.clear();
+                // CLAP does not have a separate unit, so we'll include the unit here
+                param_ptr.normalized_value_to_string_into(
+                    value as f32 / param_ptr.step_count().unwrap_or(1) as f32,
+                    true,
+                    &mut string,
                 );
+                strlcpy(dest, &string);
 
                 true
             }
@@ -3164,7 +3469,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         match state::deserialize_json(&read_buffer) {
             Some(mut state) => {
-                let success = wrapper.set_state_inner(&mut state);
+                let success = wrapper.set_state_inner(&mut state, false);
                 if success {
                     nih_trace!("Loaded state ({} bytes)", read_buffer.len());
                 }
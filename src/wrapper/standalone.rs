@@ -9,11 +9,18 @@ use self::wrapper::{Wrapper, WrapperError};
 use super::util::setup_logger;
 use crate::prelude::Plugin;
 
+mod automation;
 mod backend;
 mod config;
 mod context;
+mod test_signal;
+mod window_loop;
 mod wrapper;
 
+pub use self::window_loop::{
+    BaseviewWindowLoop, EditorFrameHandler, EditorWindow, WindowLoop, WindowRequest,
+};
+
 /// Open an NIH-plug plugin as a standalone application. If the plugin has an editor, this will open
 /// the editor and block until the editor is closed. Otherwise this will block until SIGINT is
 /// received. This is mainly useful for quickly testing plugin GUIs. In order to use this, you will
@@ -45,6 +52,11 @@ mod wrapper;
 /// By default this will connect to the 'default' audio and MIDI ports. Use the command line options
 /// to change this. `--help` lists all available options.
 ///
+/// The plugin's state can be loaded from a file using `--load <path>`, and it can be written to a
+/// file when the application exits using `--save-on-exit <path>`. These files use the same format
+/// the plugin's state is stored in by the host, so they can be used to start the plugin with a
+/// specific preset.
+///
 /// If the wrapped plugin fails to initialize or throws an error during audio processing, then this
 /// function will return `false`.
 pub fn nih_export_standalone<P: Plugin>() -> bool {
@@ -57,6 +69,21 @@ pub fn nih_export_standalone<P: Plugin>() -> bool {
 /// of using [`std::env::args()`].
 pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = String>>(
     args: Args,
+) -> bool {
+    nih_export_standalone_with_window_loop::<P, _, _>(args, BaseviewWindowLoop)
+}
+
+/// The same as [`nih_export_standalone_with_args()`], but the editor's window is created and
+/// managed by `window_loop` instead of by NIH-plug. This can be used to embed the standalone
+/// application in a larger application that runs its own event loop, or to share a single event
+/// loop between multiple plugins. See [`WindowLoop`] for more information.
+pub fn nih_export_standalone_with_window_loop<
+    P: Plugin,
+    Args: IntoIterator<Item = String>,
+    L: WindowLoop,
+>(
+    args: Args,
+    mut window_loop: L,
 ) -> bool {
     setup_logger();
 
@@ -75,7 +102,7 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
         config::BackendType::Auto => {
             let result = backend::Jack::new::<P>(config.clone()).map(|backend| {
                 nih_log!("Using the JACK backend");
-                run_wrapper::<P, _>(backend, config.clone())
+                run_wrapper::<P, _>(backend, config.clone(), &mut window_loop)
             });
 
             #[cfg(target_os = "linux")]
@@ -83,7 +110,11 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
                 match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::Alsa) {
                     Ok(backend) => {
                         nih_log!("Using the ALSA backend");
-                        Ok(run_wrapper::<P, _>(backend, config.clone()))
+                        Ok(run_wrapper::<P, _>(
+                            backend,
+                            config.clone(),
+                            &mut window_loop,
+                        ))
                     }
                     Err(err) => {
                         nih_error!(
@@ -99,7 +130,11 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
                 match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::CoreAudio) {
                     Ok(backend) => {
                         nih_log!("Using the CoreAudio backend");
-                        Ok(run_wrapper::<P, _>(backend, config.clone()))
+                        Ok(run_wrapper::<P, _>(
+                            backend,
+                            config.clone(),
+                            &mut window_loop,
+                        ))
                     }
                     Err(err) => {
                         nih_error!(
@@ -115,7 +150,11 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
                 match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::Wasapi) {
                     Ok(backend) => {
                         nih_log!("Using the WASAPI backend");
-                        Ok(run_wrapper::<P, _>(backend, config.clone()))
+                        Ok(run_wrapper::<P, _>(
+                            backend,
+                            config.clone(),
+                            &mut window_loop,
+                        ))
                     }
                     Err(err) => {
                         nih_error!(
@@ -129,11 +168,15 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
 
             result.unwrap_or_else(|_| {
                 nih_error!("Falling back to the dummy audio backend, audio and MIDI will not work");
-                run_wrapper::<P, _>(backend::Dummy::new::<P>(config.clone()), config)
+                run_wrapper::<P, _>(
+                    backend::Dummy::new::<P>(config.clone()),
+                    config,
+                    &mut window_loop,
+                )
             })
         }
         config::BackendType::Jack => match backend::Jack::new::<P>(config.clone()) {
-            Ok(backend) => run_wrapper::<P, _>(backend, config),
+            Ok(backend) => run_wrapper::<P, _>(backend, config, &mut window_loop),
             Err(err) => {
                 nih_error!("Could not initialize the JACK backend: {:#}", err);
                 false
@@ -142,7 +185,7 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
         #[cfg(target_os = "linux")]
         config::BackendType::Alsa => {
             match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::Alsa) {
-                Ok(backend) => run_wrapper::<P, _>(backend, config),
+                Ok(backend) => run_wrapper::<P, _>(backend, config, &mut window_loop),
                 Err(err) => {
                     nih_error!("Could not initialize the ALSA backend: {:#}", err);
                     false
@@ -152,7 +195,7 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
         #[cfg(target_os = "macos")]
         config::BackendType::CoreAudio => {
             match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::CoreAudio) {
-                Ok(backend) => run_wrapper::<P, _>(backend, config),
+                Ok(backend) => run_wrapper::<P, _>(backend, config, &mut window_loop),
                 Err(err) => {
                     nih_error!("Could not initialize the CoreAudio backend: {:#}", err);
                     false
@@ -162,20 +205,26 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
         #[cfg(target_os = "windows")]
         config::BackendType::Wasapi => {
             match backend::CpalMidir::new::<P>(config.clone(), cpal::HostId::Wasapi) {
-                Ok(backend) => run_wrapper::<P, _>(backend, config),
+                Ok(backend) => run_wrapper::<P, _>(backend, config, &mut window_loop),
                 Err(err) => {
                     nih_error!("Could not initialize the WASAPI backend: {:#}", err);
                     false
                 }
             }
         }
-        config::BackendType::Dummy => {
-            run_wrapper::<P, _>(backend::Dummy::new::<P>(config.clone()), config)
-        }
+        config::BackendType::Dummy => run_wrapper::<P, _>(
+            backend::Dummy::new::<P>(config.clone()),
+            config,
+            &mut window_loop,
+        ),
     }
 }
 
-fn run_wrapper<P: Plugin, B: Backend<P>>(backend: B, config: WrapperConfig) -> bool {
+fn run_wrapper<P: Plugin, B: Backend<P>>(
+    backend: B,
+    config: WrapperConfig,
+    window_loop: &mut dyn WindowLoop,
+) -> bool {
     let wrapper = match Wrapper::<P, _>::new(backend, config) {
         Ok(wrapper) => wrapper,
         Err(err) => {
@@ -185,7 +234,7 @@ fn run_wrapper<P: Plugin, B: Backend<P>>(backend: B, config: WrapperConfig) -> b
     };
 
     // TODO: Add a repl while the application is running to interact with parameters
-    match wrapper.run() {
+    match wrapper.run(window_loop) {
         Ok(()) => true,
         Err(err) => {
             print_error(err);
@@ -199,5 +248,14 @@ fn print_error(error: WrapperError) {
         WrapperError::InitializationFailed => {
             nih_error!("The plugin failed to initialize");
         }
+        WrapperError::LoadStateFailed(err) => {
+            nih_error!("Could not load the plugin's state: {err}");
+        }
+        WrapperError::LoadAutomationFailed(err) => {
+            nih_error!("Could not load the automation file: {err}");
+        }
+        WrapperError::SaveStateFailed(err) => {
+            nih_error!("Could not save the plugin's state: {err}");
+        }
     }
 }
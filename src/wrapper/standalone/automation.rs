@@ -0,0 +1,610 @@
+//! Parameter automation replay for the standalone wrapper's `--automation` option.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use crate::prelude::{AudioIOLayout, AuxiliaryBuffers, Buffer, NoteEvent, ParamPtr, SysExMessage};
+use crate::util::permit_alloc;
+use crate::wrapper::util::buffer_management::{BufferManager, Buffers, ChannelPointers};
+
+/// A single parameter change read from an automation file.
+#[derive(Debug, Clone, Copy)]
+pub struct AutomationPoint {
+    /// The sample the change should happen at, counted from the first sample the plugin processed.
+    pub sample: u64,
+    pub param_ptr: ParamPtr,
+    pub normalized_value: f32,
+}
+
+/// Replays the parameter changes from an automation file. Keeps track of the current playback
+/// position, so the wrapper only needs to tell it how far into the current buffer it is. This also
+/// contains the scratch storage used to split the backend's buffers into smaller blocks at the
+/// changes' sample offsets, so none of this allocates on the audio thread. Because of those raw
+/// pointers this is not `Sync`, so it should be created on the audio thread.
+pub struct AutomationPlayer<S: SysExMessage> {
+    /// The automation points, sorted by their sample.
+    points: Vec<AutomationPoint>,
+    /// The index of the next point in `points` that has not yet been applied.
+    next_point_idx: usize,
+    /// The number of samples processed before the current buffer.
+    playhead: u64,
+
+    /// Used to create the buffers for a block within the backend's buffer.
+    buffers: SubBlockBuffers,
+    /// The input events for the current block. Contains the `NoteEvent::MonoAutomation` events for
+    /// the block's parameter changes followed by the backend's events, with the timings made
+    /// relative to the start of the block. This never grows beyond `event_capacity` events.
+    pub block_input_events: Vec<NoteEvent<S>>,
+    event_capacity: usize,
+}
+
+/// Creates [`Buffer`]s pointing to a range of samples within another set of buffers.
+struct SubBlockBuffers {
+    buffer_manager: BufferManager,
+
+    /// Scratch storage for the channel pointers passed to the buffer manager. These are refilled
+    /// from the backend's buffers on every call.
+    main_channel_pointers: Vec<*mut f32>,
+    aux_input_channel_pointers: Vec<Vec<*mut f32>>,
+    aux_output_channel_pointers: Vec<Vec<*mut f32>>,
+}
+
+// SAFETY: Just like in `BufferManager`, the raw pointers are only used as scratch storage within
+//         `SubBlockBuffers::create_buffers()`
+unsafe impl Send for SubBlockBuffers {}
+
+/// Read an automation file. Every non-empty line contains a parameter change in the form
+/// `sample_offset,param_id,normalized_value`, where `sample_offset` is the sample the change
+/// should happen at counted from the first sample the plugin processed. Lines starting with `#`
+/// are ignored. The lines don't need to be sorted, but changes to the same parameter at the same
+/// sample are applied in the order they appear in.
+pub fn read_automation_file(
+    path: &Path,
+    param_id_to_ptr: &HashMap<String, ParamPtr>,
+) -> Result<Vec<AutomationPoint>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Could not read '{}'", path.display()))?;
+
+    parse_automation(&contents, param_id_to_ptr)
+}
+
+/// Parse the contents of an automation file. See [`read_automation_file()`].
+fn parse_automation(
+    contents: &str,
+    param_id_to_ptr: &HashMap<String, ParamPtr>,
+) -> Result<Vec<AutomationPoint>> {
+    let mut points = Vec::new();
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let point = parse_automation_point(line, param_id_to_ptr)
+            .with_context(|| format!("Invalid automation on line {}", line_idx + 1))?;
+        points.push(point);
+    }
+
+    // This sort is stable, so points at the same sample stay in the file's order
+    points.sort_by_key(|point| point.sample);
+
+    Ok(points)
+}
+
+fn parse_automation_point(
+    line: &str,
+    param_id_to_ptr: &HashMap<String, ParamPtr>,
+) -> Result<AutomationPoint> {
+    let mut fields = line.split(',').map(str::trim);
+    let (Some(sample), Some(param_id), Some(normalized_value), None) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        anyhow::bail!("Expected 'sample_offset,param_id,normalized_value', found '{line}'");
+    };
+
+    let sample = sample
+        .parse()
+        .with_context(|| format!("'{sample}' is not a valid sample offset"))?;
+    let param_ptr = *param_id_to_ptr
+        .get(param_id)
+        .with_context(|| format!("Unknown parameter ID '{param_id}'"))?;
+    let normalized_value: f32 = normalized_value
+        .parse()
+        .with_context(|| format!("'{normalized_value}' is not a valid number"))?;
+    if !(0.0..=1.0).contains(&normalized_value) {
+        anyhow::bail!("The normalized value {normalized_value} is not in the range [0, 1]");
+    }
+
+    Ok(AutomationPoint {
+        sample,
+        param_ptr,
+        normalized_value,
+    })
+}
+
+impl<S: SysExMessage> AutomationPlayer<S> {
+    pub fn new(
+        points: Vec<AutomationPoint>,
+        max_buffer_size: usize,
+        audio_io_layout: AudioIOLayout,
+        event_capacity: usize,
+    ) -> Self {
+        Self {
+            points,
+            next_point_idx: 0,
+            playhead: 0,
+
+            buffers: SubBlockBuffers::for_audio_io_layout(max_buffer_size, audio_io_layout),
+            block_input_events: Vec::with_capacity(event_capacity),
+            event_capacity,
+        }
+    }
+
+    /// Apply the automation points for the block starting at `block_start` in the current buffer
+    /// and fill [`block_input_events`][Self::block_input_events] with that block's input events.
+    /// Returns the end of the block. With `sample_accurate` set, the block ends at the next
+    /// automation point so every change happens at its exact sample. Otherwise every change within
+    /// the buffer is applied at the start of the buffer, and the block spans the entire buffer.
+    ///
+    /// `apply_point` is called for every automation point in the block. Changes to polyphonically
+    /// modulatable parameters are also added to the input events as
+    /// [`NoteEvent::MonoAutomation`] events, just like the CLAP wrapper does.
+    pub fn prepare_block(
+        &mut self,
+        block_start: usize,
+        num_samples: usize,
+        sample_accurate: bool,
+        input_events: &[NoteEvent<S>],
+        mut apply_point: impl FnMut(&AutomationPoint),
+    ) -> usize {
+        let apply_until = if sample_accurate {
+            block_start + 1
+        } else {
+            num_samples
+        };
+
+        self.block_input_events.clear();
+        while let Some(point) = self.next_point_before(apply_until) {
+            apply_point(&point);
+
+            if let Some(poly_modulation_id) = unsafe { point.param_ptr.poly_modulation_id() } {
+                let timing = self.point_offset(&point).saturating_sub(block_start);
+                self.push_block_input_event(NoteEvent::MonoAutomation {
+                    timing: timing as u32,
+                    poly_modulation_id,
+                    normalized_value: point.normalized_value,
+                });
+            }
+        }
+
+        let block_end = if sample_accurate {
+            self.next_point_offset()
+                .map_or(num_samples, |offset| offset.min(num_samples))
+        } else {
+            num_samples
+        };
+
+        // The events before the end of the block are moved to the start of the block. Any stray
+        // events past the end of the buffer are included in the last block.
+        for event in input_events {
+            let timing = event.timing() as usize;
+            if timing >= block_start && (timing < block_end || block_end == num_samples) {
+                let mut event = event.clone();
+                event.subtract_timing(block_start as u32);
+                self.push_block_input_event(event);
+            }
+        }
+
+        // NOTE: The automation events come first, and this sort is stable, so parameter changes
+        //       are still processed before note events at the same sample
+        // FIXME: Stable sort allocates if the slice is large enough, just like in the VST3 wrapper
+        permit_alloc(|| self.block_input_events.sort_by_key(|event| event.timing()));
+
+        block_end
+    }
+
+    fn push_block_input_event(&mut self, event: NoteEvent<S>) {
+        if self.block_input_events.len() < self.event_capacity {
+            self.block_input_events.push(event);
+        } else {
+            nih_debug_assert_failure!("The automation's event queue is full, dropping event...");
+        }
+    }
+
+    /// Return the next automation point if it should happen before `sample_offset` in the current
+    /// buffer.
+    pub fn next_point_before(&mut self, sample_offset: usize) -> Option<AutomationPoint> {
+        let point = *self.points.get(self.next_point_idx)?;
+        if point.sample < self.playhead + sample_offset as u64 {
+            self.next_point_idx += 1;
+            Some(point)
+        } else {
+            None
+        }
+    }
+
+    /// The offset of the next automation point relative to the start of the current buffer, if
+    /// there are any points left. This may be beyond the end of the buffer.
+    pub fn next_point_offset(&self) -> Option<usize> {
+        self.points
+            .get(self.next_point_idx)
+            .map(|point| point.sample.saturating_sub(self.playhead) as usize)
+    }
+
+    /// The offset of an automation point returned from
+    /// [`next_point_before()`][Self::next_point_before()] relative to the start of the current
+    /// buffer.
+    pub fn point_offset(&self, point: &AutomationPoint) -> usize {
+        point.sample.saturating_sub(self.playhead) as usize
+    }
+
+    /// Move the playhead to the start of the next buffer.
+    pub fn advance(&mut self, num_samples: usize) {
+        self.playhead += num_samples as u64;
+    }
+
+    /// Create buffers for `num_samples` samples of `buffer` and `aux`, starting at `sample_offset`.
+    /// The main and auxiliary output buffers point directly to the original buffers' samples.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` and `aux` may not be used while the returned buffers are alive.
+    pub unsafe fn create_sub_block_buffers<'a>(
+        &'a mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        sample_offset: usize,
+        num_samples: usize,
+    ) -> Buffers<'a, 'a> {
+        self.buffers
+            .create_buffers(buffer, aux, sample_offset, num_samples)
+    }
+}
+
+impl SubBlockBuffers {
+    fn for_audio_io_layout(max_buffer_size: usize, audio_io_layout: AudioIOLayout) -> Self {
+        let num_output_channels = audio_io_layout
+            .main_output_channels
+            .map(|channels| channels.get() as usize)
+            .unwrap_or(0);
+
+        Self {
+            buffer_manager: BufferManager::for_audio_io_layout(max_buffer_size, audio_io_layout),
+
+            main_channel_pointers: Vec::with_capacity(num_output_channels),
+            aux_input_channel_pointers: audio_io_layout
+                .aux_input_ports
+                .iter()
+                .map(|num_channels| Vec::with_capacity(num_channels.get() as usize))
+                .collect(),
+            aux_output_channel_pointers: audio_io_layout
+                .aux_output_ports
+                .iter()
+                .map(|num_channels| Vec::with_capacity(num_channels.get() as usize))
+                .collect(),
+        }
+    }
+
+    unsafe fn create_buffers<'a>(
+        &'a mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        sample_offset: usize,
+        num_samples: usize,
+    ) -> Buffers<'a, 'a> {
+        fill_channel_pointers(&mut self.main_channel_pointers, buffer);
+        for (channel_pointers, buffer) in self
+            .aux_input_channel_pointers
+            .iter_mut()
+            .zip(aux.inputs.iter_mut())
+        {
+            fill_channel_pointers(channel_pointers, buffer);
+        }
+        for (channel_pointers, buffer) in self
+            .aux_output_channel_pointers
+            .iter_mut()
+            .zip(aux.outputs.iter_mut())
+        {
+            fill_channel_pointers(channel_pointers, buffer);
+        }
+
+        // The main buffer is already processed in-place, so there's no input to copy
+        let main_channel_pointers = &mut self.main_channel_pointers;
+        let aux_input_channel_pointers = &mut self.aux_input_channel_pointers;
+        let aux_output_channel_pointers = &mut self.aux_output_channel_pointers;
+        self.buffer_manager
+            .create_buffers(sample_offset, num_samples, |buffer_sources| {
+                *buffer_sources.main_output_channel_pointers =
+                    channel_pointers(main_channel_pointers);
+                for (source, channel_pointers_storage) in buffer_sources
+                    .aux_input_channel_pointers
+                    .iter_mut()
+                    .zip(aux_input_channel_pointers.iter_mut())
+                {
+                    *source = channel_pointers(channel_pointers_storage);
+                }
+                for (silent, input) in buffer_sources
+                    .aux_input_silent
+                    .iter_mut()
+                    .zip(aux.inputs.iter())
+                {
+                    *silent = input.is_silent();
+                }
+                for (source, channel_pointers_storage) in buffer_sources
+                    .aux_output_channel_pointers
+                    .iter_mut()
+                    .zip(aux_output_channel_pointers.iter_mut())
+                {
+                    *source = channel_pointers(channel_pointers_storage);
+                }
+            })
+    }
+}
+
+fn fill_channel_pointers(channel_pointers: &mut Vec<*mut f32>, buffer: &mut Buffer) {
+    channel_pointers.clear();
+    channel_pointers.extend(
+        buffer
+            .as_slice()
+            .iter_mut()
+            .map(|channel| channel.as_mut_ptr()),
+    );
+}
+
+fn channel_pointers(channel_pointers: &mut [*mut f32]) -> Option<ChannelPointers> {
+    if channel_pointers.is_empty() {
+        None
+    } else {
+        Some(ChannelPointers {
+            ptrs: NonNull::new(channel_pointers.as_mut_ptr()).unwrap(),
+            num_channels: channel_pointers.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use super::*;
+    use crate::prelude::{FloatParam, FloatRange, Param};
+
+    const NUM_SAMPLES: usize = 64;
+
+    fn param_id_to_ptr(gain: &FloatParam, poly: &FloatParam) -> HashMap<String, ParamPtr> {
+        HashMap::from([
+            (String::from("gain"), gain.as_ptr()),
+            (String::from("poly"), poly.as_ptr()),
+        ])
+    }
+
+    fn float_param() -> FloatParam {
+        FloatParam::new("Param", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+    }
+
+    fn note_on(timing: u32) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing,
+            voice_id: None,
+            channel: 0,
+            note: 60,
+            velocity: 1.0,
+        }
+    }
+
+    /// Run a buffer through [`AutomationPlayer::prepare_block()`], returning the blocks' start and
+    /// end samples, the values applied for every block, and every block's input events.
+    #[allow(clippy::type_complexity)]
+    fn prepare_blocks(
+        player: &mut AutomationPlayer<()>,
+        sample_accurate: bool,
+        input_events: &[NoteEvent<()>],
+    ) -> Vec<((usize, usize), Vec<f32>, Vec<NoteEvent<()>>)> {
+        let mut blocks = Vec::new();
+        let mut block_start = 0;
+        while block_start < NUM_SAMPLES {
+            let mut applied = Vec::new();
+            let block_end = player.prepare_block(
+                block_start,
+                NUM_SAMPLES,
+                sample_accurate,
+                input_events,
+                |point| applied.push(point.normalized_value),
+            );
+
+            blocks.push((
+                (block_start, block_end),
+                applied,
+                player.block_input_events.clone(),
+            ));
+            block_start = block_end;
+        }
+        player.advance(NUM_SAMPLES);
+
+        blocks
+    }
+
+    #[test]
+    fn parse_automation_file() {
+        let gain = float_param();
+        let poly = float_param();
+        let param_id_to_ptr = param_id_to_ptr(&gain, &poly);
+
+        let points = parse_automation(
+            "# sample_offset,param_id,normalized_value\n\n64, gain, 0.5\n0,gain,1\n64,poly,0.25\n  \
+             32,poly,0\n",
+            &param_id_to_ptr,
+        )
+        .unwrap();
+
+        // Points at the same sample stay in the file's order
+        let points: Vec<_> = points
+            .iter()
+            .map(|point| (point.sample, point.param_ptr, point.normalized_value))
+            .collect();
+        assert_eq!(
+            points,
+            [
+                (0, gain.as_ptr(), 1.0),
+                (32, poly.as_ptr(), 0.0),
+                (64, gain.as_ptr(), 0.5),
+                (64, poly.as_ptr(), 0.25),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_automation_file() {
+        let gain = float_param();
+        let poly = float_param();
+        let param_id_to_ptr = param_id_to_ptr(&gain, &poly);
+
+        for contents in [
+            "0,gain",
+            "0,gain,0.5,1",
+            "-1,gain,0.5",
+            "0,unknown,0.5",
+            "0,gain,loud",
+            "0,gain,1.5",
+        ] {
+            assert!(
+                parse_automation(contents, &param_id_to_ptr).is_err(),
+                "{contents}"
+            );
+        }
+
+        let err = parse_automation("0,gain,0.5\n\n10,gain,2", &param_id_to_ptr).unwrap_err();
+        assert!(format!("{err:#}").contains("line 3"), "{err:#}");
+    }
+
+    #[test]
+    fn sample_accurate_blocks() {
+        let gain = float_param();
+        let poly = float_param().with_poly_modulation_id(7);
+        let points = parse_automation(
+            "0,gain,0.1\n10,poly,0.2\n10,gain,0.3\n40,gain,0.4\n100,gain,0.5",
+            &param_id_to_ptr(&gain, &poly),
+        )
+        .unwrap();
+        let mut player =
+            AutomationPlayer::new(points, NUM_SAMPLES, AudioIOLayout::const_default(), 16);
+
+        // The stray event past the end of the buffer ends up in the last block
+        let blocks = prepare_blocks(&mut player, true, &[note_on(5), note_on(20), note_on(70)]);
+        assert_eq!(
+            blocks,
+            [
+                ((0, 10), vec![0.1], vec![note_on(5)]),
+                (
+                    (10, 40),
+                    vec![0.2, 0.3],
+                    vec![
+                        NoteEvent::MonoAutomation {
+                            timing: 0,
+                            poly_modulation_id: 7,
+                            normalized_value: 0.2,
+                        },
+                        note_on(10),
+                    ],
+                ),
+                ((40, 64), vec![0.4], vec![note_on(30)]),
+            ]
+        );
+
+        // The last point is relative to the start of the second buffer
+        let blocks = prepare_blocks(&mut player, true, &[]);
+        assert_eq!(
+            blocks,
+            [((0, 36), vec![], vec![]), ((36, 64), vec![0.5], vec![])]
+        );
+    }
+
+    #[test]
+    fn non_sample_accurate_blocks() {
+        let gain = float_param();
+        let poly = float_param().with_poly_modulation_id(7);
+        let points = parse_automation(
+            "0,gain,0.1\n10,poly,0.2\n40,gain,0.3\n100,gain,0.4",
+            &param_id_to_ptr(&gain, &poly),
+        )
+        .unwrap();
+        let mut player =
+            AutomationPlayer::new(points, NUM_SAMPLES, AudioIOLayout::const_default(), 16);
+
+        let blocks = prepare_blocks(&mut player, false, &[note_on(5)]);
+        assert_eq!(
+            blocks,
+            [(
+                (0, 64),
+                vec![0.1, 0.2, 0.3],
+                vec![
+                    note_on(5),
+                    NoteEvent::MonoAutomation {
+                        timing: 10,
+                        poly_modulation_id: 7,
+                        normalized_value: 0.2,
+                    },
+                ],
+            )]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn block_input_events_capacity() {
+        let mut player =
+            AutomationPlayer::new(Vec::new(), NUM_SAMPLES, AudioIOLayout::const_default(), 2);
+
+        let blocks = prepare_blocks(&mut player, true, &[note_on(0), note_on(1), note_on(2)]);
+        assert_eq!(blocks, [((0, 64), vec![], vec![note_on(0), note_on(1)])]);
+    }
+
+    #[test]
+    fn sub_block_buffers() {
+        let audio_io_layout = AudioIOLayout {
+            main_output_channels: NonZeroU32::new(2),
+            ..AudioIOLayout::const_default()
+        };
+        let mut player: AutomationPlayer<()> =
+            AutomationPlayer::new(Vec::new(), NUM_SAMPLES, audio_io_layout, 16);
+
+        let mut storage: Vec<Vec<f32>> = (0..2)
+            .map(|channel| {
+                (0..NUM_SAMPLES)
+                    .map(|sample| (channel * NUM_SAMPLES + sample) as f32)
+                    .collect()
+            })
+            .collect();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(NUM_SAMPLES, |output_slices| {
+                *output_slices = storage
+                    .iter_mut()
+                    .map(|channel| &mut *(channel.as_mut_slice() as *mut [f32]))
+                    .collect();
+            })
+        };
+        let mut aux = AuxiliaryBuffers {
+            inputs: &mut [],
+            outputs: &mut [],
+        };
+
+        {
+            let buffers = unsafe { player.create_sub_block_buffers(&mut buffer, &mut aux, 16, 8) };
+            assert_eq!(buffers.main_buffer.samples(), 8);
+            let channels = buffers.main_buffer.as_slice();
+            assert_eq!(channels.len(), 2);
+            assert_eq!(channels[0][0], 16.0);
+            assert_eq!(channels[1][7], 87.0);
+
+            channels[1][0] = -1.0;
+        }
+
+        // The block's buffers point directly to the original buffer's samples
+        assert_eq!(storage[1][15], 79.0);
+        assert_eq!(storage[1][16], -1.0);
+    }
+}
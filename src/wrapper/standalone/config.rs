@@ -1,5 +1,6 @@
 use clap::{Parser, ValueEnum};
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 
 use crate::prelude::{AudioIOLayout, Plugin};
 
@@ -93,6 +94,42 @@ pub struct WrapperConfig {
     /// The time signature's denominator.
     #[clap(value_parser, long, default_value = "4")]
     pub timesig_denom: u32,
+
+    /// Feed a generated test signal to the plugin's main input instead of the audio device's input.
+    /// This can be used to check a plugin's signal flow and latency without an external source.
+    #[clap(value_parser, long)]
+    pub test_signal: Option<TestSignal>,
+    /// The test signal's frequency in Hertz. For the impulse train this is the number of impulses
+    /// per second.
+    #[clap(value_parser, long, default_value = "440")]
+    pub test_signal_frequency: f32,
+    /// The test signal's peak level in decibels.
+    #[clap(value_parser, long, default_value = "-12")]
+    pub test_signal_gain: f32,
+    /// Mix the test signal with the audio device's input instead of replacing it.
+    #[clap(value_parser, long)]
+    pub mix_test_signal: bool,
+
+    /// Load the plugin's state from this file before processing any audio. This can be either a
+    /// state file written with '--save-on-exit', or a preset exported from the plugin's GUI.
+    #[clap(value_parser, long, value_name = "PATH")]
+    pub load: Option<PathBuf>,
+    /// Write the plugin's state to this file when the application exits. This uses the same format
+    /// plugin hosts store the plugin's state in.
+    ///
+    /// The state is only written when the application exits normally, i.e. after the editor has
+    /// been closed.
+    #[clap(value_parser, long, value_name = "PATH")]
+    pub save_on_exit: Option<PathBuf>,
+    /// Replay parameter automation from this CSV file. Every line contains a parameter change in
+    /// the form 'sample_offset,param_id,normalized_value', where the sample offset is counted from
+    /// the first sample the plugin processes. Lines starting with '#' are ignored.
+    ///
+    /// For plugins that enable sample accurate automation the audio buffers are split so the
+    /// changes happen at exactly those samples. Otherwise the changes are applied at the start of
+    /// the buffer containing them.
+    #[clap(value_parser, long, value_name = "PATH")]
+    pub automation: Option<PathBuf>,
 }
 
 /// Determines which audio and MIDI backend should be used.
@@ -117,6 +154,19 @@ pub enum BackendType {
     Dummy,
 }
 
+/// A test signal the standalone wrapper can feed to the plugin's main input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TestSignal {
+    /// A sine wave at the test signal's frequency.
+    Sine,
+    /// White noise.
+    WhiteNoise,
+    /// Pink noise, which has equal energy per octave.
+    PinkNoise,
+    /// Single sample impulses, repeated at the test signal's frequency.
+    Impulse,
+}
+
 impl WrapperConfig {
     /// Get the audio IO layout for a plugin based on this configuration. Exits the application if
     /// the IO layout could not be parsed from the config. This doesn't return a `Result` to be able to differentiate between backend-specific errors and config parsing errors.
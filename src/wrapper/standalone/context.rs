@@ -3,7 +3,7 @@ use std::sync::Arc;
 use super::backend::Backend;
 use super::wrapper::{Task, Wrapper};
 use crate::prelude::{
-    GuiContext, InitContext, ParamPtr, Plugin, PluginApi, PluginNoteEvent, ProcessContext,
+    GuiContext, InitContext, ParamPtr, Plugin, PluginApi, PluginNoteEvent, ProcessContext, RawHost,
     Transport,
 };
 
@@ -16,7 +16,6 @@ pub(crate) struct WrapperInitContext<'a, P: Plugin, B: Backend<P>> {
 /// can hold on to lock guards for event queues. Otherwise reading these events would require
 /// constant unnecessary atomic operations to lock the uncontested `RwLock`s.
 pub(crate) struct WrapperProcessContext<'a, P: Plugin, B: Backend<P>> {
-    #[allow(dead_code)]
     pub(super) wrapper: &'a Wrapper<P, B>,
     pub(super) input_events: &'a [PluginNoteEvent<P>],
     // The current index in `input_events`, since we're not actually popping anything from a queue
@@ -41,6 +40,10 @@ impl<P: Plugin, B: Backend<P>> InitContext<P> for WrapperInitContext<'_, P, B> {
         PluginApi::Standalone
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Standalone
+    }
+
     fn execute(&self, task: P::BackgroundTask) {
         (self.wrapper.task_executor.lock())(task);
     }
@@ -59,8 +62,15 @@ impl<P: Plugin, B: Backend<P>> ProcessContext<P> for WrapperProcessContext<'_, P
         PluginApi::Standalone
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Standalone
+    }
+
     fn execute_background(&self, task: P::BackgroundTask) {
-        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        let priority = P::background_task_priority(&task);
+        let task_posted = self
+            .wrapper
+            .schedule_background(Task::PluginTask(task), priority);
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
@@ -86,6 +96,11 @@ impl<P: Plugin, B: Backend<P>> ProcessContext<P> for WrapperProcessContext<'_, P
         }
     }
 
+    #[inline]
+    fn remaining_events(&self) -> usize {
+        self.input_events.len() - self.input_events_idx
+    }
+
     fn send_event(&mut self, event: PluginNoteEvent<P>) {
         self.output_events.push(event);
     }
@@ -97,6 +112,27 @@ impl<P: Plugin, B: Backend<P>> ProcessContext<P> for WrapperProcessContext<'_, P
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn port_names_changed(&self) {
+        // The ports are registered once when the standalone starts, so they cannot be renamed
+        // afterwards
+    }
+
+    fn sample_accurate_automation_active(&self) -> bool {
+        // There's no host automation in the standalone, parameter changes from the GUI are always
+        // handled at the start of the buffer
+        false
+    }
+
+    unsafe fn raw_set_read_only_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        // There's no host to inform here, so this goes through the same queue as the GUI's
+        // parameter changes
+        self.wrapper.set_parameter(param, normalized);
+    }
+
+    unsafe fn raw_set_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        self.wrapper.set_parameter(param, normalized);
+    }
 }
 
 impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
@@ -104,11 +140,34 @@ impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
         PluginApi::Standalone
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Standalone
+    }
+
+    fn is_plugin_initialized(&self) -> bool {
+        // The standalone wrapper fails to start if the plugin could not be initialized, and the
+        // editor is only opened after that
+        true
+    }
+
+    fn last_transport(&self) -> Option<Transport> {
+        self.wrapper.last_transport.load()
+    }
+
     fn request_resize(&self) -> bool {
         self.wrapper.request_resize();
         true
     }
 
+    fn request_resize_to(&self, width: u32, height: u32) -> bool {
+        let size_changed = match self.wrapper.editor.borrow().as_ref() {
+            Some(editor) => editor.lock().set_size(width, height),
+            None => false,
+        };
+
+        size_changed && self.request_resize()
+    }
+
     unsafe fn raw_begin_set_parameter(&self, _param: ParamPtr) {
         // Since there's no automation being recorded here, gestures don't mean anything
 
@@ -159,4 +218,8 @@ impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object_from_gui(state)
     }
+
+    fn get_default_state(&self) -> crate::wrapper::state::PluginState {
+        self.wrapper.get_default_state_object()
+    }
 }
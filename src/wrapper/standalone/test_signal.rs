@@ -0,0 +1,86 @@
+//! Test signal generation for the standalone wrapper's `--test-signal` option.
+
+use super::config::{TestSignal, WrapperConfig};
+use crate::buffer::Buffer;
+use crate::util;
+use crate::util::noise::{NoiseColor, NoiseGenerator};
+use crate::util::osc::{Oscillator, Waveform};
+
+/// Generates the test signal selected in the [`WrapperConfig`] and writes it to the plugin's main
+/// input. The same signal is written to every channel. This doesn't allocate, and the noise is
+/// generated from a fixed seed so the signal is the same every time the standalone is started.
+pub struct TestSignalGenerator {
+    signal: TestSignal,
+    /// The test signal's linear peak gain.
+    gain: f32,
+    /// Whether the signal should be added to the existing input instead of replacing it.
+    mix: bool,
+
+    /// Used for [`TestSignal::Sine`].
+    oscillator: Oscillator,
+    /// Used for [`TestSignal::WhiteNoise`] and [`TestSignal::PinkNoise`].
+    noise: NoiseGenerator,
+    /// The number of samples between two impulses for [`TestSignal::Impulse`].
+    impulse_interval: u32,
+    /// The number of samples until the next impulse.
+    samples_until_impulse: u32,
+}
+
+impl TestSignalGenerator {
+    /// Create a generator for the test signal from the config, if the config enables one.
+    pub fn from_config(config: &WrapperConfig, sample_rate: f32) -> Option<Self> {
+        let signal = config.test_signal?;
+
+        let mut oscillator = Oscillator::new(Waveform::Sine);
+        oscillator.set_frequency(sample_rate, config.test_signal_frequency);
+        let noise_color = match signal {
+            TestSignal::PinkNoise => NoiseColor::Pink,
+            _ => NoiseColor::White,
+        };
+
+        Some(Self {
+            signal,
+            gain: util::db_to_gain(config.test_signal_gain),
+            mix: config.mix_test_signal,
+
+            oscillator,
+            noise: NoiseGenerator::new(noise_color, 0x9e3779b9),
+            impulse_interval: (sample_rate / config.test_signal_frequency.max(f32::EPSILON))
+                .round()
+                .max(1.0) as u32,
+            samples_until_impulse: 0,
+        })
+    }
+
+    /// Write the next block of the test signal to `buffer`, which contains the plugin's main input.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        for mut channel_samples in buffer.iter_samples() {
+            let sample = self.next_sample() * self.gain;
+            for output_sample in channel_samples.iter_mut() {
+                if self.mix {
+                    *output_sample += sample;
+                } else {
+                    *output_sample = sample;
+                }
+            }
+        }
+    }
+
+    /// Generate the next sample of the test signal in `[-1, 1]`.
+    fn next_sample(&mut self) -> f32 {
+        match self.signal {
+            TestSignal::Sine => self.oscillator.next_sample(),
+            TestSignal::WhiteNoise => self.noise.next_sample(),
+            TestSignal::PinkNoise => self.noise.next_sample().clamp(-1.0, 1.0),
+            TestSignal::Impulse => {
+                if self.samples_until_impulse == 0 {
+                    self.samples_until_impulse = self.impulse_interval - 1;
+                    1.0
+                } else {
+                    self.samples_until_impulse -= 1;
+                    0.0
+                }
+            }
+        }
+    }
+}
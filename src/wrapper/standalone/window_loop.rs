@@ -0,0 +1,195 @@
+//! Abstractions for hosting the standalone application's editor window. By default the editor is
+//! opened in its own baseview window with its own event loop, but applications embedding a
+//! standalone plugin can supply their own [`WindowLoop`] to manage the window themselves.
+
+use baseview::{EventStatus, Window, WindowHandler, WindowOpenOptions};
+use crossbeam::channel;
+use parking_lot::Mutex;
+use raw_window_handle::HasRawWindowHandle;
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::prelude::{Editor, GuiContext, ParentWindowHandle};
+
+/// Opens a window for the plugin's editor and runs that window's event loop. The standalone
+/// wrapper calls [`run()`][Self::run()] from the main thread after it has started processing
+/// audio, and audio processing stops once that function returns.
+///
+/// The default implementation, [`BaseviewWindowLoop`], opens a baseview window with its own event
+/// loop. A host application that wants to manage a single event loop for multiple plugins or for
+/// its own windows can instead implement this trait and pass it to
+/// [`nih_export_standalone_with_window_loop()`][super::nih_export_standalone_with_window_loop()].
+pub trait WindowLoop {
+    /// Create a window for the editor, spawn the editor into it with [`EditorWindow::spawn()`],
+    /// and then block until the window has been closed. While the window is open, the
+    /// implementation needs to regularly call [`EditorFrameHandler::poll()`] on the returned
+    /// handler, for instance once per frame, and act on the requests it returns. The window should
+    /// be closed after receiving a [`WindowRequest::Close`].
+    fn run(&mut self, window: EditorWindow);
+}
+
+/// The information needed to open a window for the plugin's editor, and a way to spawn the editor
+/// into that window. Passed to [`WindowLoop::run()`].
+pub struct EditorWindow {
+    title: String,
+    size: (u32, u32),
+    scale_factor: Option<f32>,
+
+    editor: Arc<Mutex<Box<dyn Editor>>>,
+    context: Arc<dyn GuiContext>,
+    requests: channel::Receiver<WindowRequest>,
+}
+
+/// Returned by [`EditorWindow::spawn()`]. This keeps the editor alive, and it is used to receive
+/// requests from the editor and the audio thread. The editor is closed when this is dropped.
+pub struct EditorFrameHandler {
+    /// The editor handle for the plugin's open editor. The editor should clean itself up when it
+    /// gets dropped.
+    _editor_handle: Box<dyn Any + Send>,
+    /// This is used to communicate with the wrapper from the audio thread and from within the
+    /// window's event loop on the GUI thread.
+    requests: channel::Receiver<WindowRequest>,
+}
+
+/// A request sent to the editor's window from the wrapper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowRequest {
+    /// Resize the window to the following logical size.
+    Resize(u32, u32),
+    /// Close the window. This will cause the application to terminate.
+    Close,
+}
+
+/// The default [`WindowLoop`]. This opens the editor in a new baseview window and blocks until that
+/// window is closed.
+#[derive(Debug, Default)]
+pub struct BaseviewWindowLoop;
+
+/// The baseview window handler used by [`BaseviewWindowLoop`].
+struct BaseviewWindowHandler {
+    frame_handler: EditorFrameHandler,
+}
+
+impl EditorWindow {
+    pub(super) fn new(
+        title: String,
+        editor: Arc<Mutex<Box<dyn Editor>>>,
+        context: Arc<dyn GuiContext>,
+        scale_factor: Option<f32>,
+        requests: channel::Receiver<WindowRequest>,
+    ) -> Self {
+        let size = editor.lock().size();
+
+        Self {
+            title,
+            size,
+            scale_factor,
+
+            editor,
+            context,
+            requests,
+        }
+    }
+
+    /// The window's title. This is the plugin's name.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The editor's logical size in pixels, before scaling.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// The DPI scaling factor that should be applied to the window, if any. This is always `None`
+    /// on macOS since the OS handles the scaling there, and the window should then use the system's
+    /// scaling factor instead.
+    pub fn scale_factor(&self) -> Option<f32> {
+        self.scale_factor
+    }
+
+    /// Spawn the editor into a window. The returned handler keeps the editor alive, and it needs to
+    /// be polled regularly from the window's event loop. See [`WindowLoop::run()`].
+    pub fn spawn(self, parent: ParentWindowHandle) -> EditorFrameHandler {
+        // TODO: This spawn function should be able to fail and return an error, but baseview does
+        //       not support this yet. Once this is added, we should immediately close the parent
+        //       window when this happens so the loop can exit.
+        let editor_handle = self.editor.lock().spawn(parent, self.context);
+
+        EditorFrameHandler {
+            _editor_handle: editor_handle,
+            requests: self.requests,
+        }
+    }
+}
+
+impl EditorFrameHandler {
+    /// Get the next pending request for the editor's window, if there is one. This should be called
+    /// in a loop from the window's event loop until it returns `None`.
+    pub fn poll(&mut self) -> Option<WindowRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl WindowLoop for BaseviewWindowLoop {
+    fn run(&mut self, window: EditorWindow) {
+        let scaling_policy = match window.scale_factor() {
+            Some(scale_factor) => baseview::WindowScalePolicy::ScaleFactor(scale_factor as f64),
+            None => baseview::WindowScalePolicy::SystemScaleFactor,
+        };
+
+        let (width, height) = window.size();
+        Window::open_blocking(
+            WindowOpenOptions {
+                title: window.title().to_owned(),
+                size: baseview::Size {
+                    width: width as f64,
+                    height: height as f64,
+                },
+                scale: scaling_policy,
+                gl_config: None,
+            },
+            move |baseview_window| {
+                let parent_handle = match baseview_window.raw_window_handle() {
+                    raw_window_handle::RawWindowHandle::Xlib(handle) => {
+                        ParentWindowHandle::X11Window(handle.window as u32)
+                    }
+                    raw_window_handle::RawWindowHandle::Xcb(handle) => {
+                        ParentWindowHandle::X11Window(handle.window)
+                    }
+                    raw_window_handle::RawWindowHandle::AppKit(handle) => {
+                        ParentWindowHandle::AppKitNsView(handle.ns_view)
+                    }
+                    raw_window_handle::RawWindowHandle::Win32(handle) => {
+                        ParentWindowHandle::Win32Hwnd(handle.hwnd)
+                    }
+                    handle => unimplemented!("Unsupported window handle: {handle:?}"),
+                };
+
+                BaseviewWindowHandler {
+                    frame_handler: window.spawn(parent_handle),
+                }
+            },
+        )
+    }
+}
+
+impl WindowHandler for BaseviewWindowHandler {
+    fn on_frame(&mut self, window: &mut Window) {
+        while let Some(request) = self.frame_handler.poll() {
+            match request {
+                WindowRequest::Resize(new_width, new_height) => {
+                    window.resize(baseview::Size {
+                        width: new_width as f64,
+                        height: new_height as f64,
+                    });
+                }
+                WindowRequest::Close => window.close(),
+            }
+        }
+    }
+
+    fn on_event(&mut self, _window: &mut Window, _event: baseview::Event) -> EventStatus {
+        EventStatus::Ignored
+    }
+}
@@ -1,27 +1,31 @@
+use anyhow::{Context, Result};
 use atomic_refcell::AtomicRefCell;
-use baseview::{EventStatus, Window, WindowHandler, WindowOpenOptions};
+use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::{self, Sender};
 use crossbeam::queue::ArrayQueue;
 use parking_lot::Mutex;
-use raw_window_handle::HasRawWindowHandle;
-use std::any::Any;
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::thread;
 
+use super::automation::{self, AutomationPlayer, AutomationPoint};
 use super::backend::Backend;
 use super::config::WrapperConfig;
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
+use super::test_signal::TestSignalGenerator;
+use super::window_loop::{EditorWindow, WindowLoop, WindowRequest};
 use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
 use crate::prelude::{
-    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, ParamFlags, ParamPtr, Params,
-    ParentWindowHandle, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor,
-    Transport,
+    AsyncExecutor, AudioIOLayout, AuxiliaryBuffers, Buffer, BufferConfig, Editor, ParamFlags,
+    ParamPtr, Params, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor,
+    TaskPriority, Transport,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::state::{self, PluginState};
-use crate::wrapper::util::process_wrapper;
+use crate::wrapper::util::{catch_panic, process_wrapper, silence_outputs};
 
 /// How many parameter changes we can store in our unprocessed parameter change queue. Storing more
 /// than this many parameters at a time will cause changes to get lost.
@@ -44,15 +48,14 @@ pub struct Wrapper<P: Plugin, B: Backend<P>> {
     /// creating an editor. Wrapped in an `AtomicRefCell` because it needs to be initialized late.
     pub editor: AtomicRefCell<Option<Arc<Mutex<Box<dyn Editor>>>>>,
     /// A channel for sending tasks to the GUI window, if the plugin has a GUI. Set in `run()`.
-    gui_tasks_sender: AtomicRefCell<Option<Sender<GuiTask>>>,
+    gui_tasks_sender: AtomicRefCell<Option<Sender<WindowRequest>>>,
 
     /// A realtime-safe task queue so the plugin can schedule tasks that need to be run later on the
     /// GUI thread. See the same field in the VST3 wrapper for more information on why this looks
     /// the way it does.
     event_loop: AtomicRefCell<Option<OsEventLoop<Task<P>, Self>>>,
 
-    /// This is used to grab the DPI scaling config. Not used on macOS.
-    #[allow(unused)]
+    /// This is used to grab the DPI scaling config and the test signal settings.
     config: WrapperConfig,
 
     /// A mapping from parameter pointers to string parameter IDs. This is used as part of
@@ -67,11 +70,17 @@ pub struct Wrapper<P: Plugin, B: Backend<P>> {
     /// The bus and buffer configurations are static for the standalone target.
     audio_io_layout: AudioIOLayout,
     buffer_config: BufferConfig,
+    /// A copy of the transport information from the most recent process call, or `None` if the
+    /// plugin has not processed any audio yet. Used for `GuiContext::last_transport()`.
+    pub last_transport: AtomicCell<Option<Transport>>,
 
     /// Parameter changes that have been output by the GUI that have not yet been set in the plugin.
     /// This queue will be flushed at the end of every processing cycle, just like in the plugin
     /// versions.
     unprocessed_param_changes: ArrayQueue<(ParamPtr, f32)>,
+    /// The parameter automation read from the file passed to `--automation`, if any. This is moved
+    /// to the audio thread when it starts, where it's turned into an [`AutomationPlayer`].
+    automation_points: AtomicRefCell<Option<Vec<AutomationPoint>>>,
     /// The plugin is able to restore state through a method on the `GuiContext`. To avoid changing
     /// parameters mid-processing and running into garbled data if the host also tries to load state
     /// at the same time the restoring happens at the end of each processing call. If this zero
@@ -107,48 +116,18 @@ pub enum Task<P: Plugin> {
 }
 
 /// Errors that may arise while initializing the wrapped plugins.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum WrapperError {
     /// The plugin returned `false` during initialization.
     InitializationFailed,
-}
-
-struct WrapperWindowHandler {
-    /// The editor handle for the plugin's open editor. The editor should clean itself up when it
-    /// gets dropped.
-    _editor_handle: Box<dyn Any>,
-
-    /// This is used to communicate with the wrapper from the audio thread and from within the
-    /// baseview window handler on the GUI thread.
-    gui_task_receiver: channel::Receiver<GuiTask>,
-}
-
-/// A message sent to the GUI thread.
-pub enum GuiTask {
-    /// Resize the window to the following physical size.
-    Resize(u32, u32),
-    /// The close window. This will cause the application to terminate.
-    Close,
-}
-
-impl WindowHandler for WrapperWindowHandler {
-    fn on_frame(&mut self, window: &mut Window) {
-        while let Ok(task) = self.gui_task_receiver.try_recv() {
-            match task {
-                GuiTask::Resize(new_width, new_height) => {
-                    window.resize(baseview::Size {
-                        width: new_width as f64,
-                        height: new_height as f64,
-                    });
-                }
-                GuiTask::Close => window.close(),
-            }
-        }
-    }
-
-    fn on_event(&mut self, _window: &mut Window, _event: baseview::Event) -> EventStatus {
-        EventStatus::Ignored
-    }
+    /// The state file passed to `--load` could not be loaded. Contains the formatted error.
+    LoadStateFailed(String),
+    /// The plugin's state could not be written to the file passed to `--save-on-exit`. Contains
+    /// the formatted error.
+    SaveStateFailed(String),
+    /// The file passed to `--automation` could not be read or contains invalid automation.
+    /// Contains the formatted error.
+    LoadAutomationFailed(String),
 }
 
 impl<P: Plugin, B: Backend<P>> MainThreadExecutor<Task<P>> for Wrapper<P, B> {
@@ -249,7 +228,9 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             },
             config,
 
+            last_transport: AtomicCell::new(None),
             unprocessed_param_changes: ArrayQueue::new(EVENT_QUEUE_CAPACITY),
+            automation_points: AtomicRefCell::new(None),
             updated_state_sender,
             updated_state_receiver,
             current_latency: AtomicU32::new(0),
@@ -267,7 +248,9 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                     let wrapper = wrapper.clone();
 
                     move |task| {
-                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        let priority = P::background_task_priority(&task);
+                        let task_posted =
+                            wrapper.schedule_background(Task::PluginTask(task), priority);
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
@@ -282,6 +265,15 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             })
             .map(|editor| Arc::new(Mutex::new(editor)));
 
+        // Plugins can rely on the sample rate being valid. The backends should always report a
+        // valid sample rate.
+        nih_debug_assert!(
+            wrapper.buffer_config.sample_rate.is_finite()
+                && wrapper.buffer_config.sample_rate > 0.0,
+            "Invalid sample rate: {}",
+            wrapper.buffer_config.sample_rate
+        );
+
         // Before initializing the plugin, make sure all smoothers are set the the default values
         for param in wrapper.param_id_to_ptr.values() {
             unsafe { param.update_smoother(wrapper.buffer_config.sample_rate, true) };
@@ -299,15 +291,30 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             process_wrapper(|| plugin.reset());
         }
 
+        // The state is loaded after the plugin has been initialized so this behaves the same way
+        // as a host restoring the plugin's state, but before any audio has been processed
+        if let Some(path) = &wrapper.config.load {
+            wrapper
+                .load_state_file(path)
+                .map_err(|err| WrapperError::LoadStateFailed(format!("{err:#}")))?;
+        }
+
+        if let Some(path) = &wrapper.config.automation {
+            let points = automation::read_automation_file(path, &wrapper.param_id_to_ptr)
+                .map_err(|err| WrapperError::LoadAutomationFailed(format!("{err:#}")))?;
+            *wrapper.automation_points.borrow_mut() = Some(points);
+        }
+
         Ok(wrapper)
     }
 
-    /// Open the editor, start processing audio, and block this thread until the editor is closed.
-    /// If the plugin does not have an editor, then this will block until SIGINT is received.
+    /// Open the editor using `window_loop`, start processing audio, and block this thread until the
+    /// editor is closed. If the plugin does not have an editor, then this will block until SIGINT
+    /// is received.
     ///
     /// Will return an error if the plugin threw an error during audio processing or if the editor
     /// could not be opened.
-    pub fn run(self: Arc<Self>) -> Result<(), WrapperError> {
+    pub fn run(self: Arc<Self>, window_loop: &mut dyn WindowLoop) -> Result<(), WrapperError> {
         let (gui_task_sender, gui_task_receiver) = channel::bounded(512);
         *self.gui_tasks_sender.borrow_mut() = Some(gui_task_sender.clone());
 
@@ -326,57 +333,24 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
 
                 // DPI scaling should not be used on macOS since the OS handles it there
                 #[cfg(target_os = "macos")]
-                let scaling_policy = baseview::WindowScalePolicy::SystemScaleFactor;
+                let scale_factor = None;
                 #[cfg(not(target_os = "macos"))]
-                let scaling_policy = {
+                let scale_factor = {
                     editor.lock().set_scale_factor(self.config.dpi_scale);
-                    baseview::WindowScalePolicy::ScaleFactor(self.config.dpi_scale as f64)
+                    Some(self.config.dpi_scale)
                 };
 
-                let (width, height) = editor.lock().size();
-                Window::open_blocking(
-                    WindowOpenOptions {
-                        title: String::from(P::NAME),
-                        size: baseview::Size {
-                            width: width as f64,
-                            height: height as f64,
-                        },
-                        scale: scaling_policy,
-                        gl_config: None,
-                    },
-                    move |window| {
-                        let parent_handle = match window.raw_window_handle() {
-                            raw_window_handle::RawWindowHandle::Xlib(handle) => {
-                                ParentWindowHandle::X11Window(handle.window as u32)
-                            }
-                            raw_window_handle::RawWindowHandle::Xcb(handle) => {
-                                ParentWindowHandle::X11Window(handle.window)
-                            }
-                            raw_window_handle::RawWindowHandle::AppKit(handle) => {
-                                ParentWindowHandle::AppKitNsView(handle.ns_view)
-                            }
-                            raw_window_handle::RawWindowHandle::Win32(handle) => {
-                                ParentWindowHandle::Win32Hwnd(handle.hwnd)
-                            }
-                            handle => unimplemented!("Unsupported window handle: {handle:?}"),
-                        };
-
-                        // TODO: This spawn function should be able to fail and return an error, but
-                        //       baseview does not support this yet. Once this is added, we should
-                        //       immediately close the parent window when this happens so the loop
-                        //       can exit.
-                        let editor_handle = editor.lock().spawn(parent_handle, context);
-
-                        WrapperWindowHandler {
-                            _editor_handle: editor_handle,
-                            gui_task_receiver,
-                        }
-                    },
-                )
+                window_loop.run(EditorWindow::new(
+                    String::from(P::NAME),
+                    editor,
+                    context,
+                    scale_factor,
+                    gui_task_receiver,
+                ));
             }
             None => {
                 // TODO: Properly block until SIGINT is received if the plugin does not have an editor
-                // TODO: Make sure to handle `GuiTask::Close` here as well
+                // TODO: Make sure to handle `WindowRequest::Close` here as well
                 nih_log!("{} does not have a GUI, blocking indefinitely...", P::NAME);
                 std::thread::park();
             }
@@ -389,6 +363,11 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         // application, but it seems like a good idea to stay consistent.
         self.plugin.lock().deactivate();
 
+        if let Some(path) = &self.config.save_on_exit {
+            self.save_state_file(path)
+                .map_err(|err| WrapperError::SaveStateFailed(format!("{err:#}")))?;
+        }
+
         Ok(())
     }
 
@@ -433,6 +412,18 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         }
     }
 
+    /// Get a state object containing the default values for all of the plugin's parameters. May be
+    /// called by the plugin's GUI to reset the parameters.
+    pub fn get_default_state_object(&self) -> PluginState {
+        unsafe {
+            state::serialize_default_object::<P>(
+                self.param_id_to_ptr
+                    .iter()
+                    .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+            )
+        }
+    }
+
     /// Update the plugin's internal state, called by the plugin itself from the GUI thread. To
     /// prevent corrupting data and changing parameters during processing the actual state is only
     /// updated at the end of the audio processing cycle.
@@ -453,15 +444,77 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         }
     }
 
+    /// Load the plugin's state from a file written by the plugin wrappers or by
+    /// [`save_state_file()`][Self::save_state_file()], and restore it. Returns an error if the
+    /// file could not be parsed or if it contains parameters the plugin doesn't have. This should
+    /// only be called before the audio thread has started.
+    fn load_state_file(&self, path: &Path) -> Result<()> {
+        let state_bytes =
+            fs::read(path).with_context(|| format!("Could not read '{}'", path.display()))?;
+        let mut state = unsafe { state::deserialize_json(&state_bytes) }
+            .with_context(|| format!("'{}' does not contain a valid state", path.display()))?;
+
+        // The plugin may migrate old parameter IDs in its `filter_state()` implementation, so the
+        // IDs need to be checked after that has been applied. `deserialize_object()` also calls
+        // `filter_state()`, so this is done on a copy.
+        let mut filtered_state = state.clone();
+        P::filter_state(&mut filtered_state);
+        let unknown_param_ids: Vec<&str> = filtered_state
+            .params
+            .keys()
+            .filter(|param_id| !self.param_id_to_ptr.contains_key(*param_id))
+            .map(String::as_str)
+            .collect();
+        if !unknown_param_ids.is_empty() {
+            anyhow::bail!(
+                "'{}' contains parameters that {} does not have: {}",
+                path.display(),
+                P::NAME,
+                unknown_param_ids.join(", ")
+            );
+        }
+
+        if !self.set_state_inner(&mut state, false) {
+            anyhow::bail!(
+                "{} could not restore the state from '{}'",
+                P::NAME,
+                path.display()
+            );
+        }
+
+        nih_log!("Loaded the plugin's state from '{}'", path.display());
+
+        Ok(())
+    }
+
+    /// Write the plugin's current state to a file. This uses the same format the plugin wrappers
+    /// use to store the plugin's state in the host's project files.
+    fn save_state_file(&self, path: &Path) -> Result<()> {
+        let state_bytes = unsafe {
+            state::serialize_json::<P>(
+                self.params.clone(),
+                self.param_id_to_ptr
+                    .iter()
+                    .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+            )
+        }?;
+        fs::write(path, state_bytes)
+            .with_context(|| format!("Could not write to '{}'", path.display()))?;
+
+        nih_log!("Saved the plugin's state to '{}'", path.display());
+
+        Ok(())
+    }
+
     /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
     /// can be run in the background without blocking either the GUI or the audio thread.
     ///
     /// If the task queue is full, then this will return false.
     #[must_use]
-    pub fn schedule_background(&self, task: Task<P>) -> bool {
+    pub fn schedule_background(&self, task: Task<P>, priority: TaskPriority) -> bool {
         let event_loop = self.event_loop.borrow();
         let event_loop = event_loop.as_ref().unwrap();
-        event_loop.schedule_background(task)
+        event_loop.schedule_background(task, priority)
     }
 
     /// Posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be delegated
@@ -483,7 +536,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
 
             // This will cause the editor to be resized at the start of the next frame
             let push_successful = gui_tasks_sender
-                .send(GuiTask::Resize(unscaled_width, unscaled_height))
+                .send(WindowRequest::Resize(unscaled_width, unscaled_height))
                 .is_ok();
             nih_debug_assert!(push_successful, "Could not queue window resize");
         }
@@ -503,8 +556,23 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
     fn run_audio_thread(
         self: Arc<Self>,
         should_terminate: Arc<AtomicBool>,
-        gui_task_sender: channel::Sender<GuiTask>,
+        gui_task_sender: channel::Sender<WindowRequest>,
     ) {
+        // The test signal replaces the plugin's main input, so it's ignored for plugins without one
+        let mut test_signal = if self.audio_io_layout.main_input_channels.is_some() {
+            TestSignalGenerator::from_config(&self.config, self.buffer_config.sample_rate)
+        } else {
+            None
+        };
+        let mut automation = self.automation_points.borrow_mut().take().map(|points| {
+            AutomationPlayer::new(
+                points,
+                self.buffer_config.max_buffer_size as usize,
+                self.audio_io_layout,
+                EVENT_QUEUE_CAPACITY,
+            )
+        });
+
         self.clone().backend.borrow_mut().run(
             move |buffer, aux, transport, input_events, output_events| {
                 // TODO: This process wrapper should actually be in the backends (since the backends
@@ -515,18 +583,37 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                         return false;
                     }
 
-                    let sample_rate = self.buffer_config.sample_rate;
+                    if let Some(test_signal) = &mut test_signal {
+                        test_signal.process(buffer);
+                    }
+
                     {
                         let mut plugin = self.plugin.lock();
-                        if let ProcessStatus::Error(err) = plugin.process(
-                            buffer,
-                            aux,
-                            &mut self.make_process_context(transport, input_events, output_events),
-                        ) {
+                        let result = match &mut automation {
+                            Some(automation) => self.process_automated(
+                                &mut plugin,
+                                automation,
+                                buffer,
+                                aux,
+                                transport,
+                                input_events,
+                                output_events,
+                            ),
+                            None => self.process_block(
+                                &mut plugin,
+                                buffer,
+                                aux,
+                                transport,
+                                input_events,
+                                output_events,
+                            ),
+                        };
+                        if let ProcessStatus::Error(err) = result {
                             nih_error!("The plugin returned an error while processing:");
                             nih_error!("{}", err);
 
-                            let push_successful = gui_task_sender.send(GuiTask::Close).is_ok();
+                            let push_successful =
+                                gui_task_sender.send(WindowRequest::Close).is_ok();
                             nih_debug_assert!(
                                 push_successful,
                                 "Could not queue window close, the editor will remain open"
@@ -544,17 +631,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                     while let Some((param_ptr, normalized_value)) =
                         self.unprocessed_param_changes.pop()
                     {
-                        if unsafe { param_ptr.set_normalized_value(normalized_value) } {
-                            unsafe { param_ptr.update_smoother(sample_rate, false) };
-                            let task_posted = self.schedule_gui(Task::ParameterValueChanged(
-                                param_ptr,
-                                normalized_value,
-                            ));
-                            nih_debug_assert!(
-                                task_posted,
-                                "The task queue is full, dropping task..."
-                            );
-                        }
+                        self.set_normalized_value(param_ptr, normalized_value);
                     }
 
                     // After processing audio, we'll check if the editor has sent us updated plugin
@@ -565,7 +642,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                     //        alternative that doesn't do that
                     let updated_state = permit_alloc(|| self.updated_state_receiver.try_recv());
                     if let Ok(mut state) = updated_state {
-                        self.set_state_inner(&mut state);
+                        self.set_state_inner(&mut state, P::SMOOTH_RUNTIME_STATE_RESTORES);
 
                         // We'll pass the state object back to the GUI thread so deallocation can
                         // happen there without potentially blocking the audio thread
@@ -595,12 +672,127 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         WrapperInitContext { wrapper: self }
     }
 
+    /// Run the plugin's process function on a single block of audio. If the plugin panics, then
+    /// the outputs are silenced and an error is returned.
+    fn process_block(
+        &self,
+        plugin: &mut P,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        transport: Transport,
+        input_events: &[PluginNoteEvent<P>],
+        output_events: &mut Vec<PluginNoteEvent<P>>,
+    ) -> ProcessStatus {
+        let mut context = self.make_process_context(transport, input_events, output_events);
+        // Some audio backends call this function from a C callback
+        catch_panic("Plugin::process()", || {
+            plugin.process(buffer, aux, &mut context)
+        })
+        .unwrap_or_else(|| {
+            silence_outputs(buffer, aux);
+            ProcessStatus::Error("The plugin panicked while processing audio")
+        })
+    }
+
+    /// The same as [`process_block()`][Self::process_block()], but while applying the parameter
+    /// changes from an automation file. If the plugin enables sample accurate automation, then
+    /// the buffer is split into smaller blocks so every change happens at the exact sample from
+    /// the automation file. See [`AutomationPlayer::prepare_block()`].
+    #[allow(clippy::too_many_arguments)]
+    fn process_automated(
+        &self,
+        plugin: &mut P,
+        automation: &mut AutomationPlayer<P::SysExMessage>,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        transport: Transport,
+        input_events: &[PluginNoteEvent<P>],
+        output_events: &mut Vec<PluginNoteEvent<P>>,
+    ) -> ProcessStatus {
+        let num_samples = buffer.samples();
+        let mut result = ProcessStatus::Normal;
+        let mut block_start = 0;
+        while block_start < num_samples {
+            let block_end = automation.prepare_block(
+                block_start,
+                num_samples,
+                P::SAMPLE_ACCURATE_AUTOMATION,
+                input_events,
+                |point| self.set_normalized_value(point.param_ptr, point.normalized_value),
+            );
+
+            let num_output_events = output_events.len();
+            let block_input_events = std::mem::take(&mut automation.block_input_events);
+            result = if block_start == 0 && block_end == num_samples {
+                self.process_block(
+                    plugin,
+                    buffer,
+                    aux,
+                    transport,
+                    &block_input_events,
+                    output_events,
+                )
+            } else {
+                // SAFETY: `buffer` and `aux` are not used until the block has been processed
+                let buffers = unsafe {
+                    automation.create_sub_block_buffers(
+                        buffer,
+                        aux,
+                        block_start,
+                        block_end - block_start,
+                    )
+                };
+                let mut block_aux = AuxiliaryBuffers {
+                    inputs: buffers.aux_inputs,
+                    outputs: buffers.aux_outputs,
+                };
+
+                self.process_block(
+                    plugin,
+                    buffers.main_buffer,
+                    &mut block_aux,
+                    transport.at_sample_offset(block_start as u32),
+                    &block_input_events,
+                    output_events,
+                )
+            };
+            automation.block_input_events = block_input_events;
+
+            // The plugin's output events are relative to the start of the block
+            for event in &mut output_events[num_output_events..] {
+                event.add_timing(block_start as u32);
+            }
+
+            if let ProcessStatus::Error(_) = result {
+                break;
+            }
+
+            block_start = block_end;
+        }
+
+        automation.advance(num_samples);
+
+        result
+    }
+
+    /// Set a parameter's value from the audio thread and inform the editor about the change.
+    fn set_normalized_value(&self, param_ptr: ParamPtr, normalized_value: f32) {
+        if unsafe { param_ptr.set_normalized_value(normalized_value) } {
+            unsafe { param_ptr.update_smoother(self.buffer_config.sample_rate, false) };
+            let task_posted =
+                self.schedule_gui(Task::ParameterValueChanged(param_ptr, normalized_value));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
     fn make_process_context<'a>(
         &'a self,
         transport: Transport,
         input_events: &'a [PluginNoteEvent<P>],
         output_events: &'a mut Vec<PluginNoteEvent<P>>,
     ) -> WrapperProcessContext<'a, P, B> {
+        self.last_transport.store(Some(transport));
+
         WrapperProcessContext {
             wrapper: self,
             input_events,
@@ -615,13 +807,15 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
     /// centralize all of this behavior. Includes `permit_alloc()`s around the deserialization and
     /// initialization for the use case where `set_state_object_from_gui()` was called while the
     /// plugin is process audio.
+    /// If `smooth_params` is set, then the parameters' smoothers will ramp to the restored values
+    /// instead of being reset.
     ///
     /// Implicitly emits `Task::ParameterValuesChanged`.
     ///
     /// # Notes
     ///
     /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
-    fn set_state_inner(&self, state: &mut PluginState) -> bool {
+    fn set_state_inner(&self, state: &mut PluginState, smooth_params: bool) -> bool {
         // FIXME: This is obviously not realtime-safe, but loading presets without doing this could
         //        lead to inconsistencies. It's the plugin's responsibility to not perform any
         //        realtime-unsafe work when the initialize function is called a second time if it
@@ -634,6 +828,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                 self.params.clone(),
                 |param_id| self.param_id_to_ptr.get(param_id).copied(),
                 Some(&self.buffer_config),
+                smooth_params,
             )
         });
         if !success {
@@ -3,11 +3,12 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 
 use crate::params::ParamMut;
-use crate::prelude::{BufferConfig, Param, ParamPtr, Params, Plugin};
+use crate::prelude::{BufferConfig, Param, ParamFlags, ParamPtr, Params, Plugin};
+use crate::wrapper::util::catch_panic;
 
 // These state objects are also exposed directly to the plugin so it can do its own internal preset
 // management
@@ -50,6 +51,59 @@ pub struct PluginState {
     /// The individual fields are also serialized as JSON so they can safely be restored
     /// independently of the other fields.
     pub fields: BTreeMap<String, String>,
+
+    /// The IDs of the parameters in `params` that only contain editor or configuration state,
+    /// because they have the [`ParamFlags::VIEW_STATE`] flag. Everything else affects the plugin's
+    /// sound.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub view_state_params: BTreeSet<String>,
+    /// The keys of the fields in `fields` that only contain editor or configuration state, because
+    /// they were annotated with `#[view_state]`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub view_state_fields: BTreeSet<String>,
+}
+
+impl PluginState {
+    /// Get a copy of this state with only the parameters and fields that affect the plugin's
+    /// sound. Loading this state leaves the editor and configuration state untouched, which is
+    /// useful for comparing presets.
+    pub fn without_view_state(&self) -> PluginState {
+        let mut state = self.filter_view_state(false);
+        state.view_state_params.clear();
+        state.view_state_fields.clear();
+
+        state
+    }
+
+    /// Get a copy of this state with only the parameters and fields that contain editor or
+    /// configuration state. This is the inverse of
+    /// [`without_view_state()`][Self::without_view_state()]. Loading this state leaves the
+    /// plugin's sound untouched, so it can be used to restore a window layout independently of the
+    /// sound parameters.
+    pub fn view_state_only(&self) -> PluginState {
+        self.filter_view_state(true)
+    }
+
+    /// Copy the parameters and fields that either are or aren't part of the view state.
+    fn filter_view_state(&self, view_state: bool) -> PluginState {
+        PluginState {
+            version: self.version.clone(),
+            params: self
+                .params
+                .iter()
+                .filter(|(param_id, _)| self.view_state_params.contains(*param_id) == view_state)
+                .map(|(param_id, value)| (param_id.clone(), value.clone()))
+                .collect(),
+            fields: self
+                .fields
+                .iter()
+                .filter(|(key, _)| self.view_state_fields.contains(*key) == view_state)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            view_state_params: self.view_state_params.clone(),
+            view_state_fields: self.view_state_fields.clone(),
+        }
+    }
 }
 
 /// Create a parameters iterator from the hashtables stored in the plugin wrappers. This avoids
@@ -89,45 +143,81 @@ pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
     // We'll serialize parameter values as a simple `string_param_id: display_value` map.
     // NOTE: If the plugin is being modulated (and the plugin is a CLAP plugin in Bitwig Studio),
     //       then this should save the values without any modulation applied to it
-    let params: BTreeMap<_, _> = params_iter
-        .into_iter()
-        .map(|(param_id_str, param_ptr)| match param_ptr {
-            ParamPtr::FloatParam(p) => (
-                param_id_str.clone(),
-                ParamValue::F32((*p).unmodulated_plain_value()),
-            ),
-            ParamPtr::IntParam(p) => (
-                param_id_str.clone(),
-                ParamValue::I32((*p).unmodulated_plain_value()),
-            ),
-            ParamPtr::BoolParam(p) => (
-                param_id_str.clone(),
-                ParamValue::Bool((*p).unmodulated_plain_value()),
-            ),
-            ParamPtr::EnumParam(p) => (
-                // Enums are either serialized based on the active variant's index (which may not be
-                // the same as the discriminator), or a custom set stable string ID. The latter
-                // allows the variants to be reordered.
-                param_id_str.clone(),
-                match (*p).unmodulated_plain_id() {
-                    Some(id) => ParamValue::String(id.to_owned()),
-                    None => ParamValue::I32((*p).unmodulated_plain_value()),
-                },
-            ),
-        })
-        .collect();
+    let (params, view_state_params) = serialize_params(params_iter, |param_ptr| match param_ptr {
+        ParamPtr::FloatParam(p) => ParamValue::F32((*p).unmodulated_plain_value()),
+        ParamPtr::IntParam(p) => ParamValue::I32((*p).unmodulated_plain_value()),
+        ParamPtr::BoolParam(p) => ParamValue::Bool((*p).unmodulated_plain_value()),
+        // Enums are either serialized based on the active variant's index (which may not be the
+        // same as the discriminator), or a custom set stable string ID. The latter allows the
+        // variants to be reordered.
+        ParamPtr::EnumParam(p) => match (*p).unmodulated_plain_id() {
+            Some(id) => ParamValue::String(id.to_owned()),
+            None => ParamValue::I32((*p).unmodulated_plain_value()),
+        },
+    });
 
     // The plugin can also persist arbitrary fields alongside its parameters. This is useful for
     // storing things like sample data.
     let fields = plugin_params.serialize_fields();
+    let view_state_fields = plugin_params.view_state_field_keys().into_iter().collect();
 
     PluginState {
         version: String::from(P::VERSION),
         params,
         fields,
+        view_state_params,
+        view_state_fields,
+    }
+}
+
+/// Create a state object where every parameter is set to its default value. Persistent fields don't
+/// have a default value, so they're not included in this object. Loading this state thus resets
+/// all parameters while leaving the persistent fields untouched. See [`serialize_object()`] for
+/// more information on the parameters iterator.
+pub(crate) unsafe fn serialize_default_object<'a, P: Plugin>(
+    params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+) -> PluginState {
+    let (params, view_state_params) = serialize_params(params_iter, |param_ptr| match param_ptr {
+        ParamPtr::FloatParam(p) => ParamValue::F32((*p).default_plain_value()),
+        ParamPtr::IntParam(p) => ParamValue::I32((*p).default_plain_value()),
+        ParamPtr::BoolParam(p) => ParamValue::Bool((*p).default_plain_value()),
+        // The variant index is only used within the same plugin version, so the stable IDs
+        // aren't needed here
+        ParamPtr::EnumParam(p) => ParamValue::I32((*p).default_plain_value()),
+    });
+
+    PluginState {
+        version: String::from(P::VERSION),
+        params,
+        fields: BTreeMap::new(),
+        view_state_params,
+        view_state_fields: BTreeSet::new(),
     }
 }
 
+/// Collect the parameter values computed by `param_value` for [`serialize_object()`] and
+/// [`serialize_default_object()`], together with the IDs of the parameters that are part of the
+/// view state.
+unsafe fn serialize_params<'a>(
+    params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    param_value: impl Fn(ParamPtr) -> ParamValue,
+) -> (BTreeMap<String, ParamValue>, BTreeSet<String>) {
+    // NOTE: Read-only parameters are meters set by the plugin, so they're not part of the state
+    let mut view_state_params = BTreeSet::new();
+    let params = params_iter
+        .into_iter()
+        .filter(|(_, param_ptr)| !param_ptr.flags().contains(ParamFlags::READ_ONLY))
+        .inspect(|(param_id_str, param_ptr)| {
+            if param_ptr.flags().contains(ParamFlags::VIEW_STATE) {
+                view_state_params.insert((*param_id_str).clone());
+            }
+        })
+        .map(|(param_id_str, param_ptr)| (param_id_str.clone(), param_value(param_ptr)))
+        .collect();
+
+    (params, view_state_params)
+}
+
 /// Serialize a plugin's state to a vector containing JSON data. This can (and should) be shared
 /// across plugin formats. If the `zstd` feature is enabled, then the state will be compressed using
 /// Zstandard.
@@ -135,7 +225,11 @@ pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
 ) -> Result<Vec<u8>> {
-    let plugin_state = serialize_object::<P>(plugin_params, params_iter);
+    // This calls `Params::serialize_fields()`, which may contain user code that panics
+    let plugin_state = catch_panic("Params::serialize_fields()", || {
+        serialize_object::<P>(plugin_params, params_iter)
+    })
+    .context("The plugin panicked while serializing its state")?;
     let json = serde_json::to_vec(&plugin_state).context("Could not format as JSON")?;
 
     #[cfg(feature = "zstd")]
@@ -167,7 +261,8 @@ pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
 /// include expensive user written code. See [`make_params_getter()`].
 ///
 /// Make sure to reinitialize plugin after deserializing the state so it can react to the new
-/// parameter values. The smoothers have already been reset by this function.
+/// parameter values. The smoothers have already been reset by this function, unless
+/// `smooth_params` is set. In that case the smoothers will ramp to the new values instead.
 ///
 /// The [`Plugin`] argument is used to call [`Plugin::filter_state()`] just before loading the
 /// state.
@@ -176,6 +271,7 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_getter: impl Fn(&str) -> Option<ParamPtr>,
     current_buffer_config: Option<&BufferConfig>,
+    smooth_params: bool,
 ) -> bool {
     // This lets the plugin perform migrations on old state if needed
     P::filter_state(state);
@@ -190,6 +286,12 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
             }
         };
 
+        // States saved before a parameter was made read-only may still contain a value for it,
+        // but the plugin is the only one that should set these parameters
+        if param_ptr.flags().contains(ParamFlags::READ_ONLY) {
+            continue;
+        }
+
         match (param_ptr, param_value) {
             (ParamPtr::FloatParam(p), ParamValue::F32(v)) => {
                 (*p).set_plain_value(*v);
@@ -225,9 +327,10 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
             }
         }
 
-        // Make sure everything starts out in sync
+        // Make sure everything starts out in sync, unless the plugin wants to smoothly transition
+        // to the new values
         if let Some(sample_rate) = sample_rate {
-            param_ptr.update_smoother(sample_rate, true);
+            param_ptr.update_smoother(sample_rate, !smooth_params);
         }
     }
 
@@ -2,7 +2,12 @@ use backtrace::Backtrace;
 use std::cmp;
 use std::marker::PhantomData;
 use std::os::raw::c_char;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
+use crate::audio_setup::{AudioIOLayout, AuxiliaryBuffers};
+use crate::buffer::Buffer;
+use crate::plugin::AuxOutputPortNames;
 use crate::util::permit_alloc;
 
 pub(crate) mod buffer_management;
@@ -174,6 +179,196 @@ fn log_panics() {
     }));
 }
 
+/// Run `f` and catch any panics that occur in it. Unwinding across the FFI boundary into the host
+/// is undefined behavior, so this should be used for plugin callbacks that are called directly
+/// from the host. Returns `None` if `f` panicked, in which case the wrapper should report a failure
+/// to the host. The panic message itself is already logged by the panic hook installed in
+/// [`setup_logger()`].
+///
+/// This only works when the plugin is compiled with `panic = "unwind"`, which is the default.
+pub fn catch_panic<T, F: FnOnce() -> T>(description: &str, f: F) -> Option<T> {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            // The panic payload is a boxed value, so dropping it deallocates
+            permit_alloc(|| {
+                drop(payload);
+                nih_error!("Caught a panic in {description}, reporting a failure")
+            });
+            None
+        }
+    }
+}
+
+/// The name for the auxiliary output port with the given index, or `None` if the port doesn't
+/// exist. This uses the function from
+/// [`Plugin::aux_output_port_names()`][crate::prelude::Plugin::aux_output_port_names()] if the
+/// plugin has one, and it falls back to the name from the audio IO layout if that function returns
+/// `None` or panics.
+pub fn aux_output_port_name(
+    aux_output_port_names: Option<&AuxOutputPortNames>,
+    audio_io_layout: &AudioIOLayout,
+    aux_output_idx: usize,
+) -> Option<String> {
+    if aux_output_idx >= audio_io_layout.aux_output_ports.len() {
+        return None;
+    }
+
+    aux_output_port_names
+        .and_then(|f| {
+            catch_panic("Plugin::aux_output_port_names()", || {
+                f(audio_io_layout, aux_output_idx)
+            })
+            .flatten()
+        })
+        .or_else(|| audio_io_layout.aux_output_name(aux_output_idx))
+}
+
+/// Fill the main output buffer and all auxiliary output buffers with silence. This is used to
+/// bypass the plugin's output after [`Plugin::process()`][crate::prelude::Plugin::process()]
+/// panicked, since the buffers may contain garbage at that point.
+pub fn silence_outputs(main_buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+    for channel in main_buffer.as_slice() {
+        channel.fill(0.0);
+    }
+    for buffer in aux.outputs.iter_mut() {
+        for channel in buffer.as_slice() {
+            channel.fill(0.0);
+        }
+    }
+}
+
+/// A short linear fade-in applied to the plugin's outputs after the host has reset the plugin, as
+/// configured through [`Plugin::RESET_FADE_IN_MS`][crate::prelude::Plugin::RESET_FADE_IN_MS]. The
+/// fade may span multiple process calls if the blocks are shorter than the fade.
+#[derive(Debug, Default)]
+pub struct ResetFadeIn {
+    /// The fade's length in samples. This is zero when no fade has been started.
+    length: usize,
+    /// The number of samples of the fade that have already been applied. The fade has finished
+    /// once this reaches `length`.
+    position: usize,
+}
+
+impl ResetFadeIn {
+    /// Start a new fade-in lasting `length_ms` milliseconds. This should be called right after the
+    /// host-initiated `Plugin::reset()` calls.
+    pub fn start(&mut self, length_ms: f32, sample_rate: f32) {
+        self.length = (length_ms / 1000.0 * sample_rate).round().max(0.0) as usize;
+        self.position = 0;
+    }
+
+    /// Apply the fade to the main output buffer and all auxiliary output buffers if a fade is in
+    /// progress. The gain starts at zero for the first sample after the reset, and it reaches unity
+    /// after the fade's length.
+    pub fn apply(&mut self, main_buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+        if self.position >= self.length {
+            return;
+        }
+
+        let (position, length) = (self.position, self.length);
+        let apply_fade = |channels: &mut [&mut [f32]]| {
+            for channel in channels {
+                for (sample_idx, sample) in channel.iter_mut().take(length - position).enumerate() {
+                    *sample *= (position + sample_idx) as f32 / length as f32;
+                }
+            }
+        };
+
+        apply_fade(main_buffer.as_slice());
+        for buffer in aux.outputs.iter_mut() {
+            apply_fade(buffer.as_slice());
+        }
+
+        self.position = (position + main_buffer.samples()).min(length);
+    }
+}
+
+/// Keeps track of the plugin's latency for the CLAP and VST3 wrappers, and decides when the host
+/// needs to be notified about a latency change. The host is only notified when the latency differs
+/// from the latency it last queried, and multiple changes in quick succession are coalesced into a
+/// single notification. With [`Plugin::DEFER_LATENCY_CHANGES`] the notification can also be
+/// postponed until the plugin stops processing audio.
+///
+/// The wrapper notifies the host by scheduling a task on the main thread whenever one of these
+/// functions returns `true`. That task should then call
+/// [`take_pending()`][Self::take_pending()] to check whether the host still needs to be notified.
+///
+/// [`Plugin::DEFER_LATENCY_CHANGES`]: crate::prelude::Plugin::DEFER_LATENCY_CHANGES
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    /// The current latency in samples, as set by the plugin through the `InitContext` and the
+    /// `ProcessContext`.
+    current: AtomicU32,
+    /// The latency the host last queried. No notification is needed when the latency has been
+    /// changed back to this value before the host was notified.
+    reported: AtomicU32,
+    /// Whether a notification task has been scheduled that has not yet been handled.
+    notification_pending: AtomicBool,
+    /// Whether a latency change was made while notifications were deferred. The notification is
+    /// sent when [`flush_deferred()`][Self::flush_deferred()] is called.
+    notification_deferred: AtomicBool,
+}
+
+impl LatencyTracker {
+    /// The plugin's current latency in samples.
+    pub fn current(&self) -> u32 {
+        self.current.load(Ordering::SeqCst)
+    }
+
+    /// Return the plugin's current latency and remember it as the latency the host knows about.
+    /// This should be called when the host queries the plugin's latency.
+    pub fn report(&self) -> u32 {
+        let latency = self.current.load(Ordering::SeqCst);
+        self.reported.store(latency, Ordering::SeqCst);
+
+        latency
+    }
+
+    /// Change the plugin's latency. Returns `true` if the wrapper should schedule a task to notify
+    /// the host. If `defer` is set, then the notification is postponed until the next
+    /// [`flush_deferred()`][Self::flush_deferred()] call.
+    #[must_use]
+    pub fn set(&self, samples: u32, defer: bool) -> bool {
+        let old_latency = self.current.swap(samples, Ordering::SeqCst);
+        if old_latency == samples || samples == self.reported.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        if defer {
+            self.notification_deferred.store(true, Ordering::SeqCst);
+            false
+        } else {
+            !self.notification_pending.swap(true, Ordering::SeqCst)
+        }
+    }
+
+    /// Send any notifications that were deferred by [`set()`][Self::set()]. This should be called
+    /// when the plugin stops processing audio and when it gets deactivated. Returns `true` if the
+    /// wrapper should schedule a task to notify the host.
+    #[must_use]
+    pub fn flush_deferred(&self) -> bool {
+        self.notification_deferred.swap(false, Ordering::SeqCst)
+            && self.current.load(Ordering::SeqCst) != self.reported.load(Ordering::SeqCst)
+            && !self.notification_pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Mark the scheduled notification as handled. Returns `true` if the host still needs to be
+    /// notified, or `false` if the latency has since been changed back to the latency the host
+    /// already knows about. Any latency changes after this call will need a new notification.
+    #[must_use]
+    pub fn take_pending(&self) -> bool {
+        self.notification_pending.store(false, Ordering::SeqCst);
+        self.current.load(Ordering::SeqCst) != self.reported.load(Ordering::SeqCst)
+    }
+
+    /// Mark the notification as handled without notifying the host, for instance because the task
+    /// could not be scheduled.
+    pub fn cancel_pending(&self) {
+        self.notification_pending.store(false, Ordering::SeqCst);
+    }
+}
+
 /// A wrapper around the entire process function, including the plugin wrapper parts. This sets up
 /// `assert_no_alloc` if needed, while also making sure that things like FTZ are set up correctly if
 /// the host has not already done so.
@@ -279,6 +474,7 @@ mod miri {
     use std::ffi::CStr;
 
     use super::*;
+    use crate::prelude::{new_nonzero_u32, PortNames};
 
     #[test]
     fn strlcpy_normal() {
@@ -301,4 +497,139 @@ mod miri {
             Ok("Hello")
         );
     }
+
+    /// Run a block of ones through the fade, returning the faded channels.
+    fn process_block(fade_in: &mut ResetFadeIn, block_size: usize) -> Vec<Vec<f32>> {
+        let mut storage = vec![vec![1.0f32; block_size]; 2];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(block_size, |output_slices| {
+                *output_slices = storage
+                    .iter_mut()
+                    .map(|channel| &mut *(channel.as_mut_slice() as *mut [f32]))
+                    .collect();
+            })
+        };
+        let mut aux = AuxiliaryBuffers {
+            inputs: &mut [],
+            outputs: &mut [],
+        };
+
+        fade_in.apply(&mut buffer, &mut aux);
+
+        storage
+    }
+
+    #[test]
+    fn reset_fade_in() {
+        const BLOCK_SIZE: usize = 64;
+        const SAMPLE_RATE: f32 = 48_000.0;
+
+        // A 1 ms fade at 48 kHz lasts 48 samples
+        let mut fade_in = ResetFadeIn::default();
+        fade_in.start(1.0, SAMPLE_RATE);
+
+        let first_block = process_block(&mut fade_in, BLOCK_SIZE);
+        for channel in &first_block {
+            assert_eq!(channel[0], 0.0);
+            assert!(channel[1] > 0.0 && channel[47] < 1.0);
+            assert!(channel[48..].iter().all(|sample| *sample == 1.0));
+        }
+
+        // The fade should not affect any later blocks
+        let second_block = process_block(&mut fade_in, BLOCK_SIZE);
+        for channel in &second_block {
+            assert!(channel.iter().all(|sample| *sample == 1.0));
+        }
+
+        // Fades longer than a block continue in the next block
+        fade_in.start(1.0, SAMPLE_RATE);
+        let first_block = process_block(&mut fade_in, 32);
+        assert_eq!(first_block[0][31], 31.0 / 48.0);
+        let second_block = process_block(&mut fade_in, 32);
+        assert_eq!(second_block[0][0], 32.0 / 48.0);
+        assert!(second_block[0][16..].iter().all(|sample| *sample == 1.0));
+    }
+
+    #[test]
+    fn aux_output_port_names() {
+        const LAYOUT: AudioIOLayout = AudioIOLayout {
+            aux_output_ports: &[new_nonzero_u32(2); 3],
+            names: PortNames {
+                aux_outputs: &["Band 1", "Band 2", "Band 3"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        };
+
+        let names: AuxOutputPortNames = Box::new(|_, aux_output_idx| match aux_output_idx {
+            0 => Some(String::from("Renamed")),
+            1 => None,
+            _ => panic!("Oh no"),
+        });
+
+        // Panics fall back to the layout's names, just like returning `None`
+        assert_eq!(
+            aux_output_port_name(Some(&names), &LAYOUT, 0).as_deref(),
+            Some("Renamed")
+        );
+        assert_eq!(
+            aux_output_port_name(Some(&names), &LAYOUT, 1).as_deref(),
+            Some("Band 2")
+        );
+        assert_eq!(
+            aux_output_port_name(Some(&names), &LAYOUT, 2).as_deref(),
+            Some("Band 3")
+        );
+        assert_eq!(aux_output_port_name(Some(&names), &LAYOUT, 3), None);
+        assert_eq!(
+            aux_output_port_name(None, &LAYOUT, 0).as_deref(),
+            Some("Band 1")
+        );
+    }
+
+    #[test]
+    fn latency_notifications() {
+        let latency = LatencyTracker::default();
+        assert!(latency.set(64, false));
+        assert_eq!(latency.current(), 64);
+
+        // Changes are coalesced until the notification has been handled
+        assert!(!latency.set(128, false));
+        assert!(latency.take_pending());
+        assert_eq!(latency.report(), 128);
+
+        // Nothing needs to happen when the latency doesn't change, or when it changes back to the
+        // latency the host last queried before the notification was handled
+        assert!(!latency.set(128, false));
+        assert!(latency.set(256, false));
+        assert!(!latency.set(128, false));
+        assert!(!latency.take_pending());
+        assert!(latency.set(256, false));
+    }
+
+    #[test]
+    fn deferred_latency_notifications() {
+        let latency = LatencyTracker::default();
+        assert!(!latency.set(64, true));
+        assert!(!latency.set(128, true));
+        assert_eq!(latency.current(), 128);
+
+        // The deferred notification is only sent once
+        assert!(latency.flush_deferred());
+        assert!(!latency.flush_deferred());
+        assert!(latency.take_pending());
+        assert_eq!(latency.report(), 128);
+
+        // There's nothing to flush when the latency has been changed back in the meantime
+        assert!(!latency.set(256, true));
+        assert!(!latency.set(128, true));
+        assert!(!latency.flush_deferred());
+
+        // Or when the host has already queried the new latency, for instance after reactivating
+        // the plugin
+        assert!(!latency.set(256, true));
+        assert_eq!(latency.report(), 256);
+        assert!(!latency.flush_deferred());
+    }
 }
@@ -29,6 +29,9 @@ pub struct BufferManager {
     main_output_channel_pointers: Option<ChannelPointers>,
     aux_input_channel_pointers: Vec<Option<ChannelPointers>>,
     aux_output_channel_pointers: Vec<Option<ChannelPointers>>,
+    /// Whether the host has guaranteed that an auxiliary input only contains silence. Used to set
+    /// [`Buffer::is_silent()`] on the auxiliary input buffers.
+    aux_input_silent: Vec<bool>,
 
     /// The backing buffers that will be filled during `create_buffers`. This `'static` lifetime
     /// will be shortened when returning a reference to these buffers in `create_buffers` to match
@@ -42,10 +45,24 @@ pub struct BufferManager {
     aux_input_storage: Vec<Vec<Vec<f32>>>,
 
     aux_output_buffers: Vec<Buffer<'static>>,
+
+    /// The number of main input channels in the plugin's audio IO layout.
+    num_main_input_channels: usize,
+    /// Backs the main buffer when the host's main channel counts don't match the plugin's audio IO
+    /// layout. This is only allocated when remapping has been enabled using
+    /// [`with_main_io_remapping()`][Self::with_main_io_remapping()].
+    main_remapping_storage: Vec<Vec<f32>>,
+    /// Whether the main buffer created in the last `create_buffers()` call is backed by
+    /// `main_remapping_storage`. If it is, then [`finish_buffers()`][Self::finish_buffers()] needs
+    /// to write its contents back to the host's main output buffers.
+    main_io_remapped: bool,
+    /// The `sample_offset` and `num_samples` passed to the last `create_buffers()` call.
+    last_block: (usize, usize),
 }
 
 // SAFETY: The raw pointers in the `ChannelPointers` fields/vectors are only used as scratch storage
-//         inside of the `create_buffers()` function.
+//         inside of the `create_buffers()` function, and by `finish_buffers()` directly after the
+//         created buffers have been used.
 unsafe impl Send for BufferManager {}
 unsafe impl Sync for BufferManager {}
 
@@ -57,6 +74,9 @@ pub struct BufferSource<'a> {
     pub main_output_channel_pointers: &'a mut Option<ChannelPointers>,
     pub aux_input_channel_pointers: &'a mut [Option<ChannelPointers>],
     pub aux_output_channel_pointers: &'a mut [Option<ChannelPointers>],
+    /// Should only be set to `true` for an auxiliary input when the host guarantees that the input
+    /// is silent for the entire buffer, e.g. through CLAP's constant mask or VST3's silence flags.
+    pub aux_input_silent: &'a mut [bool],
 }
 
 /// Pointers to raw multichannel audio data for this port.
@@ -136,6 +156,7 @@ impl BufferManager {
             main_output_channel_pointers: None,
             aux_input_channel_pointers: vec![None; audio_io_layout.aux_input_ports.len()],
             aux_output_channel_pointers: vec![None; audio_io_layout.aux_output_ports.len()],
+            aux_input_silent: vec![false; audio_io_layout.aux_input_ports.len()],
 
             main_buffer,
 
@@ -143,14 +164,39 @@ impl BufferManager {
             aux_input_storage,
 
             aux_output_buffers,
+
+            num_main_input_channels: audio_io_layout
+                .main_input_channels
+                .map(NonZeroU32::get)
+                .unwrap_or(0) as usize,
+            main_remapping_storage: Vec::new(),
+            main_io_remapped: false,
+            last_block: (0, 0),
         }
     }
 
+    /// Up- or downmix the host's main input and output channels when their channel counts don't
+    /// match the plugin's audio IO layout, instead of only copying the overlapping channels. See
+    /// [`Plugin::REMAP_MAIN_IO_CHANNELS`][crate::prelude::Plugin::REMAP_MAIN_IO_CHANNELS]. When
+    /// this is enabled, the wrapper must call [`finish_buffers()`][Self::finish_buffers()] after
+    /// the plugin has processed the buffers.
+    pub fn with_main_io_remapping(mut self, max_buffer_size: usize) -> Self {
+        // NOTE: `vec![Vec::with_capacity(max_buffer_size); n]` would clone the inner vectors, and
+        //       those clones don't keep the original vector's capacity
+        self.main_remapping_storage = (0..self.main_buffer.channels())
+            .map(|_| Vec::with_capacity(max_buffer_size))
+            .collect();
+
+        self
+    }
+
     /// Initialize the buffers using the host provided buffer pointers and return a reference to the
     /// created buffers that can be passed to `Plugin::process()`. This accounts for in-place main
     /// IO, missing channel pointers, null pointers, and mismatching channel counts. All
     /// uninitialized buffer data (aux outputs, and main output channels with no matching input
-    /// channel) are filled with zeroes.
+    /// channel) are filled with zeroes. If remapping has been enabled with
+    /// [`with_main_io_remapping()`][Self::with_main_io_remapping()], then mismatching main channel
+    /// counts are up- or downmixed instead.
     ///
     /// `sample_offset` and `num_samples` can be used to slice a set of host channel pointers for
     /// sample accurate automation. If any of the outputs are missing because the host hasn't
@@ -175,90 +221,44 @@ impl BufferManager {
         self.main_output_channel_pointers = None;
         self.aux_input_channel_pointers.fill(None);
         self.aux_output_channel_pointers.fill(None);
+        self.aux_input_silent.fill(false);
         set_buffer_sources(&mut BufferSource {
             main_input_channel_pointers: &mut self.main_input_channel_pointers,
             main_output_channel_pointers: &mut self.main_output_channel_pointers,
             aux_input_channel_pointers: &mut self.aux_input_channel_pointers,
             aux_output_channel_pointers: &mut self.aux_output_channel_pointers,
+            aux_input_silent: &mut self.aux_input_silent,
         });
 
-        // The main buffer points directly to the main output pointers
-        self.main_buffer.set_slices(num_samples, |output_slices| {
-            match self.main_output_channel_pointers {
-                Some(output_channel_pointers) => {
-                    nih_debug_assert_eq!(output_slices.len(), output_channel_pointers.num_channels);
-                    for (channel_idx, output_slice) in output_slices
-                        .iter_mut()
-                        .enumerate()
-                        .take(output_channel_pointers.num_channels)
-                    {
-                        let output_channel_pointer =
-                            output_channel_pointers.ptrs.as_ptr().add(channel_idx);
-
-                        *output_slice = std::slice::from_raw_parts_mut(
-                            (*output_channel_pointer).add(sample_offset),
-                            num_samples,
-                        );
-                    }
-
-                    // If the caller/host should have provided buffer pointers but didn't then we
-                    // must get rid of any dangling slices
-                    output_slices[output_channel_pointers.num_channels..].fill_with(|| &mut [])
+        self.last_block = (sample_offset, num_samples);
+        self.main_io_remapped = !self.main_remapping_storage.is_empty()
+            && match (
+                self.main_input_channel_pointers,
+                self.main_output_channel_pointers,
+            ) {
+                (input_channel_pointers, Some(output_channel_pointers)) => {
+                    output_channel_pointers.num_channels != self.main_remapping_storage.len()
+                        || input_channel_pointers.is_some_and(|input_channel_pointers| {
+                            input_channel_pointers.num_channels != self.num_main_input_channels
+                        })
                 }
-                None => {
-                    nih_debug_assert_eq!(output_slices.len(), 0);
-
-                    // Same as above
-                    output_slices.fill_with(|| &mut [])
-                }
-            }
-        });
-
-        // Since NIH-plug processes audio in-place, main input data needs to be copied to the main
-        // output buffers
-        if let (Some(input_channel_pointers), Some(output_channel_pointers)) = (
-            self.main_input_channel_pointers,
-            self.main_output_channel_pointers,
-        ) {
-            self.main_buffer.set_slices(num_samples, |output_slices| {
-                for (channel_idx, output_slice) in output_slices
-                    .iter_mut()
-                    .enumerate()
-                    .take(input_channel_pointers.num_channels)
-                {
-                    let input_channel_pointer =
-                        *input_channel_pointers.ptrs.as_ptr().add(channel_idx);
-                    debug_assert!(channel_idx < output_channel_pointers.num_channels);
-                    let output_channel_pointer =
-                        *output_channel_pointers.ptrs.as_ptr().add(channel_idx);
-
-                    // If the host processes the main IO out of place then the inputs need to be
-                    // copied to the output buffers. Otherwise the input should already be there.
-                    if input_channel_pointer != output_channel_pointer {
-                        output_slice.copy_from_slice(std::slice::from_raw_parts_mut(
-                            input_channel_pointer.add(sample_offset),
-                            num_samples,
-                        ))
-                    }
-                }
-            });
+                (_, None) => false,
+            };
 
-            // Any excess channels will need to be filled with zeroes since they'd otherwise point
-            // to whatever was left in the buffer
-            if input_channel_pointers.num_channels < output_channel_pointers.num_channels {
-                self.main_buffer.set_slices(num_samples, |output_slices| {
-                    for slice in &mut output_slices[input_channel_pointers.num_channels..] {
-                        slice.fill(0.0);
-                    }
-                });
-            }
+        if self.main_io_remapped {
+            self.set_remapped_main_buffer_slices(sample_offset, num_samples);
+        } else {
+            self.set_main_buffer_slices(sample_offset, num_samples);
         }
 
         // Because NIH-plug's `Buffer` type is geared around in-place processing, auxiliary inputs
         // need to be copied to our own buffers first (backed by the 'storage' vectors on this
         // object). That way the plugin can modify those buffers like any other buffers.
-        for (input_channel_pointers, (input_storage, input_buffer)) in
-            self.aux_input_channel_pointers.iter().zip(
+        for ((input_channel_pointers, silent), (input_storage, input_buffer)) in self
+            .aux_input_channel_pointers
+            .iter()
+            .zip(self.aux_input_silent.iter())
+            .zip(
                 self.aux_input_storage
                     .iter_mut()
                     .zip(self.aux_input_buffers.iter_mut()),
@@ -314,6 +314,9 @@ impl BufferManager {
                     *channel_slice = &mut *(channel_storage.as_mut_slice() as *mut [f32]);
                 }
             });
+
+            // Missing inputs are filled with zeroes above, so those are always silent
+            input_buffer.set_silent(*silent || input_channel_pointers.is_none());
         }
 
         // The auxiliary output buffers can point directly to the host's buffers. This logic is the
@@ -371,6 +374,192 @@ impl BufferManager {
             aux_outputs: &mut self.aux_output_buffers,
         })
     }
+
+    /// Write the main buffer's contents back to the host's main output buffers if the host's main
+    /// channel counts did not match the plugin's layout during the last
+    /// [`create_buffers()`][Self::create_buffers()] call. This must be called after the plugin has
+    /// processed the created buffers when remapping has been enabled with
+    /// [`with_main_io_remapping()`][Self::with_main_io_remapping()]. Otherwise this does nothing.
+    ///
+    /// # Safety
+    ///
+    /// The main output `ChannelPointers` passed to the last `create_buffers()` call must still
+    /// point to memory regions that are valid to write to.
+    pub unsafe fn finish_buffers(&mut self) {
+        if !self.main_io_remapped {
+            return;
+        }
+
+        let (sample_offset, num_samples) = self.last_block;
+        if let Some(output_channel_pointers) = self.main_output_channel_pointers {
+            let storage = &self.main_remapping_storage;
+            remap_channels(
+                |channel_idx| storage[channel_idx].as_ptr(),
+                storage.len(),
+                |channel_idx| {
+                    (*output_channel_pointers.ptrs.as_ptr().add(channel_idx)).add(sample_offset)
+                },
+                output_channel_pointers.num_channels,
+                num_samples,
+            );
+        }
+    }
+
+    /// Point the main buffer to the host's main output buffers and copy the main inputs there. Used
+    /// in [`create_buffers()`][Self::create_buffers()] when the channel counts match the plugin's
+    /// layout or when remapping is disabled.
+    unsafe fn set_main_buffer_slices(&mut self, sample_offset: usize, num_samples: usize) {
+        // The main buffer points directly to the main output pointers
+        self.main_buffer.set_slices(num_samples, |output_slices| {
+            match self.main_output_channel_pointers {
+                Some(output_channel_pointers) => {
+                    nih_debug_assert_eq!(output_slices.len(), output_channel_pointers.num_channels);
+                    for (channel_idx, output_slice) in output_slices
+                        .iter_mut()
+                        .enumerate()
+                        .take(output_channel_pointers.num_channels)
+                    {
+                        let output_channel_pointer =
+                            output_channel_pointers.ptrs.as_ptr().add(channel_idx);
+
+                        *output_slice = std::slice::from_raw_parts_mut(
+                            (*output_channel_pointer).add(sample_offset),
+                            num_samples,
+                        );
+                    }
+
+                    // If the caller/host should have provided buffer pointers but didn't then we
+                    // must get rid of any dangling slices
+                    output_slices[output_channel_pointers.num_channels..].fill_with(|| &mut [])
+                }
+                None => {
+                    nih_debug_assert_eq!(output_slices.len(), 0);
+
+                    // Same as above
+                    output_slices.fill_with(|| &mut [])
+                }
+            }
+        });
+
+        // Since NIH-plug processes audio in-place, main input data needs to be copied to the main
+        // output buffers
+        if let (Some(input_channel_pointers), Some(output_channel_pointers)) = (
+            self.main_input_channel_pointers,
+            self.main_output_channel_pointers,
+        ) {
+            self.main_buffer.set_slices(num_samples, |output_slices| {
+                for (channel_idx, output_slice) in output_slices
+                    .iter_mut()
+                    .enumerate()
+                    .take(input_channel_pointers.num_channels)
+                {
+                    let input_channel_pointer =
+                        *input_channel_pointers.ptrs.as_ptr().add(channel_idx);
+                    debug_assert!(channel_idx < output_channel_pointers.num_channels);
+                    let output_channel_pointer =
+                        *output_channel_pointers.ptrs.as_ptr().add(channel_idx);
+
+                    // If the host processes the main IO out of place then the inputs need to be
+                    // copied to the output buffers. Otherwise the input should already be there.
+                    if input_channel_pointer != output_channel_pointer {
+                        output_slice.copy_from_slice(std::slice::from_raw_parts_mut(
+                            input_channel_pointer.add(sample_offset),
+                            num_samples,
+                        ))
+                    }
+                }
+            });
+
+            // Any excess channels will need to be filled with zeroes since they'd otherwise point
+            // to whatever was left in the buffer
+            if input_channel_pointers.num_channels < output_channel_pointers.num_channels {
+                self.main_buffer.set_slices(num_samples, |output_slices| {
+                    for slice in &mut output_slices[input_channel_pointers.num_channels..] {
+                        slice.fill(0.0);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Back the main buffer by `main_remapping_storage`, and up- or downmix the host's main inputs
+    /// to the plugin's main input channels. The plugin's other main channels are silenced.
+    unsafe fn set_remapped_main_buffer_slices(&mut self, sample_offset: usize, num_samples: usize) {
+        let num_main_input_channels = self.num_main_input_channels;
+        let storage = &mut self.main_remapping_storage;
+        for channel in storage.iter_mut() {
+            nih_debug_assert!(num_samples <= channel.capacity());
+            channel.resize(num_samples, 0.0);
+            channel.fill(0.0);
+        }
+
+        if let Some(input_channel_pointers) = self.main_input_channel_pointers {
+            let num_remapped_channels = num_main_input_channels.min(storage.len());
+            remap_channels(
+                |channel_idx| {
+                    (*input_channel_pointers.ptrs.as_ptr().add(channel_idx))
+                        .add(sample_offset)
+                        .cast_const()
+                },
+                input_channel_pointers.num_channels,
+                |channel_idx| storage[channel_idx].as_mut_ptr(),
+                num_remapped_channels,
+                num_samples,
+            );
+        }
+
+        self.main_buffer.set_slices(num_samples, |output_slices| {
+            debug_assert_eq!(output_slices.len(), storage.len());
+
+            for (output_slice, channel_storage) in output_slices.iter_mut().zip(storage.iter_mut())
+            {
+                // SAFETY: `channel_storage` is no longer used accessed directly after this
+                *output_slice = &mut *(channel_storage.as_mut_slice() as *mut [f32]);
+            }
+        });
+    }
+}
+
+/// Up- or downmix `num_source_channels` channels of audio to `num_target_channels` channels. A
+/// single source channel is copied to every target channel, and multiple source channels are
+/// averaged when there is a single target channel. For other channel counts the overlapping
+/// channels are copied and the remaining target channels are filled with zeroes.
+///
+/// # Safety
+///
+/// The pointers returned by `source_channel` and `target_channel` must point to at least
+/// `num_samples` samples, and the source and target channels may not overlap.
+unsafe fn remap_channels(
+    source_channel: impl Fn(usize) -> *const f32,
+    num_source_channels: usize,
+    mut target_channel: impl FnMut(usize) -> *mut f32,
+    num_target_channels: usize,
+    num_samples: usize,
+) {
+    for target_idx in 0..num_target_channels {
+        let target = std::slice::from_raw_parts_mut(target_channel(target_idx), num_samples);
+        match (num_source_channels, num_target_channels) {
+            (1, _) => {
+                target.copy_from_slice(std::slice::from_raw_parts(source_channel(0), num_samples))
+            }
+            (2.., 1) => {
+                target.fill(0.0);
+
+                let gain = (num_source_channels as f32).recip();
+                for source_idx in 0..num_source_channels {
+                    let source =
+                        std::slice::from_raw_parts(source_channel(source_idx), num_samples);
+                    for (target_sample, source_sample) in target.iter_mut().zip(source) {
+                        *target_sample += source_sample * gain;
+                    }
+                }
+            }
+            _ if target_idx < num_source_channels => target.copy_from_slice(
+                std::slice::from_raw_parts(source_channel(target_idx), num_samples),
+            ),
+            _ => target.fill(0.0),
+        }
+    }
 }
 
 #[cfg(any(miri, test))]
@@ -501,4 +690,63 @@ mod miri {
             }
         }
     }
+
+    #[test]
+    fn remapped_buffer_io() {
+        const STEREO_LAYOUT: AudioIOLayout = AudioIOLayout {
+            main_input_channels: Some(new_nonzero_u32(2)),
+            main_output_channels: Some(new_nonzero_u32(2)),
+            ..AudioIOLayout::const_default()
+        };
+
+        // The host connects the stereo plugin to a mono track, and processes audio out of place
+        let mut input_storage = [vec![0.5f32; BUFFER_SIZE]];
+        let mut output_storage = [vec![0.0f32; BUFFER_SIZE]];
+        let mut input_channel_pointers: Vec<*mut f32> = input_storage
+            .iter_mut()
+            .map(|channel_slice| channel_slice.as_mut_ptr())
+            .collect();
+        let mut output_channel_pointers: Vec<*mut f32> = output_storage
+            .iter_mut()
+            .map(|channel_slice| channel_slice.as_mut_ptr())
+            .collect();
+
+        let mut buffer_manager = BufferManager::for_audio_io_layout(BUFFER_SIZE, STEREO_LAYOUT)
+            .with_main_io_remapping(BUFFER_SIZE);
+        // Creating the remapped buffers should not need to allocate
+        assert_eq!(buffer_manager.main_remapping_storage.len(), 2);
+        for channel in &buffer_manager.main_remapping_storage {
+            assert!(channel.capacity() >= BUFFER_SIZE);
+        }
+        let buffers = unsafe {
+            buffer_manager.create_buffers(0, BUFFER_SIZE, |buffer_sources| {
+                *buffer_sources.main_input_channel_pointers = Some(ChannelPointers {
+                    ptrs: NonNull::new(input_channel_pointers.as_mut_ptr()).unwrap(),
+                    num_channels: input_channel_pointers.len(),
+                });
+                *buffer_sources.main_output_channel_pointers = Some(ChannelPointers {
+                    ptrs: NonNull::new(output_channel_pointers.as_mut_ptr()).unwrap(),
+                    num_channels: output_channel_pointers.len(),
+                });
+            })
+        };
+
+        // The mono input is copied to both channels, and the output is downmixed back to mono
+        let main_buffer = buffers.main_buffer.as_slice();
+        assert_eq!(main_buffer.len(), 2);
+        for channel in main_buffer.iter() {
+            assert!(channel.iter().all(|sample| *sample == 0.5));
+        }
+        for sample in main_buffer[0].iter_mut() {
+            *sample = 1.0;
+        }
+
+        unsafe { buffer_manager.finish_buffers() };
+        for sample in &output_storage[0] {
+            assert!(*sample == 0.75);
+        }
+        for sample in &input_storage[0] {
+            assert!(*sample == 0.5);
+        }
+    }
 }
@@ -7,7 +7,7 @@ use vst3_sys::vst::IComponentHandler;
 
 use crate::prelude::{
     GuiContext, InitContext, ParamPtr, PluginApi, PluginNoteEvent, PluginState, ProcessContext,
-    Transport, Vst3Plugin,
+    RawHost, Transport, Vst3Plugin,
 };
 
 use super::inner::{Task, WrapperInner};
@@ -41,6 +41,7 @@ pub(crate) struct WrapperProcessContext<'a, P: Vst3Plugin> {
     pub(super) inner: &'a WrapperInner<P>,
     pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
     pub(super) output_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) output_param_changes_guard: AtomicRefMut<'a, VecDeque<(u32, f32)>>,
     pub(super) transport: Transport,
 }
 
@@ -67,6 +68,10 @@ impl<P: Vst3Plugin> InitContext<P> for WrapperInitContext<'_, P> {
         PluginApi::Vst3
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Vst3(self.inner.host_context.load(Ordering::Acquire))
+    }
+
     fn execute(&self, task: P::BackgroundTask) {
         (self.inner.task_executor.lock())(task);
     }
@@ -86,8 +91,15 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         PluginApi::Vst3
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Vst3(self.inner.host_context.load(Ordering::Acquire))
+    }
+
     fn execute_background(&self, task: P::BackgroundTask) {
-        let task_posted = self.inner.schedule_background(Task::PluginTask(task));
+        let priority = P::background_task_priority(&task);
+        let task_posted = self
+            .inner
+            .schedule_background(Task::PluginTask(task), priority);
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
@@ -105,6 +117,11 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         self.input_events_guard.pop_front()
     }
 
+    #[inline]
+    fn remaining_events(&self) -> usize {
+        self.input_events_guard.len()
+    }
+
     fn send_event(&mut self, event: PluginNoteEvent<P>) {
         self.output_events_guard.push_back(event);
     }
@@ -116,6 +133,40 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn port_names_changed(&self) {
+        self.inner.port_names_changed()
+    }
+
+    fn sample_accurate_automation_active(&self) -> bool {
+        self.inner
+            .sample_accurate_automation_active
+            .load(Ordering::Relaxed)
+    }
+
+    unsafe fn raw_set_read_only_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        match self.inner.param_ptr_to_hash.get(&param) {
+            // The value will be updated and sent to the host at the end of the processing cycle.
+            // Only the last value set during this cycle is sent.
+            Some(hash) => match self
+                .output_param_changes_guard
+                .iter_mut()
+                .find(|(param_hash, _)| param_hash == hash)
+            {
+                Some((_, value)) => *value = normalized,
+                None => self
+                    .output_param_changes_guard
+                    .push_back((*hash, normalized)),
+            },
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    unsafe fn raw_set_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        // VST3 doesn't have gestures on the audio thread. The host records output parameter
+        // changes the same way as it records the user's changes.
+        self.raw_set_read_only_parameter_normalized(param, normalized)
+    }
 }
 
 impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
@@ -123,6 +174,18 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
         PluginApi::Vst3
     }
 
+    unsafe fn raw_host(&self) -> RawHost {
+        RawHost::Vst3(self.inner.host_context.load(Ordering::Acquire))
+    }
+
+    fn is_plugin_initialized(&self) -> bool {
+        self.inner.plugin_initialized.load(Ordering::Acquire)
+    }
+
+    fn last_transport(&self) -> Option<Transport> {
+        self.inner.last_transport.load()
+    }
+
     fn request_resize(&self) -> bool {
         let task_posted = self.inner.schedule_gui(Task::RequestResize);
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
@@ -132,6 +195,16 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
         true
     }
 
+    fn request_resize_to(&self, width: u32, height: u32) -> bool {
+        let size_changed = match self.inner.editor.borrow().as_ref() {
+            Some(editor) => editor.lock().set_size(width, height),
+            None => false,
+        };
+
+        // The resize request is handled asynchronously, so the old size can't be restored here
+        size_changed && self.request_resize()
+    }
+
     // All of these functions are supposed to be called from the main thread, so we'll put some
     // trust in the caller and assume that this is indeed the case
     unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
@@ -228,4 +301,8 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: PluginState) {
         self.inner.set_state_object_from_gui(state)
     }
+
+    fn get_default_state(&self) -> PluginState {
+        self.inner.get_default_state_object()
+    }
 }
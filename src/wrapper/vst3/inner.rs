@@ -3,7 +3,9 @@ use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::{self, SendTimeoutError};
 use parking_lot::{Mutex, RwLock};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use vst3_sys::base::{kInvalidArgument, kResultOk, tresult};
@@ -15,14 +17,18 @@ use super::param_units::ParamUnits;
 use super::util::{ObjectPtr, VstPtr, VST3_MIDI_PARAMS_END, VST3_MIDI_PARAMS_START};
 use super::view::WrapperView;
 use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
+use crate::midi::{HighResolutionCCs, HighResolutionVelocity};
 use crate::prelude::{
-    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, MidiConfig, ParamFlags, ParamPtr, Params,
-    Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor, Transport, Vst3Plugin,
+    AsyncExecutor, AudioIOLayout, AuxOutputPortNames, BufferConfig, Editor, MidiConfig, ParamFlags,
+    ParamPtr, Params, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor,
+    TaskPriority, Transport, Vst3Plugin,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::buffer_management::BufferManager;
-use crate::wrapper::util::{hash_param_id, process_wrapper};
+use crate::wrapper::util::{
+    aux_output_port_name, catch_panic, hash_param_id, process_wrapper, LatencyTracker, ResetFadeIn,
+};
 
 /// The actual wrapper bits. We need this as an `Arc<T>` so we can safely use our event loop API.
 /// Since we can't combine that with VST3's interior reference counting this just has to be moved to
@@ -32,6 +38,9 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     pub plugin: Mutex<P>,
     /// The plugin's background task executor closure.
     pub task_executor: Mutex<TaskExecutor<P>>,
+    /// The plugin's auxiliary output port name overrides, if it has any. See
+    /// [`Plugin::aux_output_port_names()`]. These are called without locking the plugin.
+    aux_output_port_names: Option<AuxOutputPortNames>,
     /// The plugin's parameters. These are fetched once during initialization. That way the
     /// `ParamPtr`s are guaranteed to live at least as long as this object and we can interact with
     /// the `Params` object without having to acquire a lock on `plugin`.
@@ -44,6 +53,10 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// The host's [`IComponentHandler`] instance, if passed through
     /// [`IEditController::set_component_handler`].
     pub component_handler: AtomicRefCell<Option<VstPtr<dyn IComponentHandler>>>,
+    /// The `FUnknown*` host context passed to `IPluginBase::initialize()`. This is only exposed to
+    /// the plugin through [`RawHost::Vst3`][crate::prelude::RawHost::Vst3], and it is reset to a
+    /// null pointer when the host terminates the plugin.
+    pub host_context: AtomicPtr<c_void>,
 
     /// Our own [`IPlugView`] instance. This is set while the editor is actually visible (which is
     /// different form the lifetime of [`WrapperView`][super::WrapperView] itself).
@@ -63,24 +76,43 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// Whether the plugin is currently processing audio. In other words, the last state
     /// `IAudioProcessor::setActive()` has been called with.
     pub is_processing: AtomicBool,
+    /// Whether the host has sent a parameter change in the middle of a buffer since processing was
+    /// last started. Only set when `P::SAMPLE_ACCURATE_AUTOMATION` is enabled. Exposed through
+    /// `ProcessContext::sample_accurate_automation_active()`.
+    pub sample_accurate_automation_active: AtomicBool,
     /// The current audio IO layout. Modified through `IAudioProcessor::setBusArrangements()` after
     /// matching the proposed bus arrangement to one of the supported ones. The plugin's first audio
     /// IO layout is chosen as the default. Because of the way VST3 works it's not possible to
     /// change the number of busses from that default, only the channel counts can change.
     pub current_audio_io_layout: AtomicCell<AudioIOLayout>,
+    /// The audio IO layout as seen by the host. This is the same as `current_audio_io_layout`,
+    /// except when [`Plugin::REMAP_MAIN_IO_CHANNELS`] is enabled and the host's bus arrangement
+    /// uses a different number of main input or output channels. In that case this contains the
+    /// host's main channel counts, and the wrapper up- or downmixes between the two layouts.
+    pub host_audio_io_layout: AtomicCell<AudioIOLayout>,
     /// The current buffer configuration, containing the sample rate and the maximum block size.
     /// Will be set in `IAudioProcessor::setupProcessing()`.
     pub current_buffer_config: AtomicCell<Option<BufferConfig>>,
     /// The current audio processing mode. Set in `IAudioProcessor::setup_processing()`.
     pub current_process_mode: AtomicCell<ProcessMode>,
+    /// Whether `Plugin::initialize()` has succeeded at least once. Exposed through
+    /// `GuiContext::is_plugin_initialized()`.
+    pub plugin_initialized: AtomicBool,
     /// The last process status returned by the plugin. This is used for tail handling.
     pub last_process_status: AtomicCell<ProcessStatus>,
+    /// A copy of the transport information from the most recent process call, or `None` if the
+    /// plugin has not processed any audio yet. Used for `GuiContext::last_transport()`.
+    pub last_transport: AtomicCell<Option<Transport>>,
     /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
-    /// [`ProcessContext`].
-    pub current_latency: AtomicU32,
+    /// [`ProcessContext`], and the latency the host last queried through
+    /// `IAudioProcessor::getLatencySamples()`. Decides when a restart needs to be requested.
+    pub latency: LatencyTracker,
     /// A data structure that helps manage and create buffers for all of the plugin's inputs and
     /// outputs based on channel pointers provided by the host.
     pub buffer_manager: AtomicRefCell<BufferManager>,
+    /// The fade-in applied to the plugin's outputs after the host resets the plugin. See
+    /// [`Plugin::RESET_FADE_IN_MS`][crate::prelude::Plugin::RESET_FADE_IN_MS].
+    pub reset_fade_in: AtomicRefCell<ResetFadeIn>,
     /// The incoming events for the plugin, if `P::ACCEPTS_MIDI` is set. If
     /// `P::SAMPLE_ACCURATE_AUTOMATION`, this is also read in lockstep with the parameter change
     /// block splitting.
@@ -92,14 +124,27 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// Stores any events the plugin has output during the current processing cycle, analogous to
     /// `input_events`.
     pub output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Stores the new normalized values for parameters set by the plugin from the audio thread
+    /// during the current processing cycle. This includes both read-only parameters and regular
+    /// parameters set through the `ProcessContext`. These are sent to the host through the output
+    /// parameter changes at the end of the processing cycle. Setting a parameter again replaces its
+    /// existing entry, so this is preallocated to hold every parameter and never allocates.
+    pub output_param_changes: AtomicRefCell<VecDeque<(u32, f32)>>,
     /// VST3 has several useful predefined note expressions, but for some reason they are the only
     /// note event type that don't have MIDI note ID and channel fields. So we need to keep track of
     /// the most recent VST3 note IDs we've seen, and then map those back to MIDI note IDs and
     /// channels as needed.
     pub note_expression_controller: AtomicRefCell<NoteExpressionController>,
+    /// MIDI CCs are sent as parameter changes in VST3, so the MIDI 1.0 High Resolution Velocity
+    /// Prefix CC needs to be combined with the velocity of the next note event after the events
+    /// have been sorted.
+    pub high_resolution_velocity: AtomicRefCell<HighResolutionVelocity>,
+    /// Coalesces two message 14-bit MIDI CCs. Like the above, this can only be applied after the
+    /// events have been sorted.
+    pub high_resolution_ccs: AtomicRefCell<HighResolutionCCs>,
     /// Unprocessed parameter changes and note events sent by the host during a process call.
     /// Parameter changes are sent as separate queues for each parameter, and note events are in
-    /// another queue on top of that. And if `P::MIDI_INPUT >= MidiConfig::MidiCCs`, then we can
+    /// another queue on top of that. And if `P::MIDI_INPUT >= MidiConfig::MidiCCOnly`, then we can
     /// also receive MIDI CC messages through special parameter changes. On top of that, we also
     /// support sample accurate automation through block splitting if
     /// `P::SAMPLE_ACCURATE_AUTOMATION` is set. To account for all of this, we'll read all of the
@@ -137,6 +182,10 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// having to add a setter function to the parameter (or even worse, have it be completely
     /// untyped).
     pub param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// A reusable buffer for formatting parameter values when the host requests a parameter's
+    /// string representation. This avoids allocations when the host frequently polls these strings.
+    /// The buffer starts out with room for 128 bytes, which is enough for most parameter values.
+    pub param_string_scratch: Mutex<String>,
 }
 
 /// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
@@ -190,6 +239,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
     pub fn new() -> Arc<Self> {
         let mut plugin = P::default();
         let task_executor = Mutex::new(plugin.task_executor());
+        let aux_output_port_names = plugin.aux_output_port_names();
 
         // This is used to allow the plugin to restore preset data from its editor, see the comment
         // on `Self::updated_state_sender`
@@ -235,7 +285,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
 
                 bypass_param_exists |= is_bypass;
 
-                if P::MIDI_INPUT >= MidiConfig::MidiCCs
+                if P::MIDI_INPUT >= MidiConfig::MidiCCOnly
                     && (VST3_MIDI_PARAMS_START..VST3_MIDI_PARAMS_END).contains(hash)
                 {
                     nih_debug_assert_failure!(
@@ -269,6 +319,8 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             .iter()
             .map(|(id, hash, _, _)| (id.clone(), *hash))
             .collect();
+        // Every parameter occupies at most one slot in `output_param_changes`
+        let num_params = param_id_hashes_ptrs_groups.len();
         let param_ptr_to_hash = param_id_hashes_ptrs_groups
             .into_iter()
             .map(|(_, hash, ptr, _)| (ptr, hash))
@@ -277,17 +329,20 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         let wrapper = Arc::new(Self {
             plugin: Mutex::new(plugin),
             task_executor,
+            aux_output_port_names,
             params,
             // Initialized later as it needs a reference to the wrapper for the async executor
             editor: AtomicRefCell::new(None),
 
             component_handler: AtomicRefCell::new(None),
+            host_context: AtomicPtr::new(ptr::null_mut()),
 
             plug_view: RwLock::new(None),
 
             event_loop: AtomicRefCell::new(None),
 
             is_processing: AtomicBool::new(false),
+            sample_accurate_automation_active: AtomicBool::new(false),
             // Some hosts, like the current version of Bitwig and Ardour at the time of writing,
             // will try using the plugin's default not yet initialized bus arrangement. Because of
             // that, we'll always initialize this configuration even before the host requests a
@@ -295,19 +350,28 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             current_audio_io_layout: AtomicCell::new(
                 P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
             ),
+            host_audio_io_layout: AtomicCell::new(
+                P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
+            ),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
+            plugin_initialized: AtomicBool::new(false),
             last_process_status: AtomicCell::new(ProcessStatus::Normal),
-            current_latency: AtomicU32::new(0),
+            last_transport: AtomicCell::new(None),
+            latency: LatencyTracker::default(),
             // This is initialized just before calling `Plugin::initialize()` so that during the
             // process call buffers can be initialized without any allocations
             buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
                 0,
                 AudioIOLayout::default(),
             )),
+            reset_fade_in: AtomicRefCell::new(ResetFadeIn::default()),
             input_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
             output_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
+            output_param_changes: AtomicRefCell::new(VecDeque::with_capacity(num_params)),
             note_expression_controller: AtomicRefCell::new(NoteExpressionController::default()),
+            high_resolution_velocity: AtomicRefCell::new(HighResolutionVelocity::new()),
+            high_resolution_ccs: AtomicRefCell::new(HighResolutionCCs::new()),
             process_events: AtomicRefCell::new(Vec::with_capacity(4096)),
             updated_state_sender,
             updated_state_receiver,
@@ -318,6 +382,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             param_units,
             param_id_to_hash,
             param_ptr_to_hash,
+            param_string_scratch: Mutex::new(String::with_capacity(128)),
         });
 
         // FIXME: Right now this is safe, but if we are going to have a singleton main thread queue
@@ -335,7 +400,9 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                     let wrapper = wrapper.clone();
 
                     move |task| {
-                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        let priority = P::background_task_priority(&task);
+                        let task_posted =
+                            wrapper.schedule_background(Task::PluginTask(task), priority);
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
@@ -373,23 +440,56 @@ impl<P: Vst3Plugin> WrapperInner<P> {
     }
 
     pub fn make_process_context(&self, transport: Transport) -> WrapperProcessContext<'_, P> {
+        self.last_transport.store(Some(transport));
+
         WrapperProcessContext {
             inner: self,
             input_events_guard: self.input_events.borrow_mut(),
             output_events_guard: self.output_events.borrow_mut(),
+            output_param_changes_guard: self.output_param_changes.borrow_mut(),
             transport,
         }
     }
 
+    /// Snap all parameter smoothers to their current values if the plugin has enabled
+    /// [`Plugin::RESET_SMOOTHERS_ON_RESET`][crate::prelude::Plugin::RESET_SMOOTHERS_ON_RESET]. This
+    /// should be called right before the host-initiated `Plugin::reset()` calls.
+    pub fn reset_smoothers_if_enabled(&self) {
+        if !P::RESET_SMOOTHERS_ON_RESET {
+            return;
+        }
+
+        if let Some(buffer_config) = self.current_buffer_config.load() {
+            for param in self.param_by_hash.values() {
+                unsafe { param.update_smoother(buffer_config.sample_rate, true) };
+            }
+        }
+    }
+
+    /// Start the fade-in configured through
+    /// [`Plugin::RESET_FADE_IN_MS`][crate::prelude::Plugin::RESET_FADE_IN_MS], if any. This should
+    /// be called right after the host-initiated `Plugin::reset()` calls.
+    pub fn start_reset_fade_in_if_enabled(&self) {
+        if P::RESET_FADE_IN_MS <= 0.0 {
+            return;
+        }
+
+        if let Some(buffer_config) = self.current_buffer_config.load() {
+            self.reset_fade_in
+                .borrow_mut()
+                .start(P::RESET_FADE_IN_MS, buffer_config.sample_rate);
+        }
+    }
+
     /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
     /// can be run in the background without blocking either the GUI or the audio thread.
     ///
     /// If the task queue is full, then this will return false.
     #[must_use]
-    pub fn schedule_background(&self, task: Task<P>) -> bool {
+    pub fn schedule_background(&self, task: Task<P>, priority: TaskPriority) -> bool {
         let event_loop = self.event_loop.borrow();
         let event_loop = event_loop.as_ref().unwrap();
-        event_loop.schedule_background(task)
+        event_loop.schedule_background(task, priority)
     }
 
     /// Either posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be
@@ -473,6 +573,17 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         }
     }
 
+    /// Get a state object containing the default values for all of the plugin's parameters. May be
+    /// called by the plugin's GUI to reset the parameters.
+    pub fn get_default_state_object(&self) -> PluginState {
+        unsafe {
+            state::serialize_default_object::<P>(state::make_params_iter(
+                &self.param_by_hash,
+                &self.param_id_to_hash,
+            ))
+        }
+    }
+
     /// Update the plugin's internal state, called by the plugin itself from the GUI thread. To
     /// prevent corrupting data and changing parameters during processing the actual state is only
     /// updated at the end of the audio processing cycle.
@@ -509,7 +620,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             } else {
                 // Otherwise we'll set the state right here and now, since this function should be
                 // called from a GUI thread
-                self.set_state_inner(&mut state);
+                self.set_state_inner(&mut state, false);
                 break;
             }
         }
@@ -528,25 +639,54 @@ impl<P: Vst3Plugin> WrapperInner<P> {
 
     pub fn set_latency_samples(&self, samples: u32) {
         // Only trigger a restart if it's actually needed
-        let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
-        if old_latency != samples {
-            let task_posted =
-                self.schedule_gui(Task::TriggerRestart(RestartFlags::kLatencyChanged as i32));
-            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        let defer = P::DEFER_LATENCY_CHANGES && self.is_processing.load(Ordering::SeqCst);
+        if self.latency.set(samples, defer) {
+            self.request_latency_restart();
+        }
+    }
+
+    /// Request a restart for a latency change that was deferred while the plugin was processing
+    /// audio. Called when the host stops processing audio and when it deactivates the plugin.
+    pub fn flush_deferred_latency_change(&self) {
+        if self.latency.flush_deferred() {
+            self.request_latency_restart();
         }
     }
 
+    fn request_latency_restart(&self) {
+        let task_posted =
+            self.schedule_gui(Task::TriggerRestart(RestartFlags::kLatencyChanged as i32));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        if !task_posted {
+            self.latency.cancel_pending();
+        }
+    }
+
+    pub fn port_names_changed(&self) {
+        let task_posted =
+            self.schedule_gui(Task::TriggerRestart(RestartFlags::kIoTitlesChanged as i32));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    /// The name for an auxiliary output port. This lets the plugin override the name from the
+    /// audio IO layout without locking the plugin.
+    pub fn aux_output_name(&self, audio_io_layout: &AudioIOLayout, idx: usize) -> Option<String> {
+        aux_output_port_name(self.aux_output_port_names.as_ref(), audio_io_layout, idx)
+    }
+
     /// Immediately set the plugin state. Returns `false` if the deserialization failed. The plugin
     /// state is set from a couple places, so this function aims to deduplicate that. Includes
     /// `permit_alloc()`s around the deserialization and initialization for the use case where
     /// `set_state_object_from_gui()` was called while the plugin is process audio.
+    /// If `smooth_params` is set, then the parameters' smoothers will ramp to the restored values
+    /// instead of being reset.
     ///
     /// Implicitly emits `Task::ParameterValuesChanged`.
     ///
     /// # Notes
     ///
     /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
-    pub fn set_state_inner(&self, state: &mut PluginState) -> bool {
+    pub fn set_state_inner(&self, state: &mut PluginState, smooth_params: bool) -> bool {
         let audio_io_layout = self.current_audio_io_layout.load();
         let buffer_config = self.current_buffer_config.load();
 
@@ -556,13 +696,17 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         //        supports runtime preset loading.  `state::deserialize_object()` normally never
         //        allocates, but if the plugin has persistent non-parameter data then its
         //        `deserialize_fields()` implementation may still allocate.
-        let mut success = permit_alloc(|| unsafe {
-            state::deserialize_object::<P>(
-                state,
-                self.params.clone(),
-                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
-                buffer_config.as_ref(),
-            )
+        let mut success = permit_alloc(|| {
+            catch_panic("Params::deserialize_fields()", || unsafe {
+                state::deserialize_object::<P>(
+                    state,
+                    self.params.clone(),
+                    state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    buffer_config.as_ref(),
+                    smooth_params,
+                )
+            })
+            .unwrap_or(false)
         });
         if !success {
             nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
@@ -577,10 +721,13 @@ impl<P: Vst3Plugin> WrapperInner<P> {
 
             // See above
             success = permit_alloc(|| {
-                plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+                catch_panic("Plugin::initialize()", || {
+                    plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+                })
+                .unwrap_or(false)
             });
             if success {
-                process_wrapper(|| plugin.reset());
+                process_wrapper(|| catch_panic("Plugin::reset()", || plugin.reset()));
             }
         }
 
@@ -609,11 +756,17 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
     fn execute(&self, task: Task<P>, is_gui_thread: bool) {
         // This function is always called from the main thread
         match task {
-            Task::PluginTask(task) => (self.task_executor.lock())(task),
+            Task::PluginTask(task) => {
+                catch_panic("Plugin::task_executor()", || {
+                    (self.task_executor.lock())(task)
+                });
+            }
             Task::ParameterValuesChanged => {
                 if self.plug_view.read().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
-                        editor.lock().param_values_changed();
+                        catch_panic("Editor::param_values_changed()", || {
+                            editor.lock().param_values_changed()
+                        });
                     }
                 }
             }
@@ -621,15 +774,31 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
                 if self.plug_view.read().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
-                        editor
-                            .lock()
-                            .param_value_changed(param_id, normalized_value);
+                        catch_panic("Editor::param_value_changed()", || {
+                            editor
+                                .lock()
+                                .param_value_changed(param_id, normalized_value)
+                        });
                     }
                 }
             }
-            Task::TriggerRestart(flags) => match &*self.component_handler.borrow() {
+            Task::TriggerRestart(mut flags) => match &*self.component_handler.borrow() {
                 Some(handler) => unsafe {
                     nih_debug_assert!(is_gui_thread);
+
+                    // The host will query the most recent latency regardless, so any changes made
+                    // after this point need to trigger a new restart. If the latency has since been
+                    // changed back to the value the host already knows about, then no restart is
+                    // needed at all.
+                    if flags & RestartFlags::kLatencyChanged as i32 != 0
+                        && !self.latency.take_pending()
+                    {
+                        flags &= !(RestartFlags::kLatencyChanged as i32);
+                    }
+                    if flags == 0 {
+                        return;
+                    }
+
                     let result = handler.restart_component(flags);
                     nih_debug_assert_eq!(
                         result,
@@ -638,7 +807,14 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
                         flags
                     );
                 },
-                None => nih_debug_assert_failure!("Component handler not yet set"),
+                None => {
+                    // The host will query the latency when it sets up the plugin anyways
+                    if flags & RestartFlags::kLatencyChanged as i32 != 0 {
+                        self.latency.cancel_pending();
+                    }
+
+                    nih_debug_assert_failure!("Component handler not yet set")
+                }
             },
             Task::RequestResize => match &*self.plug_view.read() {
                 Some(plug_view) => unsafe {
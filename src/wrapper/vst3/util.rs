@@ -1,12 +1,12 @@
-use std::cmp;
 use std::ops::Deref;
+use vst3_com::vst::DataEvent;
 use vst3_sys::interfaces::IUnknown;
 use vst3_sys::vst::TChar;
 use vst3_sys::ComInterface;
-use widestring::U16CString;
 
-/// When `Plugin::MIDI_INPUT` is set to `MidiConfig::MidiCCs` or higher then we'll register 130*16
-/// additional parameters to handle MIDI CCs, channel pressure, and pitch bend, in that order.
+/// When `Plugin::MIDI_INPUT` is set to `MidiConfig::MidiCCOnly` or higher then we'll register
+/// 130*16 additional parameters to handle MIDI CCs, channel pressure, and pitch bend, in that
+/// order.
 /// vst3-sys doesn't expose these constants.
 pub const VST3_MIDI_CCS: u32 = 130;
 pub const VST3_MIDI_CHANNELS: u32 = 16;
@@ -42,23 +42,50 @@ pub fn u16strlcpy(dest: &mut [TChar], src: &str) {
         return;
     }
 
-    let src_utf16 = match U16CString::from_str(src) {
-        Ok(s) => s,
-        Err(err) => {
-            nih_debug_assert_failure!("Invalid UTF-16 string: {}", err);
-            return;
-        }
-    };
-    let src_utf16_chars = src_utf16.as_slice();
-    let src_utf16_chars_signed: &[TChar] =
-        unsafe { &*(src_utf16_chars as *const [u16] as *const [TChar]) };
+    if src.contains('\0') {
+        nih_debug_assert_failure!("Invalid UTF-16 string: the string contains a null character");
+        return;
+    }
 
-    // Make sure there's always room for a null terminator
-    let copy_len = cmp::min(dest.len() - 1, src_utf16_chars_signed.len());
-    dest[..copy_len].copy_from_slice(&src_utf16_chars_signed[..copy_len]);
+    // The string is encoded directly into `dest` so this doesn't allocate. Make sure there's always
+    // room for a null terminator.
+    let mut copy_len = 0;
+    for (dest_char, src_char) in dest[..dest.len() - 1].iter_mut().zip(src.encode_utf16()) {
+        *dest_char = src_char as TChar;
+        copy_len += 1;
+    }
     dest[copy_len] = 0;
 }
 
+/// The `DataEvent` type for SysEx messages. vst3-sys doesn't expose this constant.
+pub const VST3_DATA_EVENT_MIDI_SYSEX: u32 = 0;
+
+/// Create an output SysEx data event for a message in `sysex_buffer`. The event points to
+/// `sysex_buffer`, so it may only be passed to the host while that buffer is still alive.
+pub fn sysex_data_event(sysex_buffer: &[u8]) -> DataEvent {
+    DataEvent {
+        size: sysex_buffer.len() as u32,
+        type_: VST3_DATA_EVENT_MIDI_SYSEX,
+        bytes: sysex_buffer.as_ptr(),
+    }
+}
+
+/// Get the message from an input SysEx data event. Returns `None` if the event does not contain a
+/// SysEx message.
+///
+/// # Safety
+///
+/// `event.bytes` needs to point to at least `event.size` bytes that stay alive for as long as the
+/// returned slice is used.
+pub unsafe fn sysex_data_event_buffer(event: &DataEvent) -> Option<&[u8]> {
+    if event.type_ != VST3_DATA_EVENT_MIDI_SYSEX {
+        return None;
+    }
+
+    assert!(!event.bytes.is_null());
+    Some(std::slice::from_raw_parts(event.bytes, event.size as usize))
+}
+
 /// Send+Sync wrapper for these interface pointers.
 #[repr(transparent)]
 pub struct VstPtr<T: vst3_sys::ComInterface + ?Sized> {
@@ -135,6 +162,37 @@ mod miri {
         );
     }
 
+    #[test]
+    fn sysex_data_event_roundtrip() {
+        use crate::midi::sysex::{self, Dump};
+        use crate::midi::{MidiResult, NoteEvent};
+
+        let mut payload = [0; 510];
+        for (idx, byte) in payload.iter_mut().enumerate() {
+            *byte = (idx % 128) as u8;
+        }
+
+        // This follows the same steps as the wrapper's output and input event handling
+        let event = NoteEvent::MidiSysEx {
+            timing: 4,
+            message: Dump(payload),
+        };
+        let (padded_sysex_buffer, length) = match event.clone().as_midi() {
+            Some(MidiResult::SysEx(padded_sysex_buffer, length)) => (padded_sysex_buffer, length),
+            result => panic!("Unexpected result: {result:?}"),
+        };
+        let sysex_buffer = sysex::message_from_padded_buffer(&padded_sysex_buffer, length).unwrap();
+        let data_event = sysex_data_event(sysex_buffer);
+        assert_eq!(data_event.size, 512);
+
+        let received_sysex_buffer = unsafe { sysex_data_event_buffer(&data_event) }.unwrap();
+        assert_eq!(received_sysex_buffer, sysex_buffer);
+        assert_eq!(
+            NoteEvent::from_midi(event.timing(), received_sysex_buffer),
+            Ok(event)
+        );
+    }
+
     #[test]
     fn u16strlcpy_overflow() {
         let mut dest = [0; 6];
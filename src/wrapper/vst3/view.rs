@@ -14,6 +14,7 @@ use super::inner::{Task, WrapperInner};
 use super::util::{ObjectPtr, VstPtr};
 use crate::plugin::vst3::Vst3Plugin;
 use crate::prelude::{Editor, ParentWindowHandle};
+use crate::wrapper::util::catch_panic;
 
 // Alias needed for the VST3 attribute macro
 use vst3_sys as vst3_com;
@@ -299,11 +300,14 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
                 }
             };
 
-            *editor_handle = Some(
+            *editor_handle = catch_panic("Editor::spawn()", || {
                 self.editor
                     .lock()
-                    .spawn(parent_handle, self.inner.clone().make_gui_context()),
-            );
+                    .spawn(parent_handle, self.inner.clone().make_gui_context())
+            });
+            if editor_handle.is_none() {
+                return kResultFalse;
+            }
             *self.inner.plug_view.write() = Some(ObjectPtr::from(self));
 
             kResultOk
@@ -320,7 +324,9 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         let mut editor_handle = self.editor_handle.write();
         if editor_handle.is_some() {
             *self.inner.plug_view.write() = None;
-            *editor_handle = None;
+            catch_panic("the editor handle's destructor", || {
+                drop(editor_handle.take())
+            });
 
             kResultOk
         } else {
@@ -456,7 +462,11 @@ impl<P: Vst3Plugin> IPlugViewContentScaleSupport for WrapperView<P> {
             return kResultFalse;
         }
 
-        if self.editor.lock().set_scale_factor(factor) {
+        let scale_factor_set = catch_panic("Editor::set_scale_factor()", || {
+            self.editor.lock().set_scale_factor(factor)
+        })
+        .unwrap_or(false);
+        if scale_factor_set {
             self.scaling_factor.store(factor, Ordering::Relaxed);
             kResultOk
         } else {
@@ -5,7 +5,7 @@ use std::num::NonZeroU32;
 use std::ptr::NonNull;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use vst3_com::vst::{DataEvent, IProcessContextRequirementsFlags, ProcessModes};
+use vst3_com::vst::{IProcessContextRequirementsFlags, ProcessModes};
 use vst3_sys::base::{kInvalidArgument, kNoInterface, kResultFalse, kResultOk, tresult, TBool};
 use vst3_sys::base::{IBStream, IPluginBase};
 use vst3_sys::utils::SharedVstPtr;
@@ -22,18 +22,23 @@ use widestring::U16CStr;
 use super::inner::{ProcessEvent, WrapperInner};
 use super::note_expressions::{self, NoteExpressionController};
 use super::util::{
-    u16strlcpy, VstPtr, VST3_MIDI_CCS, VST3_MIDI_NUM_PARAMS, VST3_MIDI_PARAMS_START,
+    sysex_data_event, sysex_data_event_buffer, u16strlcpy, VstPtr, VST3_MIDI_CCS,
+    VST3_MIDI_NUM_PARAMS, VST3_MIDI_PARAMS_START,
 };
 use super::util::{VST3_MIDI_CHANNELS, VST3_MIDI_PARAMS_END};
 use super::view::WrapperView;
+use crate::midi::sysex;
 use crate::prelude::{
-    AuxiliaryBuffers, BufferConfig, MidiConfig, NoteEvent, ParamFlags, ProcessMode, ProcessStatus,
-    SysExMessage, Transport, Vst3Plugin,
+    AudioIOLayout, AuxiliaryBuffers, BufferConfig, MidiConfig, NoteEvent, ParamFlags, ProcessMode,
+    ProcessStatus, SysExMessage, Transport, Vst3Plugin,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::state;
 use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
-use crate::wrapper::util::{clamp_input_event_timing, clamp_output_event_timing, process_wrapper};
+use crate::wrapper::util::{
+    catch_panic, clamp_input_event_timing, clamp_output_event_timing, process_wrapper,
+    silence_outputs,
+};
 
 // Alias needed for the VST3 attribute macro
 use vst3_sys as vst3_com;
@@ -64,12 +69,19 @@ impl<P: Vst3Plugin> Drop for Wrapper<P> {
 }
 
 impl<P: Vst3Plugin> IPluginBase for Wrapper<P> {
-    unsafe fn initialize(&self, _context: *mut c_void) -> tresult {
-        // We currently don't need or allow any initialization logic
+    unsafe fn initialize(&self, context: *mut c_void) -> tresult {
+        // We currently don't need or allow any initialization logic. The host context is only
+        // stored so the plugin can access it through `RawHost`.
+        self.inner.host_context.store(context, Ordering::Release);
+
         kResultOk
     }
 
     unsafe fn terminate(&self) -> tresult {
+        self.inner
+            .host_context
+            .store(std::ptr::null_mut(), Ordering::Release);
+
         kResultOk
     }
 }
@@ -146,6 +158,7 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
         check_null_ptr!(info);
 
         let current_audio_io_layout = self.inner.current_audio_io_layout.load();
+        let host_audio_io_layout = self.inner.host_audio_io_layout.load();
 
         match (type_, dir, index) {
             (t, d, _)
@@ -164,8 +177,10 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 let aux_input_idx = (index - aux_input_start_idx).max(0) as usize;
                 if index == 0 && has_main_input {
                     info.bus_type = vst3_sys::vst::BusTypes::kMain as i32;
-                    info.channel_count =
-                        current_audio_io_layout.main_input_channels.unwrap().get() as i32;
+                    info.channel_count = host_audio_io_layout
+                        .main_input_channels
+                        .map(NonZeroU32::get)
+                        .unwrap_or_default() as i32;
                     u16strlcpy(&mut info.name, &current_audio_io_layout.main_input_name());
 
                     kResultOk
@@ -203,7 +218,7 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                     info.bus_type = vst3_sys::vst::BusTypes::kMain as i32;
                     // NOTE: See above, this becomes a 0 channel output if the plugin doesn't have a
                     //       main output
-                    info.channel_count = current_audio_io_layout
+                    info.channel_count = host_audio_io_layout
                         .main_output_channels
                         .map(NonZeroU32::get)
                         .unwrap_or_default() as i32;
@@ -216,8 +231,9 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                         current_audio_io_layout.aux_output_ports[aux_output_idx].get() as i32;
                     u16strlcpy(
                         &mut info.name,
-                        &current_audio_io_layout
-                            .aux_output_name(aux_output_idx)
+                        &self
+                            .inner
+                            .aux_output_name(&current_audio_io_layout, aux_output_idx)
                             .expect("Out of bounds auxiliary output port"),
                     );
 
@@ -237,7 +253,11 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 info.media_type = vst3_sys::vst::MediaTypes::kEvent as i32;
                 info.direction = vst3_sys::vst::BusDirections::kInput as i32;
                 info.channel_count = 16;
-                u16strlcpy(&mut info.name, "Note Input");
+                if P::MIDI_INPUT == MidiConfig::MidiCCOnly {
+                    u16strlcpy(&mut info.name, "MIDI Input");
+                } else {
+                    u16strlcpy(&mut info.name, "Note Input");
+                }
                 info.bus_type = vst3_sys::vst::BusTypes::kMain as i32;
                 info.flags = vst3_sys::vst::BusFlags::kDefaultActive as u32;
                 kResultOk
@@ -382,7 +402,11 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 let mut init_context = self.inner.make_init_context();
                 let audio_io_layout = self.inner.current_audio_io_layout.load();
                 let mut plugin = self.inner.plugin.lock();
-                if plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context) {
+                let initialized = catch_panic("Plugin::initialize()", || {
+                    plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+                })
+                .unwrap_or(false);
+                if initialized {
                     // NOTE: We don't call `Plugin::reset()` here. The call is done in `set_process()`
                     //       instead. Otherwise we would call the function twice, and `set_process()` needs
                     //       to be called after this function before the plugin may process audio again.
@@ -390,10 +414,16 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                     // This preallocates enough space so we can transform all of the host's raw
                     // channel pointers into a set of `Buffer` objects for the plugin's main and
                     // auxiliary IO
-                    *self.inner.buffer_manager.borrow_mut() = BufferManager::for_audio_io_layout(
+                    let mut buffer_manager = BufferManager::for_audio_io_layout(
                         buffer_config.max_buffer_size as usize,
                         audio_io_layout,
                     );
+                    if P::REMAP_MAIN_IO_CHANNELS {
+                        buffer_manager = buffer_manager
+                            .with_main_io_remapping(buffer_config.max_buffer_size as usize);
+                    }
+                    *self.inner.buffer_manager.borrow_mut() = buffer_manager;
+                    self.inner.plugin_initialized.store(true, Ordering::Release);
 
                     kResultOk
                 } else {
@@ -402,7 +432,10 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
             }
             (true, None) => kResultFalse,
             (false, _) => {
-                self.inner.plugin.lock().deactivate();
+                catch_panic("Plugin::deactivate()", || {
+                    self.inner.plugin.lock().deactivate()
+                });
+                self.inner.flush_deferred_latency_change();
 
                 kResultOk
             }
@@ -451,7 +484,7 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
 
         match state::deserialize_json(&read_buffer) {
             Some(mut state) => {
-                if self.inner.set_state_inner(&mut state) {
+                if self.inner.set_state_inner(&mut state, false) {
                     nih_trace!("Loaded state ({} bytes)", read_buffer.len());
                     kResultOk
                 } else {
@@ -515,7 +548,7 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
 
     unsafe fn get_parameter_count(&self) -> i32 {
         // We need to add a whole bunch of parameters if the plugin accepts MIDI CCs
-        if P::MIDI_INPUT >= MidiConfig::MidiCCs {
+        if P::MIDI_INPUT >= MidiConfig::MidiCCOnly {
             self.inner.param_hashes.len() as i32 + VST3_MIDI_NUM_PARAMS as i32
         } else {
             self.inner.param_hashes.len() as i32
@@ -539,7 +572,7 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
         // If the parameter is a generated MIDI CC/channel pressure/pitch bend then it needs to be
         // handled separately
         let num_actual_params = self.inner.param_hashes.len() as i32;
-        if P::MIDI_INPUT >= MidiConfig::MidiCCs && param_index >= num_actual_params {
+        if P::MIDI_INPUT >= MidiConfig::MidiCCOnly && param_index >= num_actual_params {
             let midi_param_relative_idx = (param_index - num_actual_params) as u32;
             // This goes up to 130 for the 128 CCs followed by channel pressure and pitch bend
             let midi_cc = midi_param_relative_idx % VST3_MIDI_CCS;
@@ -568,12 +601,15 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             let flags = param_ptr.flags();
             let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
             let hidden = flags.contains(ParamFlags::HIDDEN);
+            let read_only = flags.contains(ParamFlags::READ_ONLY);
             let is_bypass = flags.contains(ParamFlags::BYPASS);
 
             info.id = *param_hash;
             u16strlcpy(&mut info.title, param_ptr.name());
             u16strlcpy(&mut info.short_title, param_ptr.name());
-            u16strlcpy(&mut info.units, param_ptr.unit());
+            // Units are usually prefixed with a space for display purposes, but hosts will add
+            // their own spacing when displaying the units separately
+            u16strlcpy(&mut info.units, param_ptr.unit().trim());
             info.step_count = param_ptr.step_count().unwrap_or(0) as i32;
             info.default_normalized_value = default_value as f64;
             info.unit_id = *param_unit;
@@ -584,6 +620,9 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             if hidden {
                 info.flags |= ParameterFlags::kIsReadOnly as i32 | (1 << 4); // kIsHidden
             }
+            if read_only {
+                info.flags |= ParameterFlags::kIsReadOnly as i32;
+            }
             if is_bypass {
                 info.flags |= ParameterFlags::kIsBypass as i32;
             }
@@ -606,10 +645,14 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
         //       should be fine right? They should be hidden anyways.
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => {
-                u16strlcpy(
-                    dest,
-                    &param_ptr.normalized_value_to_string(value_normalized as f32, false),
+                let mut param_string = self.inner.param_string_scratch.lock();
+                param_string.clear();
+                param_ptr.normalized_value_to_string_into(
+                    value_normalized as f32,
+                    false,
+                    &mut param_string,
                 );
+                u16strlcpy(dest, &param_string);
 
                 kResultOk
             }
@@ -717,71 +760,101 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
         }
 
         // NIH-plug no longer supports flexible IO layouts. Instead we'll try to find an audio IO
-        // layout that matches the host's requested layout.
-        let matching_layout = P::AUDIO_IO_LAYOUTS
-            .iter()
-            .find(|layout| {
-                // If the number of ports/busses doesn't match then we can immediately discard the
-                // layout. VST3 doesn't allow for optional switchable ports like CLAP does. Only the
-                // channel counts can change.
-                let num_layout_ins = if layout.main_input_channels.is_some() {
-                    1
-                } else {
-                    0
-                } + layout.aux_input_ports.len();
-                let num_layout_outs = if layout.main_output_channels.is_some() {
-                    1
-                } else {
-                    0
-                } + layout.aux_output_ports.len();
-                if num_ins as usize != num_layout_ins || num_outs as usize != num_layout_outs {
-                    return false;
-                }
+        // layout that matches the host's requested layout. If the plugin allows it, the main
+        // channel counts may also differ between mono and stereo, with the wrapper doing the
+        // up- or downmixing.
+        let main_channels_match = |host_channels: u32, layout_channels: u32, remap: bool| {
+            host_channels == layout_channels
+                || (remap && matches!((host_channels, layout_channels), (1, 2) | (2, 1)))
+        };
+        let layout_matches = |layout: &AudioIOLayout, remap: bool| {
+            // If the number of ports/busses doesn't match then we can immediately discard the
+            // layout. VST3 doesn't allow for optional switchable ports like CLAP does. Only the
+            // channel counts can change.
+            let num_layout_ins = if layout.main_input_channels.is_some() {
+                1
+            } else {
+                0
+            } + layout.aux_input_ports.len();
+            let num_layout_outs = if layout.main_output_channels.is_some() {
+                1
+            } else {
+                0
+            } + layout.aux_output_ports.len();
+            if num_ins as usize != num_layout_ins || num_outs as usize != num_layout_outs {
+                return false;
+            }
 
-                // NOTE: We completely ignore the speaker arrangements and only look at the channel
-                //       counts here. This may cause issues at some point, but it works for now.
-                let has_main_input = layout.main_input_channels.is_some();
-                let aux_input_start_idx = if has_main_input { 0 } else { 1 };
-                if has_main_input
-                    && (*inputs).count_ones() != layout.main_input_channels.unwrap().get()
+            // NOTE: We completely ignore the speaker arrangements and only look at the channel
+            //       counts here. This may cause issues at some point, but it works for now.
+            let has_main_input = layout.main_input_channels.is_some();
+            let aux_input_start_idx = if has_main_input { 0 } else { 1 };
+            if has_main_input
+                && !main_channels_match(
+                    (*inputs).count_ones(),
+                    layout.main_input_channels.unwrap().get(),
+                    remap,
+                )
+            {
+                return false;
+            }
+            for (aux_input_idx, channel_count) in layout.aux_input_ports.iter().enumerate() {
+                if (*inputs.add(aux_input_idx + aux_input_start_idx)).count_ones()
+                    != channel_count.get()
                 {
                     return false;
                 }
-                for (aux_input_idx, channel_count) in layout.aux_input_ports.iter().enumerate() {
-                    if (*inputs.add(aux_input_idx + aux_input_start_idx)).count_ones()
-                        != channel_count.get()
-                    {
-                        return false;
-                    }
-                }
+            }
 
-                let has_main_output = layout.main_output_channels.is_some();
-                let aux_output_start_idx = if has_main_output { 0 } else { 1 };
-                if (*outputs).count_ones()
-                    != layout
-                        .main_output_channels
-                        .map(NonZeroU32::get)
-                        .unwrap_or_default()
+            let has_main_output = layout.main_output_channels.is_some();
+            let aux_output_start_idx = if has_main_output { 0 } else { 1 };
+            if !main_channels_match(
+                (*outputs).count_ones(),
+                layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or_default(),
+                remap,
+            ) {
+                return false;
+            }
+            for (aux_output_idx, channel_count) in layout.aux_output_ports.iter().enumerate() {
+                if (*outputs.add(aux_output_idx + aux_output_start_idx)).count_ones()
+                    != channel_count.get()
                 {
                     return false;
                 }
-                for (aux_output_idx, channel_count) in layout.aux_output_ports.iter().enumerate() {
-                    if (*outputs.add(aux_output_idx + aux_output_start_idx)).count_ones()
-                        != channel_count.get()
-                    {
-                        return false;
-                    }
-                }
+            }
+
+            true
+        };
 
-                true
+        // Exact matches are always preferred over layouts that require remapping
+        let matching_layout = P::AUDIO_IO_LAYOUTS
+            .iter()
+            .find(|layout| layout_matches(layout, false))
+            .or_else(|| {
+                P::AUDIO_IO_LAYOUTS
+                    .iter()
+                    .find(|layout| P::REMAP_MAIN_IO_CHANNELS && layout_matches(layout, true))
             })
             .copied();
 
         match matching_layout {
             Some(layout) => {
                 // This layout is used from hereon onwards, at least until this function is called
-                // again
+                // again. The host's main channel counts are stored separately since they may differ
+                // from the plugin's layout when remapping.
+                let mut host_layout = layout;
+                if layout.main_input_channels.is_some() {
+                    host_layout.main_input_channels = NonZeroU32::new((*inputs).count_ones());
+                }
+                if layout.main_output_channels.is_some() {
+                    host_layout.main_output_channels = NonZeroU32::new((*outputs).count_ones());
+                }
+
                 self.inner.current_audio_io_layout.store(layout);
+                self.inner.host_audio_io_layout.store(host_layout);
 
                 kResultOk
             }
@@ -814,7 +887,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
             }
         };
 
-        let current_audio_io_layout = self.inner.current_audio_io_layout.load();
+        // This reports the host's main channel counts, which can differ from the plugin's layout
+        // when the plugin remaps its main IO channels
+        let current_audio_io_layout = self.inner.host_audio_io_layout.load();
         let num_channels = if dir == vst3_sys::vst::BusDirections::kInput as i32 {
             let has_main_input = current_audio_io_layout.main_input_channels.is_some();
             let aux_input_start_idx = if has_main_input { 1 } else { 0 };
@@ -857,7 +932,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
     }
 
     unsafe fn get_latency_samples(&self) -> u32 {
-        self.inner.current_latency.load(Ordering::SeqCst)
+        self.inner.latency.report()
     }
 
     unsafe fn setup_processing(&self, setup: *const vst3_sys::vst::ProcessSetup) -> tresult {
@@ -870,6 +945,16 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
             vst3_sys::vst::SymbolicSampleSizes::kSample32 as i32
         );
 
+        // Plugins can rely on the sample rate being valid, so we'll refuse to set up processing
+        // otherwise
+        if !(setup.sample_rate.is_finite() && setup.sample_rate > 0.0) {
+            nih_debug_assert_failure!(
+                "The host tried to set up processing with a sample rate of {}",
+                setup.sample_rate
+            );
+            return kInvalidArgument;
+        }
+
         // This is needed when activating the plugin and when restoring state
         self.inner.current_buffer_config.store(Some(BufferConfig {
             sample_rate: setup.sample_rate as f32,
@@ -901,6 +986,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
         // Always reset the processing status when the plugin gets activated or deactivated
         self.inner.last_process_status.store(ProcessStatus::Normal);
         self.inner.is_processing.store(state, Ordering::SeqCst);
+        self.inner
+            .sample_accurate_automation_active
+            .store(false, Ordering::SeqCst);
 
         // This function is also used to reset buffers on the plugin, so we should do the same
         // thing. We don't call `reset()` in `setup_processing()` for that same reason.
@@ -921,7 +1009,11 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 }
             };
 
-            process_wrapper(|| plugin.reset());
+            self.inner.reset_smoothers_if_enabled();
+            process_wrapper(|| catch_panic("Plugin::reset()", || plugin.reset()));
+            self.inner.start_reset_fade_in_if_enabled();
+        } else {
+            self.inner.flush_deferred_latency_change();
         }
 
         // We don't have any special handling for suspending and resuming plugins, yet
@@ -1015,7 +1107,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
 
                                 // MIDI CC messages, channel pressure, and pitch bend are also sent
                                 // as parameter changes
-                                if P::MIDI_INPUT >= MidiConfig::MidiCCs
+                                if P::MIDI_INPUT >= MidiConfig::MidiCCOnly
                                     && (VST3_MIDI_PARAMS_START..VST3_MIDI_PARAMS_END)
                                         .contains(&param_hash)
                                 {
@@ -1046,6 +1138,12 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                         },
                                     }));
                                 } else if P::SAMPLE_ACCURATE_AUTOMATION {
+                                    if timing > 0 {
+                                        self.inner
+                                            .sample_accurate_automation_active
+                                            .store(true, Ordering::Relaxed);
+                                    }
+
                                     process_events.push(ProcessEvent::ParameterChange {
                                         timing,
                                         hash: param_hash,
@@ -1064,7 +1162,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 }
             }
 
-            // Then we'll add all of our input events
+            // Then we'll add all of our input events. Plugins using `MidiConfig::MidiCCOnly` only
+            // receive SysEx through here, since MIDI CCs are sent as parameter changes.
+            let receives_notes = P::MIDI_INPUT != MidiConfig::MidiCCOnly && P::MIDI_INPUT_NOTES;
             if P::MIDI_INPUT >= MidiConfig::Basic {
                 let mut note_expression_controller =
                     self.inner.note_expression_controller.borrow_mut();
@@ -1082,7 +1182,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                             total_buffer_len as u32,
                         );
 
-                        if event.type_ == EventTypes::kNoteOnEvent as u16 {
+                        if receives_notes && event.type_ == EventTypes::kNoteOnEvent as u16 {
                             let event = event.event.note_on;
 
                             // We need to keep track of note IDs to be able to handle not
@@ -1100,7 +1200,8 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 note: event.pitch as u8,
                                 velocity: event.velocity,
                             }));
-                        } else if event.type_ == EventTypes::kNoteOffEvent as u16 {
+                        } else if receives_notes && event.type_ == EventTypes::kNoteOffEvent as u16
+                        {
                             let event = event.event.note_off;
                             process_events.push(ProcessEvent::NoteEvent(NoteEvent::NoteOff {
                                 timing,
@@ -1113,7 +1214,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 note: event.pitch as u8,
                                 velocity: event.velocity,
                             }));
-                        } else if event.type_ == EventTypes::kPolyPressureEvent as u16 {
+                        } else if receives_notes
+                            && event.type_ == EventTypes::kPolyPressureEvent as u16
+                        {
                             let event = event.event.poly_pressure;
                             process_events.push(ProcessEvent::NoteEvent(NoteEvent::PolyPressure {
                                 timing,
@@ -1126,7 +1229,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 note: event.pitch as u8,
                                 pressure: event.pressure,
                             }));
-                        } else if event.type_ == EventTypes::kNoteExpressionValueEvent as u16 {
+                        } else if receives_notes
+                            && event.type_ == EventTypes::kNoteExpressionValueEvent as u16
+                        {
                             let event = event.event.note_expression_value;
                             match note_expression_controller.translate_event(timing, &event) {
                                 Some(translated_event) => {
@@ -1137,20 +1242,14 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                     event.type_id
                                 ),
                             }
-                        } else if event.type_ == EventTypes::kDataEvent as u16
-                            && event.event.data.type_ == 0
-                        {
-                            // 0 = kMidiSysEx
-                            let event = event.event.data;
-
+                        } else if event.type_ == EventTypes::kDataEvent as u16 {
                             // `NoteEvent::from_midi` prints some tracing if parsing fails, which is
                             // not necessarily an error
-                            assert!(!event.bytes.is_null());
-                            let sysex_buffer =
-                                std::slice::from_raw_parts(event.bytes, event.size as usize);
-                            if let Ok(note_event) = NoteEvent::from_midi(timing, sysex_buffer) {
-                                process_events.push(ProcessEvent::NoteEvent(note_event));
-                            };
+                            if let Some(sysex_buffer) = sysex_data_event_buffer(&event.event.data) {
+                                if let Ok(note_event) = NoteEvent::from_midi(timing, sysex_buffer) {
+                                    process_events.push(ProcessEvent::NoteEvent(note_event));
+                                };
+                            }
                         }
                     }
                 }
@@ -1170,6 +1269,20 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 })
             });
 
+            // VST3 hosts send the high resolution velocity prefix CC as a parameter change at the
+            // same sample as the note, so this can only be applied after sorting the events. The
+            // same applies to the two halves of 14-bit MIDI CCs.
+            if P::MIDI_INPUT >= MidiConfig::MidiCCOnly {
+                let mut high_resolution_velocity = self.inner.high_resolution_velocity.borrow_mut();
+                let mut high_resolution_ccs = self.inner.high_resolution_ccs.borrow_mut();
+                for event in process_events.iter_mut() {
+                    if let ProcessEvent::NoteEvent(event) = event {
+                        high_resolution_velocity.process(event);
+                        high_resolution_ccs.process(event);
+                    }
+                }
+            }
+
             let mut block_start = 0usize;
             let mut block_end;
             let mut event_start_idx = 0;
@@ -1277,6 +1390,14 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
 
                                             *aux_input_channel_pointers =
                                                 Some(ChannelPointers { ptrs, num_channels });
+                                            // The silence flags contain one bit per channel
+                                            buffer_source.aux_input_silent[aux_input_no] =
+                                                num_channels <= u64::BITS as usize
+                                                    && (0..num_channels).all(|channel_idx| {
+                                                        audio_input.silence_flags
+                                                            & (1 << channel_idx)
+                                                            != 0
+                                                    });
                                         }
                                         None => continue,
                                     }
@@ -1335,6 +1456,14 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                     if !data.context.is_null() {
                         let context = &*data.context;
 
+                        // The plugin won't know about sample rate changes unless the host
+                        // reinitializes the plugin through `IAudioProcessor::setup_processing()`
+                        nih_debug_assert_eq!(
+                            context.sample_rate as f32,
+                            sample_rate,
+                            "The host changed the sample rate without calling setupProcessing()"
+                        );
+
                         // These constants are missing from vst3-sys, see:
                         // https://steinbergmedia.github.io/vst3_doc/vstinterfaces/structSteinberg_1_1Vst_1_1ProcessContext.html
                         transport.playing = context.state & (1 << 1) != 0; // kPlaying
@@ -1398,13 +1527,27 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                             outputs: buffers.aux_outputs,
                         };
                         let mut context = self.inner.make_process_context(transport);
-                        let result = plugin.process(buffers.main_buffer, &mut aux, &mut context);
+                        let result = catch_panic("Plugin::process()", || {
+                            plugin.process(buffers.main_buffer, &mut aux, &mut context)
+                        })
+                        .unwrap_or_else(|| {
+                            silence_outputs(buffers.main_buffer, &mut aux);
+                            ProcessStatus::Error("The plugin panicked while processing audio")
+                        });
+                        self.inner
+                            .reset_fade_in
+                            .borrow_mut()
+                            .apply(buffers.main_buffer, &mut aux);
                         self.inner.last_process_status.store(result);
                         result
                     } else {
                         ProcessStatus::Normal
                     };
 
+                    // If the host's main channel counts don't match the plugin's layout, then the
+                    // plugin's output still needs to be up- or downmixed to the host's buffers
+                    buffer_manager.finish_buffers();
+
                     match result {
                         ProcessStatus::Error(err) => {
                             nih_debug_assert_failure!("Process error: {}", err);
@@ -1415,6 +1558,37 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                     }
                 };
 
+                // Read-only parameter values set by the plugin are sent to the host as output
+                // parameter changes. The parameters' values are only updated now to avoid changing
+                // them in the middle of the process call.
+                {
+                    let mut output_param_changes = self.inner.output_param_changes.borrow_mut();
+                    while let Some((param_hash, normalized_value)) =
+                        output_param_changes.pop_front()
+                    {
+                        self.inner.set_normalized_value_by_hash(
+                            param_hash,
+                            normalized_value,
+                            Some(sample_rate),
+                        );
+
+                        if let Some(param_changes) = data.output_param_changes.upgrade() {
+                            let mut queue_idx = 0;
+                            if let Some(param_queue) = param_changes
+                                .add_parameter_data(&param_hash, &mut queue_idx)
+                                .upgrade()
+                            {
+                                let mut point_idx = 0;
+                                param_queue.add_point(
+                                    block_start as i32,
+                                    normalized_value as f64,
+                                    &mut point_idx,
+                                );
+                            }
+                        }
+                    }
+                }
+
                 // Send any events output by the plugin during the process cycle
                 if let Some(events) = data.output_events.upgrade() {
                     let mut output_events = self.inner.output_events.borrow_mut();
@@ -1551,7 +1725,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 timing: _,
                                 channel,
                                 pressure,
-                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCs => {
+                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCOnly => {
                                 vst3_event.type_ = EventTypes::kLegacyMIDICCOutEvent as u16;
                                 vst3_event.event.legacy_midi_cc_out = LegacyMidiCCOutEvent {
                                     control_number: 128, // kAfterTouch
@@ -1564,7 +1738,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 timing: _,
                                 channel,
                                 value,
-                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCs => {
+                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCOnly => {
                                 let scaled = (value * ((1 << 14) - 1) as f32).round() as i32;
 
                                 vst3_event.type_ = EventTypes::kLegacyMIDICCOutEvent as u16;
@@ -1580,7 +1754,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 channel,
                                 cc,
                                 value,
-                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCs => {
+                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCOnly => {
                                 vst3_event.type_ = EventTypes::kLegacyMIDICCOutEvent as u16;
                                 vst3_event.event.legacy_midi_cc_out = LegacyMidiCCOutEvent {
                                     control_number: cc,
@@ -1593,7 +1767,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 timing: _,
                                 channel,
                                 program,
-                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCs => {
+                            } if P::MIDI_OUTPUT >= MidiConfig::MidiCCOnly => {
                                 vst3_event.type_ = EventTypes::kLegacyMIDICCOutEvent as u16;
                                 vst3_event.event.legacy_midi_cc_out = LegacyMidiCCOutEvent {
                                     control_number: 130, // kCtrlProgramChange
@@ -1606,16 +1780,15 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 if P::MIDI_OUTPUT >= MidiConfig::Basic =>
                             {
                                 let (padded_sysex_buffer, length) = message.to_buffer();
-                                let padded_sysex_buffer = padded_sysex_buffer.borrow();
-                                nih_debug_assert!(padded_sysex_buffer.len() >= length);
-                                let sysex_buffer = &padded_sysex_buffer[..length];
+                                let Some(sysex_buffer) = sysex::message_from_padded_buffer(
+                                    padded_sysex_buffer.borrow(),
+                                    length,
+                                ) else {
+                                    continue;
+                                };
 
                                 vst3_event.type_ = EventTypes::kDataEvent as u16;
-                                vst3_event.event.data = DataEvent {
-                                    size: sysex_buffer.len() as u32,
-                                    type_: 0, // kMidiSysEx
-                                    bytes: sysex_buffer.as_ptr(),
-                                };
+                                vst3_event.event.data = sysex_data_event(sysex_buffer);
 
                                 // NOTE: We need to have this call here while `sysex_buffer` is
                                 //       still in scope since the event contains pointers to it
@@ -1654,7 +1827,8 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
             //        doesn't do that
             let updated_state = permit_alloc(|| self.inner.updated_state_receiver.try_recv());
             if let Ok(mut state) = updated_state {
-                self.inner.set_state_inner(&mut state);
+                self.inner
+                    .set_state_inner(&mut state, P::SMOOTH_RUNTIME_STATE_RESTORES);
 
                 // We'll pass the state object back to the GUI thread so deallocation can happen
                 // there without potentially blocking the audio thread
@@ -1688,7 +1862,7 @@ impl<P: Vst3Plugin> IMidiMapping for Wrapper<P> {
         midi_cc_number: vst3_com::vst::CtrlNumber,
         param_id: *mut vst3_com::vst::ParamID,
     ) -> tresult {
-        if P::MIDI_INPUT < MidiConfig::MidiCCs
+        if P::MIDI_INPUT < MidiConfig::MidiCCOnly
             || bus_index != 0
             || !(0..VST3_MIDI_CHANNELS as i16).contains(&channel)
             || !(0..VST3_MIDI_CCS as i16).contains(&midi_cc_number)
@@ -1710,7 +1884,10 @@ impl<P: Vst3Plugin> IMidiMapping for Wrapper<P> {
 impl<P: Vst3Plugin> INoteExpressionController for Wrapper<P> {
     unsafe fn get_note_expression_count(&self, bus_idx: i32, _channel: i16) -> i32 {
         // Apparently you need to define the predefined note expressions. Thanks VST3.
-        if P::MIDI_INPUT >= MidiConfig::Basic && bus_idx == 0 {
+        if P::MIDI_INPUT >= MidiConfig::Basic
+            && P::MIDI_INPUT != MidiConfig::MidiCCOnly
+            && bus_idx == 0
+        {
             note_expressions::KNOWN_NOTE_EXPRESSIONS.len() as i32
         } else {
             0
@@ -1725,6 +1902,7 @@ impl<P: Vst3Plugin> INoteExpressionController for Wrapper<P> {
         info: *mut NoteExpressionTypeInfo,
     ) -> tresult {
         if P::MIDI_INPUT < MidiConfig::Basic
+            || P::MIDI_INPUT == MidiConfig::MidiCCOnly
             || bus_idx != 0
             || !(0..note_expressions::KNOWN_NOTE_EXPRESSIONS.len() as i32)
                 .contains(&note_expression_idx)